@@ -0,0 +1,154 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Allocation-count regression checks for the operations on node labels and the generation of
+//! proofs. The allocations are counted by a global allocator, and the run fails if any of the
+//! operations allocates more than its budget.
+//!
+//! Run with `cargo bench -p akd --features bench --bench allocations`
+
+use akd::append_only_zks::InsertMode;
+use akd::storage::manager::StorageManager;
+use akd::storage::memory::AsyncInMemoryDatabase;
+use akd::NamedConfiguration;
+use akd::{Azks, AzksElement, AzksValue, NodeLabel};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// The maximum number of allocations of a membership proof, per level of the tree
+const MEMBERSHIP_PROOF_BUDGET_PER_LEVEL: usize = 10;
+
+struct CountingAllocator;
+
+thread_local! {
+    // Only the allocations of the measuring thread are counted, so that the background threads
+    // of the runtime do not interfere
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Runs the operation, returning its output and the number of allocations it made
+fn count_allocations<T>(operation: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATIONS.with(Cell::get);
+    let output = operation();
+    (output, ALLOCATIONS.with(Cell::get) - before)
+}
+
+fn check(name: &str, allocations: usize, budget: usize) -> bool {
+    let within_budget = allocations <= budget;
+    println!(
+        "{name}: {allocations} allocations (budget {budget}){}",
+        if within_budget { "" } else { " EXCEEDED" }
+    );
+    within_budget
+}
+
+fn random_label(rng: &mut StdRng) -> NodeLabel {
+    NodeLabel::new(rng.gen::<[u8; 32]>(), 256)
+}
+
+fn node_label_operations<TC: NamedConfiguration>(rng: &mut StdRng) -> bool {
+    let labels = (0..1000).map(|_| random_label(rng)).collect::<Vec<_>>();
+    let value = AzksValue(rng.gen::<[u8; 32]>());
+
+    let (_, allocations) = count_allocations(|| {
+        for pair in labels.windows(2) {
+            let prefix = pair[0].get_longest_common_prefix::<TC>(pair[1]);
+            assert!(prefix.is_prefix_of(&pair[1]));
+            let _ = prefix.get_prefix_ordering(pair[0]);
+            let _ = TC::compute_parent_hash_from_children(
+                &value,
+                &pair[0].value::<TC>(),
+                &value,
+                &pair[1].value::<TC>(),
+            );
+        }
+    });
+    check(
+        &format!("Node label operations ({})", TC::name()),
+        allocations,
+        0,
+    )
+}
+
+fn membership_proofs<TC: NamedConfiguration>(rng: &mut StdRng) -> bool {
+    let num_leaves = 1000;
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let db = StorageManager::new(AsyncInMemoryDatabase::new(), None, None, None);
+    let mut azks = runtime.block_on(Azks::new::<TC, _>(&db)).unwrap();
+    let leaves = (0..num_leaves)
+        .map(|_| AzksElement {
+            label: random_label(rng),
+            value: AzksValue(rng.gen::<[u8; 32]>()),
+        })
+        .collect::<Vec<_>>();
+    runtime
+        .block_on(azks.batch_insert_nodes::<TC, _>(&db, leaves.clone(), InsertMode::Directory))
+        .unwrap();
+
+    let mut within_budget = true;
+    for leaf in leaves.iter().take(10) {
+        let (proof, allocations) = count_allocations(|| {
+            runtime
+                .block_on(azks.get_membership_proof::<TC, _>(&db, leaf.label))
+                .unwrap()
+        });
+        within_budget &= check(
+            &format!(
+                "Membership proof of depth {} ({})",
+                proof.sibling_proofs.len(),
+                TC::name()
+            ),
+            allocations,
+            MEMBERSHIP_PROOF_BUDGET_PER_LEVEL * (proof.sibling_proofs.len() + 1),
+        );
+    }
+    within_budget
+}
+
+fn run<TC: NamedConfiguration>() -> bool {
+    let mut rng = StdRng::seed_from_u64(42);
+    // Both checks are run, so that all of the regressions are reported
+    let labels_within_budget = node_label_operations::<TC>(&mut rng);
+    membership_proofs::<TC>(&mut rng) && labels_within_budget
+}
+
+fn main() {
+    // NOTE(new_config): Add a new configuration here
+    let within_budget = [
+        run::<akd::WhatsAppV1Configuration>(),
+        run::<akd::ExperimentalConfiguration<akd::ExampleLabel>>(),
+        run::<akd::Sha3Configuration<akd::ExampleLabel>>(),
+    ];
+    if within_budget.contains(&false) {
+        eprintln!("Some of the operations exceeded their allocation budget");
+        std::process::exit(1);
+    }
+}
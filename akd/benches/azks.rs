@@ -16,7 +16,7 @@ use akd::storage::manager::StorageManager;
 use akd::storage::memory::AsyncInMemoryDatabase;
 use akd::NamedConfiguration;
 use akd::{Azks, AzksElement, AzksValue, NodeLabel};
-use criterion::{BatchSize, Criterion};
+use criterion::{BatchSize, Criterion, Throughput};
 use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
 
@@ -75,26 +75,80 @@ fn batch_insertion<TC: NamedConfiguration>(c: &mut Criterion) {
     });
 }
 
-bench_config!(audit_verify);
-fn audit_verify<TC: NamedConfiguration>(c: &mut Criterion) {
+bench_config!(batch_insertion_without_cache);
+fn batch_insertion_without_cache<TC: NamedConfiguration>(c: &mut Criterion) {
     let num_initial_leaves = 10000;
-    let num_inserted_leaves = 10000;
+    let num_inserted_leaves = 1000;
 
     let mut rng = StdRng::seed_from_u64(42);
     let runtime = tokio::runtime::Builder::new_multi_thread().build().unwrap();
 
-    // prepare node sets for start and end epochs
+    // prepare node set for initial leaves
     let initial_node_set = gen_nodes(&mut rng, num_initial_leaves);
+
+    // prepare node set for batch insertion
     let node_set = gen_nodes(&mut rng, num_inserted_leaves);
 
-    // benchmark audit verify
+    // benchmark batch insertion, where every node which is read while updating the
+    // hashes of the tree is read from the database rather than a cache
     let id = format!(
-        "Audit verify (epoch 1: {} leaves, epoch 2: {} leaves) ({})",
+        "Batch insertion without cache ({} initial leaves, {} inserted leaves) ({})",
         num_initial_leaves,
         num_inserted_leaves,
         TC::name(),
     );
     c.bench_function(&id, move |b| {
+        b.iter_batched(
+            || {
+                let database = AsyncInMemoryDatabase::new();
+                let db = StorageManager::new_no_cache(database);
+                let mut azks = runtime.block_on(Azks::new::<TC, _>(&db)).unwrap();
+
+                // insert initial leaves as part of setup
+                runtime
+                    .block_on(azks.batch_insert_nodes::<TC, _>(
+                        &db,
+                        initial_node_set.clone(),
+                        InsertMode::Directory,
+                    ))
+                    .unwrap();
+                (azks, db, node_set.clone())
+            },
+            |(mut azks, db, node_set)| {
+                runtime
+                    .block_on(azks.batch_insert_nodes::<TC, _>(
+                        &db,
+                        node_set,
+                        InsertMode::Directory,
+                    ))
+                    .unwrap();
+            },
+            BatchSize::PerIteration,
+        );
+    });
+}
+
+bench_config!(audit_verify);
+fn audit_verify<TC: NamedConfiguration>(c: &mut Criterion) {
+    let num_initial_leaves = 10000;
+    let num_inserted_leaves = 10000;
+
+    let mut rng = StdRng::seed_from_u64(42);
+    let runtime = tokio::runtime::Builder::new_multi_thread().build().unwrap();
+
+    // prepare node sets for start and end epochs
+    let initial_node_set = gen_nodes(&mut rng, num_initial_leaves);
+    let node_set = gen_nodes(&mut rng, num_inserted_leaves);
+
+    // benchmark audit verify, reporting the throughput in inserted leaves so that it can be
+    // compared across the configurations of the group
+    let id = format!(
+        "Audit verify (epoch 1: {} leaves, epoch 2: {} leaves)",
+        num_initial_leaves, num_inserted_leaves,
+    );
+    let mut group = c.benchmark_group(id);
+    group.throughput(Throughput::Elements(num_inserted_leaves as u64));
+    group.bench_function(TC::name(), move |b| {
         b.iter_batched(
             || {
                 let database = AsyncInMemoryDatabase::new();
@@ -139,6 +193,7 @@ fn audit_verify<TC: NamedConfiguration>(c: &mut Criterion) {
             BatchSize::PerIteration,
         );
     });
+    group.finish();
 }
 
 bench_config!(audit_generate);
@@ -193,7 +248,13 @@ fn gen_nodes(rng: &mut impl Rng, num_nodes: usize) -> Vec<AzksElement> {
         .collect()
 }
 
-group_config!(azks_benches, batch_insertion, audit_verify, audit_generate);
+group_config!(
+    azks_benches,
+    batch_insertion,
+    batch_insertion_without_cache,
+    audit_verify,
+    audit_generate
+);
 
 fn main() {
     // NOTE(new_config): Add a new configuration here
@@ -202,6 +263,8 @@ fn main() {
     azks_benches_whatsapp_v1_config();
     #[cfg(feature = "experimental")]
     azks_benches_experimental_config();
+    #[cfg(feature = "sha3_256")]
+    azks_benches_sha3_256_config();
 
     Criterion::default().configure_from_args().final_summary();
 }
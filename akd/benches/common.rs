@@ -20,6 +20,11 @@ macro_rules! bench_config {
             fn [<$x _ experimental_config>](c: &mut Criterion) {
                 $x::<akd_core::ExperimentalConfiguration<akd_core::ExampleLabel>>(c)
             }
+
+            #[cfg(feature = "sha3_256")]
+            fn [<$x _ sha3_256_config>](c: &mut Criterion) {
+                $x::<akd_core::Sha3Configuration<akd_core::ExampleLabel>>(c)
+            }
         }
     };
 }
@@ -43,6 +48,13 @@ macro_rules! group_config {
                     [<$group _ experimental_config>],
                 )+
             );
+
+            #[cfg(feature = "sha3_256")]
+            criterion_group!(
+                $(
+                    [<$group _ sha3_256_config>],
+                )+
+            );
         }
     };
 }
@@ -15,11 +15,60 @@ use akd::storage::manager::StorageManager;
 use akd::storage::memory::AsyncInMemoryDatabase;
 use akd::NamedConfiguration;
 use akd::{AkdLabel, AkdValue, Directory};
-use criterion::{BatchSize, Criterion};
+use criterion::{BatchSize, Criterion, Throughput};
 use rand::distributions::Alphanumeric;
 use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
 
+bench_config!(publish);
+fn publish<TC: NamedConfiguration>(c: &mut Criterion) {
+    let num_initial_users = 1000;
+    let num_updates = 1000;
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_time()
+        .build()
+        .unwrap();
+
+    let updates = |range: std::ops::Range<usize>| {
+        range
+            .map(|i| {
+                (
+                    AkdLabel::from(&format!("User {i}")),
+                    AkdValue::from(&format!("Value {i}")),
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    let initial_updates = updates(0..num_initial_users);
+    let published_updates = updates(num_initial_users / 2..num_initial_users / 2 + num_updates);
+
+    // benchmark a publish which updates half of the existing users and adds as many new ones,
+    // reporting the throughput in updates so that it can be compared across the configurations
+    // of the group
+    let id = format!("Publish ({num_initial_users} initial users, {num_updates} updates)");
+    let mut group = c.benchmark_group(id);
+    group.throughput(Throughput::Elements(num_updates as u64));
+    group.bench_function(TC::name(), move |b| {
+        b.iter_batched(
+            || {
+                let db = StorageManager::new(AsyncInMemoryDatabase::new(), None, None, None);
+                let directory = runtime
+                    .block_on(Directory::<TC, _, _>::new(db, HardCodedAkdVRF {}))
+                    .unwrap();
+                runtime
+                    .block_on(directory.publish(initial_updates.clone()))
+                    .unwrap();
+                (directory, published_updates.clone())
+            },
+            |(directory, updates)| {
+                runtime.block_on(directory.publish(updates)).unwrap();
+            },
+            BatchSize::PerIteration,
+        );
+    });
+    group.finish();
+}
+
 bench_config!(history_generation);
 fn history_generation<TC: NamedConfiguration>(c: &mut Criterion) {
     let num_users = 1000;
@@ -89,7 +138,7 @@ fn history_generation<TC: NamedConfiguration>(c: &mut Criterion) {
     });
 }
 
-group_config!(directory_benches, history_generation);
+group_config!(directory_benches, publish, history_generation);
 
 fn main() {
     // NOTE(new_config): Add a new configuration here
@@ -98,6 +147,8 @@ fn main() {
     directory_benches_whatsapp_v1_config();
     #[cfg(feature = "experimental")]
     directory_benches_experimental_config();
+    #[cfg(feature = "sha3_256")]
+    directory_benches_sha3_256_config();
 
     Criterion::default().configure_from_args().final_summary();
 }
@@ -0,0 +1,223 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Benchmarks of the generation of lookup, batch lookup and key history proofs, across tree sizes
+//! and cache configurations.
+//!
+//! The tree sizes (in number of labels) are read from the comma-separated `AKD_BENCH_TREE_SIZES`
+//! environment variable, and default to 10^4 and 10^5. Larger trees (e.g.
+//! `AKD_BENCH_TREE_SIZES=1000000,10000000`) take a long time to set up, and are held in memory.
+
+#[macro_use]
+extern crate criterion;
+
+mod common;
+
+use akd::ecvrf::HardCodedAkdVRF;
+use akd::storage::manager::StorageManager;
+use akd::storage::memory::AsyncInMemoryDatabase;
+use akd::NamedConfiguration;
+use akd::{AkdLabel, AkdValue, Directory, HistoryParams};
+use criterion::measurement::WallTime;
+use criterion::{BatchSize, BenchmarkGroup, BenchmarkId, Criterion, Throughput};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const DEFAULT_TREE_SIZES: [usize; 2] = [10_000, 100_000];
+/// The number of labels published per epoch while building a tree
+const PUBLISH_BATCH_SIZE: usize = 100_000;
+/// The number of labels which are updated in each of the epochs following the initial ones, so
+/// that their key history has several versions
+const NUM_HISTORY_LABELS: usize = 100;
+const NUM_HISTORY_EPOCHS: usize = 10;
+const BATCH_LOOKUP_SIZE: usize = 100;
+
+/// The cache configurations of the storage manager of the benchmarked directories
+#[derive(Clone, Copy, Debug)]
+enum CacheConfig {
+    /// No cache, every node is read from the database
+    Uncached,
+    /// A cache which is flushed before each proof generation
+    Cold,
+    /// A cache which retains the nodes read by the previous proof generations
+    Warm,
+}
+
+impl CacheConfig {
+    const ALL: [CacheConfig; 3] = [Self::Uncached, Self::Cold, Self::Warm];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Uncached => "no cache",
+            Self::Cold => "cold cache",
+            Self::Warm => "warm cache",
+        }
+    }
+
+    fn storage(&self, db: &AsyncInMemoryDatabase) -> StorageManager<AsyncInMemoryDatabase> {
+        match self {
+            Self::Uncached => StorageManager::new_no_cache(db.clone()),
+            Self::Cold | Self::Warm => StorageManager::new(db.clone(), None, None, None),
+        }
+    }
+}
+
+fn tree_sizes() -> Vec<usize> {
+    match std::env::var("AKD_BENCH_TREE_SIZES") {
+        Ok(sizes) => sizes
+            .split(',')
+            .map(|size| {
+                size.trim()
+                    .parse()
+                    .expect("AKD_BENCH_TREE_SIZES must be a comma-separated list of tree sizes")
+            })
+            .collect(),
+        Err(_) => DEFAULT_TREE_SIZES.to_vec(),
+    }
+}
+
+fn label(i: usize) -> AkdLabel {
+    AkdLabel::from(&format!("User {i}"))
+}
+
+/// Publishes `num_labels` labels, followed by [NUM_HISTORY_EPOCHS] epochs which update the first
+/// [NUM_HISTORY_LABELS] of them
+fn build_tree<TC: NamedConfiguration>(
+    runtime: &tokio::runtime::Runtime,
+    num_labels: usize,
+) -> AsyncInMemoryDatabase {
+    let db = AsyncInMemoryDatabase::new();
+    let directory = runtime
+        .block_on(Directory::<TC, _, _>::new(
+            StorageManager::new_no_cache(db.clone()),
+            HardCodedAkdVRF {},
+        ))
+        .unwrap();
+    for start in (0..num_labels).step_by(PUBLISH_BATCH_SIZE) {
+        let updates = (start..num_labels.min(start + PUBLISH_BATCH_SIZE))
+            .map(|i| (label(i), AkdValue::from(&format!("Value {i}"))))
+            .collect::<Vec<_>>();
+        runtime.block_on(directory.publish(updates)).unwrap();
+    }
+    for epoch in 0..NUM_HISTORY_EPOCHS {
+        let updates = (0..NUM_HISTORY_LABELS.min(num_labels))
+            .map(|i| (label(i), AkdValue::from(&format!("Value {i} {epoch}"))))
+            .collect::<Vec<_>>();
+        runtime.block_on(directory.publish(updates)).unwrap();
+    }
+    db
+}
+
+/// Benchmarks a proof generation on the directory, flushing its cache before each generation for
+/// [CacheConfig::Cold]
+fn bench_generation<TC: NamedConfiguration, F>(
+    group: &mut BenchmarkGroup<WallTime>,
+    runtime: &tokio::runtime::Runtime,
+    id: BenchmarkId,
+    db: &AsyncInMemoryDatabase,
+    cache_config: CacheConfig,
+    generate: F,
+) where
+    F: Fn(&Directory<TC, AsyncInMemoryDatabase, HardCodedAkdVRF>, &mut StdRng),
+{
+    let storage = cache_config.storage(db);
+    let directory = runtime
+        .block_on(Directory::<TC, _, _>::new(
+            storage.clone(),
+            HardCodedAkdVRF {},
+        ))
+        .unwrap();
+    let mut rng = StdRng::seed_from_u64(42);
+    group.bench_function(id, |b| match cache_config {
+        CacheConfig::Cold => b.iter_batched(
+            || runtime.block_on(storage.flush_cache()),
+            |_| generate(&directory, &mut rng),
+            BatchSize::PerIteration,
+        ),
+        CacheConfig::Uncached | CacheConfig::Warm => b.iter(|| generate(&directory, &mut rng)),
+    });
+}
+
+bench_config!(proof_generation);
+fn proof_generation<TC: NamedConfiguration>(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_time()
+        .build()
+        .unwrap();
+
+    for num_labels in tree_sizes() {
+        let db = build_tree::<TC>(&runtime, num_labels);
+
+        let mut group = c.benchmark_group(format!("Lookup ({})", TC::name()));
+        for cache_config in CacheConfig::ALL {
+            bench_generation::<TC, _>(
+                &mut group,
+                &runtime,
+                BenchmarkId::new(cache_config.name(), num_labels),
+                &db,
+                cache_config,
+                |directory, rng| {
+                    let label = label(rng.gen_range(0..num_labels));
+                    runtime.block_on(directory.lookup(label)).unwrap();
+                },
+            );
+        }
+        group.finish();
+
+        let mut group = c.benchmark_group(format!("Batch lookup ({})", TC::name()));
+        group.throughput(Throughput::Elements(BATCH_LOOKUP_SIZE as u64));
+        for cache_config in CacheConfig::ALL {
+            bench_generation::<TC, _>(
+                &mut group,
+                &runtime,
+                BenchmarkId::new(cache_config.name(), num_labels),
+                &db,
+                cache_config,
+                |directory, rng| {
+                    let labels = (0..BATCH_LOOKUP_SIZE)
+                        .map(|_| label(rng.gen_range(0..num_labels)))
+                        .collect::<Vec<_>>();
+                    runtime.block_on(directory.batch_lookup(&labels)).unwrap();
+                },
+            );
+        }
+        group.finish();
+
+        let mut group = c.benchmark_group(format!("Key history ({})", TC::name()));
+        for cache_config in CacheConfig::ALL {
+            bench_generation::<TC, _>(
+                &mut group,
+                &runtime,
+                BenchmarkId::new(cache_config.name(), num_labels),
+                &db,
+                cache_config,
+                |directory, rng| {
+                    let label = label(rng.gen_range(0..NUM_HISTORY_LABELS.min(num_labels)));
+                    runtime
+                        .block_on(directory.key_history(&label, HistoryParams::Complete))
+                        .unwrap();
+                },
+            );
+        }
+        group.finish();
+    }
+}
+
+group_config!(proof_benches, proof_generation);
+
+fn main() {
+    // NOTE(new_config): Add a new configuration here
+
+    #[cfg(feature = "whatsapp_v1")]
+    proof_benches_whatsapp_v1_config();
+    #[cfg(feature = "experimental")]
+    proof_benches_experimental_config();
+    #[cfg(feature = "sha3_256")]
+    proof_benches_sha3_256_config();
+
+    Criterion::default().configure_from_args().final_summary();
+}
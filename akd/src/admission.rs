@@ -0,0 +1,161 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Admission control for the proof generations of a [crate::Directory], which bounds
+//! the load a burst of requests can put on the storage layer. Requests beyond the
+//! configured limits are either queued (for a bounded amount of time) or shed with a
+//! [DirectoryError::Overloaded] error.
+
+use crate::errors::{AkdError, DirectoryError};
+use crate::runtime;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// The limits enforced on the proof generations of a directory, configured via
+/// [crate::Directory::with_admission_control].
+///
+/// The cost of a proof generation is the number of versions it has to prove: one per
+/// label for a lookup, and the number of update proofs plus marker versions for a
+/// key history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdmissionConfig {
+    max_concurrent: usize,
+    max_call_cost: Option<u64>,
+    queue_timeout: Option<Duration>,
+}
+
+impl AdmissionConfig {
+    /// Allows at most `max_concurrent` proof generations to run at a time. By default,
+    /// any further request is shed immediately.
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            max_concurrent,
+            max_call_cost: None,
+            queue_timeout: None,
+        }
+    }
+
+    /// Rejects any proof generation whose cost exceeds `max_call_cost`
+    pub fn with_max_call_cost(mut self, max_call_cost: u64) -> Self {
+        self.max_call_cost = Some(max_call_cost);
+        self
+    }
+
+    /// Queues requests for up to `timeout` while the maximum number of proof generations
+    /// are running, rather than shedding them immediately
+    pub fn with_queue_timeout(mut self, timeout: Duration) -> Self {
+        self.queue_timeout = Some(timeout);
+        self
+    }
+
+    /// The maximum number of proof generations which run at a time
+    pub fn max_concurrent(&self) -> usize {
+        self.max_concurrent
+    }
+}
+
+/// Enforces an [AdmissionConfig], shared by all clones of a directory
+pub(crate) struct AdmissionController {
+    config: AdmissionConfig,
+    permits: Arc<Semaphore>,
+    rejected: AtomicU64,
+}
+
+impl AdmissionController {
+    pub(crate) fn new(config: AdmissionConfig) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(config.max_concurrent)),
+            config,
+            rejected: AtomicU64::new(0),
+        }
+    }
+
+    /// Waits for a proof generation slot, which is held until the returned permit is dropped
+    pub(crate) async fn admit(&self) -> Result<OwnedSemaphorePermit, AkdError> {
+        let permit = match self.config.queue_timeout {
+            None => self.permits.clone().try_acquire_owned().ok(),
+            Some(timeout) => runtime::timeout(timeout, self.permits.clone().acquire_owned())
+                .await
+                .ok()
+                .and_then(Result::ok),
+        };
+        permit.ok_or_else(|| {
+            self.reject(format!(
+                "All {} proof generation slots are in use",
+                self.config.max_concurrent
+            ))
+        })
+    }
+
+    /// Checks the cost of a proof generation against the per-call budget
+    pub(crate) fn check_cost(&self, cost: u64) -> Result<(), AkdError> {
+        match self.config.max_call_cost {
+            Some(max_call_cost) if cost > max_call_cost => Err(self.reject(format!(
+                "Proof generation of cost {cost} exceeds the per-call budget of {max_call_cost}"
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// The number of requests which have been rejected
+    pub(crate) fn rejected_count(&self) -> u64 {
+        self.rejected.load(Ordering::Relaxed)
+    }
+
+    fn reject(&self, message: String) -> AkdError {
+        self.rejected.fetch_add(1, Ordering::Relaxed);
+        AkdError::Directory(DirectoryError::Overloaded(message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_admission_sheds_when_saturated() {
+        let controller = AdmissionController::new(AdmissionConfig::new(1));
+        let permit = controller.admit().await.unwrap();
+        assert!(matches!(
+            controller.admit().await,
+            Err(AkdError::Directory(DirectoryError::Overloaded(_)))
+        ));
+        drop(permit);
+        assert!(controller.admit().await.is_ok());
+        assert_eq!(1, controller.rejected_count());
+    }
+
+    #[tokio::test]
+    async fn test_admission_queues_until_timeout() {
+        let controller = Arc::new(AdmissionController::new(
+            AdmissionConfig::new(1).with_queue_timeout(Duration::from_millis(50)),
+        ));
+        let permit = controller.admit().await.unwrap();
+        assert!(controller.admit().await.is_err());
+
+        // A queued request is admitted once the running one completes
+        let queued = {
+            let controller = controller.clone();
+            tokio::spawn(async move { controller.admit().await.map(|_| ()) })
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        drop(permit);
+        assert!(queued.await.unwrap().is_ok());
+        assert_eq!(1, controller.rejected_count());
+    }
+
+    #[test]
+    fn test_admission_call_cost() {
+        let controller = AdmissionController::new(AdmissionConfig::new(1).with_max_call_cost(10));
+        assert!(controller.check_cost(10).is_ok());
+        assert!(controller.check_cost(11).is_err());
+        assert_eq!(1, controller.rejected_count());
+    }
+}
@@ -7,20 +7,23 @@
 
 //! An implementation of an append-only zero knowledge set
 
+use crate::cancellation::CancellationToken;
 use crate::hash::EMPTY_DIGEST;
 use crate::helper_structs::LookupInfo;
-use crate::storage::manager::StorageManager;
+use crate::runtime;
+use crate::storage::manager::{LookupSetPreload, PreloadStrategy, StorageManager};
 use crate::storage::types::StorageType;
 use crate::tree_node::{
     new_interior_node, new_leaf_node, new_root_node, node_to_azks_value, node_to_label,
-    NodeHashingMode, NodeKey, TreeNode, TreeNodeType,
+    NodeHashingMode, NodeKey, TreeNode, TreeNodeType, TreeNodeWithPreviousValue,
 };
 use crate::Configuration;
 use crate::{
     errors::{AkdError, DirectoryError, ParallelismError, TreeNodeError},
     storage::{Database, Storable},
-    AppendOnlyProof, AzksElement, AzksValue, Digest, Direction, MembershipProof, NodeLabel,
-    NonMembershipProof, PrefixOrdering, SiblingProof, SingleAppendOnlyProof, SizeOf, ARITY,
+    AppendOnlyProof, AuditProofSegment, AzksElement, AzksValue, Digest, Direction, MembershipProof,
+    NodeLabel, NonMembershipProof, PrefixOrdering, SiblingProof, SingleAppendOnlyProof, SizeOf,
+    ARITY,
 };
 use async_recursion::async_recursion;
 use log::info;
@@ -36,7 +39,7 @@ pub const DEFAULT_AZKS_KEY: u8 = 1u8;
 
 /// The default available parallelism for parallel batch insertions, used when
 /// available parallelism cannot be determined at runtime. Should be > 1
-#[cfg(feature = "parallel_insert")]
+#[cfg(any(feature = "parallel_insert", feature = "parallel_audit"))]
 pub const DEFAULT_AVAILABLE_PARALLELISM: usize = 32;
 
 async fn tic_toc<T>(f: impl core::future::Future<Output = T>) -> (T, Option<f64>) {
@@ -52,10 +55,10 @@ async fn tic_toc<T>(f: impl core::future::Future<Output = T>) -> (T, Option<f64>
 }
 
 fn get_parallel_levels() -> Option<u8> {
-    #[cfg(not(feature = "parallel_insert"))]
+    #[cfg(any(not(feature = "parallel_insert"), feature = "profiling"))]
     return None;
 
-    #[cfg(feature = "parallel_insert")]
+    #[cfg(all(feature = "parallel_insert", not(feature = "profiling")))]
     {
         // Based on profiling results, the best performance is achieved when the
         // number of spawned tasks is equal to the number of available threads.
@@ -66,20 +69,30 @@ fn get_parallel_levels() -> Option<u8> {
         // approximation that should yield good performance in most cases.
         let available_parallelism = std::thread::available_parallelism()
             .map_or(DEFAULT_AVAILABLE_PARALLELISM, |v| v.into());
-        // The number of tasks spawned at a level is the number of leaves at
-        // the level. As we are using a binary tree, the number of leaves at a
-        // level is 2^level. Therefore, the number of levels that should be
-        // executed in parallel is the log2 of the number of available threads.
-        let parallel_levels = (available_parallelism as f32).log2().ceil() as u8;
+        let parallel_levels = get_parallel_levels_for_tasks(available_parallelism);
 
         info!(
-            "Insert will be performed in parallel (available parallelism: {}, parallel levels: {})",
+            "Insert will be performed in parallel (available parallelism: {}, parallel levels: {:?})",
             available_parallelism, parallel_levels
         );
-        Some(parallel_levels)
+        parallel_levels
     }
 }
 
+/// Computes the number of tree levels which should be processed in parallel to
+/// spread the work across (roughly) the given number of tasks. With the
+/// `profiling` feature, the work is never spread across tasks.
+pub(crate) fn get_parallel_levels_for_tasks(num_tasks: usize) -> Option<u8> {
+    if num_tasks <= 1 || cfg!(feature = "profiling") {
+        return None;
+    }
+    // The number of tasks spawned at a level is the number of leaves at
+    // the level. As we are using a binary tree, the number of leaves at a
+    // level is 2^level. Therefore, the number of levels that should be
+    // executed in parallel is the log2 of the number of tasks.
+    Some((num_tasks as f32).log2().ceil() as u8)
+}
+
 /// An azks is built both by the [crate::directory::Directory] and the auditor.
 /// However, both constructions have very minor differences, and the insert
 /// mode enum is used to differentiate between the two.
@@ -300,11 +313,50 @@ impl Azks {
         storage: &StorageManager<S>,
         nodes: Vec<AzksElement>,
         insert_mode: InsertMode,
+    ) -> Result<(), AkdError> {
+        self.batch_insert_nodes_with_levels::<TC, _>(
+            storage,
+            nodes,
+            insert_mode,
+            get_parallel_levels(),
+        )
+        .await
+    }
+
+    /// Insert a batch of new leaves, spreading the insertion across (roughly) `num_tasks`
+    /// tokio tasks regardless of the `parallel_insert` feature. The leaves are partitioned
+    /// by the top-level prefixes of their labels, the subtree of each partition is built by
+    /// its own task, and the hashes of the subtrees are then merged up to the root. The
+    /// resulting tree is the same as for [Azks::batch_insert_nodes].
+    pub async fn batch_insert_nodes_in_parallel<TC: Configuration, S: Database + 'static>(
+        &mut self,
+        storage: &StorageManager<S>,
+        nodes: Vec<AzksElement>,
+        insert_mode: InsertMode,
+        num_tasks: usize,
+    ) -> Result<(), AkdError> {
+        self.batch_insert_nodes_with_levels::<TC, _>(
+            storage,
+            nodes,
+            insert_mode,
+            get_parallel_levels_for_tasks(num_tasks),
+        )
+        .await
+    }
+
+    async fn batch_insert_nodes_with_levels<TC: Configuration, S: Database + 'static>(
+        &mut self,
+        storage: &StorageManager<S>,
+        nodes: Vec<AzksElement>,
+        insert_mode: InsertMode,
+        parallel_levels: Option<u8>,
     ) -> Result<(), AkdError> {
         let azks_element_set = AzksElementSet::from(nodes);
 
         // preload the nodes that we will visit during the insertion
-        let (_, time_s) = tic_toc(self.preload_nodes(storage, &azks_element_set)).await;
+        let (_, time_s) =
+            tic_toc(self.preload_nodes(storage, &azks_element_set, storage.preload_strategy()))
+                .await;
         if let Some(time) = time_s {
             info!("Preload of tree took {} s", time,);
         }
@@ -320,7 +372,7 @@ impl Azks {
                 azks_element_set,
                 self.latest_epoch,
                 insert_mode,
-                get_parallel_levels(),
+                parallel_levels,
             )
             .await?;
             root_node.write_to_storage(storage, is_new).await?;
@@ -414,6 +466,10 @@ impl Azks {
             azks_element_set.partition(current_node.label);
         let child_parallel_levels =
             parallel_levels.and_then(|x| if x <= 1 { None } else { Some(x - 1) });
+        // The updated children are retained, so that the hash of the current node can be
+        // computed from them without reading them back from storage
+        let mut updated_left_child = None;
+        let mut updated_right_child = None;
 
         // handle the left child
         let maybe_handle = if !left_azks_element_set.is_empty() {
@@ -434,7 +490,7 @@ impl Azks {
             if parallel_levels.is_some() {
                 // spawn a task and return the handle if there are still levels
                 // to be processed in parallel
-                Some(tokio::task::spawn(left_future))
+                Some(runtime::spawn(left_future))
             } else {
                 // else handle the left child in the current task
                 let (mut left_node, left_is_new, left_num_inserted) = left_future.await?;
@@ -442,6 +498,7 @@ impl Azks {
                 current_node.set_child(&mut left_node)?;
                 left_node.write_to_storage(storage, left_is_new).await?;
                 num_inserted += left_num_inserted;
+                updated_left_child = Some(left_node);
                 None
             }
         } else {
@@ -465,6 +522,7 @@ impl Azks {
             current_node.set_child(&mut right_node)?;
             right_node.write_to_storage(storage, right_is_new).await?;
             num_inserted += right_num_inserted;
+            updated_right_child = Some(right_node);
         }
 
         // join on the handle for the left child, if present
@@ -475,17 +533,182 @@ impl Azks {
             current_node.set_child(&mut left_node)?;
             left_node.write_to_storage(storage, left_is_new).await?;
             num_inserted += left_num_inserted;
+            updated_left_child = Some(left_node);
         }
 
         // Phase 3: Update the hash of the current node and return it along with
-        // the number of nodes inserted.
+        // the number of nodes inserted. Each node on the paths of the inserted
+        // leaves is hashed exactly once, after all of its children were updated.
         current_node
-            .update_hash::<TC, _>(storage, NodeHashingMode::from(insert_mode))
+            .update_hash_with_children::<TC, _>(
+                storage,
+                NodeHashingMode::from(insert_mode),
+                [updated_left_child, updated_right_child],
+            )
             .await?;
 
         Ok((current_node, is_new, num_inserted))
     }
 
+    /// Builds the first epoch of an empty tree from a batch of leaves. In contrast to
+    /// [Azks::batch_insert_nodes], the tree is constructed bottom-up in memory without
+    /// reading any nodes from storage. The resulting nodes (including the updated root)
+    /// are returned, and it is the caller's responsibility to write them to storage.
+    pub(crate) async fn bulk_build<TC: Configuration>(
+        &mut self,
+        mut nodes: Vec<AzksElement>,
+        insert_mode: InsertMode,
+    ) -> Result<Vec<TreeNodeWithPreviousValue>, AkdError> {
+        if self.latest_epoch != 0 || self.num_nodes != 1 {
+            return Err(AkdError::Directory(DirectoryError::Publish(format!(
+                "Cannot bulk build a tree which is not empty (epoch {}, {} nodes)",
+                self.latest_epoch, self.num_nodes
+            ))));
+        }
+        nodes.sort_unstable();
+        if nodes.windows(2).any(|pair| pair[0].label == pair[1].label) {
+            return Err(AkdError::Directory(DirectoryError::Publish(
+                "Cannot bulk build a tree from a set of leaves that contain duplicate labels"
+                    .to_string(),
+            )));
+        }
+
+        self.increment_epoch();
+        let previous_root = new_root_node::<TC>();
+        let mut root_node = previous_root.clone();
+        let mut built_nodes = Self::bulk_build_children::<TC>(
+            &mut root_node,
+            AzksElementSet::from(nodes),
+            self.latest_epoch,
+            insert_mode,
+            get_parallel_levels(),
+        )
+        .await?;
+        self.num_nodes += built_nodes.len() as u64;
+        info!("Bulk build completed ({} new nodes)", built_nodes.len());
+
+        let mut records = built_nodes
+            .drain(..)
+            .map(|node| TreeNodeWithPreviousValue {
+                label: node.label,
+                latest_node: node,
+                previous_node: None,
+            })
+            .collect::<Vec<_>>();
+        records.push(TreeNodeWithPreviousValue {
+            label: root_node.label,
+            latest_node: root_node,
+            previous_node: Some(previous_root),
+        });
+        Ok(records)
+    }
+
+    /// Builds the subtree containing the given (non-empty) set of leaves, returning
+    /// its root along with all of its descendants
+    #[async_recursion]
+    async fn recursive_bulk_build<TC: Configuration>(
+        azks_element_set: AzksElementSet,
+        epoch: u64,
+        insert_mode: InsertMode,
+        parallel_levels: Option<u8>,
+    ) -> Result<(TreeNode, Vec<TreeNode>), AkdError> {
+        if let [node] = &azks_element_set[..] {
+            return Ok((new_leaf_node::<TC>(node.label, &node.value, epoch), vec![]));
+        }
+
+        let lcp_label = azks_element_set.get_longest_common_prefix::<TC>();
+        let mut current_node = new_interior_node::<TC>(lcp_label, epoch);
+        let descendants = Self::bulk_build_children::<TC>(
+            &mut current_node,
+            azks_element_set,
+            epoch,
+            insert_mode,
+            parallel_levels,
+        )
+        .await?;
+        Ok((current_node, descendants))
+    }
+
+    /// Builds the subtrees below the given node from the set of leaves, setting the
+    /// node's children and hash. The built children and their descendants are returned.
+    #[async_recursion]
+    async fn bulk_build_children<TC: Configuration>(
+        current_node: &mut TreeNode,
+        azks_element_set: AzksElementSet,
+        epoch: u64,
+        insert_mode: InsertMode,
+        parallel_levels: Option<u8>,
+    ) -> Result<Vec<TreeNode>, AkdError> {
+        let (left_azks_element_set, right_azks_element_set) =
+            azks_element_set.partition(current_node.label);
+        let child_parallel_levels =
+            parallel_levels.and_then(|x| if x <= 1 { None } else { Some(x - 1) });
+
+        // handle the left child, in a separate task if there are still levels to be
+        // processed in parallel
+        let mut left_subtree = None;
+        let mut maybe_handle = None;
+        if !left_azks_element_set.is_empty() {
+            let left_future = Self::recursive_bulk_build::<TC>(
+                left_azks_element_set,
+                epoch,
+                insert_mode,
+                child_parallel_levels,
+            );
+            if parallel_levels.is_some() {
+                maybe_handle = Some(runtime::spawn(left_future));
+            } else {
+                left_subtree = Some(left_future.await?);
+            }
+        }
+
+        // handle the right child in the current task
+        let right_subtree = if !right_azks_element_set.is_empty() {
+            Some(
+                Self::recursive_bulk_build::<TC>(
+                    right_azks_element_set,
+                    epoch,
+                    insert_mode,
+                    child_parallel_levels,
+                )
+                .await?,
+            )
+        } else {
+            None
+        };
+
+        // join on the handle for the left child, if present
+        if let Some(handle) = maybe_handle {
+            left_subtree =
+                Some(handle.await.map_err(|e| {
+                    AkdError::Parallelism(ParallelismError::JoinErr(e.to_string()))
+                })??);
+        }
+
+        let mut built_nodes = Vec::new();
+        let [mut left_child, mut right_child] = [left_subtree, right_subtree].map(|subtree| {
+            subtree.map(|(child_node, descendants)| {
+                built_nodes.extend(descendants);
+                child_node
+            })
+        });
+        for child_node in [&mut left_child, &mut right_child].into_iter().flatten() {
+            current_node.set_child(child_node)?;
+        }
+
+        // The children are in memory, so the hash is computed without going through storage
+        let hash_mode = NodeHashingMode::from(insert_mode);
+        current_node.hash = TC::compute_parent_hash_from_children(
+            &node_to_azks_value::<TC>(&left_child, hash_mode),
+            &node_to_label::<TC>(&left_child).value::<TC>(),
+            &node_to_azks_value::<TC>(&right_child, hash_mode),
+            &node_to_label::<TC>(&right_child).value::<TC>(),
+        );
+        built_nodes.extend(left_child);
+        built_nodes.extend(right_child);
+        Ok(built_nodes)
+    }
+
     #[cfg(feature = "greedy_lookup_preload")]
     async fn get_next_node_in_child_path_from_cache<S: Database + Send + Sync>(
         &self,
@@ -500,11 +723,7 @@ impl Azks {
                     .await
                 {
                     Some(crate::storage::types::DbRecord::TreeNode(tnpv)) => {
-                        if let Ok(node) = tnpv.determine_node_to_get(self.latest_epoch) {
-                            Some(node)
-                        } else {
-                            None
-                        }
+                        tnpv.determine_node_to_get(self.latest_epoch).ok()
                     }
                     _ => None,
                 }
@@ -515,11 +734,7 @@ impl Azks {
                     .await
                 {
                     Some(crate::storage::types::DbRecord::TreeNode(tnpv)) => {
-                        if let Ok(node) = tnpv.determine_node_to_get(self.latest_epoch) {
-                            Some(node)
-                        } else {
-                            None
-                        }
+                        tnpv.determine_node_to_get(self.latest_epoch).ok()
                     }
                     _ => None,
                 }
@@ -541,22 +756,35 @@ impl Azks {
         let mut results = HashSet::new();
         let labels = [li.existent_label, li.marker_label, li.non_existent_label];
 
-        let root_node: TreeNode =
-            TreeNode::get_from_storage(storage, &NodeKey(NodeLabel::root()), self.latest_epoch)
-                .await?;
+        // The root is only read from the cache, if it is missing it is loaded together with
+        // the rest of the path rather than in a separate roundtrip
+        let root_node = match storage
+            .get_from_cache_only::<crate::tree_node::TreeNodeWithPreviousValue>(&NodeKey(
+                NodeLabel::root(),
+            ))
+            .await
+        {
+            Some(crate::storage::types::DbRecord::TreeNode(tnpv)) => {
+                tnpv.determine_node_to_get(self.latest_epoch).ok()
+            }
+            _ => None,
+        };
 
         for label in labels {
-            let mut cnode = root_node.clone();
-            // walk through the cache to find the next node in the tree which isn't already loaded
-            while let Some(node) = self
-                .get_next_node_in_child_path_from_cache(storage, &cnode, &label)
-                .await
-            {
-                cnode = node;
+            let mut start_len = 0;
+            if let Some(mut cnode) = root_node.clone() {
+                // walk through the cache to find the next node in the tree which isn't already loaded
+                while let Some(node) = self
+                    .get_next_node_in_child_path_from_cache(storage, &cnode, &label)
+                    .await
+                {
+                    cnode = node;
+                }
+                start_len = cnode.label.label_len;
             }
             // load the rest of the nodes in the path, as soon as a child node can't be resolved. In the worst-case
             // this is loading every possible node on the path (i.e. uninitialized cache)
-            for len in cnode.label.label_len..256 {
+            for len in start_len..256 {
                 results.insert(label.get_prefix(len));
             }
         }
@@ -599,10 +827,8 @@ impl Azks {
         // generate the required proof structs.
         let children = nodes
             .into_iter()
-            .flat_map(|node| match (node.left_child, node.right_child) {
-                (Some(l), Some(r)) => vec![NodeKey(l), NodeKey(r)],
-                _ => vec![],
-            })
+            .filter_map(|node| Some([NodeKey(node.left_child?), NodeKey(node.right_child?)]))
+            .flatten()
             .collect::<Vec<_>>();
         requested_count += children.len() as u64;
 
@@ -628,7 +854,7 @@ impl Azks {
         // Collect lookup labels needed and convert them into Nodes for preloading.
         let lookup_nodes: Vec<AzksElement> = lookup_infos
             .iter()
-            .flat_map(|li| vec![li.existent_label, li.marker_label, li.non_existent_label])
+            .flat_map(|li| [li.existent_label, li.marker_label, li.non_existent_label])
             .chain(marker_labels.unwrap_or_default().iter().cloned())
             .map(|l| AzksElement {
                 label: l,
@@ -637,15 +863,21 @@ impl Azks {
             .collect();
 
         // Load nodes.
-        self.preload_nodes(storage, &AzksElementSet::from(lookup_nodes))
-            .await
+        self.preload_nodes(
+            storage,
+            &AzksElementSet::from(lookup_nodes),
+            &LookupSetPreload,
+        )
+        .await
     }
 
-    /// Preloads given nodes using breadth-first search.
+    /// Preloads the nodes selected by the strategy using breadth-first search, starting from
+    /// the root and following the paths to the given nodes.
     pub(crate) async fn preload_nodes<S: Database>(
         &self,
         storage: &StorageManager<S>,
         azks_element_set: &AzksElementSet,
+        strategy: &dyn PreloadStrategy,
     ) -> Result<u64, AkdError> {
         if !storage.has_cache() {
             info!("No cache found, skipping preload");
@@ -666,7 +898,9 @@ impl Azks {
             // individually for each node's state.
             current_nodes = nodes
                 .iter()
-                .filter(|node| azks_element_set.contains_prefix(&node.label))
+                .filter(|node| {
+                    strategy.expand(&node.label, azks_element_set.contains_prefix(&node.label))
+                })
                 .flat_map(|node| {
                     [Direction::Left, Direction::Right]
                         .iter()
@@ -764,6 +998,24 @@ impl Azks {
         storage: &StorageManager<S>,
         start_epoch: u64,
         end_epoch: u64,
+    ) -> Result<AppendOnlyProof, AkdError> {
+        self.get_append_only_proof_cancellable::<TC, _>(
+            storage,
+            start_epoch,
+            end_epoch,
+            &CancellationToken::new(),
+        )
+        .await
+    }
+
+    /// Same as [Azks::get_append_only_proof], but checks the cancellation token before
+    /// generating the proof for each epoch
+    pub async fn get_append_only_proof_cancellable<TC: Configuration, S: Database + 'static>(
+        &self,
+        storage: &StorageManager<S>,
+        start_epoch: u64,
+        end_epoch: u64,
+        cancellation: &CancellationToken,
     ) -> Result<AppendOnlyProof, AkdError> {
         let latest_epoch = self.get_latest_epoch();
         if latest_epoch < end_epoch || end_epoch <= start_epoch {
@@ -783,6 +1035,7 @@ impl Azks {
             TreeNode::get_from_storage(storage, &NodeKey(NodeLabel::root()), latest_epoch).await?;
 
         for ep in start_epoch..end_epoch {
+            cancellation.check("Audit")?;
             let (fallable_load_count, time_s) = tic_toc(self.gather_audit_proof_nodes::<_>(
                 vec![node.clone()],
                 storage,
@@ -825,29 +1078,126 @@ impl Azks {
         Ok(AppendOnlyProof { proofs, epochs })
     }
 
-    fn determine_retrieval_nodes(
-        node: &TreeNode,
+    /// Starts generating the append-only proof between `start_epoch` and `end_epoch` in
+    /// segments, which are then retrieved with [Azks::next_append_only_proof_segment].
+    /// The same restrictions as for [Azks::get_append_only_proof] apply to the epochs.
+    pub(crate) async fn start_append_only_proof_segments<S: Database>(
+        &self,
+        storage: &StorageManager<S>,
         start_epoch: u64,
         end_epoch: u64,
-    ) -> Vec<NodeLabel> {
-        if node.node_type == TreeNodeType::Leaf {
-            return vec![];
+    ) -> Result<AuditProofCursor, AkdError> {
+        let latest_epoch = self.get_latest_epoch();
+        if latest_epoch < end_epoch || end_epoch <= start_epoch {
+            return Err(AkdError::Directory(DirectoryError::InvalidEpoch(format!(
+                "Start epoch must be less than end epoch, and end epoch must be at most the latest epoch. \
+                Start epoch: {start_epoch}, end epoch: {end_epoch}, latest_epoch: {latest_epoch}."
+            ))));
         }
+        let root =
+            TreeNode::get_from_storage(storage, &NodeKey(NodeLabel::root()), latest_epoch).await?;
+        Ok(AuditProofCursor {
+            epoch: start_epoch,
+            end_epoch,
+            index: 0,
+            pending: vec![root.clone()],
+            root,
+        })
+    }
 
-        if node.get_latest_epoch() <= start_epoch {
-            return vec![];
+    /// Generates the next segment of the append-only proof, with at most `max_nodes`
+    /// inserted and unchanged nodes (but at least one). The nodes are visited in the same
+    /// order as in [Azks::get_append_only_proof], while keeping only the nodes along the
+    /// current path of the traversal in memory. Returns [None] once the proofs for all
+    /// the epochs have been generated.
+    pub(crate) async fn next_append_only_proof_segment<TC: Configuration, S: Database>(
+        &self,
+        storage: &StorageManager<S>,
+        cursor: &mut AuditProofCursor,
+        max_nodes: usize,
+    ) -> Result<Option<AuditProofSegment>, AkdError> {
+        if cursor.epoch >= cursor.end_epoch {
+            return Ok(None);
         }
+        let (start_epoch, end_epoch) = (cursor.epoch, cursor.epoch + 1);
+        let mut unchanged = Vec::<AzksElement>::new();
+        let mut leaves = Vec::<AzksElement>::new();
 
-        if node.min_descendant_epoch > end_epoch {
-            return vec![];
+        while unchanged.len() + leaves.len() < max_nodes.max(1) {
+            let node = match cursor.pending.pop() {
+                Some(node) => node,
+                None => break,
+            };
+            if node.get_latest_epoch() <= start_epoch {
+                // the root being unchanged since the last epoch results in an empty proof
+                if node.node_type != TreeNodeType::Root {
+                    unchanged.push(AzksElement {
+                        label: node.label,
+                        value: node_to_azks_value::<TC>(
+                            &Some(node),
+                            NodeHashingMode::WithLeafEpoch,
+                        ),
+                    });
+                }
+            } else if node.min_descendant_epoch > end_epoch {
+                continue;
+            } else if node.node_type == TreeNodeType::Leaf {
+                leaves.push(AzksElement {
+                    label: node.label,
+                    value: node.hash,
+                });
+            } else {
+                let children = [node.left_child, node.right_child]
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>();
+                let keys = children.iter().copied().map(NodeKey).collect::<Vec<_>>();
+                let mut fetched =
+                    TreeNode::batch_get_from_storage(storage, &keys, self.get_latest_epoch())
+                        .await?;
+                // the left child is pushed last, so that it is visited first
+                for label in children.into_iter().rev() {
+                    let position = fetched
+                        .iter()
+                        .position(|child| child.label == label)
+                        .ok_or(TreeNodeError::NonexistentAtEpoch(
+                            label,
+                            self.get_latest_epoch(),
+                        ))?;
+                    cursor.pending.push(fetched.swap_remove(position));
+                }
+            }
         }
 
-        match (node.left_child, node.right_child) {
-            (Some(lc), None) => vec![lc],
-            (None, Some(rc)) => vec![rc],
-            (Some(lc), Some(rc)) => vec![lc, rc],
-            _ => vec![],
+        let segment = AuditProofSegment {
+            epoch: start_epoch,
+            index: cursor.index,
+            last: cursor.pending.is_empty(),
+            inserted: leaves,
+            unchanged_nodes: unchanged,
+        };
+        if segment.last {
+            cursor.epoch += 1;
+            cursor.index = 0;
+            cursor.pending = vec![cursor.root.clone()];
+        } else {
+            cursor.index += 1;
         }
+        Ok(Some(segment))
+    }
+
+    fn determine_retrieval_nodes(
+        node: &TreeNode,
+        start_epoch: u64,
+        end_epoch: u64,
+    ) -> [Option<NodeLabel>; 2] {
+        if node.node_type == TreeNodeType::Leaf
+            || node.get_latest_epoch() <= start_epoch
+            || node.min_descendant_epoch > end_epoch
+        {
+            return [None, None];
+        }
+        [node.left_child, node.right_child]
     }
 
     async fn gather_audit_proof_nodes<S: Database>(
@@ -860,6 +1210,7 @@ impl Azks {
         let mut children_to_fetch: Vec<NodeKey> = nodes
             .iter()
             .flat_map(|node| Self::determine_retrieval_nodes(node, start_epoch, end_epoch))
+            .flatten()
             .map(NodeKey)
             .collect();
 
@@ -875,6 +1226,7 @@ impl Azks {
             children_to_fetch = got
                 .iter()
                 .flat_map(|node| Self::determine_retrieval_nodes(node, start_epoch, end_epoch))
+                .flatten()
                 .map(NodeKey)
                 .collect();
         }
@@ -920,15 +1272,15 @@ impl Azks {
             });
         } else {
             let maybe_task: Option<
-                tokio::task::JoinHandle<Result<(Vec<AzksElement>, Vec<AzksElement>), AkdError>>,
+                runtime::JoinHandle<Result<(Vec<AzksElement>, Vec<AzksElement>), AkdError>>,
             > = if let Some(left_child) = node.left_child {
                 #[cfg(feature = "parallel_insert")]
                 {
                     if parallel_levels.map(|p| p as u64 > level).unwrap_or(false) {
                         // we can parallelise further!
                         let storage_clone = storage.clone();
-                        let tsk: tokio::task::JoinHandle<Result<_, AkdError>> =
-                            tokio::spawn(async move {
+                        let tsk: runtime::JoinHandle<Result<_, AkdError>> =
+                            runtime::spawn(async move {
                                 let my_storage = storage_clone;
                                 let child_node = TreeNode::get_from_storage(
                                     &my_storage,
@@ -1055,6 +1407,86 @@ impl Azks {
         Ok(TC::compute_root_hash_from_val(&root_node.hash))
     }
 
+    /// Counts the leaves of the tree which were inserted after `after_epoch`, up to and
+    /// including `until_epoch`. As leaves are never updated once inserted, this only visits
+    /// the subtrees which contain such leaves.
+    pub(crate) async fn count_leaves_inserted_between<S: Database>(
+        &self,
+        storage: &StorageManager<S>,
+        after_epoch: u64,
+        until_epoch: u64,
+    ) -> Result<u64, AkdError> {
+        let mut count = 0;
+        let mut frontier = vec![NodeKey(NodeLabel::root())];
+        while !frontier.is_empty() {
+            let nodes =
+                TreeNode::batch_get_from_storage(storage, &frontier, self.latest_epoch).await?;
+            frontier = Vec::new();
+            for node in nodes {
+                if node.last_epoch <= after_epoch || node.min_descendant_epoch > until_epoch {
+                    continue;
+                }
+                if node.node_type == TreeNodeType::Leaf {
+                    count += 1;
+                } else {
+                    frontier.extend(
+                        [node.left_child, node.right_child]
+                            .into_iter()
+                            .flatten()
+                            .map(NodeKey),
+                    );
+                }
+            }
+        }
+        Ok(count)
+    }
+
+    /// Checks that the stored values of the nodes along a path from the root to a leaf, at the
+    /// latest epoch, match the values recomputed from their children. At each level, the path
+    /// descends in the direction given by the next bit of `path` (or into the only child).
+    /// Returns the label of the first node with an inconsistent value, if any.
+    pub(crate) async fn find_inconsistent_node_on_path<TC: Configuration, S: Database>(
+        &self,
+        storage: &StorageManager<S>,
+        path: Digest,
+    ) -> Result<Option<NodeLabel>, AkdError> {
+        let epoch = self.latest_epoch;
+        let mut node =
+            TreeNode::get_from_storage(storage, &NodeKey(NodeLabel::root()), epoch).await?;
+        let mut depth = 0;
+        loop {
+            if node.node_type == TreeNodeType::Leaf {
+                return Ok(None);
+            }
+            let left = node.get_child_node(storage, Direction::Left, epoch).await?;
+            let right = node
+                .get_child_node(storage, Direction::Right, epoch)
+                .await?;
+            let expected = if left.is_none() && right.is_none() {
+                TC::empty_root_value()
+            } else {
+                TC::compute_parent_hash_from_children(
+                    &node_to_azks_value::<TC>(&left, NodeHashingMode::WithLeafEpoch),
+                    &node_to_label::<TC>(&left).value::<TC>(),
+                    &node_to_azks_value::<TC>(&right, NodeHashingMode::WithLeafEpoch),
+                    &node_to_label::<TC>(&right).value::<TC>(),
+                )
+            };
+            if expected != node.hash {
+                return Ok(Some(node.label));
+            }
+
+            let go_right = (path[(depth / 8) % path.len()] >> (7 - depth % 8)) & 1 == 1;
+            node = match (left, right) {
+                (Some(_), Some(right)) if go_right => right,
+                (Some(left), _) => left,
+                (None, Some(right)) => right,
+                (None, None) => return Ok(None),
+            };
+            depth += 1;
+        }
+    }
+
     /// Gets the latest epoch of this azks. If an update aka epoch transition
     /// is in progress, this should return the most recent completed epoch.
     pub fn get_latest_epoch(&self) -> u64 {
@@ -1104,13 +1536,13 @@ impl Azks {
             let direction = Direction::try_from(prefix_ordering).map_err(|_| {
                 AkdError::TreeNode(TreeNodeError::NoDirection(curr_node.label, None))
             })?;
-            let child = curr_node
+            let Some(child) = curr_node
                 .get_child_node(storage, direction, latest_epoch)
-                .await?;
-            if child.is_none() {
+                .await?
+            else {
                 // Special case, if the root node has a direction with no child there
                 break;
-            }
+            };
 
             // Find the sibling node. Note that for ARITY = 2, this does not need to be
             // an array, as it can just be a single node.
@@ -1128,19 +1560,7 @@ impl Azks {
                 direction,
             });
 
-            prev_node = curr_node.clone();
-            match curr_node
-                .get_child_node(storage, direction, latest_epoch)
-                .await?
-            {
-                Some(n) => curr_node = n,
-                None => {
-                    return Err(AkdError::TreeNode(TreeNodeError::NoChildAtEpoch(
-                        latest_epoch,
-                        direction,
-                    )));
-                }
-            }
+            prev_node = std::mem::replace(&mut curr_node, child);
             prefix_ordering = curr_node.label.get_prefix_ordering(label);
             equal = label == curr_node.label;
         }
@@ -1168,9 +1588,25 @@ impl Azks {
 
 type AppendOnlyHelper = (Vec<AzksElement>, Vec<AzksElement>);
 
+/// The state of an append-only proof which is generated in segments, see
+/// [Azks::start_append_only_proof_segments]
+pub(crate) struct AuditProofCursor {
+    /// The epoch whose proof is currently being generated
+    epoch: u64,
+    /// The epoch up to which the proofs are generated
+    end_epoch: u64,
+    /// The index of the next segment of the current epoch
+    index: u64,
+    /// The nodes which remain to be visited for the current epoch, in reverse order
+    pending: Vec<TreeNode>,
+    /// The root node, from which the traversal of each epoch starts
+    root: TreeNode,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::storage::manager::{FullBfsPreload, PrefixLimitedPreload};
     use crate::storage::types::DbRecord;
     use crate::storage::StorageUtil;
     use crate::test_config;
@@ -1212,6 +1648,7 @@ mod tests {
                 username: crate::AkdLabel::random(&mut rng),
                 value: crate::AkdValue::random(&mut rng),
                 version: 1,
+                blinding: None,
             },
         };
 
@@ -1403,6 +1840,102 @@ mod tests {
         Ok(())
     }
 
+    test_config!(test_batch_insert_in_parallel);
+    async fn test_batch_insert_in_parallel<TC: Configuration>() -> Result<(), AkdError> {
+        let mut rng = StdRng::seed_from_u64(42);
+        let first_batch = gen_random_elements(500, &mut rng);
+        let second_batch = gen_random_elements(500, &mut rng);
+
+        let database = AsyncInMemoryDatabase::new();
+        let db = StorageManager::new_no_cache(database.clone());
+        let mut azks1 = Azks::new::<TC, _>(&db).await?;
+        for batch in [&first_batch, &second_batch] {
+            azks1
+                .batch_insert_nodes_in_parallel::<TC, _>(
+                    &db,
+                    batch.clone(),
+                    InsertMode::Directory,
+                    1,
+                )
+                .await?;
+        }
+
+        for num_tasks in [2, 5, 16] {
+            let database2 = AsyncInMemoryDatabase::new();
+            let db2 = StorageManager::new_no_cache(database2.clone());
+            let mut azks2 = Azks::new::<TC, _>(&db2).await?;
+            for batch in [&first_batch, &second_batch] {
+                azks2
+                    .batch_insert_nodes_in_parallel::<TC, _>(
+                        &db2,
+                        batch.clone(),
+                        InsertMode::Directory,
+                        num_tasks,
+                    )
+                    .await?;
+            }
+            assert_eq!(azks1, azks2);
+            assert_eq!(
+                azks1.get_root_hash::<TC, _>(&db).await?,
+                azks2.get_root_hash::<TC, _>(&db2).await?
+            );
+        }
+
+        Ok(())
+    }
+
+    test_config!(test_bulk_build);
+    async fn test_bulk_build<TC: Configuration>() -> Result<(), AkdError> {
+        let num_nodes = 1000;
+        let mut rng = StdRng::seed_from_u64(42);
+        let azks_element_set = gen_random_elements(num_nodes, &mut rng);
+
+        let database = AsyncInMemoryDatabase::new();
+        let db = StorageManager::new_no_cache(database.clone());
+        let mut azks1 = Azks::new::<TC, _>(&db).await?;
+        azks1
+            .batch_insert_nodes::<TC, _>(&db, azks_element_set.clone(), InsertMode::Directory)
+            .await?;
+
+        let database2 = AsyncInMemoryDatabase::new();
+        let db2 = StorageManager::new_no_cache(database2);
+        let mut azks2 = Azks::new::<TC, _>(&db2).await?;
+        let mut built_nodes = azks2
+            .bulk_build::<TC>(azks_element_set.clone(), InsertMode::Directory)
+            .await?;
+        assert_eq!(azks1, azks2);
+
+        // The bulk built tree is identical to the incrementally inserted one
+        let mut inserted_nodes = database
+            .batch_get_type_direct::<TreeNodeWithPreviousValue>()
+            .await?
+            .into_iter()
+            .map(|record| match record {
+                DbRecord::TreeNode(node) => node,
+                _ => panic!("Retrieved a record which is not a tree node"),
+            })
+            .collect::<Vec<_>>();
+        inserted_nodes.sort_by_key(|node| node.label);
+        built_nodes.sort_by_key(|node| node.label);
+        assert_eq!(inserted_nodes, built_nodes);
+
+        // Bulk building is only possible for an empty tree, and without duplicates
+        assert!(azks2
+            .bulk_build::<TC>(azks_element_set.clone(), InsertMode::Directory)
+            .await
+            .is_err());
+        let mut azks3 = Azks::new::<TC, _>(&db2).await?;
+        assert!(azks3
+            .bulk_build::<TC>(
+                [&azks_element_set[..], &azks_element_set[..1]].concat(),
+                InsertMode::Directory
+            )
+            .await
+            .is_err());
+
+        Ok(())
+    }
+
     test_config!(test_insert_num_nodes);
     async fn test_insert_num_nodes<TC: Configuration>() -> Result<(), AkdError> {
         let database = AsyncInMemoryDatabase::new();
@@ -1554,7 +2087,7 @@ mod tests {
         ]);
         let expected_preload_count = 3u64;
         let actual_preload_count = azks
-            .preload_nodes(&storage_manager, &azks_element_set)
+            .preload_nodes(&storage_manager, &azks_element_set, &LookupSetPreload)
             .await
             .expect("Failed to preload nodes");
 
@@ -1565,6 +2098,68 @@ mod tests {
         Ok(())
     }
 
+    test_config!(test_preload_strategies);
+    async fn test_preload_strategies<TC: Configuration>() -> Result<(), AkdError> {
+        let mut rng = StdRng::seed_from_u64(42);
+        let database = AsyncInMemoryDatabase::new();
+        let storage_manager = StorageManager::new(database.clone(), None, None, None);
+        let mut azks = Azks::new::<TC, _>(&storage_manager).await?;
+        let elements = (0..32)
+            .map(|_| AzksElement {
+                label: crate::utils::random_label(&mut rng),
+                value: AzksValue(EMPTY_DIGEST),
+            })
+            .collect::<Vec<_>>();
+        azks.batch_insert_nodes::<TC, _>(&storage_manager, elements.clone(), InsertMode::Directory)
+            .await?;
+        let num_nodes = database
+            .batch_get_type_direct::<TreeNodeWithPreviousValue>()
+            .await?
+            .len() as u64;
+
+        let targeted = AzksElementSet::from(vec![elements[0]]);
+        let full = azks
+            .preload_nodes(&storage_manager, &targeted, &FullBfsPreload)
+            .await?;
+        let lookup_set = azks
+            .preload_nodes(&storage_manager, &targeted, &LookupSetPreload)
+            .await?;
+        let root_only = azks
+            .preload_nodes(
+                &storage_manager,
+                &targeted,
+                &PrefixLimitedPreload { max_prefix_len: 0 },
+            )
+            .await?;
+        let unlimited = azks
+            .preload_nodes(
+                &storage_manager,
+                &targeted,
+                &PrefixLimitedPreload {
+                    max_prefix_len: 256,
+                },
+            )
+            .await?;
+
+        assert_eq!(num_nodes, full);
+        assert!(1 < lookup_set && lookup_set < full);
+        assert_eq!(1, root_only);
+        assert_eq!(lookup_set, unlimited);
+
+        // The strategy of the storage manager is used by publishes
+        let storage_manager = storage_manager.with_preload_strategy(FullBfsPreload);
+        assert_eq!(
+            num_nodes,
+            azks.preload_nodes(
+                &storage_manager,
+                &targeted,
+                storage_manager.preload_strategy()
+            )
+            .await?
+        );
+        Ok(())
+    }
+
     test_config!(test_azks_element_set_partition);
     async fn test_azks_element_set_partition<TC: Configuration>() -> Result<(), AkdError> {
         let num_nodes = 5;
@@ -11,11 +11,16 @@ use akd_core::configuration::Configuration;
 
 use crate::AzksValue;
 use crate::{
-    append_only_zks::InsertMode,
-    errors::{AkdError, AuditorError, AzksError},
+    append_only_zks::{get_parallel_levels_for_tasks, InsertMode},
+    errors::{AkdError, AuditorError, AzksError, ParallelismError},
     storage::{manager::StorageManager, memory::AsyncInMemoryDatabase},
-    AppendOnlyProof, Azks, Digest, SingleAppendOnlyProof,
+    AppendOnlyProof, AuditProofSegment, Azks, AzksElement, CompressedAppendOnlyProof, Digest,
+    NodeLabel, PrefixOrdering, SingleAppendOnlyProof,
 };
+use futures::{Stream, StreamExt};
+
+#[cfg(feature = "parallel_audit")]
+use crate::append_only_zks::DEFAULT_AVAILABLE_PARALLELISM;
 
 /// Verifies an audit proof, given start and end hashes for a merkle patricia tree.
 pub async fn audit_verify<TC: Configuration>(
@@ -52,13 +57,94 @@ pub async fn audit_verify<TC: Configuration>(
     Ok(())
 }
 
-/// Helper for audit, verifies an append-only proof
+/// Verifies a [CompressedAppendOnlyProof] (as produced by
+/// [crate::directory::Directory::audit_range_compressed]), given the root hashes of the
+/// audited epochs. As with [audit_verify], there should be one more hash than epochs in the proof.
+pub async fn audit_verify_compressed<TC: Configuration>(
+    hashes: Vec<Digest>,
+    proof: CompressedAppendOnlyProof,
+) -> Result<(), AkdError> {
+    if proof.epochs.len() + 1 != hashes.len() || proof.epochs.len() != proof.proofs.len() {
+        return Err(AkdError::AuditErr(AuditorError::VerifyAuditProof(format!(
+            "The proof has {} epochs and {} proofs, but {} hashes were provided. \
+            There should be as many proofs as epochs, and one more hash than epochs!",
+            proof.epochs.len(),
+            proof.proofs.len(),
+            hashes.len()
+        ))));
+    }
+    for (i, (epoch_proof, epoch)) in proof.proofs.into_iter().zip(proof.epochs).enumerate() {
+        let unchanged_nodes = epoch_proof
+            .unchanged_nodes
+            .iter()
+            .map(|&index| {
+                usize::try_from(index)
+                    .ok()
+                    .and_then(|index| proof.unchanged_nodes.get(index))
+                    .copied()
+                    .ok_or_else(|| {
+                        AkdError::AuditErr(AuditorError::VerifyAuditProof(format!(
+                            "The proof for epoch {epoch} refers to unchanged node {index}, \
+                            but the proof only has {} unchanged nodes",
+                            proof.unchanged_nodes.len()
+                        )))
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let single_proof = SingleAppendOnlyProof {
+            inserted: epoch_proof.inserted,
+            unchanged_nodes,
+        };
+        verify_consecutive_append_only::<TC>(&single_proof, hashes[i], hashes[i + 1], epoch + 1)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Helper for audit, verifies an append-only proof. With the `parallel_audit` feature, the
+/// proof is verified with [verify_consecutive_append_only_in_parallel], using the available
+/// parallelism of the machine.
 pub async fn verify_consecutive_append_only<TC: Configuration>(
     proof: &SingleAppendOnlyProof,
     start_hash: Digest,
     end_hash: Digest,
     end_epoch: u64,
 ) -> Result<(), AkdError> {
+    #[cfg(feature = "parallel_audit")]
+    return verify_consecutive_append_only_in_parallel::<TC>(
+        proof,
+        start_hash,
+        end_hash,
+        end_epoch,
+        std::thread::available_parallelism().map_or(DEFAULT_AVAILABLE_PARALLELISM, |v| v.into()),
+    )
+    .await;
+
+    #[cfg(not(feature = "parallel_audit"))]
+    verify_consecutive_append_only_with_azks::<TC>(proof, start_hash, end_hash, end_epoch).await
+}
+
+#[cfg_attr(feature = "parallel_audit", allow(dead_code))]
+async fn verify_consecutive_append_only_with_azks<TC: Configuration>(
+    proof: &SingleAppendOnlyProof,
+    start_hash: Digest,
+    end_hash: Digest,
+    end_epoch: u64,
+) -> Result<(), AkdError> {
+    let (computed_start_root_hash, computed_end_root_hash) =
+        compute_append_only_root_hashes::<TC>(proof, end_epoch).await?;
+    if computed_start_root_hash != start_hash || computed_end_root_hash != end_hash {
+        return Err(AkdError::AzksErr(AzksError::VerifyAppendOnlyProof));
+    }
+    Ok(())
+}
+
+/// Computes the root hashes of the tree before and after the epoch `end_epoch`, from the
+/// nodes of its append-only proof
+pub(crate) async fn compute_append_only_root_hashes<TC: Configuration>(
+    proof: &SingleAppendOnlyProof,
+    end_epoch: u64,
+) -> Result<(Digest, Digest), AkdError> {
     let db = AsyncInMemoryDatabase::new();
     let manager = StorageManager::new_no_cache(db);
 
@@ -66,7 +152,6 @@ pub async fn verify_consecutive_append_only<TC: Configuration>(
     azks.batch_insert_nodes::<TC, _>(&manager, proof.unchanged_nodes.clone(), InsertMode::Auditor)
         .await?;
     let computed_start_root_hash: Digest = azks.get_root_hash::<TC, _>(&manager).await?;
-    let mut verified = computed_start_root_hash == start_hash;
     azks.latest_epoch = end_epoch - 1;
     let updated_inserted = proof
         .inserted
@@ -80,9 +165,425 @@ pub async fn verify_consecutive_append_only<TC: Configuration>(
     azks.batch_insert_nodes::<TC, _>(&manager, updated_inserted, InsertMode::Auditor)
         .await?;
     let computed_end_root_hash: Digest = azks.get_root_hash::<TC, _>(&manager).await?;
-    verified = verified && (computed_end_root_hash == end_hash);
-    if !verified {
+    Ok((computed_start_root_hash, computed_end_root_hash))
+}
+
+/// Verifies an append-only proof, like [verify_consecutive_append_only], but splits the trees
+/// into (up to) `num_tasks` subtrees below the root, whose hashes are computed concurrently on
+/// tokio's blocking thread pool. The nodes of the proof may be given in any order.
+pub async fn verify_consecutive_append_only_in_parallel<TC: Configuration>(
+    proof: &SingleAppendOnlyProof,
+    start_hash: Digest,
+    end_hash: Digest,
+    end_epoch: u64,
+    num_tasks: usize,
+) -> Result<(), AkdError> {
+    let start_nodes = proof.unchanged_nodes.clone();
+    let mut end_nodes = start_nodes.clone();
+    end_nodes.extend(proof.inserted.iter().map(|x| AzksElement {
+        label: x.label,
+        value: AzksValue(TC::hash_leaf_with_commitment(x.value, end_epoch).0),
+    }));
+    let (computed_start_root_hash, computed_end_root_hash) = futures::try_join!(
+        compute_root_hash_in_parallel::<TC>(start_nodes, num_tasks),
+        compute_root_hash_in_parallel::<TC>(end_nodes, num_tasks),
+    )?;
+    if computed_start_root_hash != start_hash || computed_end_root_hash != end_hash {
         return Err(AkdError::AzksErr(AzksError::VerifyAppendOnlyProof));
     }
     Ok(())
 }
+
+/// The maximum number of levels below the root at which the tree is split for a parallel
+/// verification, i.e. at most 256 subtrees are hashed concurrently
+const MAX_PARALLEL_AUDIT_LEVELS: u8 = 8;
+
+/// Computes the root hash of the tree made up of the given nodes (as with auditor-mode
+/// insertion), splitting it into subtrees by the first bits of their labels
+async fn compute_root_hash_in_parallel<TC: Configuration>(
+    mut nodes: Vec<AzksElement>,
+    num_tasks: usize,
+) -> Result<Digest, AkdError> {
+    let levels = get_parallel_levels_for_tasks(num_tasks)
+        .unwrap_or(0)
+        .min(MAX_PARALLEL_AUDIT_LEVELS);
+    // for labels where neither is a prefix of the other, the order of their (zero-padded)
+    // values is their order in the tree
+    nodes.sort_unstable_by(|a, b| {
+        a.label
+            .label_val
+            .cmp(&b.label.label_val)
+            .then(a.label.label_len.cmp(&b.label.label_len))
+    });
+
+    let mut tasks = vec![];
+    for subtree in nodes.chunk_by(|a, b| subtree_index(a, levels) == subtree_index(b, levels)) {
+        let subtree = subtree.to_vec();
+        tasks.push(crate::runtime::spawn_blocking(move || {
+            let mut hasher = StreamingTreeHasher::default();
+            for node in subtree {
+                hasher.push::<TC>(node)?;
+            }
+            Ok::<_, AkdError>(hasher.into_subtree::<TC>())
+        }));
+    }
+
+    // the roots of the subtrees are the nodes of the tree above them
+    let mut hasher = StreamingTreeHasher::default();
+    for task in tasks {
+        let subtree = task
+            .await
+            .map_err(|e| AkdError::Parallelism(ParallelismError::JoinErr(e.to_string())))??;
+        if let Some(subtree) = subtree {
+            hasher.push_subtree::<TC>(subtree)?;
+        }
+    }
+    Ok(hasher.finish::<TC>())
+}
+
+/// The index of the subtree containing the node, among the subtrees `levels` below the root
+fn subtree_index(node: &AzksElement, levels: u8) -> u8 {
+    if levels == 0 {
+        0
+    } else {
+        node.label.label_val[0] >> (8 - levels)
+    }
+}
+
+/// The progress of an auditor: the latest epoch it has verified, along with the root hash
+/// of that epoch. Persisting the state (e.g. with [AuditorState::to_bytes]) allows an
+/// auditor to continue with [resume] after a restart, without re-verifying the proofs of
+/// all the previous epochs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Deserialize, serde::Serialize)
+)]
+pub struct AuditorState {
+    /// The latest verified epoch
+    pub epoch: u64,
+    /// The root hash of the latest verified epoch
+    pub root_hash: Digest,
+}
+
+impl AuditorState {
+    /// The length of an encoded [AuditorState]
+    const ENCODED_LEN: usize = 8 + crate::hash::DIGEST_BYTES;
+
+    /// Creates the state of an auditor which trusts the provided root hash of the epoch,
+    /// e.g. the root hash of the epoch from which the auditing starts
+    pub fn new(epoch: u64, root_hash: Digest) -> Self {
+        Self { epoch, root_hash }
+    }
+
+    /// Encodes the state as the big-endian epoch, followed by the root hash
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [&self.epoch.to_be_bytes()[..], &self.root_hash[..]].concat()
+    }
+
+    /// Decodes a state which was encoded with [AuditorState::to_bytes]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, AkdError> {
+        if bytes.len() != Self::ENCODED_LEN {
+            return Err(AkdError::AuditErr(AuditorError::InvalidState(format!(
+                "Expected {} bytes, but got {}",
+                Self::ENCODED_LEN,
+                bytes.len()
+            ))));
+        }
+        let (epoch, root_hash) = bytes.split_at(8);
+        Ok(Self {
+            epoch: u64::from_be_bytes(epoch.try_into().expect("Slice with incorrect length")),
+            root_hash: crate::hash::try_parse_digest(root_hash)
+                .map_err(|err| AkdError::AuditErr(AuditorError::InvalidState(err)))?,
+        })
+    }
+}
+
+/// Continues auditing from the provided state, verifying the proof for the epochs which
+/// follow it. The proof must start at the epoch of the state, and `new_hashes` are the
+/// root hashes of the epochs after it (one per epoch of the proof). Returns the state
+/// after the last epoch of the proof.
+pub async fn resume<TC: Configuration>(
+    state: &AuditorState,
+    new_hashes: Vec<Digest>,
+    proof: AppendOnlyProof,
+) -> Result<AuditorState, AkdError> {
+    if let Some((index, epoch)) = proof
+        .epochs
+        .iter()
+        .enumerate()
+        .find(|(index, epoch)| **epoch != state.epoch + *index as u64)
+    {
+        return Err(AkdError::AuditErr(AuditorError::VerifyAuditProof(format!(
+            "The proof should cover the consecutive epochs following epoch {}, but epoch {} is at position {}",
+            state.epoch, epoch, index
+        ))));
+    }
+    let next_state = AuditorState::new(
+        state.epoch + proof.epochs.len() as u64,
+        new_hashes.last().copied().unwrap_or(state.root_hash),
+    );
+    let hashes = std::iter::once(state.root_hash).chain(new_hashes).collect();
+    audit_verify::<TC>(hashes, proof).await?;
+    Ok(next_state)
+}
+
+/// Verifies an audit proof which is provided as a stream of [AuditProofSegment]s (as produced
+/// by [crate::directory::Directory::audit_stream]), given the root hashes of the audited epochs.
+/// As with [audit_verify], there should be one more hash than epochs in the proof.
+///
+/// The segments are consumed one at a time with an [AuditStreamVerifier], so that the memory
+/// needed is bounded by the size of a segment, regardless of the size of the proof.
+pub async fn audit_verify_stream<TC: Configuration>(
+    hashes: Vec<Digest>,
+    segments: impl Stream<Item = Result<AuditProofSegment, AkdError>>,
+) -> Result<(), AkdError> {
+    let mut verifier = AuditStreamVerifier::new(hashes)?;
+    futures::pin_mut!(segments);
+    while let Some(segment) = segments.next().await {
+        verifier.verify_segment::<TC>(segment?)?;
+    }
+    verifier.finish()
+}
+
+/// Incrementally verifies an audit proof, segment by segment. The segments must be
+/// provided in the order in which they are generated by
+/// [crate::directory::Directory::audit_stream], i.e. the segments of each epoch in order
+/// of their index, and the epochs consecutively.
+///
+/// Instead of reconstructing the trees of the audited epochs, only the hashes of the
+/// subtrees along the right-most path of the nodes received so far are kept. The verifier
+/// is therefore small, and can be cloned (or serialized) to resume the verification
+/// from a given segment later on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Deserialize, serde::Serialize)
+)]
+pub struct AuditStreamVerifier {
+    hashes: Vec<Digest>,
+    /// The number of epochs whose proofs have been verified
+    verified_epochs: usize,
+    /// The epoch and index of the next expected segment, if any segment was received yet
+    next_segment: Option<(u64, u64)>,
+    start_tree: StreamingTreeHasher,
+    end_tree: StreamingTreeHasher,
+}
+
+impl AuditStreamVerifier {
+    /// Creates a verifier for an audit proof between the epochs with the provided root
+    /// hashes, which must contain at least two hashes
+    pub fn new(hashes: Vec<Digest>) -> Result<Self, AkdError> {
+        if hashes.len() < 2 {
+            return Err(AkdError::AuditErr(AuditorError::VerifyAuditProof(format!(
+                "At least two hashes are needed to verify an audit proof, but {} were provided",
+                hashes.len()
+            ))));
+        }
+        Ok(Self {
+            hashes,
+            verified_epochs: 0,
+            next_segment: None,
+            start_tree: StreamingTreeHasher::default(),
+            end_tree: StreamingTreeHasher::default(),
+        })
+    }
+
+    /// The epoch and index of the segment which is expected next, or [None] if no
+    /// segment has been verified yet
+    pub fn next_segment(&self) -> Option<(u64, u64)> {
+        self.next_segment
+    }
+
+    /// Verifies the next segment of the proof. Once the last segment of an epoch has been
+    /// received, the root hashes of the epoch and its successor are checked.
+    pub fn verify_segment<TC: Configuration>(
+        &mut self,
+        segment: AuditProofSegment,
+    ) -> Result<(), AkdError> {
+        if self.verified_epochs + 1 >= self.hashes.len() {
+            return Err(AkdError::AuditErr(AuditorError::VerifyAuditProof(format!(
+                "Received a segment for epoch {}, but the proofs of all {} epochs were already verified",
+                segment.epoch,
+                self.verified_epochs
+            ))));
+        }
+        let expected = self.next_segment.unwrap_or((segment.epoch, 0));
+        if (segment.epoch, segment.index) != expected {
+            return Err(AkdError::AuditErr(AuditorError::VerifyAuditProof(format!(
+                "Expected segment {} of epoch {}, but received segment {} of epoch {}",
+                expected.1, expected.0, segment.index, segment.epoch
+            ))));
+        }
+
+        let end_epoch = segment.epoch + 1;
+        let mut unchanged = segment.unchanged_nodes.into_iter().peekable();
+        let mut inserted = segment
+            .inserted
+            .into_iter()
+            .map(|mut node| {
+                node.value = AzksValue(TC::hash_leaf_with_commitment(node.value, end_epoch).0);
+                node
+            })
+            .peekable();
+        // Both lists are ordered, so they are merged into the order of the nodes in the tree
+        loop {
+            let take_unchanged = match (unchanged.peek(), inserted.peek()) {
+                (Some(left), Some(right)) => is_ordered::<TC>(&left.label, &right.label),
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (None, None) => break,
+            };
+            if take_unchanged {
+                let node = unchanged.next().expect("Peeked node is missing");
+                self.start_tree.push::<TC>(node)?;
+                self.end_tree.push::<TC>(node)?;
+            } else {
+                let node = inserted.next().expect("Peeked node is missing");
+                self.end_tree.push::<TC>(node)?;
+            }
+        }
+
+        if !segment.last {
+            self.next_segment = Some((segment.epoch, segment.index + 1));
+            return Ok(());
+        }
+        let start_hash = std::mem::take(&mut self.start_tree).finish::<TC>();
+        let end_hash = std::mem::take(&mut self.end_tree).finish::<TC>();
+        if start_hash != self.hashes[self.verified_epochs]
+            || end_hash != self.hashes[self.verified_epochs + 1]
+        {
+            return Err(AkdError::AzksErr(AzksError::VerifyAppendOnlyProof));
+        }
+        self.verified_epochs += 1;
+        self.next_segment = Some((end_epoch, 0));
+        Ok(())
+    }
+
+    /// Completes the verification, checking that the proofs of all the epochs were received
+    pub fn finish(self) -> Result<(), AkdError> {
+        if self.verified_epochs + 1 != self.hashes.len() {
+            return Err(AkdError::AuditErr(AuditorError::VerifyAuditProof(format!(
+                "The proofs of {} epochs were verified, but {} hashes were provided",
+                self.verified_epochs,
+                self.hashes.len()
+            ))));
+        }
+        Ok(())
+    }
+}
+
+/// Computes the root hash of a tree from its nodes, given in increasing order of their labels
+/// (i.e. depth-first with the left child first). Each node is the root of a subtree, whose hash
+/// is the value of the node. Only the subtrees which can still be extended by the nodes to come
+/// are retained, so that at most one node per level of the tree is held in memory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Deserialize, serde::Serialize)
+)]
+struct StreamingTreeHasher {
+    /// The completed subtrees, whose labels have an increasing common prefix with their successor
+    subtrees: Vec<AzksElement>,
+}
+
+impl StreamingTreeHasher {
+    fn push<TC: Configuration>(&mut self, node: AzksElement) -> Result<(), AkdError> {
+        if node.label.get_len() == 0 {
+            return Err(AkdError::AuditErr(AuditorError::VerifyAuditProof(
+                "The proof contains a node with the root label".to_string(),
+            )));
+        }
+        self.push_subtree::<TC>(node)
+    }
+
+    /// Adds a subtree which was hashed separately, which may be the entire tree
+    fn push_subtree<TC: Configuration>(&mut self, node: AzksElement) -> Result<(), AkdError> {
+        if let Some(previous) = self.subtrees.last() {
+            if !is_ordered::<TC>(&previous.label, &node.label) {
+                return Err(AkdError::AuditErr(AuditorError::VerifyAuditProof(format!(
+                    "Node {:?} does not follow node {:?} in the tree",
+                    node.label, previous.label
+                ))));
+            }
+            // subtrees which branch off below the new node are complete
+            let branch_len = common_prefix_len::<TC>(&previous.label, &node.label);
+            while self.subtrees.len() >= 2
+                && common_prefix_len::<TC>(
+                    &self.subtrees[self.subtrees.len() - 2].label,
+                    &self.subtrees[self.subtrees.len() - 1].label,
+                ) > branch_len
+            {
+                self.merge_last::<TC>();
+            }
+        }
+        self.subtrees.push(node);
+        Ok(())
+    }
+
+    /// Replaces the last two subtrees with their parent
+    fn merge_last<TC: Configuration>(&mut self) {
+        let right = self.subtrees.pop().expect("Missing right subtree");
+        let left = self.subtrees.pop().expect("Missing left subtree");
+        self.subtrees.push(AzksElement {
+            label: left.label.get_longest_common_prefix::<TC>(right.label),
+            value: TC::compute_parent_hash_from_children(
+                &left.value,
+                &left.label.value::<TC>(),
+                &right.value,
+                &right.label.value::<TC>(),
+            ),
+        });
+    }
+
+    /// Merges all the subtrees, returning the root of the resulting subtree (if any)
+    fn into_subtree<TC: Configuration>(mut self) -> Option<AzksElement> {
+        while self.subtrees.len() >= 2 {
+            self.merge_last::<TC>();
+        }
+        self.subtrees.pop()
+    }
+
+    /// Returns the root hash of the tree
+    fn finish<TC: Configuration>(self) -> Digest {
+        let root_value = match self.into_subtree::<TC>() {
+            None => TC::empty_root_value(),
+            // the last merge happened at the root
+            Some(root) if root.label.get_len() == 0 => root.value,
+            // the root has a single child
+            Some(child) => {
+                let empty_value = TC::empty_node_hash();
+                let empty_label = TC::empty_label().value::<TC>();
+                let label = child.label.value::<TC>();
+                if NodeLabel::root().get_prefix_ordering(child.label) == PrefixOrdering::WithZero {
+                    TC::compute_parent_hash_from_children(
+                        &child.value,
+                        &label,
+                        &empty_value,
+                        &empty_label,
+                    )
+                } else {
+                    TC::compute_parent_hash_from_children(
+                        &empty_value,
+                        &empty_label,
+                        &child.value,
+                        &label,
+                    )
+                }
+            }
+        };
+        TC::compute_root_hash_from_val(&root_value)
+    }
+}
+
+/// Returns whether the first label precedes the second one in the tree, with neither
+/// being a prefix of the other
+fn is_ordered<TC: Configuration>(first: &NodeLabel, second: &NodeLabel) -> bool {
+    let prefix = first.get_longest_common_prefix::<TC>(*second);
+    prefix.get_prefix_ordering(*first) == PrefixOrdering::WithZero
+        && prefix.get_prefix_ordering(*second) == PrefixOrdering::WithOne
+}
+
+fn common_prefix_len<TC: Configuration>(first: &NodeLabel, second: &NodeLabel) -> u32 {
+    first.get_longest_common_prefix::<TC>(*second).get_len()
+}
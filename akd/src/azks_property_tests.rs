@@ -0,0 +1,190 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Property-based tests of the invariants of the AZKS, which insert randomly generated
+//! sequences of leaves over a few epochs and check that:
+//!
+//! - The root hash of each epoch does not depend on the order of the leaves within a batch,
+//!   nor on how the leaves of an epoch are partitioned into batches
+//! - The membership proofs of all of the inserted labels verify
+//! - The non-membership proofs of labels which were never inserted verify
+//! - The append-only proof from the first epoch to the last chains the root hashes of the epochs
+
+use crate::append_only_zks::{Azks, AzksElementSet, InsertMode};
+use crate::client::{verify_membership_for_tests_only, verify_nonmembership_for_tests_only};
+use crate::errors::AkdError;
+use crate::storage::memory::AsyncInMemoryDatabase;
+use crate::storage::StorageManager;
+use crate::{auditor::audit_verify, AzksElement, AzksValue, Configuration, NodeLabel};
+
+use proptest::prelude::*;
+use proptest::test_runner::{Config, TestCaseError, TestRunner};
+
+// *** Tests *** //
+
+#[cfg(test)]
+#[allow(clippy::items_after_test_module)]
+mod azks_invariant_tests {
+    const CASES: u32 = 16;
+
+    // NOTE(new_config): Add a new configuration here
+    #[test]
+    fn test_azks_invariants_whatsapp_v1_config() {
+        super::run_azks_property_tests::<crate::WhatsAppV1Configuration>(CASES);
+    }
+
+    #[test]
+    fn test_azks_invariants_experimental_config() {
+        super::run_azks_property_tests::<crate::ExperimentalConfiguration<crate::ExampleLabel>>(
+            CASES,
+        );
+    }
+
+    #[test]
+    fn test_azks_invariants_sha3_256_config() {
+        super::run_azks_property_tests::<crate::Sha3Configuration<crate::ExampleLabel>>(CASES);
+    }
+
+    #[test]
+    fn test_azks_invariants_domain_separated_config() {
+        super::run_azks_property_tests::<crate::DomainSeparatedConfiguration<crate::ExampleLabel>>(
+            CASES,
+        );
+    }
+}
+
+// *** Run the property-based tests for a given configuration *** //
+/// Run the property-based tests of the AZKS invariants for a given configuration, with the
+/// given number of randomly generated cases. This panics with the (shrunk) failing case if
+/// an invariant does not hold. It creates its own runtime, so it must not be called from
+/// within an asynchronous context.
+pub fn run_azks_property_tests<TC: Configuration>(cases: u32) {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let mut runner = TestRunner::new(Config {
+        cases,
+        failure_persistence: None,
+        ..Config::default()
+    });
+    let strategy = (
+        // The labels and values of the inserted leaves
+        prop::collection::btree_map(any::<[u8; 32]>(), any::<[u8; 32]>(), 1..32),
+        // The number of epochs over which the leaves are inserted
+        1usize..5,
+        // The size of the batches into which the leaves of the first epoch are partitioned
+        1usize..8,
+        // Labels which are (most likely) never inserted
+        prop::collection::vec(any::<[u8; 32]>(), 1..4),
+    );
+    runner
+        .run(&strategy, |(leaves, num_epochs, batch_size, absent)| {
+            let leaves = leaves
+                .into_iter()
+                .map(|(label, value)| AzksElement {
+                    label: NodeLabel::new(label, 256),
+                    value: AzksValue(value),
+                })
+                .collect::<Vec<_>>();
+            let absent = absent
+                .into_iter()
+                .map(|label| NodeLabel::new(label, 256))
+                .filter(|label| leaves.iter().all(|leaf| leaf.label != *label))
+                .collect::<Vec<_>>();
+            runtime
+                .block_on(check_azks_invariants::<TC>(
+                    leaves, num_epochs, batch_size, absent,
+                ))
+                .map_err(|err| TestCaseError::fail(err.to_string()))
+        })
+        .unwrap();
+}
+
+// *** New Test Helper Functions *** //
+async fn check_azks_invariants<TC: Configuration>(
+    leaves: Vec<AzksElement>,
+    num_epochs: usize,
+    batch_size: usize,
+    absent: Vec<NodeLabel>,
+) -> Result<(), AkdError> {
+    let epochs = leaves
+        .chunks(leaves.len().div_ceil(num_epochs))
+        .map(|epoch| epoch.to_vec())
+        .collect::<Vec<_>>();
+
+    // Insert the leaves of each epoch in a single batch
+    let db = StorageManager::new_no_cache(AsyncInMemoryDatabase::new());
+    let mut azks = Azks::new::<TC, _>(&db).await?;
+    let mut root_hashes = vec![];
+    for epoch in &epochs {
+        azks.batch_insert_nodes::<TC, _>(&db, epoch.clone(), InsertMode::Directory)
+            .await?;
+        root_hashes.push(azks.get_root_hash::<TC, _>(&db).await?);
+    }
+
+    // The root hashes do not depend on the order of the leaves within a batch
+    let reversed_db = StorageManager::new_no_cache(AsyncInMemoryDatabase::new());
+    let mut reversed_azks = Azks::new::<TC, _>(&reversed_db).await?;
+    for (epoch, root_hash) in epochs.iter().zip(&root_hashes) {
+        let mut reversed = epoch.clone();
+        reversed.reverse();
+        reversed_azks
+            .batch_insert_nodes::<TC, _>(&reversed_db, reversed, InsertMode::Directory)
+            .await?;
+        assert_eq!(
+            *root_hash,
+            reversed_azks.get_root_hash::<TC, _>(&reversed_db).await?,
+            "The root hash depends on the order of the leaves"
+        );
+    }
+
+    // Nor on how the leaves of an epoch are partitioned into batches
+    let partitioned_db = StorageManager::new_no_cache(AsyncInMemoryDatabase::new());
+    let mut partitioned_azks = Azks::new::<TC, _>(&partitioned_db).await?;
+    partitioned_azks.latest_epoch = 1;
+    for batch in epochs[0].chunks(batch_size) {
+        let (root_node, is_new, _) = Azks::recursive_batch_insert_nodes::<TC, _>(
+            &partitioned_db,
+            Some(NodeLabel::root()),
+            AzksElementSet::from(batch.to_vec()),
+            1,
+            InsertMode::Directory,
+            None,
+        )
+        .await?;
+        root_node.write_to_storage(&partitioned_db, is_new).await?;
+    }
+    assert_eq!(
+        root_hashes[0],
+        partitioned_azks
+            .get_root_hash::<TC, _>(&partitioned_db)
+            .await?,
+        "The root hash depends on the partitioning of the leaves into batches"
+    );
+
+    // The membership proofs of all of the inserted labels verify
+    let root_hash = *root_hashes.last().unwrap();
+    for leaf in &leaves {
+        let proof = azks.get_membership_proof::<TC, _>(&db, leaf.label).await?;
+        verify_membership_for_tests_only::<TC>(root_hash, &proof)?;
+    }
+
+    // As do the non-membership proofs of labels which were never inserted
+    for label in absent {
+        let proof = azks.get_non_membership_proof::<TC, _>(&db, label).await?;
+        verify_nonmembership_for_tests_only::<TC>(root_hash, &proof)?;
+    }
+
+    // And the append-only proof chaining the root hashes of all of the epochs
+    if root_hashes.len() > 1 {
+        let proof = azks
+            .get_append_only_proof::<TC, _>(&db, 1, root_hashes.len() as u64)
+            .await?;
+        audit_verify::<TC>(root_hashes, proof).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,123 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! A synchronous facade of a [crate::Directory], for applications which are not asynchronous.
+//!
+//! The [Directory] of this module owns a (single-threaded) tokio runtime, on which each of its
+//! methods runs the corresponding method of the wrapped directory to completion. The methods
+//! have the same semantics as their asynchronous counterparts, but must not be called from
+//! within an asynchronous context, as they would block the thread of its executor (tokio
+//! panics in that case).
+//!
+//! ```
+//! use akd::blocking::Directory;
+//! use akd::ecvrf::HardCodedAkdVRF;
+//! use akd::storage::memory::AsyncInMemoryDatabase;
+//! use akd::storage::StorageManager;
+//! use akd::{AkdLabel, AkdValue};
+//!
+//! type Config = akd::WhatsAppV1Configuration;
+//!
+//! let storage = StorageManager::new_no_cache(AsyncInMemoryDatabase::new());
+//! let akd = Directory::<Config, _, _>::new(storage, HardCodedAkdVRF {}).unwrap();
+//! let epoch_hash = akd
+//!     .publish(vec![(AkdLabel::from("hello"), AkdValue::from("world"))])
+//!     .unwrap();
+//! let (lookup_proof, _) = akd.lookup(AkdLabel::from("hello")).unwrap();
+//! # assert_eq!(1, epoch_hash.epoch());
+//! ```
+
+use crate::ecvrf::{VRFKeyStorage, VRFPublicKey};
+use crate::errors::{AkdError, ParallelismError};
+use crate::storage::manager::StorageManager;
+use crate::storage::Database;
+use crate::{AkdLabel, AkdValue, AppendOnlyProof, EpochHash, HistoryProof, LookupProof};
+use akd_core::configuration::Configuration;
+use akd_core::verify::history::HistoryParams;
+
+use tokio::runtime::Runtime;
+
+/// A [crate::Directory] whose methods block until they complete, see the
+/// [module documentation](self)
+pub struct Directory<TC, S: Database, V> {
+    directory: crate::Directory<TC, S, V>,
+    runtime: Runtime,
+}
+
+impl<TC, S, V> Directory<TC, S, V>
+where
+    TC: Configuration,
+    S: Database + 'static,
+    V: VRFKeyStorage,
+{
+    /// Creates a directory over the storage layer, as with [crate::Directory::new]
+    pub fn new(storage: StorageManager<S>, vrf: V) -> Result<Self, AkdError> {
+        let runtime = new_runtime()?;
+        let directory = runtime.block_on(crate::Directory::new(storage, vrf))?;
+        Ok(Self { directory, runtime })
+    }
+
+    /// Wraps a directory which was already created (e.g. with [crate::DirectoryBuilder])
+    pub fn from_directory(directory: crate::Directory<TC, S, V>) -> Result<Self, AkdError> {
+        Ok(Self {
+            directory,
+            runtime: new_runtime()?,
+        })
+    }
+
+    /// The wrapped directory, e.g. to configure it with its `with_*` methods
+    pub fn into_inner(self) -> crate::Directory<TC, S, V> {
+        self.directory
+    }
+
+    /// Blocking version of [crate::Directory::publish]
+    pub fn publish(&self, updates: Vec<(AkdLabel, AkdValue)>) -> Result<EpochHash, AkdError> {
+        self.runtime.block_on(self.directory.publish(updates))
+    }
+
+    /// Blocking version of [crate::Directory::lookup]
+    pub fn lookup(&self, akd_label: AkdLabel) -> Result<(LookupProof, EpochHash), AkdError> {
+        self.runtime.block_on(self.directory.lookup(akd_label))
+    }
+
+    /// Blocking version of [crate::Directory::key_history]
+    pub fn key_history(
+        &self,
+        akd_label: &AkdLabel,
+        params: HistoryParams,
+    ) -> Result<(HistoryProof, EpochHash), AkdError> {
+        self.runtime
+            .block_on(self.directory.key_history(akd_label, params))
+    }
+
+    /// Blocking version of [crate::Directory::audit]
+    pub fn audit(
+        &self,
+        audit_start_ep: u64,
+        audit_end_ep: u64,
+    ) -> Result<AppendOnlyProof, AkdError> {
+        self.runtime
+            .block_on(self.directory.audit(audit_start_ep, audit_end_ep))
+    }
+
+    /// Blocking version of [crate::Directory::get_epoch_hash]
+    pub fn get_epoch_hash(&self) -> Result<EpochHash, AkdError> {
+        self.runtime.block_on(self.directory.get_epoch_hash())
+    }
+
+    /// Blocking version of [crate::Directory::get_public_key]
+    pub fn get_public_key(&self) -> Result<VRFPublicKey, AkdError> {
+        self.runtime.block_on(self.directory.get_public_key())
+    }
+}
+
+fn new_runtime() -> Result<Runtime, AkdError> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .map_err(|err| AkdError::Parallelism(ParallelismError::Runtime(err.to_string())))
+}
@@ -0,0 +1,268 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! A builder of a [Directory], which validates its configuration upfront. Rather than surfacing
+//! a misconfiguration on the first publish or proof generation which relies on it (e.g. a VRF
+//! key which cannot be retrieved, or a publish lease which expires immediately), the
+//! [DirectoryBuilder] rejects it with a [ConfigurationError] before the storage layer is touched.
+//!
+//! The options which are not covered by the builder can still be configured on the built
+//! directory with the `with_*` methods of [Directory].
+
+use crate::admission::AdmissionConfig;
+use crate::directory::Directory;
+use crate::ecvrf::VRFKeyStorage;
+use crate::errors::{AkdError, ConfigurationError};
+use crate::retention::RetentionPolicy;
+use crate::signature::EpochSigner;
+use crate::storage::manager::StorageManager;
+use crate::storage::Database;
+use crate::telemetry::{self, Recorder};
+use akd_core::configuration::Configuration;
+
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The caching of the records read from the storage layer by a [DirectoryBuilder]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CachePolicy {
+    /// Every read reaches the storage layer (see [StorageManager::new_no_cache])
+    Disabled,
+    /// The records are cached in memory (see [StorageManager::new]), with the defaults of the
+    /// cache used for any of the settings which are not provided
+    Timed {
+        /// How long a record is cached for
+        item_lifetime: Option<Duration>,
+        /// The size of the cache above which it is cleaned
+        limit_bytes: Option<usize>,
+        /// How often the cache is cleaned of its expired records
+        clean_frequency: Option<Duration>,
+    },
+}
+
+impl Default for CachePolicy {
+    fn default() -> Self {
+        Self::Timed {
+            item_lifetime: None,
+            limit_bytes: None,
+            clean_frequency: None,
+        }
+    }
+}
+
+/// Builds a [Directory] over a storage layer, see the [module documentation](self)
+pub struct DirectoryBuilder<TC, S, V> {
+    db: S,
+    vrf: V,
+    cache_policy: CachePolicy,
+    retention: Option<RetentionPolicy>,
+    publish_lease: Option<(Vec<u8>, Duration)>,
+    signer: Option<Arc<dyn EpochSigner>>,
+    recorder: Option<Box<dyn Recorder>>,
+    admission: Option<AdmissionConfig>,
+    proof_cache_capacity: Option<usize>,
+    tc: PhantomData<TC>,
+}
+
+impl<TC, S, V> DirectoryBuilder<TC, S, V>
+where
+    TC: Configuration,
+    S: Database + 'static,
+    V: VRFKeyStorage,
+{
+    /// Starts the configuration of a directory over the storage layer `db`, with the VRF key
+    /// held by `vrf`. By default, the records are cached as with [CachePolicy::default].
+    pub fn new(db: S, vrf: V) -> Self {
+        Self {
+            db,
+            vrf,
+            cache_policy: CachePolicy::default(),
+            retention: None,
+            publish_lease: None,
+            signer: None,
+            recorder: None,
+            admission: None,
+            proof_cache_capacity: None,
+            tc: PhantomData,
+        }
+    }
+
+    /// Configures the caching of the records read from the storage layer
+    pub fn with_cache_policy(mut self, policy: CachePolicy) -> Self {
+        self.cache_policy = policy;
+        self
+    }
+
+    /// Configures the [RetentionPolicy] of the directory, see [Directory::with_retention_policy]
+    pub fn with_retention_policy(mut self, policy: RetentionPolicy) -> Self {
+        self.retention = Some(policy);
+        self
+    }
+
+    /// Configures the advisory lease acquired for each publish, see
+    /// [Directory::with_publish_lease]. The holder must not be empty, and the time-to-live must
+    /// be at least a millisecond.
+    pub fn with_publish_lease(mut self, holder: Vec<u8>, ttl: Duration) -> Self {
+        self.publish_lease = Some((holder, ttl));
+        self
+    }
+
+    /// Configures the signer of the root hash of each published epoch, see
+    /// [Directory::with_epoch_signer]. The public key of the signer is retrieved when the
+    /// directory is built, to check that its signing key is available.
+    pub fn with_epoch_signer<ES: EpochSigner + 'static>(mut self, signer: ES) -> Self {
+        self.signer = Some(Arc::new(signer));
+        self
+    }
+
+    /// Configures the recorder to which the metrics of the library are emitted, which is
+    /// installed for the whole process (see [telemetry::set_recorder]) once the directory is
+    /// built. Building fails if another recorder was already installed.
+    pub fn with_metrics_recorder<R: Recorder + 'static>(mut self, recorder: R) -> Self {
+        self.recorder = Some(Box::new(recorder));
+        self
+    }
+
+    /// Configures limits on the proof generations of the directory, see
+    /// [Directory::with_admission_control]. At least one proof generation must be allowed at
+    /// a time.
+    pub fn with_admission_control(mut self, config: AdmissionConfig) -> Self {
+        self.admission = Some(config);
+        self
+    }
+
+    /// Configures a cache of up to `capacity` (at least 1) lookup proofs, see
+    /// [Directory::with_proof_cache]
+    pub fn with_proof_cache(mut self, capacity: usize) -> Self {
+        self.proof_cache_capacity = Some(capacity);
+        self
+    }
+
+    /// Checks the options which do not depend on the VRF key or the signer
+    fn validate_options(&self) -> Result<(), ConfigurationError> {
+        if let CachePolicy::Timed {
+            item_lifetime,
+            limit_bytes,
+            clean_frequency,
+        } = self.cache_policy
+        {
+            if item_lifetime == Some(Duration::ZERO) {
+                return Err(invalid_option(
+                    "cache item lifetime",
+                    "records would expire as soon as they are cached",
+                ));
+            }
+            if limit_bytes == Some(0) {
+                return Err(invalid_option(
+                    "cache limit",
+                    "use CachePolicy::Disabled to disable the cache",
+                ));
+            }
+            if clean_frequency == Some(Duration::ZERO) {
+                return Err(invalid_option(
+                    "cache clean frequency",
+                    "the cache cannot be cleaned continuously",
+                ));
+            }
+        }
+        if let Some((holder, ttl)) = &self.publish_lease {
+            if holder.is_empty() {
+                return Err(invalid_option(
+                    "publish lease holder",
+                    "the holder must identify the instance",
+                ));
+            }
+            if ttl.as_millis() == 0 {
+                return Err(invalid_option(
+                    "publish lease time-to-live",
+                    "the lease would expire before the publish",
+                ));
+            }
+        }
+        if let Some(retention) = &self.retention {
+            if retention.audit_retention() == Some(Duration::ZERO) {
+                return Err(invalid_option(
+                    "audit retention",
+                    "no audit could be served",
+                ));
+            }
+        }
+        if let Some(admission) = &self.admission {
+            if admission.max_concurrent() == 0 {
+                return Err(invalid_option(
+                    "admission control",
+                    "every proof generation would be rejected",
+                ));
+            }
+        }
+        if self.proof_cache_capacity == Some(0) {
+            return Err(invalid_option(
+                "proof cache capacity",
+                "no proof could be cached",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Validates the configuration and builds the directory, which creates the tree in the
+    /// storage layer if none is found (as with [Directory::new]). Nothing is written to the
+    /// storage layer, nor is the metrics recorder installed, if the configuration is invalid.
+    pub async fn build(self) -> Result<Directory<TC, S, V>, AkdError> {
+        self.validate_options()?;
+        if self.recorder.is_some() && telemetry::recorder_installed() {
+            return Err(ConfigurationError::MetricsRecorder.into());
+        }
+        self.vrf
+            .get_vrf_public_key_bytes::<TC>()
+            .await
+            .map_err(ConfigurationError::VrfKey)?;
+        if let Some(signer) = &self.signer {
+            signer
+                .get_public_key()
+                .await
+                .map_err(ConfigurationError::EpochSigner)?;
+        }
+
+        let storage = match self.cache_policy {
+            CachePolicy::Disabled => StorageManager::new_no_cache(self.db),
+            CachePolicy::Timed {
+                item_lifetime,
+                limit_bytes,
+                clean_frequency,
+            } => StorageManager::new(self.db, item_lifetime, limit_bytes, clean_frequency),
+        };
+        let mut directory = Directory::<TC, S, V>::new(storage, self.vrf).await?;
+        if let Some(policy) = self.retention {
+            directory = directory.with_retention_policy(policy);
+        }
+        if let Some((holder, ttl)) = self.publish_lease {
+            directory = directory.with_publish_lease(holder, ttl);
+        }
+        if let Some(signer) = self.signer {
+            directory = directory.with_shared_epoch_signer(signer);
+        }
+        if let Some(config) = self.admission {
+            directory = directory.with_admission_control(config);
+        }
+        if let Some(capacity) = self.proof_cache_capacity {
+            directory = directory.with_proof_cache(capacity);
+        }
+
+        if let Some(recorder) = self.recorder {
+            telemetry::set_recorder(recorder).map_err(|_| ConfigurationError::MetricsRecorder)?;
+        }
+        Ok(directory)
+    }
+}
+
+fn invalid_option(option: &'static str, reason: &str) -> ConfigurationError {
+    ConfigurationError::InvalidOption {
+        option,
+        reason: reason.to_string(),
+    }
+}
@@ -0,0 +1,91 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Cooperative cancellation of long-running [crate::Directory] operations. A
+//! [CancellationToken] is checked by the operation between its stages, so that it
+//! aborts with a [DirectoryError::Cancelled] error once the token is cancelled or
+//! its deadline passes, without leaving partial writes behind in storage.
+
+use crate::errors::{AkdError, DirectoryError};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A token which a caller can use to abort an operation it is no longer waiting
+/// for. Clones of the token share the cancellation state, so one clone can be
+/// passed to the operation while another is cancelled.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+}
+
+impl CancellationToken {
+    /// Creates a token which is only cancelled by [CancellationToken::cancel]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a token which is additionally cancelled once `deadline` passes
+    pub fn with_deadline(deadline: Instant) -> Self {
+        Self {
+            cancelled: Arc::new(AtomicBool::new(false)),
+            deadline: Some(deadline),
+        }
+    }
+
+    /// Creates a token which is additionally cancelled once `timeout` has elapsed
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self::with_deadline(Instant::now() + timeout)
+    }
+
+    /// Cancels the token, and all of its clones
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns whether the token was cancelled or its deadline has passed
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// Returns an error if the token is cancelled, to be called by the `operation`
+    /// at each point where it can be aborted
+    pub(crate) fn check(&self, operation: &str) -> Result<(), AkdError> {
+        if self.is_cancelled() {
+            return Err(AkdError::Directory(DirectoryError::Cancelled(format!(
+                "{operation} was cancelled"
+            ))));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancellation_token() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(clone.check("Operation").is_ok());
+        token.cancel();
+        assert!(clone.is_cancelled());
+        assert!(matches!(
+            clone.check("Operation"),
+            Err(AkdError::Directory(DirectoryError::Cancelled(_)))
+        ));
+
+        assert!(CancellationToken::with_timeout(Duration::ZERO).is_cancelled());
+        assert!(!CancellationToken::with_timeout(Duration::from_secs(60)).is_cancelled());
+    }
+}
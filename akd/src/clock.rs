@@ -0,0 +1,75 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! The source of the wall-clock time of a [crate::Directory], which timestamps the
+//! [crate::EpochMetadata] of each published epoch and the publish leases acquired in storage.
+//!
+//! By default the time is read from the system clock ([SystemClock]), and a different
+//! [Clock] can be configured with [crate::Directory::with_clock], e.g. to control the
+//! passage of time in tests.
+
+use crate::errors::{AkdError, DirectoryError};
+
+use log::error;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// A source of the current time
+pub trait Clock: Send + Sync {
+    /// Returns the current time, in milliseconds since the UNIX epoch
+    fn now_ms(&self) -> Result<u64, AkdError>;
+}
+
+/// A [Clock] reading the time of the system
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_ms(&self) -> Result<u64, AkdError> {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.as_millis() as u64)
+            .map_err(|err| {
+                error!("System clock is set before the UNIX epoch: {}", err);
+                AkdError::Directory(DirectoryError::Publish(format!(
+                    "Unable to determine the publication timestamp: {err}"
+                )))
+            })
+    }
+}
+
+/// A [Clock] whose time only changes when it is explicitly set or advanced. Clones share
+/// the same time.
+#[derive(Clone, Debug, Default)]
+pub struct ManualClock {
+    now_ms: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    /// Creates a new clock, starting at the provided time
+    pub fn new(now_ms: u64) -> Self {
+        Self {
+            now_ms: Arc::new(AtomicU64::new(now_ms)),
+        }
+    }
+
+    /// Sets the current time of the clock
+    pub fn set(&self, now_ms: u64) {
+        self.now_ms.store(now_ms, Ordering::SeqCst);
+    }
+
+    /// Advances the current time of the clock by the provided number of milliseconds
+    pub fn advance(&self, ms: u64) {
+        self.now_ms.fetch_add(ms, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now_ms(&self) -> Result<u64, AkdError> {
+        Ok(self.now_ms.load(Ordering::SeqCst))
+    }
+}
@@ -0,0 +1,190 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Test utilities checking that distinct configurations are incompatible with each other, so
+//! that a server and its clients which are misconfigured with different configurations fail
+//! loudly rather than silently
+
+use crate::auditor::audit_verify;
+use crate::client::{key_history_verify, lookup_verify};
+use crate::directory::Directory;
+use crate::ecvrf::HardCodedAkdVRF;
+use crate::storage::memory::AsyncInMemoryDatabase;
+use crate::storage::StorageManager;
+use crate::{AkdLabel, AkdValue, HistoryParams, HistoryVerificationParams, NamedConfiguration};
+
+// *** Tests *** //
+
+#[cfg(test)]
+#[allow(clippy::items_after_test_module)]
+mod configuration_matrix_tests {
+    use crate::errors::AkdError;
+    use crate::NamedConfiguration;
+
+    /// The name of a configuration, as serialized in the metadata of an artifact
+    #[derive(serde::Serialize, serde::Deserialize)]
+    struct Metadata {
+        configuration: String,
+    }
+
+    // NOTE(new_config): Add a new configuration here
+    fn configuration_names() -> Vec<&'static str> {
+        vec![
+            crate::WhatsAppV1Configuration::name(),
+            crate::ExperimentalConfiguration::<crate::ExampleLabel>::name(),
+            crate::Sha3Configuration::<crate::ExampleLabel>::name(),
+            crate::DomainSeparatedConfiguration::<crate::ExampleLabel>::name(),
+        ]
+    }
+
+    async fn run_against<TC: NamedConfiguration, OtherTC: NamedConfiguration>() {
+        if TC::name() != OtherTC::name() {
+            super::run_test_cases_for_configuration_pair::<TC, OtherTC>().await;
+        }
+    }
+
+    // NOTE(new_config): Add a new configuration here
+    test_config!(test_configuration_matrix);
+    async fn test_configuration_matrix<TC: NamedConfiguration>() -> Result<(), AkdError> {
+        run_against::<TC, crate::WhatsAppV1Configuration>().await;
+        run_against::<TC, crate::ExperimentalConfiguration<crate::ExampleLabel>>().await;
+        run_against::<TC, crate::Sha3Configuration<crate::ExampleLabel>>().await;
+        run_against::<TC, crate::DomainSeparatedConfiguration<crate::ExampleLabel>>().await;
+
+        // The name round-trips through serialized metadata, and identifies the configuration
+        let metadata = serde_json::to_string(&Metadata {
+            configuration: TC::name().to_string(),
+        })
+        .unwrap();
+        let metadata: Metadata = serde_json::from_str(&metadata).unwrap();
+        let matches = configuration_names()
+            .into_iter()
+            .filter(|name| *name == metadata.configuration)
+            .collect::<Vec<_>>();
+        assert_eq!(vec![TC::name()], matches);
+        Ok(())
+    }
+}
+
+// *** Run the test cases for a pair of configurations *** //
+/// Run the cross-configuration test suite for a pair of distinct configurations: the proofs
+/// generated by a directory with the first configuration must be rejected by the verifiers of
+/// the second configuration, and the names of the configurations must be distinct identifiers
+/// (so that they round-trip through serialized metadata, such as that of a fixture or a
+/// snapshot). This is public because it can be used to check custom configurations against
+/// those of the library.
+pub async fn run_test_cases_for_configuration_pair<
+    TC: NamedConfiguration,
+    OtherTC: NamedConfiguration,
+>() {
+    test_configuration_names::<TC, OtherTC>();
+    test_proofs_rejected::<TC, OtherTC>().await;
+}
+
+// *** New Test Helper Functions *** //
+fn test_configuration_names<TC: NamedConfiguration, OtherTC: NamedConfiguration>() {
+    for name in [TC::name(), OtherTC::name()] {
+        assert!(!name.is_empty());
+        assert!(
+            name.chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_'),
+            "The configuration name {name} is not an identifier"
+        );
+    }
+    assert_ne!(TC::name(), OtherTC::name());
+}
+
+async fn test_proofs_rejected<TC: NamedConfiguration, OtherTC: NamedConfiguration>() {
+    let storage = StorageManager::new_no_cache(AsyncInMemoryDatabase::new());
+    let akd = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {})
+        .await
+        .unwrap();
+    let mut root_hashes = vec![akd.get_epoch_hash().await.unwrap().hash()];
+    for value in ["world", "world2"] {
+        let epoch_hash = akd
+            .publish(vec![
+                (AkdLabel::from("hello"), AkdValue::from(value)),
+                (AkdLabel::from(value), AkdValue::from(value)),
+            ])
+            .await
+            .unwrap();
+        root_hashes.push(epoch_hash.hash());
+    }
+    let vrf_pk = akd.get_public_key().await.unwrap();
+    let label = AkdLabel::from("hello");
+
+    // Lookup proofs
+    let (proof, epoch_hash) = akd.lookup(label.clone()).await.unwrap();
+    let verify = |proof| {
+        lookup_verify::<OtherTC>(
+            vrf_pk.as_bytes(),
+            epoch_hash.hash(),
+            epoch_hash.epoch(),
+            label.clone(),
+            proof,
+        )
+    };
+    lookup_verify::<TC>(
+        vrf_pk.as_bytes(),
+        epoch_hash.hash(),
+        epoch_hash.epoch(),
+        label.clone(),
+        proof.clone(),
+    )
+    .unwrap();
+    assert!(
+        verify(proof).is_err(),
+        "A lookup proof of the {} configuration verified with the {} configuration",
+        TC::name(),
+        OtherTC::name()
+    );
+
+    // Key history proofs
+    let (proof, epoch_hash) = akd
+        .key_history(&label, HistoryParams::default())
+        .await
+        .unwrap();
+    let verify = |proof| {
+        key_history_verify::<OtherTC>(
+            vrf_pk.as_bytes(),
+            epoch_hash.hash(),
+            epoch_hash.epoch(),
+            label.clone(),
+            proof,
+            HistoryVerificationParams::default(),
+        )
+    };
+    key_history_verify::<TC>(
+        vrf_pk.as_bytes(),
+        epoch_hash.hash(),
+        epoch_hash.epoch(),
+        label.clone(),
+        proof.clone(),
+        HistoryVerificationParams::default(),
+    )
+    .unwrap();
+    assert!(
+        verify(proof).is_err(),
+        "A key history proof of the {} configuration verified with the {} configuration",
+        TC::name(),
+        OtherTC::name()
+    );
+
+    // Audit proofs
+    let proof = akd.audit(1, 2).await.unwrap();
+    audit_verify::<TC>(root_hashes[1..].to_vec(), proof.clone())
+        .await
+        .unwrap();
+    assert!(
+        audit_verify::<OtherTC>(root_hashes[1..].to_vec(), proof)
+            .await
+            .is_err(),
+        "An audit proof of the {} configuration verified with the {} configuration",
+        TC::name(),
+        OtherTC::name()
+    );
+}
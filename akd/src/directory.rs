@@ -7,27 +7,102 @@
 
 //! Implementation of an auditable key directory
 
+use crate::admission::{AdmissionConfig, AdmissionController};
 use crate::append_only_zks::{Azks, InsertMode};
-use crate::ecvrf::{VRFKeyStorage, VRFPublicKey};
+use crate::builder::DirectoryBuilder;
+use crate::cancellation::CancellationToken;
+use crate::clock::{Clock, SystemClock};
+use crate::ecvrf::{
+    Ed25519Sha512Tai, PublicKeyOnlyVRF, VRFKeyStorage, VRFPublicKey, VrfError, VrfScheme,
+};
+use crate::epoch_publisher::EpochPublisher;
 use crate::errors::{AkdError, DirectoryError, StorageError};
-use crate::helper_structs::LookupInfo;
+use crate::helper_structs::{
+    DirectoryStats, EpochDiff, EpochReplay, HealthReport, LabelPage, LookupInfo, PublishPreview,
+};
+use crate::proof_cache::{ProofCache, ProofCacheStats};
+use crate::retention::{RetentionEnforcer, RetentionPolicy, RetentionReport};
+use crate::signature::{EpochSigner, SignatureError};
 use crate::storage::manager::StorageManager;
-use crate::storage::types::{DbRecord, ValueState, ValueStateRetrievalFlag};
+use crate::storage::memory::AsyncInMemoryDatabase;
+use crate::storage::types::{
+    DbRecord, EpochRootRecord, JournalEntry, JournalLabel, PrecomputedLookupProof,
+    PrecomputedVrfProof, PublishLease, RollbackRecord, UpdateJournal, UpdateJournalMode,
+    ValueState, ValueStateKey, ValueStateRetrievalFlag, VrfProofKey,
+};
 use crate::storage::Database;
+use crate::telemetry::{self, Counter, Histogram};
+use crate::tree_node::{NodeKey, TreeNodeWithPreviousValue};
 use crate::{
-    AkdLabel, AkdValue, AppendOnlyProof, AzksElement, Digest, EpochHash, HistoryProof, LookupProof,
-    UpdateProof,
+    AkdLabel, AkdValue, AppendOnlyProof, AuditProofSegment, AzksElement, AzksValue,
+    CompressedAppendOnlyProof, CompressedSingleAppendOnlyProof, Digest, EpochHash, EpochMetadata,
+    EpochMetadataProof, EpochRootChain, HistoryProof, KeyRotationProof, LabelNamespace,
+    LookupProof, MergeBridgeProof, NodeLabel, NonMembershipLookupProof, SizeOf, UpdateProof,
+    VrfRotationCertificate,
 };
 
 use crate::VersionFreshness;
-use akd_core::configuration::Configuration;
+use akd_core::configuration::{Configuration, HashPurpose};
+use akd_core::hash::EMPTY_DIGEST;
 use akd_core::utils::get_marker_versions;
 use akd_core::verify::history::HistoryParams;
-use log::{error, info};
-use std::collections::{HashMap, HashSet};
+use futures::{Stream, StreamExt};
+use log::{debug, error, info, warn};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::marker::PhantomData;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, Notify, OwnedSemaphorePermit, RwLock};
+use zeroize::Zeroizing;
+
+/// The number of records which are consumed and written together during a [Directory::bulk_import]
+const BULK_IMPORT_CHUNK_SIZE: usize = 10_000;
+/// The number of nodes per segment with which a [Directory::audit_range_compressed] proof is generated
+const COMPRESSED_AUDIT_SEGMENT_SIZE: usize = 10_000;
+/// The number of labels which are enumerated and republished together during a
+/// [Directory::rotate_vrf_key]
+const VRF_ROTATION_PAGE_SIZE: usize = 1_000;
+/// The number of labels enumerated at a time when reading the history of a directory, or when
+/// summarizing the changes between two of its epochs
+const HISTORY_PAGE_SIZE: usize = 1_000;
+/// The number of labels whose lookup proofs are generated and stored together by
+/// [Directory::precompute_lookup_proofs]
+const PRECOMPUTE_PAGE_SIZE: usize = 1_000;
+/// The number of paths of the tree which are checked by [Directory::health_check]
+const HEALTH_CHECK_SAMPLED_PATHS: u64 = 4;
+/// The domain separator of the sampled paths and the VRF input of [Directory::health_check]
+const HEALTH_CHECK_DOMAIN_SEPARATOR: &[u8] = b"akd_health_check";
+
+/// The domain separator of the keyed hashes of the labels recorded in the update journals
+const UPDATE_JOURNAL_DOMAIN_SEPARATOR: &[u8] = b"akd_update_journal";
+
+/// A rotation of the VRF key of a directory, as performed by [Directory::rotate_vrf_key].
+///
+/// The rotation is not recorded in storage, and so it must be provided through
+/// [Directory::with_vrf_key_rotation] to every instance which is constructed with the new key.
+#[derive(Clone)]
+pub struct VrfKeyRotation<V> {
+    /// The epoch at which the labels were republished under the new key
+    pub rotation_epoch: u64,
+    /// The previous key, which is only needed to produce [KeyRotationProof]s during the
+    /// transition window, and can be dropped once clients have moved to the new key
+    pub previous_vrf: Option<V>,
+    /// The rotation epoch of the previous key, if it was itself rotated to (0 otherwise)
+    pub previous_rotation_epoch: u64,
+}
+
+/// Whether the VRF proofs of the published versions are precomputed in storage, see
+/// [Directory::with_precomputed_vrf_proofs]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum VrfProofPrecomputation {
+    /// The VRF proofs are computed with the private key as they are needed
+    Disabled,
+    /// The VRF proofs of each published version are also precomputed into storage
+    Store,
+    /// The VRF proofs are read from storage, as the directory only holds the public key
+    Read,
+}
 
 /// The representation of a auditable key directory
 pub struct Directory<TC, S: Database, V> {
@@ -40,6 +115,52 @@ pub struct Directory<TC, S: Database, V> {
     /// (in this case we do utilize the write() lock which can only occur 1
     /// at a time and gates further read() locks being acquired during write()).
     cache_lock: Arc<RwLock<()>>,
+    /// Whether the [EpochMetadata] written at publish time is also inserted into
+    /// the tree, binding it to the root hash of the epoch
+    bind_epoch_metadata: bool,
+    /// If provided, the signer used to sign the root hash of each published epoch
+    signer: Option<Arc<dyn EpochSigner>>,
+    /// The publishers to which the root hash of each published epoch is distributed
+    epoch_publishers: Vec<Arc<dyn EpochPublisher>>,
+    /// Updates which have been enqueued for a later publish, keyed by label so that
+    /// the most recently enqueued value for a label supersedes any earlier one
+    pending_updates: Arc<Mutex<HashMap<AkdLabel, AkdValue>>>,
+    /// Notified whenever an update is enqueued
+    pending_notify: Arc<Notify>,
+    /// If provided, the holder identifier and time-to-live (in milliseconds) of the
+    /// advisory lease which is acquired in storage for each publish
+    publish_lease: Option<(Vec<u8>, u64)>,
+    /// The duration of the most recent successful publish by this instance
+    last_publish_duration: Arc<Mutex<Option<Duration>>>,
+    /// The namespaces registered via [Directory::with_label_namespace]. If non-empty,
+    /// every published label must belong to one of them
+    label_namespaces: Arc<HashSet<LabelNamespace>>,
+    /// If provided, the limits on concurrent proof generations
+    admission: Option<Arc<AdmissionController>>,
+    /// If provided, the number of tasks across which the tree insertion of each publish
+    /// is spread, overriding the default of the `parallel_insert` feature
+    insert_tasks: Option<usize>,
+    /// If provided, the number of tasks across which the VRF evaluations of each publish are
+    /// spread, instead of a task per evaluation
+    vrf_tasks: Option<usize>,
+    /// If provided, the rotation of the VRF key which this directory went through
+    vrf_rotation: Option<VrfKeyRotation<V>>,
+    /// Whether every published value must be committed to with a blinding supplied by the client
+    require_blinding: bool,
+    /// The number of decoy leaves inserted into the tree by each publish
+    decoy_leaves: usize,
+    /// The source of the publication timestamps
+    clock: Arc<dyn Clock>,
+    /// If provided, the retention policy enforced after each publish
+    retention: Option<Arc<RetentionEnforcer>>,
+    /// If provided, the cache of the lookup proofs of the latest epoch
+    proof_cache: Option<Arc<ProofCache>>,
+    /// If provided, how the journal of the updates of each published epoch is recorded
+    update_journal: Option<UpdateJournalMode>,
+    /// Whether the root hash of each published epoch is recorded in storage
+    root_history: bool,
+    /// Whether the VRF proofs of the published versions are precomputed in storage
+    vrf_proofs: VrfProofPrecomputation,
     tc: PhantomData<TC>,
 }
 
@@ -50,6 +171,26 @@ impl<TC, S: Database, V: VRFKeyStorage> Clone for Directory<TC, S, V> {
             storage: self.storage.clone(),
             vrf: self.vrf.clone(),
             cache_lock: self.cache_lock.clone(),
+            bind_epoch_metadata: self.bind_epoch_metadata,
+            signer: self.signer.clone(),
+            epoch_publishers: self.epoch_publishers.clone(),
+            pending_updates: self.pending_updates.clone(),
+            pending_notify: self.pending_notify.clone(),
+            publish_lease: self.publish_lease.clone(),
+            last_publish_duration: self.last_publish_duration.clone(),
+            label_namespaces: self.label_namespaces.clone(),
+            admission: self.admission.clone(),
+            insert_tasks: self.insert_tasks,
+            vrf_tasks: self.vrf_tasks,
+            vrf_rotation: self.vrf_rotation.clone(),
+            require_blinding: self.require_blinding,
+            decoy_leaves: self.decoy_leaves,
+            clock: self.clock.clone(),
+            retention: self.retention.clone(),
+            proof_cache: self.proof_cache.clone(),
+            update_journal: self.update_journal,
+            root_history: self.root_history,
+            vrf_proofs: self.vrf_proofs,
             tc: PhantomData,
         }
     }
@@ -82,17 +223,336 @@ where
             storage,
             cache_lock: Arc::new(RwLock::new(())),
             vrf,
+            bind_epoch_metadata: false,
+            signer: None,
+            epoch_publishers: vec![],
+            pending_updates: Arc::new(Mutex::new(HashMap::new())),
+            pending_notify: Arc::new(Notify::new()),
+            publish_lease: None,
+            last_publish_duration: Arc::new(Mutex::new(None)),
+            label_namespaces: Arc::new(HashSet::new()),
+            admission: None,
+            insert_tasks: None,
+            vrf_tasks: None,
+            vrf_rotation: None,
+            require_blinding: false,
+            decoy_leaves: 0,
+            clock: Arc::new(SystemClock),
+            retention: None,
+            proof_cache: None,
+            update_journal: None,
+            root_history: false,
+            vrf_proofs: VrfProofPrecomputation::Disabled,
             tc: PhantomData,
         })
     }
 
+    /// Starts the configuration of a directory over the storage layer `db` with a
+    /// [DirectoryBuilder], which validates the configuration before building the directory
+    pub fn builder(db: S, vrf: V) -> DirectoryBuilder<TC, S, V> {
+        DirectoryBuilder::new(db, vrf)
+    }
+
+    /// Configures whether the [EpochMetadata] recorded for each subsequently published epoch
+    /// is bound into the root hash of that epoch. When enabled, the metadata commitment is
+    /// inserted into the tree as a leaf, and clients can verify publication times via
+    /// [Directory::get_epoch_metadata_proof].
+    ///
+    /// Note that this should be set consistently across every instance publishing to the same
+    /// storage layer, as only the epochs published while enabled will have a bound commitment.
+    pub fn with_epoch_metadata_binding(mut self, enabled: bool) -> Self {
+        self.bind_epoch_metadata = enabled;
+        self
+    }
+
+    /// Registers a namespace of labels which can be published to this directory. Once any
+    /// namespace is registered, publishes (and imports) are restricted to labels constructed via
+    /// [LabelNamespace::label] for one of the registered namespaces, so that the label spaces
+    /// hosted by the directory are domain separated from each other.
+    pub fn with_label_namespace(mut self, namespace: LabelNamespace) -> Self {
+        Arc::make_mut(&mut self.label_namespaces).insert(namespace);
+        self
+    }
+
+    /// Whether the [EpochMetadata] of subsequently published epochs is bound into their root hash
+    pub(crate) fn binds_epoch_metadata(&self) -> bool {
+        self.bind_epoch_metadata
+    }
+
+    /// The namespaces registered via [Directory::with_label_namespace]
+    pub fn label_namespaces(&self) -> Vec<LabelNamespace> {
+        let mut namespaces = self.label_namespaces.iter().cloned().collect::<Vec<_>>();
+        namespaces.sort();
+        namespaces
+    }
+
+    /// Configures limits on the proof generations ([Directory::lookup], [Directory::lookup_absent],
+    /// [Directory::batch_lookup] and [Directory::key_history]) of this directory and all of its
+    /// clones, so that a burst of expensive requests is queued or shed rather than saturating the
+    /// storage layer.
+    /// Rejected requests fail with [DirectoryError::Overloaded].
+    pub fn with_admission_control(mut self, config: AdmissionConfig) -> Self {
+        self.admission = Some(Arc::new(AdmissionController::new(config)));
+        self
+    }
+
+    /// Configures the number of tokio tasks across which the tree insertion of each publish is
+    /// spread (see [Azks::batch_insert_nodes_in_parallel]), which is otherwise determined by the
+    /// `parallel_insert` feature. A value of 0 or 1 inserts sequentially, as does any value with
+    /// the `profiling` feature.
+    pub fn with_insert_parallelism(mut self, num_tasks: usize) -> Self {
+        self.insert_tasks = Some(num_tasks);
+        self
+    }
+
+    /// Configures the number of tasks across which the VRF evaluations of each publish (and of
+    /// [Directory::bulk_import] and [Directory::rotate_vrf_key]) are spread, see
+    /// [VRFKeyStorage::get_node_labels_with_parallelism]. This only has an effect with the
+    /// `parallel_vrf` feature, which otherwise spawns a task per evaluation.
+    pub fn with_vrf_parallelism(mut self, num_tasks: usize) -> Self {
+        self.vrf_tasks = Some(num_tasks);
+        self
+    }
+
+    /// Configures the directory to acquire a lease in storage for each publish (as well as each
+    /// [Directory::rollback_last_epoch] and [Directory::bulk_import]), so that only one of the instances
+    /// sharing the storage layer can advance the epoch at a time. The lease is identified by
+    /// `holder` (which should be unique per instance) and must be held for the duration of the
+    /// publish, which should not exceed `ttl`.
+    ///
+    /// The transaction of the publish is committed with the fencing token of the lease (see
+    /// [crate::storage::DbSetState::Fenced]), so that the storage layer rejects the commit of a
+    /// writer whose lease expired and was taken over by another instance. This requires a storage
+    /// layer implementing [Database::acquire_publish_lease] and fencing its writes accordingly.
+    pub fn with_publish_lease(mut self, holder: Vec<u8>, ttl: Duration) -> Self {
+        self.publish_lease = Some((holder, ttl.as_millis() as u64));
+        self
+    }
+
+    /// Configures an [EpochSigner] which signs the `(epoch, root_hash)` pair of each
+    /// subsequently published epoch. The signature is stored in the [EpochMetadata] of the
+    /// epoch, and can be checked by relying parties with [crate::client::verify_epoch_signature].
+    pub fn with_epoch_signer<ES: EpochSigner + 'static>(mut self, signer: ES) -> Self {
+        self.signer = Some(Arc::new(signer));
+        self
+    }
+
+    /// Configures an [EpochSigner] which is shared with other instances, see
+    /// [Directory::with_epoch_signer]
+    pub(crate) fn with_shared_epoch_signer(mut self, signer: Arc<dyn EpochSigner>) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Registers an [EpochPublisher], to which the epoch, root hash and signature (if
+    /// configured with [Directory::with_epoch_signer]) of each subsequently published epoch
+    /// is distributed once the publish is committed. Several publishers can be registered,
+    /// and are invoked in order of registration. As the epoch is already committed, a failure
+    /// of a publisher is logged rather than failing the publish.
+    pub fn with_epoch_publisher<EP: EpochPublisher + 'static>(mut self, publisher: EP) -> Self {
+        self.epoch_publishers.push(Arc::new(publisher));
+        self
+    }
+
+    /// Configures the rotation of the VRF key which this directory went through, so that its
+    /// proofs account for the labels having been republished under the current key at the
+    /// rotation epoch (see [Directory::rotate_vrf_key]).
+    pub fn with_vrf_key_rotation(mut self, rotation: VrfKeyRotation<V>) -> Self {
+        self.vrf_rotation = Some(rotation);
+        self
+    }
+
+    /// Configures whether every value published to the directory must be committed to with a
+    /// blinding supplied by the client (see [Directory::publish_with_blindings]), so that the
+    /// commitments in the tree cannot be opened with the commitment key alone. When enabled,
+    /// publishing a value without a blinding fails, as does [Directory::bulk_import].
+    pub fn with_required_blinding(mut self, enabled: bool) -> Self {
+        self.require_blinding = enabled;
+        self
+    }
+
+    /// Configures the number of decoy leaves which each subsequent publish inserts into the tree
+    /// alongside the leaves of the updated labels, so that observers of the audit proofs cannot
+    /// tell exactly how many labels were updated in an epoch. The labels and commitments of the
+    /// decoys are derived from the commitment key, and so cannot be told apart from those of the
+    /// real leaves without it. No decoys are inserted by a publish without any updates.
+    ///
+    /// Since every decoy stays in the tree, they add to its size (and to the cost of the proofs)
+    /// in the same way as updates do.
+    pub fn with_decoy_leaves(mut self, count: usize) -> Self {
+        self.decoy_leaves = count;
+        self
+    }
+
+    /// Configures the directory to record the journal of the updates (the label and version of
+    /// each published value) of each subsequently published or imported epoch through the storage
+    /// layer, e.g. for external analytics, or to collect the updates to provide to
+    /// [Directory::replay_epoch]. The journals are retrieved with [Directory::get_update_journal]
+    /// and [Directory::stream_update_journals]. With [UpdateJournalMode::HashedLabels], only
+    /// keyed hashes of the labels are recorded.
+    ///
+    /// The journal of an epoch is written (as a [DbRecord::UpdateJournal]) in the transaction
+    /// of the epoch, and a publish fails if its journal cannot be written.
+    pub fn with_update_journal(mut self, mode: UpdateJournalMode) -> Self {
+        self.update_journal = Some(mode);
+        self
+    }
+
+    /// Records the root hash of each subsequently published or imported epoch in storage, linked
+    /// to the root hash of the previous epoch by a hash chain, so that the root hashes of past
+    /// epochs can be served with [Directory::get_epoch_hashes]. The chain starts at the first
+    /// epoch published with the root history enabled (or after an epoch whose root hash was not
+    /// recorded).
+    ///
+    /// Like the update journal, the root hash of an epoch is written (as a
+    /// [DbRecord::EpochRootRecord]) in the transaction of the epoch, and a publish fails if it
+    /// cannot be written.
+    pub fn with_root_history(mut self) -> Self {
+        self.root_history = true;
+        self
+    }
+
+    /// Precomputes the VRF proofs needed to serve the lookup and key history proofs of each
+    /// subsequently published or imported version into storage (as
+    /// [DbRecord::PrecomputedVrfProof]s), along with the openings of the commitments to their
+    /// values, so that replicas which only hold the VRF public key (see
+    /// [Directory::new_read_only]) can serve these proofs as well.
+    ///
+    /// Besides the fresh and stale proofs of each published version, these are the fresh proofs
+    /// of the marker versions of its history: the following versions up to the next power of two,
+    /// and the powers of two up to the current epoch. The latter are precomputed for every label
+    /// whenever the epoch reaches a power of two, which requires a storage layer implementing
+    /// [Database::list_labels], and adds a VRF proof per label to the publish of such an epoch.
+    /// Like the value states, the proofs are written in the transaction of the epoch, and a
+    /// publish fails if they cannot be computed.
+    ///
+    /// The proofs of the versions published before this is enabled are not precomputed, and the
+    /// VRF key of the directory can no longer be rotated, as the precomputed proofs would not
+    /// carry over to the new key.
+    pub fn with_precomputed_vrf_proofs(mut self) -> Self {
+        self.vrf_proofs = VrfProofPrecomputation::Store;
+        self
+    }
+
+    /// Configures the [Clock] from which the publication timestamps of the subsequently
+    /// published epochs (and the times at which publish leases are acquired) are read, which
+    /// is otherwise the [SystemClock].
+    pub fn with_clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Configures the [RetentionPolicy] of this directory and all of its clones, which is
+    /// enforced after each publish (see [Directory::enforce_retention]) rather than retaining
+    /// the history of every epoch indefinitely. This requires a storage layer implementing
+    /// [Database::batch_delete] to prune the [EpochMetadata] of expired epochs.
+    pub fn with_retention_policy(mut self, policy: RetentionPolicy) -> Self {
+        self.retention = Some(Arc::new(RetentionEnforcer::new(policy)));
+        self
+    }
+
+    /// Configures a cache of up to `capacity` lookup proofs, shared by this directory and all of
+    /// its clones, from which [Directory::lookup] serves the repeated lookups of a label within
+    /// the same epoch. The cache is emptied whenever an epoch is published or rolled back by one
+    /// of these instances, or when [Directory::poll_for_azks_changes] detects a new epoch.
+    ///
+    /// Values tombstoned directly in storage (see [StorageManager::tombstone_value_states]) keep
+    /// being served from the cache until the next epoch, unless [Directory::invalidate_proof_cache]
+    /// is called.
+    pub fn with_proof_cache(mut self, capacity: usize) -> Self {
+        self.proof_cache = Some(Arc::new(ProofCache::new(capacity)));
+        self
+    }
+
+    /// Statistics about the use of the proof cache, if configured via
+    /// [Directory::with_proof_cache]
+    pub fn proof_cache_stats(&self) -> Option<ProofCacheStats> {
+        self.proof_cache.as_ref().map(|cache| cache.stats())
+    }
+
+    /// Empties the proof cache (if configured via [Directory::with_proof_cache]), so that every
+    /// subsequent lookup generates its proof from storage
+    pub fn invalidate_proof_cache(&self) {
+        if let Some(cache) = &self.proof_cache {
+            cache.invalidate();
+        }
+    }
+
     /// Updates the directory to include the input label-value pairs.
     ///
     /// Note that the vector of label-value pairs should not contain any entries with duplicate labels. This
     /// condition is explicitly checked, and an error will be returned if this is the case.
     pub async fn publish(&self, updates: Vec<(AkdLabel, AkdValue)>) -> Result<EpochHash, AkdError> {
+        self.publish_with_note(updates, None).await
+    }
+
+    /// Same as [Directory::publish], but the lookup proofs of every label of the directory are
+    /// then generated and stored (see [Directory::precompute_lookup_proofs]), so that they can be
+    /// served with [Directory::precomputed_lookup] without generating them. This is meant for
+    /// deployments with a bounded set of labels, as the cost of each publish grows with it.
+    ///
+    /// If the proofs fail to be precomputed, the error is returned even though the epoch is
+    /// already committed, and the precomputation can be retried.
+    pub async fn publish_with_proof_precompute(
+        &self,
+        updates: Vec<(AkdLabel, AkdValue)>,
+    ) -> Result<EpochHash, AkdError> {
+        let epoch_hash = self.publish(updates).await?;
+        self.precompute_lookup_proofs().await?;
+        Ok(epoch_hash)
+    }
+
+    /// Same as [Directory::publish], but additionally records the provided operator note
+    /// in the [EpochMetadata] of the new epoch.
+    pub async fn publish_with_note(
+        &self,
+        updates: Vec<(AkdLabel, AkdValue)>,
+        note: Option<String>,
+    ) -> Result<EpochHash, AkdError> {
+        self.publish_cancellable(updates, note, &CancellationToken::new())
+            .await
+    }
+
+    /// Same as [Directory::publish_with_note], but aborts with [DirectoryError::Cancelled] if the
+    /// cancellation token is cancelled before the new epoch is committed. Nothing is written to
+    /// storage by a cancelled publish, while a publish which has already been committed completes.
+    pub async fn publish_cancellable(
+        &self,
+        updates: Vec<(AkdLabel, AkdValue)>,
+        note: Option<String>,
+        cancellation: &CancellationToken,
+    ) -> Result<EpochHash, AkdError> {
+        self.publish_blinded(updates, HashMap::new(), note, cancellation)
+            .await
+    }
+
+    /// Same as [Directory::publish], but the commitment to the value of each label in `blindings`
+    /// is computed with the blinding supplied by the client for it (see
+    /// [Configuration::get_blinded_commitment_nonce]). The blindings are stored alongside the
+    /// values, and the proofs served for them verify as usual.
+    ///
+    /// Each blinding must be non-empty, at most [ValueState::MAX_BLINDING_LENGTH] bytes long, and
+    /// for a label which is being updated. Unless configured with [Directory::with_required_blinding],
+    /// the labels without a blinding are committed to with the commitment key alone.
+    pub async fn publish_with_blindings(
+        &self,
+        updates: Vec<(AkdLabel, AkdValue)>,
+        blindings: HashMap<AkdLabel, Vec<u8>>,
+    ) -> Result<EpochHash, AkdError> {
+        self.publish_blinded(updates, blindings, None, &CancellationToken::new())
+            .await
+    }
+
+    async fn publish_blinded(
+        &self,
+        updates: Vec<(AkdLabel, AkdValue)>,
+        blindings: HashMap<AkdLabel, Vec<u8>>,
+        note: Option<String>,
+        cancellation: &CancellationToken,
+    ) -> Result<EpochHash, AkdError> {
         // The guard will be dropped at the end of the publish
-        let _guard = self.cache_lock.read().await;
+        let guard = self.cache_lock.read().await;
+        let started = Instant::now();
 
         // Check for duplicate labels and return an error if any are encountered
         let distinct_set: HashSet<AkdLabel> =
@@ -102,98 +562,102 @@ where
                 "Cannot publish with a set of entries that contain duplicate labels".to_string(),
             )));
         }
+        self.check_label_namespaces(updates.iter().map(|(label, _)| label))?;
+        self.check_tombstones(updates.iter())?;
+        self.check_blindings(&distinct_set, &blindings)?;
 
-        let mut update_set = Vec::<AzksElement>::new();
-        let mut user_data_update_set = Vec::<ValueState>::new();
-
-        let mut current_azks = self.retrieve_azks().await?;
-        let current_epoch = current_azks.get_latest_epoch();
-        let next_epoch = current_epoch + 1;
-
-        let mut keys: Vec<AkdLabel> = updates
-            .iter()
-            .map(|(akd_label, _val)| akd_label.clone())
-            .collect();
-
-        // sort the keys, as inserting in primary-key order is more efficient for MySQL
-        keys.sort();
-
-        // we're only using the maximum "version" of the user's state at the last epoch
-        // they were seen in the directory. Therefore we've minimized the call to only
-        // return a hashmap of AkdLabel => u64 and not retrieving the other data which is not
-        // read (i.e. the actual _data_ payload).
-        let all_user_versions_retrieved = self
-            .storage
-            .get_user_state_versions(&keys, ValueStateRetrievalFlag::LeqEpoch(current_epoch))
-            .await?;
-
-        info!(
-            "Retrieved {} previous user versions of {} requested",
-            all_user_versions_retrieved.len(),
-            keys.len()
-        );
+        // The note is stored alongside the epoch, so bound its size up front
+        if let Some(note) = &note {
+            if note.len() > EpochMetadata::MAX_NOTE_LENGTH {
+                return Err(AkdError::Directory(DirectoryError::Publish(format!(
+                    "Epoch note of {} bytes exceeds the maximum length of {} bytes",
+                    note.len(),
+                    EpochMetadata::MAX_NOTE_LENGTH
+                ))));
+            }
+        }
+        cancellation.check("Publish")?;
+        let timestamp_ms = self.clock.now_ms()?;
 
-        let vrf_computations = updates
-            .iter()
-            .flat_map(
-                |(akd_label, akd_value)| match all_user_versions_retrieved.get(akd_label) {
-                    None => vec![(
-                        akd_label.clone(),
-                        VersionFreshness::Fresh,
-                        1u64,
-                        akd_value.clone(),
-                    )],
-                    Some((latest_version, existing_akd_value)) => {
-                        if existing_akd_value == akd_value {
-                            // Skip this because the user is trying to re-publish the same value
-                            return vec![];
-                        }
-                        vec![
-                            (
-                                akd_label.clone(),
-                                VersionFreshness::Stale,
-                                *latest_version,
-                                akd_value.clone(),
-                            ),
-                            (
-                                akd_label.clone(),
-                                VersionFreshness::Fresh,
-                                *latest_version + 1,
-                                akd_value.clone(),
-                            ),
-                        ]
-                    }
-                },
+        let lease = self.acquire_publish_lease(timestamp_ms).await?;
+        let result = self
+            .publish_with_lease(
+                updates,
+                &blindings,
+                note,
+                timestamp_ms,
+                lease.as_ref(),
+                cancellation,
             )
-            .collect::<Vec<_>>();
+            .await;
+        if let Some(lease) = &lease {
+            if let Err(err) = self.storage.release_publish_lease(lease).await {
+                error!("Failed to release the publish lease: {}", err);
+            }
+        }
+        if result.is_ok() {
+            let duration = started.elapsed();
+            telemetry::record_histogram(Histogram::PublishDuration, duration.as_secs_f64(), &[]);
+            *self.last_publish_duration.lock().await = Some(duration);
+        }
+        drop(guard);
 
-        let vrf_map = self
-            .vrf
-            .get_node_labels::<TC>(&vrf_computations)
-            .await?
-            .into_iter()
-            .collect::<HashMap<_, _>>();
+        // The epoch is already committed, so a failure to prune its history is only logged
+        if result.is_ok() && self.retention.is_some() {
+            if let Err(err) = self.enforce_retention().await {
+                error!("Failed to enforce the retention policy: {}", err);
+            }
+        }
+        result
+    }
 
-        let commitment_key = self.derive_commitment_key().await?;
+    /// Prunes the history which the [RetentionPolicy] configured via
+    /// [Directory::with_retention_policy] no longer retains, as of the current epoch and time.
+    /// This is done automatically after each publish, and excludes proof generations while
+    /// it runs. Nothing is pruned if no policy is configured.
+    pub async fn enforce_retention(&self) -> Result<RetentionReport, AkdError> {
+        let Some(retention) = &self.retention else {
+            return Ok(RetentionReport::default());
+        };
+        let _guard = self.cache_lock.write().await;
+        let latest_epoch = self.retrieve_azks().await?.get_latest_epoch();
+        let report = retention
+            .enforce(&self.storage, latest_epoch, self.clock.now_ms()?)
+            .await?;
+        if report != RetentionReport::default() {
+            info!(
+                "Retention policy enforced at epoch {}: pruned {} node states, the metadata of {} epochs and {} value states",
+                latest_epoch,
+                report.pruned_node_states,
+                report.pruned_epoch_metadata,
+                report.pruned_value_states
+            );
+        }
+        Ok(report)
+    }
 
-        for ((akd_label, freshness, version, akd_value), node_label) in vrf_map {
-            let azks_value = match freshness {
-                VersionFreshness::Stale => TC::stale_azks_value(),
-                VersionFreshness::Fresh => {
-                    TC::compute_fresh_azks_value(&commitment_key, &node_label, version, &akd_value)
-                }
-            };
-            update_set.push(AzksElement {
-                label: node_label,
-                value: azks_value,
-            });
+    async fn publish_with_lease(
+        &self,
+        updates: Vec<(AkdLabel, AkdValue)>,
+        blindings: &HashMap<AkdLabel, Vec<u8>>,
+        note: Option<String>,
+        timestamp_ms: u64,
+        lease: Option<&PublishLease>,
+        cancellation: &CancellationToken,
+    ) -> Result<EpochHash, AkdError> {
+        let current_azks = self.retrieve_azks_for_publish(lease).await?;
+        let current_epoch = current_azks.get_latest_epoch();
+        let next_epoch = current_epoch + 1;
 
-            if freshness == VersionFreshness::Fresh {
-                let latest_state =
-                    ValueState::new(akd_label, akd_value, version, node_label, next_epoch);
-                user_data_update_set.push(latest_state);
-            }
-        }
+        let PreparedUpdates {
+            mut update_set,
+            user_data_update_set,
+            commitment_key,
+            ..
+        } = self
+            .prepare_updates(&updates, blindings, current_epoch)
+            .await?;
+        cancellation.check("Publish")?;
 
         if update_set.is_empty() {
             info!("After filtering for duplicated user information, there is no publish which is necessary (0 updates)");
@@ -201,32 +665,194 @@ where
             let root_hash = current_azks.get_root_hash::<TC, _>(&self.storage).await?;
             return Ok(EpochHash(current_epoch, root_hash));
         }
+        update_set.extend(derive_decoy_leaves::<TC>(
+            &*commitment_key,
+            next_epoch,
+            self.decoy_leaves,
+        ));
 
-        if !self.storage.begin_transaction() {
-            error!("Transaction is already active");
-            return Err(AkdError::Storage(StorageError::Transaction(
-                "Transaction is already active".to_string(),
-            )));
+        let epoch_metadata = EpochMetadata {
+            epoch: next_epoch,
+            timestamp_ms,
+            update_count: user_data_update_set.len() as u64,
+            note,
+            bound: self.bind_epoch_metadata,
+            signature: None,
+        };
+        self.commit_epoch(
+            current_azks,
+            update_set,
+            user_data_update_set,
+            epoch_metadata,
+            &commitment_key,
+            lease,
+            cancellation,
+        )
+        .await
+    }
+
+    /// Retrieves the azks on top of which the next epoch is published
+    async fn retrieve_azks_for_publish(
+        &self,
+        lease: Option<&PublishLease>,
+    ) -> Result<Azks, AkdError> {
+        let current_azks = self.retrieve_azks().await?;
+        if lease.is_some() {
+            // Another instance may have advanced the epoch while this one wasn't holding the lease
+            let latest_azks =
+                Directory::<TC, S, V>::get_azks_from_storage(&self.storage, true).await?;
+            if latest_azks.latest_epoch != current_azks.latest_epoch {
+                return Err(AkdError::Directory(DirectoryError::Publish(format!(
+                    "Directory is at epoch {} but storage is at epoch {}, the cache must be refreshed before publishing",
+                    current_azks.latest_epoch, latest_azks.latest_epoch
+                ))));
+            }
+        }
+        Ok(current_azks)
+    }
+
+    /// Inserts the leaves of the next epoch into the tree, and commits them to storage along
+    /// with the value states and the [EpochMetadata] of the epoch
+    #[allow(clippy::too_many_arguments)]
+    async fn commit_epoch(
+        &self,
+        mut current_azks: Azks,
+        mut update_set: Vec<AzksElement>,
+        user_data_update_set: Vec<ValueState>,
+        mut epoch_metadata: EpochMetadata,
+        commitment_key: &Digest,
+        lease: Option<&PublishLease>,
+        cancellation: &CancellationToken,
+    ) -> Result<EpochHash, AkdError> {
+        let next_epoch = epoch_metadata.epoch;
+        if epoch_metadata.bound {
+            let nonce = epoch_metadata.commitment_nonce::<TC>(commitment_key);
+            update_set.push(AzksElement {
+                label: EpochMetadata::node_label::<TC>(next_epoch),
+                value: epoch_metadata.commitment::<TC>(&nonce),
+            });
         }
+        // The precomputed VRF proofs are written in the transaction, and so are only stored
+        // along with the epoch
+        let mut vrf_proofs = vec![];
+        if self.vrf_proofs == VrfProofPrecomputation::Store {
+            if next_epoch.is_power_of_two() {
+                vrf_proofs.extend(
+                    self.precompute_marker_vrf_proofs(
+                        next_epoch,
+                        &user_data_update_set,
+                        cancellation,
+                    )
+                    .await?,
+                );
+            }
+            vrf_proofs.extend(
+                self.precompute_vrf_proofs(&user_data_update_set, next_epoch, commitment_key)
+                    .await?,
+            );
+        }
+
+        self.begin_write_transaction(lease)?;
         info!("Starting inserting new leaves");
 
-        if let Err(err) = current_azks
-            .batch_insert_nodes::<TC, _>(&self.storage, update_set, InsertMode::Directory)
-            .await
-        {
+        let insert_result = match self.insert_tasks {
+            Some(num_tasks) => {
+                current_azks
+                    .batch_insert_nodes_in_parallel::<TC, _>(
+                        &self.storage,
+                        update_set,
+                        InsertMode::Directory,
+                        num_tasks,
+                    )
+                    .await
+            }
+            None => {
+                current_azks
+                    .batch_insert_nodes::<TC, _>(&self.storage, update_set, InsertMode::Directory)
+                    .await
+            }
+        };
+        if let Err(err) = insert_result {
             // If we fail to do the batch-leaf insert, we should rollback the transaction so we can try again cleanly.
             // Only fails if transaction is not currently active.
-            let _ = self.storage.rollback_transaction();
+            let _ = self.storage.rollback_transaction().await;
             // bubble up the err
             return Err(err);
         }
+        if let Err(err) = cancellation.check("Publish") {
+            let _ = self.storage.rollback_transaction().await;
+            return Err(err);
+        }
+
+        if let Some(signer) = &self.signer {
+            // The root hash is read through the active transaction, so it reflects the new epoch
+            let signature = match current_azks.get_root_hash::<TC, _>(&self.storage).await {
+                Ok(root_hash) => signer
+                    .sign_epoch(next_epoch, root_hash)
+                    .await
+                    .map_err(AkdError::from),
+                Err(err) => Err(err),
+            };
+            match signature {
+                Ok(signature) => epoch_metadata.signature = Some(signature),
+                Err(err) => {
+                    error!("Failed to sign the root hash, rolling back");
+                    let _ = self.storage.rollback_transaction().await;
+                    return Err(err);
+                }
+            }
+        }
 
+        let signature = epoch_metadata.signature.clone();
         // batch all the inserts into a single write to storage (in this case it insert's into the transaction log)
-        let mut updates = vec![DbRecord::Azks(current_azks.clone())];
+        let mut updates = vec![
+            DbRecord::Azks(current_azks.clone()),
+            DbRecord::EpochMetadata(epoch_metadata),
+        ];
+        // The labels to truncate are only recorded with the retention policy once the epoch is committed
+        let labels_over_limit = self
+            .retention
+            .as_ref()
+            .map(|retention| retention.labels_over_limit(&user_data_update_set));
+        // The journal is written in the transaction, and so is only stored along with the epoch
+        if let Some(mode) = self.update_journal {
+            updates.push(DbRecord::UpdateJournal(build_update_journal::<TC>(
+                mode,
+                next_epoch,
+                &user_data_update_set,
+                commitment_key,
+            )));
+        }
         for update in user_data_update_set.into_iter() {
             updates.push(DbRecord::ValueState(update));
         }
-        self.storage.batch_set(updates).await?;
+        updates.extend(vrf_proofs);
+        if let Err(err) = self.storage.batch_set(updates).await {
+            let _ = self.storage.rollback_transaction().await;
+            return Err(err.into());
+        }
+
+        // Make sure no other writer took over the lease while the epoch was being built
+        if let Some(lease) = lease {
+            if let Err(err) = self.check_publish_lease(lease).await {
+                error!("Lost the publish lease, rolling back");
+                let _ = self.storage.rollback_transaction().await;
+                return Err(err);
+            }
+        }
+        // This is the last point at which the publish can be aborted
+        if let Err(err) = cancellation.check("Publish") {
+            let _ = self.storage.rollback_transaction().await;
+            return Err(err);
+        }
+        // The root hash of the epoch is likewise written in the transaction
+        if self.root_history {
+            if let Err(err) = self.record_epoch_root(&current_azks, next_epoch).await {
+                error!("Failed to record the root hash, rolling back");
+                let _ = self.storage.rollback_transaction().await;
+                return Err(err);
+            }
+        }
 
         // Commit the transaction
         info!("Committing transaction");
@@ -236,182 +862,1740 @@ where
             }
             Err(err) => {
                 error!("Failed to commit transaction, rolling back");
-                let _ = self.storage.rollback_transaction();
+                let _ = self.storage.rollback_transaction().await;
                 return Err(AkdError::Storage(err));
             }
         };
+        self.invalidate_proof_cache();
+        if let (Some(retention), Some(labels)) = (&self.retention, labels_over_limit) {
+            retention.record_published(labels);
+        }
 
         let root_hash = current_azks
             .get_root_hash_safe::<TC, _>(&self.storage, next_epoch)
             .await?;
+        self.distribute_epoch(next_epoch, root_hash, signature.as_deref())
+            .await;
 
         Ok(EpochHash(next_epoch, root_hash))
     }
 
-    /// Provides proof for correctness of latest version
+    /// Records the root hash of the epoch being published, which is read through the active
+    /// transaction, along with its chain hash
+    async fn record_epoch_root(&self, azks: &Azks, epoch: u64) -> Result<(), AkdError> {
+        let root_hash = azks.get_root_hash::<TC, _>(&self.storage).await?;
+        let previous_chain_hash = self.previous_chain_hash(epoch).await?;
+        self.storage
+            .set(DbRecord::EpochRootRecord(EpochRootRecord {
+                epoch,
+                root_hash,
+                chain_hash: EpochRootChain::link::<TC>(&previous_chain_hash, epoch, &root_hash),
+            }))
+            .await?;
+        Ok(())
+    }
+
+    /// The chain hash preceding the given epoch in the root history, which is that of the
+    /// previous epoch, or [EMPTY_DIGEST] if the root hash of the previous epoch was not recorded
+    async fn previous_chain_hash(&self, epoch: u64) -> Result<Digest, AkdError> {
+        if epoch == 0 {
+            return Ok(EMPTY_DIGEST);
+        }
+        match self.storage.get::<EpochRootRecord>(&(epoch - 1)).await {
+            Ok(DbRecord::EpochRootRecord(record)) => Ok(record.chain_hash),
+            Ok(_) | Err(StorageError::NotFound(_)) => Ok(EMPTY_DIGEST),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Rotates the VRF key of the directory to `new_vrf`, returning a directory which uses the new
+    /// key along with the root hash of the rotation epoch.
     ///
-    /// * `akd_label`: The target label to generate a lookup proof for
+    /// In the rotation epoch, every version of every label is republished under the new key, and
+    /// the latest version of each label is marked stale under the previous key, so that the previous
+    /// key can no longer be used to serve lookups. Under the new key, the versions which were
+    /// published before the rotation are reported at the rotation epoch, and clients can check that
+    /// the history of a label continues across the rotation with a [KeyRotationProof] (see
+    /// [Directory::key_rotation_proof]).
     ///
-    /// Returns [Ok((LookupProof, EpochHash))] upon successful generation for the latest version
-    /// of the target label's state. [Err(_)] otherwise
-    pub async fn lookup(&self, akd_label: AkdLabel) -> Result<(LookupProof, EpochHash), AkdError> {
-        // The guard will be dropped at the end of the proof generation
-        let _guard = self.cache_lock.read().await;
-
-        let current_azks = self.retrieve_azks().await?;
+    /// The returned directory holds the previous key to produce these proofs, and the
+    /// [VrfKeyRotation] it is configured with must be provided to any other instance which is
+    /// constructed with the new key. This instance must no longer be used to publish.
+    ///
+    /// The labels are enumerated from storage, so this requires a storage layer implementing
+    /// [Database::list_labels]. Tombstoned values are republished as tombstones, and so the
+    /// history of their labels can only be verified with
+    /// [crate::HistoryVerificationParams::AllowMissingValues] under the new key.
+    pub async fn rotate_vrf_key(&self, new_vrf: V) -> Result<(Self, EpochHash), AkdError> {
+        if self.vrf_proofs != VrfProofPrecomputation::Disabled {
+            return Err(AkdError::Directory(DirectoryError::KeyRotation(
+                "Cannot rotate the VRF key of a directory whose VRF proofs are precomputed"
+                    .to_string(),
+            )));
+        }
+        // The guard will be dropped at the end of the rotation
+        let _guard = self.cache_lock.read().await;
+        let timestamp_ms = self.clock.now_ms()?;
+
+        let lease = self.acquire_publish_lease(timestamp_ms).await?;
+        let result = self
+            .rotate_vrf_key_with_lease(&new_vrf, timestamp_ms, lease.as_ref())
+            .await;
+        if let Some(lease) = &lease {
+            if let Err(err) = self.storage.release_publish_lease(lease).await {
+                error!("Failed to release the publish lease: {}", err);
+            }
+        }
+        let epoch_hash = result?;
+
+        let rotated = Directory {
+            vrf: new_vrf,
+            vrf_rotation: Some(VrfKeyRotation {
+                rotation_epoch: epoch_hash.epoch(),
+                previous_vrf: Some(self.vrf.clone()),
+                previous_rotation_epoch: self
+                    .vrf_rotation
+                    .as_ref()
+                    .map_or(0, |rotation| rotation.rotation_epoch),
+            }),
+            ..self.clone()
+        };
+        Ok((rotated, epoch_hash))
+    }
+
+    async fn rotate_vrf_key_with_lease(
+        &self,
+        new_vrf: &V,
+        timestamp_ms: u64,
+        lease: Option<&PublishLease>,
+    ) -> Result<EpochHash, AkdError> {
+        let current_azks = self.retrieve_azks_for_publish(lease).await?;
         let current_epoch = current_azks.get_latest_epoch();
-        let lookup_info = self.get_lookup_info(akd_label, current_epoch).await?;
+        let commitment_key = Self::derive_commitment_key_with(new_vrf).await?;
 
-        let root_hash = EpochHash(
-            current_epoch,
-            current_azks.get_root_hash::<TC, _>(&self.storage).await?,
+        let mut update_set = Vec::<AzksElement>::new();
+        let mut cursor = None;
+        loop {
+            let entries = self
+                .storage
+                .list_labels(cursor.as_ref(), VRF_ROTATION_PAGE_SIZE, current_epoch)
+                .await?;
+
+            let mut new_key_computations = vec![];
+            let mut retirement_computations = vec![];
+            let mut blindings = HashMap::new();
+            for entry in entries.iter() {
+                let mut states = self.storage.get_user_data(&entry.label).await?.states;
+                // Ignore states in storage that are ahead of current directory epoch
+                states.retain(|state| state.epoch <= current_epoch);
+                let latest_version = match states.iter().map(|state| state.version).max() {
+                    Some(version) => version,
+                    None => continue,
+                };
+                for state in states {
+                    if let Some(blinding) = state.blinding {
+                        blindings.insert((state.username.clone(), state.version), blinding);
+                    }
+                    if state.version < latest_version {
+                        new_key_computations.push((
+                            state.username.clone(),
+                            VersionFreshness::Stale,
+                            state.version,
+                            state.value.clone(),
+                        ));
+                    }
+                    new_key_computations.push((
+                        state.username,
+                        VersionFreshness::Fresh,
+                        state.version,
+                        state.value,
+                    ));
+                }
+                retirement_computations.push((
+                    entry.label.clone(),
+                    VersionFreshness::Stale,
+                    latest_version,
+                    AkdValue::Bytes(vec![]),
+                ));
+            }
+
+            for ((akd_label, freshness, version, akd_value), node_label) in self
+                .derive_node_labels(new_vrf, &new_key_computations)
+                .await?
+            {
+                let value = match freshness {
+                    VersionFreshness::Stale => TC::stale_azks_value(),
+                    VersionFreshness::Fresh => compute_azks_value::<TC>(
+                        &*commitment_key,
+                        &node_label,
+                        version,
+                        &akd_value,
+                        blindings.get(&(akd_label, version)).map(Vec::as_slice),
+                    ),
+                };
+                update_set.push(AzksElement {
+                    label: node_label,
+                    value,
+                });
+            }
+            for (_, node_label) in self
+                .derive_node_labels(&self.vrf, &retirement_computations)
+                .await?
+            {
+                update_set.push(AzksElement {
+                    label: node_label,
+                    value: TC::stale_azks_value(),
+                });
+            }
+
+            match entries.last() {
+                Some(entry) if entries.len() == VRF_ROTATION_PAGE_SIZE => {
+                    cursor = Some(entry.label.clone())
+                }
+                _ => break,
+            }
+        }
+
+        if update_set.is_empty() {
+            return Err(AkdError::Directory(DirectoryError::KeyRotation(
+                "Cannot rotate the VRF key of a directory without any labels".to_string(),
+            )));
+        }
+        info!(
+            "Republishing {} leaves for the rotation of the VRF key",
+            update_set.len()
         );
-        let proof = self
-            .lookup_with_info(&current_azks, lookup_info, false)
-            .await?;
-        Ok((proof, root_hash))
+
+        let epoch_metadata = EpochMetadata {
+            epoch: current_epoch + 1,
+            timestamp_ms,
+            update_count: 0,
+            note: None,
+            bound: self.bind_epoch_metadata,
+            signature: None,
+        };
+        self.commit_epoch(
+            current_azks,
+            update_set,
+            vec![],
+            epoch_metadata,
+            &commitment_key,
+            lease,
+            &CancellationToken::new(),
+        )
+        .await
     }
 
-    /// Generate a lookup proof with the provided target information
-    ///
-    /// * `current_azks`: The current [Azks] element
-    /// * `lookup_info`: The information to target in the lookup request. Includes all
-    /// necessary information to build the proof
-    /// * `skip_preload`: Denotes if we should not preload as part of this optimization. Enabled
-    /// from bulk lookup proof generation, as it has its own preloading operation
+    /// Hands a committed epoch to each of the registered [EpochPublisher]s
+    async fn distribute_epoch(&self, epoch: u64, root_hash: Digest, signature: Option<&[u8]>) {
+        for publisher in self.epoch_publishers.iter() {
+            if let Err(err) = publisher.publish_epoch(epoch, root_hash, signature).await {
+                error!(
+                    "Failed to distribute the root hash of epoch {}: {}",
+                    epoch, err
+                );
+            }
+        }
+    }
+
+    /// Imports an initial set of label-value pairs into an empty directory, as its first epoch.
+    /// The result is the same as a [Directory::publish] of all of the entries, but this is meant
+    /// for bootstrapping a directory from an existing dump of a large number of users: the entries
+    /// are consumed from the stream in chunks, and the tree is constructed bottom-up in memory
+    /// (building subtrees in parallel with the `parallel_insert` feature) rather than through the
+    /// incremental insertion path.
     ///
-    /// Returns [Ok(LookupProof)] if the proof generation succeeded, [Err(_)] otherwise
-    async fn lookup_with_info(
+    /// As with [Directory::publish], the entries should not contain any duplicate labels. All of the
+    /// records are written in a single transaction, so a failed import leaves the storage empty and
+    /// can simply be retried. The records are held in memory until the transaction is committed,
+    /// apart from the tree nodes spilled with [StorageManager::with_transaction_memory_cap].
+    pub async fn bulk_import(
         &self,
-        current_azks: &Azks,
-        lookup_info: LookupInfo,
-        skip_preload: bool,
-    ) -> Result<LookupProof, AkdError> {
-        if !skip_preload {
-            // Preload nodes needed for lookup.
-            #[cfg(feature = "greedy_lookup_preload")]
-            {
-                current_azks
-                    .greedy_preload_lookup_nodes(&self.storage, lookup_info.clone())
-                    .await?;
+        entries: impl Stream<Item = (AkdLabel, AkdValue)> + Send,
+    ) -> Result<EpochHash, AkdError> {
+        // Exclude any publishes or proof generations during the import
+        let _guard = self.cache_lock.write().await;
+
+        let timestamp_ms = self.clock.now_ms()?;
+        let lease = self.acquire_publish_lease(timestamp_ms).await?;
+        let result = self
+            .bulk_import_with_lease(entries, timestamp_ms, lease.as_ref())
+            .await;
+        if let Some(lease) = &lease {
+            if let Err(err) = self.storage.release_publish_lease(lease).await {
+                error!("Failed to release the publish lease: {}", err);
             }
-            #[cfg(not(feature = "greedy_lookup_preload"))]
-            {
-                current_azks
-                    .preload_lookup_nodes(&self.storage, &vec![lookup_info.clone()], None)
-                    .await?;
+        }
+        result
+    }
+
+    async fn bulk_import_with_lease(
+        &self,
+        entries: impl Stream<Item = (AkdLabel, AkdValue)> + Send,
+        timestamp_ms: u64,
+        lease: Option<&PublishLease>,
+    ) -> Result<EpochHash, AkdError> {
+        if self.require_blinding {
+            return Err(AkdError::Directory(DirectoryError::Publish(
+                "Bulk import does not support the blindings required by the directory".to_string(),
+            )));
+        }
+        let azks = Directory::<TC, S, V>::get_azks_from_storage(&self.storage, true).await?;
+        if azks.get_latest_epoch() != 0 {
+            return Err(AkdError::Directory(DirectoryError::Publish(format!(
+                "Bulk import requires an empty directory, but the directory is at epoch {}",
+                azks.get_latest_epoch()
+            ))));
+        }
+
+        self.begin_write_transaction(lease)?;
+        let (epoch_hash, signature) = match self
+            .bulk_import_in_transaction(azks, entries, timestamp_ms, lease)
+            .await
+        {
+            Ok(result) => result,
+            Err(err) => {
+                error!("Bulk import failed, rolling back");
+                let _ = self.storage.rollback_transaction().await;
+                return Err(err);
+            }
+        };
+        match self.storage.commit_transaction().await {
+            Ok(num_records) => {
+                info!("Bulk import committed ({} records)", num_records);
+            }
+            Err(err) => {
+                error!("Failed to commit the bulk import, rolling back");
+                let _ = self.storage.rollback_transaction().await;
+                return Err(AkdError::Storage(err));
             }
         }
-        let label = &lookup_info.value_state.username;
-        let current_version = lookup_info.value_state.version;
+        if epoch_hash.epoch() != 0 {
+            self.invalidate_proof_cache();
+            self.distribute_epoch(epoch_hash.epoch(), epoch_hash.hash(), signature.as_deref())
+                .await;
+        }
+        Ok(epoch_hash)
+    }
+
+    /// Writes the records of a bulk import into the active transaction, returning the resulting
+    /// epoch hash along with its signature
+    async fn bulk_import_in_transaction(
+        &self,
+        mut azks: Azks,
+        entries: impl Stream<Item = (AkdLabel, AkdValue)> + Send,
+        timestamp_ms: u64,
+        lease: Option<&PublishLease>,
+    ) -> Result<(EpochHash, Option<Vec<u8>>), AkdError> {
+        let epoch = 1;
         let commitment_key = self.derive_commitment_key().await?;
-        let plaintext_value = lookup_info.value_state.value;
-        let existence_vrf = self
-            .vrf
-            .get_label_proof::<TC>(label, VersionFreshness::Fresh, current_version)
-            .await?;
-        let commitment_label = self.vrf.get_node_label_from_vrf_proof(existence_vrf).await;
-        let lookup_proof = LookupProof {
-            epoch: lookup_info.value_state.epoch,
-            value: plaintext_value.clone(),
-            version: lookup_info.value_state.version,
-            existence_vrf_proof: existence_vrf.to_bytes().to_vec(),
-            existence_proof: current_azks
-                .get_membership_proof::<TC, _>(&self.storage, lookup_info.existent_label)
-                .await?,
-            marker_vrf_proof: self
-                .vrf
-                .get_label_proof::<TC>(label, VersionFreshness::Fresh, lookup_info.marker_version)
-                .await?
-                .to_bytes()
-                .to_vec(),
-            marker_proof: current_azks
-                .get_membership_proof::<TC, _>(&self.storage, lookup_info.marker_label)
-                .await?,
-            freshness_vrf_proof: self
-                .vrf
-                .get_label_proof::<TC>(label, VersionFreshness::Stale, current_version)
+
+        // The entries are consumed in chunks, with their value states going into the transaction
+        let mut update_set = Vec::<AzksElement>::new();
+        let mut journal_entries = Vec::<JournalEntry>::new();
+        let chunks = entries.chunks(BULK_IMPORT_CHUNK_SIZE);
+        futures::pin_mut!(chunks);
+        while let Some(chunk) = chunks.next().await {
+            self.check_label_namespaces(chunk.iter().map(|(label, _)| label))?;
+            self.check_tombstones(chunk.iter())?;
+            let vrf_computations = chunk
+                .into_iter()
+                .map(|(akd_label, akd_value)| (akd_label, VersionFreshness::Fresh, 1u64, akd_value))
+                .collect::<Vec<_>>();
+            let mut value_states = Vec::with_capacity(vrf_computations.len());
+            for ((akd_label, _, version, akd_value), node_label) in self
+                .derive_node_labels(&self.vrf, &vrf_computations)
                 .await?
-                .to_bytes()
-                .to_vec(),
-            freshness_proof: current_azks
-                .get_non_membership_proof::<TC, _>(&self.storage, lookup_info.non_existent_label)
-                .await?,
-            commitment_nonce: TC::get_commitment_nonce(
-                &commitment_key,
-                &commitment_label,
-                lookup_info.value_state.version,
-                &plaintext_value,
-            )
-            .to_vec(),
+            {
+                update_set.push(AzksElement {
+                    label: node_label,
+                    value: compute_azks_value::<TC>(
+                        &*commitment_key,
+                        &node_label,
+                        version,
+                        &akd_value,
+                        None,
+                    ),
+                });
+                value_states.push(ValueState::new(
+                    akd_label, akd_value, version, node_label, epoch, None,
+                ));
+            }
+            if let Some(mode) = self.update_journal {
+                journal_entries.extend(
+                    build_update_journal::<TC>(mode, epoch, &value_states, &*commitment_key)
+                        .entries,
+                );
+            }
+            let vrf_proofs = match self.vrf_proofs {
+                VrfProofPrecomputation::Store => {
+                    self.precompute_vrf_proofs(&value_states, epoch, &commitment_key)
+                        .await?
+                }
+                _ => vec![],
+            };
+            self.storage
+                .batch_set(
+                    value_states
+                        .into_iter()
+                        .map(DbRecord::ValueState)
+                        .chain(vrf_proofs)
+                        .collect(),
+                )
+                .await?;
+        }
+
+        if update_set.is_empty() {
+            info!("There were no entries to import");
+            let root_hash = azks.get_root_hash::<TC, _>(&self.storage).await?;
+            return Ok((EpochHash(0, root_hash), None));
+        }
+        info!("Imported {} value states", update_set.len());
+
+        let mut epoch_metadata = EpochMetadata {
+            epoch,
+            timestamp_ms,
+            update_count: update_set.len() as u64,
+            note: None,
+            bound: self.bind_epoch_metadata,
+            signature: None,
         };
+        if epoch_metadata.bound {
+            let nonce = epoch_metadata.commitment_nonce::<TC>(&*commitment_key);
+            update_set.push(AzksElement {
+                label: EpochMetadata::node_label::<TC>(epoch),
+                value: epoch_metadata.commitment::<TC>(&nonce),
+            });
+        }
 
-        Ok(lookup_proof)
+        let mut nodes = azks
+            .bulk_build::<TC>(update_set, InsertMode::Directory)
+            .await?
+            .into_iter();
+        loop {
+            let chunk = nodes
+                .by_ref()
+                .take(BULK_IMPORT_CHUNK_SIZE)
+                .map(DbRecord::TreeNode)
+                .collect::<Vec<_>>();
+            if chunk.is_empty() {
+                break;
+            }
+            self.storage.batch_set(chunk).await?;
+        }
+
+        let root_hash = azks.get_root_hash::<TC, _>(&self.storage).await?;
+        if let Some(signer) = &self.signer {
+            epoch_metadata.signature = Some(signer.sign_epoch(epoch, root_hash).await?);
+        }
+        if let Some(lease) = lease {
+            self.check_publish_lease(lease).await?;
+        }
+
+        if self.root_history {
+            self.record_epoch_root(&azks, epoch).await?;
+        }
+        let signature = epoch_metadata.signature.clone();
+        let mut records = vec![
+            DbRecord::EpochMetadata(epoch_metadata),
+            DbRecord::Azks(azks),
+        ];
+        if self.update_journal.is_some() {
+            journal_entries.sort_unstable_by(|a, b| a.label.cmp(&b.label));
+            records.push(DbRecord::UpdateJournal(UpdateJournal {
+                epoch,
+                entries: journal_entries,
+            }));
+        }
+        self.storage.batch_set(records).await?;
+        info!("Bulk import completed");
+
+        Ok((EpochHash(epoch, root_hash), signature))
     }
 
-    // TODO(eoz): Call proof generations async
-    /// Allows efficient batch lookups by preloading necessary nodes for the lookups.
-    pub async fn batch_lookup(
+    /// Copies the state of the directory as of the given (past) epoch into `new_storage`, which
+    /// must be empty, returning a directory over the new storage along with its root hash, e.g.
+    /// to replay production-like data in a staging environment. See [Directory::fork_at_with_vrf]
+    /// for the details.
+    pub async fn fork_at<NS: Database + 'static>(
         &self,
-        akd_labels: &[AkdLabel],
-    ) -> Result<(Vec<LookupProof>, EpochHash), AkdError> {
-        // The guard will be dropped at the end of the proof generation
-        let _guard = self.cache_lock.read().await;
+        epoch: u64,
+        new_storage: StorageManager<NS>,
+    ) -> Result<(Directory<TC, NS, V>, EpochHash), AkdError> {
+        self.fork_at_with_vrf(epoch, new_storage, self.vrf.clone())
+            .await
+    }
 
-        let current_azks = self.retrieve_azks().await?;
-        let current_epoch = current_azks.get_latest_epoch();
+    /// Same as [Directory::fork_at], but the labels are derived with the `new_vrf` key in the
+    /// forked directory.
+    ///
+    /// The history of the directory up to the given epoch is replayed into the new storage one
+    /// epoch at a time, with the publication times and notes recorded in the [EpochMetadata] of
+    /// the original epochs (for those which were not pruned by a [RetentionPolicy]). Unless the
+    /// key changes, each epoch of the fork has the same root hash as the original one, except
+    /// where values were tombstoned since (as these are replayed as tombstones).
+    ///
+    /// The forked directory carries over the configuration which determines the contents of the
+    /// tree (the binding of epoch metadata, the decoy leaves, the label namespaces and whether
+    /// blindings are required), but not the signer, publishers, publish lease, admission control
+    /// or retention policy, which can be configured on the returned directory.
+    ///
+    /// The labels are enumerated from storage, so this requires a storage layer implementing
+    /// [Database::list_labels], and every value state up to the given epoch is held in memory
+    /// while the fork is replayed. A directory which went through a rotation of its VRF key
+    /// cannot be forked.
+    pub async fn fork_at_with_vrf<NS: Database + 'static>(
+        &self,
+        epoch: u64,
+        new_storage: StorageManager<NS>,
+        new_vrf: V,
+    ) -> Result<(Directory<TC, NS, V>, EpochHash), AkdError> {
+        let history = self.read_history(Some(epoch)).await?;
+        let mut fork = Directory::<TC, NS, V>::new(new_storage, new_vrf)
+            .await?
+            .with_epoch_metadata_binding(self.bind_epoch_metadata)
+            .with_decoy_leaves(self.decoy_leaves);
+        fork.clock = self.clock.clone();
+        fork.label_namespaces = self.label_namespaces.clone();
+        fork.insert_tasks = self.insert_tasks;
+        fork.vrf_tasks = self.vrf_tasks;
 
-        // Take a union of the labels we will need proofs of for each lookup.
-        let mut lookup_infos = Vec::new();
-        for akd_label in akd_labels {
-            // Save lookup info for later use.
-            let lookup_info = self
-                .get_lookup_info(akd_label.clone(), current_epoch)
-                .await?;
-            lookup_infos.push(lookup_info.clone());
+        let mut epochs = BTreeMap::<u64, Vec<ValueState>>::new();
+        for state in history.labels.into_iter().flatten() {
+            epochs.entry(state.epoch).or_default().push(state);
         }
-
-        // Load nodes needed using the lookup infos.
-        current_azks
-            .preload_lookup_nodes(&self.storage, &lookup_infos, None)
+        let epoch_hash = fork
+            .replay_history(epochs, history.epoch, history.metadata)
             .await?;
+        info!("Forked the directory at epoch {}", epoch);
 
-        // Ensure we have got all lookup infos needed.
-        assert_eq!(akd_labels.len(), lookup_infos.len());
+        fork.require_blinding = self.require_blinding;
+        Ok((fork, epoch_hash))
+    }
 
-        let root_hash = EpochHash(
-            current_epoch,
-            current_azks.get_root_hash::<TC, _>(&self.storage).await?,
-        );
+    /// Reads the history of every label up to the given epoch (or the current epoch if [None]),
+    /// along with the [EpochMetadata] of these epochs (for those which have not been pruned).
+    /// The labels are enumerated from storage, so this requires a storage layer implementing
+    /// [Database::list_labels].
+    pub(crate) async fn read_history(&self, epoch: Option<u64>) -> Result<History, AkdError> {
+        if self.vrf_rotation.is_some() {
+            return Err(AkdError::Directory(DirectoryError::KeyRotation(
+                "Cannot read the history of a directory which went through a rotation of its VRF key"
+                    .to_string(),
+            )));
+        }
+        // The guard will be dropped once the history has been read
+        let _guard = self.cache_lock.read().await;
+        let current_epoch = self.retrieve_azks().await?.get_latest_epoch();
+        let epoch = epoch.unwrap_or(current_epoch);
+        if epoch > current_epoch {
+            return Err(AkdError::Directory(DirectoryError::InvalidEpoch(format!(
+                "Cannot read the history at epoch {epoch}, which is after the current epoch {current_epoch}"
+            ))));
+        }
 
-        let mut lookup_proofs = Vec::new();
-        for info in lookup_infos.into_iter() {
-            lookup_proofs.push(self.lookup_with_info(&current_azks, info, true).await?);
+        let mut labels = Vec::new();
+        let mut cursor = None;
+        loop {
+            let entries = self
+                .storage
+                .list_labels(cursor.as_ref(), HISTORY_PAGE_SIZE, epoch)
+                .await?;
+            for entry in entries.iter() {
+                let mut states = self.storage.get_user_data(&entry.label).await?.states;
+                states.retain(|state| state.epoch <= epoch);
+                states.sort_by_key(|state| state.version);
+                if let Some(state) = states.first().filter(|state| state.version != 1) {
+                    return Err(AkdError::Directory(DirectoryError::InvalidVersion(format!(
+                        "The history of label {:?} was truncated before version {}, and cannot be read",
+                        entry.label, state.version
+                    ))));
+                }
+                labels.push(states);
+            }
+            match entries.last() {
+                Some(entry) if entries.len() == HISTORY_PAGE_SIZE => {
+                    cursor = Some(entry.label.clone())
+                }
+                _ => break,
+            }
         }
 
-        Ok((lookup_proofs, root_hash))
+        let epochs = (1..=epoch).collect::<Vec<_>>();
+        let metadata = self
+            .storage
+            .batch_get::<EpochMetadata>(&epochs)
+            .await?
+            .into_iter()
+            .filter_map(|record| match record {
+                DbRecord::EpochMetadata(metadata) => Some((metadata.epoch, metadata)),
+                _ => None,
+            })
+            .collect();
+        Ok(History {
+            epoch,
+            labels,
+            metadata,
+        })
     }
 
-    async fn build_lookup_info(&self, latest_st: &ValueState) -> Result<LookupInfo, AkdError> {
-        let akd_label = &latest_st.username;
-        // Need to account for the case where the latest state is
-        // added but the database is in the middle of an update
-        let version = latest_st.version;
-        let marker_version = 1 << get_marker_version(version);
-        let existent_label = self
-            .vrf
-            .get_node_label::<TC>(akd_label, VersionFreshness::Fresh, version)
-            .await?;
-        let marker_label = self
-            .vrf
-            .get_node_label::<TC>(akd_label, VersionFreshness::Fresh, marker_version)
+    /// Replays the value states of each epoch up to `last_epoch` into this directory, which must
+    /// be empty, with the publication times, notes and bindings recorded in the given
+    /// [EpochMetadata] (or those of a new epoch for the epochs without metadata). An epoch
+    /// without any value states is replayed as an epoch without updates.
+    pub(crate) async fn replay_history(
+        &self,
+        mut epochs: BTreeMap<u64, Vec<ValueState>>,
+        last_epoch: u64,
+        metadata: HashMap<u64, EpochMetadata>,
+    ) -> Result<EpochHash, AkdError> {
+        let mut epoch_hash = self.get_epoch_hash().await?;
+        if epoch_hash.epoch() != 0 {
+            return Err(AkdError::Directory(DirectoryError::Publish(format!(
+                "Cannot replay a history into a directory which is already at epoch {}",
+                epoch_hash.epoch()
+            ))));
+        }
+
+        for next_epoch in 1..=last_epoch {
+            let states = epochs.remove(&next_epoch).unwrap_or_default();
+            let updates = states
+                .iter()
+                .map(|state| (state.username.clone(), state.value.clone()))
+                .collect::<Vec<_>>();
+            let blindings = states
+                .into_iter()
+                .filter_map(|state| state.blinding.map(|blinding| (state.username, blinding)))
+                .collect::<HashMap<_, _>>();
+            epoch_hash = self
+                .replay_updates(&updates, &blindings, metadata.get(&next_epoch))
+                .await?;
+        }
+        Ok(epoch_hash)
+    }
+
+    /// Publishes the updates of an epoch which is being replayed, with their recorded blindings,
+    /// and the publication time and note of the [EpochMetadata] of the original epoch (if any)
+    async fn replay_updates(
+        &self,
+        updates: &[(AkdLabel, AkdValue)],
+        blindings: &HashMap<AkdLabel, Vec<u8>>,
+        original: Option<&EpochMetadata>,
+    ) -> Result<EpochHash, AkdError> {
+        let current_azks = self.retrieve_azks().await?;
+        let next_epoch = current_azks.get_latest_epoch() + 1;
+        let PreparedUpdates {
+            mut update_set,
+            user_data_update_set,
+            commitment_key,
+            ..
+        } = self
+            .prepare_updates(updates, blindings, current_azks.get_latest_epoch())
             .await?;
-        let non_existent_label = self
-            .vrf
-            .get_node_label::<TC>(akd_label, VersionFreshness::Stale, version)
+        update_set.extend(derive_decoy_leaves::<TC>(
+            &*commitment_key,
+            next_epoch,
+            self.decoy_leaves,
+        ));
+        let epoch_metadata = EpochMetadata {
+            epoch: next_epoch,
+            timestamp_ms: match original {
+                Some(original) => original.timestamp_ms,
+                None => self.clock.now_ms()?,
+            },
+            update_count: user_data_update_set.len() as u64,
+            note: original.and_then(|original| original.note.clone()),
+            bound: original.map_or(self.bind_epoch_metadata, |original| original.bound),
+            signature: None,
+        };
+        self.commit_epoch(
+            current_azks,
+            update_set,
+            user_data_update_set,
+            epoch_metadata,
+            &commitment_key,
+            None,
+            &CancellationToken::new(),
+        )
+        .await
+    }
+
+    /// Computes the tree insertions and value states resulting from applying the updates
+    /// on top of the provided epoch
+    async fn prepare_updates(
+        &self,
+        updates: &[(AkdLabel, AkdValue)],
+        blindings: &HashMap<AkdLabel, Vec<u8>>,
+        current_epoch: u64,
+    ) -> Result<PreparedUpdates, AkdError> {
+        let next_epoch = current_epoch + 1;
+        let mut update_set = Vec::<AzksElement>::new();
+        let mut user_data_update_set = Vec::<ValueState>::new();
+
+        let mut keys: Vec<AkdLabel> = updates
+            .iter()
+            .map(|(akd_label, _val)| akd_label.clone())
+            .collect();
+
+        // sort the keys, as inserting in primary-key order is more efficient for MySQL
+        keys.sort();
+
+        // we're only using the maximum "version" of the user's state at the last epoch
+        // they were seen in the directory. Therefore we've minimized the call to only
+        // return a hashmap of AkdLabel => u64 and not retrieving the other data which is not
+        // read (i.e. the actual _data_ payload).
+        let all_user_versions_retrieved = self
+            .storage
+            .get_user_state_versions(&keys, ValueStateRetrievalFlag::LeqEpoch(current_epoch))
             .await?;
-        Ok(LookupInfo {
-            value_state: latest_st.clone(),
+
+        info!(
+            "Retrieved {} previous user versions of {} requested",
+            all_user_versions_retrieved.len(),
+            keys.len()
+        );
+
+        let mut num_new_labels = 0;
+        let mut num_unchanged_labels = 0;
+        let vrf_computations = updates
+            .iter()
+            .flat_map(
+                |(akd_label, akd_value)| match all_user_versions_retrieved.get(akd_label) {
+                    None => {
+                        num_new_labels += 1;
+                        vec![(
+                            akd_label.clone(),
+                            VersionFreshness::Fresh,
+                            1u64,
+                            akd_value.clone(),
+                        )]
+                    }
+                    Some((latest_version, existing_akd_value)) => {
+                        if existing_akd_value == akd_value {
+                            // Skip this because the user is trying to re-publish the same value
+                            num_unchanged_labels += 1;
+                            return vec![];
+                        }
+                        vec![
+                            (
+                                akd_label.clone(),
+                                VersionFreshness::Stale,
+                                *latest_version,
+                                akd_value.clone(),
+                            ),
+                            (
+                                akd_label.clone(),
+                                VersionFreshness::Fresh,
+                                *latest_version + 1,
+                                akd_value.clone(),
+                            ),
+                        ]
+                    }
+                },
+            )
+            .collect::<Vec<_>>();
+
+        let vrf_map = self
+            .derive_node_labels(&self.vrf, &vrf_computations)
+            .await?
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+
+        let commitment_key = self.derive_commitment_key().await?;
+
+        for ((akd_label, freshness, version, akd_value), node_label) in vrf_map {
+            let blinding = blindings.get(&akd_label).cloned();
+            let azks_value = match freshness {
+                VersionFreshness::Stale => TC::stale_azks_value(),
+                VersionFreshness::Fresh => compute_azks_value::<TC>(
+                    &*commitment_key,
+                    &node_label,
+                    version,
+                    &akd_value,
+                    blinding.as_deref(),
+                ),
+            };
+            update_set.push(AzksElement {
+                label: node_label,
+                value: azks_value,
+            });
+
+            if freshness == VersionFreshness::Fresh {
+                let latest_state = ValueState::new(
+                    akd_label, akd_value, version, node_label, next_epoch, blinding,
+                );
+                user_data_update_set.push(latest_state);
+            }
+        }
+
+        Ok(PreparedUpdates {
+            update_set,
+            user_data_update_set,
+            commitment_key,
+            num_new_labels,
+            num_unchanged_labels,
+        })
+    }
+
+    /// Acquires the publish lease, if configured with [Directory::with_publish_lease]
+    async fn acquire_publish_lease(&self, now_ms: u64) -> Result<Option<PublishLease>, AkdError> {
+        let Some((holder, ttl_ms)) = &self.publish_lease else {
+            return Ok(None);
+        };
+        match self
+            .storage
+            .acquire_publish_lease(holder, now_ms, *ttl_ms)
+            .await?
+        {
+            Some(lease) => {
+                info!(
+                    "Acquired the publish lease with fencing token {}",
+                    lease.fencing_token
+                );
+                Ok(Some(lease))
+            }
+            None => Err(AkdError::Directory(DirectoryError::Publish(
+                "The publish lease is held by another instance".to_string(),
+            ))),
+        }
+    }
+
+    /// Checks that each of the labels belongs to a registered namespace, if there are any
+    fn check_label_namespaces<'a>(
+        &self,
+        mut labels: impl Iterator<Item = &'a AkdLabel>,
+    ) -> Result<(), AkdError> {
+        if self.label_namespaces.is_empty() {
+            return Ok(());
+        }
+        let unregistered = labels.find(|label| {
+            LabelNamespace::split(label)
+                .is_none_or(|(namespace, _)| !self.label_namespaces.contains(&namespace))
+        });
+        match unregistered {
+            Some(label) => Err(AkdError::Directory(DirectoryError::Publish(format!(
+                "Label {label:?} does not belong to any of the registered namespaces"
+            )))),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks that none of the updates is a tombstone, which only ever replaces a published value
+    /// in storage (see [StorageManager::tombstone_value_states])
+    fn check_tombstones<'a>(
+        &self,
+        mut updates: impl Iterator<Item = &'a (AkdLabel, AkdValue)>,
+    ) -> Result<(), AkdError> {
+        match updates.find(|(_, value)| value.is_tombstone()) {
+            Some((label, _)) => Err(AkdError::Directory(DirectoryError::Publish(format!(
+                "Cannot publish a tombstone for label {label:?}"
+            )))),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks that each of the blindings is for one of the labels being updated and within the
+    /// length bounds, and that every label has one if required by [Directory::with_required_blinding]
+    fn check_blindings(
+        &self,
+        labels: &HashSet<AkdLabel>,
+        blindings: &HashMap<AkdLabel, Vec<u8>>,
+    ) -> Result<(), AkdError> {
+        for (label, blinding) in blindings.iter() {
+            if !labels.contains(label) {
+                return Err(AkdError::Directory(DirectoryError::Publish(format!(
+                    "Blinding supplied for label {label:?}, which is not being updated"
+                ))));
+            }
+            if blinding.is_empty() || blinding.len() > ValueState::MAX_BLINDING_LENGTH {
+                return Err(AkdError::Directory(DirectoryError::Publish(format!(
+                    "Blinding of {} bytes for label {label:?} is not between 1 and {} bytes long",
+                    blinding.len(),
+                    ValueState::MAX_BLINDING_LENGTH
+                ))));
+            }
+        }
+        if self.require_blinding {
+            if let Some(label) = labels.iter().find(|label| !blindings.contains_key(*label)) {
+                return Err(AkdError::Directory(DirectoryError::Publish(format!(
+                    "Label {label:?} has no blinding, which the directory requires"
+                ))));
+            }
+        }
+        Ok(())
+    }
+
+    /// Waits for a proof generation slot if admission control is configured. The returned
+    /// permit must be held for the duration of the proof generation.
+    async fn admit_proof_generation(&self) -> Result<Option<OwnedSemaphorePermit>, AkdError> {
+        match &self.admission {
+            Some(admission) => admission.admit().await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Checks the cost of a proof generation against the configured per-call budget
+    fn check_proof_cost(&self, cost: u64) -> Result<(), AkdError> {
+        match &self.admission {
+            Some(admission) => admission.check_cost(cost),
+            None => Ok(()),
+        }
+    }
+
+    /// Begins the transaction of a write to the directory, which is fenced by the publish lease
+    /// it is made under, if any (see [StorageManager::begin_fenced_transaction])
+    fn begin_write_transaction(&self, lease: Option<&PublishLease>) -> Result<(), AkdError> {
+        let started = match lease {
+            Some(lease) => self.storage.begin_fenced_transaction(lease.fencing_token),
+            None => self.storage.begin_transaction(),
+        };
+        if !started {
+            error!("Transaction is already active");
+            return Err(AkdError::Storage(StorageError::Transaction(
+                "Transaction is already active".to_string(),
+            )));
+        }
+        Ok(())
+    }
+
+    /// Renews the provided publish lease, checking that it was not taken over in the
+    /// meantime (i.e. that the fencing token is unchanged)
+    async fn check_publish_lease(&self, lease: &PublishLease) -> Result<(), AkdError> {
+        let ttl_ms = self.publish_lease.as_ref().map_or(0, |(_, ttl_ms)| *ttl_ms);
+        let renewed = self
+            .storage
+            .acquire_publish_lease(&lease.holder, self.clock.now_ms()?, ttl_ms)
+            .await?;
+        match renewed {
+            Some(renewed) if renewed.fencing_token == lease.fencing_token => Ok(()),
+            _ => Err(AkdError::Directory(DirectoryError::Publish(format!(
+                "The publish lease with fencing token {} is no longer held",
+                lease.fencing_token
+            )))),
+        }
+    }
+
+    /// Adds a label-value pair to the queue of pending updates, to be included in the next
+    /// call to [Directory::publish_pending] (e.g. by a [crate::publisher::Publisher]). If an
+    /// update for the same label is already pending, it is replaced by the new value.
+    pub async fn enqueue_update(&self, label: AkdLabel, value: AkdValue) {
+        self.pending_updates.lock().await.insert(label, value);
+        self.pending_notify.notify_one();
+    }
+
+    /// The number of distinct labels with an update pending publication
+    pub async fn pending_update_count(&self) -> usize {
+        self.pending_updates.lock().await.len()
+    }
+
+    /// Publishes all of the currently pending updates as a new epoch, returning [None] if
+    /// there were no pending updates. If the publish fails, the updates are re-queued
+    /// (unless a newer value was enqueued in the meantime for the same label).
+    pub async fn publish_pending(&self) -> Result<Option<EpochHash>, AkdError> {
+        let updates = self
+            .pending_updates
+            .lock()
+            .await
+            .drain()
+            .collect::<Vec<_>>();
+        if updates.is_empty() {
+            return Ok(None);
+        }
+
+        match self.publish(updates.clone()).await {
+            Ok(epoch_hash) => Ok(Some(epoch_hash)),
+            Err(err) => {
+                let mut pending = self.pending_updates.lock().await;
+                for (label, value) in updates.into_iter() {
+                    pending.entry(label).or_insert(value);
+                }
+                Err(err)
+            }
+        }
+    }
+
+    /// Waits until an update is enqueued, or a notification is already stored
+    pub(crate) async fn wait_for_enqueue(&self) {
+        self.pending_notify.notified().await
+    }
+
+    /// Wakes up a task waiting in [Directory::wait_for_enqueue]
+    pub(crate) fn wake_pending_waiter(&self) {
+        self.pending_notify.notify_one()
+    }
+
+    /// Computes the epoch hash and insertion statistics which publishing the updates
+    /// would result in, without writing anything to storage. This can be used to validate
+    /// large batches (e.g. for duplicate labels) prior to calling [Directory::publish].
+    ///
+    /// Note that when [Directory::with_epoch_metadata_binding] is enabled, the root hash
+    /// additionally depends on the time of publication and so will not match that of the
+    /// actual publish.
+    ///
+    /// The insertion is computed in a storage transaction which is rolled back afterwards. Since
+    /// the transaction is shared by the storage manager, the records written by any concurrent
+    /// operation (e.g. [Directory::precompute_lookup_proofs] or another preview) would be
+    /// discarded with it, so the other operations of the directory are excluded for the duration
+    /// of the preview.
+    pub async fn preview_publish(
+        &self,
+        updates: Vec<(AkdLabel, AkdValue)>,
+    ) -> Result<PublishPreview, AkdError> {
+        // The guard will be dropped at the end of the preview
+        let _guard = self.cache_lock.write().await;
+
+        let mut seen = HashSet::new();
+        let mut duplicate_labels = updates
+            .iter()
+            .filter(|(label, _)| !seen.insert(label.clone()))
+            .map(|(label, _)| label.clone())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        duplicate_labels.sort();
+        self.check_label_namespaces(updates.iter().map(|(label, _)| label))?;
+
+        let mut current_azks = self.retrieve_azks().await?;
+        let current_epoch = current_azks.get_latest_epoch();
+        let next_epoch = current_epoch + 1;
+        let PreparedUpdates {
+            mut update_set,
+            user_data_update_set,
+            commitment_key,
+            num_new_labels,
+            num_unchanged_labels,
+        } = self
+            .prepare_updates(&updates, &HashMap::new(), current_epoch)
+            .await?;
+
+        let mut preview = PublishPreview {
+            epoch_hash: None,
+            duplicate_labels,
+            new_labels: num_new_labels,
+            updated_labels: user_data_update_set.len() as u64 - num_new_labels,
+            unchanged_labels: num_unchanged_labels,
+            inserted_leaves: update_set.len() as u64,
+            num_nodes: current_azks.num_nodes,
+        };
+        if !preview.duplicate_labels.is_empty() {
+            return Ok(preview);
+        }
+        if update_set.is_empty() {
+            let root_hash = current_azks.get_root_hash::<TC, _>(&self.storage).await?;
+            preview.epoch_hash = Some(EpochHash(current_epoch, root_hash));
+            return Ok(preview);
+        }
+        update_set.extend(derive_decoy_leaves::<TC>(
+            &*commitment_key,
+            next_epoch,
+            self.decoy_leaves,
+        ));
+        preview.inserted_leaves += self.decoy_leaves as u64;
+
+        if self.bind_epoch_metadata {
+            let epoch_metadata = EpochMetadata {
+                epoch: next_epoch,
+                timestamp_ms: self.clock.now_ms()?,
+                update_count: user_data_update_set.len() as u64,
+                note: None,
+                bound: true,
+                signature: None,
+            };
+            let nonce = epoch_metadata.commitment_nonce::<TC>(&*commitment_key);
+            update_set.push(AzksElement {
+                label: EpochMetadata::node_label::<TC>(next_epoch),
+                value: epoch_metadata.commitment::<TC>(&nonce),
+            });
+            preview.inserted_leaves += 1;
+        }
+
+        // Perform the insertion within a transaction which is always rolled back, so that
+        // nothing is written to storage
+        if !self.storage.begin_transaction() {
+            error!("Transaction is already active");
+            return Err(AkdError::Storage(StorageError::Transaction(
+                "Transaction is already active".to_string(),
+            )));
+        }
+        let result = async {
+            current_azks
+                .batch_insert_nodes::<TC, _>(&self.storage, update_set, InsertMode::Directory)
+                .await?;
+            current_azks.get_root_hash::<TC, _>(&self.storage).await
+        }
+        .await;
+        self.storage.rollback_transaction().await?;
+
+        preview.epoch_hash = Some(EpochHash(next_epoch, result?));
+        preview.num_nodes = current_azks.num_nodes;
+        Ok(preview)
+    }
+
+    /// Rolls the directory back by its latest epoch to the state at the previous epoch, e.g. to
+    /// recover after a publish of corrupted input. The tree nodes modified in the latest epoch are
+    /// restored to their previous state (or removed, if they were added in it), the records of the
+    /// epoch (its value states, [EpochMetadata], update journal, recorded root hash and
+    /// precomputed lookup proofs) are removed, and a [RollbackRecord] is appended to the audit
+    /// trail in storage. Note that any root hashes and proofs handed out for the rolled-back epoch
+    /// will no longer be consistent with the directory.
+    ///
+    /// Since tree nodes only retain their latest and previous states, only the latest epoch can
+    /// be rolled back, and the restored nodes no longer have a previous state. The epoch to which
+    /// the directory was rolled back can therefore not be rolled back itself, and a
+    /// [DirectoryError::InvalidEpoch] is returned until a new epoch is published.
+    ///
+    /// The records of the epoch are removed, and the [RollbackRecord] written, in the transaction
+    /// which restores the tree (made under the publish lease, if any), so that the rollback is
+    /// either committed as a whole or not at all. This requires a storage layer implementing
+    /// [Database::batch_set_and_delete], as well as [Database::list_labels].
+    pub async fn rollback_last_epoch(&self) -> Result<RollbackRecord, AkdError> {
+        // Exclude any other publishes or proof generations during the rollback
+        let _guard = self.cache_lock.write().await;
+        self.storage.flush_cache().await;
+
+        let timestamp_ms = self.clock.now_ms()?;
+        let lease = self.acquire_publish_lease(timestamp_ms).await?;
+        let result = self.rollback_with_lease(timestamp_ms, lease.as_ref()).await;
+        if let Some(lease) = &lease {
+            if let Err(err) = self.storage.release_publish_lease(lease).await {
+                error!("Failed to release the publish lease: {}", err);
+            }
+        }
+        result
+    }
+
+    async fn rollback_with_lease(
+        &self,
+        timestamp_ms: u64,
+        lease: Option<&PublishLease>,
+    ) -> Result<RollbackRecord, AkdError> {
+        let mut azks = Directory::<TC, S, V>::get_azks_from_storage(&self.storage, true).await?;
+        let current_epoch = azks.get_latest_epoch();
+        if current_epoch == 0 {
+            return Err(AkdError::Directory(DirectoryError::InvalidEpoch(
+                "Cannot roll back a directory without any published epoch".to_string(),
+            )));
+        }
+        let epoch = current_epoch - 1;
+        if let Some(rotation) = &self.vrf_rotation {
+            if epoch < rotation.rotation_epoch {
+                return Err(AkdError::Directory(DirectoryError::InvalidEpoch(format!(
+                    "Cannot roll back epoch {current_epoch}, which is the rotation of the VRF key"
+                ))));
+            }
+        }
+        let rollback_records = self.get_rollback_records().await?;
+        if let Some(previous) = rollback_records
+            .last()
+            .filter(|previous| previous.to_epoch >= current_epoch)
+        {
+            return Err(AkdError::Directory(DirectoryError::InvalidEpoch(format!(
+                "Cannot roll back epoch {current_epoch}, to which the directory was rolled back from epoch {}",
+                previous.from_epoch
+            ))));
+        }
+
+        // Every node modified in the latest epoch lies on a path from the root through other
+        // modified nodes, as modifying a node updates the epoch of all of its ancestors
+        let mut restored = Vec::new();
+        let mut removed = Vec::new();
+        let mut to_visit = vec![crate::ROOT_LABEL];
+        while let Some(label) = to_visit.pop() {
+            let node = match self
+                .storage
+                .get_direct::<TreeNodeWithPreviousValue>(&NodeKey(label))
+                .await
+            {
+                Ok(DbRecord::TreeNode(node)) => node,
+                Ok(_) | Err(StorageError::NotFound(_)) => continue,
+                Err(err) => return Err(err.into()),
+            };
+            if node.latest_node.last_epoch <= epoch {
+                continue;
+            }
+            to_visit.extend(node.latest_node.left_child);
+            to_visit.extend(node.latest_node.right_child);
+
+            match node.previous_node {
+                Some(previous) if previous.last_epoch <= epoch => {
+                    restored.push(DbRecord::TreeNode(TreeNodeWithPreviousValue {
+                        label,
+                        latest_node: previous,
+                        previous_node: None,
+                    }))
+                }
+                Some(previous) => {
+                    return Err(AkdError::Directory(DirectoryError::InvalidEpoch(format!(
+                        "Cannot roll back to epoch {epoch}, as the state of node {label:?} at that \
+                         epoch is no longer available (its previous state is from epoch {})",
+                        previous.last_epoch
+                    ))))
+                }
+                // The node did not exist prior to its latest state
+                None => removed.push(NodeKey(label)),
+            }
+        }
+
+        warn!(
+            "Rolling back the directory from epoch {} to epoch {} ({} nodes restored, {} removed)",
+            current_epoch,
+            epoch,
+            restored.len(),
+            removed.len()
+        );
+        let mut record = RollbackRecord {
+            index: rollback_records.len() as u64,
+            timestamp_ms,
+            from_epoch: current_epoch,
+            to_epoch: epoch,
+            restored_nodes: restored.len() as u64,
+            removed_nodes: removed.len() as u64,
+            removed_value_states: 0,
+        };
+        azks.latest_epoch = epoch;
+        azks.num_nodes -= removed.len() as u64;
+
+        self.begin_write_transaction(lease)?;
+        let result = async {
+            // Remove the records of the epoch, which are enumerated through its labels, and the
+            // nodes which were added in it
+            record.removed_value_states = self
+                .remove_label_records_after(epoch, current_epoch)
+                .await?;
+            self.storage
+                .batch_delete::<EpochMetadata>(&[current_epoch])
+                .await?;
+            self.storage
+                .batch_delete::<UpdateJournal>(&[current_epoch])
+                .await?;
+            self.storage
+                .batch_delete::<EpochRootRecord>(&[current_epoch])
+                .await?;
+            self.storage
+                .batch_delete::<TreeNodeWithPreviousValue>(&removed)
+                .await?;
+
+            restored.push(DbRecord::RollbackRecord(record.clone()));
+            restored.push(DbRecord::Azks(azks));
+            self.storage.batch_set(restored).await?;
+            Ok::<(), AkdError>(())
+        }
+        .await;
+        if let Err(err) = result {
+            let _ = self.storage.rollback_transaction().await;
+            return Err(err);
+        }
+        if let Err(err) = self.storage.commit_transaction().await {
+            error!("Failed to commit transaction, rolling back");
+            let _ = self.storage.rollback_transaction().await;
+            return Err(err.into());
+        }
+        self.storage.flush_cache().await;
+        self.invalidate_proof_cache();
+        Ok(record)
+    }
+
+    /// Removes the value states and [PrecomputedLookupProof]s which were stored for the epochs
+    /// after `epoch`, of the labels published up to `latest_epoch`, returning the number of
+    /// removed value states
+    async fn remove_label_records_after(
+        &self,
+        epoch: u64,
+        latest_epoch: u64,
+    ) -> Result<u64, AkdError> {
+        let mut removed_value_states = 0;
+        let mut cursor = None;
+        loop {
+            let entries = self
+                .storage
+                .list_labels(cursor.as_ref(), PRECOMPUTE_PAGE_SIZE, latest_epoch)
+                .await?;
+            let value_states = entries
+                .iter()
+                .filter_map(|entry| {
+                    entry
+                        .last_update_epoch
+                        .filter(|update_epoch| *update_epoch > epoch)
+                        .map(|update_epoch| ValueStateKey(entry.label.to_vec(), update_epoch))
+                })
+                .collect::<Vec<_>>();
+            self.storage
+                .batch_delete::<ValueState>(&value_states)
+                .await?;
+            removed_value_states += value_states.len() as u64;
+
+            let labels = entries
+                .iter()
+                .map(|entry| entry.label.clone())
+                .collect::<Vec<_>>();
+            let stale = self
+                .storage
+                .batch_get::<PrecomputedLookupProof>(&labels)
+                .await?
+                .into_iter()
+                .filter_map(|record| match record {
+                    DbRecord::PrecomputedLookupProof(precomputed) if precomputed.epoch > epoch => {
+                        Some(precomputed.label)
+                    }
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            self.storage
+                .batch_delete::<PrecomputedLookupProof>(&stale)
+                .await?;
+
+            match entries.last() {
+                Some(entry) if entries.len() == PRECOMPUTE_PAGE_SIZE => {
+                    cursor = Some(entry.label.clone())
+                }
+                _ => break,
+            }
+        }
+        Ok(removed_value_states)
+    }
+
+    /// Retrieves the audit trail of [Directory::rollback_last_epoch] operations from storage, in the
+    /// order in which they occurred
+    pub async fn get_rollback_records(&self) -> Result<Vec<RollbackRecord>, AkdError> {
+        let mut records = Vec::new();
+        loop {
+            let index = records.len() as u64;
+            match self.storage.get_direct::<RollbackRecord>(&index).await {
+                Ok(DbRecord::RollbackRecord(record)) => records.push(record),
+                Ok(_) | Err(StorageError::NotFound(_)) => return Ok(records),
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    /// Provides proof for correctness of latest version
+    ///
+    /// * `akd_label`: The target label to generate a lookup proof for
+    ///
+    /// Returns [Ok((LookupProof, EpochHash))] upon successful generation for the latest version
+    /// of the target label's state. [Err(_)] otherwise. With [Directory::with_proof_cache], the
+    /// proof is served from the cache if the label was already looked up in the current epoch.
+    pub async fn lookup(&self, akd_label: AkdLabel) -> Result<(LookupProof, EpochHash), AkdError> {
+        self.check_proof_cost(1)?;
+        let _permit = self.admit_proof_generation().await?;
+        // The guard will be dropped at the end of the proof generation
+        let _guard = self.cache_lock.read().await;
+        let started = Instant::now();
+
+        let current_azks = self.retrieve_azks().await?;
+        let current_epoch = current_azks.get_latest_epoch();
+        if let Some(cached) = self
+            .proof_cache
+            .as_ref()
+            .and_then(|cache| cache.get(&akd_label, current_epoch))
+        {
+            telemetry::record_proof("lookup", started, &cached.0);
+            return Ok(cached);
+        }
+        let lookup_info = self
+            .get_lookup_info(akd_label.clone(), current_epoch)
+            .await?;
+
+        // All of the nodes of the proof are prefetched up front, so that generating the proof
+        // (and the root hash) afterwards does not need any further roundtrips to storage
+        let storage = self.storage.with_operation_cache();
+        let proof = self
+            .lookup_with_info(&storage, &current_azks, lookup_info, false)
+            .await?;
+        let root_hash = EpochHash(
+            current_epoch,
+            current_azks.get_root_hash::<TC, _>(&storage).await?,
+        );
+        telemetry::record_proof("lookup", started, &proof);
+        if let Some(cache) = &self.proof_cache {
+            cache.insert(akd_label, current_epoch, (proof.clone(), root_hash.clone()));
+        }
+        Ok((proof, root_hash))
+    }
+
+    /// Provides proof for correctness of latest version, as with [Directory::lookup], along with
+    /// a signature of the [EpochSigner] configured with [Directory::with_epoch_signer] which
+    /// binds the response to the `challenge` supplied by the client. A client which supplies a
+    /// fresh challenge with each lookup detects a response which is replayed (e.g. by a
+    /// malicious transport), even while the epoch has not advanced. The signature is verified
+    /// with [crate::client::verify_lookup_challenge].
+    pub async fn lookup_with_challenge(
+        &self,
+        akd_label: AkdLabel,
+        challenge: &[u8],
+    ) -> Result<(LookupProof, EpochHash, Vec<u8>), AkdError> {
+        let signer = self.signer.as_ref().ok_or_else(|| {
+            AkdError::Signature(SignatureError::SigningKey(
+                "No epoch signer is configured to sign the challenge".to_string(),
+            ))
+        })?;
+        let (proof, epoch_hash) = self.lookup(akd_label.clone()).await?;
+        let signature = signer
+            .sign_lookup_challenge(challenge, &akd_label, epoch_hash.epoch(), epoch_hash.hash())
+            .await?;
+        Ok((proof, epoch_hash, signature))
+    }
+
+    /// Provides proof that a label has not been registered in the directory, as of the current
+    /// epoch. Fails with [DirectoryError::LabelExists] if any version of the label has been
+    /// published, and with [DirectoryError::InvalidEpoch] if no epoch has been published yet.
+    pub async fn lookup_absent(
+        &self,
+        akd_label: AkdLabel,
+    ) -> Result<(NonMembershipLookupProof, EpochHash), AkdError> {
+        self.check_proof_cost(1)?;
+        let _permit = self.admit_proof_generation().await?;
+        // The guard will be dropped at the end of the proof generation
+        let _guard = self.cache_lock.read().await;
+
+        let current_azks = self.retrieve_azks().await?;
+        let current_epoch = current_azks.get_latest_epoch();
+        if current_epoch == 0 {
+            return Err(AkdError::Directory(DirectoryError::InvalidEpoch(
+                "Cannot prove the absence of a label before the first epoch is published"
+                    .to_string(),
+            )));
+        }
+        if let Ok(state) = self
+            .storage
+            .get_user_state(&akd_label, ValueStateRetrievalFlag::LeqEpoch(current_epoch))
+            .await
+        {
+            return Err(AkdError::Directory(DirectoryError::LabelExists(format!(
+                "Label {akd_label:?} is at version {} as of epoch {current_epoch}",
+                state.version
+            ))));
+        }
+
+        let node_label = self
+            .vrf
+            .get_node_label::<TC>(&akd_label, VersionFreshness::Fresh, 1)
+            .await?;
+        let proof = NonMembershipLookupProof {
+            vrf_proof: self
+                .vrf
+                .get_label_proof::<TC>(&akd_label, VersionFreshness::Fresh, 1)
+                .await?,
+            non_membership_proof: current_azks
+                .get_non_membership_proof::<TC, _>(&self.storage, node_label)
+                .await?,
+        };
+        let root_hash = EpochHash(
+            current_epoch,
+            current_azks.get_root_hash::<TC, _>(&self.storage).await?,
+        );
+        Ok((proof, root_hash))
+    }
+
+    /// Generate a lookup proof with the provided target information
+    ///
+    /// * `storage`: The storage manager to read the nodes of the proof through
+    /// * `current_azks`: The current [Azks] element
+    /// * `lookup_info`: The information to target in the lookup request. Includes all
+    ///   necessary information to build the proof
+    /// * `skip_preload`: Denotes if we should not preload as part of this optimization. Enabled
+    ///   from bulk lookup proof generation, as it has its own preloading operation
+    ///
+    /// Returns [Ok(LookupProof)] if the proof generation succeeded, [Err(_)] otherwise
+    async fn lookup_with_info(
+        &self,
+        storage: &StorageManager<S>,
+        current_azks: &Azks,
+        lookup_info: LookupInfo,
+        skip_preload: bool,
+    ) -> Result<LookupProof, AkdError> {
+        if !skip_preload {
+            // Preload nodes needed for lookup.
+            #[cfg(feature = "greedy_lookup_preload")]
+            {
+                current_azks
+                    .greedy_preload_lookup_nodes(storage, lookup_info.clone())
+                    .await?;
+            }
+            #[cfg(not(feature = "greedy_lookup_preload"))]
+            {
+                current_azks
+                    .preload_lookup_nodes(storage, &vec![lookup_info.clone()], None)
+                    .await?;
+            }
+        }
+        let label = &lookup_info.value_state.username;
+        let current_version = lookup_info.value_state.version;
+        if lookup_info.value_state.value.is_tombstone() {
+            // The value of a lookup proof is always verified, and so cannot be missing
+            return Err(AkdError::Directory(DirectoryError::Tombstoned(format!(
+                "The value of version {current_version} of label {label:?} was removed from storage"
+            ))));
+        }
+        let commitment_key = self.proof_commitment_key().await?;
+        let existence_vrf = self
+            .label_proof(&self.vrf, label, VersionFreshness::Fresh, current_version)
+            .await?;
+        let commitment_label = self
+            .vrf
+            .get_node_label_from_vrf_proof::<TC>(&existence_vrf)
+            .await?;
+        let commitment_nonce = self
+            .commitment_nonce(
+                commitment_key.as_deref(),
+                label,
+                &commitment_label,
+                &lookup_info.value_state,
+            )
+            .await?;
+        let lookup_proof = LookupProof {
+            epoch: self.leaf_epoch(lookup_info.value_state.epoch),
+            value: pad_value::<TC>(&lookup_info.value_state.value).into_owned(),
+            version: lookup_info.value_state.version,
+            existence_vrf_proof: existence_vrf,
+            existence_proof: current_azks
+                .get_membership_proof::<TC, _>(storage, lookup_info.existent_label)
+                .await?,
+            marker_vrf_proof: self
+                .label_proof(
+                    &self.vrf,
+                    label,
+                    VersionFreshness::Fresh,
+                    lookup_info.marker_version,
+                )
+                .await?,
+            marker_proof: current_azks
+                .get_membership_proof::<TC, _>(storage, lookup_info.marker_label)
+                .await?,
+            freshness_vrf_proof: self
+                .label_proof(&self.vrf, label, VersionFreshness::Stale, current_version)
+                .await?,
+            freshness_proof: current_azks
+                .get_non_membership_proof::<TC, _>(storage, lookup_info.non_existent_label)
+                .await?,
+            commitment_nonce,
+            vrf_rotation: self.vrf_rotation_certificate().await?,
+        };
+
+        Ok(lookup_proof)
+    }
+
+    /// Produces the [VrfRotationCertificate] of the rotation of the VRF key which this directory
+    /// went through (see [Directory::rotate_vrf_key]), which cross-certifies its previous and
+    /// current VRF public keys. This is only possible during the transition window of the
+    /// rotation, while the directory holds the previous key (see [VrfKeyRotation::previous_vrf]),
+    /// and [None] is returned otherwise.
+    ///
+    /// The certificate is attached to the lookup proofs generated during the transition window,
+    /// so that clients which only know the previous key can verify them.
+    pub async fn vrf_rotation_certificate(
+        &self,
+    ) -> Result<Option<VrfRotationCertificate>, AkdError> {
+        let Some((rotation_epoch, previous_vrf)) =
+            self.vrf_rotation.as_ref().and_then(|rotation| {
+                rotation
+                    .previous_vrf
+                    .as_ref()
+                    .map(|previous_vrf| (rotation.rotation_epoch, previous_vrf))
+            })
+        else {
+            return Ok(None);
+        };
+        let previous_vrf_public_key = previous_vrf.get_vrf_public_key_bytes::<TC>().await?;
+        let new_vrf_public_key = self.vrf.get_vrf_public_key_bytes::<TC>().await?;
+        let message = VrfRotationCertificate::message(
+            rotation_epoch,
+            &previous_vrf_public_key,
+            &new_vrf_public_key,
+        );
+        Ok(Some(VrfRotationCertificate {
+            rotation_epoch,
+            previous_key_proof: previous_vrf.get_proof::<TC>(&message).await?,
+            new_key_proof: self.vrf.get_proof::<TC>(&message).await?,
+            previous_vrf_public_key,
+            new_vrf_public_key,
+        }))
+    }
+
+    // TODO(eoz): Call proof generations async
+    /// Allows efficient batch lookups by preloading necessary nodes for the lookups.
+    pub async fn batch_lookup(
+        &self,
+        akd_labels: &[AkdLabel],
+    ) -> Result<(Vec<LookupProof>, EpochHash), AkdError> {
+        self.check_proof_cost(akd_labels.len() as u64)?;
+        let _permit = self.admit_proof_generation().await?;
+        // The guard will be dropped at the end of the proof generation
+        let _guard = self.cache_lock.read().await;
+
+        let current_azks = self.retrieve_azks().await?;
+        let current_epoch = current_azks.get_latest_epoch();
+
+        // Take a union of the labels we will need proofs of for each lookup.
+        let mut lookup_infos = Vec::new();
+        for akd_label in akd_labels {
+            // Save lookup info for later use.
+            let lookup_info = self
+                .get_lookup_info(akd_label.clone(), current_epoch)
+                .await?;
+            lookup_infos.push(lookup_info.clone());
+        }
+
+        // Load nodes needed using the lookup infos.
+        let storage = self.storage.with_operation_cache();
+        current_azks
+            .preload_lookup_nodes(&storage, &lookup_infos, None)
+            .await?;
+
+        // Ensure we have got all lookup infos needed.
+        assert_eq!(akd_labels.len(), lookup_infos.len());
+
+        let root_hash = EpochHash(
+            current_epoch,
+            current_azks.get_root_hash::<TC, _>(&storage).await?,
+        );
+
+        let mut lookup_proofs = Vec::new();
+        for info in lookup_infos.into_iter() {
+            lookup_proofs.push(
+                self.lookup_with_info(&storage, &current_azks, info, true)
+                    .await?,
+            );
+        }
+
+        Ok((lookup_proofs, root_hash))
+    }
+
+    /// Generates the lookup proof of every label of the directory as of the current epoch, and
+    /// stores them as [DbRecord::PrecomputedLookupProof]s, returning the number of stored
+    /// proofs. As each epoch changes the root hash of the tree, the proofs of all of the labels
+    /// (and not only those of the updated labels) are regenerated, and the labels whose latest
+    /// value is a tombstone are skipped.
+    ///
+    /// The labels are enumerated from storage, so this requires a storage layer implementing
+    /// [Database::list_labels].
+    pub async fn precompute_lookup_proofs(&self) -> Result<u64, AkdError> {
+        // The guard will be dropped once the proofs have been stored
+        let _guard = self.cache_lock.read().await;
+        let started = Instant::now();
+        let current_azks = self.retrieve_azks().await?;
+        let current_epoch = current_azks.get_latest_epoch();
+
+        let mut num_proofs = 0;
+        let mut cursor = None;
+        loop {
+            let entries = self
+                .storage
+                .list_labels(cursor.as_ref(), PRECOMPUTE_PAGE_SIZE, current_epoch)
+                .await?;
+            let mut lookup_infos = Vec::with_capacity(entries.len());
+            for entry in entries.iter() {
+                let lookup_info = self
+                    .get_lookup_info(entry.label.clone(), current_epoch)
+                    .await?;
+                if !lookup_info.value_state.value.is_tombstone() {
+                    lookup_infos.push(lookup_info);
+                }
+            }
+
+            let storage = self.storage.with_operation_cache();
+            current_azks
+                .preload_lookup_nodes(&storage, &lookup_infos, None)
+                .await?;
+            let root_hash = current_azks.get_root_hash::<TC, _>(&storage).await?;
+            let mut proofs = Vec::with_capacity(lookup_infos.len());
+            for info in lookup_infos {
+                let label = info.value_state.username.clone();
+                let proof = self
+                    .lookup_with_info(&storage, &current_azks, info, true)
+                    .await?;
+                proofs.push(DbRecord::PrecomputedLookupProof(PrecomputedLookupProof {
+                    label,
+                    epoch: current_epoch,
+                    root_hash,
+                    proof,
+                }));
+            }
+            num_proofs += proofs.len() as u64;
+            self.storage.batch_set(proofs).await?;
+
+            match entries.last() {
+                Some(entry) if entries.len() == PRECOMPUTE_PAGE_SIZE => {
+                    cursor = Some(entry.label.clone())
+                }
+                _ => break,
+            }
+        }
+        info!(
+            "Precomputed {} lookup proofs for epoch {} in {} ms",
+            num_proofs,
+            current_epoch,
+            started.elapsed().as_millis()
+        );
+        Ok(num_proofs)
+    }
+
+    /// Serves the lookup proof of a label which was stored by
+    /// [Directory::precompute_lookup_proofs], if it was precomputed for the current epoch and
+    /// root hash. Otherwise (e.g. for a label without any precomputed proof, or after a publish
+    /// without precomputation), the proof is generated with [Directory::lookup].
+    ///
+    /// A proof precomputed before a value was tombstoned directly in storage keeps being served
+    /// until the next precomputation.
+    pub async fn precomputed_lookup(
+        &self,
+        akd_label: AkdLabel,
+    ) -> Result<(LookupProof, EpochHash), AkdError> {
+        let precomputed = {
+            // The guard is dropped before falling back to generating the proof
+            let _guard = self.cache_lock.read().await;
+            let started = Instant::now();
+            let current_azks = self.retrieve_azks().await?;
+            let current_epoch = current_azks.get_latest_epoch();
+            let precomputed = match self
+                .storage
+                .get_direct::<PrecomputedLookupProof>(&akd_label)
+                .await
+            {
+                Ok(DbRecord::PrecomputedLookupProof(precomputed)) => Some(precomputed),
+                Ok(_) | Err(StorageError::NotFound(_)) => None,
+                Err(err) => return Err(err.into()),
+            };
+            match precomputed {
+                Some(precomputed) if precomputed.epoch == current_epoch => {
+                    // The root hash tells apart the epochs published again after a rollback
+                    let root_hash = current_azks.get_root_hash::<TC, _>(&self.storage).await?;
+                    (precomputed.root_hash == root_hash).then(|| {
+                        telemetry::record_proof("lookup", started, &precomputed.proof);
+                        (precomputed.proof, EpochHash(current_epoch, root_hash))
+                    })
+                }
+                _ => None,
+            }
+        };
+        match precomputed {
+            Some(precomputed) => Ok(precomputed),
+            None => self.lookup(akd_label).await,
+        }
+    }
+
+    async fn build_lookup_info(&self, latest_st: &ValueState) -> Result<LookupInfo, AkdError> {
+        let akd_label = &latest_st.username;
+        // Need to account for the case where the latest state is
+        // added but the database is in the middle of an update
+        let version = latest_st.version;
+        let marker_version = 1 << get_marker_version(version);
+        let existent_label = self
+            .node_label(&self.vrf, akd_label, VersionFreshness::Fresh, version)
+            .await?;
+        let marker_label = self
+            .node_label(
+                &self.vrf,
+                akd_label,
+                VersionFreshness::Fresh,
+                marker_version,
+            )
+            .await?;
+        let non_existent_label = self
+            .node_label(&self.vrf, akd_label, VersionFreshness::Stale, version)
+            .await?;
+        Ok(LookupInfo {
+            value_state: latest_st.clone(),
             marker_version,
             existent_label,
             marker_label,
@@ -419,375 +2603,1616 @@ where
         })
     }
 
-    async fn get_lookup_info(
+    async fn get_lookup_info(
+        &self,
+        akd_label: AkdLabel,
+        epoch: u64,
+    ) -> Result<LookupInfo, AkdError> {
+        match self
+            .storage
+            .get_user_state(&akd_label, ValueStateRetrievalFlag::LeqEpoch(epoch))
+            .await
+        {
+            Err(_) => {
+                // Need to throw an error
+                match std::str::from_utf8(&akd_label) {
+                    Ok(name) => Err(AkdError::Storage(StorageError::NotFound(format!(
+                        "User {name} at epoch {epoch}"
+                    )))),
+                    _ => Err(AkdError::Storage(StorageError::NotFound(format!(
+                        "User {akd_label:?} at epoch {epoch}"
+                    )))),
+                }
+            }
+            Ok(latest_st) => self.build_lookup_info(&latest_st).await,
+        }
+    }
+
+    /// Takes in the current state of the server and a label.
+    /// If the label is present in the current state,
+    /// this function returns all the values ever associated with it,
+    /// and the epoch at which each value was first committed to the server state.
+    /// It also returns the proof of the latest version being served at all times.
+    pub async fn key_history(
+        &self,
+        akd_label: &AkdLabel,
+        params: HistoryParams,
+    ) -> Result<(HistoryProof, EpochHash), AkdError> {
+        self.key_history_cancellable(akd_label, params, &CancellationToken::new())
+            .await
+    }
+
+    /// The [HistoryParams] with which the history proofs generated by [Directory::key_history]
+    /// for the given parameters are verified. These are the given parameters, unless the
+    /// [RetentionPolicy] of the directory bounds the number of versions retained per label
+    /// (see [RetentionPolicy::with_max_versions_per_label]).
+    pub fn history_params(&self, params: HistoryParams) -> HistoryParams {
+        match &self.retention {
+            Some(retention) => retention.policy().history_params(params),
+            None => params,
+        }
+    }
+
+    /// Same as [Directory::key_history], but aborts with [DirectoryError::Cancelled] if the
+    /// cancellation token is cancelled while the proof is being generated.
+    pub async fn key_history_cancellable(
+        &self,
+        akd_label: &AkdLabel,
+        params: HistoryParams,
+        cancellation: &CancellationToken,
+    ) -> Result<(HistoryProof, EpochHash), AkdError> {
+        let _permit = self.admit_proof_generation().await?;
+        // The guard will be dropped at the end of the proof generation
+        let _guard = self.cache_lock.read().await;
+        let started = Instant::now();
+
+        let current_azks = self.retrieve_azks().await?;
+        let current_epoch = current_azks.get_latest_epoch();
+        let mut user_data = self.storage.get_user_data(akd_label).await?.states;
+
+        // reverse sort from highest epoch to lowest
+        user_data.sort_by_key(|a| std::cmp::Reverse(a.epoch));
+
+        // apply filters specified by HistoryParams struct, within the versions retained by the
+        // directory (whose older value states may not have been pruned yet)
+        user_data = match self.history_params(params) {
+            HistoryParams::Complete => user_data,
+            HistoryParams::MostRecent(n) => user_data.into_iter().take(n).collect::<Vec<_>>(),
+        };
+
+        if user_data.is_empty() {
+            let msg = if let Ok(username_str) = std::str::from_utf8(akd_label) {
+                format!("User {username_str}")
+            } else {
+                format!("User {akd_label:?}")
+            };
+            return Err(AkdError::Storage(StorageError::NotFound(msg)));
+        }
+
+        let mut start_version = user_data[0].version;
+        let mut end_version = 0;
+        for user_state in &user_data {
+            // Ignore states in storage that are ahead of current directory epoch
+            if user_state.epoch <= current_epoch {
+                start_version = std::cmp::min(user_state.version, start_version);
+                end_version = std::cmp::max(user_state.version, end_version);
+            }
+        }
+
+        if start_version == 0 {
+            return Err(AkdError::Directory(DirectoryError::InvalidVersion(
+                "Computed start version for the key history should be non-zero".to_string(),
+            )));
+        }
+
+        let (past_marker_versions, future_marker_versions) =
+            get_marker_versions(start_version, end_version, current_epoch);
+        self.check_proof_cost(
+            (user_data.len() + past_marker_versions.len() + future_marker_versions.len()) as u64,
+        )?;
+
+        #[cfg(feature = "preload_history")]
+        {
+            let mut lookup_infos = vec![];
+            for ud in user_data.iter() {
+                if let Ok(lo) = self.build_lookup_info(ud).await {
+                    lookup_infos.push(lo);
+                }
+            }
+
+            let mut marker_labels = vec![];
+            for version in past_marker_versions
+                .iter()
+                .chain(future_marker_versions.iter())
+            {
+                let node_label = self
+                    .node_label(&self.vrf, akd_label, VersionFreshness::Fresh, *version)
+                    .await?;
+                marker_labels.push(node_label);
+            }
+
+            current_azks
+                .preload_lookup_nodes(&self.storage, &lookup_infos, Some(marker_labels))
+                .await?;
+        }
+
+        // The creation of update proofs should happen only after the preload operation (to prevent cache misses).
+        let mut update_proofs = Vec::<UpdateProof>::new();
+        for user_state in &user_data {
+            // Ignore states in storage that are ahead of current directory epoch
+            if user_state.epoch <= current_epoch {
+                cancellation.check("Key history")?;
+                let proof = self
+                    .create_single_update_proof(&current_azks, akd_label, user_state)
+                    .await?;
+                update_proofs.push(proof);
+            }
+        }
+
+        let mut past_marker_vrf_proofs = vec![];
+        let mut existence_of_past_marker_proofs = vec![];
+
+        for version in past_marker_versions {
+            cancellation.check("Key history")?;
+            let existence_vrf = self
+                .label_proof(&self.vrf, akd_label, VersionFreshness::Fresh, version)
+                .await?;
+            let node_label = self
+                .vrf
+                .get_node_label_from_vrf_proof::<TC>(&existence_vrf)
+                .await?;
+            past_marker_vrf_proofs.push(existence_vrf);
+            existence_of_past_marker_proofs.push(
+                current_azks
+                    .get_membership_proof::<TC, _>(&self.storage, node_label)
+                    .await?,
+            );
+        }
+
+        let mut future_marker_vrf_proofs = vec![];
+        let mut non_existence_of_future_marker_proofs = vec![];
+
+        for version in future_marker_versions {
+            cancellation.check("Key history")?;
+            let non_existence_vrf = self
+                .label_proof(&self.vrf, akd_label, VersionFreshness::Fresh, version)
+                .await?;
+            let node_label = self
+                .vrf
+                .get_node_label_from_vrf_proof::<TC>(&non_existence_vrf)
+                .await?;
+            non_existence_of_future_marker_proofs.push(
+                current_azks
+                    .get_non_membership_proof::<TC, _>(&self.storage, node_label)
+                    .await?,
+            );
+            future_marker_vrf_proofs.push(non_existence_vrf);
+        }
+
+        let root_hash = EpochHash(
+            current_epoch,
+            current_azks.get_root_hash::<TC, _>(&self.storage).await?,
+        );
+
+        let proof = HistoryProof {
+            update_proofs,
+            past_marker_vrf_proofs,
+            existence_of_past_marker_proofs,
+            future_marker_vrf_proofs,
+            non_existence_of_future_marker_proofs,
+        };
+        telemetry::record_proof("key_history", started, &proof);
+        Ok((proof, root_hash))
+    }
+
+    /// Generates the largest history proof of a label whose size (see [SizeOf]) fits within
+    /// `max_bytes`, so that clients on constrained links are not sent arbitrarily large proofs.
+    /// This is the complete history if it fits, and otherwise the history of the most recent
+    /// updates, whose number is found by a binary search. The [HistoryParams] with which the
+    /// proof was generated are returned along with it, as the client needs them for verification.
+    ///
+    /// Fails with [DirectoryError::ProofTooLarge] if even the history of the latest update
+    /// exceeds the budget. As each step of the search generates a proof, this costs up to a
+    /// logarithmic number (in the number of versions of the label) of [Directory::key_history].
+    pub async fn key_history_bounded(
+        &self,
+        akd_label: &AkdLabel,
+        max_bytes: usize,
+    ) -> Result<(HistoryProof, EpochHash, HistoryParams), AkdError> {
+        let (proof, root_hash) = self.key_history(akd_label, HistoryParams::Complete).await?;
+        if proof.size_of() <= max_bytes {
+            return Ok((
+                proof,
+                root_hash,
+                self.history_params(HistoryParams::Complete),
+            ));
+        }
+
+        // The proof of the `fits` most recent updates fits within the budget (if non-zero),
+        // while the proof of the `exceeds` most recent updates does not
+        let (mut fits, mut exceeds) = (0, proof.update_proofs.len());
+        let mut bounded = None;
+        while exceeds - fits > 1 {
+            let n = fits + (exceeds - fits) / 2;
+            let params = HistoryParams::MostRecent(n);
+            let (proof, root_hash) = self.key_history(akd_label, params).await?;
+            if proof.size_of() <= max_bytes {
+                fits = n;
+                bounded = Some((proof, root_hash, params));
+            } else {
+                exceeds = n;
+            }
+        }
+        bounded.ok_or_else(|| {
+            AkdError::Directory(DirectoryError::ProofTooLarge(format!(
+                "The history proof of the latest update of label {akd_label:?} exceeds {max_bytes} bytes"
+            )))
+        })
+    }
+
+    /// Generates a history proof of the updates of a label since the given version, which a
+    /// client previously verified to have been published at the given epoch, so that the client
+    /// does not have to download and verify its whole history again. The proof includes the
+    /// previously verified version, and is verified against its [crate::VerifyResult] with
+    /// [crate::client::key_history_since_verify].
+    ///
+    /// Fails with [DirectoryError::InvalidVersion] if the label has no such version at that
+    /// epoch (or the epoch follows the current one), or if its value state is no longer
+    /// retained (see [RetentionPolicy]).
+    pub async fn key_history_since(
+        &self,
+        akd_label: &AkdLabel,
+        verified_version: u64,
+        verified_epoch: u64,
+    ) -> Result<(HistoryProof, EpochHash), AkdError> {
+        let current_epoch = self.retrieve_azks().await?.get_latest_epoch();
+        // A value state may already be stored for an epoch which is not yet published
+        if verified_epoch > current_epoch {
+            return Err(AkdError::Directory(DirectoryError::InvalidVersion(format!(
+                "Version {verified_version} of label {akd_label:?} was verified at epoch {verified_epoch}, which follows the current epoch {current_epoch}"
+            ))));
+        }
+        let states = self.storage.get_user_data(akd_label).await?.states;
+        if !states
+            .iter()
+            .any(|state| state.version == verified_version && state.epoch == verified_epoch)
+        {
+            return Err(AkdError::Directory(DirectoryError::InvalidVersion(format!(
+                "Label {akd_label:?} has no retained version {verified_version} at epoch {verified_epoch}"
+            ))));
+        }
+        let latest_version = states
+            .iter()
+            .filter(|state| state.epoch <= current_epoch)
+            .map(|state| state.version)
+            .max()
+            .unwrap_or(verified_version);
+
+        let params = HistoryParams::MostRecent((latest_version - verified_version + 1) as usize);
+        let (proof, root_hash) = self.key_history(akd_label, params).await?;
+        // The history is capped by the retention policy, or the label was updated meanwhile
+        if proof
+            .update_proofs
+            .last()
+            .is_none_or(|update_proof| update_proof.version != verified_version)
+        {
+            return Err(AkdError::Directory(DirectoryError::InvalidVersion(format!(
+                "The history of label {akd_label:?} since version {verified_version} could not be generated, as it is not retained or was updated concurrently"
+            ))));
+        }
+        Ok((proof, root_hash))
+    }
+
+    /// Generates a [KeyRotationProof] showing that the latest version of a label under the
+    /// previous VRF key of the directory was carried over to its current key, when the key was
+    /// rotated with [Directory::rotate_vrf_key]. This requires the directory to hold the previous
+    /// key (see [VrfKeyRotation::previous_vrf]), and the label to have been published before
+    /// the rotation.
+    pub async fn key_rotation_proof(
+        &self,
+        akd_label: &AkdLabel,
+    ) -> Result<(KeyRotationProof, EpochHash), AkdError> {
+        let _permit = self.admit_proof_generation().await?;
+        // The guard will be dropped at the end of the proof generation
+        let _guard = self.cache_lock.read().await;
+
+        let rotation = self.vrf_rotation.as_ref().ok_or_else(|| {
+            AkdError::Directory(DirectoryError::KeyRotation(
+                "The VRF key of the directory has not been rotated".to_string(),
+            ))
+        })?;
+        let previous_vrf = rotation.previous_vrf.as_ref().ok_or_else(|| {
+            AkdError::Directory(DirectoryError::KeyRotation(
+                "The previous VRF key is not held by the directory".to_string(),
+            ))
+        })?;
+
+        let current_azks = self.retrieve_azks().await?;
+        let current_epoch = current_azks.get_latest_epoch();
+        let latest_state = self
+            .storage
+            .get_user_data(akd_label)
+            .await?
+            .states
+            .into_iter()
+            .filter(|state| state.epoch < rotation.rotation_epoch)
+            .max_by_key(|state| state.version)
+            .ok_or_else(|| {
+                AkdError::Storage(StorageError::NotFound(format!(
+                    "User {akd_label:?} before the rotation epoch {}",
+                    rotation.rotation_epoch
+                )))
+            })?;
+
+        let previous_commitment_key = Self::derive_commitment_key_with(previous_vrf).await?;
+        let previous_key_update_proof = self
+            .create_update_proof_with_key(
+                &current_azks,
+                previous_vrf,
+                Some(&previous_commitment_key),
+                latest_state.epoch.max(rotation.previous_rotation_epoch),
+                akd_label,
+                &latest_state,
+            )
+            .await?;
+        let retirement_label = previous_vrf
+            .get_node_label::<TC>(akd_label, VersionFreshness::Stale, latest_state.version)
+            .await?;
+        let retirement_vrf_proof = previous_vrf
+            .get_label_proof::<TC>(akd_label, VersionFreshness::Stale, latest_state.version)
+            .await?;
+        let retirement_proof = current_azks
+            .get_membership_proof::<TC, _>(&self.storage, retirement_label)
+            .await?;
+        let new_key_update_proof = self
+            .create_single_update_proof(&current_azks, akd_label, &latest_state)
+            .await?;
+
+        let root_hash = EpochHash(
+            current_epoch,
+            current_azks.get_root_hash::<TC, _>(&self.storage).await?,
+        );
+        Ok((
+            KeyRotationProof {
+                rotation_epoch: rotation.rotation_epoch,
+                previous_key_update_proof,
+                retirement_vrf_proof,
+                retirement_proof,
+                new_key_update_proof,
+            },
+            root_hash,
+        ))
+    }
+
+    /// Generates a [MergeBridgeProof] showing that the latest version of a label in the
+    /// `original` directory was carried over to this directory, when the original directory was
+    /// merged into it with [crate::merge::merge]. This fails if the history of the label in this
+    /// directory was replayed from the other original directory, as the label was in conflict.
+    ///
+    /// Returns the proof along with the epoch and root hash of the original directory, and then
+    /// those of this directory, against which the proof is verified.
+    pub async fn merge_bridge_proof<OS: Database + 'static, OV: VRFKeyStorage>(
+        &self,
+        original: &Directory<TC, OS, OV>,
+        akd_label: &AkdLabel,
+    ) -> Result<(MergeBridgeProof, EpochHash, EpochHash), AkdError> {
+        let (original_lookup_proof, original_root_hash) =
+            original.lookup(akd_label.clone()).await?;
+
+        let _permit = self.admit_proof_generation().await?;
+        // The guard will be dropped at the end of the proof generation
+        let _guard = self.cache_lock.read().await;
+        let current_azks = self.retrieve_azks().await?;
+        let current_epoch = current_azks.get_latest_epoch();
+        let state = self
+            .storage
+            .get_user_data(akd_label)
+            .await?
+            .states
+            .into_iter()
+            .find(|state| {
+                (state.epoch, state.version)
+                    == (original_lookup_proof.epoch, original_lookup_proof.version)
+            })
+            .ok_or_else(|| {
+                AkdError::Storage(StorageError::NotFound(format!(
+                    "User {akd_label:?} at version {} and epoch {}",
+                    original_lookup_proof.version, original_lookup_proof.epoch
+                )))
+            })?;
+        let merged_update_proof = self
+            .create_single_update_proof(&current_azks, akd_label, &state)
+            .await?;
+
+        let root_hash = EpochHash(
+            current_epoch,
+            current_azks.get_root_hash::<TC, _>(&self.storage).await?,
+        );
+        Ok((
+            MergeBridgeProof {
+                original_lookup_proof,
+                merged_update_proof,
+            },
+            original_root_hash,
+            root_hash,
+        ))
+    }
+
+    /// Poll for changes in the epoch number of the AZKS struct
+    /// stored in the storage layer. If an epoch change is detected,
+    /// the object cache (if present) is flushed immediately so
+    /// that new objects are retrieved from the storage layer against
+    /// the "latest" epoch. There is a "special" flow in the storage layer
+    /// to do a storage-layer retrieval which ignores the cache
+    pub async fn poll_for_azks_changes(
+        &self,
+        period: Duration,
+        change_detected: Option<tokio::sync::mpsc::Sender<()>>,
+    ) -> Result<(), AkdError> {
+        // Retrieve the same AZKS that all the other calls see (i.e. the version that could be cached
+        // at this point). We'll compare this via an uncached call when a change is notified
+        let mut last = Directory::<TC, S, V>::get_azks_from_storage(&self.storage, false).await?;
+
+        loop {
+            // loop forever polling for changes
+            crate::runtime::sleep(period).await;
+
+            let latest = Directory::<TC, S, V>::get_azks_from_storage(&self.storage, true).await?;
+            if latest.latest_epoch > last.latest_epoch {
+                {
+                    // acquire a singleton lock prior to flushing the cache to assert that no
+                    // cache accesses are underway (i.e. publish/proof generations/etc)
+                    let _guard = self.cache_lock.write().await;
+                    // flush the cache in its entirety
+                    self.storage.flush_cache().await;
+                    self.invalidate_proof_cache();
+                    // re-fetch the azks to load it into cache so when we release the cache lock
+                    // others will see the new AZKS loaded up and ready
+                    last =
+                        Directory::<TC, S, V>::get_azks_from_storage(&self.storage, false).await?;
+
+                    // notify change occurred
+                    if let Some(channel) = &change_detected {
+                        channel.send(()).await.map_err(|send_err| {
+                            AkdError::Storage(StorageError::Connection(format!(
+                                "Tokio MPSC sender failed to publish notification with error {send_err:?}"
+                            )))
+                        })?;
+                    }
+                    // drop the guard
+                }
+            }
+        }
+
+        #[allow(unreachable_code)]
+        Ok(())
+    }
+
+    /// Returns an [AppendOnlyProof] for the leaves inserted into the underlying tree between
+    /// the epochs `audit_start_ep` and `audit_end_ep`.
+    pub async fn audit(
+        &self,
+        audit_start_ep: u64,
+        audit_end_ep: u64,
+    ) -> Result<AppendOnlyProof, AkdError> {
+        self.audit_cancellable(audit_start_ep, audit_end_ep, &CancellationToken::new())
+            .await
+    }
+
+    /// Same as [Directory::audit], but aborts with [DirectoryError::Cancelled] if the
+    /// cancellation token is cancelled while the proof is being generated.
+    pub async fn audit_cancellable(
+        &self,
+        audit_start_ep: u64,
+        audit_end_ep: u64,
+        cancellation: &CancellationToken,
+    ) -> Result<AppendOnlyProof, AkdError> {
+        // The guard will be dropped at the end of the proof generation
+        let _guard = self.cache_lock.read().await;
+        let started = Instant::now();
+
+        let current_azks = self.retrieve_azks().await?;
+        self.check_audit_epochs(&current_azks, audit_start_ep, audit_end_ep)
+            .await?;
+
+        self.storage.disable_cache_cleaning();
+        let result = current_azks
+            .get_append_only_proof_cancellable::<TC, _>(
+                &self.storage,
+                audit_start_ep,
+                audit_end_ep,
+                cancellation,
+            )
+            .await;
+        self.storage.enable_cache_cleaning();
+        if let Ok(proof) = &result {
+            telemetry::record_proof("audit", started, proof);
+        }
+        result
+    }
+
+    /// Same as [Directory::audit], but generates the proof incrementally as a stream of
+    /// [AuditProofSegment]s, each with at most `segment_size` inserted and unchanged
+    /// nodes. Only the segment being generated is held in memory, so that the proofs
+    /// of very large epochs can be written out (e.g. uploaded to blob storage) as they
+    /// are produced. The proof of each epoch ends with a segment marked as `last`, and
+    /// the stream ends after the proof for the transition to `audit_end_ep`.
+    pub fn audit_stream(
+        &self,
+        audit_start_ep: u64,
+        audit_end_ep: u64,
+        segment_size: usize,
+    ) -> impl Stream<Item = Result<AuditProofSegment, AkdError>> + Send + '_ {
+        futures::stream::try_unfold(None, move |state| async move {
+            let (guard, azks, mut cursor) = match state {
+                Some(state) => state,
+                None => {
+                    // The guard is held until the stream is dropped or exhausted
+                    let guard = self.cache_lock.read().await;
+                    let azks = self.retrieve_azks().await?;
+                    self.check_audit_epochs(&azks, audit_start_ep, audit_end_ep)
+                        .await?;
+                    let cursor = azks
+                        .start_append_only_proof_segments(
+                            &self.storage,
+                            audit_start_ep,
+                            audit_end_ep,
+                        )
+                        .await?;
+                    (guard, azks, cursor)
+                }
+            };
+            let segment = azks
+                .next_append_only_proof_segment::<TC, _>(&self.storage, &mut cursor, segment_size)
+                .await?;
+            Ok(segment.map(|segment| (segment, Some((guard, azks, cursor)))))
+        })
+    }
+
+    /// Same as [Directory::audit], but produces a single [CompressedAppendOnlyProof] for
+    /// the range of epochs, in which each unchanged node is only included once, even if it
+    /// is part of the proofs of several epochs. The proof is verified with
+    /// [crate::auditor::audit_verify_compressed].
+    pub async fn audit_range_compressed(
+        &self,
+        audit_start_ep: u64,
+        audit_end_ep: u64,
+    ) -> Result<CompressedAppendOnlyProof, AkdError> {
+        let mut proof = CompressedAppendOnlyProof {
+            unchanged_nodes: vec![],
+            proofs: vec![],
+            epochs: vec![],
+        };
+        let mut node_indices = HashMap::<(NodeLabel, AzksValue), u64>::new();
+        let segments =
+            self.audit_stream(audit_start_ep, audit_end_ep, COMPRESSED_AUDIT_SEGMENT_SIZE);
+        futures::pin_mut!(segments);
+        while let Some(segment) = segments.next().await {
+            let segment = segment?;
+            if segment.index == 0 {
+                proof.epochs.push(segment.epoch);
+                proof.proofs.push(CompressedSingleAppendOnlyProof {
+                    inserted: vec![],
+                    unchanged_nodes: vec![],
+                });
+            }
+            let epoch_proof = proof
+                .proofs
+                .last_mut()
+                .expect("Missing proof for the epoch of the segment");
+            epoch_proof.inserted.extend(segment.inserted);
+            for node in segment.unchanged_nodes {
+                let index = *node_indices
+                    .entry((node.label, node.value))
+                    .or_insert_with(|| {
+                        proof.unchanged_nodes.push(node);
+                        proof.unchanged_nodes.len() as u64 - 1
+                    });
+                epoch_proof.unchanged_nodes.push(index);
+            }
+        }
+        Ok(proof)
+    }
+
+    async fn check_audit_epochs(
+        &self,
+        azks: &Azks,
+        audit_start_ep: u64,
+        audit_end_ep: u64,
+    ) -> Result<(), AkdError> {
+        let current_epoch = azks.get_latest_epoch();
+        if audit_start_ep >= audit_end_ep {
+            Err(AkdError::Directory(DirectoryError::InvalidEpoch(format!(
+                "Start epoch {audit_start_ep} is greater than or equal the end epoch {audit_end_ep}"
+            ))))
+        } else if current_epoch < audit_end_ep {
+            Err(AkdError::Directory(DirectoryError::InvalidEpoch(format!(
+                "End epoch {audit_end_ep} is greater than the current epoch {current_epoch}"
+            ))))
+        } else if let Some(retention) = &self.retention {
+            let metadata = match self.get_epoch_metadata(audit_start_ep).await {
+                Ok(metadata) => Some(metadata),
+                Err(AkdError::Storage(StorageError::NotFound(_))) => None,
+                Err(err) => return Err(err),
+            };
+            retention.check_audit(audit_start_ep, metadata.as_ref(), self.clock.now_ms()?)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Retrieves the [Azks]
+    pub(crate) async fn retrieve_azks(&self) -> Result<Azks, crate::errors::AkdError> {
+        Directory::<TC, S, V>::get_azks_from_storage(&self.storage, false).await
+    }
+
+    async fn get_azks_from_storage(
+        storage: &StorageManager<S>,
+        ignore_cache: bool,
+    ) -> Result<Azks, crate::errors::AkdError> {
+        let got = if ignore_cache {
+            storage
+                .get_direct::<Azks>(&crate::append_only_zks::DEFAULT_AZKS_KEY)
+                .await?
+        } else {
+            storage
+                .get::<Azks>(&crate::append_only_zks::DEFAULT_AZKS_KEY)
+                .await?
+        };
+        match got {
+            DbRecord::Azks(azks) => Ok(azks),
+            _ => {
+                error!("No AZKS can be found. You should re-initialize the directory to create a new one");
+                Err(AkdError::Storage(StorageError::NotFound(
+                    "AZKS not found".to_string(),
+                )))
+            }
+        }
+    }
+
+    // HELPERS //
+
+    /// Use this function to retrieve the [VRFPublicKey] for this AKD. This is only
+    /// supported by configurations using the [Ed25519Sha512Tai] VRF suite, see
+    /// [Directory::get_public_key_bytes] for the other suites.
+    pub async fn get_public_key(&self) -> Result<VRFPublicKey, AkdError> {
+        if TC::Vrf::SUITE_STRING != Ed25519Sha512Tai::SUITE_STRING {
+            return Err(AkdError::Vrf(VrfError::PublicKey(
+                "The VRF suite of the configuration does not use ed25519 public keys".to_string(),
+            )));
+        }
+        Ok(self.vrf.get_vrf_public_key().await?)
+    }
+
+    /// Use this function to retrieve the encoding of the VRF public key for this AKD, in the
+    /// VRF suite of its configuration. This is the public key expected by the verification
+    /// of its proofs.
+    pub async fn get_public_key_bytes(&self) -> Result<Vec<u8>, AkdError> {
+        Ok(self.vrf.get_vrf_public_key_bytes::<TC>().await?)
+    }
+
+    /// Wipes the secrets which the key storage of this directory holds in memory (see
+    /// [VRFKeyStorage::destroy_secrets]), along with those of the previous key of its VRF key
+    /// rotation, e.g. when shutting down. This waits for the publishes and proof generations
+    /// which are underway to complete. The clones of this directory share its key storage, so
+    /// they can no longer publish or generate proofs afterwards either.
+    pub async fn destroy_secrets(self) -> Result<(), AkdError> {
+        let _guard = self.cache_lock.write().await;
+        self.vrf.destroy_secrets().await?;
+        if let Some(previous_vrf) = self
+            .vrf_rotation
+            .as_ref()
+            .and_then(|rotation| rotation.previous_vrf.as_ref())
+        {
+            previous_vrf.destroy_secrets().await?;
+        }
+        Ok(())
+    }
+
+    async fn create_single_update_proof(
+        &self,
+        current_azks: &Azks,
+        akd_label: &AkdLabel,
+        user_state: &ValueState,
+    ) -> Result<UpdateProof, AkdError> {
+        let commitment_key = self.proof_commitment_key().await?;
+        self.create_update_proof_with_key(
+            current_azks,
+            &self.vrf,
+            commitment_key.as_deref(),
+            self.leaf_epoch(user_state.epoch),
+            akd_label,
+            user_state,
+        )
+        .await
+    }
+
+    /// Creates the update proof of a value state under the provided VRF key, whose leaves were
+    /// inserted into the tree at `epoch`. The membership proofs are generated against the provided
+    /// [Azks] (rather than the latest one in storage), so that they are consistent with the root
+    /// hash of the enclosing proof even if an epoch is published concurrently.
+    ///
+    /// Without a commitment key, the VRF proofs and the opening of the commitment are read from
+    /// the precomputed proofs, see [Directory::with_precomputed_vrf_proofs].
+    async fn create_update_proof_with_key(
+        &self,
+        current_azks: &Azks,
+        vrf: &V,
+        commitment_key: Option<&Digest>,
+        epoch: u64,
+        akd_label: &AkdLabel,
+        user_state: &ValueState,
+    ) -> Result<UpdateProof, AkdError> {
+        let value = &user_state.value;
+        let version = user_state.version;
+
+        let existence_vrf_proof = self
+            .label_proof(vrf, akd_label, VersionFreshness::Fresh, version)
+            .await?;
+        let existence_label = vrf
+            .get_node_label_from_vrf_proof::<TC>(&existence_vrf_proof)
+            .await?;
+        let existence_proof = current_azks
+            .get_membership_proof::<TC, _>(&self.storage, existence_label)
+            .await?;
+        let mut previous_version_proof = Option::None;
+        let mut previous_version_vrf_proof = Option::None;
+        if version > 1 {
+            let prev_vrf_proof = self
+                .label_proof(vrf, akd_label, VersionFreshness::Stale, version - 1)
+                .await?;
+            let prev_label_at_ep = vrf
+                .get_node_label_from_vrf_proof::<TC>(&prev_vrf_proof)
+                .await?;
+            previous_version_proof = Option::Some(
+                current_azks
+                    .get_membership_proof::<TC, _>(&self.storage, prev_label_at_ep)
+                    .await?,
+            );
+            previous_version_vrf_proof = Option::Some(prev_vrf_proof);
+        }
+
+        let commitment_nonce = self
+            .commitment_nonce(commitment_key, akd_label, &existence_label, user_state)
+            .await?;
+
+        Ok(UpdateProof {
+            epoch,
+            version,
+            value: pad_value::<TC>(value).into_owned(),
+            existence_vrf_proof,
+            existence_proof,
+            previous_version_vrf_proof,
+            previous_version_proof,
+            commitment_nonce,
+        })
+    }
+
+    /// Gets the root hash at the current epoch.
+    pub async fn get_epoch_hash(&self) -> Result<EpochHash, AkdError> {
+        let current_azks = self.retrieve_azks().await?;
+        let latest_epoch = current_azks.get_latest_epoch();
+        let root_hash = current_azks.get_root_hash::<TC, _>(&self.storage).await?;
+        Ok(EpochHash(latest_epoch, root_hash))
+    }
+
+    /// Enumerates the labels registered in the directory as of the current epoch, in ascending
+    /// order. This is meant for operator tooling such as reconciliation jobs, which can paginate
+    /// through all labels by passing the [LabelPage::next_cursor] of each page as the `cursor` of
+    /// the next call (starting with [None]).
+    ///
+    /// * `limit`: The maximum number of labels to return in the page
+    /// * `include_state`: Whether to include the current version and last update epoch of each label
+    ///
+    /// The labels are served from the value states in storage, and so this requires a storage layer
+    /// implementing [Database::list_labels].
+    pub async fn list_labels(
+        &self,
+        cursor: Option<AkdLabel>,
+        limit: usize,
+        include_state: bool,
+    ) -> Result<LabelPage, AkdError> {
+        // The guard will be dropped at the end of the enumeration
+        let _guard = self.cache_lock.read().await;
+
+        let current_epoch = self.retrieve_azks().await?.get_latest_epoch();
+        // Retrieve an additional label to determine whether there is a next page
+        let mut entries = self
+            .storage
+            .list_labels(cursor.as_ref(), limit.saturating_add(1), current_epoch)
+            .await?;
+        let next_cursor = if entries.len() > limit {
+            entries.truncate(limit);
+            entries.last().map(|entry| entry.label.clone())
+        } else {
+            None
+        };
+        if !include_state {
+            for entry in entries.iter_mut() {
+                entry.current_version = None;
+                entry.last_update_epoch = None;
+            }
+        }
+
+        Ok(LabelPage {
+            entries,
+            next_cursor,
+        })
+    }
+
+    /// Reports statistics about the directory and its storage, e.g. for server operators
+    /// to expose on a health endpoint. The storage statistics are those of the
+    /// [StorageManager] of this directory instance.
+    pub async fn stats(&self) -> Result<DirectoryStats, AkdError> {
+        // The guard will be dropped at the end of the retrieval
+        let _guard = self.cache_lock.read().await;
+
+        let azks = self.retrieve_azks().await?;
+        let epoch = azks.get_latest_epoch();
+        let total_labels = match self.storage.count_labels(epoch).await {
+            Ok(count) => Some(count),
+            Err(err) => {
+                debug!("Failed to count the labels in storage: {}", err);
+                None
+            }
+        };
+        let last_publish_duration_ms = self
+            .last_publish_duration
+            .lock()
+            .await
+            .map(|duration| duration.as_millis() as u64);
+
+        Ok(DirectoryStats {
+            epoch,
+            total_labels,
+            total_tree_nodes: azks.num_nodes,
+            last_publish_duration_ms,
+            rejected_proof_generations: self
+                .admission
+                .as_ref()
+                .map_or(0, |admission| admission.rejected_count()),
+            storage: self.storage.stats(),
+        })
+    }
+
+    /// Summarizes the changes to the directory after `epoch_a`, up to and including `epoch_b`,
+    /// e.g. for operators to check the epochs for anomalies before publishing their audit
+    /// proofs. The summary is computed from the stored value states and tree, and requires
+    /// the storage layer to support enumerating labels (see [Database::list_labels]).
+    pub async fn diff(&self, epoch_a: u64, epoch_b: u64) -> Result<EpochDiff, AkdError> {
+        // The guard will be dropped at the end of the summary
+        let _guard = self.cache_lock.read().await;
+
+        let azks = self.retrieve_azks().await?;
+        let current_epoch = azks.get_latest_epoch();
+        if epoch_a >= epoch_b {
+            return Err(AkdError::Directory(DirectoryError::InvalidEpoch(format!(
+                "Start epoch {epoch_a} is greater than or equal the end epoch {epoch_b}"
+            ))));
+        }
+        if epoch_b > current_epoch {
+            return Err(AkdError::Directory(DirectoryError::InvalidEpoch(format!(
+                "End epoch {epoch_b} is greater than the current epoch {current_epoch}"
+            ))));
+        }
+
+        let mut diff = EpochDiff {
+            from_epoch: epoch_a,
+            to_epoch: epoch_b,
+            total_labels: 0,
+            labels_added: 0,
+            labels_updated: 0,
+            value_updates: 0,
+            leaves_inserted: azks
+                .count_leaves_inserted_between(&self.storage, epoch_a, epoch_b)
+                .await?,
+        };
+        let mut cursor = None;
+        loop {
+            let entries = self
+                .storage
+                .list_labels(cursor.as_ref(), HISTORY_PAGE_SIZE, epoch_b)
+                .await?;
+            diff.total_labels += entries.len() as u64;
+            let changed = entries
+                .iter()
+                .filter(|entry| entry.last_update_epoch.is_some_and(|epoch| epoch > epoch_a))
+                .collect::<Vec<_>>();
+            let changed_labels = changed
+                .iter()
+                .map(|entry| entry.label.clone())
+                .collect::<Vec<_>>();
+            let previous_versions = self
+                .storage
+                .get_user_state_versions(
+                    &changed_labels,
+                    ValueStateRetrievalFlag::LeqEpoch(epoch_a),
+                )
+                .await?;
+            for entry in changed {
+                let version = entry.current_version.unwrap_or_default();
+                match previous_versions.get(&entry.label) {
+                    Some((previous_version, _)) => {
+                        diff.labels_updated += 1;
+                        diff.value_updates += version.saturating_sub(*previous_version);
+                    }
+                    None => {
+                        diff.labels_added += 1;
+                        diff.value_updates += version;
+                    }
+                }
+            }
+            match entries.last() {
+                Some(entry) if entries.len() == HISTORY_PAGE_SIZE => {
+                    cursor = Some(entry.label.clone())
+                }
+                _ => break,
+            }
+        }
+        Ok(diff)
+    }
+
+    /// Re-executes the publication of a past epoch from the set of updates which was recorded
+    /// for it, without modifying the directory, e.g. to detect storage corruption or a publish
+    /// bug after the fact. The returned [EpochReplay] holds the root hash which the epoch
+    /// should have had, along with the root hash which is stored in the tree.
+    ///
+    /// The stored root hashes of the epoch and of the previous one are computed from the
+    /// audit proof of the epoch. The previous epoch is recomputed by forking the directory
+    /// (see [Directory::fork_at]) into memory, which is checked against its stored root hash,
+    /// and the updates are then published on the fork with the blindings and [EpochMetadata]
+    /// recorded for the epoch. As with a fork, this requires a storage layer implementing
+    /// [Database::list_labels], and holds every value state up to the epoch in memory.
+    pub async fn replay_epoch(
         &self,
-        akd_label: AkdLabel,
         epoch: u64,
-    ) -> Result<LookupInfo, AkdError> {
-        match self
-            .storage
-            .get_user_state(&akd_label, ValueStateRetrievalFlag::LeqEpoch(epoch))
-            .await
-        {
-            Err(_) => {
-                // Need to throw an error
-                match std::str::from_utf8(&akd_label) {
-                    Ok(name) => Err(AkdError::Storage(StorageError::NotFound(format!(
-                        "User {name} at epoch {epoch}"
-                    )))),
-                    _ => Err(AkdError::Storage(StorageError::NotFound(format!(
-                        "User {akd_label:?} at epoch {epoch}"
-                    )))),
+        updates: Vec<(AkdLabel, AkdValue)>,
+    ) -> Result<EpochReplay, AkdError> {
+        if epoch == 0 {
+            return Err(AkdError::Directory(DirectoryError::InvalidEpoch(
+                "Cannot replay epoch 0, which has no updates".to_string(),
+            )));
+        }
+        let proof = self.audit(epoch - 1, epoch).await?;
+        let (stored_previous_root_hash, stored_root_hash) =
+            crate::auditor::compute_append_only_root_hashes::<TC>(&proof.proofs[0], epoch).await?;
+
+        let (fork, previous_epoch_hash) = self
+            .fork_at(
+                epoch - 1,
+                StorageManager::new_no_cache(AsyncInMemoryDatabase::new()),
+            )
+            .await?;
+
+        let mut blindings = HashMap::new();
+        for (label, _) in updates.iter() {
+            match self
+                .storage
+                .get_user_state(label, ValueStateRetrievalFlag::SpecificEpoch(epoch))
+                .await
+            {
+                Ok(state) => {
+                    if let Some(blinding) = state.blinding {
+                        blindings.insert(label.clone(), blinding);
+                    }
                 }
+                Err(StorageError::NotFound(_)) => (),
+                Err(err) => return Err(err.into()),
             }
-            Ok(latest_st) => self.build_lookup_info(&latest_st).await,
         }
+        let original = match self.get_epoch_metadata(epoch).await {
+            Ok(metadata) => Some(metadata),
+            Err(AkdError::Storage(StorageError::NotFound(_))) => None,
+            Err(err) => return Err(err),
+        };
+        let replayed = fork
+            .replay_updates(&updates, &blindings, original.as_ref())
+            .await?;
+
+        Ok(EpochReplay {
+            epoch,
+            stored_root_hash,
+            replayed_root_hash: replayed.hash(),
+            previous_root_consistent: previous_epoch_hash.hash() == stored_previous_root_hash,
+        })
     }
 
-    /// Takes in the current state of the server and a label.
-    /// If the label is present in the current state,
-    /// this function returns all the values ever associated with it,
-    /// and the epoch at which each value was first committed to the server state.
-    /// It also returns the proof of the latest version being served at all times.
-    pub async fn key_history(
-        &self,
-        akd_label: &AkdLabel,
-        params: HistoryParams,
-    ) -> Result<(HistoryProof, EpochHash), AkdError> {
-        // The guard will be dropped at the end of the proof generation
+    /// Checks that the directory is able to serve requests, e.g. for a load balancer's readiness
+    /// probe. This checks that:
+    /// - The tree can be read from storage, and how long it takes
+    /// - The stored root value of the latest epoch is consistent with the rest of the tree, by
+    ///   recomputing the values of the nodes on a few paths from their children, which are
+    ///   sampled pseudo-randomly from the current time of the [Clock] of the directory
+    /// - A VRF proof can be generated with the VRF key of the directory
+    ///
+    /// The failed checks are recorded in the returned [HealthReport] rather than returned as
+    /// errors, so that the report is always complete.
+    pub async fn health_check(&self) -> HealthReport {
+        // The guard will be dropped at the end of the check
         let _guard = self.cache_lock.read().await;
+        let mut failures = Vec::new();
 
-        let current_azks = self.retrieve_azks().await?;
-        let current_epoch = current_azks.get_latest_epoch();
-        let mut user_data = self.storage.get_user_data(akd_label).await?.states;
-
-        // reverse sort from highest epoch to lowest
-        user_data.sort_by(|a, b| b.epoch.cmp(&a.epoch));
+        let started = Instant::now();
+        let azks = self.retrieve_azks().await;
+        let storage_latency_ms = started.elapsed().as_millis() as u64;
+        let (epoch, root_hash_consistent) = match azks {
+            Ok(azks) => {
+                // The paths are sampled from the time of the clock of the directory, so that they
+                // are deterministic under an injected clock (e.g. in a simulation)
+                let seed = self.clock.now_ms().unwrap_or_default();
+                let mut consistent = Some(true);
+                for index in 0..HEALTH_CHECK_SAMPLED_PATHS {
+                    let path = TC::hash(
+                        &[
+                            HEALTH_CHECK_DOMAIN_SEPARATOR,
+                            &seed.to_be_bytes(),
+                            &index.to_be_bytes(),
+                        ]
+                        .concat(),
+                    );
+                    match azks
+                        .find_inconsistent_node_on_path::<TC, _>(&self.storage, path)
+                        .await
+                    {
+                        Ok(None) => {}
+                        Ok(Some(label)) => {
+                            failures.push(format!(
+                                "The stored value of the node {label:?} does not match its children"
+                            ));
+                            consistent = Some(false);
+                            break;
+                        }
+                        Err(err) => {
+                            failures.push(format!("Failed to read the tree from storage: {err}"));
+                            consistent = None;
+                            break;
+                        }
+                    }
+                }
+                (Some(azks.get_latest_epoch()), consistent)
+            }
+            Err(err) => {
+                failures.push(format!("Failed to read the tree from storage: {err}"));
+                (None, None)
+            }
+        };
 
-        // apply filters specified by HistoryParams struct
-        user_data = match params {
-            HistoryParams::Complete => user_data,
-            HistoryParams::MostRecent(n) => user_data.into_iter().take(n).collect::<Vec<_>>(),
+        let vrf_key_available = match self
+            .vrf
+            .get_proof::<TC>(HEALTH_CHECK_DOMAIN_SEPARATOR)
+            .await
+        {
+            Ok(_) => true,
+            Err(err) => {
+                failures.push(format!("Failed to generate a VRF proof: {err}"));
+                false
+            }
         };
 
-        if user_data.is_empty() {
-            let msg = if let Ok(username_str) = std::str::from_utf8(akd_label) {
-                format!("User {username_str}")
-            } else {
-                format!("User {akd_label:?}")
-            };
-            return Err(AkdError::Storage(StorageError::NotFound(msg)));
+        HealthReport {
+            epoch,
+            storage_reachable: epoch.is_some(),
+            storage_latency_ms,
+            root_hash_consistent,
+            vrf_key_available,
+            failures,
         }
+    }
 
-        let mut start_version = user_data[0].version;
-        let mut end_version = 0;
-        for user_state in &user_data {
-            // Ignore states in storage that are ahead of current directory epoch
-            if user_state.epoch <= current_epoch {
-                start_version = std::cmp::min(user_state.version, start_version);
-                end_version = std::cmp::max(user_state.version, end_version);
-            }
+    /// Retrieves the [EpochMetadata] which was recorded when the given epoch was published.
+    pub async fn get_epoch_metadata(&self, epoch: u64) -> Result<EpochMetadata, AkdError> {
+        match self.storage.get::<EpochMetadata>(&epoch).await? {
+            DbRecord::EpochMetadata(metadata) => Ok(metadata),
+            _ => Err(AkdError::Storage(StorageError::NotFound(format!(
+                "EpochMetadata for epoch {epoch}"
+            )))),
         }
+    }
 
-        if start_version == 0 {
-            return Err(AkdError::Directory(DirectoryError::InvalidVersion(
-                "Computed start version for the key history should be non-zero".to_string(),
-            )));
+    /// Retrieves the journal of the updates published in the given epoch, as recorded with
+    /// [Directory::with_update_journal]. A [StorageError::NotFound] is returned if no journal
+    /// was recorded for the epoch (e.g. if it was published before the journal was configured).
+    pub async fn get_update_journal(&self, epoch: u64) -> Result<UpdateJournal, AkdError> {
+        self.check_update_journal_epochs(epoch, epoch).await?;
+        match self.storage.get::<UpdateJournal>(&epoch).await? {
+            DbRecord::UpdateJournal(journal) => Ok(journal),
+            _ => Err(AkdError::Storage(StorageError::NotFound(format!(
+                "UpdateJournal for epoch {epoch}"
+            )))),
         }
+    }
 
-        let (past_marker_versions, future_marker_versions) =
-            get_marker_versions(start_version, end_version, current_epoch);
-
-        #[cfg(feature = "preload_history")]
-        {
-            let mut lookup_infos = vec![];
-            for ud in user_data.iter() {
-                if let Ok(lo) = self.build_lookup_info(ud).await {
-                    lookup_infos.push(lo);
+    /// Streams the journals of the updates published in the epochs from `start_epoch` up to
+    /// and including `end_epoch`, in order, as recorded with [Directory::with_update_journal].
+    /// Only the journal being yielded is held in memory, and the epochs for which no journal
+    /// was recorded are skipped.
+    pub fn stream_update_journals(
+        &self,
+        start_epoch: u64,
+        end_epoch: u64,
+    ) -> impl Stream<Item = Result<UpdateJournal, AkdError>> + Send + '_ {
+        futures::stream::try_unfold(None, move |next_epoch| async move {
+            let mut epoch = match next_epoch {
+                Some(epoch) => epoch,
+                None => {
+                    self.check_update_journal_epochs(start_epoch, end_epoch)
+                        .await?;
+                    start_epoch
+                }
+            };
+            while epoch <= end_epoch {
+                let journal = self.storage.get::<UpdateJournal>(&epoch).await;
+                epoch += 1;
+                match journal {
+                    Ok(DbRecord::UpdateJournal(journal)) => {
+                        return Ok(Some((journal, Some(epoch))));
+                    }
+                    Ok(_) | Err(StorageError::NotFound(_)) => (),
+                    Err(err) => return Err(err.into()),
                 }
             }
+            Ok(None)
+        })
+    }
 
-            let mut marker_labels = vec![];
-            for version in past_marker_versions
-                .iter()
-                .chain(future_marker_versions.iter())
-            {
-                let node_label = self
-                    .vrf
-                    .get_node_label::<TC>(akd_label, VersionFreshness::Fresh, *version)
-                    .await?;
-                marker_labels.push(node_label);
-            }
+    async fn check_update_journal_epochs(
+        &self,
+        start_epoch: u64,
+        end_epoch: u64,
+    ) -> Result<(), AkdError> {
+        let current_epoch = self.retrieve_azks().await?.get_latest_epoch();
+        if start_epoch == 0 || start_epoch > end_epoch {
+            Err(AkdError::Directory(DirectoryError::InvalidEpoch(format!(
+                "Invalid range of epochs {start_epoch} to {end_epoch}, the first epoch with updates is 1"
+            ))))
+        } else if end_epoch > current_epoch {
+            Err(AkdError::Directory(DirectoryError::InvalidEpoch(format!(
+                "End epoch {end_epoch} is greater than the current epoch {current_epoch}"
+            ))))
+        } else {
+            Ok(())
+        }
+    }
 
-            current_azks
-                .preload_lookup_nodes(&self.storage, &lookup_infos, Some(marker_labels))
-                .await?;
+    /// Retrieves the root hashes of the epochs from `start_epoch` up to and including
+    /// `end_epoch`, as recorded with [Directory::with_root_history], along with the chain
+    /// hashes linking them to each other and to the root hashes of the preceding epochs (see
+    /// [EpochRootChain]). This lets clients and auditors bootstrap, or extend, a history of
+    /// the root hashes without downloading the audit proofs of the epochs. The chain should be
+    /// checked with [crate::client::verify_epoch_root_chain] against a chain hash which the
+    /// client trusts independently of this response. A
+    /// [StorageError::NotFound] is returned if the root hash of an epoch of the range was not
+    /// recorded.
+    pub async fn get_epoch_hashes(
+        &self,
+        start_epoch: u64,
+        end_epoch: u64,
+    ) -> Result<EpochRootChain, AkdError> {
+        let current_epoch = self.retrieve_azks().await?.get_latest_epoch();
+        if start_epoch > end_epoch {
+            return Err(AkdError::Directory(DirectoryError::InvalidEpoch(format!(
+                "Invalid range of epochs {start_epoch} to {end_epoch}"
+            ))));
+        }
+        if end_epoch > current_epoch {
+            return Err(AkdError::Directory(DirectoryError::InvalidEpoch(format!(
+                "End epoch {end_epoch} is greater than the current epoch {current_epoch}"
+            ))));
         }
 
-        // The creation of update proofs should happen only after the preload operation (to prevent cache misses).
-        let mut update_proofs = Vec::<UpdateProof>::new();
-        for user_state in &user_data {
-            // Ignore states in storage that are ahead of current directory epoch
-            if user_state.epoch <= current_epoch {
-                let proof = self
-                    .create_single_update_proof(akd_label, user_state)
-                    .await?;
-                update_proofs.push(proof);
-            }
+        let previous_chain_hash = self.previous_chain_hash(start_epoch).await?;
+        let mut root_hashes = Vec::new();
+        let mut chain_hash = previous_chain_hash;
+        for epoch in start_epoch..=end_epoch {
+            let record = match self.storage.get::<EpochRootRecord>(&epoch).await? {
+                DbRecord::EpochRootRecord(record) => record,
+                _ => {
+                    return Err(AkdError::Storage(StorageError::NotFound(format!(
+                        "EpochRootRecord for epoch {epoch}"
+                    ))))
+                }
+            };
+            root_hashes.push(record.root_hash);
+            chain_hash = record.chain_hash;
         }
+        Ok(EpochRootChain {
+            start_epoch,
+            previous_chain_hash,
+            root_hashes,
+            chain_hash,
+        })
+    }
 
-        let mut past_marker_vrf_proofs = vec![];
-        let mut existence_of_past_marker_proofs = vec![];
+    /// Generates an [EpochMetadataProof] showing that the [EpochMetadata] for the given
+    /// epoch is bound into the root hash at the current epoch. This is only possible for
+    /// epochs which were published with [Directory::with_epoch_metadata_binding] enabled,
+    /// as recorded in [EpochMetadata::bound]. A [DirectoryError::InvalidEpoch] is returned
+    /// if the epoch has not been published yet, or if its metadata was not bound.
+    pub async fn get_epoch_metadata_proof(
+        &self,
+        epoch: u64,
+    ) -> Result<(EpochMetadataProof, EpochHash), AkdError> {
+        // The guard will be dropped at the end of the proof generation
+        let _guard = self.cache_lock.read().await;
 
-        for version in past_marker_versions {
-            let node_label = self
-                .vrf
-                .get_node_label::<TC>(akd_label, VersionFreshness::Fresh, version)
-                .await?;
-            let existence_vrf = self
-                .vrf
-                .get_label_proof::<TC>(akd_label, VersionFreshness::Fresh, version)
-                .await?;
-            past_marker_vrf_proofs.push(existence_vrf.to_bytes().to_vec());
-            existence_of_past_marker_proofs.push(
-                current_azks
-                    .get_membership_proof::<TC, _>(&self.storage, node_label)
-                    .await?,
-            );
+        let current_azks = self.retrieve_azks().await?;
+        let current_epoch = current_azks.get_latest_epoch();
+        if epoch == 0 || epoch > current_epoch {
+            return Err(AkdError::Directory(DirectoryError::InvalidEpoch(format!(
+                "Epoch {epoch} has not been published (current epoch: {current_epoch})"
+            ))));
         }
 
-        let mut future_marker_vrf_proofs = vec![];
-        let mut non_existence_of_future_marker_proofs = vec![];
-
-        for version in future_marker_versions {
-            let node_label = self
-                .vrf
-                .get_node_label::<TC>(akd_label, VersionFreshness::Fresh, version)
-                .await?;
-            non_existence_of_future_marker_proofs.push(
-                current_azks
-                    .get_non_membership_proof::<TC, _>(&self.storage, node_label)
-                    .await?,
-            );
-            future_marker_vrf_proofs.push(
-                self.vrf
-                    .get_label_proof::<TC>(akd_label, VersionFreshness::Fresh, version)
-                    .await?
-                    .to_bytes()
-                    .to_vec(),
-            );
+        let metadata = self.get_epoch_metadata(epoch).await?;
+        if !metadata.bound {
+            return Err(AkdError::Directory(DirectoryError::InvalidEpoch(format!(
+                "Metadata for epoch {epoch} was not bound into the tree"
+            ))));
         }
 
+        let label = EpochMetadata::node_label::<TC>(epoch);
+        let membership_proof = current_azks
+            .get_membership_proof::<TC, _>(&self.storage, label)
+            .await?;
+        let commitment_key = self.derive_commitment_key_for_epoch(epoch).await?;
+        let commitment_nonce = metadata.commitment_nonce::<TC>(&*commitment_key).to_vec();
+
         let root_hash = EpochHash(
             current_epoch,
             current_azks.get_root_hash::<TC, _>(&self.storage).await?,
         );
-
         Ok((
-            HistoryProof {
-                update_proofs,
-                past_marker_vrf_proofs,
-                existence_of_past_marker_proofs,
-                future_marker_vrf_proofs,
-                non_existence_of_future_marker_proofs,
+            EpochMetadataProof {
+                metadata,
+                membership_proof,
+                commitment_nonce,
             },
             root_hash,
         ))
     }
 
-    /// Poll for changes in the epoch number of the AZKS struct
-    /// stored in the storage layer. If an epoch change is detected,
-    /// the object cache (if present) is flushed immediately so
-    /// that new objects are retrieved from the storage layer against
-    /// the "latest" epoch. There is a "special" flow in the storage layer
-    /// to do a storage-layer retrieval which ignores the cache
-    pub async fn poll_for_azks_changes(
+    // We simply hash the VRF private key to derive the commitment key
+    /// Derives the node labels of a collection of VRF computations with the provided key, with
+    /// the parallelism configured by [Directory::with_vrf_parallelism]
+    async fn derive_node_labels(
         &self,
-        period: tokio::time::Duration,
-        change_detected: Option<tokio::sync::mpsc::Sender<()>>,
-    ) -> Result<(), AkdError> {
-        // Retrieve the same AZKS that all the other calls see (i.e. the version that could be cached
-        // at this point). We'll compare this via an uncached call when a change is notified
-        let mut last = Directory::<TC, S, V>::get_azks_from_storage(&self.storage, false).await?;
-
-        loop {
-            // loop forever polling for changes
-            tokio::time::sleep(period).await;
+        vrf: &V,
+        computations: &[(AkdLabel, VersionFreshness, u64, AkdValue)],
+    ) -> Result<Vec<((AkdLabel, VersionFreshness, u64, AkdValue), NodeLabel)>, AkdError> {
+        telemetry::increment_counter(Counter::VrfEvaluations, computations.len() as u64, &[]);
+        let node_labels = match self.vrf_tasks {
+            Some(num_tasks) => {
+                vrf.get_node_labels_with_parallelism::<TC>(computations, num_tasks)
+                    .await?
+            }
+            None => vrf.get_node_labels::<TC>(computations).await?,
+        };
+        Ok(node_labels)
+    }
 
-            let latest = Directory::<TC, S, V>::get_azks_from_storage(&self.storage, true).await?;
-            if latest.latest_epoch > last.latest_epoch {
-                {
-                    // acquire a singleton lock prior to flushing the cache to assert that no
-                    // cache accesses are underway (i.e. publish/proof generations/etc)
-                    let _guard = self.cache_lock.write().await;
-                    // flush the cache in its entirety
-                    self.storage.flush_cache().await;
-                    // re-fetch the azks to load it into cache so when we release the cache lock
-                    // others will see the new AZKS loaded up and ready
-                    last =
-                        Directory::<TC, S, V>::get_azks_from_storage(&self.storage, false).await?;
+    /// Precomputes the VRF proofs of the value states published at `epoch`, along with the
+    /// fresh proofs of the marker versions of their histories (see
+    /// [Directory::with_precomputed_vrf_proofs])
+    async fn precompute_vrf_proofs(
+        &self,
+        value_states: &[ValueState],
+        epoch: u64,
+        commitment_key: &Digest,
+    ) -> Result<Vec<DbRecord>, AkdError> {
+        let mut records = vec![];
+        for state in value_states {
+            let commitment_nonce = get_commitment_nonce::<TC>(
+                commitment_key,
+                &state.label,
+                state.version,
+                &state.value,
+                state.blinding.as_deref(),
+            );
+            records.push(
+                self.precompute_vrf_proof(
+                    &state.username,
+                    VersionFreshness::Fresh,
+                    state.version,
+                    Some(commitment_nonce.to_vec()),
+                )
+                .await?,
+            );
+            records.push(
+                self.precompute_vrf_proof(
+                    &state.username,
+                    VersionFreshness::Stale,
+                    state.version,
+                    None,
+                )
+                .await?,
+            );
 
-                    // notify change occurred
-                    if let Some(channel) = &change_detected {
-                        channel.send(()).await.map_err(|send_err| {
-                            AkdError::Storage(StorageError::Connection(format!(
-                                "Tokio MPSC sender failed to publish notification with error {send_err:?}"
-                            )))
-                        })?;
-                    }
-                    // drop the guard
-                }
+            // Once a label reaches a power of two, the following versions up to the next one
+            let mut marker_versions = vec![];
+            if state.version.is_power_of_two() {
+                marker_versions.extend(state.version + 1..state.version.saturating_mul(2));
+            }
+            // For a new label, the powers of two up to the current epoch, as those of the later
+            // epochs are precomputed once they are reached
+            if state.version == 1 {
+                marker_versions.extend(
+                    (1..u64::BITS)
+                        .map(|exponent| 1u64 << exponent)
+                        .take_while(|marker_version| *marker_version <= epoch),
+                );
+            }
+            for version in marker_versions {
+                records.push(
+                    self.precompute_vrf_proof(
+                        &state.username,
+                        VersionFreshness::Fresh,
+                        version,
+                        None,
+                    )
+                    .await?,
+                );
             }
         }
+        Ok(records)
+    }
 
-        #[allow(unreachable_code)]
-        Ok(())
+    /// Precomputes the fresh proofs of the marker version `epoch`, a power of two, for every
+    /// label published before it (whose versions are all below the epoch), apart from those
+    /// which reach the epoch as their version in `value_states`. The proofs of each page of
+    /// labels are requested concurrently, so that a [crate::ecvrf::RemoteVrfSigner] can batch
+    /// them.
+    async fn precompute_marker_vrf_proofs(
+        &self,
+        epoch: u64,
+        value_states: &[ValueState],
+        cancellation: &CancellationToken,
+    ) -> Result<Vec<DbRecord>, AkdError> {
+        let published = value_states
+            .iter()
+            .filter(|state| state.version == epoch)
+            .map(|state| &state.username)
+            .collect::<HashSet<_>>();
+        let mut records = vec![];
+        let mut cursor = None;
+        loop {
+            cancellation.check("Publish")?;
+            let entries = self
+                .storage
+                .list_labels(cursor.as_ref(), PRECOMPUTE_PAGE_SIZE, epoch - 1)
+                .await?;
+            records.extend(
+                futures::future::try_join_all(
+                    entries
+                        .iter()
+                        .filter(|entry| !published.contains(&entry.label))
+                        .map(|entry| {
+                            self.precompute_vrf_proof(
+                                &entry.label,
+                                VersionFreshness::Fresh,
+                                epoch,
+                                None,
+                            )
+                        }),
+                )
+                .await?,
+            );
+
+            match entries.last() {
+                Some(entry) if entries.len() == PRECOMPUTE_PAGE_SIZE => {
+                    cursor = Some(entry.label.clone())
+                }
+                _ => break,
+            }
+        }
+        Ok(records)
     }
 
-    /// Returns an [AppendOnlyProof] for the leaves inserted into the underlying tree between
-    /// the epochs `audit_start_ep` and `audit_end_ep`.
-    pub async fn audit(
+    async fn precompute_vrf_proof(
         &self,
-        audit_start_ep: u64,
-        audit_end_ep: u64,
-    ) -> Result<AppendOnlyProof, AkdError> {
-        // The guard will be dropped at the end of the proof generation
-        let _guard = self.cache_lock.read().await;
-
-        let current_azks = self.retrieve_azks().await?;
-        let current_epoch = current_azks.get_latest_epoch();
+        akd_label: &AkdLabel,
+        freshness: VersionFreshness,
+        version: u64,
+        commitment_nonce: Option<Vec<u8>>,
+    ) -> Result<DbRecord, AkdError> {
+        Ok(DbRecord::PrecomputedVrfProof(PrecomputedVrfProof {
+            label: akd_label.clone(),
+            freshness,
+            version,
+            proof: self
+                .vrf
+                .get_label_proof::<TC>(akd_label, freshness, version)
+                .await?,
+            commitment_nonce,
+        }))
+    }
 
-        if audit_start_ep >= audit_end_ep {
-            Err(AkdError::Directory(DirectoryError::InvalidEpoch(format!(
-                "Start epoch {audit_start_ep} is greater than or equal the end epoch {audit_end_ep}"
-            ))))
-        } else if current_epoch < audit_end_ep {
-            Err(AkdError::Directory(DirectoryError::InvalidEpoch(format!(
-                "End epoch {audit_end_ep} is greater than the current epoch {current_epoch}"
-            ))))
-        } else {
-            self.storage.disable_cache_cleaning();
-            let result = current_azks
-                .get_append_only_proof::<TC, _>(&self.storage, audit_start_ep, audit_end_ep)
-                .await;
-            self.storage.enable_cache_cleaning();
-            result
+    /// Reads the VRF proof precomputed by the writer of the storage layer for a read-only replica
+    async fn read_vrf_proof(
+        &self,
+        akd_label: &AkdLabel,
+        freshness: VersionFreshness,
+        version: u64,
+    ) -> Result<PrecomputedVrfProof, AkdError> {
+        let key = VrfProofKey(akd_label.clone(), freshness, version);
+        match self.storage.get::<PrecomputedVrfProof>(&key).await {
+            Ok(DbRecord::PrecomputedVrfProof(precomputed)) => Ok(precomputed),
+            Ok(_) | Err(StorageError::NotFound(_)) => Err(AkdError::Vrf(VrfError::SigningKey(
+                format!("The VRF proof of {key:?} was not precomputed"),
+            ))),
+            Err(err) => Err(err.into()),
         }
     }
 
-    /// Retrieves the [Azks]
-    pub(crate) async fn retrieve_azks(&self) -> Result<Azks, crate::errors::AkdError> {
-        Directory::<TC, S, V>::get_azks_from_storage(&self.storage, false).await
+    /// The VRF proof of a version of a label, which is computed with `vrf` unless it is read from
+    /// the precomputed proofs by a read-only replica
+    async fn label_proof(
+        &self,
+        vrf: &V,
+        akd_label: &AkdLabel,
+        freshness: VersionFreshness,
+        version: u64,
+    ) -> Result<Vec<u8>, AkdError> {
+        match self.vrf_proofs {
+            VrfProofPrecomputation::Read => Ok(self
+                .read_vrf_proof(akd_label, freshness, version)
+                .await?
+                .proof),
+            _ => Ok(vrf
+                .get_label_proof::<TC>(akd_label, freshness, version)
+                .await?),
+        }
     }
 
-    async fn get_azks_from_storage(
-        storage: &StorageManager<S>,
-        ignore_cache: bool,
-    ) -> Result<Azks, crate::errors::AkdError> {
-        let got = if ignore_cache {
-            storage
-                .get_direct::<Azks>(&crate::append_only_zks::DEFAULT_AZKS_KEY)
-                .await?
-        } else {
-            storage
-                .get::<Azks>(&crate::append_only_zks::DEFAULT_AZKS_KEY)
-                .await?
-        };
-        match got {
-            DbRecord::Azks(azks) => Ok(azks),
-            _ => {
-                error!("No AZKS can be found. You should re-initialize the directory to create a new one");
-                Err(AkdError::Storage(StorageError::NotFound(
-                    "AZKS not found".to_string(),
-                )))
+    /// The node label of a version of a label, see [Directory::label_proof]
+    async fn node_label(
+        &self,
+        vrf: &V,
+        akd_label: &AkdLabel,
+        freshness: VersionFreshness,
+        version: u64,
+    ) -> Result<NodeLabel, AkdError> {
+        match self.vrf_proofs {
+            VrfProofPrecomputation::Read => {
+                let proof = self.label_proof(vrf, akd_label, freshness, version).await?;
+                Ok(vrf.get_node_label_from_vrf_proof::<TC>(&proof).await?)
             }
+            _ => Ok(vrf
+                .get_node_label::<TC>(akd_label, freshness, version)
+                .await?),
         }
     }
 
-    /// HELPERS ///
-
-    /// Use this function to retrieve the [VRFPublicKey] for this AKD.
-    pub async fn get_public_key(&self) -> Result<VRFPublicKey, AkdError> {
-        Ok(self.vrf.get_vrf_public_key().await?)
+    /// The commitment key with which the openings of the commitments to the values are computed
+    /// in the proofs, which is [None] for a read-only replica as it reads the precomputed ones
+    async fn proof_commitment_key(&self) -> Result<Option<Zeroizing<Digest>>, AkdError> {
+        match self.vrf_proofs {
+            VrfProofPrecomputation::Read => Ok(None),
+            _ => Ok(Some(self.derive_commitment_key().await?)),
+        }
     }
 
-    async fn create_single_update_proof(
+    /// The opening of the commitment to the value of a value state, whose fresh node label is
+    /// `existence_label`, which is read from the precomputed proofs without a commitment key
+    async fn commitment_nonce(
         &self,
+        commitment_key: Option<&Digest>,
         akd_label: &AkdLabel,
+        existence_label: &NodeLabel,
         user_state: &ValueState,
-    ) -> Result<UpdateProof, AkdError> {
-        let epoch = user_state.epoch;
-        let value = &user_state.value;
-        let version = user_state.version;
-
-        let label_at_ep = self
-            .vrf
-            .get_node_label::<TC>(akd_label, VersionFreshness::Fresh, version)
-            .await?;
-
-        let current_azks = self.retrieve_azks().await?;
-        let existence_vrf = self
-            .vrf
-            .get_label_proof::<TC>(akd_label, VersionFreshness::Fresh, version)
-            .await?;
-        let existence_vrf_proof = existence_vrf.to_bytes().to_vec();
-        let existence_label = self.vrf.get_node_label_from_vrf_proof(existence_vrf).await;
-        let existence_proof = current_azks
-            .get_membership_proof::<TC, _>(&self.storage, label_at_ep)
-            .await?;
-        let mut previous_version_proof = Option::None;
-        let mut previous_version_vrf_proof = Option::None;
-        if version > 1 {
-            let prev_label_at_ep = self
-                .vrf
-                .get_node_label::<TC>(akd_label, VersionFreshness::Stale, version - 1)
-                .await?;
-            previous_version_proof = Option::Some(
-                current_azks
-                    .get_membership_proof::<TC, _>(&self.storage, prev_label_at_ep)
-                    .await?,
-            );
-            previous_version_vrf_proof = Option::Some(
-                self.vrf
-                    .get_label_proof::<TC>(akd_label, VersionFreshness::Stale, version - 1)
-                    .await?
-                    .to_bytes()
-                    .to_vec(),
-            );
+    ) -> Result<Vec<u8>, AkdError> {
+        match commitment_key {
+            Some(commitment_key) => Ok(get_commitment_nonce::<TC>(
+                commitment_key,
+                existence_label,
+                user_state.version,
+                &user_state.value,
+                user_state.blinding.as_deref(),
+            )
+            .to_vec()),
+            None => self
+                .read_vrf_proof(akd_label, VersionFreshness::Fresh, user_state.version)
+                .await?
+                .commitment_nonce
+                .ok_or_else(|| {
+                    AkdError::Vrf(VrfError::SigningKey(format!(
+                        "The commitment opening of version {} of label {akd_label:?} was not precomputed",
+                        user_state.version
+                    )))
+                }),
         }
+    }
 
-        let commitment_key = self.derive_commitment_key().await?;
-        let commitment_nonce =
-            TC::get_commitment_nonce(&commitment_key, &existence_label, version, value).to_vec();
+    async fn derive_commitment_key(&self) -> Result<Zeroizing<Digest>, AkdError> {
+        Self::derive_commitment_key_with(&self.vrf).await
+    }
 
-        Ok(UpdateProof {
-            epoch,
-            version,
-            value: value.clone(),
-            existence_vrf_proof,
-            existence_proof,
-            previous_version_vrf_proof,
-            previous_version_proof,
-            commitment_nonce,
-        })
+    /// Derives the commitment key from the commitment secret of a key storage. Both are
+    /// zeroized once they are dropped.
+    async fn derive_commitment_key_with(vrf: &V) -> Result<Zeroizing<Digest>, AkdError> {
+        let raw_key = Zeroizing::new(vrf.retrieve_commitment_secret().await?);
+        Ok(Zeroizing::new(TC::hash_with_purpose(
+            HashPurpose::CommitmentKey,
+            &[&raw_key],
+        )))
     }
 
-    /// Gets the root hash at the current epoch.
-    pub async fn get_epoch_hash(&self) -> Result<EpochHash, AkdError> {
-        let current_azks = self.retrieve_azks().await?;
-        let latest_epoch = current_azks.get_latest_epoch();
-        let root_hash = current_azks.get_root_hash::<TC, _>(&self.storage).await?;
-        Ok(EpochHash(latest_epoch, root_hash))
+    /// Derives the commitment key with which the leaves of an epoch were committed to, which
+    /// changes along with the VRF key
+    async fn derive_commitment_key_for_epoch(
+        &self,
+        epoch: u64,
+    ) -> Result<Zeroizing<Digest>, AkdError> {
+        match &self.vrf_rotation {
+            Some(rotation) if epoch < rotation.rotation_epoch => match &rotation.previous_vrf {
+                Some(previous_vrf) if epoch >= rotation.previous_rotation_epoch => {
+                    Self::derive_commitment_key_with(previous_vrf).await
+                }
+                _ => Err(AkdError::Directory(DirectoryError::KeyRotation(format!(
+                    "Epoch {epoch} was published under a VRF key which is not held by the directory"
+                )))),
+            },
+            _ => self.derive_commitment_key().await,
+        }
     }
 
-    // We simply hash the VRF private key to derive the commitment key
-    async fn derive_commitment_key(&self) -> Result<Digest, AkdError> {
-        let raw_key = self.vrf.retrieve().await?;
-        let commitment_key = TC::hash(&raw_key);
-        Ok(commitment_key)
+    /// The epoch at which the leaves of a value state published at `epoch` were inserted into
+    /// the tree under the current VRF key, which is the rotation epoch if the value state was
+    /// published under a previous key
+    fn leaf_epoch(&self, epoch: u64) -> u64 {
+        self.vrf_rotation
+            .as_ref()
+            .map_or(epoch, |rotation| epoch.max(rotation.rotation_epoch))
+    }
+}
+
+impl<TC, S> Directory<TC, S, PublicKeyOnlyVRF>
+where
+    TC: Configuration,
+    S: Database + 'static,
+{
+    /// Constructs a [ReadOnlyDirectory] over shared storage for an instance which only holds
+    /// the VRF public key, so that no signing material needs to be present on proof-serving
+    /// replicas. Publishing is statically prevented by the returned type.
+    ///
+    /// Such an instance can serve audit proofs, epoch hashes, epoch metadata and the VRF public
+    /// key. The VRF proofs and value commitments of lookup and key history proofs can only be
+    /// computed with the VRF private key, and so they are read from those precomputed by the
+    /// writer of the storage layer, which must be configured with
+    /// [Directory::with_precomputed_vrf_proofs]. Generating a proof which needs any other
+    /// evaluation of the VRF (such as [Directory::lookup_absent]), or the commitment key (such as
+    /// [Directory::get_epoch_metadata_proof]), fails with a VRF error.
+    pub async fn new_read_only(
+        storage: StorageManager<S>,
+        vrf_public_key: VRFPublicKey,
+    ) -> Result<ReadOnlyDirectory<TC, S, PublicKeyOnlyVRF>, AkdError> {
+        let ReadOnlyDirectory(directory) =
+            ReadOnlyDirectory::new(storage, PublicKeyOnlyVRF::new(vrf_public_key)).await?;
+        Ok(ReadOnlyDirectory(Directory {
+            vrf_proofs: VrfProofPrecomputation::Read,
+            ..directory
+        }))
     }
 }
 
+/// The history of a directory up to an epoch, as read by [Directory::read_history]
+pub(crate) struct History {
+    /// The epoch up to which the history was read
+    pub(crate) epoch: u64,
+    /// The value states of each label, in increasing order of version
+    pub(crate) labels: Vec<Vec<ValueState>>,
+    /// The metadata of the epochs, for those which have not been pruned
+    pub(crate) metadata: HashMap<u64, EpochMetadata>,
+}
+
+/// The result of [Directory::prepare_updates]
+struct PreparedUpdates {
+    update_set: Vec<AzksElement>,
+    user_data_update_set: Vec<ValueState>,
+    commitment_key: Zeroizing<Digest>,
+    num_new_labels: u64,
+    num_unchanged_labels: u64,
+}
+
 /// A thin newtype which offers read-only interactivity with a [Directory].
 #[derive(Clone)]
 pub struct ReadOnlyDirectory<TC, S, V>(Directory<TC, S, V>)
@@ -812,7 +4237,7 @@ where
             return Err(AkdError::Directory(DirectoryError::ReadOnlyDirectory(
                 format!(
                     "Cannot start directory in read-only mode when AZKS is missing, error: {:?}",
-                    azks.err().take()
+                    azks.err()
                 ),
             )));
         }
@@ -821,15 +4246,89 @@ where
             storage,
             cache_lock: Arc::new(RwLock::new(())),
             vrf,
+            bind_epoch_metadata: false,
+            signer: None,
+            epoch_publishers: vec![],
+            pending_updates: Arc::new(Mutex::new(HashMap::new())),
+            pending_notify: Arc::new(Notify::new()),
+            publish_lease: None,
+            last_publish_duration: Arc::new(Mutex::new(None)),
+            label_namespaces: Arc::new(HashSet::new()),
+            admission: None,
+            insert_tasks: None,
+            vrf_tasks: None,
+            vrf_rotation: None,
+            require_blinding: false,
+            decoy_leaves: 0,
+            clock: Arc::new(SystemClock),
+            retention: None,
+            proof_cache: None,
+            update_journal: None,
+            root_history: false,
+            vrf_proofs: VrfProofPrecomputation::Disabled,
             tc: PhantomData,
         }))
     }
 
+    /// Configures limits on the proof generations of this directory,
+    /// see [Directory::with_admission_control].
+    pub fn with_admission_control(self, config: AdmissionConfig) -> Self {
+        Self(self.0.with_admission_control(config))
+    }
+
+    /// Configures the rotation of the VRF key which this directory went through,
+    /// see [Directory::with_vrf_key_rotation].
+    pub fn with_vrf_key_rotation(self, rotation: VrfKeyRotation<V>) -> Self {
+        Self(self.0.with_vrf_key_rotation(rotation))
+    }
+
+    /// Configures a cache of lookup proofs, see [Directory::with_proof_cache]
+    pub fn with_proof_cache(self, capacity: usize) -> Self {
+        Self(self.0.with_proof_cache(capacity))
+    }
+
+    /// Configures the [RetentionPolicy] enforced by the writer of the storage layer, so that the
+    /// proofs of this directory account for the history it prunes, see
+    /// [Directory::with_retention_policy]. A read-only directory does not enforce the policy.
+    pub fn with_retention_policy(self, policy: RetentionPolicy) -> Self {
+        Self(self.0.with_retention_policy(policy))
+    }
+
+    /// Configures the [EpochSigner] which signs the responses of
+    /// [ReadOnlyDirectory::lookup_with_challenge], see [Directory::with_epoch_signer]. This
+    /// should be the signer of the writer of the storage layer, so that clients verify the
+    /// responses with the same public key as the root hashes.
+    pub fn with_epoch_signer<ES: EpochSigner + 'static>(self, signer: ES) -> Self {
+        Self(self.0.with_epoch_signer(signer))
+    }
+
+    /// Read-only access to [Directory::proof_cache_stats]
+    pub fn proof_cache_stats(&self) -> Option<ProofCacheStats> {
+        self.0.proof_cache_stats()
+    }
+
     /// Read-only access to [Directory::lookup](Directory::lookup).
     pub async fn lookup(&self, uname: AkdLabel) -> Result<(LookupProof, EpochHash), AkdError> {
         self.0.lookup(uname).await
     }
 
+    /// Read-only access to [Directory::lookup_with_challenge].
+    pub async fn lookup_with_challenge(
+        &self,
+        akd_label: AkdLabel,
+        challenge: &[u8],
+    ) -> Result<(LookupProof, EpochHash, Vec<u8>), AkdError> {
+        self.0.lookup_with_challenge(akd_label, challenge).await
+    }
+
+    /// Read-only access to [Directory::lookup_absent].
+    pub async fn lookup_absent(
+        &self,
+        uname: AkdLabel,
+    ) -> Result<(NonMembershipLookupProof, EpochHash), AkdError> {
+        self.0.lookup_absent(uname).await
+    }
+
     /// Read-only access to [Directory::batch_lookup](Directory::batch_lookup).
     pub async fn batch_lookup(
         &self,
@@ -838,6 +4337,14 @@ where
         self.0.batch_lookup(unames).await
     }
 
+    /// Read-only access to [Directory::precomputed_lookup].
+    pub async fn precomputed_lookup(
+        &self,
+        uname: AkdLabel,
+    ) -> Result<(LookupProof, EpochHash), AkdError> {
+        self.0.precomputed_lookup(uname).await
+    }
+
     /// Read-only access to [Directory::key_history](Directory::key_history).
     pub async fn key_history(
         &self,
@@ -847,10 +4354,63 @@ where
         self.0.key_history(uname, params).await
     }
 
+    /// Read-only access to [Directory::key_history_since].
+    pub async fn key_history_since(
+        &self,
+        uname: &AkdLabel,
+        verified_version: u64,
+        verified_epoch: u64,
+    ) -> Result<(HistoryProof, EpochHash), AkdError> {
+        self.0
+            .key_history_since(uname, verified_version, verified_epoch)
+            .await
+    }
+
+    /// Read-only access to [Directory::history_params].
+    pub fn history_params(&self, params: HistoryParams) -> HistoryParams {
+        self.0.history_params(params)
+    }
+
+    /// Read-only access to [Directory::key_history_bounded].
+    pub async fn key_history_bounded(
+        &self,
+        uname: &AkdLabel,
+        max_bytes: usize,
+    ) -> Result<(HistoryProof, EpochHash, HistoryParams), AkdError> {
+        self.0.key_history_bounded(uname, max_bytes).await
+    }
+
+    /// Read-only access to [Directory::key_history_cancellable].
+    pub async fn key_history_cancellable(
+        &self,
+        uname: &AkdLabel,
+        params: HistoryParams,
+        cancellation: &CancellationToken,
+    ) -> Result<(HistoryProof, EpochHash), AkdError> {
+        self.0
+            .key_history_cancellable(uname, params, cancellation)
+            .await
+    }
+
+    /// Read-only access to [Directory::vrf_rotation_certificate].
+    pub async fn vrf_rotation_certificate(
+        &self,
+    ) -> Result<Option<VrfRotationCertificate>, AkdError> {
+        self.0.vrf_rotation_certificate().await
+    }
+
+    /// Read-only access to [Directory::key_rotation_proof].
+    pub async fn key_rotation_proof(
+        &self,
+        uname: &AkdLabel,
+    ) -> Result<(KeyRotationProof, EpochHash), AkdError> {
+        self.0.key_rotation_proof(uname).await
+    }
+
     /// Read-only access to [Directory::poll_for_azks_changes](Directory::poll_for_azks_changes).
     pub async fn poll_for_azks_changes(
         &self,
-        period: tokio::time::Duration,
+        period: Duration,
         change_detected: Option<tokio::sync::mpsc::Sender<()>>,
     ) -> Result<(), AkdError> {
         self.0.poll_for_azks_changes(period, change_detected).await
@@ -865,6 +4425,40 @@ where
         self.0.audit(audit_start_ep, audit_end_ep).await
     }
 
+    /// Read-only access to [Directory::audit_cancellable].
+    pub async fn audit_cancellable(
+        &self,
+        audit_start_ep: u64,
+        audit_end_ep: u64,
+        cancellation: &CancellationToken,
+    ) -> Result<AppendOnlyProof, AkdError> {
+        self.0
+            .audit_cancellable(audit_start_ep, audit_end_ep, cancellation)
+            .await
+    }
+
+    /// Read-only access to [Directory::audit_stream].
+    pub fn audit_stream(
+        &self,
+        audit_start_ep: u64,
+        audit_end_ep: u64,
+        segment_size: usize,
+    ) -> impl Stream<Item = Result<AuditProofSegment, AkdError>> + Send + '_ {
+        self.0
+            .audit_stream(audit_start_ep, audit_end_ep, segment_size)
+    }
+
+    /// Read-only access to [Directory::audit_range_compressed].
+    pub async fn audit_range_compressed(
+        &self,
+        audit_start_ep: u64,
+        audit_end_ep: u64,
+    ) -> Result<CompressedAppendOnlyProof, AkdError> {
+        self.0
+            .audit_range_compressed(audit_start_ep, audit_end_ep)
+            .await
+    }
+
     /// Read-only access to [Directory::get_epoch_hash].
     pub async fn get_epoch_hash(&self) -> Result<EpochHash, AkdError> {
         self.0.get_epoch_hash().await
@@ -874,15 +4468,200 @@ where
     pub async fn get_public_key(&self) -> Result<VRFPublicKey, AkdError> {
         self.0.get_public_key().await
     }
+
+    /// Read-only access to [Directory::get_public_key_bytes].
+    pub async fn get_public_key_bytes(&self) -> Result<Vec<u8>, AkdError> {
+        self.0.get_public_key_bytes().await
+    }
+
+    /// Read-only access to [Directory::stats].
+    pub async fn stats(&self) -> Result<DirectoryStats, AkdError> {
+        self.0.stats().await
+    }
+
+    /// Read-only access to [Directory::diff].
+    pub async fn diff(&self, epoch_a: u64, epoch_b: u64) -> Result<EpochDiff, AkdError> {
+        self.0.diff(epoch_a, epoch_b).await
+    }
+
+    /// Read-only access to [Directory::replay_epoch].
+    pub async fn replay_epoch(
+        &self,
+        epoch: u64,
+        updates: Vec<(AkdLabel, AkdValue)>,
+    ) -> Result<EpochReplay, AkdError> {
+        self.0.replay_epoch(epoch, updates).await
+    }
+
+    /// Read-only access to [Directory::health_check].
+    pub async fn health_check(&self) -> HealthReport {
+        self.0.health_check().await
+    }
+
+    /// Read-only access to [Directory::list_labels].
+    pub async fn list_labels(
+        &self,
+        cursor: Option<AkdLabel>,
+        limit: usize,
+        include_state: bool,
+    ) -> Result<LabelPage, AkdError> {
+        self.0.list_labels(cursor, limit, include_state).await
+    }
+
+    /// Read-only access to [Directory::get_epoch_metadata].
+    pub async fn get_epoch_metadata(&self, epoch: u64) -> Result<EpochMetadata, AkdError> {
+        self.0.get_epoch_metadata(epoch).await
+    }
+
+    /// Read-only access to [Directory::get_update_journal].
+    pub async fn get_update_journal(&self, epoch: u64) -> Result<UpdateJournal, AkdError> {
+        self.0.get_update_journal(epoch).await
+    }
+
+    /// Read-only access to [Directory::get_epoch_hashes].
+    pub async fn get_epoch_hashes(
+        &self,
+        start_epoch: u64,
+        end_epoch: u64,
+    ) -> Result<EpochRootChain, AkdError> {
+        self.0.get_epoch_hashes(start_epoch, end_epoch).await
+    }
+
+    /// Read-only access to [Directory::stream_update_journals].
+    pub fn stream_update_journals(
+        &self,
+        start_epoch: u64,
+        end_epoch: u64,
+    ) -> impl Stream<Item = Result<UpdateJournal, AkdError>> + Send + '_ {
+        self.0.stream_update_journals(start_epoch, end_epoch)
+    }
+
+    /// Read-only access to [Directory::fork_at], which only writes to the new storage.
+    pub async fn fork_at<NS: Database + 'static>(
+        &self,
+        epoch: u64,
+        new_storage: StorageManager<NS>,
+    ) -> Result<(Directory<TC, NS, V>, EpochHash), AkdError> {
+        self.0.fork_at(epoch, new_storage).await
+    }
+
+    /// Read-only access to [Directory::get_epoch_metadata_proof].
+    pub async fn get_epoch_metadata_proof(
+        &self,
+        epoch: u64,
+    ) -> Result<(EpochMetadataProof, EpochHash), AkdError> {
+        self.0.get_epoch_metadata_proof(epoch).await
+    }
+}
+
+// Helpers
+
+/// Pads a value as configured by [Configuration::value_padding_bucket_size], which is the value
+/// that is committed to and included in proofs
+fn pad_value<TC: Configuration>(value: &AkdValue) -> Cow<'_, AkdValue> {
+    match TC::value_padding_bucket_size() {
+        Some(bucket_size) => Cow::Owned(value.pad(bucket_size)),
+        None => Cow::Borrowed(value),
+    }
+}
+
+/// Derives the nonce of the commitment to a (padded) value, co-derived with the blinding supplied
+/// by the client if there is one
+fn get_commitment_nonce<TC: Configuration>(
+    commitment_key: &[u8],
+    label: &NodeLabel,
+    version: u64,
+    value: &AkdValue,
+    blinding: Option<&[u8]>,
+) -> Digest {
+    let value = &*pad_value::<TC>(value);
+    match blinding {
+        Some(blinding) => {
+            TC::get_blinded_commitment_nonce(commitment_key, label, version, value, blinding)
+        }
+        None => TC::get_commitment_nonce(commitment_key, label, version, value),
+    }
+}
+
+/// Computes the commitment to a fresh (padded) value, with the blinding supplied by the client if
+/// there is one
+fn compute_azks_value<TC: Configuration>(
+    commitment_key: &[u8],
+    label: &NodeLabel,
+    version: u64,
+    value: &AkdValue,
+    blinding: Option<&[u8]>,
+) -> AzksValue {
+    let value = &*pad_value::<TC>(value);
+    match blinding {
+        Some(blinding) => {
+            TC::compute_blinded_azks_value(commitment_key, label, version, value, blinding)
+        }
+        None => TC::compute_fresh_azks_value(commitment_key, label, version, value),
+    }
+}
+
+/// Builds the journal of the updates of an epoch (see [Directory::with_update_journal]), whose
+/// hashed labels are keyed with the commitment key
+fn build_update_journal<TC: Configuration>(
+    mode: UpdateJournalMode,
+    epoch: u64,
+    updates: &[ValueState],
+    commitment_key: &[u8],
+) -> UpdateJournal {
+    let mut entries = updates
+        .iter()
+        .map(|state| JournalEntry {
+            label: match mode {
+                UpdateJournalMode::Labels => JournalLabel::Label(state.username.clone()),
+                UpdateJournalMode::HashedLabels => JournalLabel::Hashed(TC::hash(
+                    &[
+                        UPDATE_JOURNAL_DOMAIN_SEPARATOR,
+                        commitment_key,
+                        &state.username,
+                    ]
+                    .concat(),
+                )),
+            },
+            version: state.version,
+        })
+        .collect::<Vec<_>>();
+    entries.sort_unstable_by(|a, b| a.label.cmp(&b.label));
+    UpdateJournal { epoch, entries }
 }
 
-/// Helpers
+/// Derives the decoy leaves inserted into the tree in an epoch (see [Directory::with_decoy_leaves])
+fn derive_decoy_leaves<TC: Configuration>(
+    commitment_key: &[u8],
+    epoch: u64,
+    count: usize,
+) -> Vec<AzksElement> {
+    (0..count as u64)
+        .map(|index| {
+            let derive = |kind: &[u8]| {
+                TC::hash_with_purpose(
+                    HashPurpose::Decoy,
+                    &[
+                        commitment_key,
+                        kind,
+                        &epoch.to_be_bytes(),
+                        &index.to_be_bytes(),
+                    ],
+                )
+            };
+            AzksElement {
+                label: NodeLabel::new(derive(b"label"), 256),
+                value: AzksValue(derive(b"value")),
+            }
+        })
+        .collect()
+}
 
 pub(crate) fn get_marker_version(version: u64) -> u64 {
     (64 - version.leading_zeros() - 1).into()
 }
 
-/// Helpers for testing
+// Helpers for testing
 
 /// This enum is meant to insert corruptions into a malicious publish function.
 #[derive(Debug, Clone)]
@@ -960,14 +4739,19 @@ impl<TC: Configuration, S: Database + 'static, V: VRFKeyStorage> Directory<TC, S
                         .get_node_label::<TC>(&akd_label, VersionFreshness::Fresh, latest_version)
                         .await?;
 
-                    let value_to_add =
-                        TC::compute_fresh_azks_value(&commitment_key, &label, latest_version, &val);
+                    let value_to_add = compute_azks_value::<TC>(
+                        &*commitment_key,
+                        &label,
+                        latest_version,
+                        &val,
+                        None,
+                    );
                     update_set.push(AzksElement {
                         label,
                         value: value_to_add,
                     });
                     let latest_state =
-                        ValueState::new(akd_label, val, latest_version, label, next_epoch);
+                        ValueState::new(akd_label, val, latest_version, label, next_epoch, None);
                     user_data_update_set.push(latest_state);
                 }
                 Some((_, previous_value)) if val == *previous_value => {
@@ -990,11 +4774,12 @@ impl<TC: Configuration, S: Database + 'static, V: VRFKeyStorage> Directory<TC, S
                         .get_node_label::<TC>(&akd_label, VersionFreshness::Fresh, latest_version)
                         .await?;
                     let stale_value_to_add = TC::stale_azks_value();
-                    let fresh_value_to_add = TC::compute_fresh_azks_value(
-                        &commitment_key,
+                    let fresh_value_to_add = compute_azks_value::<TC>(
+                        &*commitment_key,
                         &fresh_label,
                         latest_version,
                         &val,
+                        None,
                     );
                     match &corruption {
                         // Some malicious server might not want to mark an old and compromised key as stale.
@@ -1018,8 +4803,14 @@ impl<TC: Configuration, S: Database + 'static, V: VRFKeyStorage> Directory<TC, S
                         label: fresh_label,
                         value: fresh_value_to_add,
                     });
-                    let new_state =
-                        ValueState::new(akd_label, val, latest_version, fresh_label, next_epoch);
+                    let new_state = ValueState::new(
+                        akd_label,
+                        val,
+                        latest_version,
+                        fresh_label,
+                        next_epoch,
+                        None,
+                    );
                     user_data_update_set.push(new_state);
                 }
             }
@@ -1033,7 +4824,7 @@ impl<TC: Configuration, S: Database + 'static, V: VRFKeyStorage> Directory<TC, S
             return Ok(EpochHash(current_epoch, root_hash));
         }
 
-        if let false = self.storage.begin_transaction() {
+        if !self.storage.begin_transaction() {
             error!("Transaction is already active");
             return Err(AkdError::Storage(StorageError::Transaction(
                 "Transaction is already active".to_string(),
@@ -1055,7 +4846,7 @@ impl<TC: Configuration, S: Database + 'static, V: VRFKeyStorage> Directory<TC, S
         // now commit the transaction
         if let Err(err) = self.storage.commit_transaction().await {
             // ignore any rollback error(s)
-            let _ = self.storage.rollback_transaction();
+            let _ = self.storage.rollback_transaction().await;
             return Err(AkdError::Storage(err));
         }
 
@@ -0,0 +1,182 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Hooks for distributing the root hash of each published epoch to external parties
+//! (e.g. a transparency log, or gossiping witnesses), which allows them to detect a
+//! directory which presents different histories to different clients.
+//!
+//! An [EpochPublisher] registered with [crate::Directory::with_epoch_publisher] is invoked
+//! after each successful publish, with the root hash and the signature of the new epoch
+//! (if the directory was configured with a [crate::signature::EpochSigner]).
+//! [LogFileEpochPublisher] is a sample implementation which appends the signed root hashes
+//! to a local append-only file.
+
+use crate::Digest;
+
+use async_trait::async_trait;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tokio::sync::Mutex;
+
+/// Errors thrown when distributing the root hash of an epoch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EpochPublisherError {
+    /// The root hash could not be delivered, e.g. due to an I/O or network failure
+    Delivery(String),
+    /// The epoch does not follow the latest epoch which was previously distributed
+    OutOfOrder {
+        /// The latest epoch which was distributed
+        latest: u64,
+        /// The epoch which was attempted to be distributed
+        epoch: u64,
+    },
+    /// The existing log could not be parsed
+    MalformedLog(String),
+}
+
+impl std::error::Error for EpochPublisherError {}
+
+impl fmt::Display for EpochPublisherError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Delivery(err) => write!(f, "Failed to deliver the root hash: {err}"),
+            Self::OutOfOrder { latest, epoch } => write!(
+                f,
+                "Epoch {epoch} cannot be published after the latest published epoch {latest}"
+            ),
+            Self::MalformedLog(err) => write!(f, "Malformed epoch log: {err}"),
+        }
+    }
+}
+
+/// Distributes the root hash of each published epoch. The signature is present
+/// if the directory signs its epochs, and can be verified against the public key
+/// of the signer with [crate::client::verify_epoch_signature].
+#[async_trait]
+pub trait EpochPublisher: Send + Sync {
+    /// Distribute the root hash of a newly published epoch
+    async fn publish_epoch(
+        &self,
+        epoch: u64,
+        root_hash: Digest,
+        signature: Option<&[u8]>,
+    ) -> Result<(), EpochPublisherError>;
+}
+
+/// An entry of the log written by [LogFileEpochPublisher]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpochLogEntry {
+    /// The published epoch
+    pub epoch: u64,
+    /// The root hash of the epoch
+    pub root_hash: Digest,
+    /// The signature over the root hash, if the epoch was signed
+    pub signature: Option<Vec<u8>>,
+}
+
+impl EpochLogEntry {
+    /// Encodes the entry as a line of the log: the epoch, followed by the hex-encoded root
+    /// hash and signature (or `-` when unsigned), separated by spaces
+    fn to_line(&self) -> String {
+        let signature = self
+            .signature
+            .as_ref()
+            .map_or_else(|| "-".to_string(), hex::encode);
+        format!(
+            "{} {} {}\n",
+            self.epoch,
+            hex::encode(self.root_hash),
+            signature
+        )
+    }
+
+    fn from_line(line: &str) -> Result<Self, EpochPublisherError> {
+        let malformed = || EpochPublisherError::MalformedLog(format!("Invalid entry '{line}'"));
+        let parts = line.split(' ').collect::<Vec<_>>();
+        let [epoch, root_hash, signature] = parts.as_slice() else {
+            return Err(malformed());
+        };
+        let root_hash = hex::decode(root_hash).map_err(|_| malformed())?;
+        Ok(Self {
+            epoch: epoch.parse().map_err(|_| malformed())?,
+            root_hash: root_hash.try_into().map_err(|_| malformed())?,
+            signature: match *signature {
+                "-" => None,
+                signature => Some(hex::decode(signature).map_err(|_| malformed())?),
+            },
+        })
+    }
+}
+
+/// Appends the root hash (and signature) of each published epoch to a local file, one line
+/// per epoch. Epochs must be appended in increasing order, so that the file forms an
+/// append-only history which can be handed to auditors or replicated to other parties.
+pub struct LogFileEpochPublisher {
+    path: PathBuf,
+    /// The latest epoch in the log
+    latest: Mutex<Option<u64>>,
+}
+
+impl LogFileEpochPublisher {
+    /// Creates a publisher which appends to the log at the provided path, which is created
+    /// if it does not exist yet
+    pub fn new(path: impl AsRef<Path>) -> Result<Self, EpochPublisherError> {
+        let path = path.as_ref().to_path_buf();
+        let latest = if path.exists() {
+            Self::read_log(&path)?.last().map(|entry| entry.epoch)
+        } else {
+            None
+        };
+        Ok(Self {
+            path,
+            latest: Mutex::new(latest),
+        })
+    }
+
+    /// Reads all the entries of the log at the provided path
+    pub fn read_log(path: impl AsRef<Path>) -> Result<Vec<EpochLogEntry>, EpochPublisherError> {
+        std::fs::read_to_string(path)
+            .map_err(|err| EpochPublisherError::MalformedLog(err.to_string()))?
+            .lines()
+            .map(EpochLogEntry::from_line)
+            .collect()
+    }
+}
+
+#[async_trait]
+impl EpochPublisher for LogFileEpochPublisher {
+    async fn publish_epoch(
+        &self,
+        epoch: u64,
+        root_hash: Digest,
+        signature: Option<&[u8]>,
+    ) -> Result<(), EpochPublisherError> {
+        let mut latest = self.latest.lock().await;
+        if let Some(latest) = *latest {
+            if epoch <= latest {
+                return Err(EpochPublisherError::OutOfOrder { latest, epoch });
+            }
+        }
+        let entry = EpochLogEntry {
+            epoch,
+            root_hash,
+            signature: signature.map(|signature| signature.to_vec()),
+        };
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|err| EpochPublisherError::Delivery(err.to_string()))?;
+        file.write_all(entry.to_line().as_bytes())
+            .and_then(|_| file.sync_data())
+            .map_err(|err| EpochPublisherError::Delivery(err.to_string()))?;
+        *latest = Some(epoch);
+        Ok(())
+    }
+}
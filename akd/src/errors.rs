@@ -23,6 +23,8 @@ pub enum AkdError {
     AzksErr(AzksError),
     /// Vrf related error
     Vrf(akd_core::ecvrf::VrfError),
+    /// Epoch signing related error
+    Signature(akd_core::signature::SignatureError),
     /// Storage layer error thrown
     Storage(StorageError),
     /// Audit verification error thrown
@@ -59,6 +61,12 @@ impl From<akd_core::ecvrf::VrfError> for AkdError {
     }
 }
 
+impl From<akd_core::signature::SignatureError> for AkdError {
+    fn from(error: akd_core::signature::SignatureError) -> Self {
+        Self::Signature(error)
+    }
+}
+
 impl From<AzksError> for AkdError {
     fn from(error: AzksError) -> Self {
         Self::AzksErr(error)
@@ -98,6 +106,9 @@ impl std::fmt::Display for AkdError {
             AkdError::Vrf(err) => {
                 writeln!(f, "AKD VRF Error: {err}")
             }
+            AkdError::Signature(err) => {
+                writeln!(f, "AKD Signature Error: {err}")
+            }
             AkdError::Storage(err) => {
                 writeln!(f, "AKD Storage Error: {err}")
             }
@@ -232,6 +243,20 @@ pub enum DirectoryError {
     Publish(String),
     /// Detected an invalid version
     InvalidVersion(String),
+    /// The request was rejected by the admission controller
+    Overloaded(String),
+    /// The operation was aborted through its cancellation token
+    Cancelled(String),
+    /// Tried to prove the absence of a label which exists
+    LabelExists(String),
+    /// An error related to the rotation of the VRF key
+    KeyRotation(String),
+    /// The value which a proof would reveal was removed from storage (see [crate::AkdValue::Tombstone])
+    Tombstoned(String),
+    /// No proof could be generated within the requested size budget
+    ProofTooLarge(String),
+    /// The configuration of a [crate::DirectoryBuilder] was rejected
+    InvalidConfiguration(ConfigurationError),
 }
 
 impl std::error::Error for DirectoryError {}
@@ -254,6 +279,81 @@ impl fmt::Display for DirectoryError {
             Self::InvalidVersion(inner_message) => {
                 write!(f, "Invalid version error: {inner_message}")
             }
+            Self::Overloaded(inner_message) => {
+                write!(f, "Directory overloaded: {inner_message}")
+            }
+            Self::Cancelled(inner_message) => {
+                write!(f, "Operation cancelled: {inner_message}")
+            }
+            Self::LabelExists(inner_message) => {
+                write!(f, "Label exists: {inner_message}")
+            }
+            Self::KeyRotation(inner_message) => {
+                write!(f, "Key rotation error: {inner_message}")
+            }
+            Self::Tombstoned(inner_message) => {
+                write!(f, "Tombstoned value: {inner_message}")
+            }
+            Self::ProofTooLarge(inner_message) => {
+                write!(f, "Proof too large: {inner_message}")
+            }
+            Self::InvalidConfiguration(err) => {
+                write!(f, "Invalid configuration: {err}")
+            }
+        }
+    }
+}
+
+impl From<ConfigurationError> for DirectoryError {
+    fn from(err: ConfigurationError) -> Self {
+        Self::InvalidConfiguration(err)
+    }
+}
+
+impl From<ConfigurationError> for AkdError {
+    fn from(err: ConfigurationError) -> Self {
+        Self::Directory(err.into())
+    }
+}
+
+/// The errors thrown when validating the configuration of a [crate::DirectoryBuilder]
+#[derive(Debug, Eq, PartialEq)]
+pub enum ConfigurationError {
+    /// An option was set to a value outside of its valid range
+    InvalidOption {
+        /// The name of the option
+        option: &'static str,
+        /// Why the value is invalid
+        reason: String,
+    },
+    /// The VRF key storage could not provide the VRF public key
+    VrfKey(akd_core::ecvrf::VrfError),
+    /// The epoch signer could not provide its public key
+    EpochSigner(akd_core::signature::SignatureError),
+    /// A metrics recorder was already installed in the process
+    MetricsRecorder,
+}
+
+impl std::error::Error for ConfigurationError {}
+
+impl fmt::Display for ConfigurationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidOption { option, reason } => {
+                write!(f, "Invalid value of {option}: {reason}")
+            }
+            Self::VrfKey(err) => {
+                write!(f, "The VRF public key could not be retrieved: {err}")
+            }
+            Self::EpochSigner(err) => {
+                write!(
+                    f,
+                    "The epoch signer's public key could not be retrieved: {err}"
+                )
+            }
+            Self::MetricsRecorder => {
+                write!(f, "A metrics recorder was already installed")
+            }
         }
     }
 }
@@ -305,6 +405,8 @@ impl fmt::Display for StorageError {
 pub enum AuditorError {
     /// A general auditor error
     VerifyAuditProof(String),
+    /// A persisted [crate::auditor::AuditorState] could not be decoded
+    InvalidState(String),
 }
 
 impl std::error::Error for AuditorError {}
@@ -315,6 +417,9 @@ impl fmt::Display for AuditorError {
             Self::VerifyAuditProof(err_string) => {
                 write!(f, "Failed to verify audit {err_string}")
             }
+            Self::InvalidState(err_string) => {
+                write!(f, "Invalid auditor state: {err_string}")
+            }
         }
     }
 }
@@ -325,6 +430,8 @@ impl fmt::Display for AuditorError {
 pub enum ParallelismError {
     /// A tokio task join error
     JoinErr(String),
+    /// The runtime of a [crate::blocking::Directory] could not be started
+    Runtime(String),
 }
 
 impl std::error::Error for ParallelismError {}
@@ -335,6 +442,9 @@ impl fmt::Display for ParallelismError {
             Self::JoinErr(err_string) => {
                 write!(f, "Failed to join tokio task {err_string}")
             }
+            Self::Runtime(err_string) => {
+                write!(f, "Failed to start the runtime {err_string}")
+            }
         }
     }
 }
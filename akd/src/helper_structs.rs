@@ -8,8 +8,10 @@
 //! Helper structs that are used for various data structures,
 //! to make it easier to pass arguments around.
 
+use crate::storage::manager::StorageStats;
+use crate::storage::types::{LabelEntry, ValueState};
 use crate::Digest;
-use crate::{storage::types::ValueState, NodeLabel};
+use crate::{AkdLabel, NodeLabel};
 
 /// Root hash of the tree and its associated epoch
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
@@ -26,6 +28,144 @@ impl EpochHash {
     }
 }
 
+/// The outcome of a dry-run publish, as computed by [crate::Directory::preview_publish]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublishPreview {
+    /// The epoch and root hash which publishing the updates would result in. This is [None]
+    /// if the updates contain duplicate labels, in which case publishing would be rejected.
+    pub epoch_hash: Option<EpochHash>,
+    /// Labels which appear more than once in the updates
+    pub duplicate_labels: Vec<AkdLabel>,
+    /// The number of labels which do not exist in the directory yet
+    pub new_labels: u64,
+    /// The number of existing labels which would receive a new value
+    pub updated_labels: u64,
+    /// The number of labels which would be skipped, as their value is unchanged
+    pub unchanged_labels: u64,
+    /// The number of leaves which would be inserted into the tree
+    pub inserted_leaves: u64,
+    /// The number of nodes in the tree after publishing
+    pub num_nodes: u64,
+}
+
+/// A page of labels, as enumerated by [crate::Directory::list_labels]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelPage {
+    /// The labels in the page, in ascending order
+    pub entries: Vec<LabelEntry>,
+    /// The cursor from which to retrieve the next page, or [None] if this is the last page
+    pub next_cursor: Option<AkdLabel>,
+}
+
+/// Statistics of a directory and its storage, as reported by [crate::Directory::stats]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Deserialize, serde::Serialize)
+)]
+pub struct DirectoryStats {
+    /// The current epoch
+    pub epoch: u64,
+    /// The number of labels registered in the directory, or [None] if the storage layer
+    /// does not support counting them (see [crate::storage::Database::count_labels])
+    pub total_labels: Option<u64>,
+    /// The number of nodes in the tree
+    pub total_tree_nodes: u64,
+    /// The duration of the most recent successful publish by this directory instance, in
+    /// milliseconds, or [None] if it has not published yet
+    pub last_publish_duration_ms: Option<u64>,
+    /// The number of proof generations which were rejected by the admission controller
+    /// (see [crate::Directory::with_admission_control])
+    pub rejected_proof_generations: u64,
+    /// Cache and storage access statistics
+    pub storage: StorageStats,
+}
+
+/// A summary of the changes to a directory between two epochs, as computed by
+/// [crate::Directory::diff]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Deserialize, serde::Serialize)
+)]
+pub struct EpochDiff {
+    /// The earlier epoch, whose state is the baseline of the summary
+    pub from_epoch: u64,
+    /// The later epoch
+    pub to_epoch: u64,
+    /// The number of labels registered in the directory as of the later epoch
+    pub total_labels: u64,
+    /// The number of labels which were registered after the earlier epoch
+    pub labels_added: u64,
+    /// The number of labels which existed as of the earlier epoch and received a new value
+    pub labels_updated: u64,
+    /// The number of values published for the added and updated labels, which exceeds the
+    /// number of these labels if some of them were updated more than once
+    pub value_updates: u64,
+    /// The number of leaves inserted into the tree, i.e. the growth of the tree. This includes
+    /// the stale markers of the previous versions of the updated labels, along with any decoy
+    /// leaves and epoch metadata commitments.
+    pub leaves_inserted: u64,
+}
+
+/// The outcome of the re-execution of a published epoch, as reported by
+/// [crate::Directory::replay_epoch]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Deserialize, serde::Serialize)
+)]
+pub struct EpochReplay {
+    /// The epoch which was re-executed
+    pub epoch: u64,
+    /// The root hash of the epoch, as stored in the tree
+    pub stored_root_hash: Digest,
+    /// The root hash of the epoch, as recomputed from the previous epoch and the updates
+    pub replayed_root_hash: Digest,
+    /// Whether the previous epoch, as recomputed from the stored value states, has the root
+    /// hash which is stored in the tree. If not, the replayed root hash is not expected to
+    /// match either, as the corruption precedes the epoch.
+    pub previous_root_consistent: bool,
+}
+
+impl EpochReplay {
+    /// Whether the epoch was re-executed to the root hash which is stored in the tree
+    pub fn matches(&self) -> bool {
+        self.previous_root_consistent && self.stored_root_hash == self.replayed_root_hash
+    }
+}
+
+/// The outcome of a health check of a directory, as reported by [crate::Directory::health_check],
+/// e.g. for a load balancer's readiness probe
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Deserialize, serde::Serialize)
+)]
+pub struct HealthReport {
+    /// The latest epoch, or [None] if the tree could not be read from storage
+    pub epoch: Option<u64>,
+    /// Whether the tree could be read from storage
+    pub storage_reachable: bool,
+    /// How long reading the tree from storage took, in milliseconds
+    pub storage_latency_ms: u64,
+    /// Whether the stored values of the nodes on the sampled paths of the tree match the values
+    /// recomputed from their children, up to the root. This is [None] if the check could not
+    /// be completed.
+    pub root_hash_consistent: Option<bool>,
+    /// Whether a VRF proof could be generated with the VRF key of the directory
+    pub vrf_key_available: bool,
+    /// A description of each of the checks which failed
+    pub failures: Vec<String>,
+}
+
+impl HealthReport {
+    /// Whether all of the checks passed, i.e. the directory is ready to serve requests
+    pub fn is_healthy(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
 #[derive(Clone, Debug)]
 /// Info needed for a lookup of a user for an epoch
 pub struct LookupInfo {
@@ -30,12 +30,12 @@
 //! This library supports the following operations for the directory it maintains:
 //! - [Publishing](#publishing): Allows the directory server to insert and update new entries into the directory.
 //! - [Lookup Proofs](#lookup-proofs): Handles point queries to the directory, providing proofs of validity based on the server's
-//! public key and a root hash for an epoch.
+//!   public key and a root hash for an epoch.
 //! - [History Proofs](#history-proofs): For a given index in the directory, provides proofs for the history of updates to this
-//! entry, matched against the server's public key and a root hash for an epoch.
+//!   entry, matched against the server's public key and a root hash for an epoch.
 //! - [Append-Only Proofs](#append-only-proofs): For a pair of epochs, provides a proof to an auditor that the database has evolved
-//! consistently and in an append-only manner. These append-only proofs use a verifiable random function (VRF)
-//! to avoid leaking any information about the labels and their corresponding values.
+//!   consistently and in an append-only manner. These append-only proofs use a verifiable random function (VRF)
+//!   to avoid leaking any information about the labels and their corresponding values.
 //!
 //!
 //! ### Asynchronicity
@@ -69,6 +69,27 @@
 //! # });
 //! ```
 //!
+//! Alternatively, a [`DirectoryBuilder`] validates the options of the directory (such as its
+//! caching, retention policy or epoch signer) before building it, and rejects an invalid
+//! configuration with a [`errors::ConfigurationError`]:
+//! ```
+//! use akd::builder::CachePolicy;
+//! use akd::storage::memory::AsyncInMemoryDatabase;
+//! use akd::ecvrf::HardCodedAkdVRF;
+//! use akd::directory::Directory;
+//!
+//! type Config = akd::WhatsAppV1Configuration;
+//!
+//! # tokio_test::block_on(async {
+//! let akd = Directory::<Config, _, _>::builder(AsyncInMemoryDatabase::new(), HardCodedAkdVRF{})
+//!     .with_cache_policy(CachePolicy::Disabled)
+//!     .with_proof_cache(1000)
+//!     .build()
+//!     .await
+//!     .expect("Could not create a new directory");
+//! # });
+//! ```
+//!
 //! For more information on setting configurations, see the [Configurations](#configurations) section.
 //!
 //! ## Publishing
@@ -254,12 +275,13 @@
 //! First, it encodes a [`HistoryParams`]. Note that the same argument for [`HistoryParams`] that was used to generate
 //! the key history proof must also be used to verify the proof. Otherwise, verification may fail.
 //!
-//! [`HistoryVerificationParams`] also allows the consumer to specify whether or not a "tombstoned" value should be
+//! [`HistoryVerificationParams`] also allows the consumer to specify whether or not a tombstone
+//! ([`AkdValue::Tombstone`], for a value which was removed from storage) should be
 //! accepted in place of a valid value for the corresponding entry. This is useful
 //! in scenarios where the consumer wishes to verify that a particular entry exists,
 //! but does not care about the value associated with it. The default behavior is to
-//! not accept tombstoned values, but [`HistoryVerificationParams::AllowMissingValues`] can
-//! be specified to enable this behavior.
+//! not accept tombstones, but [`HistoryVerificationParams::AllowMissingValues`] can
+//! be specified to enable this behavior, in which case the tombstones are returned as such.
 //! ```
 //! # use akd::storage::StorageManager;
 //! # use akd::storage::memory::AsyncInMemoryDatabase;
@@ -418,12 +440,23 @@
 //! ## Configurations
 //!
 //! This library supports the notion of a [Configuration], which can be used to customize the directory's cryptographic operations. We provide
-//! two default configurations: `WhatsAppV1Configuration` and `ExperimentalConfiguration`.
+//! three default configurations: `WhatsAppV1Configuration`, `ExperimentalConfiguration` and `Sha3Configuration`.
 //!
 //! - `WhatsAppV1Configuration` matches the configuration used for Whatsapp's key transparency deployment
 //! - `ExperimentalConfiguration` is the configuration which matches the main branch deployment for AKD
+//! - `Sha3Configuration` is the same as `ExperimentalConfiguration`, except that it hashes with SHA3-256
+//!   (rather than BLAKE3), for deployments with compliance requirements pinning SHA-3
+//! - `DomainSeparatedConfiguration` is the same as `ExperimentalConfiguration`, except that each hash is prefixed
+//!   with a tag for its purpose (leaf, parent, commitment or label derivation). Since its hashes differ, an existing
+//!   directory migrates to it by importing the latest values of its labels into a new directory (see [Directory::bulk_import])
+//!
+//! Any of these can be wrapped in a [PaddedConfiguration], e.g. `PaddedConfiguration<WhatsAppV1Configuration, 64>`,
+//! which pads each value to a multiple of the given number of bytes before committing to it, so that proofs do not
+//! reveal the exact length of a value.
+//!
+//! The `ExperimentalConfiguration` hashes with BLAKE3, and is also exported as `Blake3Configuration`.
 //!
-//! An `ExperimentalConfiguration` implements domain separation for its hashing operations by the specifying of a struct that
+//! An `ExperimentalConfiguration` (or `Sha3Configuration`) implements domain separation for its hashing operations by the specifying of a struct that
 //! implements [DomainLabel]. For example, to set the domain label as `"ExampleLabel"`, we define the struct [ExampleLabel] as:
 //! ```
 //! #[derive(Clone)]
@@ -444,6 +477,10 @@
 //! - [HistoryParams::Complete]: Includes a complete history of all updates to an entry. This is the default option.
 //! - [HistoryParams::MostRecent]: Includes (at most) the most recent input number of updates for an entry.
 //!
+//! A directory whose [retention::RetentionPolicy] bounds the number of versions retained per label only
+//! serves the most recent ones, in which case the proofs are verified with the parameters returned by
+//! [Directory::history_params] (see [HistoryParams::capped_to]).
+//!
 //! Note that the "insecure" options are not recommended for use in production, as they do not provide a
 //! complete history of updates, and lack inclusion proofs for earlier entries. These options should only be
 //! used for testing purposes.
@@ -455,56 +492,78 @@
 //!
 //! Configurations:
 //! - `whatsapp_v1`: Enables usage of `WhatsAppV1Configuration`
-//! - `experimental`: Enables usage of `ExperimentalConfiguration`
+//! - `experimental`: Enables usage of `ExperimentalConfiguration` (and `Blake3Configuration`)
+//! - `sha3_256`: Enables usage of `Sha3Configuration`
+//! - `domain_separated`: Enables usage of `DomainSeparatedConfiguration`, which prefixes each
+//!   hash with a tag for its purpose
+//! - `vrf_p256`: Enables the ECVRF-P256-SHA256-TAI VRF suite (`P256Sha256Tai`), which can be
+//!   selected with `ExperimentalConfiguration<L, P256Sha256Tai>`
 //!
 //! Performance optimizations:
 //! - `parallel_vrf`: Enables the VRF computations to be run in parallel
 //! - `parallel_insert`: Enables nodes to be inserted via multiple threads during a publish operation
+//! - `parallel_audit`: Enables audit proofs to be verified across multiple threads, split by subtree
 //! - `preload_history`: Enable pre-loading of the nodes when generating history proofs
 //! - `greedy_lookup_preload`: Greedy loading of lookup proof nodes
 //!
 //! Benchmarking:
-//! - `bench`: Feature used when running benchmarks
+//! - `bench`: Feature used when running benchmarks, which are run for each of the configurations
+//!   so that the throughput of their hash functions can be compared
 //! - `slow_internal_db`: Artifically slow the in-memory database (for benchmarking)
+//! - `profiling`: Runs the tree insertions, append-only proof generation and VRF evaluations
+//!   sequentially on the calling task, regardless of the parallelism features, so that flamegraphs
+//!   of a directory on a single-threaded runtime follow a single call stack
 //!
 //! Utilities:
 //! - `public_auditing`: Enables the publishing of audit proofs
 //! - `serde_serialization`: Will enable `serde` serialization support on all public structs used in storage & transmission operations. This is helpful
-//! in the event you wish to directly serialize the structures to transmit between library <-> storage layer or library <-> clients. If you're
-//! also utilizing VRFs (see (2.) below) it will additionally enable the _serde_ feature in the ed25519-dalek crate.
+//!   in the event you wish to directly serialize the structures to transmit between library <-> storage layer or library <-> clients. If you're
+//!   also utilizing VRFs (see (2.) below) it will additionally enable the _serde_ feature in the ed25519-dalek crate.
 //! - `runtime_metrics`: Collects metrics on the accesses to the storage layer
 //! - `public_tests`: Will expose some internal sanity testing functionality, which is often helpful so you don't have to write all your own
-//! unit test cases when implementing a storage layer yourself. This helps guarantee the sanity of a given storage implementation. Should be
-//! used only in unit testing scenarios by altering your Cargo.toml as such:
+//!   unit test cases when implementing a storage layer yourself. This helps guarantee the sanity of a given storage implementation. Should be
+//!   used only in unit testing scenarios by altering your Cargo.toml as such:
 //!
 
 #![warn(missing_docs)]
 #![allow(clippy::multiple_crate_versions)]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
-#[cfg(feature = "rand")]
-extern crate rand;
-
 // Due to the amount of types an implementing storage layer needs to access,
 // it's quite unreasonable to expose them all at the crate root, and a storage
 // implementer will simply need to import the necessary inner types which are
 // a dependency of ths [`Storage`] trait anyways
 
+pub mod admission;
 pub mod append_only_zks;
 pub mod auditor;
+#[cfg(feature = "tokio_runtime")]
+pub mod blocking;
+pub mod builder;
+pub mod cancellation;
 pub mod client;
+pub mod clock;
 pub mod directory;
+pub mod epoch_publisher;
 pub mod errors;
 pub mod helper_structs;
+pub mod merge;
+pub mod proof_cache;
+pub mod publisher;
+pub mod retention;
+pub mod runtime;
 pub mod storage;
+pub mod telemetry;
 pub mod tree_node;
 
 #[cfg(feature = "public_auditing")]
 pub mod local_auditing;
+#[cfg(feature = "simulation")]
+pub mod simulation;
 
 pub use akd_core::{
-    configuration, configuration::*, ecvrf, hash, hash::Digest, proto, types::*, verify,
-    verify::history::HistoryParams, ARITY,
+    bundle, configuration, configuration::*, ecvrf, hash, hash::Digest, proto, signature, types::*,
+    verify, verify::history::HistoryParams, ARITY,
 };
 
 #[macro_use]
@@ -512,12 +571,19 @@ mod utils;
 
 // ========== Type re-exports which are commonly used ========== //
 pub use append_only_zks::Azks;
+pub use builder::DirectoryBuilder;
 pub use client::HistoryVerificationParams;
 pub use directory::Directory;
-pub use helper_structs::EpochHash;
+pub use helper_structs::{
+    DirectoryStats, EpochDiff, EpochHash, EpochReplay, HealthReport, LabelPage, PublishPreview,
+};
 
 // ========== Constants and type aliases ========== //
 #[cfg(any(test, feature = "public_tests"))]
+pub mod azks_property_tests;
+#[cfg(any(test, feature = "public_tests"))]
+pub mod compatibility_tests;
+#[cfg(any(test, feature = "public_tests"))]
 pub mod test_utils;
 #[cfg(test)]
 mod tests;
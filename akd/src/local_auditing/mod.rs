@@ -0,0 +1,1216 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! This module contains all the type conversions between internal AKD & message types
+//! with the protobuf types
+//!
+//! Additionally it supports the conversion between the output from the `Directory` to
+//! public-storage safe blob types encoded with Protobuf. The blobs can be published to
+//! a storage medium implementing [AuditBlobStorage] with an [AuditBlobWriter], which
+//! maintains an [AuditManifest] listing the published epochs with the checksums of their
+//! blobs. Auditors then consume the blobs with an [AuditBlobReader], without needing to
+//! talk to the server. Without a manifest, the blobs can be discovered by listing the storage
+//! into a [ProofIndex].
+//!
+//! The proofs streamed by [crate::Directory::audit_stream] can also be uploaded to a blob
+//! bucket implementing [ProofBlobStore] with [upload_audit_stream], in parts as they are
+//! generated. With the `object_store` feature, the `object_store` module provides stores for
+//! S3 and GCS buckets.
+
+#[cfg(feature = "object_store")]
+pub mod object_store;
+
+use crate::errors::AkdError;
+use crate::{AuditProofSegment, Configuration, Digest};
+use async_trait::async_trait;
+use futures::{Stream, StreamExt};
+use protobuf::Message;
+use std::collections::BTreeMap;
+use std::convert::{TryFrom, TryInto};
+use std::marker::PhantomData;
+use std::path::PathBuf;
+
+/// Local audit processing errors
+#[derive(Debug)]
+pub enum LocalAuditorError {
+    /// An error parsing the blob name to/from a string
+    NameParseError(String),
+    /// An error between the lengths of hashes + proofs
+    MisMatchedLengths(String),
+    /// A conversion error occurred
+    ConversionError(akd_core::proto::ConversionError),
+    /// An error parsing an [AuditManifest]
+    ManifestParseError(String),
+    /// A blob is inconsistent with the manifest, or the blobs are inconsistent with each other
+    IntegrityError(String),
+    /// An error reading from or writing to an [AuditBlobStorage]
+    StorageError(String),
+    /// An error generating the audit proof which is being uploaded
+    AuditProofError(String),
+}
+
+impl From<akd_core::proto::ConversionError> for LocalAuditorError {
+    fn from(err: akd_core::proto::ConversionError) -> Self {
+        Self::ConversionError(err)
+    }
+}
+
+impl From<protobuf::Error> for LocalAuditorError {
+    fn from(err: protobuf::Error) -> Self {
+        Self::ConversionError(err.into())
+    }
+}
+
+// ************************ Converters ************************ //
+
+macro_rules! hash_from_ref {
+    ($obj:expr) => {
+        crate::hash::try_parse_digest($obj)
+            .map_err(akd_core::proto::ConversionError::Deserialization)
+    };
+}
+
+// ************************ Helper Functions ************************ //
+
+const NAME_SEPARATOR: char = '/';
+
+/// Represents the NAME of an audit blob and can be
+/// flatted to/from a string
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Default, Copy)]
+pub struct AuditBlobName {
+    /// The epoch this audit proof is related to
+    pub epoch: u64,
+    /// The previous root hash from `&self.epoch - 1`
+    pub previous_hash: Digest,
+    /// The current updated root hash
+    pub current_hash: Digest,
+}
+
+impl std::fmt::Display for AuditBlobName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let previous_hash = hex::encode(self.previous_hash);
+        let current_hash = hex::encode(self.current_hash);
+        write!(
+            f,
+            "{}{}{}{}{}",
+            self.epoch, NAME_SEPARATOR, previous_hash, NAME_SEPARATOR, current_hash
+        )
+    }
+}
+
+impl TryFrom<&str> for AuditBlobName {
+    type Error = LocalAuditorError;
+
+    fn try_from(name: &str) -> Result<Self, Self::Error> {
+        let parts = name.split(NAME_SEPARATOR).collect::<Vec<_>>();
+        if parts.len() < 3 {
+            return Err(LocalAuditorError::NameParseError(
+                "Name is malformed, there are not enough components to reconstruct!".to_string(),
+            ));
+        }
+        // PART[0] = EPOCH
+        let epoch: u64 = parts[0].parse().map_err(|_| {
+            LocalAuditorError::NameParseError(format!("Failed to parse '{}' into an u64", parts[0]))
+        })?;
+
+        // PART[1] = PREVIOUS_HASH
+        let previous_hash_bytes = hex::decode(parts[1]).map_err(|hex_err| {
+            LocalAuditorError::NameParseError(format!(
+                "Failed to decode previous hash from hex string: {hex_err}"
+            ))
+        })?;
+        let previous_hash = hash_from_ref!(&previous_hash_bytes)?;
+
+        // PART[2] = CURRENT_HASH
+        let current_hash_bytes = hex::decode(parts[2]).map_err(|hex_err| {
+            LocalAuditorError::NameParseError(format!(
+                "Failed to decode current hash from hex string: {hex_err}"
+            ))
+        })?;
+        let current_hash = hash_from_ref!(&current_hash_bytes)?;
+
+        Ok(AuditBlobName {
+            epoch,
+            current_hash,
+            previous_hash,
+        })
+    }
+}
+
+/// The constructed blobs with naming encoding the
+/// blob name = "EPOCH/PREVIOUS_ROOT_HASH/CURRENT_ROOT_HASH"
+#[derive(Clone)]
+pub struct AuditBlob {
+    /// The name of the blob, which can be decomposed into logical components (phash, chash, epoch)
+    pub name: AuditBlobName,
+    /// The binary data comprising the blob contents
+    pub data: Vec<u8>,
+}
+
+impl AuditBlob {
+    /// Construct a new AuditBlob from the internal structures, which is ready to be written to persistent storage
+    pub fn new(
+        previous_hash: Digest,
+        current_hash: Digest,
+        epoch: u64,
+        proof: &crate::SingleAppendOnlyProof,
+    ) -> Result<AuditBlob, LocalAuditorError> {
+        let name = AuditBlobName {
+            epoch,
+            previous_hash,
+            current_hash,
+        };
+        let proto: akd_core::proto::specs::types::SingleAppendOnlyProof = proof.into();
+
+        Ok(AuditBlob {
+            name,
+            data: proto.write_to_bytes()?,
+        })
+    }
+
+    /// Decode a protobuf encoded AuditBlob into it's components (phash, chash, epoch, proof)
+    pub fn decode(
+        &self,
+    ) -> Result<(u64, Digest, Digest, crate::SingleAppendOnlyProof), LocalAuditorError> {
+        let proof =
+            akd_core::proto::specs::types::SingleAppendOnlyProof::parse_from_bytes(&self.data)?;
+        let local_proof: crate::SingleAppendOnlyProof = (&proof).try_into()?;
+
+        Ok((
+            self.name.epoch,
+            hash_from_ref!(&self.name.previous_hash)?,
+            hash_from_ref!(&self.name.current_hash)?,
+            local_proof,
+        ))
+    }
+}
+
+/// Convert an append-only proof to "Audit Blobs" which are to be stored in a publicly readable storage medium
+/// suitable for public auditing
+pub fn generate_audit_blobs(
+    hashes: Vec<Digest>,
+    proof: crate::AppendOnlyProof,
+) -> Result<Vec<AuditBlob>, LocalAuditorError> {
+    if proof.epochs.len() + 1 != hashes.len() {
+        return Err(LocalAuditorError::MisMatchedLengths(format!(
+            "The proof has a different number of epochs than needed for hashes.
+            The number of hashes you provide should be one more than the number of epochs!
+            Number of epochs = {}, number of hashes = {}",
+            proof.epochs.len(),
+            hashes.len()
+        )));
+    }
+
+    if proof.epochs.len() != proof.proofs.len() {
+        return Err(LocalAuditorError::MisMatchedLengths(format!(
+            "The proof has {} epochs and {} proofs. These should be equal!",
+            proof.epochs.len(),
+            proof.proofs.len()
+        )));
+    }
+
+    let mut results = Vec::with_capacity(proof.proofs.len());
+
+    for i in 0..hashes.len() - 1 {
+        let previous_hash = hashes[i];
+        let current_hash = hashes[i + 1];
+        // The epoch provided is the source epoch, i.e. the proof is validating from (T, T+1)
+        let epoch = proof.epochs[i];
+
+        let blob = AuditBlob::new(previous_hash, current_hash, epoch, &proof.proofs[i])?;
+        results.push(blob);
+    }
+
+    Ok(results)
+}
+
+// ************************ Published layout ************************ //
+
+/// The key of the [AuditManifest] in an [AuditBlobStorage]
+pub const MANIFEST_KEY: &str = "manifest";
+
+/// The header line of an encoded [AuditManifest], including the version of the format
+const MANIFEST_HEADER: &str = "akd_audit_manifest 1";
+
+/// The entry of a single audit blob in an [AuditManifest]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AuditManifestEntry {
+    /// The name of the blob, which is also its key in the storage
+    pub name: AuditBlobName,
+    /// The hash of the blob's contents
+    pub checksum: Digest,
+    /// The size of the blob's contents in bytes
+    pub size: u64,
+}
+
+/// The list of the audit blobs which are published in an [AuditBlobStorage], so that
+/// auditors can discover them without listing the storage.
+///
+/// The manifest is encoded as text, with a header line followed by one line per blob,
+/// in increasing order of epochs. Each line consists of the blob name, the hex-encoded
+/// checksum and the size of the blob, separated by spaces.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AuditManifest {
+    entries: BTreeMap<u64, AuditManifestEntry>,
+}
+
+impl AuditManifest {
+    /// The entries of the manifest, in increasing order of epochs
+    pub fn entries(&self) -> impl Iterator<Item = &AuditManifestEntry> {
+        self.entries.values()
+    }
+
+    /// The entry of the blob for the provided epoch, if it was published
+    pub fn get(&self, epoch: u64) -> Option<&AuditManifestEntry> {
+        self.entries.get(&epoch)
+    }
+
+    /// The latest epoch for which a blob was published
+    pub fn latest_epoch(&self) -> Option<u64> {
+        self.entries.keys().next_back().copied()
+    }
+
+    /// Adds a blob to the manifest. The blob must be for the epoch following the latest
+    /// epoch in the manifest, starting from the root hash which that epoch ended with.
+    pub fn add<TC: Configuration>(&mut self, blob: &AuditBlob) -> Result<(), LocalAuditorError> {
+        if let Some(latest) = self.entries.values().next_back() {
+            if blob.name.epoch != latest.name.epoch + 1 {
+                return Err(LocalAuditorError::IntegrityError(format!(
+                    "The blob for epoch {} does not follow the latest epoch {} of the manifest",
+                    blob.name.epoch, latest.name.epoch
+                )));
+            }
+            if blob.name.previous_hash != latest.name.current_hash {
+                return Err(LocalAuditorError::IntegrityError(format!(
+                    "The previous hash of the blob for epoch {} does not match the current hash of epoch {}",
+                    blob.name.epoch, latest.name.epoch
+                )));
+            }
+        }
+        self.entries.insert(
+            blob.name.epoch,
+            AuditManifestEntry {
+                name: blob.name,
+                checksum: TC::hash(&blob.data),
+                size: blob.data.len() as u64,
+            },
+        );
+        Ok(())
+    }
+
+    /// Encodes the manifest into its text format
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut encoded = format!("{MANIFEST_HEADER}\n");
+        for entry in self.entries.values() {
+            encoded.push_str(&format!(
+                "{} {} {}\n",
+                entry.name,
+                hex::encode(entry.checksum),
+                entry.size
+            ));
+        }
+        encoded.into_bytes()
+    }
+
+    /// Decodes a manifest from its text format
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, LocalAuditorError> {
+        let text = std::str::from_utf8(bytes).map_err(|err| {
+            LocalAuditorError::ManifestParseError(format!("Manifest is not valid UTF-8: {err}"))
+        })?;
+        let mut lines = text.lines();
+        if lines.next() != Some(MANIFEST_HEADER) {
+            return Err(LocalAuditorError::ManifestParseError(format!(
+                "Manifest does not start with the header '{MANIFEST_HEADER}'"
+            )));
+        }
+
+        let mut manifest = Self::default();
+        let mut previous_epoch = None;
+        for line in lines {
+            let parts = line.split(' ').collect::<Vec<_>>();
+            if parts.len() != 3 {
+                return Err(LocalAuditorError::ManifestParseError(format!(
+                    "Manifest line '{line}' does not have 3 components"
+                )));
+            }
+            let name = AuditBlobName::try_from(parts[0])?;
+            let checksum_bytes = hex::decode(parts[1]).map_err(|hex_err| {
+                LocalAuditorError::ManifestParseError(format!(
+                    "Failed to decode checksum from hex string: {hex_err}"
+                ))
+            })?;
+            let checksum = hash_from_ref!(&checksum_bytes)?;
+            let size = parts[2].parse().map_err(|_| {
+                LocalAuditorError::ManifestParseError(format!(
+                    "Failed to parse '{}' into an u64",
+                    parts[2]
+                ))
+            })?;
+            if previous_epoch.is_some_and(|previous| previous >= name.epoch) {
+                return Err(LocalAuditorError::ManifestParseError(format!(
+                    "Manifest entries are not in increasing order of epochs at epoch {}",
+                    name.epoch
+                )));
+            }
+            previous_epoch = Some(name.epoch);
+            manifest.entries.insert(
+                name.epoch,
+                AuditManifestEntry {
+                    name,
+                    checksum,
+                    size,
+                },
+            );
+        }
+        Ok(manifest)
+    }
+}
+
+/// A storage medium (e.g. a local directory or a blob bucket) to which audit blobs are
+/// published, with each object identified by a string key
+#[async_trait]
+pub trait AuditBlobStorage: Send + Sync {
+    /// Reads the object with the provided key, returning [None] if it does not exist
+    async fn read(&self, key: &str) -> Result<Option<Vec<u8>>, LocalAuditorError>;
+
+    /// Writes the object with the provided key, replacing any existing object
+    async fn write(&self, key: &str, data: &[u8]) -> Result<(), LocalAuditorError>;
+
+    /// Lists the keys of the objects which start with the provided prefix, in no particular
+    /// order (see [ProofIndex])
+    async fn list(&self, _prefix: &str) -> Result<Vec<String>, LocalAuditorError> {
+        Err(LocalAuditorError::StorageError(
+            "Listing is not supported by this storage".to_string(),
+        ))
+    }
+}
+
+/// An [AuditBlobStorage] in a local directory, where keys are paths relative to the directory
+#[derive(Clone, Debug)]
+pub struct LocalAuditBlobStorage {
+    root: PathBuf,
+}
+
+impl LocalAuditBlobStorage {
+    /// Creates the storage in the provided directory
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+#[async_trait]
+impl AuditBlobStorage for LocalAuditBlobStorage {
+    async fn read(&self, key: &str) -> Result<Option<Vec<u8>>, LocalAuditorError> {
+        match std::fs::read(self.root.join(key)) {
+            Ok(data) => Ok(Some(data)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(LocalAuditorError::StorageError(format!(
+                "Failed to read '{key}': {err}"
+            ))),
+        }
+    }
+
+    async fn write(&self, key: &str, data: &[u8]) -> Result<(), LocalAuditorError> {
+        let path = self.root.join(key);
+        let write = || {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            // Written to a temporary file first, so that readers never observe partial objects
+            let mut temp_path = path.clone().into_os_string();
+            temp_path.push(".tmp");
+            std::fs::write(&temp_path, data)?;
+            std::fs::rename(&temp_path, &path)
+        };
+        write().map_err(|err| {
+            LocalAuditorError::StorageError(format!("Failed to write '{key}': {err}"))
+        })
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, LocalAuditorError> {
+        let mut keys = vec![];
+        if self.root.exists() {
+            list_directory(&self.root, "", &mut keys).map_err(|err| {
+                LocalAuditorError::StorageError(format!(
+                    "Failed to list '{}': {err}",
+                    self.root.display()
+                ))
+            })?;
+        }
+        keys.retain(|key| key.starts_with(prefix));
+        Ok(keys)
+    }
+}
+
+/// Collects the keys of the files below `dir`, each prefixed by `parent` (the key of `dir`).
+/// Files whose name is not valid UTF-8 cannot be keys, and are skipped.
+fn list_directory(
+    dir: &std::path::Path,
+    parent: &str,
+    keys: &mut Vec<String>,
+) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let Some(name) = entry
+            .file_name()
+            .to_str()
+            .map(|name| format!("{parent}{name}"))
+        else {
+            continue;
+        };
+        if entry.file_type()?.is_dir() {
+            list_directory(&entry.path(), &format!("{name}/"), keys)?;
+        } else {
+            keys.push(name);
+        }
+    }
+    Ok(())
+}
+
+/// Publishes audit blobs to an [AuditBlobStorage], keeping its [AuditManifest] up to date.
+/// Each blob is stored under its name, and the manifest under [MANIFEST_KEY].
+pub struct AuditBlobWriter<TC, S> {
+    storage: S,
+    manifest: AuditManifest,
+    _tc: PhantomData<TC>,
+}
+
+impl<TC: Configuration, S: AuditBlobStorage> AuditBlobWriter<TC, S> {
+    /// Opens the storage for writing, continuing from its existing manifest (if any)
+    pub async fn open(storage: S) -> Result<Self, LocalAuditorError> {
+        let manifest = match storage.read(MANIFEST_KEY).await? {
+            Some(bytes) => AuditManifest::from_bytes(&bytes)?,
+            None => AuditManifest::default(),
+        };
+        Ok(Self {
+            storage,
+            manifest,
+            _tc: PhantomData,
+        })
+    }
+
+    /// The manifest of the published blobs
+    pub fn manifest(&self) -> &AuditManifest {
+        &self.manifest
+    }
+
+    /// Publishes the blob of the epoch following the latest published epoch. The blob is
+    /// written before the manifest, so that the manifest only refers to complete blobs.
+    pub async fn write(&mut self, blob: &AuditBlob) -> Result<(), LocalAuditorError> {
+        let mut manifest = self.manifest.clone();
+        manifest.add::<TC>(blob)?;
+        self.storage
+            .write(&blob.name.to_string(), &blob.data)
+            .await?;
+        self.storage
+            .write(MANIFEST_KEY, &manifest.to_bytes())
+            .await?;
+        self.manifest = manifest;
+        Ok(())
+    }
+}
+
+/// The default size of the parts of the uploads to a [ProofBlobStore], which satisfies the
+/// constraints of both S3 (at least 5 MiB) and GCS (a multiple of 256 KiB)
+pub const DEFAULT_PART_SIZE: usize = 8 * 1024 * 1024;
+
+/// An upload of an object in parts to a [ProofBlobStore], as started with
+/// [ProofBlobStore::start_upload]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MultipartUpload {
+    /// The key of the object being uploaded
+    pub key: String,
+    /// The identifier of the upload in the store (e.g. the upload id of an S3 multipart
+    /// upload, or the session URI of a GCS resumable upload)
+    pub upload_id: String,
+    /// The identifiers of the parts uploaded so far, in order
+    pub parts: Vec<String>,
+    /// The number of bytes uploaded so far
+    pub uploaded_bytes: u64,
+}
+
+/// A blob bucket (e.g. S3 or GCS) to which audit blobs can be uploaded in parts, so that a
+/// blob does not need to be held in memory in its entirety before it is uploaded (see
+/// [upload_audit_stream]). The objects are also readable and writable as a whole, as an
+/// [AuditBlobStorage].
+#[async_trait]
+pub trait ProofBlobStore: AuditBlobStorage {
+    /// The size of each part of an upload but the last, which may be smaller
+    fn part_size(&self) -> usize {
+        DEFAULT_PART_SIZE
+    }
+
+    /// Starts the upload of the object with the provided key
+    async fn start_upload(&self, key: &str) -> Result<MultipartUpload, LocalAuditorError>;
+
+    /// Uploads the next part of the object, returning the identifier of the part. The last
+    /// part is flagged with `last`, and is the only part which may be smaller than
+    /// [ProofBlobStore::part_size] (or empty).
+    async fn upload_part(
+        &self,
+        upload: &MultipartUpload,
+        data: &[u8],
+        last: bool,
+    ) -> Result<String, LocalAuditorError>;
+
+    /// Completes the upload once its last part was uploaded, after which the object is
+    /// readable under its key
+    async fn complete_upload(&self, upload: MultipartUpload) -> Result<(), LocalAuditorError>;
+
+    /// Aborts the upload, discarding the parts which were uploaded
+    async fn abort_upload(&self, upload: MultipartUpload) -> Result<(), LocalAuditorError>;
+}
+
+/// Uploads an audit proof, as streamed by [crate::Directory::audit_stream], to a
+/// [ProofBlobStore] with a blob per epoch. Each blob is stored under its [AuditBlobName],
+/// which is the layout that auditors listing the bucket expect. The `hashes` are the root
+/// hashes of the audited epochs, from the start epoch to the end epoch (i.e. one more than
+/// the number of epochs, as with [generate_audit_blobs]). Returns the names of the uploaded
+/// blobs.
+///
+/// Each segment is encoded as a protobuf `SingleAppendOnlyProof` and appended to the blob
+/// of its epoch, so that at most a part of each blob is held in memory. Since protobuf
+/// merges the repeated fields of concatenated messages, the blob decodes (see
+/// [AuditBlob::decode]) into the proof of the whole epoch. The upload of a blob is aborted
+/// if the stream fails before the last segment of its epoch.
+///
+/// The blobs are not added to an [AuditManifest], as its checksums cover the contents of
+/// the whole blobs.
+pub async fn upload_audit_stream<S: ProofBlobStore>(
+    store: &S,
+    hashes: &[Digest],
+    segments: impl Stream<Item = Result<AuditProofSegment, AkdError>>,
+) -> Result<Vec<AuditBlobName>, LocalAuditorError> {
+    futures::pin_mut!(segments);
+    let mut names = vec![];
+    let mut current = None;
+    while let Some(segment) = segments.next().await {
+        let result = match segment {
+            Ok(segment) => append_segment(store, hashes, &mut names, &mut current, segment).await,
+            Err(err) => Err(LocalAuditorError::AuditProofError(err.to_string())),
+        };
+        if let Err(err) = result {
+            if let Some((upload, _)) = current {
+                let _ = store.abort_upload(upload).await;
+            }
+            return Err(err);
+        }
+    }
+    if let Some((upload, _)) = current {
+        let key = upload.key.clone();
+        let _ = store.abort_upload(upload).await;
+        return Err(LocalAuditorError::AuditProofError(format!(
+            "The audit proof ended before the last segment of the blob '{key}'"
+        )));
+    }
+    Ok(names)
+}
+
+/// Appends a segment to the blob of its epoch, starting the upload of the blob with the first
+/// segment of the epoch and completing it with the last one
+async fn append_segment<S: ProofBlobStore>(
+    store: &S,
+    hashes: &[Digest],
+    names: &mut Vec<AuditBlobName>,
+    current: &mut Option<(MultipartUpload, Vec<u8>)>,
+    segment: AuditProofSegment,
+) -> Result<(), LocalAuditorError> {
+    let (upload, buffer) = match current {
+        Some(current) => current,
+        None => {
+            let index = names.len();
+            let (previous_hash, current_hash) = match (hashes.get(index), hashes.get(index + 1)) {
+                (Some(previous_hash), Some(current_hash)) => (*previous_hash, *current_hash),
+                _ => {
+                    return Err(LocalAuditorError::MisMatchedLengths(format!(
+                        "The audit proof has more epochs than needed for the {} hashes",
+                        hashes.len()
+                    )))
+                }
+            };
+            if let Some(previous) = names.last() {
+                if segment.epoch != previous.epoch {
+                    return Err(LocalAuditorError::IntegrityError(format!(
+                        "The proof of epoch {} does not follow the proof of epoch {}",
+                        segment.epoch, previous.epoch
+                    )));
+                }
+            }
+            // The blobs are named by the epoch which the proof ends at
+            let name = AuditBlobName {
+                epoch: segment.epoch + 1,
+                previous_hash,
+                current_hash,
+            };
+            let upload = store.start_upload(&name.to_string()).await?;
+            names.push(name);
+            current.insert((upload, vec![]))
+        }
+    };
+
+    let last = segment.last;
+    let proof = crate::SingleAppendOnlyProof {
+        inserted: segment.inserted,
+        unchanged_nodes: segment.unchanged_nodes,
+    };
+    let proto: akd_core::proto::specs::types::SingleAppendOnlyProof = (&proof).into();
+    buffer.extend(proto.write_to_bytes()?);
+
+    let part_size = store.part_size();
+    while buffer.len() > part_size {
+        let rest = buffer.split_off(part_size);
+        upload_part(store, upload, buffer, false).await?;
+        *buffer = rest;
+    }
+    if last {
+        upload_part(store, upload, buffer, true).await?;
+        if let Some((upload, _)) = current.take() {
+            store.complete_upload(upload).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn upload_part<S: ProofBlobStore>(
+    store: &S,
+    upload: &mut MultipartUpload,
+    data: &[u8],
+    last: bool,
+) -> Result<(), LocalAuditorError> {
+    let part = store.upload_part(upload, data, last).await?;
+    upload.parts.push(part);
+    upload.uploaded_bytes += data.len() as u64;
+    Ok(())
+}
+
+/// Reads the audit blobs published by an [AuditBlobWriter], checking their integrity
+/// against the manifest
+pub struct AuditBlobReader<TC, S> {
+    storage: S,
+    manifest: AuditManifest,
+    _tc: PhantomData<TC>,
+}
+
+impl<TC: Configuration, S: AuditBlobStorage> AuditBlobReader<TC, S> {
+    /// Opens the storage for reading, loading its manifest
+    pub async fn open(storage: S) -> Result<Self, LocalAuditorError> {
+        let mut reader = Self {
+            storage,
+            manifest: AuditManifest::default(),
+            _tc: PhantomData,
+        };
+        reader.refresh().await?;
+        Ok(reader)
+    }
+
+    /// The manifest of the published blobs
+    pub fn manifest(&self) -> &AuditManifest {
+        &self.manifest
+    }
+
+    /// Reloads the manifest, to discover blobs which were published since it was loaded
+    pub async fn refresh(&mut self) -> Result<(), LocalAuditorError> {
+        let bytes = self.storage.read(MANIFEST_KEY).await?.ok_or_else(|| {
+            LocalAuditorError::StorageError(format!("No manifest found at '{MANIFEST_KEY}'"))
+        })?;
+        self.manifest = AuditManifest::from_bytes(&bytes)?;
+        Ok(())
+    }
+
+    /// Reads the blob of the provided epoch, verifying its size and checksum
+    pub async fn read(&self, epoch: u64) -> Result<AuditBlob, LocalAuditorError> {
+        let entry = self.manifest.get(epoch).ok_or_else(|| {
+            LocalAuditorError::StorageError(format!("Epoch {epoch} is not in the manifest"))
+        })?;
+        let key = entry.name.to_string();
+        let data = self.storage.read(&key).await?.ok_or_else(|| {
+            LocalAuditorError::StorageError(format!("The blob '{key}' is missing"))
+        })?;
+        if data.len() as u64 != entry.size || TC::hash(&data) != entry.checksum {
+            return Err(LocalAuditorError::IntegrityError(format!(
+                "The blob '{key}' does not match its checksum in the manifest"
+            )));
+        }
+        Ok(AuditBlob {
+            name: entry.name,
+            data,
+        })
+    }
+}
+
+/// The audit blobs available in an [AuditBlobStorage], discovered by listing the storage
+/// rather than through an [AuditManifest] (e.g. for blobs uploaded with
+/// [upload_audit_stream]).
+///
+/// By convention, the blobs are stored under a common prefix (which may be empty), followed
+/// by their [AuditBlobName] (i.e. `{prefix}{epoch}/{previous hash}/{current hash}`). The
+/// other objects below the prefix (e.g. the manifest) are ignored. The index lets auditors
+/// check that the published epochs are contiguous, and report the epochs which are missing.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProofIndex {
+    prefix: String,
+    blobs: BTreeMap<u64, AuditBlobName>,
+}
+
+impl ProofIndex {
+    /// Lists the blobs below the prefix of the storage (see [AuditBlobStorage::list])
+    pub async fn load<S: AuditBlobStorage + ?Sized>(
+        storage: &S,
+        prefix: &str,
+    ) -> Result<Self, LocalAuditorError> {
+        let keys = storage.list(prefix).await?;
+        Self::from_keys(prefix, keys.iter().map(|key| key.as_str()))
+    }
+
+    /// Indexes the blobs among the keys of a listing, e.g. of a bucket listed with another
+    /// client. Fails if two blobs are published for the same epoch.
+    pub fn from_keys<'a>(
+        prefix: &str,
+        keys: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Self, LocalAuditorError> {
+        let mut blobs = BTreeMap::new();
+        for key in keys {
+            let Some(name) = key
+                .strip_prefix(prefix)
+                .and_then(|name| AuditBlobName::try_from(name).ok())
+            else {
+                continue;
+            };
+            if let Some(existing) = blobs.insert(name.epoch, name) {
+                if existing != name {
+                    return Err(LocalAuditorError::IntegrityError(format!(
+                        "Two blobs are published for epoch {}: '{existing}' and '{name}'",
+                        name.epoch
+                    )));
+                }
+            }
+        }
+        Ok(Self {
+            prefix: prefix.to_string(),
+            blobs,
+        })
+    }
+
+    /// The names of the blobs, in increasing order of epochs
+    pub fn blobs(&self) -> impl Iterator<Item = &AuditBlobName> {
+        self.blobs.values()
+    }
+
+    /// The number of blobs in the index
+    pub fn len(&self) -> usize {
+        self.blobs.len()
+    }
+
+    /// Whether no blob was found
+    pub fn is_empty(&self) -> bool {
+        self.blobs.is_empty()
+    }
+
+    /// The name of the blob for the provided epoch, if it is published
+    pub fn get(&self, epoch: u64) -> Option<&AuditBlobName> {
+        self.blobs.get(&epoch)
+    }
+
+    /// The key under which the blob is stored
+    pub fn key(&self, name: &AuditBlobName) -> String {
+        format!("{}{name}", self.prefix)
+    }
+
+    /// The earliest epoch for which a blob is published
+    pub fn first_epoch(&self) -> Option<u64> {
+        self.blobs.keys().next().copied()
+    }
+
+    /// The latest epoch for which a blob is published
+    pub fn latest_epoch(&self) -> Option<u64> {
+        self.blobs.keys().next_back().copied()
+    }
+
+    /// The ranges of epochs between the first and the latest epoch for which no blob is
+    /// published, in increasing order
+    pub fn gaps(&self) -> Vec<std::ops::RangeInclusive<u64>> {
+        self.blobs
+            .keys()
+            .zip(self.blobs.keys().skip(1))
+            .filter(|(previous, next)| **next > **previous + 1)
+            .map(|(previous, next)| previous + 1..=next - 1)
+            .collect()
+    }
+
+    /// The epochs whose blob does not start from the root hash which the blob of the
+    /// preceding epoch ends with, in increasing order
+    pub fn hash_mismatches(&self) -> Vec<u64> {
+        self.blobs
+            .values()
+            .zip(self.blobs.values().skip(1))
+            .filter(|(previous, next)| {
+                next.epoch == previous.epoch + 1 && next.previous_hash != previous.current_hash
+            })
+            .map(|(_, next)| next.epoch)
+            .collect()
+    }
+
+    /// Checks that the blobs cover a contiguous range of epochs, with each blob starting
+    /// from the root hash which the previous one ends with
+    pub fn check_contiguous(&self) -> Result<(), LocalAuditorError> {
+        if let Some(gap) = self.gaps().first() {
+            return Err(LocalAuditorError::IntegrityError(format!(
+                "No blob is published for epochs {} to {}",
+                gap.start(),
+                gap.end()
+            )));
+        }
+        if let Some(epoch) = self.hash_mismatches().first() {
+            return Err(LocalAuditorError::IntegrityError(format!(
+                "The previous hash of the blob for epoch {epoch} does not match the current hash of epoch {}",
+                epoch - 1
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        AuditBlob, AuditBlobName, AuditBlobReader, AuditBlobStorage, AuditBlobWriter,
+        AuditManifest, LocalAuditBlobStorage, LocalAuditorError, MultipartUpload, ProofBlobStore,
+        ProofIndex, MANIFEST_KEY,
+    };
+    use crate::ecvrf::HardCodedAkdVRF;
+    use crate::storage::{manager::StorageManager, memory::AsyncInMemoryDatabase};
+    use crate::{AkdLabel, AkdValue, AppendOnlyProof, Directory};
+    use async_trait::async_trait;
+    use std::collections::HashMap;
+    use std::convert::TryInto;
+    use std::sync::Mutex;
+
+    type TC = crate::ExperimentalConfiguration<crate::ExampleLabel>;
+
+    /// Publishes a few epochs, returning the audit blob of each of them
+    async fn generate_blobs(epochs: u64) -> Vec<AuditBlob> {
+        let storage = StorageManager::new_no_cache(AsyncInMemoryDatabase::new());
+        let akd = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {})
+            .await
+            .unwrap();
+        let mut blobs = vec![];
+        for epoch in 1..=epochs {
+            let previous = akd.get_epoch_hash().await.unwrap();
+            let current = akd
+                .publish(vec![(
+                    AkdLabel(format!("user{epoch}").into_bytes()),
+                    AkdValue::from("value"),
+                )])
+                .await
+                .unwrap();
+            let proof = akd.audit(previous.epoch(), current.epoch()).await.unwrap();
+            blobs.push(
+                AuditBlob::new(previous.hash(), current.hash(), epoch, &proof.proofs[0]).unwrap(),
+            );
+        }
+        blobs
+    }
+
+    #[tokio::test]
+    async fn test_audit_manifest_encoding() -> Result<(), LocalAuditorError> {
+        let mut manifest = AuditManifest::default();
+        for blob in generate_blobs(3).await {
+            manifest.add::<TC>(&blob)?;
+        }
+        assert_eq!(Some(3), manifest.latest_epoch());
+        assert_eq!(manifest, AuditManifest::from_bytes(&manifest.to_bytes())?);
+
+        let mut invalid_header = manifest.to_bytes();
+        invalid_header[0] = b'x';
+        assert!(matches!(
+            AuditManifest::from_bytes(&invalid_header),
+            Err(LocalAuditorError::ManifestParseError(_))
+        ));
+
+        // Entries must be in increasing order of epochs
+        let text = String::from_utf8(manifest.to_bytes()).unwrap();
+        let mut lines = text.lines().collect::<Vec<_>>();
+        lines.swap(1, 2);
+        assert!(matches!(
+            AuditManifest::from_bytes(lines.join("\n").as_bytes()),
+            Err(LocalAuditorError::ManifestParseError(_))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_audit_blob_writer_and_reader() -> Result<(), LocalAuditorError> {
+        let root = std::env::temp_dir().join(format!("akd_audit_blobs_{}", std::process::id()));
+        let storage = LocalAuditBlobStorage::new(&root);
+        let blobs = generate_blobs(3).await;
+
+        let mut writer = AuditBlobWriter::<TC, _>::open(storage.clone()).await?;
+        writer.write(&blobs[0]).await?;
+        // The blobs must be published in order
+        assert!(matches!(
+            writer.write(&blobs[2]).await,
+            Err(LocalAuditorError::IntegrityError(_))
+        ));
+        // A reopened writer continues from the published manifest
+        let mut writer = AuditBlobWriter::<TC, _>::open(storage.clone()).await?;
+        assert_eq!(Some(1), writer.manifest().latest_epoch());
+        writer.write(&blobs[1]).await?;
+        writer.write(&blobs[2]).await?;
+
+        let reader = AuditBlobReader::<TC, _>::open(storage.clone()).await?;
+        assert_eq!(writer.manifest(), reader.manifest());
+        for blob in blobs.iter() {
+            let read = reader.read(blob.name.epoch).await?;
+            assert_eq!(blob.name, read.name);
+            assert_eq!(blob.data, read.data);
+        }
+
+        // Tampering with a blob is detected
+        let mut data = blobs[1].data.clone();
+        data[0] ^= 1;
+        storage.write(&blobs[1].name.to_string(), &data).await?;
+        assert!(matches!(
+            reader.read(blobs[1].name.epoch).await,
+            Err(LocalAuditorError::IntegrityError(_))
+        ));
+        assert!(storage.read(MANIFEST_KEY).await?.is_some());
+
+        std::fs::remove_dir_all(&root).unwrap();
+        Ok(())
+    }
+
+    /// A [ProofBlobStore] in memory, with tiny parts
+    #[derive(Default)]
+    struct InMemoryProofBlobStore {
+        objects: Mutex<HashMap<String, Vec<u8>>>,
+        uploads: Mutex<HashMap<String, Vec<Vec<u8>>>>,
+    }
+
+    #[async_trait]
+    impl AuditBlobStorage for InMemoryProofBlobStore {
+        async fn read(&self, key: &str) -> Result<Option<Vec<u8>>, LocalAuditorError> {
+            Ok(self.objects.lock().unwrap().get(key).cloned())
+        }
+
+        async fn write(&self, key: &str, data: &[u8]) -> Result<(), LocalAuditorError> {
+            self.objects
+                .lock()
+                .unwrap()
+                .insert(key.to_string(), data.to_vec());
+            Ok(())
+        }
+
+        async fn list(&self, prefix: &str) -> Result<Vec<String>, LocalAuditorError> {
+            Ok(self
+                .objects
+                .lock()
+                .unwrap()
+                .keys()
+                .filter(|key| key.starts_with(prefix))
+                .cloned()
+                .collect())
+        }
+    }
+
+    #[async_trait]
+    impl ProofBlobStore for InMemoryProofBlobStore {
+        fn part_size(&self) -> usize {
+            64
+        }
+
+        async fn start_upload(&self, key: &str) -> Result<MultipartUpload, LocalAuditorError> {
+            let upload_id = format!("upload-{key}");
+            self.uploads
+                .lock()
+                .unwrap()
+                .insert(upload_id.clone(), vec![]);
+            Ok(MultipartUpload {
+                key: key.to_string(),
+                upload_id,
+                parts: vec![],
+                uploaded_bytes: 0,
+            })
+        }
+
+        async fn upload_part(
+            &self,
+            upload: &MultipartUpload,
+            data: &[u8],
+            last: bool,
+        ) -> Result<String, LocalAuditorError> {
+            assert!(last || data.len() == self.part_size());
+            let mut uploads = self.uploads.lock().unwrap();
+            let parts = uploads.get_mut(&upload.upload_id).unwrap();
+            parts.push(data.to_vec());
+            Ok(parts.len().to_string())
+        }
+
+        async fn complete_upload(&self, upload: MultipartUpload) -> Result<(), LocalAuditorError> {
+            let parts = self
+                .uploads
+                .lock()
+                .unwrap()
+                .remove(&upload.upload_id)
+                .unwrap();
+            assert_eq!(parts.len(), upload.parts.len());
+            self.write(&upload.key, &parts.concat()).await
+        }
+
+        async fn abort_upload(&self, upload: MultipartUpload) -> Result<(), LocalAuditorError> {
+            self.uploads.lock().unwrap().remove(&upload.upload_id);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upload_audit_stream() -> Result<(), LocalAuditorError> {
+        let storage = StorageManager::new_no_cache(AsyncInMemoryDatabase::new());
+        let akd = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {})
+            .await
+            .unwrap();
+        let mut hashes = vec![akd.get_epoch_hash().await.unwrap().hash()];
+        for epoch in 1..=3 {
+            let updates = (0..5)
+                .map(|i| {
+                    (
+                        AkdLabel(format!("user{epoch}-{i}").into_bytes()),
+                        AkdValue::from("value"),
+                    )
+                })
+                .collect();
+            hashes.push(akd.publish(updates).await.unwrap().hash());
+        }
+
+        let store = InMemoryProofBlobStore::default();
+        let names =
+            super::upload_audit_stream(&store, &hashes[1..], akd.audit_stream(1, 3, 2)).await?;
+        assert_eq!(
+            vec![2, 3],
+            names.iter().map(|name| name.epoch).collect::<Vec<_>>()
+        );
+        assert!(store.uploads.lock().unwrap().is_empty());
+
+        // Each blob decodes into the proof of its epoch
+        for name in names {
+            let data = store.read(&name.to_string()).await?.unwrap();
+            assert!(data.len() > store.part_size());
+            let (end_epoch, previous_hash, current_hash, proof) =
+                AuditBlob { name, data }.decode()?;
+            crate::auditor::audit_verify::<TC>(
+                vec![previous_hash, current_hash],
+                AppendOnlyProof {
+                    proofs: vec![proof],
+                    epochs: vec![end_epoch - 1],
+                },
+            )
+            .await
+            .unwrap();
+        }
+
+        // The uploaded blobs are discoverable by listing the store
+        let index = ProofIndex::load(&store, "").await?;
+        assert_eq!(Some(2), index.first_epoch());
+        assert_eq!(Some(3), index.latest_epoch());
+        index.check_contiguous()?;
+
+        // An upload which fails part-way through is aborted
+        let store = InMemoryProofBlobStore::default();
+        let result =
+            super::upload_audit_stream(&store, &hashes[..2], akd.audit_stream(0, 3, 2)).await;
+        assert!(matches!(
+            result,
+            Err(LocalAuditorError::MisMatchedLengths(_))
+        ));
+        assert!(store.uploads.lock().unwrap().is_empty());
+        assert_eq!(1, store.objects.lock().unwrap().len());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_proof_index() -> Result<(), LocalAuditorError> {
+        let root = std::env::temp_dir().join(format!("akd_proof_index_{}", std::process::id()));
+        let storage = LocalAuditBlobStorage::new(&root);
+        // Listing a storage without any object finds no blob
+        assert!(ProofIndex::load(&storage, "").await?.is_empty());
+
+        let blobs = generate_blobs(4).await;
+        let mut writer = AuditBlobWriter::<TC, _>::open(storage.clone()).await?;
+        for blob in blobs.iter() {
+            writer.write(blob).await?;
+        }
+        // The manifest is not a blob
+        let index = ProofIndex::load(&storage, "").await?;
+        assert_eq!(4, index.len());
+        assert_eq!(
+            blobs.iter().map(|blob| blob.name).collect::<Vec<_>>(),
+            index.blobs().copied().collect::<Vec<_>>()
+        );
+        assert_eq!(blobs[2].name.to_string(), index.key(&blobs[2].name));
+        index.check_contiguous()?;
+        assert!(ProofIndex::load(&storage, "proofs/").await?.is_empty());
+        std::fs::remove_dir_all(&root).unwrap();
+
+        // The blobs below a prefix are indexed by their name relative to the prefix
+        let keys = [&blobs[0], &blobs[1], &blobs[3]]
+            .iter()
+            .map(|blob| format!("proofs/{}", blob.name))
+            .chain(std::iter::once("proofs/notes.txt".to_string()))
+            .chain(std::iter::once(format!("other/{}", blobs[2].name)))
+            .collect::<Vec<_>>();
+        let index = ProofIndex::from_keys("proofs/", keys.iter().map(|key| key.as_str()))?;
+        assert_eq!(Some(1), index.first_epoch());
+        assert_eq!(Some(4), index.latest_epoch());
+        assert_eq!(None, index.get(3));
+        assert_eq!(
+            format!("proofs/{}", blobs[3].name),
+            index.key(&blobs[3].name)
+        );
+        assert_eq!(vec![3..=3], index.gaps());
+        assert!(matches!(
+            index.check_contiguous(),
+            Err(LocalAuditorError::IntegrityError(_))
+        ));
+
+        // Blobs which do not chain their root hashes are reported
+        let mut forked = blobs[2].name;
+        forked.previous_hash = forked.current_hash;
+        let keys = [blobs[0].name, blobs[1].name, forked].map(|name| name.to_string());
+        let index = ProofIndex::from_keys("", keys.iter().map(|key| key.as_str()))?;
+        assert!(index.gaps().is_empty());
+        assert_eq!(vec![3], index.hash_mismatches());
+        assert!(index.check_contiguous().is_err());
+
+        // Two blobs for the same epoch are an equivocation
+        let keys = [blobs[2].name, forked].map(|name| name.to_string());
+        assert!(matches!(
+            ProofIndex::from_keys("", keys.iter().map(|key| key.as_str())),
+            Err(LocalAuditorError::IntegrityError(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_audit_proof_naming_conventions() -> Result<(), LocalAuditorError> {
+        let expected_name = "54/0101010101010101010101010101010101010101010101010101010101010101/0000000000000000000000000000000000000000000000000000000000000000";
+
+        let blob_name = AuditBlobName {
+            current_hash: crate::hash::EMPTY_DIGEST,
+            previous_hash: [1u8; crate::hash::DIGEST_BYTES],
+            epoch: 54,
+        };
+
+        let name = blob_name.to_string();
+        assert_ne!(String::new(), name);
+
+        assert_eq!(expected_name.to_string(), blob_name.to_string());
+
+        let blob_name_ref: &str = name.as_ref();
+        let decomposed: AuditBlobName = blob_name_ref.try_into()?;
+        assert_eq!(blob_name, decomposed);
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Merging of two directories into a new one, for the consolidation of two deployments.
+//!
+//! The merged directory contains the union of the labels of the two directories. The history of
+//! each label is replayed from one of them with the same versions, values and epochs, so that its
+//! clients can check that the history they verified continues in the merged directory with a
+//! [crate::MergeBridgeProof] (see [crate::Directory::merge_bridge_proof] and
+//! [crate::client::merge_bridge_verify]). Each epoch of the merged directory thus contains the
+//! updates of that epoch in both directories.
+//!
+//! A label which is present in both directories is in conflict, and only its history in one of
+//! them is kept, as determined by the [MergeConflictPolicy]. The clients of the other directory
+//! cannot bridge to the merged directory for this label, and should be notified of the
+//! [MergeConflict]s reported by the merge.
+
+use crate::directory::{Directory, History};
+use crate::ecvrf::VRFKeyStorage;
+use crate::errors::AkdError;
+use crate::storage::types::ValueState;
+use crate::storage::Database;
+use crate::{AkdLabel, Configuration, EpochHash, EpochMetadata};
+
+use log::info;
+use std::collections::{BTreeMap, HashMap};
+
+/// Which of two conflicting histories of a label is kept by a [merge]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeConflictPolicy {
+    /// The history in the first directory is kept
+    PreferFirst,
+    /// The history in the second directory is kept
+    PreferSecond,
+    /// The history whose latest version was published most recently (according to the
+    /// [EpochMetadata] of its epoch) is kept, or the history in the first directory if both were
+    /// published at the same time
+    PreferMostRecent,
+}
+
+/// One of the two directories of a [merge]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeSource {
+    /// The first directory
+    First,
+    /// The second directory
+    Second,
+}
+
+/// A label which is present in both directories of a [merge]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeConflict {
+    /// The label
+    pub label: AkdLabel,
+    /// The directory whose history of the label was kept
+    pub kept: MergeSource,
+}
+
+/// The outcome of a [merge]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeReport {
+    /// The latest epoch of the merged directory, and its root hash
+    pub epoch_hash: EpochHash,
+    /// The number of labels of the merged directory
+    pub num_labels: u64,
+    /// The labels which are present in both directories, in increasing order
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// Merges the labels of the `first` and `second` directories into `target`, which must be empty,
+/// resolving the conflicts with the given policy. The history of each label is replayed with the
+/// same versions, values and epochs as in the directory from which it is kept, while the tree of
+/// the target is built with its own VRF key and configuration (e.g. its decoy leaves).
+///
+/// The publication time of each epoch of the merged directory is the latest of its publication
+/// times in the two directories. The history of both directories is held in memory during the
+/// merge, and so they should not be published to while it runs. This requires storage layers
+/// implementing [Database::list_labels], and directories which have not gone through a rotation
+/// of their VRF key.
+pub async fn merge<TC, S1, V1, S2, V2, S, V>(
+    first: &Directory<TC, S1, V1>,
+    second: &Directory<TC, S2, V2>,
+    target: &Directory<TC, S, V>,
+    policy: MergeConflictPolicy,
+) -> Result<MergeReport, AkdError>
+where
+    TC: Configuration,
+    S1: Database + 'static,
+    V1: VRFKeyStorage,
+    S2: Database + 'static,
+    V2: VRFKeyStorage,
+    S: Database + 'static,
+    V: VRFKeyStorage,
+{
+    let first = first.read_history(None).await?;
+    let second = second.read_history(None).await?;
+
+    let mut labels = BTreeMap::<AkdLabel, (MergeSource, Vec<ValueState>)>::new();
+    let mut conflicts = Vec::new();
+    for (source, history) in [(MergeSource::First, &first), (MergeSource::Second, &second)] {
+        for states in history.labels.iter() {
+            let Some(label) = states.first().map(|state| state.username.clone()) else {
+                continue;
+            };
+            match labels.get(&label) {
+                None => {
+                    labels.insert(label, (source, states.clone()));
+                }
+                Some((_, kept)) => {
+                    let replace = match policy {
+                        MergeConflictPolicy::PreferFirst => false,
+                        MergeConflictPolicy::PreferSecond => true,
+                        MergeConflictPolicy::PreferMostRecent => {
+                            latest_timestamp_ms(states, history) > latest_timestamp_ms(kept, &first)
+                        }
+                    };
+                    let kept = if replace {
+                        labels.insert(label.clone(), (source, states.clone()));
+                        MergeSource::Second
+                    } else {
+                        MergeSource::First
+                    };
+                    conflicts.push(MergeConflict { label, kept });
+                }
+            }
+        }
+    }
+    conflicts.sort_by(|a, b| a.label.cmp(&b.label));
+
+    let num_labels = labels.len() as u64;
+    let mut epochs = BTreeMap::<u64, Vec<ValueState>>::new();
+    for state in labels.into_values().flat_map(|(_, states)| states) {
+        epochs.entry(state.epoch).or_default().push(state);
+    }
+    let last_epoch = first.epoch.max(second.epoch);
+    let metadata = (1..=last_epoch)
+        .filter_map(|epoch| {
+            let timestamp_ms = [&first, &second]
+                .iter()
+                .filter_map(|history| history.metadata.get(&epoch))
+                .map(|metadata| metadata.timestamp_ms)
+                .max()?;
+            Some((
+                epoch,
+                EpochMetadata {
+                    epoch,
+                    timestamp_ms,
+                    update_count: 0,
+                    note: None,
+                    bound: target.binds_epoch_metadata(),
+                    signature: None,
+                },
+            ))
+        })
+        .collect::<HashMap<_, _>>();
+
+    let epoch_hash = target.replay_history(epochs, last_epoch, metadata).await?;
+    info!(
+        "Merged {} labels into the directory at epoch {} ({} conflicts)",
+        num_labels,
+        epoch_hash.epoch(),
+        conflicts.len()
+    );
+    Ok(MergeReport {
+        epoch_hash,
+        num_labels,
+        conflicts,
+    })
+}
+
+/// The publication time of the latest version of a label, if recorded
+fn latest_timestamp_ms(states: &[ValueState], history: &History) -> Option<u64> {
+    let latest = states.iter().max_by_key(|state| state.version)?;
+    history
+        .metadata
+        .get(&latest.epoch)
+        .map(|metadata| metadata.timestamp_ms)
+}
@@ -0,0 +1,145 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! A cache of the lookup proofs generated by a [crate::Directory], configured via
+//! [crate::Directory::with_proof_cache]. Between two publishes, every lookup of a label
+//! generates the same proof, so read-heavy deployments can serve the lookups of hot labels
+//! from memory rather than from the storage layer.
+//!
+//! The proofs are keyed by label and epoch, and only those of the latest epoch seen by the
+//! cache are retained: the cache is emptied as soon as a proof of a later epoch is cached,
+//! and whenever the directory publishes or rolls back an epoch.
+
+use crate::{AkdLabel, EpochHash, LookupProof};
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+/// Statistics about the use of the proof cache of a directory, see
+/// [crate::Directory::proof_cache_stats]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProofCacheStats {
+    /// The number of lookups which were served from the cache
+    pub hits: u64,
+    /// The number of lookups whose proof had to be generated
+    pub misses: u64,
+    /// The number of proofs which were evicted to make room for others
+    pub evictions: u64,
+    /// The number of proofs currently held by the cache
+    pub entries: usize,
+    /// The fraction of lookups which were served from the cache, or [None] if the cache
+    /// has not been used yet
+    pub hit_rate: Option<f64>,
+}
+
+#[derive(Default)]
+struct CacheState {
+    /// The epoch of the cached proofs
+    epoch: u64,
+    proofs: HashMap<AkdLabel, (LookupProof, EpochHash)>,
+    /// The labels of the cached proofs, in the order in which they were inserted
+    insertion_order: VecDeque<AkdLabel>,
+}
+
+impl CacheState {
+    fn clear(&mut self, epoch: u64) {
+        self.epoch = epoch;
+        self.proofs.clear();
+        self.insertion_order.clear();
+    }
+}
+
+/// Keeps up to a bounded number of the lookup proofs of the latest epoch, shared by all clones
+/// of a directory. Once full, the proofs which were cached first are evicted first.
+pub(crate) struct ProofCache {
+    capacity: usize,
+    state: Mutex<CacheState>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl ProofCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            state: Mutex::new(CacheState::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, CacheState> {
+        // The state is left consistent by every operation, so a poisoned lock can be reused
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// The cached lookup proof of the label at the given epoch, if any
+    pub(crate) fn get(&self, label: &AkdLabel, epoch: u64) -> Option<(LookupProof, EpochHash)> {
+        let cached = {
+            let state = self.lock_state();
+            if state.epoch == epoch {
+                state.proofs.get(label).cloned()
+            } else {
+                None
+            }
+        };
+        match cached {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+        cached
+    }
+
+    /// Caches the lookup proof of the label at the given epoch, emptying the cache first if
+    /// the epoch is later than the one of the cached proofs. Proofs of an earlier epoch (e.g.
+    /// generated concurrently with a publish) are not cached.
+    pub(crate) fn insert(&self, label: AkdLabel, epoch: u64, proof: (LookupProof, EpochHash)) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut state = self.lock_state();
+        if epoch < state.epoch {
+            return;
+        }
+        if epoch > state.epoch {
+            state.clear(epoch);
+        }
+        if state.proofs.insert(label.clone(), proof).is_some() {
+            return;
+        }
+        state.insertion_order.push_back(label);
+        if state.insertion_order.len() > self.capacity {
+            if let Some(evicted) = state.insertion_order.pop_front() {
+                state.proofs.remove(&evicted);
+                self.evictions.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Empties the cache, so that no proof generated before is served anymore
+    pub(crate) fn invalidate(&self) {
+        self.lock_state().clear(0);
+    }
+
+    pub(crate) fn stats(&self) -> ProofCacheStats {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        let lookups = hits + misses;
+        ProofCacheStats {
+            hits,
+            misses,
+            evictions: self.evictions.load(Ordering::Relaxed),
+            entries: self.lock_state().proofs.len(),
+            hit_rate: (lookups > 0).then(|| hits as f64 / lookups as f64),
+        }
+    }
+}
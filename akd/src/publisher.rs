@@ -0,0 +1,151 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! A background task which automatically publishes the updates enqueued in a
+//! [Directory] (via [Directory::enqueue_update]) on a fixed cadence, or as soon
+//! as enough updates have accumulated.
+
+use crate::directory::Directory;
+use crate::ecvrf::VRFKeyStorage;
+use crate::errors::AkdError;
+use crate::runtime::{self, JoinHandle};
+use crate::storage::Database;
+use crate::EpochHash;
+
+use akd_core::configuration::Configuration;
+use log::{error, info};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Callback invoked with the number of pending updates right before they are published
+pub type PrePublishHook = Box<dyn Fn(usize) + Send + Sync>;
+/// Callback invoked with the outcome of each publish
+pub type PostPublishHook = Box<dyn Fn(&Result<EpochHash, AkdError>) + Send + Sync>;
+
+/// Publishes the pending updates of a [Directory] automatically. A publish is triggered
+/// whenever the configured interval elapses with updates pending, or immediately once
+/// the number of pending updates reaches the (optional) batch size threshold.
+pub struct Publisher<TC, S: Database, V> {
+    directory: Directory<TC, S, V>,
+    interval: Duration,
+    batch_size_threshold: Option<usize>,
+    pre_publish: Option<PrePublishHook>,
+    post_publish: Option<PostPublishHook>,
+}
+
+impl<TC, S, V> Publisher<TC, S, V>
+where
+    TC: Configuration,
+    S: Database + 'static,
+    V: VRFKeyStorage + 'static,
+{
+    /// Creates a publisher for the provided directory, which publishes on the given interval
+    pub fn new(directory: Directory<TC, S, V>, interval: Duration) -> Self {
+        Self {
+            directory,
+            interval,
+            batch_size_threshold: None,
+            pre_publish: None,
+            post_publish: None,
+        }
+    }
+
+    /// Publish as soon as at least `threshold` updates are pending, without waiting
+    /// for the interval to elapse
+    pub fn with_batch_size_threshold(mut self, threshold: usize) -> Self {
+        self.batch_size_threshold = Some(threshold);
+        self
+    }
+
+    /// Registers a callback which is invoked before each publish
+    pub fn with_pre_publish_hook(mut self, hook: impl Fn(usize) + Send + Sync + 'static) -> Self {
+        self.pre_publish = Some(Box::new(hook));
+        self
+    }
+
+    /// Registers a callback which is invoked after each publish, with its result
+    pub fn with_post_publish_hook(
+        mut self,
+        hook: impl Fn(&Result<EpochHash, AkdError>) + Send + Sync + 'static,
+    ) -> Self {
+        self.post_publish = Some(Box::new(hook));
+        self
+    }
+
+    /// Spawns the publisher onto the installed runtime (see [crate::runtime])
+    pub fn spawn(self) -> PublisherHandle {
+        let directory = self.directory.clone();
+        let stopped = Arc::new(AtomicBool::new(false));
+        let task_stopped = stopped.clone();
+        let task = runtime::spawn(async move { self.run(task_stopped).await });
+        PublisherHandle {
+            stopped,
+            wake: Box::new(move || directory.wake_pending_waiter()),
+            task,
+        }
+    }
+
+    async fn run(self, stopped: Arc<AtomicBool>) {
+        let mut deadline = Instant::now() + self.interval;
+        while !stopped.load(Ordering::SeqCst) {
+            let pending = self.directory.pending_update_count().await;
+            let threshold_reached = self
+                .batch_size_threshold
+                .is_some_and(|threshold| pending >= threshold);
+            let now = Instant::now();
+            if !threshold_reached && now < deadline {
+                // Woken up by either the deadline passing or an update being enqueued
+                let _ = runtime::timeout(deadline - now, self.directory.wait_for_enqueue()).await;
+                continue;
+            }
+
+            deadline = Instant::now() + self.interval;
+            if pending == 0 {
+                continue;
+            }
+
+            if let Some(hook) = &self.pre_publish {
+                hook(pending);
+            }
+            let result = match self.directory.publish_pending().await {
+                Ok(Some(epoch_hash)) => {
+                    info!("Publisher published epoch {}", epoch_hash.epoch());
+                    Ok(epoch_hash)
+                }
+                // The updates were drained in between, so there's nothing to report
+                Ok(None) => continue,
+                Err(err) => {
+                    error!("Publisher failed to publish pending updates: {}", err);
+                    Err(err)
+                }
+            };
+            if let Some(hook) = &self.post_publish {
+                hook(&result);
+            }
+        }
+    }
+}
+
+/// A handle to a running [Publisher]
+pub struct PublisherHandle {
+    stopped: Arc<AtomicBool>,
+    wake: Box<dyn Fn() + Send + Sync>,
+    task: JoinHandle<()>,
+}
+
+impl PublisherHandle {
+    /// Stops the publisher, waiting for any in-flight publish to complete. Updates
+    /// which are still pending remain enqueued in the directory.
+    pub async fn stop(self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        (self.wake)();
+        if let Err(err) = self.task.await {
+            error!("Publisher task terminated abnormally: {}", err);
+        }
+    }
+}
@@ -0,0 +1,333 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Retention of the history of a [crate::Directory], which otherwise grows with every
+//! published epoch. A [RetentionPolicy] configured via [crate::Directory::with_retention_policy]
+//! is enforced after each publish, by pruning:
+//! - The previous states of the tree nodes which are no longer needed to roll back the latest
+//!   epoch of the directory (see [crate::Directory::rollback_last_epoch])
+//! - The [EpochMetadata] of the epochs published longer ago than the audit retention period,
+//!   after which audits starting at these epochs are rejected
+//! - The value states of the versions of a label beyond its most recent ones, whose history
+//!   proofs are then truncated (see [RetentionPolicy::with_max_versions_per_label])
+
+use crate::errors::{AkdError, DirectoryError};
+use crate::storage::manager::StorageManager;
+use crate::storage::types::{DbRecord, ValueState, ValueStateKey};
+use crate::storage::Database;
+use crate::tree_node::{NodeKey, TreeNodeWithPreviousValue};
+use crate::{AkdLabel, EpochMetadata, HistoryParams};
+
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// The number of epochs whose [EpochMetadata] is retrieved at a time while pruning
+const METADATA_BATCH_SIZE: u64 = 1000;
+
+/// The history retained by a directory, configured via
+/// [crate::Directory::with_retention_policy]. By default, everything is retained.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    kept_epochs: Option<u64>,
+    audit_retention: Option<Duration>,
+    max_versions_per_label: Option<usize>,
+}
+
+impl RetentionPolicy {
+    /// Creates a policy which retains everything
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Retains the node states of the last `kept_epochs` epochs (at least 1), so that the latest
+    /// epoch of the directory can still be rolled back. As a proof generation which started
+    /// before a publish may still be reading the node states of the previous epoch, at least 2
+    /// epochs should be kept when other instances serve proofs from the same storage layer.
+    pub fn with_kept_epochs(mut self, kept_epochs: u64) -> Self {
+        self.kept_epochs = Some(kept_epochs.max(1));
+        self
+    }
+
+    /// Retains the [EpochMetadata] of the epochs published within the last `retention`, and
+    /// only serves audits starting at one of these epochs. The metadata of the latest epoch is
+    /// always retained.
+    pub fn with_audit_retention(mut self, retention: Duration) -> Self {
+        self.audit_retention = Some(retention);
+        self
+    }
+
+    /// Retains the value states of the `max_versions` (at least 1) most recent versions of each
+    /// label, so that the storage and history proofs of a label do not grow with each of its
+    /// updates. The history of a label is truncated once it is next updated beyond this number
+    /// of versions.
+    ///
+    /// The history proofs of the directory then only include the most recent versions, and are
+    /// verified with the [HistoryParams] returned by [RetentionPolicy::history_params] (see
+    /// [HistoryParams::capped_to]), so that clients can tell a truncated history from a complete
+    /// one. As the tree still commits to every version, the oldest served update proves that its
+    /// label had previous versions. A directory cannot be forked at, or rolled back to, an epoch
+    /// before the oldest retained version of a label.
+    pub fn with_max_versions_per_label(mut self, max_versions: usize) -> Self {
+        self.max_versions_per_label = Some(max_versions.max(1));
+        self
+    }
+
+    /// The number of epochs whose node states are retained, if bounded
+    pub fn kept_epochs(&self) -> Option<u64> {
+        self.kept_epochs
+    }
+
+    /// The period for which audits are served, if bounded
+    pub fn audit_retention(&self) -> Option<Duration> {
+        self.audit_retention
+    }
+
+    /// The number of versions retained per label, if bounded
+    pub fn max_versions_per_label(&self) -> Option<usize> {
+        self.max_versions_per_label
+    }
+
+    /// The parameters with which the history proofs generated for the given parameters are
+    /// verified, given the number of versions retained per label
+    pub fn history_params(&self, params: HistoryParams) -> HistoryParams {
+        match self.max_versions_per_label {
+            Some(max_versions) => params.capped_to(max_versions),
+            None => params,
+        }
+    }
+}
+
+/// The records pruned by an enforcement of a [RetentionPolicy]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetentionReport {
+    /// The number of tree nodes whose previous state was pruned
+    pub pruned_node_states: u64,
+    /// The number of epochs whose [EpochMetadata] was pruned
+    pub pruned_epoch_metadata: u64,
+    /// The number of value states pruned from the histories of labels
+    pub pruned_value_states: u64,
+}
+
+/// Enforces a [RetentionPolicy], shared by all clones of a directory.
+///
+/// The progress of the enforcement is only tracked in memory, so that the first enforcement
+/// by a new instance visits every node of the tree, while the subsequent ones only visit the
+/// nodes updated since the previous enforcement.
+pub(crate) struct RetentionEnforcer {
+    policy: RetentionPolicy,
+    /// No node whose latest state is from this epoch or earlier has a previous state
+    nodes_pruned_through: AtomicU64,
+    /// The [EpochMetadata] of every epoch up to this one has been pruned
+    metadata_pruned_through: AtomicU64,
+    /// The labels published with more versions than are retained since the previous
+    /// enforcement
+    labels_to_truncate: Mutex<HashSet<AkdLabel>>,
+}
+
+impl RetentionEnforcer {
+    pub(crate) fn new(policy: RetentionPolicy) -> Self {
+        Self {
+            policy,
+            nodes_pruned_through: AtomicU64::new(0),
+            metadata_pruned_through: AtomicU64::new(0),
+            labels_to_truncate: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub(crate) fn policy(&self) -> &RetentionPolicy {
+        &self.policy
+    }
+
+    /// The labels among the value states of an epoch which have more versions than are retained
+    pub(crate) fn labels_over_limit(&self, states: &[ValueState]) -> Vec<AkdLabel> {
+        let Some(max_versions) = self.policy.max_versions_per_label else {
+            return vec![];
+        };
+        states
+            .iter()
+            .filter(|state| state.version > max_versions as u64)
+            .map(|state| state.username.clone())
+            .collect()
+    }
+
+    /// Records the labels of a committed epoch with more versions than are retained (see
+    /// [RetentionEnforcer::labels_over_limit]), which are truncated by the next enforcement
+    pub(crate) fn record_published(&self, labels: Vec<AkdLabel>) {
+        if labels.is_empty() {
+            return;
+        }
+        let mut labels_to_truncate = self
+            .labels_to_truncate
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        labels_to_truncate.extend(labels);
+    }
+
+    /// Prunes the records which the policy no longer retains as of the latest epoch and the
+    /// current time. This must not run concurrently with proof generations or publishes.
+    pub(crate) async fn enforce<S: Database>(
+        &self,
+        storage: &StorageManager<S>,
+        latest_epoch: u64,
+        now_ms: u64,
+    ) -> Result<RetentionReport, AkdError> {
+        let mut report = RetentionReport::default();
+        if let Some(kept_epochs) = self.policy.kept_epochs {
+            // Rolling back to the oldest kept epoch only restores the nodes updated after it
+            let threshold = (latest_epoch + 1).saturating_sub(kept_epochs);
+            report.pruned_node_states = self.prune_node_states(storage, threshold).await?;
+        }
+        if let Some(retention) = self.policy.audit_retention {
+            let cutoff_ms = now_ms.saturating_sub(retention.as_millis() as u64);
+            report.pruned_epoch_metadata = self
+                .prune_epoch_metadata(storage, latest_epoch, cutoff_ms)
+                .await?;
+        }
+        if let Some(max_versions) = self.policy.max_versions_per_label {
+            report.pruned_value_states = self.truncate_histories(storage, max_versions).await?;
+        }
+        Ok(report)
+    }
+
+    /// Prunes the value states of the recorded labels beyond their `max_versions` most recent
+    /// versions
+    async fn truncate_histories<S: Database>(
+        &self,
+        storage: &StorageManager<S>,
+        max_versions: usize,
+    ) -> Result<u64, AkdError> {
+        let labels = std::mem::take(
+            &mut *self
+                .labels_to_truncate
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()),
+        );
+        let mut pruned = Vec::new();
+        for label in labels {
+            let mut states = storage.get_user_data(&label).await?.states;
+            states.sort_by_key(|state| std::cmp::Reverse(state.version));
+            pruned.extend(
+                states
+                    .into_iter()
+                    .skip(max_versions)
+                    .map(|state| ValueStateKey(label.to_vec(), state.epoch)),
+            );
+        }
+
+        storage.batch_delete::<ValueState>(&pruned).await?;
+        Ok(pruned.len() as u64)
+    }
+
+    /// Prunes the previous state of every node whose latest state is from the threshold epoch
+    /// or earlier
+    async fn prune_node_states<S: Database>(
+        &self,
+        storage: &StorageManager<S>,
+        threshold: u64,
+    ) -> Result<u64, AkdError> {
+        let pruned_through = self.nodes_pruned_through.load(Ordering::SeqCst);
+        if threshold <= pruned_through {
+            return Ok(0);
+        }
+
+        // Updating a node updates the epoch of all of its ancestors, so the nodes which
+        // were not updated since the previous enforcement are skipped along with their subtrees
+        let mut pruned = Vec::new();
+        let mut level = vec![NodeKey(crate::ROOT_LABEL)];
+        while !level.is_empty() {
+            let records = storage
+                .batch_get::<TreeNodeWithPreviousValue>(&level)
+                .await?;
+            level = Vec::new();
+            for record in records {
+                let DbRecord::TreeNode(node) = record else {
+                    continue;
+                };
+                if node.latest_node.last_epoch <= pruned_through {
+                    continue;
+                }
+                level.extend(node.latest_node.left_child.map(NodeKey));
+                level.extend(node.latest_node.right_child.map(NodeKey));
+                if node.latest_node.last_epoch <= threshold && node.previous_node.is_some() {
+                    pruned.push(DbRecord::TreeNode(TreeNodeWithPreviousValue {
+                        previous_node: None,
+                        ..node
+                    }));
+                }
+            }
+        }
+
+        let count = pruned.len() as u64;
+        storage.batch_set(pruned).await?;
+        self.nodes_pruned_through.store(threshold, Ordering::SeqCst);
+        Ok(count)
+    }
+
+    /// Prunes the [EpochMetadata] of the epochs before the latest one which were published
+    /// before the cutoff time
+    async fn prune_epoch_metadata<S: Database>(
+        &self,
+        storage: &StorageManager<S>,
+        latest_epoch: u64,
+        cutoff_ms: u64,
+    ) -> Result<u64, AkdError> {
+        let mut pruned_through = self.metadata_pruned_through.load(Ordering::SeqCst);
+        let mut pruned = Vec::new();
+        'batches: while pruned_through + 1 < latest_epoch {
+            let end = (pruned_through + METADATA_BATCH_SIZE).min(latest_epoch - 1);
+            let epochs = (pruned_through + 1..=end).collect::<Vec<_>>();
+            let mut metadata = storage
+                .batch_get::<EpochMetadata>(&epochs)
+                .await?
+                .into_iter()
+                .filter_map(|record| match record {
+                    DbRecord::EpochMetadata(metadata) => Some(metadata),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            metadata.sort_by_key(|metadata| metadata.epoch);
+
+            // The epochs without metadata have already been pruned
+            for metadata in metadata {
+                if metadata.timestamp_ms >= cutoff_ms {
+                    pruned_through = metadata.epoch - 1;
+                    break 'batches;
+                }
+                pruned.push(metadata.epoch);
+            }
+            pruned_through = end;
+        }
+
+        storage.batch_delete::<EpochMetadata>(&pruned).await?;
+        self.metadata_pruned_through
+            .store(pruned_through, Ordering::SeqCst);
+        Ok(pruned.len() as u64)
+    }
+
+    /// Checks that audits starting at the given epoch are still served, given its metadata
+    /// (if it has not been pruned)
+    pub(crate) fn check_audit(
+        &self,
+        start_epoch: u64,
+        metadata: Option<&EpochMetadata>,
+        now_ms: u64,
+    ) -> Result<(), AkdError> {
+        let Some(retention) = self.policy.audit_retention else {
+            return Ok(());
+        };
+        let cutoff_ms = now_ms.saturating_sub(retention.as_millis() as u64);
+        match metadata {
+            Some(metadata) if metadata.timestamp_ms >= cutoff_ms => Ok(()),
+            _ => Err(AkdError::Directory(DirectoryError::InvalidEpoch(format!(
+                "Audits starting at epoch {start_epoch} are no longer served, as it was published more than {} ms ago",
+                retention.as_millis()
+            )))),
+        }
+    }
+}
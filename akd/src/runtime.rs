@@ -0,0 +1,243 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! A facade for the asynchronous runtime on which the library spawns its tasks (e.g. the
+//! parallel insertions of a publish, or the background [crate::publisher::Publisher]) and
+//! waits on timers (e.g. the timeouts of [crate::admission::AdmissionConfig]).
+//!
+//! With the `tokio_runtime` feature (enabled by default), the library runs on the ambient
+//! [tokio] runtime unless another [Runtime] is installed with [set_runtime]. Without it, a
+//! runtime must be installed before a directory is used, which allows embedding the directory
+//! on another executor without depending on the tokio runtime (the library only relies on the
+//! runtime-agnostic synchronization primitives of tokio). Note that the `parallel_vrf` feature
+//! evaluates VRFs on the tokio runtime regardless of the installed runtime.
+//!
+//! For example, a runtime for [`async-std`](https://docs.rs/async-std) only needs to forward
+//! each call to the corresponding function:
+//!
+//! ```ignore
+//! struct AsyncStdRuntime;
+//!
+//! impl akd::runtime::Runtime for AsyncStdRuntime {
+//!     fn spawn(&self, future: BoxFuture<'static, ()>) {
+//!         async_std::task::spawn(future);
+//!     }
+//!
+//!     fn spawn_blocking(&self, task: Box<dyn FnOnce() + Send>) {
+//!         async_std::task::spawn_blocking(task);
+//!     }
+//!
+//!     fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+//!         Box::pin(async_std::task::sleep(duration))
+//!     }
+//! }
+//!
+//! akd::runtime::set_runtime(AsyncStdRuntime).unwrap();
+//! ```
+
+use futures::channel::oneshot;
+use futures::future::{self, BoxFuture, Either};
+use futures::FutureExt;
+use std::fmt;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::pin::Pin;
+use std::sync::OnceLock;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// Spawns the tasks of the library and provides its timers
+pub trait Runtime: Send + Sync {
+    /// Spawns a future which runs to completion in the background
+    fn spawn(&self, future: BoxFuture<'static, ()>);
+
+    /// Runs a closure which may block (e.g. CPU-bound work) on a thread where blocking is
+    /// acceptable
+    fn spawn_blocking(&self, task: Box<dyn FnOnce() + Send>);
+
+    /// Returns a future which completes once the duration has elapsed
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+}
+
+impl<R: Runtime + ?Sized> Runtime for Box<R> {
+    fn spawn(&self, future: BoxFuture<'static, ()>) {
+        (**self).spawn(future)
+    }
+
+    fn spawn_blocking(&self, task: Box<dyn FnOnce() + Send>) {
+        (**self).spawn_blocking(task)
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        (**self).sleep(duration)
+    }
+}
+
+/// The [tokio] runtime, which the library uses by default. The tasks are spawned on the
+/// runtime of the calling task.
+#[cfg(feature = "tokio_runtime")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioRuntime;
+
+#[cfg(feature = "tokio_runtime")]
+impl Runtime for TokioRuntime {
+    fn spawn(&self, future: BoxFuture<'static, ()>) {
+        tokio::task::spawn(future);
+    }
+
+    fn spawn_blocking(&self, task: Box<dyn FnOnce() + Send>) {
+        tokio::task::spawn_blocking(task);
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// The error returned by [set_runtime] when a runtime was already installed
+#[derive(Debug)]
+pub struct SetRuntimeError;
+
+impl std::error::Error for SetRuntimeError {}
+
+impl fmt::Display for SetRuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "A runtime was already installed")
+    }
+}
+
+static RUNTIME: OnceLock<Box<dyn Runtime>> = OnceLock::new();
+
+/// Installs the runtime on which the library spawns its tasks and waits on timers. This can
+/// only be called once in the lifetime of the process, before the library first uses the
+/// runtime, and fails if a runtime was already installed (or [TokioRuntime] was already
+/// installed as the default).
+pub fn set_runtime<R: Runtime + 'static>(runtime: R) -> Result<(), SetRuntimeError> {
+    RUNTIME.set(Box::new(runtime)).map_err(|_| SetRuntimeError)
+}
+
+fn runtime() -> &'static dyn Runtime {
+    #[cfg(feature = "tokio_runtime")]
+    let runtime = RUNTIME.get_or_init(|| Box::new(TokioRuntime));
+    #[cfg(not(feature = "tokio_runtime"))]
+    let runtime = RUNTIME
+        .get()
+        .expect("No runtime was installed, see akd::runtime::set_runtime");
+    runtime
+}
+
+/// The error of a task which panicked, or was dropped by the runtime before completing
+#[derive(Debug)]
+pub(crate) struct JoinError(String);
+
+impl fmt::Display for JoinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Task failed to complete: {}", self.0)
+    }
+}
+
+/// A handle to the output of a task spawned with [spawn] or [spawn_blocking]. As with a tokio
+/// join handle, dropping it detaches the task rather than cancelling it.
+pub(crate) struct JoinHandle<T>(oneshot::Receiver<Result<T, JoinError>>);
+
+impl<T> Future for JoinHandle<T> {
+    type Output = Result<T, JoinError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.0.poll_unpin(cx).map(|result| {
+            result.unwrap_or_else(|_| Err(JoinError("the task was cancelled".to_string())))
+        })
+    }
+}
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> JoinError {
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_default();
+    JoinError(format!("the task panicked ({message})"))
+}
+
+/// Spawns a future on the installed runtime, returning a handle to its output
+pub(crate) fn spawn<F>(future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let (sender, receiver) = oneshot::channel();
+    runtime().spawn(Box::pin(async move {
+        let output = AssertUnwindSafe(future)
+            .catch_unwind()
+            .await
+            .map_err(panic_message);
+        let _ = sender.send(output);
+    }));
+    JoinHandle(receiver)
+}
+
+/// Runs a blocking closure on the installed runtime, returning a handle to its output
+pub(crate) fn spawn_blocking<F, R>(task: F) -> JoinHandle<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let (sender, receiver) = oneshot::channel();
+    runtime().spawn_blocking(Box::new(move || {
+        let output = std::panic::catch_unwind(AssertUnwindSafe(task)).map_err(panic_message);
+        let _ = sender.send(output);
+    }));
+    JoinHandle(receiver)
+}
+
+/// Waits until the duration has elapsed on the installed runtime
+pub(crate) async fn sleep(duration: Duration) {
+    runtime().sleep(duration).await
+}
+
+/// The error returned by [timeout] when the duration elapsed before the future completed
+#[derive(Debug)]
+pub(crate) struct Elapsed;
+
+/// Runs a future until it completes, or until the duration has elapsed on the installed runtime
+pub(crate) async fn timeout<F: Future>(
+    duration: Duration,
+    future: F,
+) -> Result<F::Output, Elapsed> {
+    match future::select(Box::pin(future), runtime().sleep(duration)).await {
+        Either::Left((output, _)) => Ok(output),
+        Either::Right(_) => Err(Elapsed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_spawn() {
+        assert_eq!(3, spawn(async { 1 + 2 }).await.unwrap());
+        assert_eq!(3, spawn_blocking(|| 1 + 2).await.unwrap());
+
+        let error = spawn(async { panic!("failure") }).await.unwrap_err();
+        assert!(error.to_string().contains("failure"));
+        assert!(spawn_blocking(|| panic!("failure")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_timeout() {
+        assert_eq!(
+            1,
+            timeout(Duration::from_secs(60), async { 1 }).await.unwrap()
+        );
+        assert!(
+            timeout(Duration::from_millis(1), sleep(Duration::from_secs(60)))
+                .await
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,318 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! A deterministic simulation of a [Directory] serving interleaved publishes, lookups, key
+//! history and audit requests, for reproducible end-to-end tests.
+//!
+//! Everything which varies between runs of a simulation is derived from its seed:
+//! - The workload, i.e. which operations are issued concurrently at each step
+//! - The time, which is read from a [ManualClock] advanced by a random amount at each step
+//! - The interleaving of the concurrent operations and the order in which batches of records
+//!   are returned by storage, which are perturbed by a [SimulatedDatabase]
+//!
+//! All of the operations of a step are driven by a single future, and so a simulation run on
+//! a current-thread runtime (such as that of `#[tokio::test]`) always produces the same trace
+//! of [Event]s for the same [SimulationConfig]. A failing simulation can then be replayed from
+//! its seed, e.g. with more logging.
+//!
+//! Every proof is verified as it is received, and [Outcome::VerificationFailed] is never
+//! expected in the trace of a correct directory.
+
+use crate::auditor::audit_verify;
+use crate::client::{key_history_verify, lookup_verify};
+use crate::clock::ManualClock;
+use crate::directory::Directory;
+use crate::ecvrf::{HardCodedAkdVRF, VRFPublicKey};
+use crate::errors::AkdError;
+use crate::storage::memory::AsyncInMemoryDatabase;
+use crate::storage::StorageManager;
+use crate::{AkdLabel, AkdValue, Configuration, Digest, HistoryParams, HistoryVerificationParams};
+
+use rand::rngs::StdRng;
+use rand::seq::{IteratorRandom, SliceRandom};
+use rand::{Rng, SeedableRng};
+use std::sync::Mutex;
+use tokio::runtime::{Handle, RuntimeFlavor};
+
+mod storage;
+#[cfg(test)]
+mod tests;
+
+pub use storage::SimulatedDatabase;
+
+type SimulatedDirectory<TC> =
+    Directory<TC, SimulatedDatabase<AsyncInMemoryDatabase>, HardCodedAkdVRF>;
+
+/// The parameters of a simulation
+#[derive(Clone, Debug)]
+pub struct SimulationConfig {
+    /// The seed from which the simulation is derived
+    pub seed: u64,
+    /// The number of distinct labels which are published and looked up
+    pub num_labels: usize,
+    /// The number of steps of the simulation
+    pub num_steps: usize,
+    /// The maximum number of operations which are issued concurrently at each step
+    pub max_concurrency: usize,
+    /// The maximum number of times the scheduler is yielded to before each storage operation
+    pub max_yields: usize,
+    /// The time at which the simulation starts, in milliseconds since the UNIX epoch
+    pub start_time_ms: u64,
+    /// The maximum amount of time which elapses between two steps, in milliseconds
+    pub max_step_duration_ms: u64,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            num_labels: 10,
+            num_steps: 10,
+            max_concurrency: 4,
+            max_yields: 3,
+            start_time_ms: 1_700_000_000_000,
+            max_step_duration_ms: 60_000,
+        }
+    }
+}
+
+/// An operation issued against the directory
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Operation {
+    /// Publishes the updates
+    Publish(Vec<(AkdLabel, AkdValue)>),
+    /// Looks up the label, and verifies the proof
+    Lookup(AkdLabel),
+    /// Retrieves the key history of the label, and verifies the proof
+    KeyHistory(AkdLabel),
+    /// Audits the range of epochs, and verifies the proof
+    Audit {
+        /// The first epoch of the range
+        start_epoch: u64,
+        /// The last epoch of the range
+        end_epoch: u64,
+    },
+}
+
+/// The outcome of an [Operation]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The updates were published
+    Published {
+        /// The new epoch
+        epoch: u64,
+        /// The root hash of the new epoch
+        root_hash: Digest,
+        /// The publication time of the new epoch
+        timestamp_ms: u64,
+    },
+    /// The lookup proof verified
+    LookedUp {
+        /// The epoch of the proof
+        epoch: u64,
+        /// The version of the value of the label
+        version: u64,
+    },
+    /// The key history proof verified
+    HistoryVerified {
+        /// The epoch of the proof
+        epoch: u64,
+        /// The number of versions of the value of the label in the proof
+        num_versions: usize,
+    },
+    /// The audit proof verified
+    Audited,
+    /// The directory rejected the operation, e.g. a lookup of a label which was never published
+    Rejected(String),
+    /// The proof returned by the directory did not verify
+    VerificationFailed(String),
+}
+
+/// An entry of the trace of a simulation, recorded when an operation completes
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Event {
+    /// The step at which the operation was issued
+    pub step: usize,
+    /// The operation
+    pub operation: Operation,
+    /// The outcome of the operation
+    pub outcome: Outcome,
+}
+
+/// Runs a simulation, returning its trace, in the order in which the operations completed.
+/// This fails if it is not run on a current-thread runtime, on which it is deterministic.
+pub async fn run_simulation<TC: Configuration>(
+    config: &SimulationConfig,
+) -> Result<Vec<Event>, AkdError> {
+    if Handle::current().runtime_flavor() != RuntimeFlavor::CurrentThread {
+        return Err(AkdError::TestErr(
+            "A simulation must be run on a current-thread runtime".to_string(),
+        ));
+    }
+
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let clock = ManualClock::new(config.start_time_ms);
+    let storage = StorageManager::new_no_cache(SimulatedDatabase::new(
+        AsyncInMemoryDatabase::new(),
+        rng.gen(),
+        config.max_yields,
+    ));
+    // The tree insertion is not spread across tasks, whose scheduling is not controlled
+    let akd = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {})
+        .await?
+        .with_clock(clock.clone())
+        .with_insert_parallelism(1)
+        .with_vrf_parallelism(1);
+    let vrf_public_key = akd.get_public_key().await?;
+    let labels = (0..config.num_labels)
+        .map(|i| AkdLabel::from(format!("label_{i}").as_str()))
+        .collect::<Vec<_>>();
+
+    let trace = Mutex::new(vec![]);
+    let mut root_hashes = vec![akd.get_epoch_hash().await?.hash()];
+    for step in 0..config.num_steps {
+        clock.advance(rng.gen_range(0..=config.max_step_duration_ms));
+        let operations =
+            generate_operations(config, &labels, root_hashes.len() as u64 - 1, &mut rng);
+
+        let futures = operations.into_iter().map(|operation| {
+            let (akd, vrf_public_key, root_hashes, trace) =
+                (&akd, &vrf_public_key, &root_hashes, &trace);
+            async move {
+                let outcome =
+                    execute::<TC>(akd, vrf_public_key, root_hashes, operation.clone()).await;
+                trace.lock().unwrap().push(Event {
+                    step,
+                    operation,
+                    outcome,
+                });
+            }
+        });
+        futures::future::join_all(futures).await;
+
+        root_hashes.extend(trace.lock().unwrap().iter().filter_map(|event| {
+            match (event.step, &event.outcome) {
+                (event_step, Outcome::Published { root_hash, .. }) if event_step == step => {
+                    Some(*root_hash)
+                }
+                _ => None,
+            }
+        }));
+    }
+    Ok(trace.into_inner().unwrap())
+}
+
+/// Generates the operations issued at a step, of which at most one is a publish (since
+/// concurrent publishes to the same storage are not supported). The first step always
+/// publishes, so that the other operations are not all rejected.
+fn generate_operations(
+    config: &SimulationConfig,
+    labels: &[AkdLabel],
+    latest_epoch: u64,
+    rng: &mut StdRng,
+) -> Vec<Operation> {
+    let mut operations = vec![];
+    let num_operations = rng.gen_range(1..=config.max_concurrency);
+    if latest_epoch == 0 || rng.gen_bool(0.5) {
+        let num_updates = rng.gen_range(1..=labels.len());
+        let value = AkdValue::from(format!("value_{}", rng.gen::<u32>()).as_str());
+        operations.push(Operation::Publish(
+            labels
+                .iter()
+                .choose_multiple(rng, num_updates)
+                .into_iter()
+                .map(|label| (label.clone(), value.clone()))
+                .collect(),
+        ));
+    }
+    while operations.len() < num_operations {
+        let label = labels.choose(rng).unwrap().clone();
+        operations.push(match rng.gen_range(0..3) {
+            0 => Operation::Lookup(label),
+            1 => Operation::KeyHistory(label),
+            _ if latest_epoch >= 2 => {
+                let start_epoch = rng.gen_range(1..latest_epoch);
+                Operation::Audit {
+                    start_epoch,
+                    end_epoch: rng.gen_range(start_epoch + 1..=latest_epoch),
+                }
+            }
+            _ => Operation::Lookup(label),
+        });
+    }
+    operations.shuffle(rng);
+    operations
+}
+
+async fn execute<TC: Configuration>(
+    akd: &SimulatedDirectory<TC>,
+    vrf_public_key: &VRFPublicKey,
+    root_hashes: &[Digest],
+    operation: Operation,
+) -> Outcome {
+    let result = match operation {
+        Operation::Publish(updates) => match akd.publish(updates).await {
+            Ok(epoch_hash) => akd
+                .get_epoch_metadata(epoch_hash.epoch())
+                .await
+                .map(|metadata| Outcome::Published {
+                    epoch: epoch_hash.epoch(),
+                    root_hash: epoch_hash.hash(),
+                    timestamp_ms: metadata.timestamp_ms,
+                }),
+            Err(err) => Err(err),
+        },
+        Operation::Lookup(label) => akd.lookup(label.clone()).await.map(|(proof, epoch_hash)| {
+            match lookup_verify::<TC>(
+                vrf_public_key.as_bytes(),
+                epoch_hash.hash(),
+                epoch_hash.epoch(),
+                label,
+                proof,
+            ) {
+                Ok(result) => Outcome::LookedUp {
+                    epoch: epoch_hash.epoch(),
+                    version: result.version,
+                },
+                Err(err) => Outcome::VerificationFailed(err.to_string()),
+            }
+        }),
+        Operation::KeyHistory(label) => akd
+            .key_history(&label, HistoryParams::default())
+            .await
+            .map(|(proof, epoch_hash)| {
+                match key_history_verify::<TC>(
+                    vrf_public_key.as_bytes(),
+                    epoch_hash.hash(),
+                    epoch_hash.epoch(),
+                    label,
+                    proof,
+                    HistoryVerificationParams::default(),
+                ) {
+                    Ok(results) => Outcome::HistoryVerified {
+                        epoch: epoch_hash.epoch(),
+                        num_versions: results.len(),
+                    },
+                    Err(err) => Outcome::VerificationFailed(err.to_string()),
+                }
+            }),
+        Operation::Audit {
+            start_epoch,
+            end_epoch,
+        } => match akd.audit(start_epoch, end_epoch).await {
+            Ok(proof) => {
+                let hashes = root_hashes[start_epoch as usize..=end_epoch as usize].to_vec();
+                Ok(match audit_verify::<TC>(hashes, proof).await {
+                    Ok(()) => Outcome::Audited,
+                    Err(err) => Outcome::VerificationFailed(err.to_string()),
+                })
+            }
+            Err(err) => Err(err),
+        },
+    };
+    result.unwrap_or_else(|err| Outcome::Rejected(err.to_string()))
+}
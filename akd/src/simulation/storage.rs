@@ -0,0 +1,162 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! A storage layer which perturbs the scheduling and ordering of the operations of another
+//! storage layer, deterministically from a seed
+
+use crate::errors::StorageError;
+use crate::storage::types::{
+    DbRecord, KeyData, LabelEntry, PublishLease, ValueState, ValueStateRetrievalFlag,
+};
+use crate::storage::{Database, DbSetState, Storable};
+use crate::{AkdLabel, AkdValue};
+
+#[cfg(not(feature = "unboxed_storage"))]
+use async_trait::async_trait;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Wraps a [Database], yielding to the scheduler a seeded random number of times before
+/// each operation (so that the operations of concurrent futures interleave differently
+/// for different seeds) and returning the records of batch retrievals in a seeded random
+/// order. Clones share the same random number generator.
+#[derive(Clone)]
+pub struct SimulatedDatabase<S> {
+    inner: S,
+    rng: Arc<Mutex<StdRng>>,
+    max_yields: usize,
+}
+
+impl<S: Database> SimulatedDatabase<S> {
+    /// Wraps the provided database, yielding at most `max_yields` times before each operation
+    pub fn new(inner: S, seed: u64, max_yields: usize) -> Self {
+        Self {
+            inner,
+            rng: Arc::new(Mutex::new(StdRng::seed_from_u64(seed))),
+            max_yields,
+        }
+    }
+
+    async fn perturb(&self) {
+        let yields = self.rng.lock().unwrap().gen_range(0..=self.max_yields);
+        for _ in 0..yields {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    fn shuffle(&self, records: &mut [DbRecord]) {
+        records.shuffle(&mut *self.rng.lock().unwrap());
+    }
+}
+
+#[cfg_attr(not(feature = "unboxed_storage"), async_trait)]
+impl<S: Database> Database for SimulatedDatabase<S> {
+    async fn set(&self, record: DbRecord) -> Result<(), StorageError> {
+        self.perturb().await;
+        self.inner.set(record).await
+    }
+
+    async fn batch_set(
+        &self,
+        records: Vec<DbRecord>,
+        state: DbSetState,
+    ) -> Result<(), StorageError> {
+        self.perturb().await;
+        self.inner.batch_set(records, state).await
+    }
+
+    async fn get<St: Storable>(&self, id: &St::StorageKey) -> Result<DbRecord, StorageError> {
+        self.perturb().await;
+        self.inner.get::<St>(id).await
+    }
+
+    async fn batch_get<St: Storable>(
+        &self,
+        ids: &[St::StorageKey],
+    ) -> Result<Vec<DbRecord>, StorageError> {
+        self.perturb().await;
+        let mut records = self.inner.batch_get::<St>(ids).await?;
+        self.shuffle(&mut records);
+        Ok(records)
+    }
+
+    async fn get_user_data(&self, username: &AkdLabel) -> Result<KeyData, StorageError> {
+        self.perturb().await;
+        self.inner.get_user_data(username).await
+    }
+
+    async fn get_user_state(
+        &self,
+        username: &AkdLabel,
+        flag: ValueStateRetrievalFlag,
+    ) -> Result<ValueState, StorageError> {
+        self.perturb().await;
+        self.inner.get_user_state(username, flag).await
+    }
+
+    async fn get_user_state_versions(
+        &self,
+        usernames: &[AkdLabel],
+        flag: ValueStateRetrievalFlag,
+    ) -> Result<HashMap<AkdLabel, (u64, AkdValue)>, StorageError> {
+        self.perturb().await;
+        self.inner.get_user_state_versions(usernames, flag).await
+    }
+
+    async fn acquire_publish_lease(
+        &self,
+        holder: &[u8],
+        now_ms: u64,
+        ttl_ms: u64,
+    ) -> Result<Option<PublishLease>, StorageError> {
+        self.perturb().await;
+        self.inner
+            .acquire_publish_lease(holder, now_ms, ttl_ms)
+            .await
+    }
+
+    async fn release_publish_lease(&self, lease: &PublishLease) -> Result<(), StorageError> {
+        self.perturb().await;
+        self.inner.release_publish_lease(lease).await
+    }
+
+    async fn batch_delete<St: Storable>(&self, ids: &[St::StorageKey]) -> Result<(), StorageError> {
+        self.perturb().await;
+        self.inner.batch_delete::<St>(ids).await
+    }
+
+    async fn batch_set_and_delete(
+        &self,
+        records: Vec<DbRecord>,
+        deletions: Vec<Vec<u8>>,
+        state: DbSetState,
+    ) -> Result<(), StorageError> {
+        self.perturb().await;
+        self.inner
+            .batch_set_and_delete(records, deletions, state)
+            .await
+    }
+
+    // The labels are listed in order, which is part of the contract of the storage layer
+    async fn list_labels(
+        &self,
+        cursor: Option<&AkdLabel>,
+        limit: usize,
+        epoch: u64,
+    ) -> Result<Vec<LabelEntry>, StorageError> {
+        self.perturb().await;
+        self.inner.list_labels(cursor, limit, epoch).await
+    }
+
+    async fn count_labels(&self, epoch: u64) -> Result<u64, StorageError> {
+        self.perturb().await;
+        self.inner.count_labels(epoch).await
+    }
+}
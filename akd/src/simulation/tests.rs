@@ -0,0 +1,81 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Tests of the deterministic simulation harness
+
+use super::{run_simulation, Event, Operation, Outcome, SimulationConfig};
+use crate::errors::AkdError;
+use crate::test_config;
+use crate::Configuration;
+
+fn config(seed: u64) -> SimulationConfig {
+    SimulationConfig {
+        seed,
+        ..SimulationConfig::default()
+    }
+}
+
+/// Checks that no proof failed to verify, and that the epochs were published in order
+fn check_trace(trace: &[Event]) {
+    let mut latest_epoch = 0;
+    let mut latest_timestamp_ms = 0;
+    for event in trace {
+        match &event.outcome {
+            Outcome::VerificationFailed(err) => {
+                panic!("The proof of {:?} failed to verify: {err}", event.operation)
+            }
+            Outcome::Published {
+                epoch,
+                timestamp_ms,
+                ..
+            } => {
+                assert_eq!(latest_epoch + 1, *epoch);
+                assert!(*timestamp_ms >= latest_timestamp_ms);
+                latest_epoch = *epoch;
+                latest_timestamp_ms = *timestamp_ms;
+            }
+            Outcome::Rejected(err) => assert!(
+                !matches!(event.operation, Operation::Publish(_)),
+                "A publish was rejected: {err}"
+            ),
+            _ => {}
+        }
+    }
+}
+
+test_config!(test_simulation_is_reproducible);
+async fn test_simulation_is_reproducible<TC: Configuration>() -> Result<(), AkdError> {
+    for seed in 0..2 {
+        let trace = run_simulation::<TC>(&config(seed)).await?;
+        check_trace(&trace);
+        assert_eq!(trace, run_simulation::<TC>(&config(seed)).await?);
+    }
+    Ok(())
+}
+
+test_config!(test_simulation_depends_on_seed);
+async fn test_simulation_depends_on_seed<TC: Configuration>() -> Result<(), AkdError> {
+    let mut trace = run_simulation::<TC>(&config(0)).await?;
+    let other_trace = run_simulation::<TC>(&config(1)).await?;
+    assert_ne!(trace, other_trace);
+    trace.extend(other_trace);
+
+    // Every kind of operation is exercised
+    assert!(trace
+        .iter()
+        .any(|event| matches!(event.outcome, Outcome::Published { .. })));
+    assert!(trace
+        .iter()
+        .any(|event| matches!(event.outcome, Outcome::LookedUp { .. })));
+    assert!(trace
+        .iter()
+        .any(|event| matches!(event.outcome, Outcome::HistoryVerified { .. })));
+    assert!(trace
+        .iter()
+        .any(|event| matches!(event.outcome, Outcome::Audited)));
+    Ok(())
+}
@@ -11,6 +11,7 @@
 use super::{CachedItem, DEFAULT_CACHE_CLEAN_FREQUENCY_MS, DEFAULT_ITEM_LIFETIME_MS};
 use crate::storage::DbRecord;
 use crate::storage::Storable;
+use crate::telemetry::{self, Counter};
 use akd_core::SizeOf;
 use dashmap::DashMap;
 #[cfg(not(feature = "runtime_metrics"))]
@@ -108,7 +109,7 @@ impl TimedCache {
                         .iter()
                         .map(|kv| (kv.key().clone(), kv.value().expiration))
                         .collect::<Vec<_>>();
-                    keys_and_expiration.sort_by(|(_, a), (_, b)| a.cmp(b));
+                    keys_and_expiration.sort_by_key(|(_, a)| *a);
                     // take `num_clean` old entries and remove them
                     for key in keys_and_expiration
                         .into_iter()
@@ -192,10 +193,12 @@ impl TimedCache {
             // of an in-memory transaction and should ignore expiration
             // of cache items until this flag is disabled again
             if ignore_clean || result.expiration > Instant::now() {
+                telemetry::increment_counter(Counter::CacheHits, 1, &[]);
                 return Some(result.data.clone());
             }
         }
 
+        telemetry::increment_counter(Counter::CacheMisses, 1, &[]);
         None
     }
 
@@ -237,6 +240,21 @@ impl TimedCache {
         }
     }
 
+    /// Remove a batch of items from the cache
+    pub async fn batch_remove(&self, keys: &[Vec<u8>]) {
+        let azks_key = crate::append_only_zks::Azks::get_full_binary_key_id(
+            &crate::append_only_zks::DEFAULT_AZKS_KEY,
+        );
+        for key in keys {
+            // special case for AZKS
+            if *key == azks_key {
+                *self.azks.write().await = None;
+            } else {
+                self.map.remove(key);
+            }
+        }
+    }
+
     /// Flush the cache
     pub async fn flush(&self) {
         self.map.clear();
@@ -31,6 +31,7 @@ async fn test_cache_put_and_expires() {
         },
         value: AkdValue::from("some value"),
         username: AkdLabel::from("user"),
+        blinding: None,
     });
     let key = ValueStateKey(AkdLabel::from("user").0.to_vec(), 1);
     cache.put(&value_state).await;
@@ -57,6 +58,7 @@ async fn test_cache_overwrite() {
         },
         value: AkdValue::from("some value"),
         username: AkdLabel::from("user"),
+        blinding: None,
     };
     let key = ValueStateKey(AkdLabel::from("user").0.to_vec(), 1);
 
@@ -69,6 +71,7 @@ async fn test_cache_overwrite() {
         },
         value: AkdValue::from("some value"),
         username: AkdLabel::from("user"),
+        blinding: None,
     };
     cache.put(&DbRecord::ValueState(value_state)).await;
     cache
@@ -96,6 +99,7 @@ async fn test_cache_memory_pressure() {
         },
         value: AkdValue::from("some value"),
         username: AkdLabel::from("user"),
+        blinding: None,
     });
     let key = ValueStateKey(AkdLabel::from("user").0.to_vec(), 1);
     cache.put(&value_state).await;
@@ -127,6 +131,7 @@ async fn test_many_memory_pressure() {
             },
             value: AkdValue::from("test"),
             username: AkdLabel::from("user"),
+            blinding: None,
         })
         .map(DbRecord::ValueState)
         .collect::<Vec<_>>();
@@ -13,24 +13,27 @@ use crate::storage::cache::TimedCache;
 use crate::storage::transaction::Transaction;
 use crate::storage::types::DbRecord;
 use crate::storage::types::KeyData;
+use crate::storage::types::LabelEntry;
+use crate::storage::types::PublishLease;
 use crate::storage::types::ValueState;
 use crate::storage::Database;
 use crate::storage::DbSetState;
 use crate::storage::Storable;
 use crate::storage::StorageError;
+use crate::tree_node::{NodeKey, TreeNodeWithPreviousValue};
 use crate::AkdLabel;
 use crate::AkdValue;
 
-use log::debug;
 #[cfg(feature = "runtime_metrics")]
-use log::{error, info, warn};
+use log::error;
+use log::{debug, info, warn};
 use std::collections::HashMap;
 use std::collections::HashSet;
 #[cfg(feature = "runtime_metrics")]
 use std::sync::atomic::AtomicU64;
 #[cfg(feature = "runtime_metrics")]
 use std::sync::atomic::Ordering;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use super::types::ValueStateRetrievalFlag;
@@ -51,28 +54,55 @@ const METRIC_GET_USER_STATE_VERSIONS: Metric = 9;
 #[cfg(feature = "runtime_metrics")]
 const NUM_METRICS: usize = 10;
 
+mod preload;
+mod stats;
 #[cfg(test)]
 mod tests;
 
+pub use preload::{FullBfsPreload, LookupSetPreload, PrefixLimitedPreload, PreloadStrategy};
+use stats::StatsRecorder;
+pub use stats::{LatencyPercentiles, StorageStats};
+
 /// Represents the manager of the storage mediums, including caching
 /// and transactional operations (creating the transaction, committing it, etc)
 pub struct StorageManager<Db: Database> {
     cache: Option<TimedCache>,
+    /// Whether the cache is scoped to a single operation (see [StorageManager::with_operation_cache]),
+    /// in which case its lookups are not reported in the cache statistics
+    operation_cache: bool,
     transaction: Transaction,
     /// The underlying database managed by this storage manager
     db: Arc<Db>,
     #[cfg(feature = "runtime_metrics")]
     metrics: [Arc<AtomicU64>; NUM_METRICS],
+    stats: Arc<StatsRecorder>,
+    /// The strategy with which the nodes visited by a publish are preloaded into the cache
+    preload_strategy: Arc<dyn PreloadStrategy>,
+    /// The size (in bytes) above which the tree nodes of the active transaction are written to
+    /// storage ahead of its commit, see [StorageManager::with_transaction_memory_cap]
+    transaction_memory_cap: Option<usize>,
+    /// The keys of the tree nodes of the active transaction which were written to storage ahead
+    /// of its commit, and are to be reverted if it is rolled back
+    spilled_nodes: Arc<Mutex<HashSet<NodeKey>>>,
+    /// The fencing token under which the active transaction writes to storage, see
+    /// [StorageManager::begin_fenced_transaction]
+    fencing_token: Arc<Mutex<Option<u64>>>,
 }
 
 impl<Db: Database> Clone for StorageManager<Db> {
     fn clone(&self) -> Self {
         Self {
             cache: self.cache.clone(),
+            operation_cache: self.operation_cache,
             transaction: self.transaction.clone(),
             db: self.db.clone(),
             #[cfg(feature = "runtime_metrics")]
             metrics: self.metrics.clone(),
+            stats: self.stats.clone(),
+            preload_strategy: self.preload_strategy.clone(),
+            transaction_memory_cap: self.transaction_memory_cap,
+            spilled_nodes: self.spilled_nodes.clone(),
+            fencing_token: self.fencing_token.clone(),
         }
     }
 }
@@ -85,10 +115,16 @@ impl<Db: Database> StorageManager<Db> {
     pub fn new_no_cache(db: Db) -> Self {
         Self {
             cache: None,
+            operation_cache: false,
             transaction: Transaction::new(),
             db: Arc::new(db),
             #[cfg(feature = "runtime_metrics")]
             metrics: [0; NUM_METRICS].map(|_| Arc::new(AtomicU64::new(0))),
+            stats: Arc::new(StatsRecorder::default()),
+            preload_strategy: Arc::new(LookupSetPreload),
+            transaction_memory_cap: None,
+            spilled_nodes: Arc::new(Mutex::new(HashSet::new())),
+            fencing_token: Arc::new(Mutex::new(None)),
         }
     }
 
@@ -105,10 +141,16 @@ impl<Db: Database> StorageManager<Db> {
                 cache_limit_bytes,
                 cache_clean_frequency,
             )),
+            operation_cache: false,
             transaction: Transaction::new(),
             db: Arc::new(db),
             #[cfg(feature = "runtime_metrics")]
             metrics: [0; NUM_METRICS].map(|_| Arc::new(AtomicU64::new(0))),
+            stats: Arc::new(StatsRecorder::default()),
+            preload_strategy: Arc::new(LookupSetPreload),
+            transaction_memory_cap: None,
+            spilled_nodes: Arc::new(Mutex::new(HashSet::new())),
+            fencing_token: Arc::new(Mutex::new(None)),
         }
     }
 
@@ -118,11 +160,61 @@ impl<Db: Database> StorageManager<Db> {
         self.db.clone()
     }
 
+    /// Returns a storage manager over the same database (and transaction) which, if this manager
+    /// has no cache, caches the records it reads in a cache of its own. This is meant for a single
+    /// operation which prefetches the records it needs, so that they are not read individually
+    /// again by the remainder of the operation.
+    pub(crate) fn with_operation_cache(&self) -> Self {
+        let mut storage = self.clone();
+        if storage.cache.is_none() {
+            storage.cache = Some(TimedCache::new(None, None, None));
+            storage.operation_cache = true;
+        }
+        storage
+    }
+
+    /// Configures the [PreloadStrategy] with which the tree nodes visited by a publish are loaded
+    /// into the cache beforehand, trading the memory used by the cache for the number of
+    /// individual reads during the publish. The default is [LookupSetPreload]. Nothing is
+    /// preloaded by a storage manager without a cache.
+    pub fn with_preload_strategy<P: PreloadStrategy + 'static>(mut self, strategy: P) -> Self {
+        self.preload_strategy = Arc::new(strategy);
+        self
+    }
+
+    /// Caps the memory held by the records of an active transaction (e.g. those of a publish)
+    /// to approximately `max_bytes`. Whenever the records exceed the cap, the tree nodes among
+    /// them are written to storage ahead of the commit rather than buffered until then.
+    ///
+    /// This is safe for readers, since a tree node keeps its state at the previous epoch, which
+    /// is the one they read until the commit writes the [crate::Azks] record. If the transaction
+    /// is rolled back instead, the spilled nodes are reverted to their previous state, while the
+    /// spilled nodes which were new are left in storage, unreachable from the tree.
+    pub fn with_transaction_memory_cap(mut self, max_bytes: usize) -> Self {
+        self.transaction_memory_cap = Some(max_bytes);
+        self
+    }
+
+    /// Retrieve the approximate size (in bytes) of the records held by the active transaction
+    pub fn transaction_memory_usage(&self) -> usize {
+        self.transaction.memory_usage()
+    }
+
+    /// The strategy with which the tree nodes visited by a publish are preloaded
+    pub(crate) fn preload_strategy(&self) -> &dyn PreloadStrategy {
+        self.preload_strategy.as_ref()
+    }
+
     /// Returns whether the storage manager has a cache
     pub fn has_cache(&self) -> bool {
         self.cache.is_some()
     }
 
+    /// Retrieve the cache and database access statistics of the storage manager
+    pub fn stats(&self) -> StorageStats {
+        self.stats.snapshot()
+    }
+
     /// Log metrics from the storage manager (cache, transaction, and storage hit rates etc)
     pub async fn log_metrics(&self, level: log::Level) {
         if let Some(cache) = &self.cache {
@@ -184,6 +276,9 @@ impl<Db: Database> StorageManager<Db> {
     /// Start an in-memory transaction of changes
     pub fn begin_transaction(&self) -> bool {
         let started = self.transaction.begin_transaction();
+        if started {
+            self.lock_fencing_token().take();
+        }
 
         // disable the cache cleaning since we're in a write transaction
         // and will want to keep cached objects for the life of the transaction
@@ -194,11 +289,28 @@ impl<Db: Database> StorageManager<Db> {
         started
     }
 
+    /// Start a transaction made under the publish lease with the given fencing token. Its commit,
+    /// as well as the tree nodes written ahead of it, are written with [DbSetState::Fenced], so
+    /// that the data layer rejects them if the lease has been taken over in the meantime.
+    pub fn begin_fenced_transaction(&self, fencing_token: u64) -> bool {
+        if !self.begin_transaction() {
+            return false;
+        }
+        *self.lock_fencing_token() = Some(fencing_token);
+        true
+    }
+
     /// Commit a transaction in the database
     pub async fn commit_transaction(&self) -> Result<u64, StorageError> {
         // this retrieves all the trans operations, and "de-activates" the transaction flag
-        let records = self.transaction.commit_transaction()?;
-        let num_records = records.len();
+        let (records, deletions) = self.transaction.commit_transaction()?;
+        let num_records = records.len() + deletions.len();
+        // The token is retained until the commit succeeds, so that a rollback after a rejected
+        // commit reverts the spilled nodes under it as well
+        let state = match *self.lock_fencing_token() {
+            Some(fencing_token) => DbSetState::Fenced(fencing_token),
+            None => DbSetState::TransactionCommit,
+        };
 
         // The transaction is now complete (or reverted) and therefore we can re-enable
         // the cache cleaning status
@@ -206,7 +318,7 @@ impl<Db: Database> StorageManager<Db> {
             cache.enable_clean();
         }
 
-        if records.is_empty() {
+        if records.is_empty() && deletions.is_empty() {
             // no-op, there's nothing to commit
             return Ok(0);
         }
@@ -218,29 +330,180 @@ impl<Db: Database> StorageManager<Db> {
             ))),
         }?;
 
-        // update the cache
+        // update the cache, evicting the records again if the write is rejected
+        let cached_ids = match &self.cache {
+            Some(cache) => {
+                cache.batch_put(&records).await;
+                records
+                    .iter()
+                    .map(|record| record.get_full_binary_id())
+                    .collect::<Vec<_>>()
+            }
+            None => vec![],
+        };
+
+        // Write to the database, along with the deletions of the transaction (if any)
+        let written = if deletions.is_empty() {
+            self.tic_toc(METRIC_WRITE_TIME, self.db.batch_set(records, state))
+                .await
+        } else {
+            self.tic_toc(
+                METRIC_WRITE_TIME,
+                self.db
+                    .batch_set_and_delete(records, deletions.clone(), state),
+            )
+            .await
+        };
+        if let Err(err) = written {
+            if let Some(cache) = &self.cache {
+                cache.batch_remove(&cached_ids).await;
+            }
+            return Err(err);
+        }
         if let Some(cache) = &self.cache {
-            cache.batch_put(&records).await;
+            cache.batch_remove(&deletions).await;
         }
+        self.increment_metric(METRIC_BATCH_SET);
+        self.lock_spilled_nodes().clear();
+        self.lock_fencing_token().take();
+        Ok(num_records as u64)
+    }
 
-        // Write to the database
+    /// Rollback a transaction, reverting the tree nodes which were written to storage ahead of
+    /// its commit (see [StorageManager::with_transaction_memory_cap]). These are reverted even
+    /// if the transaction failed to commit, and so is no longer active.
+    pub async fn rollback_transaction(&self) -> Result<(), StorageError> {
+        let rolled_back = self.transaction.rollback_transaction();
+        if rolled_back.is_ok() {
+            // The transaction is being reverted and therefore we can re-enable
+            // the cache cleaning status
+            if let Some(cache) = &self.cache {
+                cache.enable_clean();
+            }
+        }
+        let reverted = self.revert_spilled_nodes().await;
+        self.lock_fencing_token().take();
+        reverted?;
+        rolled_back
+    }
+
+    fn lock_fencing_token(&self) -> std::sync::MutexGuard<'_, Option<u64>> {
+        self.fencing_token
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// The state with which the records of the active transaction are written ahead of its commit
+    fn spill_state(&self) -> DbSetState {
+        match *self.lock_fencing_token() {
+            Some(fencing_token) => DbSetState::Fenced(fencing_token),
+            None => DbSetState::General,
+        }
+    }
+
+    fn lock_spilled_nodes(&self) -> std::sync::MutexGuard<'_, HashSet<NodeKey>> {
+        // The set is left consistent by every operation, so a poisoned lock can be reused
+        self.spilled_nodes
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Writes the tree nodes of the active transaction to storage if its records exceed the
+    /// configured memory cap
+    async fn spill_if_over_cap(&self) -> Result<(), StorageError> {
+        let Some(cap) = self.transaction_memory_cap else {
+            return Ok(());
+        };
+        if self.transaction.memory_usage() <= cap {
+            return Ok(());
+        }
+        let records = self.transaction.tree_nodes();
+        if records.is_empty() {
+            return Ok(());
+        }
+        let keys = records
+            .iter()
+            .filter_map(|record| match record {
+                DbRecord::TreeNode(node) => Some(NodeKey(node.label)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        // The keys are recorded first, so that the nodes are reverted on a rollback even if
+        // the write fails midway
+        self.lock_spilled_nodes().extend(keys);
+
+        debug!(
+            "Transaction holds {} bytes, writing {} tree nodes to storage ahead of the commit",
+            self.transaction.memory_usage(),
+            records.len()
+        );
         self.tic_toc(
             METRIC_WRITE_TIME,
-            self.db.batch_set(records, DbSetState::TransactionCommit),
+            self.db.batch_set(records.clone(), self.spill_state()),
         )
         .await?;
         self.increment_metric(METRIC_BATCH_SET);
-        Ok(num_records as u64)
+        // The spilled nodes are read from storage from now on, rather than kept in the cache
+        if let Some(cache) = &self.cache {
+            let ids = records
+                .iter()
+                .map(|record| record.get_full_binary_id())
+                .collect::<Vec<_>>();
+            cache.batch_remove(&ids).await;
+        }
+        self.transaction.remove_spilled(&records);
+        Ok(())
     }
 
-    /// Rollback a transaction
-    pub fn rollback_transaction(&self) -> Result<(), StorageError> {
-        self.transaction.rollback_transaction()?;
-        // The transaction is being reverted and therefore we can re-enable
-        // the cache cleaning status
+    /// Reverts the tree nodes which were written to storage ahead of the commit of a transaction
+    /// which is being rolled back to their previous state
+    async fn revert_spilled_nodes(&self) -> Result<(), StorageError> {
+        // The keys are only forgotten once the nodes are reverted, so that a failed revert is
+        // retried by a later rollback
+        let keys = self
+            .lock_spilled_nodes()
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        if keys.is_empty() {
+            return Ok(());
+        }
         if let Some(cache) = &self.cache {
-            cache.enable_clean();
+            let ids = keys
+                .iter()
+                .map(TreeNodeWithPreviousValue::get_full_binary_key_id)
+                .collect::<Vec<_>>();
+            cache.batch_remove(&ids).await;
+        }
+        let reverted = self
+            .db
+            .batch_get::<TreeNodeWithPreviousValue>(&keys)
+            .await?
+            .into_iter()
+            .filter_map(|record| match record {
+                DbRecord::TreeNode(TreeNodeWithPreviousValue {
+                    label,
+                    previous_node: Some(previous_node),
+                    ..
+                }) => Some(DbRecord::TreeNode(TreeNodeWithPreviousValue {
+                    label,
+                    latest_node: previous_node,
+                    previous_node: None,
+                })),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        info!(
+            "Reverting {} tree nodes written ahead of a rolled back transaction",
+            reverted.len()
+        );
+        if !reverted.is_empty() {
+            if let Err(err) = self.db.batch_set(reverted, self.spill_state()).await {
+                warn!("Failed to revert the tree nodes of a rolled back transaction: {err}");
+                return Err(err);
+            }
         }
+        self.lock_spilled_nodes().clear();
         Ok(())
     }
 
@@ -268,7 +531,7 @@ impl<Db: Database> StorageManager<Db> {
         // we're in a transaction, set the item in the transaction
         if self.is_transaction_active() {
             self.transaction.set(&record);
-            return Ok(());
+            return self.spill_if_over_cap().await;
         }
 
         // update the cache
@@ -292,7 +555,7 @@ impl<Db: Database> StorageManager<Db> {
         // we're in a transaction, set the items in the transaction
         if self.is_transaction_active() {
             self.transaction.batch_set(&records);
-            return Ok(());
+            return self.spill_if_over_cap().await;
         }
 
         // update the cache
@@ -336,8 +599,12 @@ impl<Db: Database> StorageManager<Db> {
 
         // check for a cache hit
         if let Some(cache) = &self.cache {
-            if let Some(result) = cache.hit_test::<St>(id).await {
-                return Some(result);
+            let result = cache.hit_test::<St>(id).await;
+            if !self.operation_cache {
+                self.stats.record_cache_lookup(result.is_some());
+            }
+            if result.is_some() {
+                return result;
             }
         }
 
@@ -346,6 +613,13 @@ impl<Db: Database> StorageManager<Db> {
 
     /// Retrieve a stored record from the database
     pub async fn get<St: Storable>(&self, id: &St::StorageKey) -> Result<DbRecord, StorageError> {
+        if self.is_transaction_active() && self.transaction.is_deleted::<St>(id) {
+            return Err(StorageError::NotFound(format!(
+                "{:?} {:?} (deleted in the transaction)",
+                St::data_type(),
+                id
+            )));
+        }
         if let Some(result) = self.get_from_cache_only::<St>(id).await {
             return Ok(result);
         }
@@ -388,11 +662,19 @@ impl<Db: Database> StorageManager<Db> {
                     key_set.remove(id);
                     continue;
                 }
+                if self.transaction.is_deleted::<St>(id) {
+                    key_set.remove(id);
+                    continue;
+                }
             }
 
             // check if item is cached
             if let Some(cache) = &self.cache {
-                if let Some(result) = cache.hit_test::<St>(id).await {
+                let result = cache.hit_test::<St>(id).await;
+                if !self.operation_cache {
+                    self.stats.record_cache_lookup(result.is_some());
+                }
+                if let Some(result) = result {
                     records.push(result);
                     key_set.remove(id);
                     continue;
@@ -434,13 +716,14 @@ impl<Db: Database> StorageManager<Db> {
         let key_data = self.get_user_data(username).await?;
         let mut new_data = vec![];
         for value_state in key_data.states.into_iter() {
-            if value_state.epoch <= epoch && value_state.value.0 != crate::TOMBSTONE {
+            if value_state.epoch <= epoch && !value_state.value.is_tombstone() {
                 new_data.push(DbRecord::ValueState(ValueState {
                     epoch: value_state.epoch,
                     label: value_state.label,
-                    value: crate::AkdValue(crate::TOMBSTONE.to_vec()),
+                    value: crate::AkdValue::Tombstone,
                     username: value_state.username,
                     version: value_state.version,
+                    blinding: None,
                 }));
             }
         }
@@ -526,7 +809,7 @@ impl<Db: Database> StorageManager<Db> {
 
             let transaction_records = self
                 .transaction
-                .get_users_data(&[username.clone()])
+                .get_users_data(std::slice::from_ref(username))
                 .remove(username)
                 .unwrap_or_default();
             for transaction_record in transaction_records.into_iter() {
@@ -585,6 +868,67 @@ impl<Db: Database> StorageManager<Db> {
         Ok(data)
     }
 
+    /// Acquire or renew the advisory publish lease directly in the data layer,
+    /// see [Database::acquire_publish_lease]
+    pub async fn acquire_publish_lease(
+        &self,
+        holder: &[u8],
+        now_ms: u64,
+        ttl_ms: u64,
+    ) -> Result<Option<PublishLease>, StorageError> {
+        self.db.acquire_publish_lease(holder, now_ms, ttl_ms).await
+    }
+
+    /// Release the advisory publish lease directly in the data layer,
+    /// see [Database::release_publish_lease]
+    pub async fn release_publish_lease(&self, lease: &PublishLease) -> Result<(), StorageError> {
+        self.db.release_publish_lease(lease).await
+    }
+
+    /// Delete a batch of records in the data layer, evicting them from the cache. In a
+    /// transaction, the records are deleted in the transaction instead, and so only from storage
+    /// when it is committed (with [Database::batch_set_and_delete]). The records deleted in a
+    /// transaction are not found by [StorageManager::get] and [StorageManager::batch_get] until
+    /// it ends, but still are by the user data queries.
+    pub async fn batch_delete<St: Storable>(
+        &self,
+        ids: &[St::StorageKey],
+    ) -> Result<(), StorageError> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        // we're in a transaction, delete the items in the transaction
+        if self.is_transaction_active() {
+            self.transaction.batch_delete::<St>(ids);
+            return Ok(());
+        }
+
+        if let Some(cache) = &self.cache {
+            let keys = ids
+                .iter()
+                .map(|id| St::get_full_binary_key_id(id))
+                .collect::<Vec<_>>();
+            cache.batch_remove(&keys).await;
+        }
+        self.db.batch_delete::<St>(ids).await
+    }
+
+    /// Enumerate the labels in the data layer, ignoring any caching or transaction pending
+    pub async fn list_labels(
+        &self,
+        cursor: Option<&AkdLabel>,
+        limit: usize,
+        epoch: u64,
+    ) -> Result<Vec<LabelEntry>, StorageError> {
+        self.db.list_labels(cursor, limit, epoch).await
+    }
+
+    /// Count the labels in the data layer, ignoring any caching or transaction pending
+    pub async fn count_labels(&self, epoch: u64) -> Result<u64, StorageError> {
+        self.db.count_labels(epoch).await
+    }
+
     fn compare_db_and_transaction_records(
         state_epoch: u64,
         transaction_value: ValueState,
@@ -629,20 +973,18 @@ impl<Db: Database> StorageManager<Db> {
         }
     }
 
-    async fn tic_toc<T>(&self, _metric: Metric, f: impl std::future::Future<Output = T>) -> T {
-        #[cfg(feature = "runtime_metrics")]
-        {
-            let tic = std::time::Instant::now();
-            let out = f.await;
-            let delta = std::time::Instant::now().duration_since(tic);
-
-            self.metrics[_metric].fetch_add(delta.as_millis() as u64, Ordering::Relaxed);
+    async fn tic_toc<T>(&self, metric: Metric, f: impl std::future::Future<Output = T>) -> T {
+        let tic = std::time::Instant::now();
+        let out = f.await;
+        let delta = std::time::Instant::now().duration_since(tic);
 
-            out
-        }
-        #[cfg(not(feature = "runtime_metrics"))]
-        {
-            f.await
+        match metric {
+            METRIC_WRITE_TIME => self.stats.record_write(delta),
+            _ => self.stats.record_read(delta),
         }
+        #[cfg(feature = "runtime_metrics")]
+        self.metrics[metric].fetch_add(delta.as_millis() as u64, Ordering::Relaxed);
+
+        out
     }
 }
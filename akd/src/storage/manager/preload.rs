@@ -0,0 +1,62 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! The strategies with which the tree nodes visited by a publish are preloaded into the cache
+//! of the [super::StorageManager], see [super::StorageManager::with_preload_strategy]
+
+use crate::NodeLabel;
+
+/// Determines which tree nodes are loaded into the cache before a publish inserts its leaves.
+///
+/// Nodes are loaded in batches, one level of the tree at a time starting from the root, and the
+/// children of a loaded node are loaded in the next batch if [PreloadStrategy::expand] returns
+/// true for it. The nodes which are not preloaded are read individually from storage when the
+/// insertion visits them.
+pub trait PreloadStrategy: Send + Sync {
+    /// Whether the children of the loaded node with the given label should be loaded as well.
+    /// `on_path` is whether the node is on the path from the root to one of the inserted leaves,
+    /// i.e. whether the insertion visits its children.
+    fn expand(&self, label: &NodeLabel, on_path: bool) -> bool;
+}
+
+/// Loads the whole tree, so that a publish does not read any node individually, at the cost of
+/// holding every node in the cache. This is only suitable for small trees.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FullBfsPreload;
+
+impl PreloadStrategy for FullBfsPreload {
+    fn expand(&self, _label: &NodeLabel, _on_path: bool) -> bool {
+        true
+    }
+}
+
+/// Loads the nodes on the paths to the inserted leaves, down to the nodes whose label is at
+/// least `max_prefix_len` bits long. The deeper nodes of these paths, which are shared by few of
+/// the inserted leaves, are read individually, bounding the size of the preload of a large
+/// publish.
+#[derive(Debug, Clone, Copy)]
+pub struct PrefixLimitedPreload {
+    /// The length (in bits) of the labels of the nodes whose children are not preloaded
+    pub max_prefix_len: u32,
+}
+
+impl PreloadStrategy for PrefixLimitedPreload {
+    fn expand(&self, label: &NodeLabel, on_path: bool) -> bool {
+        on_path && label.label_len < self.max_prefix_len
+    }
+}
+
+/// Loads every node on the paths to the inserted leaves, i.e. exactly the nodes visited by the
+/// insertion. This is the default strategy.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LookupSetPreload;
+
+impl PreloadStrategy for LookupSetPreload {
+    fn expand(&self, _label: &NodeLabel, on_path: bool) -> bool {
+        on_path
+    }
+}
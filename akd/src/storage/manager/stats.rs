@@ -0,0 +1,132 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Tracking of cache and storage access statistics for the [super::StorageManager]
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// The number of most recent operations from which latency percentiles are computed
+const LATENCY_WINDOW_SIZE: usize = 1024;
+
+/// Latency percentiles over the most recent storage operations, in microseconds
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Deserialize, serde::Serialize)
+)]
+pub struct LatencyPercentiles {
+    /// The number of operations the percentiles are computed from
+    pub samples: u64,
+    /// The median latency
+    pub p50_us: u64,
+    /// The 90th percentile latency
+    pub p90_us: u64,
+    /// The 99th percentile latency
+    pub p99_us: u64,
+    /// The maximum latency
+    pub max_us: u64,
+}
+
+/// Access statistics of a [super::StorageManager], since its creation
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Deserialize, serde::Serialize)
+)]
+pub struct StorageStats {
+    /// The number of records which were served from the cache
+    pub cache_hits: u64,
+    /// The number of records which were looked up in the cache, but had to be read from storage
+    pub cache_misses: u64,
+    /// The fraction of cache lookups which were hits, or [None] if there is no cache or it
+    /// has not been used yet
+    pub cache_hit_rate: Option<f64>,
+    /// Latencies of the reads from the database
+    pub read_latency: LatencyPercentiles,
+    /// Latencies of the writes to the database
+    pub write_latency: LatencyPercentiles,
+}
+
+#[derive(Default)]
+struct LatencyWindow {
+    samples_us: Mutex<VecDeque<u64>>,
+}
+
+impl LatencyWindow {
+    fn record(&self, duration: Duration) {
+        if let Ok(mut samples) = self.samples_us.lock() {
+            if samples.len() == LATENCY_WINDOW_SIZE {
+                samples.pop_front();
+            }
+            samples.push_back(duration.as_micros() as u64);
+        }
+    }
+
+    fn percentiles(&self) -> LatencyPercentiles {
+        let mut samples = match self.samples_us.lock() {
+            Ok(samples) => samples.iter().copied().collect::<Vec<_>>(),
+            Err(_) => return LatencyPercentiles::default(),
+        };
+        if samples.is_empty() {
+            return LatencyPercentiles::default();
+        }
+        samples.sort_unstable();
+        // nearest-rank percentile
+        let percentile = |p: usize| samples[(samples.len() * p).div_ceil(100).max(1) - 1];
+        LatencyPercentiles {
+            samples: samples.len() as u64,
+            p50_us: percentile(50),
+            p90_us: percentile(90),
+            p99_us: percentile(99),
+            max_us: samples[samples.len() - 1],
+        }
+    }
+}
+
+/// Records the statistics which are reported in [StorageStats]
+#[derive(Default)]
+pub(crate) struct StatsRecorder {
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+    reads: LatencyWindow,
+    writes: LatencyWindow,
+}
+
+impl StatsRecorder {
+    pub(crate) fn record_cache_lookup(&self, hit: bool) {
+        let counter = if hit {
+            &self.cache_hits
+        } else {
+            &self.cache_misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_read(&self, duration: Duration) {
+        self.reads.record(duration);
+    }
+
+    pub(crate) fn record_write(&self, duration: Duration) {
+        self.writes.record(duration);
+    }
+
+    pub(crate) fn snapshot(&self) -> StorageStats {
+        let cache_hits = self.cache_hits.load(Ordering::Relaxed);
+        let cache_misses = self.cache_misses.load(Ordering::Relaxed);
+        let lookups = cache_hits + cache_misses;
+        StorageStats {
+            cache_hits,
+            cache_misses,
+            cache_hit_rate: (lookups > 0).then(|| cache_hits as f64 / lookups as f64),
+            read_latency: self.reads.percentiles(),
+            write_latency: self.writes.percentiles(),
+        }
+    }
+}
@@ -294,3 +294,254 @@ async fn test_storage_manager_cache_populated_by_batch_get() {
             .await
     );
 }
+
+#[tokio::test]
+async fn test_storage_manager_stats() {
+    let db = AsyncInMemoryDatabase::new();
+    let storage_manager =
+        StorageManager::new(db, Some(std::time::Duration::from_secs(1000)), None, None);
+    assert_eq!(StorageStats::default(), storage_manager.stats());
+
+    let record = DbRecord::build_epoch_metadata(1, 1234, 1, None, false, None);
+    storage_manager
+        .set(DbRecord::EpochMetadata(record))
+        .await
+        .expect("Failed to set record");
+
+    // a cache hit, followed by two misses which fall through to the database
+    storage_manager
+        .get::<EpochMetadata>(&1)
+        .await
+        .expect("Failed to get record");
+    assert!(storage_manager.get::<EpochMetadata>(&2).await.is_err());
+    storage_manager.flush_cache().await;
+    storage_manager
+        .get::<EpochMetadata>(&1)
+        .await
+        .expect("Failed to get record");
+
+    let stats = storage_manager.stats();
+    assert_eq!(1, stats.cache_hits);
+    assert_eq!(2, stats.cache_misses);
+    assert_eq!(Some(1.0 / 3.0), stats.cache_hit_rate);
+    assert_eq!(2, stats.read_latency.samples);
+    assert_eq!(1, stats.write_latency.samples);
+    assert!(stats.read_latency.p50_us <= stats.read_latency.p99_us);
+    assert!(stats.read_latency.p99_us <= stats.read_latency.max_us);
+}
+
+#[tokio::test]
+async fn test_storage_manager_transaction_memory_cap() {
+    let db = AsyncInMemoryDatabase::new();
+    // every write to the transaction exceeds the cap
+    let storage_manager = StorageManager::new(db, None, None, None).with_transaction_memory_cap(1);
+
+    let node = |label: NodeLabel, epoch: u64| crate::tree_node::TreeNode {
+        label,
+        last_epoch: epoch,
+        min_descendant_epoch: epoch,
+        parent: NodeLabel::root(),
+        node_type: crate::tree_node::TreeNodeType::Leaf,
+        left_child: None,
+        right_child: None,
+        hash: AzksValue(EMPTY_DIGEST),
+    };
+    let (modified, new) = (NodeLabel::new([1u8; 32], 1), NodeLabel::new([2u8; 32], 2));
+    let committed = TreeNodeWithPreviousValue {
+        label: modified,
+        latest_node: node(modified, 1),
+        previous_node: None,
+    };
+    let updated = TreeNodeWithPreviousValue {
+        label: modified,
+        latest_node: node(modified, 2),
+        previous_node: Some(node(modified, 1)),
+    };
+    let added = TreeNodeWithPreviousValue {
+        label: new,
+        latest_node: node(new, 2),
+        previous_node: None,
+    };
+    storage_manager
+        .set(DbRecord::TreeNode(committed.clone()))
+        .await
+        .expect("Failed to set record");
+
+    // The tree nodes of the transaction are written to storage rather than buffered
+    assert!(storage_manager.begin_transaction());
+    storage_manager
+        .batch_set(vec![
+            DbRecord::TreeNode(updated.clone()),
+            DbRecord::TreeNode(added),
+        ])
+        .await
+        .expect("Failed to set batch of records");
+    assert_eq!(0, storage_manager.transaction.count());
+    assert_eq!(0, storage_manager.transaction_memory_usage());
+    assert_eq!(
+        Ok(DbRecord::TreeNode(updated.clone())),
+        storage_manager
+            .get::<TreeNodeWithPreviousValue>(&NodeKey(modified))
+            .await
+    );
+
+    // A rollback reverts the modified nodes to their previous state
+    storage_manager
+        .rollback_transaction()
+        .await
+        .expect("Failed to rollback transaction");
+    assert_eq!(
+        Ok(DbRecord::TreeNode(committed)),
+        storage_manager
+            .get::<TreeNodeWithPreviousValue>(&NodeKey(modified))
+            .await
+    );
+
+    // The records which are not tree nodes are kept in the transaction until the commit
+    assert!(storage_manager.begin_transaction());
+    storage_manager
+        .batch_set(vec![
+            DbRecord::TreeNode(updated.clone()),
+            DbRecord::Azks(Azks {
+                latest_epoch: 2,
+                num_nodes: 2,
+            }),
+        ])
+        .await
+        .expect("Failed to set batch of records");
+    assert_eq!(1, storage_manager.transaction.count());
+    assert!(storage_manager.transaction_memory_usage() > 0);
+    assert_eq!(Ok(1), storage_manager.commit_transaction().await);
+    assert_eq!(
+        Ok(DbRecord::TreeNode(updated)),
+        storage_manager
+            .get_direct::<TreeNodeWithPreviousValue>(&NodeKey(modified))
+            .await
+    );
+}
+
+#[tokio::test]
+async fn test_storage_manager_fenced_transaction() {
+    let db = AsyncInMemoryDatabase::new();
+    let storage_manager = StorageManager::new(db.clone(), None, None, None);
+    let first = db
+        .acquire_publish_lease(b"first", 1000, 100)
+        .await
+        .unwrap()
+        .expect("Lease should be available");
+    let azks = |latest_epoch| {
+        DbRecord::Azks(Azks {
+            latest_epoch,
+            num_nodes: 1,
+        })
+    };
+
+    // A transaction commits while its lease is current
+    assert!(storage_manager.begin_fenced_transaction(first.fencing_token));
+    storage_manager
+        .set(azks(1))
+        .await
+        .expect("Failed to set record");
+    assert_eq!(Ok(1), storage_manager.commit_transaction().await);
+
+    // Once the lease has changed hands, the commit is rejected and leaves neither the storage
+    // nor the cache modified
+    assert!(storage_manager.begin_fenced_transaction(first.fencing_token));
+    storage_manager
+        .set(azks(2))
+        .await
+        .expect("Failed to set record");
+    db.acquire_publish_lease(b"second", 1200, 100)
+        .await
+        .unwrap()
+        .expect("Expired lease should be available");
+    assert!(storage_manager.commit_transaction().await.is_err());
+    // The transaction is no longer active, but the rollback still reverts any spilled nodes
+    let _ = storage_manager.rollback_transaction().await;
+    assert_eq!(
+        Ok(azks(1)),
+        storage_manager
+            .get::<Azks>(&crate::append_only_zks::DEFAULT_AZKS_KEY)
+            .await
+    );
+
+    // A transaction which is not fenced is written regardless of the lease
+    assert!(storage_manager.begin_transaction());
+    storage_manager
+        .set(azks(2))
+        .await
+        .expect("Failed to set record");
+    assert_eq!(Ok(1), storage_manager.commit_transaction().await);
+}
+
+#[tokio::test]
+async fn test_storage_manager_transactional_delete() {
+    let db = AsyncInMemoryDatabase::new();
+    let storage_manager = StorageManager::new(db.clone(), None, None, None);
+    let azks = |latest_epoch| {
+        DbRecord::Azks(Azks {
+            latest_epoch,
+            num_nodes: 1,
+        })
+    };
+    let metadata = |epoch| {
+        DbRecord::EpochMetadata(EpochMetadata {
+            epoch,
+            timestamp_ms: 1000 * epoch,
+            update_count: 0,
+            note: None,
+            bound: false,
+            signature: None,
+        })
+    };
+    storage_manager
+        .batch_set(vec![azks(2), metadata(1), metadata(2)])
+        .await
+        .expect("Failed to set batch of records");
+
+    // The records deleted in a transaction are no longer found in it, but are only deleted from
+    // storage when it is committed
+    assert!(storage_manager.begin_transaction());
+    storage_manager
+        .batch_delete::<EpochMetadata>(&[2])
+        .await
+        .expect("Failed to delete record");
+    assert!(matches!(
+        storage_manager.get::<EpochMetadata>(&2).await,
+        Err(StorageError::NotFound(_))
+    ));
+    assert_eq!(
+        Ok(vec![metadata(1)]),
+        storage_manager.batch_get::<EpochMetadata>(&[1, 2]).await
+    );
+    assert_eq!(Ok(metadata(2)), db.get::<EpochMetadata>(&2).await);
+    storage_manager
+        .rollback_transaction()
+        .await
+        .expect("Failed to roll back transaction");
+    assert_eq!(
+        Ok(metadata(2)),
+        storage_manager.get::<EpochMetadata>(&2).await
+    );
+
+    // A record set again after its deletion is written rather than deleted
+    assert!(storage_manager.begin_transaction());
+    storage_manager
+        .batch_delete::<EpochMetadata>(&[1, 2])
+        .await
+        .expect("Failed to delete records");
+    storage_manager
+        .batch_set(vec![metadata(1), azks(1)])
+        .await
+        .expect("Failed to set batch of records");
+    assert_eq!(Ok(3), storage_manager.commit_transaction().await);
+    assert_eq!(Ok(metadata(1)), db.get::<EpochMetadata>(&1).await);
+    assert!(matches!(
+        db.get::<EpochMetadata>(&2).await,
+        Err(StorageError::NotFound(_))
+    ));
+    assert!(matches!(
+        storage_manager.get::<EpochMetadata>(&2).await,
+        Err(StorageError::NotFound(_))
+    ));
+}
@@ -11,14 +11,15 @@
 
 use crate::errors::StorageError;
 use crate::storage::types::{
-    DbRecord, KeyData, StorageType, ValueState, ValueStateKey, ValueStateRetrievalFlag,
+    DbRecord, KeyData, LabelEntry, PublishLease, StorageType, ValueState, ValueStateKey,
+    ValueStateRetrievalFlag,
 };
 use crate::storage::{Database, Storable, StorageUtil};
 use crate::{AkdLabel, AkdValue};
 use async_trait::async_trait;
 use dashmap::DashMap;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, MutexGuard};
 
 type Epoch = u64;
 type UserValueMap = HashMap<Epoch, ValueState>;
@@ -30,6 +31,7 @@ type UserValueMap = HashMap<Epoch, ValueState>;
 pub struct AsyncInMemoryDatabase {
     db: Arc<DashMap<Vec<u8>, DbRecord>>,
     user_info: Arc<DashMap<Vec<u8>, UserValueMap>>,
+    publish_lease: Arc<Mutex<Option<PublishLease>>>,
 }
 
 unsafe impl Send for AsyncInMemoryDatabase {}
@@ -41,6 +43,7 @@ impl AsyncInMemoryDatabase {
         Self::default()
     }
 
+    /// Remove all records from the database
     #[cfg(test)]
     pub fn clear(&self) {
         self.db.clear();
@@ -74,20 +77,32 @@ impl AsyncInMemoryDatabase {
             )))
         }
     }
-}
 
-#[async_trait]
-impl Database for AsyncInMemoryDatabase {
-    async fn set(&self, record: DbRecord) -> Result<(), StorageError> {
-        self.batch_set(vec![record], crate::storage::DbSetState::General)
-            .await
+    /// Locks the publish lease if the write is made under [crate::storage::DbSetState::Fenced],
+    /// rejecting it if the lease has since been issued with a greater fencing token
+    fn lock_fenced_lease(
+        &self,
+        state: &crate::storage::DbSetState,
+    ) -> Result<Option<MutexGuard<'_, Option<PublishLease>>>, StorageError> {
+        let crate::storage::DbSetState::Fenced(fencing_token) = *state else {
+            return Ok(None);
+        };
+        let guard = self
+            .publish_lease
+            .lock()
+            .map_err(|err| StorageError::Other(format!("Publish lease lock poisoned: {err}")))?;
+        if let Some(lease) = guard.as_ref() {
+            if lease.fencing_token > fencing_token {
+                return Err(StorageError::Transaction(format!(
+                    "Write fenced off: the publish lease was issued with fencing token {} after {}",
+                    lease.fencing_token, fencing_token
+                )));
+            }
+        }
+        Ok(Some(guard))
     }
 
-    async fn batch_set(
-        &self,
-        records: Vec<DbRecord>,
-        _state: crate::storage::DbSetState,
-    ) -> Result<(), StorageError> {
+    fn set_internal(&self, records: Vec<DbRecord>) {
         for record in records.into_iter() {
             if let DbRecord::ValueState(value_state) = record {
                 let username = value_state.username.to_vec();
@@ -105,13 +120,44 @@ impl Database for AsyncInMemoryDatabase {
                 self.db.insert(record.get_full_binary_id(), record);
             }
         }
+    }
+
+    fn delete_internal(&self, bin_id: &[u8]) {
+        if bin_id.first() == Some(&(StorageType::ValueState as u8)) {
+            if let Ok(ValueStateKey(username, epoch)) = ValueState::key_from_full_binary(bin_id) {
+                if let Some(mut states) = self.user_info.get_mut(&username) {
+                    states.remove(&epoch);
+                }
+            }
+        } else {
+            self.db.remove(bin_id);
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "unboxed_storage"), async_trait)]
+impl Database for AsyncInMemoryDatabase {
+    async fn set(&self, record: DbRecord) -> Result<(), StorageError> {
+        self.batch_set(vec![record], crate::storage::DbSetState::General)
+            .await
+    }
+
+    async fn batch_set(
+        &self,
+        records: Vec<DbRecord>,
+        state: crate::storage::DbSetState,
+    ) -> Result<(), StorageError> {
+        // The lease is held locked during the write, so that it cannot change hands midway
+        let lease_guard = self.lock_fenced_lease(&state)?;
+        self.set_internal(records);
+        drop(lease_guard);
         Ok(())
     }
 
     /// Retrieve a stored record from the data layer
     async fn get<St: Storable>(&self, id: &St::StorageKey) -> Result<DbRecord, StorageError> {
         #[cfg(feature = "slow_internal_db")]
-        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+        crate::runtime::sleep(std::time::Duration::from_millis(10)).await;
 
         self.get_internal::<St>(id).await
     }
@@ -122,7 +168,7 @@ impl Database for AsyncInMemoryDatabase {
         ids: &[St::StorageKey],
     ) -> Result<Vec<DbRecord>, StorageError> {
         #[cfg(feature = "slow_internal_db")]
-        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+        crate::runtime::sleep(std::time::Duration::from_millis(10)).await;
 
         let mut records = Vec::new();
         for key in ids.iter() {
@@ -139,7 +185,7 @@ impl Database for AsyncInMemoryDatabase {
         if let Some(result) = self.user_info.get(&username.0) {
             let mut results: Vec<ValueState> = result.values().cloned().collect::<Vec<_>>();
             // return ordered by epoch (from smallest -> largest)
-            results.sort_by(|a, b| a.epoch.cmp(&b.epoch));
+            results.sort_by_key(|a| a.epoch);
 
             Ok(KeyData { states: results })
         } else {
@@ -223,12 +269,112 @@ impl Database for AsyncInMemoryDatabase {
             if let Ok(result) = self.get_user_state(username, flag).await {
                 map.insert(
                     AkdLabel(result.username.to_vec()),
-                    (result.version, AkdValue(result.value.to_vec())),
+                    (result.version, AkdValue::Bytes(result.value.to_vec())),
                 );
             }
         }
         Ok(map)
     }
+
+    async fn acquire_publish_lease(
+        &self,
+        holder: &[u8],
+        now_ms: u64,
+        ttl_ms: u64,
+    ) -> Result<Option<PublishLease>, StorageError> {
+        let mut guard = self
+            .publish_lease
+            .lock()
+            .map_err(|err| StorageError::Other(format!("Publish lease lock poisoned: {err}")))?;
+        let fencing_token = match &*guard {
+            Some(lease) if lease.expires_at_ms > now_ms => {
+                if lease.holder != holder {
+                    return Ok(None);
+                }
+                lease.fencing_token
+            }
+            Some(lease) => lease.fencing_token + 1,
+            None => 1,
+        };
+        let lease = PublishLease {
+            holder: holder.to_vec(),
+            fencing_token,
+            expires_at_ms: now_ms + ttl_ms,
+        };
+        *guard = Some(lease.clone());
+        Ok(Some(lease))
+    }
+
+    async fn release_publish_lease(&self, lease: &PublishLease) -> Result<(), StorageError> {
+        let mut guard = self
+            .publish_lease
+            .lock()
+            .map_err(|err| StorageError::Other(format!("Publish lease lock poisoned: {err}")))?;
+        if let Some(current) = guard.as_mut() {
+            // Expire the lease, but retain the fencing token so that it keeps increasing
+            if current.fencing_token == lease.fencing_token && current.holder == lease.holder {
+                current.expires_at_ms = 0;
+            }
+        }
+        Ok(())
+    }
+
+    async fn batch_delete<St: Storable>(&self, ids: &[St::StorageKey]) -> Result<(), StorageError> {
+        for id in ids.iter() {
+            self.delete_internal(&St::get_full_binary_key_id(id));
+        }
+        Ok(())
+    }
+
+    async fn batch_set_and_delete(
+        &self,
+        records: Vec<DbRecord>,
+        deletions: Vec<Vec<u8>>,
+        state: crate::storage::DbSetState,
+    ) -> Result<(), StorageError> {
+        let lease_guard = self.lock_fenced_lease(&state)?;
+        for bin_id in deletions.iter() {
+            self.delete_internal(bin_id);
+        }
+        self.set_internal(records);
+        drop(lease_guard);
+        Ok(())
+    }
+
+    async fn list_labels(
+        &self,
+        cursor: Option<&AkdLabel>,
+        limit: usize,
+        epoch: u64,
+    ) -> Result<Vec<LabelEntry>, StorageError> {
+        let mut entries = self
+            .user_info
+            .iter()
+            .filter(|item| cursor.is_none_or(|cursor| item.key() > &cursor.0))
+            .filter_map(|item| {
+                item.value()
+                    .values()
+                    .filter(|state| state.epoch <= epoch)
+                    .max_by_key(|state| state.epoch)
+                    .map(|state| LabelEntry {
+                        label: AkdLabel(item.key().clone()),
+                        current_version: Some(state.version),
+                        last_update_epoch: Some(state.epoch),
+                    })
+            })
+            .collect::<Vec<_>>();
+        entries.sort_unstable_by(|a, b| a.label.cmp(&b.label));
+        entries.truncate(limit);
+        Ok(entries)
+    }
+
+    async fn count_labels(&self, epoch: u64) -> Result<u64, StorageError> {
+        Ok(self
+            .user_info
+            .iter()
+            .filter(|item| item.value().values().any(|state| state.epoch <= epoch))
+            .count() as u64)
+    }
 }
 
 #[async_trait]
@@ -242,6 +388,16 @@ impl StorageUtil for AsyncInMemoryDatabase {
                 DbRecord::Azks(_) => St::data_type() == StorageType::Azks,
                 DbRecord::TreeNode(_) => St::data_type() == StorageType::TreeNode,
                 DbRecord::ValueState(_) => St::data_type() == StorageType::ValueState,
+                DbRecord::EpochMetadata(_) => St::data_type() == StorageType::EpochMetadata,
+                DbRecord::RollbackRecord(_) => St::data_type() == StorageType::RollbackRecord,
+                DbRecord::PrecomputedLookupProof(_) => {
+                    St::data_type() == StorageType::PrecomputedLookupProof
+                }
+                DbRecord::UpdateJournal(_) => St::data_type() == StorageType::UpdateJournal,
+                DbRecord::EpochRootRecord(_) => St::data_type() == StorageType::EpochRootRecord,
+                DbRecord::PrecomputedVrfProof(_) => {
+                    St::data_type() == StorageType::PrecomputedVrfProof
+                }
             })
             .collect();
 
@@ -15,6 +15,8 @@ use async_trait::async_trait;
 #[cfg(feature = "serde_serialization")]
 use serde::{de::DeserializeOwned, Serialize};
 use std::collections::HashMap;
+#[cfg(feature = "unboxed_storage")]
+use std::future::Future;
 use std::hash::Hash;
 use std::marker::{Send, Sync};
 
@@ -39,6 +41,11 @@ pub enum DbSetState {
     TransactionCommit,
     /// Being called as a general, in-line operation
     General,
+    /// Being called as part of (or ahead of) the commit of a transaction made under the publish
+    /// lease with the given fencing token, see [manager::StorageManager::begin_fenced_transaction].
+    /// Storage layers implementing [Database::acquire_publish_lease] must atomically reject the
+    /// write if the lease has since been issued with a greater fencing token.
+    Fenced(u64),
 }
 
 /// Storable represents an _item_ which can be stored in the storage layer
@@ -92,45 +99,416 @@ pub trait Storable: Clone + Sync + 'static {
 }
 
 /// A database implementation backing storage for the AKD
-#[async_trait]
+///
+/// With the `unboxed_storage` feature, the methods return unboxed futures, so that
+/// implementations with `async fn` methods (e.g. in-memory or embedded backends) do not allocate
+/// for each call on the hot read paths. Storage layers which are implemented with
+/// [macro@async_trait] should implement [BoxedDatabase] instead.
+#[cfg(feature = "unboxed_storage")]
 pub trait Database: Send + Sync {
     /// Set a record in the database
-    async fn set(&self, record: DbRecord) -> Result<(), StorageError>;
+    fn set(&self, record: DbRecord) -> impl Future<Output = Result<(), StorageError>> + Send;
 
     /// Set multiple records in the database with a minimal set of operations
-    async fn batch_set(
+    fn batch_set(
         &self,
         records: Vec<DbRecord>,
         state: DbSetState,
-    ) -> Result<(), StorageError>;
+    ) -> impl Future<Output = Result<(), StorageError>> + Send;
 
     /// Retrieve a stored record from the database
-    async fn get<St: Storable>(&self, id: &St::StorageKey) -> Result<DbRecord, StorageError>;
+    fn get<St: Storable>(
+        &self,
+        id: &St::StorageKey,
+    ) -> impl Future<Output = Result<DbRecord, StorageError>> + Send;
 
     /// Retrieve a batch of records by id from the database
-    async fn batch_get<St: Storable>(
+    fn batch_get<St: Storable>(
         &self,
         ids: &[St::StorageKey],
-    ) -> Result<Vec<DbRecord>, StorageError>;
+    ) -> impl Future<Output = Result<Vec<DbRecord>, StorageError>> + Send;
 
     /* User data searching */
 
     /// Retrieve the user data for a given user
-    async fn get_user_data(&self, username: &AkdLabel) -> Result<types::KeyData, StorageError>;
+    fn get_user_data(
+        &self,
+        username: &AkdLabel,
+    ) -> impl Future<Output = Result<types::KeyData, StorageError>> + Send;
 
     /// Retrieve a specific state for a given user
-    async fn get_user_state(
+    fn get_user_state(
         &self,
         username: &AkdLabel,
         flag: types::ValueStateRetrievalFlag,
-    ) -> Result<types::ValueState, StorageError>;
+    ) -> impl Future<Output = Result<types::ValueState, StorageError>> + Send;
 
     /// Retrieve the user -> state version mapping in bulk. This is the same as get_user_states but with less data retrieved from the storage layer
+    fn get_user_state_versions(
+        &self,
+        usernames: &[AkdLabel],
+        flag: types::ValueStateRetrievalFlag,
+    ) -> impl Future<Output = Result<HashMap<AkdLabel, (u64, AkdValue)>, StorageError>> + Send;
+
+    /* Publish coordination */
+
+    /// Atomically acquire the advisory publish lease for `holder`, or renew it if it is
+    /// already held by `holder`, such that it expires `ttl_ms` after `now_ms`. Whenever the
+    /// lease changes hands, it is issued with a fencing token strictly greater than any
+    /// previously issued token. Returns [None] if an unexpired lease is held by another holder.
+    ///
+    /// Storage layers which are shared between multiple publishing instances should implement
+    /// this, the default implementation returns an error.
+    fn acquire_publish_lease(
+        &self,
+        _holder: &[u8],
+        _now_ms: u64,
+        _ttl_ms: u64,
+    ) -> impl Future<Output = Result<Option<types::PublishLease>, StorageError>> + Send {
+        async {
+            Err(StorageError::Other(
+                "Publish leases are not supported by this storage layer".to_string(),
+            ))
+        }
+    }
+
+    /// Release the advisory publish lease, if it is still held under the fencing
+    /// token of the provided lease
+    fn release_publish_lease(
+        &self,
+        _lease: &types::PublishLease,
+    ) -> impl Future<Output = Result<(), StorageError>> + Send {
+        async {
+            Err(StorageError::Other(
+                "Publish leases are not supported by this storage layer".to_string(),
+            ))
+        }
+    }
+
+    /* Rollback support */
+
+    /// Delete a batch of records by id from the database
+    ///
+    /// This is only required for [crate::Directory::with_retention_policy], the default
+    /// implementation returns an error.
+    fn batch_delete<St: Storable>(
+        &self,
+        _ids: &[St::StorageKey],
+    ) -> impl Future<Output = Result<(), StorageError>> + Send {
+        async {
+            Err(StorageError::Other(
+                "Deletion is not supported by this storage layer".to_string(),
+            ))
+        }
+    }
+
+    /// Set a batch of records and delete a batch of records, by their full binary ids (see
+    /// [Storable::get_full_binary_id]), in a single atomic write, as for the commit of a
+    /// transaction which deletes records (see [manager::StorageManager::batch_delete]). As with
+    /// [Database::batch_set], the write must be rejected if made under [DbSetState::Fenced]
+    /// after the publish lease was taken over.
+    ///
+    /// This is only required for [crate::Directory::rollback_last_epoch], the default implementation
+    /// returns an error.
+    fn batch_set_and_delete(
+        &self,
+        _records: Vec<DbRecord>,
+        _deletions: Vec<Vec<u8>>,
+        _state: DbSetState,
+    ) -> impl Future<Output = Result<(), StorageError>> + Send {
+        async {
+            Err(StorageError::Other(
+                "Transactional deletion is not supported by this storage layer".to_string(),
+            ))
+        }
+    }
+
+    /// Retrieve up to `limit` labels which have a value state at or before the given epoch,
+    /// in ascending order starting after the `cursor` label (or from the first label if [None]).
+    /// Each entry includes the current version and last update epoch of the label, as of the
+    /// given epoch.
+    ///
+    /// This is only required for [crate::Directory::list_labels], the default implementation
+    /// returns an error.
+    fn list_labels(
+        &self,
+        _cursor: Option<&AkdLabel>,
+        _limit: usize,
+        _epoch: u64,
+    ) -> impl Future<Output = Result<Vec<types::LabelEntry>, StorageError>> + Send {
+        async {
+            Err(StorageError::Other(
+                "Label enumeration is not supported by this storage layer".to_string(),
+            ))
+        }
+    }
+
+    /// Retrieve the number of labels which have a value state at or before the given epoch
+    ///
+    /// This is only required for [crate::Directory::stats], the default implementation
+    /// returns an error.
+    fn count_labels(&self, _epoch: u64) -> impl Future<Output = Result<u64, StorageError>> + Send {
+        async {
+            Err(StorageError::Other(
+                "Label enumeration is not supported by this storage layer".to_string(),
+            ))
+        }
+    }
+}
+
+/// Defines a database trait with boxed futures (see [macro@async_trait]), which is [Database] by
+/// default and [BoxedDatabase] with the `unboxed_storage` feature
+macro_rules! boxed_database_trait {
+    ($(#[$attr:meta])* $name:ident) => {
+        $(#[$attr])*
+        #[async_trait]
+        pub trait $name: Send + Sync {
+            /// Set a record in the database
+            async fn set(&self, record: DbRecord) -> Result<(), StorageError>;
+
+            /// Set multiple records in the database with a minimal set of operations
+            async fn batch_set(
+                &self,
+                records: Vec<DbRecord>,
+                state: DbSetState,
+            ) -> Result<(), StorageError>;
+
+            /// Retrieve a stored record from the database
+            async fn get<St: Storable>(
+                &self,
+                id: &St::StorageKey,
+            ) -> Result<DbRecord, StorageError>;
+
+            /// Retrieve a batch of records by id from the database
+            async fn batch_get<St: Storable>(
+                &self,
+                ids: &[St::StorageKey],
+            ) -> Result<Vec<DbRecord>, StorageError>;
+
+            /* User data searching */
+
+            /// Retrieve the user data for a given user
+            async fn get_user_data(&self, username: &AkdLabel) -> Result<types::KeyData, StorageError>;
+
+            /// Retrieve a specific state for a given user
+            async fn get_user_state(
+                &self,
+                username: &AkdLabel,
+                flag: types::ValueStateRetrievalFlag,
+            ) -> Result<types::ValueState, StorageError>;
+
+            /// Retrieve the user -> state version mapping in bulk. This is the same as get_user_states but with less data retrieved from the storage layer
+            async fn get_user_state_versions(
+                &self,
+                usernames: &[AkdLabel],
+                flag: types::ValueStateRetrievalFlag,
+            ) -> Result<HashMap<AkdLabel, (u64, AkdValue)>, StorageError>;
+
+            /* Publish coordination */
+
+            /// Atomically acquire the advisory publish lease for `holder`, or renew it if it is
+            /// already held by `holder`, such that it expires `ttl_ms` after `now_ms`. Whenever the
+            /// lease changes hands, it is issued with a fencing token strictly greater than any
+            /// previously issued token. Returns [None] if an unexpired lease is held by another holder.
+            ///
+            /// Storage layers which are shared between multiple publishing instances should implement
+            /// this, the default implementation returns an error.
+            async fn acquire_publish_lease(
+                &self,
+                _holder: &[u8],
+                _now_ms: u64,
+                _ttl_ms: u64,
+            ) -> Result<Option<types::PublishLease>, StorageError> {
+                Err(StorageError::Other(
+                    "Publish leases are not supported by this storage layer".to_string(),
+                ))
+            }
+
+            /// Release the advisory publish lease, if it is still held under the fencing
+            /// token of the provided lease
+            async fn release_publish_lease(
+                &self,
+                _lease: &types::PublishLease,
+            ) -> Result<(), StorageError> {
+                Err(StorageError::Other(
+                    "Publish leases are not supported by this storage layer".to_string(),
+                ))
+            }
+
+            /* Rollback support */
+
+            /// Delete a batch of records by id from the database
+            ///
+            /// This is only required for [crate::Directory::with_retention_policy], the default
+            /// implementation returns an error.
+            async fn batch_delete<St: Storable>(
+                &self,
+                _ids: &[St::StorageKey],
+            ) -> Result<(), StorageError> {
+                Err(StorageError::Other(
+                    "Deletion is not supported by this storage layer".to_string(),
+                ))
+            }
+
+            /// Set a batch of records and delete a batch of records, by their full binary ids (see
+            /// [Storable::get_full_binary_id]), in a single atomic write, as for the commit of a
+            /// transaction which deletes records (see [manager::StorageManager::batch_delete]). As
+            /// with [Database::batch_set], the write must be rejected if made under
+            /// [DbSetState::Fenced] after the publish lease was taken over.
+            ///
+            /// This is only required for [crate::Directory::rollback_last_epoch], the default implementation
+            /// returns an error.
+            async fn batch_set_and_delete(
+                &self,
+                _records: Vec<DbRecord>,
+                _deletions: Vec<Vec<u8>>,
+                _state: DbSetState,
+            ) -> Result<(), StorageError> {
+                Err(StorageError::Other(
+                    "Transactional deletion is not supported by this storage layer".to_string(),
+                ))
+            }
+
+            /// Retrieve up to `limit` labels which have a value state at or before the given epoch,
+            /// in ascending order starting after the `cursor` label (or from the first label if [None]).
+            /// Each entry includes the current version and last update epoch of the label, as of the
+            /// given epoch.
+            ///
+            /// This is only required for [crate::Directory::list_labels], the default implementation
+            /// returns an error.
+            async fn list_labels(
+                &self,
+                _cursor: Option<&AkdLabel>,
+                _limit: usize,
+                _epoch: u64,
+            ) -> Result<Vec<types::LabelEntry>, StorageError> {
+                Err(StorageError::Other(
+                    "Label enumeration is not supported by this storage layer".to_string(),
+                ))
+            }
+
+            /// Retrieve the number of labels which have a value state at or before the given epoch
+            ///
+            /// This is only required for [crate::Directory::stats], the default implementation
+            /// returns an error.
+            async fn count_labels(&self, _epoch: u64) -> Result<u64, StorageError> {
+                Err(StorageError::Other(
+                    "Label enumeration is not supported by this storage layer".to_string(),
+                ))
+            }
+        }
+    };
+}
+
+boxed_database_trait!(
+    /// A database implementation backing storage for the AKD
+    ///
+    /// The methods return boxed futures, such that it is implemented with [macro@async_trait].
+    /// With the `unboxed_storage` feature, the methods return unboxed futures instead, which
+    /// spares an allocation for each call to the storage layer.
+    #[cfg(not(feature = "unboxed_storage"))]
+    Database
+);
+
+/// Without the `unboxed_storage` feature, [Database] is itself implemented with
+/// [macro@async_trait], and [BoxedDatabase] is another name for it. Storage layers which
+/// implement [BoxedDatabase] support both configurations.
+#[cfg(not(feature = "unboxed_storage"))]
+pub use Database as BoxedDatabase;
+
+boxed_database_trait!(
+    /// A variant of [Database] whose methods return boxed futures, such that it can be
+    /// implemented with [macro@async_trait]. Every implementation of [BoxedDatabase] is a
+    /// [Database], at the cost of an allocation for each call to the storage layer, which
+    /// backends whose calls are dominated by network round trips (e.g. a remote SQL database)
+    /// can afford.
+    #[cfg(feature = "unboxed_storage")]
+    BoxedDatabase
+);
+
+#[cfg(feature = "unboxed_storage")]
+impl<T: BoxedDatabase> Database for T {
+    async fn set(&self, record: DbRecord) -> Result<(), StorageError> {
+        BoxedDatabase::set(self, record).await
+    }
+
+    async fn batch_set(
+        &self,
+        records: Vec<DbRecord>,
+        state: DbSetState,
+    ) -> Result<(), StorageError> {
+        BoxedDatabase::batch_set(self, records, state).await
+    }
+
+    async fn get<St: Storable>(&self, id: &St::StorageKey) -> Result<DbRecord, StorageError> {
+        BoxedDatabase::get::<St>(self, id).await
+    }
+
+    async fn batch_get<St: Storable>(
+        &self,
+        ids: &[St::StorageKey],
+    ) -> Result<Vec<DbRecord>, StorageError> {
+        BoxedDatabase::batch_get::<St>(self, ids).await
+    }
+
+    async fn get_user_data(&self, username: &AkdLabel) -> Result<types::KeyData, StorageError> {
+        BoxedDatabase::get_user_data(self, username).await
+    }
+
+    async fn get_user_state(
+        &self,
+        username: &AkdLabel,
+        flag: types::ValueStateRetrievalFlag,
+    ) -> Result<types::ValueState, StorageError> {
+        BoxedDatabase::get_user_state(self, username, flag).await
+    }
+
     async fn get_user_state_versions(
         &self,
         usernames: &[AkdLabel],
         flag: types::ValueStateRetrievalFlag,
-    ) -> Result<HashMap<AkdLabel, (u64, AkdValue)>, StorageError>;
+    ) -> Result<HashMap<AkdLabel, (u64, AkdValue)>, StorageError> {
+        BoxedDatabase::get_user_state_versions(self, usernames, flag).await
+    }
+
+    async fn acquire_publish_lease(
+        &self,
+        holder: &[u8],
+        now_ms: u64,
+        ttl_ms: u64,
+    ) -> Result<Option<types::PublishLease>, StorageError> {
+        BoxedDatabase::acquire_publish_lease(self, holder, now_ms, ttl_ms).await
+    }
+
+    async fn release_publish_lease(&self, lease: &types::PublishLease) -> Result<(), StorageError> {
+        BoxedDatabase::release_publish_lease(self, lease).await
+    }
+
+    async fn batch_delete<St: Storable>(&self, ids: &[St::StorageKey]) -> Result<(), StorageError> {
+        BoxedDatabase::batch_delete::<St>(self, ids).await
+    }
+
+    async fn batch_set_and_delete(
+        &self,
+        records: Vec<DbRecord>,
+        deletions: Vec<Vec<u8>>,
+        state: DbSetState,
+    ) -> Result<(), StorageError> {
+        BoxedDatabase::batch_set_and_delete(self, records, deletions, state).await
+    }
+
+    async fn list_labels(
+        &self,
+        cursor: Option<&AkdLabel>,
+        limit: usize,
+        epoch: u64,
+    ) -> Result<Vec<types::LabelEntry>, StorageError> {
+        BoxedDatabase::list_labels(self, cursor, limit, epoch).await
+    }
+
+    async fn count_labels(&self, epoch: u64) -> Result<u64, StorageError> {
+        BoxedDatabase::count_labels(self, epoch).await
+    }
 }
 
 /// Optional storage layer utility functions for debug and test purposes
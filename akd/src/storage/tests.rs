@@ -9,15 +9,15 @@
 
 use crate::errors::StorageError;
 use crate::storage::types::*;
-use crate::storage::Database;
 use crate::storage::StorageManager;
+use crate::storage::{Database, Storable};
 use crate::tree_node::*;
 use crate::utils::byte_arr_from_u64;
 use crate::NodeLabel;
-use crate::{AkdLabel, AkdValue};
+use crate::{AkdLabel, AkdValue, VersionFreshness};
 
 use akd_core::hash::EMPTY_DIGEST;
-use akd_core::AzksValue;
+use akd_core::{AzksValue, EpochMetadata};
 use rand::distributions::Alphanumeric;
 use rand::{thread_rng, Rng};
 use std::time::{Duration, Instant};
@@ -29,6 +29,7 @@ type PvTreeNode = crate::tree_node::TreeNodeWithPreviousValue;
 // *** Tests *** //
 
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod memory_storage_tests {
     use crate::storage::memory::AsyncInMemoryDatabase;
     use serial_test::serial;
@@ -49,6 +50,11 @@ pub async fn run_test_cases_for_storage_impl<S: Database>(db: S) -> StorageManag
     test_get_and_set_item(&db).await;
     test_user_data(&db).await;
     test_batch_get_items(&db).await;
+    test_epoch_metadata(&db).await;
+    test_publish_lease(&db).await;
+    test_rollback_support(&db).await;
+    test_directory_records(&db).await;
+    test_list_labels(&db).await;
 
     let manager = StorageManager::new_no_cache(db);
     test_transactions(&manager).await;
@@ -130,6 +136,7 @@ async fn test_get_and_set_item<Ns: Database>(storage: &Ns) {
         label: NodeLabel::new(byte_arr_from_u64(1), 1),
         version: 1,
         value: AkdValue::from("abc123"),
+        blinding: None,
     };
     let set_result = storage.set(DbRecord::ValueState(value.clone())).await;
     assert_eq!(Ok(()), set_result);
@@ -159,11 +166,10 @@ async fn test_batch_get_items<Ns: Database>(storage: &Ns) {
 
     let mut data = Vec::new();
 
-    let mut epoch = 1;
-    for value in rand_users.iter() {
+    for (epoch, value) in (1..).zip(rand_users.iter()) {
         for user in rand_users.iter() {
             data.push(DbRecord::ValueState(ValueState {
-                value: AkdValue(value.clone()),
+                value: AkdValue::Bytes(value.clone()),
                 version: epoch,
                 label: NodeLabel {
                     label_val: byte_arr_from_u64(1),
@@ -171,9 +177,9 @@ async fn test_batch_get_items<Ns: Database>(storage: &Ns) {
                 },
                 epoch,
                 username: AkdLabel(user.clone()),
+                blinding: None,
             }));
         }
-        epoch += 1;
     }
 
     let tic = Instant::now();
@@ -314,6 +320,395 @@ async fn test_batch_get_items<Ns: Database>(storage: &Ns) {
     }
 }
 
+async fn test_epoch_metadata<Ns: Database>(storage: &Ns) {
+    let metadata = DbRecord::build_epoch_metadata(
+        1,
+        1234,
+        2,
+        Some("note".to_string()),
+        true,
+        Some(vec![7u8; 64]),
+    );
+    let set_result = storage.set(DbRecord::EpochMetadata(metadata.clone())).await;
+    assert_eq!(Ok(()), set_result);
+
+    let get_result = storage.get::<EpochMetadata>(&1).await;
+    if let Ok(DbRecord::EpochMetadata(got_metadata)) = get_result {
+        assert_eq!(got_metadata, metadata);
+    } else {
+        panic!("Failed to retrieve epoch metadata");
+    }
+
+    let data = (2..=10)
+        .map(|epoch| {
+            DbRecord::EpochMetadata(DbRecord::build_epoch_metadata(
+                epoch,
+                1234 + epoch,
+                epoch,
+                None,
+                false,
+                None,
+            ))
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        Ok(()),
+        storage
+            .batch_set(data.clone(), crate::storage::DbSetState::General)
+            .await
+    );
+
+    let keys = (1..=10).collect::<Vec<u64>>();
+    match storage.batch_get::<EpochMetadata>(&keys).await {
+        Err(err) => panic!("Failed to retrieve batch of epoch metadata: {:?}", err),
+        Ok(lst) if lst.len() != keys.len() => {
+            panic!(
+                "Retrieved list length does not match input length {} != {}",
+                lst.len(),
+                keys.len()
+            );
+        }
+        Ok(results) => {
+            for result in results.into_iter() {
+                if let DbRecord::EpochMetadata(got_metadata) = &result {
+                    if got_metadata.epoch == 1 {
+                        assert_eq!(got_metadata, &metadata);
+                    } else {
+                        assert!(data.contains(&result));
+                    }
+                } else {
+                    panic!("Retrieved a record which is not epoch metadata");
+                }
+            }
+        }
+    }
+}
+
+async fn test_publish_lease<Ns: Database>(storage: &Ns) {
+    let first = storage
+        .acquire_publish_lease(b"first", 1000, 100)
+        .await
+        .unwrap()
+        .expect("Lease should be available");
+    assert_eq!(1100, first.expires_at_ms);
+
+    // The lease is exclusive until it expires, but can be renewed by its holder
+    assert_eq!(
+        Ok(None),
+        storage.acquire_publish_lease(b"second", 1050, 100).await
+    );
+    let renewed = storage
+        .acquire_publish_lease(b"first", 1050, 100)
+        .await
+        .unwrap()
+        .expect("Lease should be renewable by its holder");
+    assert_eq!(first.fencing_token, renewed.fencing_token);
+
+    // Once expired, another holder takes over with a greater fencing token
+    let second = storage
+        .acquire_publish_lease(b"second", 1200, 100)
+        .await
+        .unwrap()
+        .expect("Expired lease should be available");
+    assert!(second.fencing_token > first.fencing_token);
+
+    // Releasing a stale lease has no effect, while releasing the current lease frees it
+    assert_eq!(Ok(()), storage.release_publish_lease(&renewed).await);
+    assert_eq!(
+        Ok(None),
+        storage.acquire_publish_lease(b"first", 1250, 100).await
+    );
+    assert_eq!(Ok(()), storage.release_publish_lease(&second).await);
+    let third = storage
+        .acquire_publish_lease(b"first", 1250, 100)
+        .await
+        .unwrap()
+        .expect("Released lease should be available");
+    assert!(third.fencing_token > second.fencing_token);
+
+    // Fenced writes are rejected once the lease was issued with a greater fencing token
+    let metadata = |epoch| {
+        DbRecord::EpochMetadata(EpochMetadata {
+            epoch,
+            timestamp_ms: 1250,
+            update_count: 0,
+            note: None,
+            bound: false,
+            signature: None,
+        })
+    };
+    let stale_epoch = 2_000_000;
+    assert!(storage
+        .batch_set(
+            vec![metadata(stale_epoch)],
+            crate::storage::DbSetState::Fenced(second.fencing_token)
+        )
+        .await
+        .is_err());
+    assert!(matches!(
+        storage.get::<EpochMetadata>(&stale_epoch).await,
+        Err(StorageError::NotFound(_))
+    ));
+    let current_epoch = 2_000_001;
+    assert_eq!(
+        Ok(()),
+        storage
+            .batch_set(
+                vec![metadata(current_epoch)],
+                crate::storage::DbSetState::Fenced(third.fencing_token)
+            )
+            .await
+    );
+    assert!(storage.get::<EpochMetadata>(&current_epoch).await.is_ok());
+    assert_eq!(Ok(()), storage.release_publish_lease(&third).await);
+}
+
+async fn test_rollback_support<Ns: Database>(storage: &Ns) {
+    // Use epochs beyond those written by the other test cases
+    let base_epoch = 1_000_000;
+    let states = (0..3)
+        .map(|i| {
+            DbRecord::ValueState(DbRecord::build_user_state(
+                b"rollback_user".to_vec(),
+                vec![i as u8],
+                i + 1,
+                1u32,
+                [1u8; 32],
+                base_epoch + i,
+                None,
+            ))
+        })
+        .collect::<Vec<_>>();
+    let metadata = DbRecord::EpochMetadata(DbRecord::build_epoch_metadata(
+        base_epoch, 1234, 1, None, false, None,
+    ));
+    assert_eq!(
+        Ok(()),
+        storage
+            .batch_set(
+                [states, vec![metadata]].concat(),
+                crate::storage::DbSetState::General
+            )
+            .await
+    );
+
+    let deletions = (1..3)
+        .map(|i| {
+            ValueState::get_full_binary_key_id(&ValueStateKey(
+                b"rollback_user".to_vec(),
+                base_epoch + i,
+            ))
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        Ok(()),
+        storage
+            .batch_set_and_delete(vec![], deletions, crate::storage::DbSetState::General)
+            .await
+    );
+    let get_result = storage
+        .get_user_state(
+            &AkdLabel::from("rollback_user"),
+            ValueStateRetrievalFlag::MaxEpoch,
+        )
+        .await;
+    assert_eq!(Ok(base_epoch), get_result.map(|state| state.epoch));
+
+    assert_eq!(
+        Ok(()),
+        storage.batch_delete::<EpochMetadata>(&[base_epoch]).await
+    );
+    assert!(matches!(
+        storage.get::<EpochMetadata>(&base_epoch).await,
+        Err(StorageError::NotFound(_))
+    ));
+
+    let record = RollbackRecord {
+        index: 0,
+        timestamp_ms: 1234,
+        from_epoch: base_epoch + 2,
+        to_epoch: base_epoch,
+        restored_nodes: 3,
+        removed_nodes: 4,
+        removed_value_states: 2,
+    };
+    assert_eq!(
+        Ok(()),
+        storage.set(DbRecord::RollbackRecord(record.clone())).await
+    );
+    assert_eq!(
+        Ok(DbRecord::RollbackRecord(record)),
+        storage.get::<RollbackRecord>(&0).await
+    );
+}
+
+async fn test_directory_records<Ns: Database>(storage: &Ns) {
+    let journal = UpdateJournal {
+        epoch: 1,
+        entries: vec![
+            JournalEntry {
+                label: JournalLabel::Label(AkdLabel::from("journal_user")),
+                version: 2,
+            },
+            JournalEntry {
+                label: JournalLabel::Hashed([3u8; 32]),
+                version: 1,
+            },
+        ],
+    };
+    let roots = (1..=3)
+        .map(|epoch| {
+            DbRecord::EpochRootRecord(EpochRootRecord {
+                epoch,
+                root_hash: [epoch as u8; 32],
+                chain_hash: [epoch as u8 + 1; 32],
+            })
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        Ok(()),
+        storage
+            .batch_set(
+                [
+                    vec![DbRecord::UpdateJournal(journal.clone())],
+                    roots.clone()
+                ]
+                .concat(),
+                crate::storage::DbSetState::General
+            )
+            .await
+    );
+
+    assert_eq!(
+        Ok(DbRecord::UpdateJournal(journal)),
+        storage.get::<UpdateJournal>(&1).await
+    );
+    assert!(matches!(
+        storage.get::<UpdateJournal>(&2).await,
+        Err(StorageError::NotFound(_))
+    ));
+    let mut got_roots = storage
+        .batch_get::<EpochRootRecord>(&[1, 2, 3])
+        .await
+        .unwrap();
+    got_roots.sort();
+    assert_eq!(roots, got_roots);
+
+    assert_eq!(
+        Ok(()),
+        storage.batch_delete::<EpochRootRecord>(&[2, 3]).await
+    );
+    assert_eq!(
+        Ok(vec![roots[0].clone()]),
+        storage.batch_get::<EpochRootRecord>(&[1, 2, 3]).await
+    );
+
+    let label = AkdLabel::from("vrf_proof_user");
+    let fresh = PrecomputedVrfProof {
+        label: label.clone(),
+        freshness: VersionFreshness::Fresh,
+        version: 2,
+        proof: vec![1u8; 80],
+        commitment_nonce: Some(vec![2u8; 32]),
+    };
+    let stale = PrecomputedVrfProof {
+        freshness: VersionFreshness::Stale,
+        commitment_nonce: None,
+        ..fresh.clone()
+    };
+    assert_eq!(
+        Ok(()),
+        storage
+            .batch_set(
+                vec![
+                    DbRecord::PrecomputedVrfProof(fresh.clone()),
+                    DbRecord::PrecomputedVrfProof(stale.clone()),
+                ],
+                crate::storage::DbSetState::General
+            )
+            .await
+    );
+    assert_eq!(
+        Ok(DbRecord::PrecomputedVrfProof(fresh)),
+        storage
+            .get::<PrecomputedVrfProof>(&VrfProofKey(label.clone(), VersionFreshness::Fresh, 2))
+            .await
+    );
+    assert_eq!(
+        Ok(DbRecord::PrecomputedVrfProof(stale)),
+        storage
+            .get::<PrecomputedVrfProof>(&VrfProofKey(label.clone(), VersionFreshness::Stale, 2))
+            .await
+    );
+    assert!(matches!(
+        storage
+            .get::<PrecomputedVrfProof>(&VrfProofKey(label, VersionFreshness::Fresh, 3))
+            .await,
+        Err(StorageError::NotFound(_))
+    ));
+}
+
+async fn test_list_labels<Ns: Database>(storage: &Ns) {
+    // Use labels which are ordered after those written by the other test cases
+    let cursor = AkdLabel(vec![0xff, 0xff]);
+    let labels = (1u8..=3)
+        .map(|i| AkdLabel(vec![0xff, 0xff, i]))
+        .collect::<Vec<_>>();
+    let states = labels
+        .iter()
+        .enumerate()
+        .flat_map(|(i, label)| {
+            (1..=i as u64 + 1).map(move |version| {
+                DbRecord::ValueState(DbRecord::build_user_state(
+                    label.to_vec(),
+                    vec![version as u8],
+                    version,
+                    1u32,
+                    [1u8; 32],
+                    10 * version,
+                    None,
+                ))
+            })
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        Ok(()),
+        storage
+            .batch_set(states, crate::storage::DbSetState::General)
+            .await
+    );
+
+    let entries = storage.list_labels(Some(&cursor), 10, 100).await.unwrap();
+    assert_eq!(
+        vec![(1, 10), (2, 20), (3, 30)],
+        entries
+            .iter()
+            .map(|entry| (
+                entry.current_version.unwrap(),
+                entry.last_update_epoch.unwrap()
+            ))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        labels,
+        entries
+            .into_iter()
+            .map(|entry| entry.label)
+            .collect::<Vec<_>>()
+    );
+
+    // States after the requested epoch are ignored, and pages start after the cursor
+    let entries = storage.list_labels(Some(&labels[0]), 1, 15).await.unwrap();
+    assert_eq!(
+        vec![LabelEntry {
+            label: labels[1].clone(),
+            current_version: Some(1),
+            last_update_epoch: Some(10),
+        }],
+        entries
+    );
+}
+
 async fn test_transactions<S: Database>(storage: &StorageManager<S>) {
     let mut rand_users: Vec<Vec<u8>> = vec![];
     for _ in 0..20 {
@@ -327,11 +722,10 @@ async fn test_transactions<S: Database>(storage: &StorageManager<S>) {
 
     let mut data = Vec::new();
 
-    let mut epoch = 1;
-    for value in rand_users.iter() {
+    for (epoch, value) in (1..).zip(rand_users.iter()) {
         for user in rand_users.iter() {
             data.push(DbRecord::ValueState(ValueState {
-                value: AkdValue(value.clone()),
+                value: AkdValue::Bytes(value.clone()),
                 version: 1u64,
                 label: NodeLabel {
                     label_val: byte_arr_from_u64(1),
@@ -339,9 +733,9 @@ async fn test_transactions<S: Database>(storage: &StorageManager<S>) {
                 },
                 epoch,
                 username: AkdLabel(user.clone()),
+                blinding: None,
             }));
         }
-        epoch += 1;
     }
 
     data.push(DbRecord::Azks(Azks {
@@ -410,7 +804,7 @@ async fn test_user_data<S: Database>(storage: &S) {
         .as_bytes()
         .to_vec();
     let mut sample_state = ValueState {
-        value: AkdValue(rand_value.clone()),
+        value: AkdValue::Bytes(rand_value.clone()),
         version: 1u64,
         label: NodeLabel {
             label_val: byte_arr_from_u64(1),
@@ -418,6 +812,7 @@ async fn test_user_data<S: Database>(storage: &S) {
         },
         epoch: 1u64,
         username: AkdLabel(rand_user),
+        blinding: None,
     };
     let mut sample_state_2 = sample_state.clone();
     sample_state_2.username = AkdLabel::from("test_user");
@@ -489,8 +884,9 @@ async fn test_user_data<S: Database>(storage: &S) {
             epoch: 123,
             version: 2,
             label: NodeLabel::new(byte_arr_from_u64(1), 1),
-            value: AkdValue(rand_value.clone()),
+            value: AkdValue::Bytes(rand_value.clone()),
             username: sample_state.username.clone(),
+            blinding: None,
         }),
         specific_result
     );
@@ -504,8 +900,9 @@ async fn test_user_data<S: Database>(storage: &S) {
                 epoch: 123,
                 version: 2,
                 label: NodeLabel::new(byte_arr_from_u64(1), 1),
-                value: AkdValue(rand_value.clone()),
+                value: AkdValue::Bytes(rand_value.clone()),
                 username: sample_state.username.clone(),
+                blinding: None,
             },
             state
         );
@@ -532,8 +929,9 @@ async fn test_user_data<S: Database>(storage: &S) {
             epoch: 123,
             version: 2,
             label: NodeLabel::new(byte_arr_from_u64(1), 1),
-            value: AkdValue(rand_value.clone()),
+            value: AkdValue::Bytes(rand_value.clone()),
             username: sample_state.username.clone(),
+            blinding: None,
         }),
         specific_result
     );
@@ -546,8 +944,9 @@ async fn test_user_data<S: Database>(storage: &S) {
             epoch: 1,
             version: 1,
             label: NodeLabel::new(byte_arr_from_u64(1), 1),
-            value: AkdValue(rand_value.clone()),
+            value: AkdValue::Bytes(rand_value.clone()),
             username: sample_state.username.clone(),
+            blinding: None,
         }),
         specific_result
     );
@@ -560,8 +959,9 @@ async fn test_user_data<S: Database>(storage: &S) {
             epoch: 456,
             version: 3,
             label: NodeLabel::new(byte_arr_from_u64(1), 1),
-            value: AkdValue(rand_value.clone()),
+            value: AkdValue::Bytes(rand_value.clone()),
             username: sample_state.username.clone(),
+            blinding: None,
         }),
         specific_result
     );
@@ -606,7 +1006,7 @@ async fn test_tombstoning_data<S: Database>(
     let rand_value = rand_user.clone();
 
     let mut sample_state = ValueState {
-        value: AkdValue(rand_value.clone()),
+        value: AkdValue::Bytes(rand_value.clone()),
         version: 1u64,
         label: NodeLabel {
             label_val: byte_arr_from_u64(1),
@@ -614,6 +1014,7 @@ async fn test_tombstoning_data<S: Database>(
         },
         epoch: 1u64,
         username: AkdLabel(rand_user.clone()),
+        blinding: None,
     };
     let mut sample_state2 = sample_state.clone();
     sample_state2.username = AkdLabel::from("tombstone_test_user");
@@ -664,10 +1065,10 @@ async fn test_tombstoning_data<S: Database>(
         .for_each(|value_state| {
             if value_state.epoch <= 1 {
                 // should be a tombstone
-                assert_eq!(crate::TOMBSTONE.to_vec(), value_state.value.0);
+                assert_eq!(crate::AkdValue::Tombstone, value_state.value);
             } else {
                 // should NOT be a tombstone
-                assert_ne!(crate::TOMBSTONE.to_vec(), value_state.value.0);
+                assert_ne!(crate::AkdValue::Tombstone, value_state.value);
             }
         });
 
@@ -679,10 +1080,10 @@ async fn test_tombstoning_data<S: Database>(
         .for_each(|value_state| {
             if value_state.epoch <= 2 {
                 // should be a tombstone
-                assert_eq!(crate::TOMBSTONE.to_vec(), value_state.value.0);
+                assert_eq!(crate::AkdValue::Tombstone, value_state.value);
             } else {
                 // should NOT be a tombstone
-                assert_ne!(crate::TOMBSTONE.to_vec(), value_state.value.0);
+                assert_ne!(crate::AkdValue::Tombstone, value_state.value);
             }
         });
 
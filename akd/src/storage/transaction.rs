@@ -13,13 +13,14 @@ use crate::storage::types::ValueState;
 use crate::storage::types::ValueStateRetrievalFlag;
 use crate::storage::Storable;
 
-use dashmap::DashMap;
+use akd_core::SizeOf;
+use dashmap::{DashMap, DashSet};
 #[cfg(feature = "runtime_metrics")]
 use log::{debug, error, info, trace, warn};
 use std::collections::HashMap;
 #[cfg(feature = "runtime_metrics")]
 use std::sync::atomic::AtomicU64;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 
 /// Represents an in-memory transaction, keeping a mutable state
@@ -29,6 +30,10 @@ use std::sync::Arc;
 #[derive(Clone)]
 pub struct Transaction {
     mods: Arc<DashMap<Vec<u8>, DbRecord>>,
+    /// The full binary ids of the records deleted in the transaction
+    deletions: Arc<DashSet<Vec<u8>>>,
+    /// The approximate size (in bytes) of the records in `mods`
+    bytes: Arc<AtomicUsize>,
     active: Arc<AtomicBool>,
 
     #[cfg(feature = "runtime_metrics")]
@@ -57,6 +62,8 @@ impl Transaction {
     pub fn new() -> Self {
         Self {
             mods: Arc::new(DashMap::new()),
+            deletions: Arc::new(DashSet::new()),
+            bytes: Arc::new(AtomicUsize::new(0)),
             active: Arc::new(AtomicBool::new(false)),
 
             #[cfg(feature = "runtime_metrics")]
@@ -71,6 +78,12 @@ impl Transaction {
         self.mods.len()
     }
 
+    /// Get the approximate size (in bytes) of the items currently in the transaction
+    /// modifications set
+    pub fn memory_usage(&self) -> usize {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
     /// Log metrics about the current transaction instance. Metrics will be cleared after log call
     pub fn log_metrics(&self, _level: log::Level) {
         #[cfg(feature = "runtime_metrics")]
@@ -95,8 +108,9 @@ impl Transaction {
         !self.active.swap(true, Ordering::Relaxed)
     }
 
-    /// Commit a transaction in the storage layer
-    pub fn commit_transaction(&self) -> Result<Vec<DbRecord>, StorageError> {
+    /// Commit a transaction in the storage layer, returning the records to write and the full
+    /// binary ids of the records to delete
+    pub fn commit_transaction(&self) -> Result<(Vec<DbRecord>, Vec<Vec<u8>>), StorageError> {
         if !self.active.load(Ordering::Relaxed) {
             return Err(StorageError::Transaction(
                 "Transaction not currently active".to_string(),
@@ -112,12 +126,15 @@ impl Transaction {
 
         // sort according to transaction priority
         records.sort_by_key(|r| r.transaction_priority());
+        let deletions = self.deletions.iter().map(|id| id.clone()).collect();
 
         // flush the trans log
         self.mods.clear();
+        self.deletions.clear();
+        self.bytes.store(0, Ordering::Relaxed);
 
         self.active.store(false, Ordering::Relaxed);
-        Ok(records)
+        Ok((records, deletions))
     }
 
     /// Rollback a transaction
@@ -130,6 +147,8 @@ impl Transaction {
 
         // rollback
         self.mods.clear();
+        self.deletions.clear();
+        self.bytes.store(0, Ordering::Relaxed);
 
         self.active.store(false, Ordering::Relaxed);
         Ok(())
@@ -152,11 +171,32 @@ impl Transaction {
         out
     }
 
+    /// Determine whether a record was deleted in the transaction
+    pub fn is_deleted<St: Storable>(&self, key: &St::StorageKey) -> bool {
+        self.deletions.contains(&St::get_full_binary_key_id(key))
+    }
+
+    /// Delete a batch of records in the transaction, which are deleted from storage at
+    /// transaction commit time
+    pub fn batch_delete<St: Storable>(&self, keys: &[St::StorageKey]) {
+        for key in keys {
+            let bin_id = St::get_full_binary_key_id(key);
+            if let Some((_, removed)) = self.mods.remove(&bin_id) {
+                self.bytes.fetch_sub(removed.size_of(), Ordering::Relaxed);
+            }
+            self.deletions.insert(bin_id);
+        }
+
+        #[cfg(feature = "runtime_metrics")]
+        {
+            self.num_writes.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
     /// Set a batch of values into the cache
     pub fn batch_set(&self, records: &[DbRecord]) {
         for record in records {
-            self.mods
-                .insert(record.get_full_binary_id(), record.clone());
+            self.insert(record);
         }
 
         #[cfg(feature = "runtime_metrics")]
@@ -167,9 +207,7 @@ impl Transaction {
 
     /// Set a value in the transaction to be committed at transaction commit time
     pub fn set(&self, record: &DbRecord) {
-        let bin_id = record.get_full_binary_id();
-
-        self.mods.insert(bin_id, record.clone());
+        self.insert(record);
 
         #[cfg(feature = "runtime_metrics")]
         {
@@ -177,6 +215,42 @@ impl Transaction {
         }
     }
 
+    fn insert(&self, record: &DbRecord) {
+        // The size of the new record is accounted for before the one of the record it replaces
+        // is released, so that concurrent writes of the same record never underflow the total
+        self.bytes.fetch_add(record.size_of(), Ordering::Relaxed);
+        self.deletions.remove(&record.get_full_binary_id());
+        if let Some(replaced) = self
+            .mods
+            .insert(record.get_full_binary_id(), record.clone())
+        {
+            self.bytes.fetch_sub(replaced.size_of(), Ordering::Relaxed);
+        }
+    }
+
+    /// Retrieve the tree nodes currently in the transaction modifications set, so that they can
+    /// be written to storage ahead of the commit
+    pub(crate) fn tree_nodes(&self) -> Vec<DbRecord> {
+        self.mods
+            .iter()
+            .filter(|pair| matches!(pair.value(), DbRecord::TreeNode(_)))
+            .map(|pair| pair.value().clone())
+            .collect()
+    }
+
+    /// Remove the given records from the transaction modifications set, once they have been
+    /// written to storage. A record which was modified again in the meantime is retained.
+    pub(crate) fn remove_spilled(&self, records: &[DbRecord]) {
+        for record in records {
+            if let Some((_, removed)) = self
+                .mods
+                .remove_if(&record.get_full_binary_id(), |_, current| current == record)
+            {
+                self.bytes.fetch_sub(removed.size_of(), Ordering::Relaxed);
+            }
+        }
+    }
+
     /// Retrieve all of the user data for a given username
     ///
     /// Note: This is a FULL SCAN operation of the entire transaction log
@@ -209,7 +283,7 @@ impl Transaction {
 
         // sort all the value lists by epoch
         for (_k, v) in results.iter_mut() {
-            v.sort_unstable_by(|a, b| a.epoch.cmp(&b.epoch));
+            v.sort_unstable_by_key(|a| a.epoch);
         }
 
         results
@@ -225,7 +299,7 @@ impl Transaction {
         flag: ValueStateRetrievalFlag,
     ) -> Option<ValueState> {
         let intermediate = self
-            .get_users_data(&[username.clone()])
+            .get_users_data(std::slice::from_ref(username))
             .remove(username)
             .unwrap_or_default();
         let out = Self::find_appropriate_item(intermediate, flag);
@@ -325,6 +399,7 @@ mod tests {
             label: NodeLabel::new(byte_arr_from_u64(1), 1),
             version: 1,
             value: AkdValue::from("abc123"),
+            blinding: None,
         });
         let value2 = DbRecord::ValueState(ValueState {
             username: AkdLabel::from("test"),
@@ -332,6 +407,7 @@ mod tests {
             label: NodeLabel::new(byte_arr_from_u64(1), 1),
             version: 2,
             value: AkdValue::from("abc1234"),
+            blinding: None,
         });
 
         let records = vec![azks, node1, node2, value1, value2];
@@ -350,7 +426,7 @@ mod tests {
 
             // ensure that committed records are in ascending priority
             let mut running_priority = 0;
-            for record in txn.commit_transaction()? {
+            for record in txn.commit_transaction()?.0 {
                 let priority = record.transaction_priority();
                 #[allow(clippy::comparison_chain)]
                 if priority > running_priority {
@@ -7,13 +7,15 @@
 
 //! Various storage and representation related types
 
-use akd_core::AzksValue;
+use akd_core::{AzksValue, EpochMetadata};
 
 use crate::storage::Storable;
 use crate::tree_node::{TreeNode, TreeNodeType, TreeNodeWithPreviousValue};
-use crate::{AkdLabel, AkdValue};
+use crate::{AkdLabel, AkdValue, Digest, LookupProof, VersionFreshness};
 use crate::{Azks, NodeLabel};
+use std::cmp::Ordering;
 use std::convert::TryInto;
+use std::hash::{Hash, Hasher};
 
 /// Various elements that can be stored
 #[derive(PartialEq, Eq, Debug, Hash, Clone, Copy)]
@@ -26,6 +28,18 @@ pub enum StorageType {
     /// Better to keep ValueState = 4 as is?
     /// ValueState
     ValueState = 4,
+    /// EpochMetadata
+    EpochMetadata = 5,
+    /// RollbackRecord
+    RollbackRecord = 6,
+    /// PrecomputedLookupProof
+    PrecomputedLookupProof = 7,
+    /// UpdateJournal
+    UpdateJournal = 8,
+    /// EpochRootRecord
+    EpochRootRecord = 9,
+    /// PrecomputedVrfProof
+    PrecomputedVrfProof = 10,
 }
 
 /// State for a value at a given version for that key
@@ -54,6 +68,10 @@ pub struct ValueState {
     pub epoch: u64,
     /// The username associated to this value state (username + epoch is the record key)
     pub username: AkdLabel,
+    /// The blinding supplied by the client for the commitment to the value, if any (see
+    /// [akd_core::Configuration::get_blinded_commitment_nonce])
+    #[cfg_attr(feature = "serde_serialization", serde(default))]
+    pub blinding: Option<Vec<u8>>,
 }
 
 impl akd_core::SizeOf for ValueState {
@@ -63,6 +81,7 @@ impl akd_core::SizeOf for ValueState {
             + self.label.size_of()
             + std::mem::size_of::<u64>()
             + self.username.size_of()
+            + self.blinding.as_ref().map(|b| b.len()).unwrap_or(0)
     }
 }
 
@@ -101,12 +120,16 @@ impl crate::storage::Storable for ValueState {
 }
 
 impl ValueState {
+    /// The maximum length, in bytes, of a blinding supplied by a client
+    pub const MAX_BLINDING_LENGTH: usize = 64;
+
     pub(crate) fn new(
         username: AkdLabel,
         plaintext_val: AkdValue,
         version: u64,
         label: NodeLabel,
         epoch: u64,
+        blinding: Option<Vec<u8>>,
     ) -> Self {
         ValueState {
             value: plaintext_val,
@@ -114,10 +137,450 @@ impl ValueState {
             label,
             epoch,
             username,
+            blinding,
+        }
+    }
+}
+
+impl crate::storage::Storable for EpochMetadata {
+    type StorageKey = u64;
+
+    fn data_type() -> StorageType {
+        StorageType::EpochMetadata
+    }
+
+    fn get_id(&self) -> u64 {
+        self.epoch
+    }
+
+    fn get_full_binary_key_id(key: &u64) -> Vec<u8> {
+        let mut result = vec![StorageType::EpochMetadata as u8];
+        result.extend_from_slice(&key.to_be_bytes());
+        result
+    }
+
+    fn key_from_full_binary(bin: &[u8]) -> Result<u64, String> {
+        if bin.len() != 9 {
+            return Err("Not enough bytes to form a proper key".to_string());
+        }
+
+        if bin[0] != StorageType::EpochMetadata as u8 {
+            return Err("Not an epoch metadata key".to_string());
+        }
+
+        let epoch_bytes: [u8; 8] = bin[1..=8].try_into().expect("Slice with incorrect length");
+        Ok(u64::from_be_bytes(epoch_bytes))
+    }
+}
+
+/// An advisory lease granting its holder exclusive permission to publish, which is
+/// used to coordinate multiple [crate::Directory] instances over the same storage
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PublishLease {
+    /// An identifier of the instance holding the lease
+    pub holder: Vec<u8>,
+    /// Monotonically increasing token, issued each time the lease changes hands
+    pub fencing_token: u64,
+    /// The time at which the lease expires, in milliseconds since the UNIX epoch
+    pub expires_at_ms: u64,
+}
+
+/// An audit trail record of a [crate::Directory::rollback_last_epoch] operation
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Deserialize, serde::Serialize)
+)]
+pub struct RollbackRecord {
+    /// The position of the record in the audit trail, which is its storage key
+    pub index: u64,
+    /// The time of the rollback, in milliseconds since the UNIX epoch
+    pub timestamp_ms: u64,
+    /// The latest epoch prior to the rollback
+    pub from_epoch: u64,
+    /// The epoch which the directory was rolled back to
+    pub to_epoch: u64,
+    /// The number of tree nodes which were restored to a previous state
+    pub restored_nodes: u64,
+    /// The number of tree nodes which were removed
+    pub removed_nodes: u64,
+    /// The number of value states which were removed
+    pub removed_value_states: u64,
+}
+
+impl akd_core::SizeOf for RollbackRecord {
+    fn size_of(&self) -> usize {
+        std::mem::size_of::<u64>() * 7
+    }
+}
+
+impl crate::storage::Storable for RollbackRecord {
+    type StorageKey = u64;
+
+    fn data_type() -> StorageType {
+        StorageType::RollbackRecord
+    }
+
+    fn get_id(&self) -> u64 {
+        self.index
+    }
+
+    fn get_full_binary_key_id(key: &u64) -> Vec<u8> {
+        u64_full_binary_key_id(StorageType::RollbackRecord, *key)
+    }
+
+    fn key_from_full_binary(bin: &[u8]) -> Result<u64, String> {
+        u64_key_from_full_binary(StorageType::RollbackRecord, bin)
+    }
+}
+
+/// A lookup proof generated ahead of time by [crate::Directory::precompute_lookup_proofs],
+/// which is stored by label (only the latest precomputed proof of each label is kept)
+///
+/// Precomputed proofs are ordered and hashed by their label, epoch and root hash only, as
+/// the proof itself is determined by them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Deserialize, serde::Serialize)
+)]
+pub struct PrecomputedLookupProof {
+    /// The label
+    pub label: AkdLabel,
+    /// The epoch of the proof
+    pub epoch: u64,
+    /// The root hash of the epoch of the proof
+    pub root_hash: Digest,
+    /// The lookup proof of the label
+    pub proof: LookupProof,
+}
+
+impl Hash for PrecomputedLookupProof {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.label.hash(state);
+        self.epoch.hash(state);
+        self.root_hash.hash(state);
+    }
+}
+
+impl PartialOrd for PrecomputedLookupProof {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PrecomputedLookupProof {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (&self.label, self.epoch, &self.root_hash).cmp(&(
+            &other.label,
+            other.epoch,
+            &other.root_hash,
+        ))
+    }
+}
+
+impl akd_core::SizeOf for PrecomputedLookupProof {
+    fn size_of(&self) -> usize {
+        self.label.size_of()
+            + std::mem::size_of::<u64>()
+            + self.root_hash.len()
+            + self.proof.size_of()
+    }
+}
+
+impl crate::storage::Storable for PrecomputedLookupProof {
+    type StorageKey = AkdLabel;
+
+    fn data_type() -> StorageType {
+        StorageType::PrecomputedLookupProof
+    }
+
+    fn get_id(&self) -> AkdLabel {
+        self.label.clone()
+    }
+
+    fn get_full_binary_key_id(key: &AkdLabel) -> Vec<u8> {
+        let mut result = vec![StorageType::PrecomputedLookupProof as u8];
+        result.extend_from_slice(key);
+        result
+    }
+
+    fn key_from_full_binary(bin: &[u8]) -> Result<AkdLabel, String> {
+        if bin.is_empty() || bin[0] != StorageType::PrecomputedLookupProof as u8 {
+            return Err("Not a precomputed lookup proof key".to_string());
+        }
+        Ok(AkdLabel(bin[1..].to_vec()))
+    }
+}
+
+/// The key of a [PrecomputedVrfProof], which is the label, freshness and version of the
+/// VRF evaluation
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Deserialize, serde::Serialize)
+)]
+pub struct VrfProofKey(pub AkdLabel, pub VersionFreshness, pub u64);
+
+/// The VRF proof of a version of a label, which is precomputed by a directory configured with
+/// [crate::Directory::with_precomputed_vrf_proofs] so that the replicas which only hold the VRF
+/// public key (see [crate::Directory::new_read_only]) can serve lookup and key history proofs
+///
+/// Precomputed VRF proofs are ordered and hashed by their key only, as the proof is determined
+/// by it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Deserialize, serde::Serialize)
+)]
+pub struct PrecomputedVrfProof {
+    /// The label
+    pub label: AkdLabel,
+    /// The freshness of the evaluation
+    pub freshness: VersionFreshness,
+    /// The version of the evaluation
+    pub version: u64,
+    /// The encoded VRF proof
+    pub proof: Vec<u8>,
+    /// The opening of the commitment to the value of the version, for the fresh evaluation of
+    /// a published version
+    pub commitment_nonce: Option<Vec<u8>>,
+}
+
+impl Hash for PrecomputedVrfProof {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.label.hash(state);
+        self.freshness.hash(state);
+        self.version.hash(state);
+    }
+}
+
+impl PartialOrd for PrecomputedVrfProof {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PrecomputedVrfProof {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (&self.label, self.freshness as u8, self.version).cmp(&(
+            &other.label,
+            other.freshness as u8,
+            other.version,
+        ))
+    }
+}
+
+impl akd_core::SizeOf for PrecomputedVrfProof {
+    fn size_of(&self) -> usize {
+        self.label.size_of()
+            + std::mem::size_of::<VersionFreshness>()
+            + std::mem::size_of::<u64>()
+            + self.proof.len()
+            + self.commitment_nonce.as_ref().map(|n| n.len()).unwrap_or(0)
+    }
+}
+
+impl crate::storage::Storable for PrecomputedVrfProof {
+    type StorageKey = VrfProofKey;
+
+    fn data_type() -> StorageType {
+        StorageType::PrecomputedVrfProof
+    }
+
+    fn get_id(&self) -> VrfProofKey {
+        VrfProofKey(self.label.clone(), self.freshness, self.version)
+    }
+
+    fn get_full_binary_key_id(key: &VrfProofKey) -> Vec<u8> {
+        let mut result = vec![StorageType::PrecomputedVrfProof as u8, key.1 as u8];
+        result.extend_from_slice(&key.2.to_be_bytes());
+        result.extend_from_slice(&key.0);
+        result
+    }
+
+    fn key_from_full_binary(bin: &[u8]) -> Result<VrfProofKey, String> {
+        if bin.len() < 10 {
+            return Err("Not enough bytes to form a proper key".to_string());
         }
+        if bin[0] != StorageType::PrecomputedVrfProof as u8 {
+            return Err("Not a precomputed VRF proof key".to_string());
+        }
+        let freshness = match bin[1] {
+            0 => VersionFreshness::Stale,
+            1 => VersionFreshness::Fresh,
+            other => return Err(format!("Invalid version freshness {other}")),
+        };
+        let version_bytes: [u8; 8] = bin[2..=9].try_into().expect("Slice with incorrect length");
+        Ok(VrfProofKey(
+            AkdLabel(bin[10..].to_vec()),
+            freshness,
+            u64::from_be_bytes(version_bytes),
+        ))
+    }
+}
+
+/// How the labels of the updates are recorded in an [UpdateJournal], see
+/// [crate::Directory::with_update_journal]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UpdateJournalMode {
+    /// The labels are recorded as they were published
+    Labels,
+    /// Only a hash of each label, keyed with the commitment key of the directory, is recorded.
+    /// The entries of a label can be linked to each other across epochs, but the label cannot
+    /// be recovered (nor guessed) without the commitment key.
+    HashedLabels,
+}
+
+/// The label of an update recorded in an [UpdateJournal]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Deserialize, serde::Serialize)
+)]
+pub enum JournalLabel {
+    /// The published label, with [UpdateJournalMode::Labels]
+    Label(AkdLabel),
+    /// The keyed hash of the published label, with [UpdateJournalMode::HashedLabels]
+    Hashed(Digest),
+}
+
+/// An update recorded in an [UpdateJournal]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Deserialize, serde::Serialize)
+)]
+pub struct JournalEntry {
+    /// The label which was updated
+    pub label: JournalLabel,
+    /// The version of the label which was published
+    pub version: u64,
+}
+
+/// The journal of the updates published in an epoch, which is recorded by
+/// [crate::Directory::with_update_journal]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Deserialize, serde::Serialize)
+)]
+pub struct UpdateJournal {
+    /// The epoch in which the updates were published
+    pub epoch: u64,
+    /// The updates, ordered by label
+    pub entries: Vec<JournalEntry>,
+}
+
+impl akd_core::SizeOf for UpdateJournal {
+    fn size_of(&self) -> usize {
+        std::mem::size_of::<u64>()
+            + self
+                .entries
+                .iter()
+                .map(|entry| {
+                    let label_size = match &entry.label {
+                        JournalLabel::Label(label) => label.size_of(),
+                        JournalLabel::Hashed(hash) => hash.len(),
+                    };
+                    label_size + std::mem::size_of::<u64>()
+                })
+                .sum::<usize>()
     }
 }
 
+impl crate::storage::Storable for UpdateJournal {
+    type StorageKey = u64;
+
+    fn data_type() -> StorageType {
+        StorageType::UpdateJournal
+    }
+
+    fn get_id(&self) -> u64 {
+        self.epoch
+    }
+
+    fn get_full_binary_key_id(key: &u64) -> Vec<u8> {
+        u64_full_binary_key_id(StorageType::UpdateJournal, *key)
+    }
+
+    fn key_from_full_binary(bin: &[u8]) -> Result<u64, String> {
+        u64_key_from_full_binary(StorageType::UpdateJournal, bin)
+    }
+}
+
+/// The root hash of a published epoch and its chain hash (see [akd_core::EpochRootChain]),
+/// which is recorded by [crate::Directory::with_root_history]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Deserialize, serde::Serialize)
+)]
+pub struct EpochRootRecord {
+    /// The published epoch
+    pub epoch: u64,
+    /// The root hash of the epoch
+    pub root_hash: Digest,
+    /// The chain hash of the epoch
+    pub chain_hash: Digest,
+}
+
+impl akd_core::SizeOf for EpochRootRecord {
+    fn size_of(&self) -> usize {
+        std::mem::size_of::<u64>() + self.root_hash.len() + self.chain_hash.len()
+    }
+}
+
+impl crate::storage::Storable for EpochRootRecord {
+    type StorageKey = u64;
+
+    fn data_type() -> StorageType {
+        StorageType::EpochRootRecord
+    }
+
+    fn get_id(&self) -> u64 {
+        self.epoch
+    }
+
+    fn get_full_binary_key_id(key: &u64) -> Vec<u8> {
+        u64_full_binary_key_id(StorageType::EpochRootRecord, *key)
+    }
+
+    fn key_from_full_binary(bin: &[u8]) -> Result<u64, String> {
+        u64_key_from_full_binary(StorageType::EpochRootRecord, bin)
+    }
+}
+
+fn u64_full_binary_key_id(data_type: StorageType, key: u64) -> Vec<u8> {
+    let mut result = vec![data_type as u8];
+    result.extend_from_slice(&key.to_be_bytes());
+    result
+}
+
+fn u64_key_from_full_binary(data_type: StorageType, bin: &[u8]) -> Result<u64, String> {
+    if bin.len() != 9 {
+        return Err("Not enough bytes to form a proper key".to_string());
+    }
+
+    if bin[0] != data_type as u8 {
+        return Err(format!("Not a {data_type:?} key"));
+    }
+
+    let key_bytes: [u8; 8] = bin[1..=8].try_into().expect("Slice with incorrect length");
+    Ok(u64::from_be_bytes(key_bytes))
+}
+
+/// A label registered in the directory, as enumerated by [crate::Directory::list_labels]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LabelEntry {
+    /// The label
+    pub label: AkdLabel,
+    /// The current version of the label, if requested
+    pub current_version: Option<u64>,
+    /// The epoch in which the label was last updated, if requested
+    pub last_update_epoch: Option<u64>,
+}
+
 /// Data associated with a given key. That is all the states at the various epochs
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(
@@ -162,6 +625,18 @@ pub enum DbRecord {
     TreeNode(TreeNodeWithPreviousValue),
     /// The state of the value for a particular key.
     ValueState(ValueState),
+    /// The metadata recorded for a published epoch
+    EpochMetadata(EpochMetadata),
+    /// A record of the audit trail of rollbacks
+    RollbackRecord(RollbackRecord),
+    /// The precomputed lookup proof of a label
+    PrecomputedLookupProof(PrecomputedLookupProof),
+    /// The journal of the updates of a published epoch
+    UpdateJournal(UpdateJournal),
+    /// The root hash and chain hash of a published epoch
+    EpochRootRecord(EpochRootRecord),
+    /// The precomputed VRF proof of a version of a label
+    PrecomputedVrfProof(PrecomputedVrfProof),
 }
 
 impl akd_core::SizeOf for DbRecord {
@@ -170,6 +645,12 @@ impl akd_core::SizeOf for DbRecord {
             DbRecord::Azks(azks) => azks.size_of(),
             DbRecord::TreeNode(node) => node.size_of(),
             DbRecord::ValueState(state) => state.size_of(),
+            DbRecord::EpochMetadata(metadata) => metadata.size_of(),
+            DbRecord::RollbackRecord(record) => record.size_of(),
+            DbRecord::PrecomputedLookupProof(proof) => proof.size_of(),
+            DbRecord::UpdateJournal(journal) => journal.size_of(),
+            DbRecord::EpochRootRecord(record) => record.size_of(),
+            DbRecord::PrecomputedVrfProof(proof) => proof.size_of(),
         }
     }
 }
@@ -180,6 +661,14 @@ impl Clone for DbRecord {
             DbRecord::Azks(azks) => DbRecord::Azks(azks.clone()),
             DbRecord::TreeNode(node) => DbRecord::TreeNode(node.clone()),
             DbRecord::ValueState(state) => DbRecord::ValueState(state.clone()),
+            DbRecord::EpochMetadata(metadata) => DbRecord::EpochMetadata(metadata.clone()),
+            DbRecord::RollbackRecord(record) => DbRecord::RollbackRecord(record.clone()),
+            DbRecord::PrecomputedLookupProof(proof) => {
+                DbRecord::PrecomputedLookupProof(proof.clone())
+            }
+            DbRecord::UpdateJournal(journal) => DbRecord::UpdateJournal(journal.clone()),
+            DbRecord::EpochRootRecord(record) => DbRecord::EpochRootRecord(record.clone()),
+            DbRecord::PrecomputedVrfProof(proof) => DbRecord::PrecomputedVrfProof(proof.clone()),
         }
     }
 }
@@ -192,6 +681,12 @@ impl DbRecord {
             DbRecord::Azks(azks) => azks.get_full_binary_id(),
             DbRecord::TreeNode(node) => node.get_full_binary_id(),
             DbRecord::ValueState(state) => state.get_full_binary_id(),
+            DbRecord::EpochMetadata(metadata) => metadata.get_full_binary_id(),
+            DbRecord::RollbackRecord(record) => record.get_full_binary_id(),
+            DbRecord::PrecomputedLookupProof(proof) => proof.get_full_binary_id(),
+            DbRecord::UpdateJournal(journal) => journal.get_full_binary_id(),
+            DbRecord::EpochRootRecord(record) => record.get_full_binary_id(),
+            DbRecord::PrecomputedVrfProof(proof) => proof.get_full_binary_id(),
         }
     }
 
@@ -284,13 +779,34 @@ impl DbRecord {
         label_len: u32,
         label_val: [u8; 32],
         epoch: u64,
+        blinding: Option<Vec<u8>>,
     ) -> ValueState {
         ValueState {
-            value: AkdValue(plaintext_val),
+            value: AkdValue::Bytes(plaintext_val),
             version,
             label: NodeLabel::new(label_val, label_len),
             epoch,
             username: AkdLabel(username),
+            blinding,
+        }
+    }
+
+    /// Build an epoch metadata record from the properties
+    pub fn build_epoch_metadata(
+        epoch: u64,
+        timestamp_ms: u64,
+        update_count: u64,
+        note: Option<String>,
+        bound: bool,
+        signature: Option<Vec<u8>>,
+    ) -> EpochMetadata {
+        EpochMetadata {
+            epoch,
+            timestamp_ms,
+            update_count,
+            note,
+            bound,
+            signature,
         }
     }
 }
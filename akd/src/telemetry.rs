@@ -0,0 +1,379 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! A facade for the metrics emitted by the library, which deployments route to their
+//! monitoring system by installing a [Recorder] with [set_recorder]. Nothing is recorded
+//! until a recorder is installed.
+//!
+//! The library emits the [Counter]s and [Histogram]s below, named following the Prometheus
+//! conventions. [PrometheusRecorder] aggregates them in memory and renders them in the
+//! Prometheus text exposition format, to be served on a scrape endpoint.
+//!
+//! To forward the metrics to the [`metrics`](https://docs.rs/metrics) crate (and any of its
+//! exporters) instead, a recorder only needs to map each metric to the corresponding macro:
+//!
+//! ```ignore
+//! struct MetricsCrateRecorder;
+//!
+//! impl akd::telemetry::Recorder for MetricsCrateRecorder {
+//!     fn increment_counter(&self, counter: Counter, value: u64, labels: &[Label]) {
+//!         let labels = labels.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>();
+//!         metrics::counter!(counter.name(), &labels).increment(value);
+//!     }
+//!
+//!     fn record_histogram(&self, histogram: Histogram, value: f64, labels: &[Label]) {
+//!         let labels = labels.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>();
+//!         metrics::histogram!(histogram.name(), &labels).record(value);
+//!     }
+//! }
+//! ```
+
+use crate::SizeOf;
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+
+/// A label attached to a metric, as a `(name, value)` pair
+pub type Label = (&'static str, &'static str);
+
+/// A monotonically increasing count
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Counter {
+    /// The number of VRF evaluations performed to derive the node labels of published values
+    VrfEvaluations,
+    /// The number of storage reads which were served by the cache
+    CacheHits,
+    /// The number of storage reads which missed the cache
+    CacheMisses,
+}
+
+impl Counter {
+    /// The name of the counter
+    pub fn name(&self) -> &'static str {
+        match self {
+            Counter::VrfEvaluations => "akd_vrf_evaluations_total",
+            Counter::CacheHits => "akd_cache_hits_total",
+            Counter::CacheMisses => "akd_cache_misses_total",
+        }
+    }
+
+    /// A description of the counter
+    pub fn description(&self) -> &'static str {
+        match self {
+            Counter::VrfEvaluations => "VRF evaluations performed when publishing",
+            Counter::CacheHits => "Storage reads served by the cache",
+            Counter::CacheMisses => "Storage reads which missed the cache",
+        }
+    }
+}
+
+/// A distribution of observed values
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Histogram {
+    /// The duration of successful publishes, in seconds
+    PublishDuration,
+    /// The time taken to generate a proof, in seconds, labelled by the type of the proof
+    ProofGenerationLatency,
+    /// The size of a generated proof, in bytes (see [SizeOf]), labelled by the type of the proof
+    ProofSize,
+}
+
+impl Histogram {
+    /// The name of the histogram
+    pub fn name(&self) -> &'static str {
+        match self {
+            Histogram::PublishDuration => "akd_publish_duration_seconds",
+            Histogram::ProofGenerationLatency => "akd_proof_generation_seconds",
+            Histogram::ProofSize => "akd_proof_size_bytes",
+        }
+    }
+
+    /// A description of the histogram
+    pub fn description(&self) -> &'static str {
+        match self {
+            Histogram::PublishDuration => "Duration of successful publishes",
+            Histogram::ProofGenerationLatency => "Time taken to generate a proof",
+            Histogram::ProofSize => "Size of a generated proof",
+        }
+    }
+
+    /// The upper bounds of the buckets into which [PrometheusRecorder] aggregates the values
+    pub fn buckets(&self) -> &'static [f64] {
+        match self {
+            Histogram::PublishDuration => &[0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0, 60.0, 300.0],
+            Histogram::ProofGenerationLatency => &[0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0],
+            Histogram::ProofSize => &[
+                1024.0, 4096.0, 16384.0, 65536.0, 262144.0, 1048576.0, 4194304.0,
+            ],
+        }
+    }
+}
+
+/// Receives the metrics emitted by the library. Implementations must be cheap, as they are
+/// invoked on the paths of the operations which they measure.
+pub trait Recorder: Send + Sync {
+    /// Increments a counter by the provided value
+    fn increment_counter(&self, counter: Counter, value: u64, labels: &[Label]);
+
+    /// Records an observation of a histogram
+    fn record_histogram(&self, histogram: Histogram, value: f64, labels: &[Label]);
+}
+
+impl<R: Recorder + ?Sized> Recorder for Box<R> {
+    fn increment_counter(&self, counter: Counter, value: u64, labels: &[Label]) {
+        (**self).increment_counter(counter, value, labels)
+    }
+
+    fn record_histogram(&self, histogram: Histogram, value: f64, labels: &[Label]) {
+        (**self).record_histogram(histogram, value, labels)
+    }
+}
+
+/// The error returned by [set_recorder] when a recorder was already installed
+#[derive(Debug)]
+pub struct SetRecorderError;
+
+impl std::error::Error for SetRecorderError {}
+
+impl fmt::Display for SetRecorderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "A telemetry recorder was already installed")
+    }
+}
+
+static RECORDER: OnceLock<Box<dyn Recorder>> = OnceLock::new();
+
+/// Installs the recorder to which the metrics of the library are emitted. This can only be
+/// called once in the lifetime of the process, and fails if a recorder was already installed.
+pub fn set_recorder<R: Recorder + 'static>(recorder: R) -> Result<(), SetRecorderError> {
+    RECORDER
+        .set(Box::new(recorder))
+        .map_err(|_| SetRecorderError)
+}
+
+/// Whether a recorder was installed with [set_recorder]
+pub(crate) fn recorder_installed() -> bool {
+    RECORDER.get().is_some()
+}
+
+pub(crate) fn increment_counter(counter: Counter, value: u64, labels: &[Label]) {
+    if let Some(recorder) = RECORDER.get() {
+        recorder.increment_counter(counter, value, labels);
+    }
+}
+
+pub(crate) fn record_histogram(histogram: Histogram, value: f64, labels: &[Label]) {
+    if let Some(recorder) = RECORDER.get() {
+        recorder.record_histogram(histogram, value, labels);
+    }
+}
+
+/// Records the generation latency (since `started`) and the size of a proof of the given type
+pub(crate) fn record_proof<P: SizeOf>(proof_type: &'static str, started: Instant, proof: &P) {
+    if let Some(recorder) = RECORDER.get() {
+        let labels = [("proof", proof_type)];
+        recorder.record_histogram(
+            Histogram::ProofGenerationLatency,
+            started.elapsed().as_secs_f64(),
+            &labels,
+        );
+        recorder.record_histogram(Histogram::ProofSize, proof.size_of() as f64, &labels);
+    }
+}
+
+#[derive(Default)]
+struct HistogramState {
+    /// The number of observations in each bucket, and then above the last bucket
+    counts: Vec<u64>,
+    sum: f64,
+}
+
+#[derive(Default)]
+struct PrometheusState {
+    counters: BTreeMap<(Counter, Vec<Label>), u64>,
+    histograms: BTreeMap<(Histogram, Vec<Label>), HistogramState>,
+}
+
+/// A [Recorder] aggregating the metrics in memory, which renders them in the Prometheus text
+/// exposition format. Clones share the same metrics, so a clone can be kept to render the
+/// metrics after another one is installed with [set_recorder].
+#[derive(Clone, Default)]
+pub struct PrometheusRecorder {
+    state: Arc<Mutex<PrometheusState>>,
+}
+
+impl PrometheusRecorder {
+    /// Creates a new recorder, without any metrics
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Retrieves the current value of a counter
+    pub fn counter(&self, counter: Counter, labels: &[Label]) -> u64 {
+        let state = self.state.lock().unwrap();
+        state
+            .counters
+            .get(&(counter, labels.to_vec()))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Retrieves the number of observations of a histogram
+    pub fn histogram_count(&self, histogram: Histogram, labels: &[Label]) -> u64 {
+        let state = self.state.lock().unwrap();
+        state
+            .histograms
+            .get(&(histogram, labels.to_vec()))
+            .map_or(0, |histogram| histogram.counts.iter().sum())
+    }
+
+    /// Renders the metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let state = self.state.lock().unwrap();
+        let mut output = String::new();
+        let mut previous = None;
+        for ((counter, labels), value) in state.counters.iter() {
+            if previous != Some(counter.name()) {
+                writeln!(
+                    output,
+                    "# HELP {} {}",
+                    counter.name(),
+                    counter.description()
+                )
+                .unwrap();
+                writeln!(output, "# TYPE {} counter", counter.name()).unwrap();
+                previous = Some(counter.name());
+            }
+            writeln!(
+                output,
+                "{}{} {}",
+                counter.name(),
+                format_labels(labels, None),
+                value
+            )
+            .unwrap();
+        }
+        for ((histogram, labels), values) in state.histograms.iter() {
+            let name = histogram.name();
+            if previous != Some(name) {
+                writeln!(output, "# HELP {} {}", name, histogram.description()).unwrap();
+                writeln!(output, "# TYPE {} histogram", name).unwrap();
+                previous = Some(name);
+            }
+            let mut cumulative = 0;
+            for (bound, count) in histogram.buckets().iter().zip(values.counts.iter()) {
+                cumulative += count;
+                let bound = bound.to_string();
+                writeln!(
+                    output,
+                    "{}_bucket{} {}",
+                    name,
+                    format_labels(labels, Some(&bound)),
+                    cumulative
+                )
+                .unwrap();
+            }
+            let total = values.counts.iter().sum::<u64>();
+            writeln!(
+                output,
+                "{}_bucket{} {}",
+                name,
+                format_labels(labels, Some("+Inf")),
+                total
+            )
+            .unwrap();
+            writeln!(
+                output,
+                "{}_sum{} {}",
+                name,
+                format_labels(labels, None),
+                values.sum
+            )
+            .unwrap();
+            writeln!(
+                output,
+                "{}_count{} {}",
+                name,
+                format_labels(labels, None),
+                total
+            )
+            .unwrap();
+        }
+        output
+    }
+}
+
+fn format_labels(labels: &[Label], le: Option<&str>) -> String {
+    let mut formatted = labels
+        .iter()
+        .map(|(name, value)| format!("{name}=\"{value}\""))
+        .collect::<Vec<_>>();
+    if let Some(le) = le {
+        formatted.push(format!("le=\"{le}\""));
+    }
+    if formatted.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", formatted.join(","))
+    }
+}
+
+impl Recorder for PrometheusRecorder {
+    fn increment_counter(&self, counter: Counter, value: u64, labels: &[Label]) {
+        let mut state = self.state.lock().unwrap();
+        *state
+            .counters
+            .entry((counter, labels.to_vec()))
+            .or_default() += value;
+    }
+
+    fn record_histogram(&self, histogram: Histogram, value: f64, labels: &[Label]) {
+        let mut state = self.state.lock().unwrap();
+        let values = state
+            .histograms
+            .entry((histogram, labels.to_vec()))
+            .or_default();
+        let buckets = histogram.buckets();
+        values.counts.resize(buckets.len() + 1, 0);
+        let bucket = buckets
+            .iter()
+            .position(|bound| value <= *bound)
+            .unwrap_or(buckets.len());
+        values.counts[bucket] += 1;
+        values.sum += value;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prometheus_recorder() {
+        let recorder = PrometheusRecorder::new();
+        recorder.increment_counter(Counter::CacheHits, 2, &[]);
+        recorder.increment_counter(Counter::CacheHits, 1, &[]);
+        let labels = [("proof", "lookup")];
+        recorder.record_histogram(Histogram::ProofSize, 2000.0, &labels);
+        recorder.record_histogram(Histogram::ProofSize, 1e9, &labels);
+
+        assert_eq!(3, recorder.counter(Counter::CacheHits, &[]));
+        assert_eq!(0, recorder.counter(Counter::CacheMisses, &[]));
+        assert_eq!(2, recorder.histogram_count(Histogram::ProofSize, &labels));
+        assert_eq!(0, recorder.histogram_count(Histogram::ProofSize, &[]));
+
+        let rendered = recorder.render();
+        assert!(rendered.contains("# TYPE akd_cache_hits_total counter\nakd_cache_hits_total 3\n"));
+        assert!(rendered.contains("akd_proof_size_bytes_bucket{proof=\"lookup\",le=\"1024\"} 0\n"));
+        assert!(rendered.contains("akd_proof_size_bytes_bucket{proof=\"lookup\",le=\"4096\"} 1\n"));
+        assert!(rendered.contains("akd_proof_size_bytes_bucket{proof=\"lookup\",le=\"+Inf\"} 2\n"));
+        assert!(rendered.contains("akd_proof_size_bytes_count{proof=\"lookup\"} 2\n"));
+    }
+}
@@ -8,26 +8,59 @@
 //! Contains the tests for the high-level API (directory, auditor, client)
 
 use std::collections::HashMap;
+use std::time::Duration;
 
-use crate::{errors::DirectoryError, test_config};
-use akd_core::{configuration::Configuration, hash::DIGEST_BYTES};
+use crate::epoch_publisher::{
+    EpochLogEntry, EpochPublisher, EpochPublisherError, LogFileEpochPublisher,
+};
+use crate::errors::DirectoryError;
+use akd_core::{
+    configuration::Configuration,
+    hash::{Digest, DIGEST_BYTES, EMPTY_DIGEST},
+};
+use futures::TryStreamExt;
 use rand::{rngs::StdRng, SeedableRng};
 
 use crate::{
-    auditor::{audit_verify, verify_consecutive_append_only},
-    client::{key_history_verify, lookup_verify},
-    directory::{Directory, PublishCorruption, ReadOnlyDirectory},
-    ecvrf::{HardCodedAkdVRF, VRFKeyStorage},
-    errors::{AkdError, StorageError},
+    admission::AdmissionConfig,
+    auditor::{
+        audit_verify, audit_verify_compressed, audit_verify_stream, resume,
+        verify_consecutive_append_only, verify_consecutive_append_only_in_parallel,
+        AuditStreamVerifier, AuditorState,
+    },
+    builder::CachePolicy,
+    cancellation::CancellationToken,
+    client::{
+        key_history_since_verify, key_history_verify, key_rotation_verify, lookup_absent_verify,
+        lookup_label_derivations, lookup_verify, merge_bridge_verify, verify_epoch_root_chain,
+        verify_lookup_challenge, verify_vrf_rotation_certificate, HashCheck, OrderingViolation,
+        ProofShapeError, VerificationError,
+    },
+    clock::ManualClock,
+    directory::{Directory, PublishCorruption, ReadOnlyDirectory, VrfKeyRotation},
+    ecvrf::{
+        CachingVrf, HardCodedAkdVRF, RemoteVrfService, RemoteVrfSigner, VRFKeyStorage, VrfError,
+    },
+    errors::{AkdError, ConfigurationError, StorageError},
+    merge::{merge, MergeConflict, MergeConflictPolicy, MergeSource},
+    publisher::Publisher,
+    retention::{RetentionPolicy, RetentionReport},
+    signature::{EpochSigner, HardCodedEpochSigner},
     storage::{
         manager::StorageManager,
         memory::AsyncInMemoryDatabase,
-        types::{DbRecord, KeyData, ValueState, ValueStateRetrievalFlag},
-        Database, DbSetState, Storable,
+        types::{
+            DbRecord, EpochRootRecord, JournalEntry, JournalLabel, KeyData, LabelEntry,
+            PrecomputedLookupProof, PrecomputedVrfProof, UpdateJournal, UpdateJournalMode,
+            ValueState, ValueStateRetrievalFlag, VrfProofKey,
+        },
+        BoxedDatabase, Database, DbSetState, Storable, StorageUtil,
     },
     tree_node::TreeNodeWithPreviousValue,
-    AkdLabel, AkdValue, AppendOnlyProof, Azks, EpochHash, HistoryParams, HistoryVerificationParams,
-    VerifyResult,
+    AkdLabel, AkdValue, AkdValueSet, AppendOnlyProof, Azks, EpochDiff, EpochHash, EpochMetadata,
+    EpochRootChain, HistoryParams, HistoryProof, HistoryVerificationParams, LabelNamespace,
+    PaddedConfiguration, RootChainAnchor, SingleAppendOnlyProof, SizeOf, VerifyResult,
+    VersionFreshness,
 };
 
 #[allow(dead_code)]
@@ -46,7 +79,7 @@ mockall::mock! {
         fn clone(&self) -> Self;
     }
     #[async_trait::async_trait]
-    impl Database for LocalDatabase {
+    impl BoxedDatabase for LocalDatabase {
         async fn set(&self, record: DbRecord) -> Result<(), StorageError>;
         async fn batch_set(
             &self,
@@ -128,6 +161,143 @@ fn setup_mocked_db(db: &mut MockLocalDatabase, test_db: &AsyncInMemoryDatabase)
         });
 }
 
+/// A database which yields to the runtime before every operation, so that concurrent directory
+/// operations interleave on a single-threaded runtime
+#[derive(Clone)]
+struct YieldingDatabase(AsyncInMemoryDatabase);
+
+#[async_trait::async_trait]
+impl BoxedDatabase for YieldingDatabase {
+    async fn set(&self, record: DbRecord) -> Result<(), StorageError> {
+        tokio::task::yield_now().await;
+        Database::set(&self.0, record).await
+    }
+
+    async fn batch_set(
+        &self,
+        records: Vec<DbRecord>,
+        state: DbSetState,
+    ) -> Result<(), StorageError> {
+        tokio::task::yield_now().await;
+        Database::batch_set(&self.0, records, state).await
+    }
+
+    async fn get<St: Storable>(&self, id: &St::StorageKey) -> Result<DbRecord, StorageError> {
+        tokio::task::yield_now().await;
+        Database::get::<St>(&self.0, id).await
+    }
+
+    async fn batch_get<St: Storable>(
+        &self,
+        ids: &[St::StorageKey],
+    ) -> Result<Vec<DbRecord>, StorageError> {
+        tokio::task::yield_now().await;
+        Database::batch_get::<St>(&self.0, ids).await
+    }
+
+    async fn get_user_data(&self, username: &AkdLabel) -> Result<KeyData, StorageError> {
+        tokio::task::yield_now().await;
+        Database::get_user_data(&self.0, username).await
+    }
+
+    async fn get_user_state(
+        &self,
+        username: &AkdLabel,
+        flag: ValueStateRetrievalFlag,
+    ) -> Result<ValueState, StorageError> {
+        tokio::task::yield_now().await;
+        Database::get_user_state(&self.0, username, flag).await
+    }
+
+    async fn get_user_state_versions(
+        &self,
+        usernames: &[AkdLabel],
+        flag: ValueStateRetrievalFlag,
+    ) -> Result<HashMap<AkdLabel, (u64, AkdValue)>, StorageError> {
+        tokio::task::yield_now().await;
+        Database::get_user_state_versions(&self.0, usernames, flag).await
+    }
+
+    async fn list_labels(
+        &self,
+        cursor: Option<&AkdLabel>,
+        limit: usize,
+        epoch: u64,
+    ) -> Result<Vec<LabelEntry>, StorageError> {
+        tokio::task::yield_now().await;
+        Database::list_labels(&self.0, cursor, limit, epoch).await
+    }
+}
+
+/// A database which rejects the commits of transactions which delete records, as it would if
+/// the publish lease were taken over in the meantime
+#[derive(Clone)]
+struct RejectingDeletionsDatabase(AsyncInMemoryDatabase);
+
+#[async_trait::async_trait]
+impl BoxedDatabase for RejectingDeletionsDatabase {
+    async fn set(&self, record: DbRecord) -> Result<(), StorageError> {
+        Database::set(&self.0, record).await
+    }
+
+    async fn batch_set(
+        &self,
+        records: Vec<DbRecord>,
+        state: DbSetState,
+    ) -> Result<(), StorageError> {
+        Database::batch_set(&self.0, records, state).await
+    }
+
+    async fn get<St: Storable>(&self, id: &St::StorageKey) -> Result<DbRecord, StorageError> {
+        Database::get::<St>(&self.0, id).await
+    }
+
+    async fn batch_get<St: Storable>(
+        &self,
+        ids: &[St::StorageKey],
+    ) -> Result<Vec<DbRecord>, StorageError> {
+        Database::batch_get::<St>(&self.0, ids).await
+    }
+
+    async fn get_user_data(&self, username: &AkdLabel) -> Result<KeyData, StorageError> {
+        Database::get_user_data(&self.0, username).await
+    }
+
+    async fn get_user_state(
+        &self,
+        username: &AkdLabel,
+        flag: ValueStateRetrievalFlag,
+    ) -> Result<ValueState, StorageError> {
+        Database::get_user_state(&self.0, username, flag).await
+    }
+
+    async fn get_user_state_versions(
+        &self,
+        usernames: &[AkdLabel],
+        flag: ValueStateRetrievalFlag,
+    ) -> Result<HashMap<AkdLabel, (u64, AkdValue)>, StorageError> {
+        Database::get_user_state_versions(&self.0, usernames, flag).await
+    }
+
+    async fn batch_set_and_delete(
+        &self,
+        _records: Vec<DbRecord>,
+        _deletions: Vec<Vec<u8>>,
+        _state: DbSetState,
+    ) -> Result<(), StorageError> {
+        Err(StorageError::Transaction("Write fenced off".to_string()))
+    }
+
+    async fn list_labels(
+        &self,
+        cursor: Option<&AkdLabel>,
+        limit: usize,
+        epoch: u64,
+    ) -> Result<Vec<LabelEntry>, StorageError> {
+        Database::list_labels(&self.0, cursor, limit, epoch).await
+    }
+}
+
 // A test to ensure that any database error at the time a Directory is created
 // does not automatically attempt to create a new aZKS. Only aZKS not found errors
 // should assume that a successful read happened and no aZKS exists.
@@ -251,6 +421,68 @@ async fn test_simple_lookup<TC: Configuration>() -> Result<(), AkdError> {
     Ok(())
 }
 
+// Test that the label derivations checked from the VRF proofs of a lookup proof are those of
+// the directory, and that a VRF proof for another version is rejected
+test_config!(test_lookup_label_derivations);
+async fn test_lookup_label_derivations<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let vrf = HardCodedAkdVRF {};
+    let akd = Directory::<TC, _, _>::new(storage, vrf.clone()).await?;
+    for epoch in 1..=3 {
+        akd.publish(vec![
+            (
+                AkdLabel::from("hello"),
+                AkdValue::Bytes(format!("world{epoch}").into_bytes()),
+            ),
+            (AkdLabel::from("hello2"), AkdValue::from("world2")),
+        ])
+        .await?;
+    }
+    let (lookup_proof, epoch_hash) = akd.lookup(AkdLabel::from("hello")).await?;
+    let vrf_pk = akd.get_public_key().await?;
+
+    let derivations = lookup_label_derivations::<TC>(
+        vrf_pk.as_bytes(),
+        epoch_hash.epoch(),
+        &AkdLabel::from("hello"),
+        &lookup_proof,
+    )?;
+    let expected = [
+        (VersionFreshness::Fresh, 3),
+        (VersionFreshness::Fresh, 2),
+        (VersionFreshness::Stale, 3),
+    ];
+    assert_eq!(expected.len(), derivations.len());
+    for ((freshness, version), derivation) in expected.into_iter().zip(derivations) {
+        assert_eq!(freshness, derivation.freshness);
+        assert_eq!(version, derivation.version);
+        assert_eq!(
+            vrf.get_node_label::<TC>(&AkdLabel::from("hello"), freshness, version)
+                .await?,
+            derivation.node_label
+        );
+        assert_eq!(
+            derivation.node_label.label_val[..],
+            derivation.vrf_output[..32]
+        );
+    }
+
+    // The marker proof is for version 2, so the VRF proof of version 3 does not derive its label
+    let mut tampered_proof = lookup_proof;
+    tampered_proof.marker_vrf_proof = tampered_proof.existence_vrf_proof.clone();
+    assert!(matches!(
+        lookup_label_derivations::<TC>(
+            vrf_pk.as_bytes(),
+            epoch_hash.epoch(),
+            &AkdLabel::from("hello"),
+            &tampered_proof
+        ),
+        Err(VerificationError::Vrf(_))
+    ));
+    Ok(())
+}
+
 // This test also covers #144: That key history doesn't fail on very small trees,
 // i.e. trees with a potentially empty child for the root node.
 // Other that it is just a simple check to see that a valid key history proof passes.
@@ -448,7 +680,7 @@ async fn test_simple_key_history<TC: Configuration>() -> Result<(), AkdError> {
         borked_proof,
         HistoryVerificationParams::default(),
     );
-    assert!(matches!(result, Err(_)), "{}", "{result:?}");
+    assert!(result.is_err(), "{}", "{result:?}");
 
     Ok(())
 }
@@ -878,15 +1110,15 @@ async fn test_simple_audit<TC: Configuration>() -> Result<(), AkdError> {
 
     // The audit should be of more than 1 epoch
     let invalid_audit = akd.audit(3, 3).await;
-    assert!(matches!(invalid_audit, Err(_)));
+    assert!(invalid_audit.is_err());
 
     // The audit epochs must be increasing
     let invalid_audit = akd.audit(3, 2).await;
-    assert!(matches!(invalid_audit, Err(_)));
+    assert!(invalid_audit.is_err());
 
     // The audit should throw an error when queried for an epoch which hasn't yet taken place!
     let invalid_audit = akd.audit(6, 7).await;
-    assert!(matches!(invalid_audit, Err(_)));
+    assert!(invalid_audit.is_err());
 
     Ok(())
 }
@@ -979,7 +1211,7 @@ async fn test_read_during_publish<TC: Configuration>() -> Result<(), AkdError> {
         .unwrap();
 
     let invalid_audit = akd.audit(2, 3).await;
-    assert!(matches!(invalid_audit, Err(_)));
+    assert!(invalid_audit.is_err());
 
     Ok(())
 }
@@ -995,7 +1227,7 @@ async fn test_directory_read_only_mode<TC: Configuration>() -> Result<(), AkdErr
     let vrf = HardCodedAkdVRF {};
     // There is no AZKS object in the storage layer, directory construction should fail
     let akd = ReadOnlyDirectory::<TC, _, _>::new(storage, vrf).await;
-    assert!(matches!(akd, Err(_)));
+    assert!(akd.is_err());
 
     Ok(())
 }
@@ -1106,7 +1338,7 @@ async fn test_tombstoned_key_history<TC: Configuration>() -> Result<(), AkdError
         history_proof.clone(),
         HistoryVerificationParams::default(),
     );
-    assert!(matches!(tombstones, Err(_)));
+    assert!(tombstones.is_err());
 
     // We should be able to verify tombstones assuming the client is accepting
     // of tombstoned states
@@ -1120,15 +1352,242 @@ async fn test_tombstoned_key_history<TC: Configuration>() -> Result<(), AkdError
             history_params: HistoryParams::default(),
         },
     )?;
-    assert_ne!(crate::TOMBSTONE, results[0].value.0);
-    assert_ne!(crate::TOMBSTONE, results[1].value.0);
-    assert_ne!(crate::TOMBSTONE, results[2].value.0);
-    assert_eq!(crate::TOMBSTONE, results[3].value.0);
-    assert_eq!(crate::TOMBSTONE, results[4].value.0);
+    assert!(!results[0].value.is_tombstone());
+    assert!(!results[1].value.is_tombstone());
+    assert!(!results[2].value.is_tombstone());
+    assert_eq!(AkdValue::Tombstone, results[3].value);
+    assert_eq!(AkdValue::Tombstone, results[4].value);
+
+    // A tombstone cannot be published, while an empty value is verified like any other
+    assert!(akd
+        .publish(vec![(AkdLabel::from("hello"), AkdValue::Tombstone)])
+        .await
+        .is_err());
+    akd.publish(vec![(AkdLabel::from("hello"), AkdValue::from(""))])
+        .await?;
+    let (history_proof, root_hash) = akd
+        .key_history(&AkdLabel::from("hello"), HistoryParams::default())
+        .await?;
+    let results = key_history_verify::<TC>(
+        vrf_pk.as_bytes(),
+        root_hash.hash(),
+        root_hash.epoch(),
+        AkdLabel::from("hello"),
+        history_proof,
+        HistoryVerificationParams::AllowMissingValues {
+            history_params: HistoryParams::default(),
+        },
+    )?;
+    assert_eq!(AkdValue::from(""), results[0].value);
+    assert_eq!(AkdValue::Tombstone, results[4].value);
+
+    // The latest value cannot be looked up once tombstoned
+    storage
+        .tombstone_value_states(&AkdLabel::from("hello"), 6)
+        .await?;
+    assert!(matches!(
+        akd.lookup(AkdLabel::from("hello")).await,
+        Err(AkdError::Directory(DirectoryError::Tombstoned(_)))
+    ));
+
+    Ok(())
+}
+
+test_config!(test_key_history_metadata);
+async fn test_key_history_metadata<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let akd = Directory::<TC, _, _>::new(storage.clone(), HardCodedAkdVRF {}).await?;
+    let vrf_pk = akd.get_public_key().await?;
+    let label = AkdLabel::from("hello");
+
+    // Versions 1-5 of the label at epochs 1-5, then updates of another label up to epoch 9
+    for i in 1..=9 {
+        let updated = if i <= 5 { "hello" } else { "other" };
+        akd.publish(vec![(
+            AkdLabel::from(updated),
+            AkdValue::from(&format!("world{i}")),
+        )])
+        .await?;
+    }
+    storage.tombstone_value_states(&label, 2).await?;
+
+    let verify = |proof: HistoryProof, epoch_hash: EpochHash, history_params| {
+        crate::client::key_history_verify_with_metadata::<TC>(
+            vrf_pk.as_bytes(),
+            epoch_hash.hash(),
+            epoch_hash.epoch(),
+            label.clone(),
+            proof,
+            HistoryVerificationParams::AllowMissingValues { history_params },
+        )
+    };
+
+    // The complete history is followed by versions 6 and 7 up to the next marker, and then
+    // marker version 8 up to the epoch
+    let (proof, epoch_hash) = akd.key_history(&label, HistoryParams::Complete).await?;
+    let (results, metadata) = verify(proof, epoch_hash, HistoryParams::Complete)?;
+    assert_eq!(5, results.len());
+    assert_eq!(
+        crate::HistoryMetadata {
+            epoch: 9,
+            complete: true,
+            past_marker_versions: vec![],
+            future_marker_versions: vec![6, 7, 8],
+            tombstoned_versions: vec![1..=2],
+        },
+        metadata
+    );
+
+    // The most recent version is preceded by marker version 4
+    let (proof, epoch_hash) = akd
+        .key_history(&label, HistoryParams::MostRecent(1))
+        .await?;
+    let (results, metadata) = verify(proof, epoch_hash, HistoryParams::MostRecent(1))?;
+    assert_eq!(5, results[0].version);
+    assert!(!metadata.complete);
+    assert_eq!(vec![4], metadata.past_marker_versions);
+    assert_eq!(vec![6, 7, 8], metadata.future_marker_versions);
+    assert!(metadata.tombstoned_versions.is_empty());
+
+    Ok(())
+}
+
+// Test that key history proofs are checked against the root hashes of pinned epochs, through
+// an anchored root chain ending at the epoch of the proof
+test_config!(test_key_history_pinned_roots);
+async fn test_key_history_pinned_roots<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let akd = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {})
+        .await?
+        .with_root_history();
+    let vrf_pk = akd.get_public_key().await?;
+    let label = AkdLabel::from("hello");
+
+    // Versions 1 and 2 of the label at epochs 1 and 3, and updates of another label at
+    // epochs 2 and 4
+    let mut roots = std::collections::BTreeMap::new();
+    for i in 1..=4 {
+        let updated = if i % 2 == 1 { "hello" } else { "other" };
+        let epoch_hash = akd
+            .publish(vec![(
+                AkdLabel::from(updated),
+                AkdValue::from(&format!("world{i}")),
+            )])
+            .await?;
+        roots.insert(epoch_hash.epoch(), epoch_hash.hash());
+    }
+
+    let (proof, epoch_hash) = akd.key_history(&label, HistoryParams::Complete).await?;
+    let chain = akd.get_epoch_hashes(2, 4).await?;
+    let verify = |pinned_roots: &std::collections::BTreeMap<u64, Digest>,
+                  root_chain: &EpochRootChain,
+                  anchor: RootChainAnchor| {
+        key_history_verify::<TC>(
+            vrf_pk.as_bytes(),
+            epoch_hash.hash(),
+            epoch_hash.epoch(),
+            label.clone(),
+            proof.clone(),
+            HistoryVerificationParams::PinnedRoots {
+                history_params: HistoryParams::Complete,
+                allow_missing_values: false,
+                pinned_roots,
+                root_chain,
+                anchor,
+            },
+        )
+    };
+
+    // The history is consistent with the actual roots covered by the chain, and with any
+    // subset of them
+    let full_chain = akd.get_epoch_hashes(1, 4).await?;
+    let genesis = RootChainAnchor::Previous(EMPTY_DIGEST);
+    assert_eq!(2, verify(&roots, &full_chain, genesis)?.len());
+    let anchor = RootChainAnchor::Last(chain.chain_hash);
+    assert_eq!(
+        2,
+        verify(&std::collections::BTreeMap::new(), &chain, anchor)?.len()
+    );
+    let pinned = [(2, roots[&2])].into_iter().collect();
+    assert_eq!(2, verify(&pinned, &chain, anchor)?.len());
+
+    // The chain must be rooted in the anchor, and end with the root of the epoch of the proof
+    assert!(verify(&pinned, &chain, RootChainAnchor::Last(roots[&4])).is_err());
+    let prefix = akd.get_epoch_hashes(2, 3).await?;
+    let prefix_anchor = RootChainAnchor::Last(prefix.chain_hash);
+    assert!(verify(&pinned, &prefix, prefix_anchor).is_err());
+
+    // Each pinned root must be the root of its epoch in the chain, and so no pinned epoch may
+    // precede the chain or follow the epoch of the proof
+    let mut forked = roots.clone();
+    forked.insert(3, roots[&2]);
+    assert!(verify(&forked, &full_chain, genesis).is_err());
+    assert!(verify(&roots, &chain, anchor).is_err());
+    let mut rolled_back = roots.clone();
+    rolled_back.insert(5, roots[&4]);
+    assert!(verify(&rolled_back, &full_chain, genesis).is_err());
 
     Ok(())
 }
 
+// Test that values are padded in proofs, and unpadded by the client after verification
+test_config!(test_padded_values);
+async fn test_padded_values<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let vrf = HardCodedAkdVRF {};
+    let akd = Directory::<PaddedConfiguration<TC, 16>, _, _>::new(storage, vrf).await?;
+    let vrf_pk = akd.get_public_key().await?;
+
+    let label = AkdLabel::from("hello");
+    let values = [AkdValue::from("world"), AkdValue::Bytes(vec![7u8; 20])];
+    for value in values.iter() {
+        akd.publish(vec![(label.clone(), value.clone())]).await?;
+    }
+
+    // The proof only reveals the bucket of the value
+    let (lookup_proof, root_hash) = akd.lookup(label.clone()).await?;
+    assert_eq!(32, lookup_proof.value.len());
+    let result = lookup_verify::<PaddedConfiguration<TC, 16>>(
+        vrf_pk.as_bytes(),
+        root_hash.hash(),
+        root_hash.epoch(),
+        label.clone(),
+        lookup_proof.clone(),
+    )?;
+    assert_eq!(values[1], result.value);
+
+    // A value which is not padded is rejected, even if its commitment verifies
+    let mut unpadded_proof = lookup_proof;
+    unpadded_proof.value = values[1].clone();
+    assert!(lookup_verify::<PaddedConfiguration<TC, 16>>(
+        vrf_pk.as_bytes(),
+        root_hash.hash(),
+        root_hash.epoch(),
+        label.clone(),
+        unpadded_proof,
+    )
+    .is_err());
+
+    let (history_proof, root_hash) = akd.key_history(&label, HistoryParams::default()).await?;
+    assert_eq!(16, history_proof.update_proofs[1].value.len());
+    let results = key_history_verify::<PaddedConfiguration<TC, 16>>(
+        vrf_pk.as_bytes(),
+        root_hash.hash(),
+        root_hash.epoch(),
+        label,
+        history_proof,
+        HistoryVerificationParams::default(),
+    )?;
+    assert_eq!(values[1], results[0].value);
+    assert_eq!(values[0], results[1].value);
+
+    // Tombstoned values are padded like any other
+    test_tombstoned_key_history::<PaddedConfiguration<TC, 16>>().await
+}
+
 test_config!(test_publish_op_makes_no_get_requests);
 async fn test_publish_op_makes_no_get_requests<TC: Configuration>() -> Result<(), AkdError> {
     let test_db = AsyncInMemoryDatabase::new();
@@ -1151,7 +1610,7 @@ async fn test_publish_op_makes_no_get_requests<TC: Configuration>() -> Result<()
     for i in 0..2 {
         updates.push((
             AkdLabel(format!("hello1{i}").as_bytes().to_vec()),
-            AkdValue(format!("hello1{i}").as_bytes().to_vec()),
+            AkdValue::Bytes(format!("hello1{i}").as_bytes().to_vec()),
         ));
     }
     // Publish the updates. Now the akd's epoch will be 1.
@@ -1179,7 +1638,7 @@ async fn test_publish_op_makes_no_get_requests<TC: Configuration>() -> Result<()
     for i in 0..2 {
         updates.push((
             AkdLabel(format!("hello1{i}").as_bytes().to_vec()),
-            AkdValue(format!("hello1{}", i + 1).as_bytes().to_vec()),
+            AkdValue::Bytes(format!("hello1{}", i + 1).as_bytes().to_vec()),
         ));
     }
 
@@ -1214,7 +1673,7 @@ async fn test_simple_lookup_for_small_tree<TC: Configuration>() -> Result<(), Ak
     for i in 0..2 {
         updates.push((
             AkdLabel(format!("hello1{i}").as_bytes().to_vec()),
-            AkdValue(format!("hello1{i}").as_bytes().to_vec()),
+            AkdValue::Bytes(format!("hello1{i}").as_bytes().to_vec()),
         ));
     }
     // Publish the updates. Now the akd's epoch will be 1.
@@ -1267,7 +1726,7 @@ async fn test_lookup_verify_invalid_version_number<TC: Configuration>() -> Resul
     for i in 0..2 {
         updates.push((
             AkdLabel(format!("hello1{i}").as_bytes().to_vec()),
-            AkdValue(format!("hello1{i}").as_bytes().to_vec()),
+            AkdValue::Bytes(format!("hello1{i}").as_bytes().to_vec()),
         ));
     }
     // Repeatedly publish the updates. Afterwards, the akd's epoch will be 10.
@@ -1294,7 +1753,7 @@ async fn test_lookup_verify_invalid_version_number<TC: Configuration>() -> Resul
 
     // Check that the result is a verification error
     match akd_result {
-        Err(akd_core::verify::VerificationError::LookupProof(_)) => (),
+        Err(VerificationError::Ordering(OrderingViolation::VersionExceedsEpoch { .. })) => (),
         _ => panic!("Expected an invalid epoch error"),
     }
 
@@ -1314,7 +1773,7 @@ async fn test_publish_duplicate_entries<TC: Configuration>() -> Result<(), AkdEr
     for i in 0..10 {
         updates.push((
             AkdLabel(format!("hello1{i}").as_bytes().to_vec()),
-            AkdValue(format!("hello1{i}").as_bytes().to_vec()),
+            AkdValue::Bytes(format!("hello1{i}").as_bytes().to_vec()),
         ));
     }
 
@@ -1329,6 +1788,171 @@ async fn test_publish_duplicate_entries<TC: Configuration>() -> Result<(), AkdEr
     Ok(())
 }
 
+// Test publishing values committed to with blindings supplied by the client
+test_config!(test_publish_with_blindings);
+async fn test_publish_with_blindings<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let vrf = HardCodedAkdVRF {};
+    let akd = Directory::<TC, _, _>::new(storage, vrf.clone()).await?;
+
+    // The same updates, published without any blindings
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let unblinded = Directory::<TC, _, _>::new(storage, vrf).await?;
+
+    let label = AkdLabel::from("hello");
+    for value in ["world", "world2"] {
+        let updates = vec![
+            (label.clone(), AkdValue::from(value)),
+            (AkdLabel::from("other"), AkdValue::from(value)),
+        ];
+        let blindings = HashMap::from([(label.clone(), format!("{value} blinding").into_bytes())]);
+        akd.publish_with_blindings(updates.clone(), blindings)
+            .await?;
+        unblinded.publish(updates).await?;
+    }
+    let vrf_pk = akd.get_public_key().await?;
+
+    // The blinded commitment verifies as usual, but with a different nonce
+    let (lookup_proof, root_hash) = akd.lookup(label.clone()).await?;
+    let (unblinded_proof, unblinded_root_hash) = unblinded.lookup(label.clone()).await?;
+    assert_ne!(
+        lookup_proof.commitment_nonce,
+        unblinded_proof.commitment_nonce
+    );
+    assert_ne!(root_hash.hash(), unblinded_root_hash.hash());
+    let result = lookup_verify::<TC>(
+        vrf_pk.as_bytes(),
+        root_hash.hash(),
+        root_hash.epoch(),
+        label.clone(),
+        lookup_proof,
+    )?;
+    assert_eq!(AkdValue::from("world2"), result.value);
+
+    let (history_proof, root_hash) = akd.key_history(&label, HistoryParams::default()).await?;
+    let results = key_history_verify::<TC>(
+        vrf_pk.as_bytes(),
+        root_hash.hash(),
+        root_hash.epoch(),
+        label.clone(),
+        history_proof,
+        HistoryVerificationParams::default(),
+    )?;
+    assert_eq!(2, results.len());
+
+    // The labels without a blinding are committed to as without any
+    let (lookup_proof, _) = akd.lookup(AkdLabel::from("other")).await?;
+    let (unblinded_proof, _) = unblinded.lookup(AkdLabel::from("other")).await?;
+    assert_eq!(
+        lookup_proof.commitment_nonce,
+        unblinded_proof.commitment_nonce
+    );
+
+    // Blindings must be for a label being updated, and within the length bounds
+    for blindings in [
+        HashMap::from([(AkdLabel::from("unknown"), vec![1u8; 32])]),
+        HashMap::from([(label.clone(), vec![])]),
+        HashMap::from([(
+            label.clone(),
+            vec![1u8; ValueState::MAX_BLINDING_LENGTH + 1],
+        )]),
+    ] {
+        let Err(AkdError::Directory(DirectoryError::Publish(_))) = akd
+            .publish_with_blindings(vec![(label.clone(), AkdValue::from("world3"))], blindings)
+            .await
+        else {
+            panic!("Expected a directory publish error");
+        };
+    }
+
+    // Once required, every label must have a blinding
+    let akd = akd.with_required_blinding(true);
+    let Err(AkdError::Directory(DirectoryError::Publish(_))) = akd
+        .publish(vec![(label.clone(), AkdValue::from("world3"))])
+        .await
+    else {
+        panic!("Expected a directory publish error");
+    };
+    akd.publish_with_blindings(
+        vec![(label.clone(), AkdValue::from("world3"))],
+        HashMap::from([(label, vec![1u8; 32])]),
+    )
+    .await?;
+
+    Ok(())
+}
+
+// Test inserting decoy leaves alongside the leaves of the updated labels
+test_config!(test_decoy_leaves);
+async fn test_decoy_leaves<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let vrf = HardCodedAkdVRF {};
+    let akd = Directory::<TC, _, _>::new(storage, vrf)
+        .await?
+        .with_decoy_leaves(5);
+    let vrf_pk = akd.get_public_key().await?;
+
+    let root_hash_0 = akd.get_epoch_hash().await?.1;
+    let updates = vec![
+        (AkdLabel::from("hello"), AkdValue::from("world")),
+        (AkdLabel::from("hello2"), AkdValue::from("world")),
+    ];
+    // The preview accounts for the decoys
+    let preview = akd.preview_publish(updates.clone()).await?;
+    assert_eq!(7, preview.inserted_leaves);
+    let root_hash_1 = akd.publish(updates).await?;
+    assert_eq!(Some(root_hash_1.clone()), preview.epoch_hash);
+    let root_hash_2 = akd
+        .publish(vec![(AkdLabel::from("hello"), AkdValue::from("world2"))])
+        .await?;
+
+    // No decoys are inserted without any updates
+    assert_eq!(
+        root_hash_2,
+        akd.publish(vec![(AkdLabel::from("hello"), AkdValue::from("world2"))])
+            .await?
+    );
+
+    // The audit proofs include the decoys (alongside the stale and fresh leaves of the
+    // updated label), and verify as usual
+    let audit_proof = akd.audit(1, 2).await?;
+    assert_eq!(2 + 5, audit_proof.proofs[0].inserted.len());
+    audit_verify::<TC>(
+        vec![root_hash_0, root_hash_1.hash(), root_hash_2.hash()],
+        akd.audit(0, 2).await?,
+    )
+    .await?;
+    audit_verify::<TC>(vec![root_hash_1.hash(), root_hash_2.hash()], audit_proof).await?;
+
+    // As do the lookup and history proofs of the real labels
+    let (lookup_proof, root_hash) = akd.lookup(AkdLabel::from("hello")).await?;
+    let result = lookup_verify::<TC>(
+        vrf_pk.as_bytes(),
+        root_hash.hash(),
+        root_hash.epoch(),
+        AkdLabel::from("hello"),
+        lookup_proof,
+    )?;
+    assert_eq!(AkdValue::from("world2"), result.value);
+    let (history_proof, root_hash) = akd
+        .key_history(&AkdLabel::from("hello"), HistoryParams::default())
+        .await?;
+    let results = key_history_verify::<TC>(
+        vrf_pk.as_bytes(),
+        root_hash.hash(),
+        root_hash.epoch(),
+        AkdLabel::from("hello"),
+        history_proof,
+        HistoryVerificationParams::default(),
+    )?;
+    assert_eq!(2, results.len());
+
+    Ok(())
+}
+
 // Test key history verification for error handling of malformed key history proofs
 test_config!(test_key_history_verify_malformed);
 async fn test_key_history_verify_malformed<TC: Configuration>() -> Result<(), AkdError> {
@@ -1339,20 +1963,18 @@ async fn test_key_history_verify_malformed<TC: Configuration>() -> Result<(), Ak
 
     let mut rng = rand::rngs::OsRng;
     for _ in 0..100 {
-        let mut updates = vec![];
-        updates.push((
-            AkdLabel(format!("label").as_bytes().to_vec()),
+        let updates = vec![(
+            AkdLabel("label".to_string().as_bytes().to_vec()),
             AkdValue::random(&mut rng),
-        ));
+        )];
         akd.publish(updates.clone()).await?;
     }
 
     for _ in 0..100 {
-        let mut updates = vec![];
-        updates.push((
-            AkdLabel(format!("another label").as_bytes().to_vec()),
+        let updates = vec![(
+            AkdLabel("another label".to_string().as_bytes().to_vec()),
             AkdValue::random(&mut rng),
-        ));
+        )];
         akd.publish(updates.clone()).await?;
     }
 
@@ -1360,7 +1982,7 @@ async fn test_key_history_verify_malformed<TC: Configuration>() -> Result<(), Ak
     let EpochHash(current_epoch, root_hash) = akd.get_epoch_hash().await?;
     // Get the VRF public key
     let vrf_pk = akd.get_public_key().await?;
-    let target_label = AkdLabel(format!("label").as_bytes().to_vec());
+    let target_label = AkdLabel("label".to_string().as_bytes().to_vec());
 
     let history_params_5 = HistoryParams::MostRecent(5);
 
@@ -1460,27 +2082,4031 @@ async fn test_key_history_verify_malformed<TC: Configuration>() -> Result<(), Ak
         .is_err());
     }
 
-    Ok(())
-}
+    // The failures are reported with structured errors
+    let verify = |proof: HistoryProof, history_params: HistoryParams, root_hash: Digest| {
+        key_history_verify::<TC>(
+            vrf_pk.as_bytes(),
+            root_hash,
+            current_epoch,
+            target_label.clone(),
+            proof,
+            HistoryVerificationParams::Default { history_params },
+        )
+        .unwrap_err()
+    };
 
-/*
-=========== Test Helpers ===========
-*/
+    let mut no_update_proofs = key_history_proof.clone();
+    no_update_proofs.update_proofs.clear();
+    assert_eq!(
+        VerificationError::MalformedProof(ProofShapeError::NoUpdateProofs),
+        verify(no_update_proofs, history_params_5, root_hash)
+    );
 
-async fn async_poll_helper_proof<TC: Configuration, T: Database + 'static, V: VRFKeyStorage>(
-    reader: &ReadOnlyDirectory<TC, T, V>,
-    value: AkdValue,
-) -> Result<(), AkdError> {
-    // reader should read "hello" and this will populate the "cache" a log
-    let (lookup_proof, root_hash) = reader.lookup(AkdLabel::from("hello")).await?;
-    assert_eq!(value, lookup_proof.value);
-    let pk = reader.get_public_key().await?;
-    lookup_verify::<TC>(
-        pk.as_bytes(),
-        root_hash.hash(),
-        root_hash.epoch(),
+    assert_eq!(
+        VerificationError::MalformedProof(ProofShapeError::TooManyUpdateProofs {
+            expected: 4,
+            actual: 5
+        }),
+        verify(
+            key_history_proof.clone(),
+            HistoryParams::MostRecent(4),
+            root_hash
+        )
+    );
+
+    let mut swapped_versions = key_history_proof.clone();
+    swapped_versions.update_proofs.swap(1, 2);
+    assert_eq!(
+        VerificationError::Ordering(OrderingViolation::NonConsecutiveVersions {
+            index: 1,
+            version: 98,
+            previous_version: 100,
+        }),
+        verify(swapped_versions, history_params_5, root_hash)
+    );
+
+    let mut missing_past_marker = key_history_proof.clone();
+    missing_past_marker.past_marker_vrf_proofs.pop();
+    assert!(matches!(
+        verify(missing_past_marker, history_params_5, root_hash),
+        VerificationError::MalformedProof(ProofShapeError::UnexpectedLength {
+            field: "past_marker_vrf_proofs",
+            ..
+        })
+    ));
+
+    let mut missing_previous_version = key_history_proof.clone();
+    missing_previous_version.update_proofs[0].previous_version_proof = None;
+    assert_eq!(
+        VerificationError::MalformedProof(ProofShapeError::MissingField("previous_version_proof")),
+        verify(missing_previous_version, history_params_5, root_hash)
+    );
+
+    let mut tampered_value = key_history_proof.clone();
+    tampered_value.update_proofs[0].value = AkdValue::random(&mut rng);
+    assert_eq!(
+        VerificationError::HashMismatch {
+            label: key_history_proof.update_proofs[0].existence_proof.label,
+            check: HashCheck::LeafValue,
+        },
+        verify(tampered_value, history_params_5, root_hash)
+    );
+
+    assert_eq!(
+        VerificationError::HashMismatch {
+            label: key_history_proof.update_proofs[0].existence_proof.label,
+            check: HashCheck::RootHash,
+        },
+        verify(key_history_proof.clone(), history_params_5, EMPTY_DIGEST)
+    );
+
+    Ok(())
+}
+
+// Test that epoch metadata is recorded at publish time and can be retrieved per epoch
+test_config!(test_epoch_metadata);
+async fn test_epoch_metadata<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let vrf = HardCodedAkdVRF {};
+    let akd = Directory::<TC, _, _>::new(storage, vrf).await?;
+
+    akd.publish(vec![
+        (AkdLabel::from("hello"), AkdValue::from("world")),
+        (AkdLabel::from("hello2"), AkdValue::from("world2")),
+    ])
+    .await?;
+    akd.publish_with_note(
+        vec![(AkdLabel::from("hello"), AkdValue::from("world3"))],
+        Some("key rotation".to_string()),
+    )
+    .await?;
+
+    let first = akd.get_epoch_metadata(1).await?;
+    assert_eq!(1, first.epoch);
+    assert_eq!(2, first.update_count);
+    assert_eq!(None, first.note);
+    assert!(!first.bound);
+
+    let second = akd.get_epoch_metadata(2).await?;
+    assert_eq!(2, second.epoch);
+    assert_eq!(1, second.update_count);
+    assert_eq!(Some("key rotation".to_string()), second.note);
+    assert!(second.timestamp_ms >= first.timestamp_ms);
+
+    // No metadata exists for an epoch which has not been published
+    assert!(matches!(
+        akd.get_epoch_metadata(3).await,
+        Err(AkdError::Storage(StorageError::NotFound(_)))
+    ));
+
+    // Notes exceeding the maximum length are rejected without publishing an epoch
+    let long_note = "a".repeat(EpochMetadata::MAX_NOTE_LENGTH + 1);
+    assert!(matches!(
+        akd.publish_with_note(
+            vec![(AkdLabel::from("hello"), AkdValue::from("world4"))],
+            Some(long_note),
+        )
+        .await,
+        Err(AkdError::Directory(DirectoryError::Publish(_)))
+    ));
+    assert_eq!(2, akd.get_epoch_hash().await?.epoch());
+
+    // Without binding enabled, there is no proof of the metadata in the tree
+    assert!(matches!(
+        akd.get_epoch_metadata_proof(1).await,
+        Err(AkdError::Directory(DirectoryError::InvalidEpoch(_)))
+    ));
+
+    Ok(())
+}
+
+// Test that epoch metadata bound into the root hash can be verified by clients,
+// and that binding does not interfere with lookups or audits
+test_config!(test_bound_epoch_metadata);
+async fn test_bound_epoch_metadata<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let vrf = HardCodedAkdVRF {};
+    let akd = Directory::<TC, _, _>::new(storage, vrf)
+        .await?
+        .with_epoch_metadata_binding(true);
+
+    let root1 = akd
+        .publish_with_note(
+            vec![(AkdLabel::from("hello"), AkdValue::from("world"))],
+            Some("first".to_string()),
+        )
+        .await?;
+    let root2 = akd
+        .publish(vec![(AkdLabel::from("hello2"), AkdValue::from("world2"))])
+        .await?;
+
+    for epoch in 1..=2 {
+        let (proof, root_hash) = akd.get_epoch_metadata_proof(epoch).await?;
+        assert_eq!(root2, root_hash);
+        assert_eq!(akd.get_epoch_metadata(epoch).await?, proof.metadata);
+        assert!(proof.metadata.bound);
+        crate::client::verify_epoch_metadata::<TC>(root_hash.hash(), &proof)?;
+
+        // Tampering with the metadata should cause verification to fail
+        let mut tampered = proof.clone();
+        tampered.metadata.timestamp_ms += 1;
+        assert!(crate::client::verify_epoch_metadata::<TC>(root_hash.hash(), &tampered).is_err());
+
+        // The commitment cannot be opened without the correct nonce
+        let mut tampered = proof.clone();
+        tampered.commitment_nonce[0] ^= 1;
+        assert!(crate::client::verify_epoch_metadata::<TC>(root_hash.hash(), &tampered).is_err());
+
+        // A proof for one epoch cannot be passed off as the metadata for another
+        let mut tampered = proof;
+        tampered.metadata.epoch = 3 - epoch;
+        assert!(crate::client::verify_epoch_metadata::<TC>(root_hash.hash(), &tampered).is_err());
+    }
+
+    let (lookup_proof, root_hash) = akd.lookup(AkdLabel::from("hello")).await?;
+    let vrf_pk = akd.get_public_key().await?;
+    lookup_verify::<TC>(
+        vrf_pk.as_bytes(),
+        root_hash.hash(),
+        root_hash.epoch(),
+        AkdLabel::from("hello"),
+        lookup_proof,
+    )?;
+
+    let audit_proof = akd.audit(1, 2).await?;
+    audit_verify::<TC>(vec![root1.hash(), root2.hash()], audit_proof).await?;
+
+    Ok(())
+}
+
+// Test that the root hash of each epoch is signed at publish time when a signer is configured
+test_config!(test_epoch_signatures);
+async fn test_epoch_signatures<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let vrf = HardCodedAkdVRF {};
+    let signer = HardCodedEpochSigner {};
+    let public_key = signer.get_public_key().await?;
+    let akd = Directory::<TC, _, _>::new(storage, vrf)
+        .await?
+        .with_epoch_signer(signer);
+
+    let mut roots = vec![];
+    roots.push(
+        akd.publish(vec![(AkdLabel::from("hello"), AkdValue::from("world"))])
+            .await?,
+    );
+    roots.push(
+        akd.publish(vec![(AkdLabel::from("hello2"), AkdValue::from("world2"))])
+            .await?,
+    );
+
+    for root in roots.iter() {
+        let signature = akd
+            .get_epoch_metadata(root.epoch())
+            .await?
+            .signature
+            .expect("Signature should be recorded for the epoch");
+        crate::client::verify_epoch_signature(&public_key, root.epoch(), root.hash(), &signature)?;
+
+        // The signature is bound to both the epoch and the root hash
+        assert!(crate::client::verify_epoch_signature(
+            &public_key,
+            root.epoch() + 1,
+            root.hash(),
+            &signature
+        )
+        .is_err());
+        let mut tampered = signature.clone();
+        tampered[0] ^= 1;
+        assert!(crate::client::verify_epoch_signature(
+            &public_key,
+            root.epoch(),
+            root.hash(),
+            &tampered
+        )
+        .is_err());
+    }
+
+    // Without a signer, no signature is recorded
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let akd = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {}).await?;
+    akd.publish(vec![(AkdLabel::from("hello"), AkdValue::from("world"))])
+        .await?;
+    assert_eq!(None, akd.get_epoch_metadata(1).await?.signature);
+
+    Ok(())
+}
+
+// Test that a lookup response is bound to the challenge supplied by the client
+test_config!(test_lookup_with_challenge);
+async fn test_lookup_with_challenge<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db.clone());
+    let vrf = HardCodedAkdVRF {};
+    let signer = HardCodedEpochSigner {};
+    let public_key = signer.get_public_key().await?;
+    let akd = Directory::<TC, _, _>::new(storage, vrf.clone())
+        .await?
+        .with_epoch_signer(signer.clone());
+    let label = AkdLabel::from("hello");
+    akd.publish(vec![(label.clone(), AkdValue::from("world"))])
+        .await?;
+
+    let (proof, root, signature) = akd.lookup_with_challenge(label.clone(), b"nonce1").await?;
+    lookup_verify::<TC>(
+        akd.get_public_key().await?.as_bytes(),
+        root.hash(),
+        root.epoch(),
+        label.clone(),
+        proof,
+    )?;
+    verify_lookup_challenge(
+        &public_key,
+        b"nonce1",
+        &label,
+        root.epoch(),
+        root.hash(),
+        &signature,
+    )?;
+
+    // A response replayed for another challenge, or for another label, is rejected
+    assert!(matches!(
+        verify_lookup_challenge(
+            &public_key,
+            b"nonce2",
+            &label,
+            root.epoch(),
+            root.hash(),
+            &signature
+        ),
+        Err(VerificationError::LookupProof(_))
+    ));
+    assert!(verify_lookup_challenge(
+        &public_key,
+        b"nonce1",
+        &AkdLabel::from("hello2"),
+        root.epoch(),
+        root.hash(),
+        &signature
+    )
+    .is_err());
+    let (_, _, other) = akd.lookup_with_challenge(label.clone(), b"nonce2").await?;
+    assert_ne!(signature, other);
+
+    // A read-only directory signs with the signer of the writer
+    let read_only =
+        ReadOnlyDirectory::<TC, _, _>::new(StorageManager::new_no_cache(db), vrf.clone())
+            .await?
+            .with_epoch_signer(signer);
+    let (_, read_only_root, read_only_signature) = read_only
+        .lookup_with_challenge(label.clone(), b"nonce3")
+        .await?;
+    assert_eq!(root, read_only_root);
+    verify_lookup_challenge(
+        &public_key,
+        b"nonce3",
+        &label,
+        root.epoch(),
+        root.hash(),
+        &read_only_signature,
+    )?;
+
+    // Without a signer, the challenge cannot be signed
+    let storage = StorageManager::new_no_cache(AsyncInMemoryDatabase::new());
+    let akd = Directory::<TC, _, _>::new(storage, vrf).await?;
+    akd.publish(vec![(label.clone(), AkdValue::from("world"))])
+        .await?;
+    assert!(matches!(
+        akd.lookup_with_challenge(label, b"nonce1").await,
+        Err(AkdError::Signature(_))
+    ));
+
+    Ok(())
+}
+
+// Test that enqueued updates are published by the publisher on reaching the batch
+// size threshold, and on the interval elapsing
+test_config!(test_publisher);
+async fn test_publisher<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let vrf = HardCodedAkdVRF {};
+    let akd = Directory::<TC, _, _>::new(storage, vrf).await?;
+
+    let (pre_tx, mut pre_rx) = tokio::sync::mpsc::unbounded_channel();
+    let (post_tx, mut post_rx) = tokio::sync::mpsc::unbounded_channel();
+    let handle = Publisher::new(akd.clone(), Duration::from_secs(3600))
+        .with_batch_size_threshold(2)
+        .with_pre_publish_hook(move |count| pre_tx.send(count).unwrap())
+        .with_post_publish_hook(move |result| {
+            post_tx
+                .send(result.as_ref().map(|epoch_hash| epoch_hash.epoch()).ok())
+                .unwrap()
+        })
+        .spawn();
+
+    // Superseded values for the same label only count once
+    akd.enqueue_update(AkdLabel::from("hello"), AkdValue::from("world"))
+        .await;
+    akd.enqueue_update(AkdLabel::from("hello"), AkdValue::from("world2"))
+        .await;
+    assert_eq!(1, akd.pending_update_count().await);
+    akd.enqueue_update(AkdLabel::from("hello2"), AkdValue::from("world"))
+        .await;
+
+    assert_eq!(Some(2), pre_rx.recv().await);
+    assert_eq!(Some(Some(1)), post_rx.recv().await);
+    assert_eq!(0, akd.pending_update_count().await);
+    let (lookup_proof, _) = akd.lookup(AkdLabel::from("hello")).await?;
+    assert_eq!(AkdValue::from("world2"), lookup_proof.value);
+    handle.stop().await;
+
+    // With no threshold, updates are published once the interval elapses
+    let (post_tx, mut post_rx) = tokio::sync::mpsc::unbounded_channel();
+    let handle = Publisher::new(akd.clone(), Duration::from_millis(50))
+        .with_post_publish_hook(move |result| {
+            post_tx
+                .send(result.as_ref().map(|epoch_hash| epoch_hash.epoch()).ok())
+                .unwrap()
+        })
+        .spawn();
+    akd.enqueue_update(AkdLabel::from("hello3"), AkdValue::from("world"))
+        .await;
+    assert_eq!(Some(Some(2)), post_rx.recv().await);
+    handle.stop().await;
+
+    // Once stopped, updates remain pending until published explicitly
+    akd.enqueue_update(AkdLabel::from("hello4"), AkdValue::from("world"))
+        .await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(1, akd.pending_update_count().await);
+    assert_eq!(3, akd.publish_pending().await?.unwrap().epoch());
+    assert_eq!(None, akd.publish_pending().await?);
+
+    Ok(())
+}
+
+// Test that only the holder of the publish lease can publish when multiple
+// directories share the same storage
+test_config!(test_publish_lease);
+async fn test_publish_lease<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db.clone());
+    let vrf = HardCodedAkdVRF {};
+    let akd = Directory::<TC, _, _>::new(storage, vrf)
+        .await?
+        .with_publish_lease(b"writer".to_vec(), Duration::from_secs(60));
+
+    akd.publish(vec![(AkdLabel::from("hello"), AkdValue::from("world"))])
+        .await?;
+
+    // While another instance holds the lease, publishing is refused
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64;
+    let other = db
+        .acquire_publish_lease(b"other", now_ms, 60_000)
+        .await?
+        .expect("The lease should have been released after publishing");
+    assert!(matches!(
+        akd.publish(vec![(AkdLabel::from("hello2"), AkdValue::from("world2"))])
+            .await,
+        Err(AkdError::Directory(DirectoryError::Publish(_)))
+    ));
+    assert_eq!(1, akd.get_epoch_hash().await?.epoch());
+    // As are rollbacks
+    assert!(matches!(
+        akd.rollback_last_epoch().await,
+        Err(AkdError::Directory(DirectoryError::Publish(_)))
+    ));
+    assert_eq!(1, akd.get_epoch_hash().await?.epoch());
+
+    db.release_publish_lease(&other).await?;
+    akd.publish(vec![(AkdLabel::from("hello2"), AkdValue::from("world2"))])
+        .await?;
+    assert_eq!(2, akd.get_epoch_hash().await?.epoch());
+
+    Ok(())
+}
+
+// Test that a read-only directory holding only the VRF public key can serve audits from
+// shared storage, along with the lookups and key histories whose VRF proofs were precomputed
+// by the writer, but not the proofs which require any other evaluation of the VRF
+test_config!(test_read_only_directory_with_public_key);
+async fn test_read_only_directory_with_public_key<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db.clone());
+    let vrf = HardCodedAkdVRF {};
+    let akd = Directory::<TC, _, _>::new(storage, vrf)
+        .await?
+        .with_precomputed_vrf_proofs();
+    let mut roots = vec![
+        akd.bulk_import(futures::stream::iter(vec![
+            (AkdLabel::from("hello"), AkdValue::from("world")),
+            (AkdLabel::from("hello2"), AkdValue::from("world")),
+        ]))
+        .await?,
+    ];
+    // Cross several powers of two, with a label which is created in a later epoch, and a
+    // value which is committed to with a blinding
+    for epoch in 2..=9u64 {
+        let mut updates = vec![(
+            AkdLabel::from("hello"),
+            AkdValue::from(format!("world{epoch}").as_str()),
+        )];
+        if epoch == 5 {
+            updates.push((AkdLabel::from("hello3"), AkdValue::from("world")));
+        }
+        let blindings = HashMap::from([(AkdLabel::from("hello"), vec![epoch as u8; 16])]);
+        roots.push(akd.publish_with_blindings(updates, blindings).await?);
+    }
+    assert!(matches!(
+        akd.rotate_vrf_key(HardCodedAkdVRF {}).await,
+        Err(AkdError::Directory(DirectoryError::KeyRotation(_)))
+    ));
+
+    let vrf_pk = akd.get_public_key().await?;
+    let reader =
+        Directory::<TC, _, _>::new_read_only(StorageManager::new_no_cache(db), vrf_pk.clone())
+            .await?;
+    assert_eq!(vrf_pk, reader.get_public_key().await?);
+    let root_hash = reader.get_epoch_hash().await?;
+    assert_eq!(roots[8], root_hash);
+
+    let audit_proof = reader.audit(1, 9).await?;
+    audit_verify::<TC>(roots.iter().map(|root| root.hash()).collect(), audit_proof).await?;
+
+    for (label, version) in [("hello", 9), ("hello2", 1), ("hello3", 1)] {
+        let label = AkdLabel::from(label);
+        let (lookup_proof, lookup_root) = reader.lookup(label.clone()).await?;
+        assert_eq!(root_hash, lookup_root);
+        assert_eq!(
+            (lookup_proof.clone(), lookup_root),
+            akd.lookup(label.clone()).await?
+        );
+        let result = lookup_verify::<TC>(
+            vrf_pk.as_bytes(),
+            root_hash.hash(),
+            root_hash.epoch(),
+            label.clone(),
+            lookup_proof,
+        )?;
+        assert_eq!(version, result.version);
+
+        let (history_proof, history_root) =
+            reader.key_history(&label, HistoryParams::Complete).await?;
+        assert_eq!(root_hash, history_root);
+        assert_eq!(
+            (history_proof.clone(), history_root),
+            akd.key_history(&label, HistoryParams::Complete).await?
+        );
+        let results = key_history_verify::<TC>(
+            vrf_pk.as_bytes(),
+            root_hash.hash(),
+            root_hash.epoch(),
+            label,
+            history_proof,
+            HistoryVerificationParams::default(),
+        )?;
+        assert_eq!(version as usize, results.len());
+        assert_eq!(version, results[0].version);
+    }
+
+    // The absence of a label requires a VRF evaluation which was not precomputed
+    assert!(matches!(
+        reader.lookup_absent(AkdLabel::from("hello4")).await,
+        Err(AkdError::Vrf(_))
+    ));
+
+    Ok(())
+}
+
+// Test that the VRF proofs precomputed for a publish are only written along with its epoch,
+// including the marker proofs of every label at a power of two epoch
+test_config!(test_precomputed_vrf_proofs_of_failed_publish);
+async fn test_precomputed_vrf_proofs_of_failed_publish<TC: Configuration>() -> Result<(), AkdError>
+{
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db.clone());
+    let akd = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {})
+        .await?
+        .with_precomputed_vrf_proofs();
+    for epoch in 1..=3 {
+        akd.publish(vec![(
+            AkdLabel::from("hello"),
+            AkdValue::from(format!("world{epoch}").as_str()),
+        )])
+        .await?;
+    }
+
+    // The signing of the root hash of epoch 4 fails, after the proofs were computed
+    let failing =
+        Directory::<TC, _, _>::new(StorageManager::new_no_cache(db.clone()), HardCodedAkdVRF {})
+            .await?
+            .with_precomputed_vrf_proofs()
+            .with_epoch_signer(MalformedEpochSigner);
+    assert!(failing
+        .publish(vec![(AkdLabel::from("hello2"), AkdValue::from("world"))])
+        .await
+        .is_err());
+    let marker_key = VrfProofKey(AkdLabel::from("hello"), VersionFreshness::Fresh, 4);
+    let published_key = VrfProofKey(AkdLabel::from("hello2"), VersionFreshness::Fresh, 1);
+    for key in [&marker_key, &published_key] {
+        assert!(matches!(
+            db.get::<PrecomputedVrfProof>(key).await,
+            Err(StorageError::NotFound(_))
+        ));
+    }
+
+    akd.publish(vec![(AkdLabel::from("hello2"), AkdValue::from("world"))])
+        .await?;
+    for key in [&marker_key, &published_key] {
+        assert!(matches!(
+            db.get::<PrecomputedVrfProof>(key).await,
+            Ok(DbRecord::PrecomputedVrfProof(_))
+        ));
+    }
+
+    Ok(())
+}
+
+// Test that previewing a publish predicts the resulting root hash without writing to storage
+test_config!(test_preview_publish);
+async fn test_preview_publish<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let vrf = HardCodedAkdVRF {};
+    let akd = Directory::<TC, _, _>::new(storage, vrf).await?;
+    let root1 = akd
+        .publish(vec![
+            (AkdLabel::from("hello"), AkdValue::from("world")),
+            (AkdLabel::from("hello2"), AkdValue::from("world")),
+        ])
+        .await?;
+
+    let updates = vec![
+        (AkdLabel::from("hello"), AkdValue::from("world2")),
+        (AkdLabel::from("hello2"), AkdValue::from("world")),
+        (AkdLabel::from("hello3"), AkdValue::from("world")),
+    ];
+    let preview = akd.preview_publish(updates.clone()).await?;
+    assert!(preview.duplicate_labels.is_empty());
+    assert_eq!(1, preview.new_labels);
+    assert_eq!(1, preview.updated_labels);
+    assert_eq!(1, preview.unchanged_labels);
+    // A stale and fresh leaf for the updated label, and a fresh leaf for the new label
+    assert_eq!(3, preview.inserted_leaves);
+
+    // Nothing was written by the preview
+    assert_eq!(root1, akd.get_epoch_hash().await?);
+    assert!(akd.lookup(AkdLabel::from("hello3")).await.is_err());
+
+    let root2 = akd.publish(updates).await?;
+    assert_eq!(Some(root2), preview.epoch_hash);
+    assert_eq!(akd.retrieve_azks().await?.num_nodes, preview.num_nodes);
+
+    // Duplicate labels are reported rather than rejected
+    let preview = akd
+        .preview_publish(vec![
+            (AkdLabel::from("hello4"), AkdValue::from("world")),
+            (AkdLabel::from("hello4"), AkdValue::from("world2")),
+        ])
+        .await?;
+    assert_eq!(vec![AkdLabel::from("hello4")], preview.duplicate_labels);
+    assert_eq!(None, preview.epoch_hash);
+
+    Ok(())
+}
+
+// Test that proofs which are generated concurrently with previews of a publish are served from
+// the published epoch, and that the precomputed proofs are not discarded with the preview
+test_config!(test_lookup_during_preview_publish);
+async fn test_lookup_during_preview_publish<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(YieldingDatabase(db.clone()));
+    let akd = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {}).await?;
+    let label = AkdLabel::from("hello");
+    let epoch_hash = akd
+        .publish(vec![(label.clone(), AkdValue::from("world"))])
+        .await?;
+    let vrf_pk = akd.get_public_key().await?;
+
+    let updates = (0..20)
+        .map(|i| {
+            (
+                AkdLabel(format!("user{i}").into_bytes()),
+                AkdValue::from("previewed"),
+            )
+        })
+        .chain(std::iter::once((
+            label.clone(),
+            AkdValue::from("previewed"),
+        )))
+        .collect::<Vec<_>>();
+    let previews = (0..2)
+        .map(|_| {
+            let akd = akd.clone();
+            let updates = updates.clone();
+            tokio::spawn(async move { akd.preview_publish(updates).await })
+        })
+        .collect::<Vec<_>>();
+    for _ in 0..10 {
+        assert_eq!(1, akd.precompute_lookup_proofs().await?);
+        assert!(matches!(
+            db.get::<PrecomputedLookupProof>(&label).await,
+            Ok(DbRecord::PrecomputedLookupProof(_))
+        ));
+        db.batch_delete::<PrecomputedLookupProof>(std::slice::from_ref(&label))
+            .await?;
+
+        let (lookup_proof, served_epoch_hash) = akd.lookup(label.clone()).await?;
+        assert_eq!(epoch_hash, served_epoch_hash);
+        assert_eq!(AkdValue::from("world"), lookup_proof.value);
+        lookup_verify::<TC>(
+            vrf_pk.as_bytes(),
+            epoch_hash.hash(),
+            epoch_hash.epoch(),
+            label.clone(),
+            lookup_proof,
+        )?;
+    }
+    for preview in previews {
+        let preview = preview.await.expect("The preview task panicked")?;
+        assert_eq!(
+            Some(2),
+            preview.epoch_hash.map(|epoch_hash| epoch_hash.epoch())
+        );
+    }
+    assert_eq!(epoch_hash, akd.get_epoch_hash().await?);
+
+    Ok(())
+}
+
+// Test that rolling back the last epoch restores the tree, value states and metadata of the
+// previous epoch
+test_config!(test_rollback_last_epoch);
+async fn test_rollback_last_epoch<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db.clone());
+    let vrf = HardCodedAkdVRF {};
+    let akd = Directory::<TC, _, _>::new(storage, vrf)
+        .await?
+        .with_update_journal(UpdateJournalMode::Labels)
+        .with_root_history();
+    let root1 = akd
+        .publish(vec![
+            (AkdLabel::from("hello"), AkdValue::from("world")),
+            (AkdLabel::from("hello2"), AkdValue::from("world")),
+        ])
+        .await?;
+    let root2 = akd
+        .publish(vec![(AkdLabel::from("hello"), AkdValue::from("world2"))])
+        .await?;
+    akd.publish(vec![
+        (AkdLabel::from("hello2"), AkdValue::from("world3")),
+        (AkdLabel::from("hello3"), AkdValue::from("world3")),
+    ])
+    .await?;
+
+    let record = akd.rollback_last_epoch().await?;
+    assert_eq!((3, 2), (record.from_epoch, record.to_epoch));
+    assert_eq!(2, record.removed_value_states);
+    assert_eq!(vec![record], akd.get_rollback_records().await?);
+
+    assert_eq!(root2, akd.get_epoch_hash().await?);
+    assert!(akd.lookup(AkdLabel::from("hello3")).await.is_err());
+    let (lookup_proof, root_hash) = akd.lookup(AkdLabel::from("hello2")).await?;
+    assert_eq!(AkdValue::from("world"), lookup_proof.value);
+    lookup_verify::<TC>(
+        akd.get_public_key().await?.as_bytes(),
+        root_hash.hash(),
+        root_hash.epoch(),
+        AkdLabel::from("hello2"),
+        lookup_proof,
+    )?;
+    assert!(matches!(
+        akd.get_epoch_metadata(3).await,
+        Err(AkdError::Storage(StorageError::NotFound(_)))
+    ));
+    assert!(matches!(
+        db.get::<UpdateJournal>(&3).await,
+        Err(StorageError::NotFound(_))
+    ));
+    assert!(matches!(
+        db.get::<EpochRootRecord>(&3).await,
+        Err(StorageError::NotFound(_))
+    ));
+    assert_eq!(2, akd.get_update_journal(2).await?.epoch);
+    let audit_proof = akd.audit(1, 2).await?;
+    audit_verify::<TC>(vec![root1.hash(), root2.hash()], audit_proof).await?;
+
+    // Publishing continues from the restored epoch
+    let root3 = akd
+        .publish(vec![(AkdLabel::from("hello3"), AkdValue::from("world"))])
+        .await?;
+    assert_eq!(3, root3.epoch());
+    let audit_proof = akd.audit(2, 3).await?;
+    audit_verify::<TC>(vec![root2.hash(), root3.hash()], audit_proof).await?;
+
+    // The restored nodes no longer have a previous state, so the epoch to which the directory
+    // was rolled back cannot be rolled back itself, but the epochs published since can
+    let record = akd.rollback_last_epoch().await?;
+    assert_eq!((3, 2), (record.from_epoch, record.to_epoch));
+    assert!(matches!(
+        akd.rollback_last_epoch().await,
+        Err(AkdError::Directory(DirectoryError::InvalidEpoch(_)))
+    ));
+    assert_eq!(root2, akd.get_epoch_hash().await?);
+    assert_eq!(2, akd.get_rollback_records().await?.len());
+    let (lookup_proof, _) = akd.lookup(AkdLabel::from("hello")).await?;
+    assert_eq!(AkdValue::from("world2"), lookup_proof.value);
+
+    // Nor can a directory without any published epoch be rolled back
+    let storage = StorageManager::new_no_cache(AsyncInMemoryDatabase::new());
+    let empty = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {}).await?;
+    assert!(matches!(
+        empty.rollback_last_epoch().await,
+        Err(AkdError::Directory(DirectoryError::InvalidEpoch(_)))
+    ));
+
+    Ok(())
+}
+
+// Test that a rollback whose commit is rejected leaves the directory and all of the records of
+// its latest epoch in place, and can then be completed
+test_config!(test_rollback_last_epoch_is_atomic);
+async fn test_rollback_last_epoch_is_atomic<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db.clone());
+    let akd = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {})
+        .await?
+        .with_update_journal(UpdateJournalMode::Labels)
+        .with_root_history();
+    akd.publish(vec![(AkdLabel::from("hello"), AkdValue::from("world"))])
+        .await?;
+    let root2 = akd
+        .publish(vec![
+            (AkdLabel::from("hello"), AkdValue::from("world2")),
+            (AkdLabel::from("hello2"), AkdValue::from("world2")),
+        ])
+        .await?;
+    akd.precompute_lookup_proofs().await?;
+    let nodes = db
+        .batch_get_type_direct::<TreeNodeWithPreviousValue>()
+        .await?;
+
+    let storage = StorageManager::new_no_cache(RejectingDeletionsDatabase(db.clone()));
+    let rejected = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {}).await?;
+    assert!(matches!(
+        rejected.rollback_last_epoch().await,
+        Err(AkdError::Storage(StorageError::Transaction(_)))
+    ));
+
+    assert_eq!(root2, akd.get_epoch_hash().await?);
+    assert!(akd.get_rollback_records().await?.is_empty());
+    assert_eq!(
+        nodes,
+        db.batch_get_type_direct::<TreeNodeWithPreviousValue>()
+            .await?
+    );
+    assert_eq!(2, akd.get_epoch_metadata(2).await?.epoch);
+    assert_eq!(2, akd.get_update_journal(2).await?.epoch);
+    assert!(matches!(
+        db.get::<EpochRootRecord>(&2).await,
+        Ok(DbRecord::EpochRootRecord(_))
+    ));
+    assert!(matches!(
+        db.get::<PrecomputedLookupProof>(&AkdLabel::from("hello2"))
+            .await,
+        Ok(DbRecord::PrecomputedLookupProof(_))
+    ));
+    let (lookup_proof, _) = akd.lookup(AkdLabel::from("hello2")).await?;
+    assert_eq!(AkdValue::from("world2"), lookup_proof.value);
+
+    let record = akd.rollback_last_epoch().await?;
+    assert_eq!(
+        (2, 1, 2),
+        (
+            record.from_epoch,
+            record.to_epoch,
+            record.removed_value_states
+        )
+    );
+    assert_eq!(vec![record], akd.get_rollback_records().await?);
+    assert!(akd.lookup(AkdLabel::from("hello2")).await.is_err());
+    assert!(matches!(
+        db.get::<PrecomputedLookupProof>(&AkdLabel::from("hello2"))
+            .await,
+        Err(StorageError::NotFound(_))
+    ));
+
+    Ok(())
+}
+
+// Test that a retention policy prunes the node states and epoch metadata it no longer retains
+test_config!(test_retention_policy);
+async fn test_retention_policy<TC: Configuration>() -> Result<(), AkdError> {
+    const HOUR_MS: u64 = 60 * 60 * 1000;
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new(db.clone(), None, None, None);
+    let vrf = HardCodedAkdVRF {};
+    let clock = ManualClock::new(0);
+    let akd = Directory::<TC, _, _>::new(storage, vrf)
+        .await?
+        .with_clock(clock.clone())
+        .with_retention_policy(
+            RetentionPolicy::new()
+                .with_kept_epochs(2)
+                .with_audit_retention(Duration::from_millis(24 * HOUR_MS)),
+        );
+
+    let mut roots = vec![];
+    for (hours, value) in [(0, "world"), (3, "world2"), (12, "world3"), (26, "world4")] {
+        clock.set(hours * HOUR_MS);
+        let mut updates = vec![(AkdLabel::from("hello"), AkdValue::from(value))];
+        if hours == 0 {
+            updates.push((AkdLabel::from("hello2"), AkdValue::from(value)));
+        }
+        roots.push(akd.publish(updates).await?);
+    }
+
+    // Only the nodes updated in the latest epoch still have a previous state
+    let nodes = db
+        .batch_get_type_direct::<TreeNodeWithPreviousValue>()
+        .await?;
+    assert!(nodes.iter().any(|record| matches!(
+        record,
+        DbRecord::TreeNode(node) if node.previous_node.is_some()
+    )));
+    for record in nodes {
+        if let DbRecord::TreeNode(node) = record {
+            assert!(node.previous_node.is_none() || node.latest_node.last_epoch == 4);
+        }
+    }
+
+    // The first epoch was published more than a day ago
+    assert!(matches!(
+        akd.get_epoch_metadata(1).await,
+        Err(AkdError::Storage(StorageError::NotFound(_)))
+    ));
+    assert!(matches!(
+        akd.audit(1, 4).await,
+        Err(AkdError::Directory(DirectoryError::InvalidEpoch(_)))
+    ));
+    let audit_proof = akd.audit(2, 4).await?;
+    audit_verify::<TC>(
+        roots[1..].iter().map(|root| root.hash()).collect(),
+        audit_proof,
+    )
+    .await?;
+
+    // Audits expire with time, even before the metadata of their epoch is pruned
+    clock.set(28 * HOUR_MS);
+    assert!(akd.audit(2, 4).await.is_err());
+    assert_eq!(2, akd.get_epoch_metadata(2).await?.epoch);
+    assert_eq!(
+        RetentionReport {
+            pruned_node_states: 0,
+            pruned_epoch_metadata: 1,
+            pruned_value_states: 0,
+        },
+        akd.enforce_retention().await?
+    );
+
+    // The directory can still be rolled back to the previous epoch
+    akd.rollback_last_epoch().await?;
+    assert_eq!(roots[2], akd.get_epoch_hash().await?);
+
+    Ok(())
+}
+
+// Test that the history of a label since a previously verified version verifies against it
+test_config!(test_key_history_since);
+async fn test_key_history_since<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db.clone());
+    let akd = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {}).await?;
+    let vrf_pk = akd.get_public_key().await?;
+
+    let label = AkdLabel::from("hello");
+    for i in 1..=3 {
+        akd.publish(vec![(
+            label.clone(),
+            AkdValue::from(format!("world{i}").as_str()),
+        )])
+        .await?;
+    }
+    let (proof, EpochHash(epoch, root_hash)) =
+        akd.key_history(&label, HistoryParams::Complete).await?;
+    let results = key_history_verify::<TC>(
+        vrf_pk.as_bytes(),
+        root_hash,
+        epoch,
+        label.clone(),
+        proof,
+        HistoryVerificationParams::default(),
+    )?;
+    let previous = results[0].clone();
+
+    // Without any newer version, only the previously verified version is proven to be the latest
+    let (proof, EpochHash(epoch, root_hash)) = akd
+        .key_history_since(&label, previous.version, previous.epoch)
+        .await?;
+    assert_eq!(1, proof.update_proofs.len());
+    assert!(key_history_since_verify::<TC>(
+        vrf_pk.as_bytes(),
+        root_hash,
+        epoch,
+        label.clone(),
+        proof,
+        &previous,
+    )?
+    .is_empty());
+
+    for i in 4..=6 {
+        akd.publish(vec![
+            (label.clone(), AkdValue::from(format!("world{i}").as_str())),
+            (
+                AkdLabel::from(format!("other{i}").as_str()),
+                AkdValue::from("value"),
+            ),
+        ])
+        .await?;
+    }
+    let (proof, EpochHash(epoch, root_hash)) = akd
+        .key_history_since(&label, previous.version, previous.epoch)
+        .await?;
+    let results = key_history_since_verify::<TC>(
+        vrf_pk.as_bytes(),
+        root_hash,
+        epoch,
+        label.clone(),
+        proof.clone(),
+        &previous,
+    )?;
+    assert_eq!(
+        vec![6, 5, 4],
+        results
+            .iter()
+            .map(|result| result.version)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(AkdValue::from("world6"), results[0].value);
+
+    // The proof does not verify against a different previous result
+    for tampered in [
+        VerifyResult {
+            value: AkdValue::from("world2"),
+            ..previous.clone()
+        },
+        VerifyResult {
+            epoch: previous.epoch - 1,
+            ..previous.clone()
+        },
+        VerifyResult {
+            version: previous.version - 1,
+            ..previous.clone()
+        },
+    ] {
+        assert!(matches!(
+            key_history_since_verify::<TC>(
+                vrf_pk.as_bytes(),
+                root_hash,
+                epoch,
+                label.clone(),
+                proof.clone(),
+                &tampered,
+            ),
+            Err(VerificationError::HistoryProof(_))
+        ));
+    }
+
+    // A version which was not published at the given epoch is rejected by the directory
+    assert!(matches!(
+        akd.key_history_since(&label, previous.version, previous.epoch + 1)
+            .await,
+        Err(AkdError::Directory(DirectoryError::InvalidVersion(_)))
+    ));
+
+    // As is a version stored for an epoch which is not published yet
+    let mut unpublished = db
+        .get_user_state(&label, ValueStateRetrievalFlag::MaxEpoch)
+        .await?;
+    unpublished.version += 1;
+    unpublished.epoch = epoch + 1;
+    db.set(DbRecord::ValueState(unpublished.clone())).await?;
+    assert!(matches!(
+        akd.key_history_since(&label, unpublished.version, unpublished.epoch)
+            .await,
+        Err(AkdError::Directory(DirectoryError::InvalidVersion(_)))
+    ));
+
+    Ok(())
+}
+
+// Test that a retention policy bounding the versions of each label truncates their history
+// proofs, which verify as such
+test_config!(test_retention_max_versions_per_label);
+async fn test_retention_max_versions_per_label<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new(db.clone(), None, None, None);
+    let akd = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {})
+        .await?
+        .with_retention_policy(RetentionPolicy::new().with_max_versions_per_label(3));
+    let vrf_pk = akd.get_public_key().await?;
+
+    let label = AkdLabel::from("hello");
+    for i in 1..=5 {
+        let mut updates = vec![(label.clone(), AkdValue::from(format!("world{i}").as_str()))];
+        if i == 1 {
+            updates.push((AkdLabel::from("hello2"), AkdValue::from("world")));
+        }
+        akd.publish(updates).await?;
+    }
+
+    // Only the value states of the 3 most recent versions are retained
+    assert_eq!(3, db.get_user_data(&label).await?.states.len());
+    assert_eq!(
+        1,
+        db.get_user_data(&AkdLabel::from("hello2"))
+            .await?
+            .states
+            .len()
+    );
+    assert_eq!(RetentionReport::default(), akd.enforce_retention().await?);
+
+    // A complete history is truncated to the retained versions, which does not verify as a
+    // complete history
+    let params = akd.history_params(HistoryParams::Complete);
+    assert!(matches!(params, HistoryParams::MostRecent(3)));
+    let (proof, EpochHash(epoch, root_hash)) =
+        akd.key_history(&label, HistoryParams::Complete).await?;
+    assert_eq!(
+        vec![5, 4, 3],
+        proof
+            .update_proofs
+            .iter()
+            .map(|update_proof| update_proof.version)
+            .collect::<Vec<_>>()
+    );
+    assert!(matches!(
+        key_history_verify::<TC>(
+            vrf_pk.as_bytes(),
+            root_hash,
+            epoch,
+            label.clone(),
+            proof.clone(),
+            HistoryVerificationParams::default(),
+        ),
+        Err(VerificationError::MalformedProof(
+            ProofShapeError::IncompleteHistory { start_version: 3 }
+        ))
+    ));
+
+    // The truncated history verifies with the parameters of the directory, and is reported as
+    // such along with the previous versions it proves to exist
+    let (results, metadata) = akd_core::verify::history::key_history_verify_with_metadata::<TC>(
+        vrf_pk.as_bytes(),
+        root_hash,
+        epoch,
+        label.clone(),
+        proof,
+        HistoryVerificationParams::Default {
+            history_params: params,
+        },
+    )?;
+    assert_eq!(AkdValue::from("world5"), results[0].value);
+    assert!(!metadata.complete);
+    assert_eq!(vec![2], metadata.past_marker_versions);
+
+    // Fewer versions than retained must still be a complete history
+    let (proof, _) = akd
+        .key_history(&AkdLabel::from("hello2"), HistoryParams::Complete)
+        .await?;
+    key_history_verify::<TC>(
+        vrf_pk.as_bytes(),
+        root_hash,
+        epoch,
+        AkdLabel::from("hello2"),
+        proof,
+        HistoryVerificationParams::Default {
+            history_params: params,
+        },
+    )?;
+    assert!(matches!(
+        akd.history_params(HistoryParams::MostRecent(2)),
+        HistoryParams::MostRecent(2)
+    ));
+
+    // The truncated history cannot be replayed into a fork
+    assert!(matches!(
+        akd.fork_at(
+            epoch,
+            StorageManager::new_no_cache(AsyncInMemoryDatabase::new())
+        )
+        .await,
+        Err(AkdError::Directory(DirectoryError::InvalidVersion(_)))
+    ));
+
+    Ok(())
+}
+
+// Test that forking a directory replays its history up to the fork epoch into new storage
+test_config!(test_fork_at);
+async fn test_fork_at<TC: Configuration>() -> Result<(), AkdError> {
+    let vrf = InMemoryKeyVrf::new(vec![1u8; 32]);
+    let akd = Directory::<TC, _, _>::new(
+        StorageManager::new_no_cache(AsyncInMemoryDatabase::new()),
+        vrf.clone(),
+    )
+    .await?
+    .with_decoy_leaves(2);
+    let root1 = akd
+        .publish_with_note(
+            vec![
+                (AkdLabel::from("hello"), AkdValue::from("world")),
+                (AkdLabel::from("hello2"), AkdValue::from("world")),
+            ],
+            Some("first".to_string()),
+        )
+        .await?;
+    let root2 = akd
+        .publish_with_blindings(
+            vec![(AkdLabel::from("hello"), AkdValue::from("world2"))],
+            HashMap::from([(AkdLabel::from("hello"), vec![7u8; 16])]),
+        )
+        .await?;
+    akd.publish(vec![(AkdLabel::from("hello3"), AkdValue::from("world3"))])
+        .await?;
+
+    let (fork, epoch_hash) = akd
+        .fork_at(
+            2,
+            StorageManager::new_no_cache(AsyncInMemoryDatabase::new()),
+        )
+        .await?;
+    assert_eq!(root2, epoch_hash);
+    assert_eq!(root2, fork.get_epoch_hash().await?);
+    let original = akd.get_epoch_metadata(1).await?;
+    let forked = fork.get_epoch_metadata(1).await?;
+    assert_eq!(
+        (original.timestamp_ms, original.note),
+        (forked.timestamp_ms, forked.note)
+    );
+    assert!(fork.lookup(AkdLabel::from("hello3")).await.is_err());
+    let (lookup_proof, root_hash) = fork.lookup(AkdLabel::from("hello")).await?;
+    assert_eq!(
+        2,
+        lookup_verify::<TC>(
+            fork.get_public_key().await?.as_bytes(),
+            root_hash.hash(),
+            root_hash.epoch(),
+            AkdLabel::from("hello"),
+            lookup_proof,
+        )?
+        .version
+    );
+    let audit_proof = fork.audit(1, 2).await?;
+    audit_verify::<TC>(vec![root1.hash(), root2.hash()], audit_proof).await?;
+
+    // The fork diverges from the original directory from then on
+    let root3 = fork
+        .publish(vec![(AkdLabel::from("hello4"), AkdValue::from("world4"))])
+        .await?;
+    assert_eq!(3, root3.epoch());
+    assert_ne!(akd.get_epoch_hash().await?, root3);
+
+    // With a new key, the labels are derived from the new key
+    let (fork, epoch_hash) = akd
+        .fork_at_with_vrf(
+            3,
+            StorageManager::new_no_cache(AsyncInMemoryDatabase::new()),
+            InMemoryKeyVrf::new(vec![2u8; 32]),
+        )
+        .await?;
+    assert_eq!(3, epoch_hash.epoch());
+    assert_ne!(akd.get_epoch_hash().await?, epoch_hash);
+    let public_key = fork.get_public_key().await?;
+    assert_ne!(akd.get_public_key().await?, public_key);
+    let (history_proof, root_hash) = fork
+        .key_history(&AkdLabel::from("hello"), HistoryParams::default())
+        .await?;
+    assert_eq!(
+        2,
+        key_history_verify::<TC>(
+            public_key.as_bytes(),
+            root_hash.hash(),
+            root_hash.epoch(),
+            AkdLabel::from("hello"),
+            history_proof,
+            HistoryVerificationParams::default(),
+        )?
+        .len()
+    );
+
+    // A fork needs a past epoch and empty storage
+    assert!(matches!(
+        akd.fork_at(
+            4,
+            StorageManager::new_no_cache(AsyncInMemoryDatabase::new())
+        )
+        .await,
+        Err(AkdError::Directory(DirectoryError::InvalidEpoch(_)))
+    ));
+    let db = AsyncInMemoryDatabase::new();
+    Directory::<TC, _, _>::new(StorageManager::new_no_cache(db.clone()), vrf)
+        .await?
+        .publish(vec![(AkdLabel::from("hello"), AkdValue::from("world"))])
+        .await?;
+    assert!(matches!(
+        akd.fork_at(1, StorageManager::new_no_cache(db)).await,
+        Err(AkdError::Directory(DirectoryError::Publish(_)))
+    ));
+
+    Ok(())
+}
+
+// Test that merging two directories keeps the histories of their labels, which can be bridged
+test_config!(test_merge);
+async fn test_merge<TC: Configuration>() -> Result<(), AkdError> {
+    let (alice, bob, carol) = (
+        AkdLabel::from("alice"),
+        AkdLabel::from("bob"),
+        AkdLabel::from("carol"),
+    );
+    let new_directory = |timestamp_ms| async move {
+        Directory::<TC, _, _>::new(
+            StorageManager::new_no_cache(AsyncInMemoryDatabase::new()),
+            HardCodedAkdVRF {},
+        )
+        .await
+        .map(|akd| akd.with_clock(ManualClock::new(timestamp_ms)))
+    };
+    let first = new_directory(100).await?;
+    first
+        .publish(vec![
+            (alice.clone(), AkdValue::from("alice 1")),
+            (carol.clone(), AkdValue::from("carol 1")),
+        ])
+        .await?;
+    first
+        .publish(vec![(alice.clone(), AkdValue::from("alice 2"))])
+        .await?;
+    let second = new_directory(200).await?;
+    second
+        .publish(vec![
+            (bob.clone(), AkdValue::from("bob 1")),
+            (carol.clone(), AkdValue::from("carol 1'")),
+        ])
+        .await?;
+    second
+        .publish(vec![(bob.clone(), AkdValue::from("bob 2"))])
+        .await?;
+    second
+        .publish(vec![(carol.clone(), AkdValue::from("carol 2'"))])
+        .await?;
+
+    // The third epoch of the merged directory is empty, as carol is kept from the first directory
+    let merged = new_directory(0).await?;
+    let report = merge(&first, &second, &merged, MergeConflictPolicy::PreferFirst).await?;
+    assert_eq!(3, report.epoch_hash.epoch());
+    assert_eq!(3, report.num_labels);
+    assert_eq!(
+        vec![MergeConflict {
+            label: carol.clone(),
+            kept: MergeSource::First,
+        }],
+        report.conflicts
+    );
+    assert_eq!(200, merged.get_epoch_metadata(1).await?.timestamp_ms);
+
+    let merged_vrf_pk = merged.get_public_key().await?;
+    for (original, label, version) in [(&first, &alice, 2), (&first, &carol, 1)] {
+        let (proof, original_root_hash, merged_root_hash) =
+            merged.merge_bridge_proof(original, label).await?;
+        let result = merge_bridge_verify::<TC>(
+            original.get_public_key().await?.as_bytes(),
+            original_root_hash.hash(),
+            original_root_hash.epoch(),
+            merged_vrf_pk.as_bytes(),
+            merged_root_hash.hash(),
+            merged_root_hash.epoch(),
+            label.clone(),
+            proof.clone(),
+        )?;
+        assert_eq!(version, result.version);
+        assert!(merge_bridge_verify::<TC>(
+            original.get_public_key().await?.as_bytes(),
+            original_root_hash.hash(),
+            original_root_hash.epoch(),
+            merged_vrf_pk.as_bytes(),
+            merged_root_hash.hash(),
+            merged_root_hash.epoch(),
+            AkdLabel::from("dave"),
+            proof,
+        )
+        .is_err());
+    }
+    assert!(merged.merge_bridge_proof(&second, &carol).await.is_err());
+
+    // The merged directory continues the histories of the labels
+    let (history_proof, root_hash) = merged.key_history(&bob, HistoryParams::default()).await?;
+    let results = key_history_verify::<TC>(
+        merged_vrf_pk.as_bytes(),
+        root_hash.hash(),
+        root_hash.epoch(),
+        bob.clone(),
+        history_proof,
+        HistoryVerificationParams::default(),
+    )?;
+    assert_eq!(
+        vec![(2, 2), (1, 1)],
+        results
+            .iter()
+            .map(|result| (result.epoch, result.version))
+            .collect::<Vec<_>>()
+    );
+    let (first_history_proof, first_root_hash) =
+        first.key_history(&alice, HistoryParams::default()).await?;
+    let (history_proof, root_hash) = merged.key_history(&alice, HistoryParams::default()).await?;
+    assert_eq!(
+        first_history_proof.update_proofs.len(),
+        history_proof.update_proofs.len()
+    );
+    key_history_verify::<TC>(
+        merged_vrf_pk.as_bytes(),
+        root_hash.hash(),
+        root_hash.epoch(),
+        alice.clone(),
+        history_proof,
+        HistoryVerificationParams::default(),
+    )?;
+    assert_eq!(2, first_root_hash.epoch());
+    // The empty epoch is part of the append-only history of the merged directory
+    assert_eq!(2, merged.audit(1, 3).await?.proofs.len());
+
+    // The most recent history of carol is that of the second directory
+    let merged = new_directory(0).await?;
+    let report = merge(
+        &first,
+        &second,
+        &merged,
+        MergeConflictPolicy::PreferMostRecent,
+    )
+    .await?;
+    assert_eq!(MergeSource::Second, report.conflicts[0].kept);
+    let (proof, original_root_hash, merged_root_hash) =
+        merged.merge_bridge_proof(&second, &carol).await?;
+    let result = merge_bridge_verify::<TC>(
+        second.get_public_key().await?.as_bytes(),
+        original_root_hash.hash(),
+        original_root_hash.epoch(),
+        merged_vrf_pk.as_bytes(),
+        merged_root_hash.hash(),
+        merged_root_hash.epoch(),
+        carol.clone(),
+        proof,
+    )?;
+    assert_eq!((3, 2), (result.epoch, result.version));
+
+    // The target of a merge must be empty
+    assert!(
+        merge(&first, &second, &merged, MergeConflictPolicy::PreferFirst)
+            .await
+            .is_err()
+    );
+
+    Ok(())
+}
+
+// Test that a bulk import results in the same directory as publishing the entries
+test_config!(test_bulk_import);
+async fn test_bulk_import<TC: Configuration>() -> Result<(), AkdError> {
+    let entries = (0..100)
+        .map(|i| {
+            (
+                AkdLabel(format!("user{i}").into_bytes()),
+                AkdValue::Bytes(format!("value{i}").into_bytes()),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let vrf = HardCodedAkdVRF {};
+    let published = Directory::<TC, _, _>::new(storage, vrf).await?;
+    let expected = published.publish(entries.clone()).await?;
+
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let vrf = HardCodedAkdVRF {};
+    let akd = Directory::<TC, _, _>::new(storage, vrf).await?;
+    let root1 = akd
+        .bulk_import(futures::stream::iter(entries.clone()))
+        .await?;
+    assert_eq!(expected, root1);
+    assert_eq!(published.retrieve_azks().await?, akd.retrieve_azks().await?);
+    assert_eq!(100, akd.get_epoch_metadata(1).await?.update_count);
+
+    let (lookup_proof, root_hash) = akd.lookup(AkdLabel::from("user42")).await?;
+    assert_eq!(AkdValue::from("value42"), lookup_proof.value);
+    lookup_verify::<TC>(
+        akd.get_public_key().await?.as_bytes(),
+        root_hash.hash(),
+        root_hash.epoch(),
+        AkdLabel::from("user42"),
+        lookup_proof,
+    )?;
+
+    // The directory can be published to as usual after the import
+    let root2 = akd
+        .publish(vec![(AkdLabel::from("user42"), AkdValue::from("updated"))])
+        .await?;
+    let audit_proof = akd.audit(1, 2).await?;
+    audit_verify::<TC>(vec![root1.hash(), root2.hash()], audit_proof).await?;
+
+    // Imports are only possible into an empty directory
+    assert!(matches!(
+        akd.bulk_import(futures::stream::iter(entries.clone()))
+            .await,
+        Err(AkdError::Directory(DirectoryError::Publish(_)))
+    ));
+
+    Ok(())
+}
+
+// Test that a failed bulk import leaves no records behind, so that it can be retried
+test_config!(test_bulk_import_failure);
+async fn test_bulk_import_failure<TC: Configuration>() -> Result<(), AkdError> {
+    let entries = (0..100)
+        .map(|i| {
+            (
+                AkdLabel(format!("user{i}").into_bytes()),
+                AkdValue::Bytes(format!("value{i}").into_bytes()),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db.clone());
+    let vrf = HardCodedAkdVRF {};
+    let akd = Directory::<TC, _, _>::new(storage, vrf).await?;
+    let mut initial_records = db.batch_get_all_direct().await?;
+    initial_records.sort();
+
+    // The duplicate is only detected once the value states of all of the entries were written
+    let duplicated = [&entries[..], &entries[..1]].concat();
+    assert!(matches!(
+        akd.bulk_import(futures::stream::iter(duplicated)).await,
+        Err(AkdError::Directory(DirectoryError::Publish(_)))
+    ));
+    assert_eq!(0, akd.get_epoch_hash().await?.epoch());
+    let mut records = db.batch_get_all_direct().await?;
+    records.sort();
+    assert_eq!(initial_records, records);
+
+    // A retry of the import succeeds
+    let root_hash = akd.bulk_import(futures::stream::iter(entries)).await?;
+    assert_eq!(1, root_hash.epoch());
+    let (lookup_proof, _) = akd.lookup(AkdLabel::from("user42")).await?;
+    assert_eq!(AkdValue::from("value42"), lookup_proof.value);
+
+    Ok(())
+}
+
+// Test migrating a directory with the experimental configuration to the domain-separated
+// configuration, by importing the latest values of its labels into a new directory
+#[cfg(all(feature = "experimental", feature = "domain_separated"))]
+#[tokio::test]
+async fn test_migrate_to_domain_separated_configuration() -> Result<(), AkdError> {
+    type OldTC = crate::ExperimentalConfiguration<crate::ExampleLabel>;
+    type NewTC = crate::DomainSeparatedConfiguration<crate::ExampleLabel>;
+
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let old = Directory::<OldTC, _, _>::new(storage, HardCodedAkdVRF {}).await?;
+    for epoch in 1..=3 {
+        old.publish(
+            (0..10)
+                .map(|i| {
+                    (
+                        AkdLabel(format!("user{i}").into_bytes()),
+                        AkdValue::Bytes(format!("value{i} at {epoch}").into_bytes()),
+                    )
+                })
+                .collect(),
+        )
+        .await?;
+    }
+
+    let mut entries = vec![];
+    let mut cursor = None;
+    loop {
+        let page = old.list_labels(cursor, 4, false).await?;
+        for entry in page.entries {
+            let (lookup_proof, _) = old.lookup(entry.label.clone()).await?;
+            entries.push((entry.label, lookup_proof.value));
+        }
+        cursor = page.next_cursor;
+        if cursor.is_none() {
+            break;
+        }
+    }
+    assert_eq!(10, entries.len());
+
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let new = Directory::<NewTC, _, _>::new(storage, HardCodedAkdVRF {}).await?;
+    let root_hash = new.bulk_import(futures::stream::iter(entries)).await?;
+    assert_ne!(old.get_epoch_hash().await?.hash(), root_hash.hash());
+
+    let label = AkdLabel::from("user7");
+    let (lookup_proof, epoch_hash) = new.lookup(label.clone()).await?;
+    let vrf_pk = new.get_public_key().await?;
+    let result = lookup_verify::<NewTC>(
+        vrf_pk.as_bytes(),
+        epoch_hash.hash(),
+        epoch_hash.epoch(),
+        label.clone(),
+        lookup_proof.clone(),
+    )?;
+    assert_eq!(AkdValue::from("value7 at 3"), result.value);
+
+    // The proofs of the migrated directory do not verify with the hashes of the old one
+    assert!(lookup_verify::<OldTC>(
+        vrf_pk.as_bytes(),
+        epoch_hash.hash(),
+        epoch_hash.epoch(),
+        label,
+        lookup_proof,
+    )
+    .is_err());
+
+    Ok(())
+}
+
+// Test paginating through the labels of the directory
+test_config!(test_list_labels);
+async fn test_list_labels<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let vrf = HardCodedAkdVRF {};
+    let akd = Directory::<TC, _, _>::new(storage, vrf).await?;
+    akd.publish(
+        (1..=5)
+            .map(|i| (AkdLabel(vec![i]), AkdValue::from("value")))
+            .collect(),
+    )
+    .await?;
+    akd.publish(vec![(AkdLabel(vec![3]), AkdValue::from("value2"))])
+        .await?;
+
+    let mut cursor = None;
+    let mut pages = vec![];
+    loop {
+        let page = akd.list_labels(cursor, 2, true).await?;
+        cursor = page.next_cursor.clone();
+        pages.push(page.entries);
+        if cursor.is_none() {
+            break;
+        }
+    }
+    assert_eq!(
+        vec![2, 2, 1],
+        pages.iter().map(Vec::len).collect::<Vec<_>>()
+    );
+    let entries = pages.concat();
+    assert_eq!(
+        (1..=5).map(|i| AkdLabel(vec![i])).collect::<Vec<_>>(),
+        entries
+            .iter()
+            .map(|entry| entry.label.clone())
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(Some(2), entries[2].current_version);
+    assert_eq!(Some(2), entries[2].last_update_epoch);
+    assert_eq!(Some(1), entries[4].current_version);
+    assert_eq!(Some(1), entries[4].last_update_epoch);
+
+    // The state is only included when requested, and a full last page has no next cursor
+    let page = akd.list_labels(Some(AkdLabel(vec![3])), 2, false).await?;
+    assert_eq!(None, page.next_cursor);
+    assert_eq!(
+        vec![
+            LabelEntry {
+                label: AkdLabel(vec![4]),
+                current_version: None,
+                last_update_epoch: None,
+            },
+            LabelEntry {
+                label: AkdLabel(vec![5]),
+                current_version: None,
+                last_update_epoch: None,
+            },
+        ],
+        page.entries
+    );
+
+    Ok(())
+}
+
+// Test the statistics reported by the directory
+test_config!(test_directory_stats);
+async fn test_directory_stats<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new(db, None, None, None);
+    let vrf = HardCodedAkdVRF {};
+    let akd = Directory::<TC, _, _>::new(storage, vrf).await?;
+
+    let stats = akd.stats().await?;
+    assert_eq!(0, stats.epoch);
+    assert_eq!(Some(0), stats.total_labels);
+    assert_eq!(1, stats.total_tree_nodes);
+    assert_eq!(None, stats.last_publish_duration_ms);
+
+    akd.publish(vec![
+        (AkdLabel::from("hello"), AkdValue::from("world")),
+        (AkdLabel::from("hello2"), AkdValue::from("world")),
+    ])
+    .await?;
+    akd.lookup(AkdLabel::from("hello")).await?;
+
+    let stats = akd.stats().await?;
+    assert_eq!(1, stats.epoch);
+    assert_eq!(Some(2), stats.total_labels);
+    assert_eq!(akd.retrieve_azks().await?.num_nodes, stats.total_tree_nodes);
+    assert!(stats.last_publish_duration_ms.is_some());
+    assert!(stats.storage.cache_hits > 0);
+    assert!(stats.storage.cache_hit_rate.is_some());
+    assert!(stats.storage.write_latency.samples > 0);
+
+    Ok(())
+}
+
+// Test the summary of the changes to a directory between two epochs
+test_config!(test_epoch_diff);
+async fn test_epoch_diff<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let vrf = HardCodedAkdVRF {};
+    let akd = Directory::<TC, _, _>::new(storage, vrf).await?;
+
+    let [a, b, c] = ["a", "b", "c"].map(AkdLabel::from);
+    akd.publish(vec![
+        (a.clone(), AkdValue::from("a1")),
+        (b.clone(), AkdValue::from("b1")),
+    ])
+    .await?;
+    akd.publish(vec![
+        (a.clone(), AkdValue::from("a2")),
+        (c.clone(), AkdValue::from("c1")),
+    ])
+    .await?;
+    akd.publish(vec![(a, AkdValue::from("a3")), (c, AkdValue::from("c2"))])
+        .await?;
+
+    let diff = akd.diff(0, 1).await?;
+    assert_eq!(2, diff.total_labels);
+    assert_eq!(2, diff.labels_added);
+    assert_eq!(0, diff.labels_updated);
+    assert_eq!(2, diff.value_updates);
+    assert_eq!(2, diff.leaves_inserted);
+
+    // The label "a" is updated twice, and "c" is added and then updated
+    let diff = akd.diff(1, 3).await?;
+    assert_eq!(
+        EpochDiff {
+            from_epoch: 1,
+            to_epoch: 3,
+            total_labels: 3,
+            labels_added: 1,
+            labels_updated: 1,
+            value_updates: 4,
+            // The new versions, along with the stale markers of the previous versions
+            leaves_inserted: 7,
+        },
+        diff
+    );
+    // The growth of the tree matches the leaves inserted in the audit proof
+    let audit_proof = akd.audit(1, 3).await?;
+    assert_eq!(
+        diff.leaves_inserted as usize,
+        audit_proof
+            .proofs
+            .iter()
+            .map(|proof| proof.inserted.len())
+            .sum::<usize>()
+    );
+
+    // The state as of an earlier epoch is unaffected by the later epochs
+    let diff = akd.diff(0, 2).await?;
+    assert_eq!(3, diff.total_labels);
+    assert_eq!(3, diff.labels_added);
+    assert_eq!(4, diff.value_updates);
+    assert_eq!(5, diff.leaves_inserted);
+
+    assert!(akd.diff(2, 2).await.is_err());
+    assert!(akd.diff(1, 4).await.is_err());
+
+    Ok(())
+}
+
+// Test that replaying an epoch from its updates detects when they differ from the published ones
+test_config!(test_replay_epoch);
+async fn test_replay_epoch<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let vrf = HardCodedAkdVRF {};
+    let akd = Directory::<TC, _, _>::new(storage, vrf)
+        .await?
+        .with_epoch_metadata_binding(true);
+
+    let [a, b, c] = ["a", "b", "c"].map(AkdLabel::from);
+    akd.publish(vec![
+        (a.clone(), AkdValue::from("a1")),
+        (b.clone(), AkdValue::from("b1")),
+    ])
+    .await?;
+    let second_epoch = vec![
+        (a.clone(), AkdValue::from("a2")),
+        (c.clone(), AkdValue::from("c1")),
+    ];
+    let EpochHash(_, second_root_hash) = akd.publish(second_epoch.clone()).await?;
+    akd.publish(vec![(b, AkdValue::from("b2"))]).await?;
+
+    // The recorded updates reproduce the published root hash
+    let replay = akd.replay_epoch(2, second_epoch).await?;
+    assert!(replay.matches());
+    assert!(replay.previous_root_consistent);
+    assert_eq!(second_root_hash, replay.stored_root_hash);
+    assert_eq!(replay.stored_root_hash, replay.replayed_root_hash);
+    let replay = akd
+        .replay_epoch(1, vec![(a.clone(), AkdValue::from("a1"))])
+        .await?;
+    assert!(!replay.matches());
+
+    // Altered or missing updates are detected
+    let replay = akd
+        .replay_epoch(
+            2,
+            vec![(a.clone(), AkdValue::from("a2")), (c, AkdValue::from("c2"))],
+        )
+        .await?;
+    assert!(replay.previous_root_consistent);
+    assert!(!replay.matches());
+    let replay = akd.replay_epoch(2, vec![(a, AkdValue::from("a2"))]).await?;
+    assert!(!replay.matches());
+
+    // The directory is left unmodified
+    assert_eq!(3, akd.get_epoch_hash().await?.epoch());
+
+    assert!(akd.replay_epoch(0, vec![]).await.is_err());
+    assert!(akd.replay_epoch(4, vec![]).await.is_err());
+
+    Ok(())
+}
+
+// Test that the journal of the updates of each epoch is recorded and can be streamed
+test_config!(test_update_journal);
+async fn test_update_journal<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let vrf = HardCodedAkdVRF {};
+    let akd = Directory::<TC, _, _>::new(storage, vrf)
+        .await?
+        .with_update_journal(UpdateJournalMode::Labels);
+
+    let [a, b, c] = ["a", "b", "c"].map(AkdLabel::from);
+    akd.publish(vec![
+        (b.clone(), AkdValue::from("b1")),
+        (a.clone(), AkdValue::from("a1")),
+    ])
+    .await?;
+    akd.publish(vec![(a.clone(), AkdValue::from("a2"))]).await?;
+
+    // The entries are ordered by label
+    let journal = akd.get_update_journal(1).await?;
+    assert_eq!(1, journal.epoch);
+    assert_eq!(
+        vec![
+            JournalEntry {
+                label: JournalLabel::Label(a.clone()),
+                version: 1
+            },
+            JournalEntry {
+                label: JournalLabel::Label(b),
+                version: 1
+            },
+        ],
+        journal.entries
+    );
+    assert_eq!(
+        vec![JournalEntry {
+            label: JournalLabel::Label(a.clone()),
+            version: 2
+        }],
+        akd.get_update_journal(2).await?.entries
+    );
+
+    // The hashed labels hide the labels, but can be linked across epochs
+    let hashed = akd
+        .clone()
+        .with_update_journal(UpdateJournalMode::HashedLabels);
+    hashed
+        .publish(vec![
+            (a.clone(), AkdValue::from("a3")),
+            (c, AkdValue::from("c1")),
+        ])
+        .await?;
+    hashed.publish(vec![(a, AkdValue::from("a4"))]).await?;
+    let third = hashed.get_update_journal(3).await?;
+    let fourth = hashed.get_update_journal(4).await?;
+    assert_eq!(2, third.entries.len());
+    assert!(third
+        .entries
+        .iter()
+        .all(|entry| matches!(entry.label, JournalLabel::Hashed(_))));
+    assert_ne!(third.entries[0].label, third.entries[1].label);
+    let hashed_a = &fourth.entries[0].label;
+    assert!(third
+        .entries
+        .iter()
+        .any(|entry| &entry.label == hashed_a && entry.version == 3));
+
+    let journals = akd
+        .stream_update_journals(1, 4)
+        .try_collect::<Vec<_>>()
+        .await?;
+    assert_eq!(
+        vec![1, 2, 3, 4],
+        journals
+            .iter()
+            .map(|journal| journal.epoch)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(fourth, journals[3]);
+    assert!(akd.get_update_journal(0).await.is_err());
+    assert!(akd.get_update_journal(5).await.is_err());
+    assert!(akd
+        .stream_update_journals(3, 2)
+        .try_collect::<Vec<_>>()
+        .await
+        .is_err());
+
+    // The epochs published without the journal have none
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let akd = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {}).await?;
+    akd.publish(vec![(AkdLabel::from("a"), AkdValue::from("a1"))])
+        .await?;
+    assert!(matches!(
+        akd.get_update_journal(1).await,
+        Err(AkdError::Storage(StorageError::NotFound(_)))
+    ));
+    assert!(akd
+        .stream_update_journals(1, 1)
+        .try_collect::<Vec<_>>()
+        .await?
+        .is_empty());
+
+    // A bulk import records the journal of its epoch as well
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let akd = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {})
+        .await?
+        .with_update_journal(UpdateJournalMode::Labels);
+    akd.bulk_import(futures::stream::iter(vec![
+        (AkdLabel::from("b"), AkdValue::from("b1")),
+        (AkdLabel::from("a"), AkdValue::from("a1")),
+    ]))
+    .await?;
+    assert_eq!(
+        vec![
+            JournalEntry {
+                label: JournalLabel::Label(AkdLabel::from("a")),
+                version: 1
+            },
+            JournalEntry {
+                label: JournalLabel::Label(AkdLabel::from("b")),
+                version: 1
+            },
+        ],
+        akd.get_update_journal(1).await?.entries
+    );
+
+    Ok(())
+}
+
+// Test that the root hashes of the published epochs are recorded and linked by a hash chain
+test_config!(test_get_epoch_hashes);
+async fn test_get_epoch_hashes<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let akd = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {}).await?;
+
+    // The chain starts at the first epoch published with the root history
+    akd.publish(vec![(AkdLabel::from("a"), AkdValue::from("a1"))])
+        .await?;
+    let akd = akd.with_root_history();
+    let mut root_hashes = vec![];
+    for value in ["a2", "a3", "a4"] {
+        let EpochHash(_, root_hash) = akd
+            .publish(vec![(AkdLabel::from("a"), AkdValue::from(value))])
+            .await?;
+        root_hashes.push(root_hash);
+    }
+
+    let chain = akd.get_epoch_hashes(2, 4).await?;
+    assert_eq!(2, chain.start_epoch);
+    assert_eq!(Some(4), chain.end_epoch());
+    assert_eq!(EMPTY_DIGEST, chain.previous_chain_hash);
+    assert_eq!(root_hashes, chain.root_hashes);
+    verify_epoch_root_chain::<TC>(&chain, RootChainAnchor::Previous(EMPTY_DIGEST))?;
+    verify_epoch_root_chain::<TC>(&chain, RootChainAnchor::Last(chain.chain_hash))?;
+
+    // A range extends the chain of the preceding epochs
+    let prefix = akd.get_epoch_hashes(2, 3).await?;
+    let suffix = akd.get_epoch_hashes(4, 4).await?;
+    verify_epoch_root_chain::<TC>(&prefix, RootChainAnchor::Previous(EMPTY_DIGEST))?;
+    verify_epoch_root_chain::<TC>(&suffix, RootChainAnchor::Previous(prefix.chain_hash))?;
+    assert_eq!(chain.chain_hash, suffix.chain_hash);
+    assert!(matches!(
+        verify_epoch_root_chain::<TC>(&suffix, RootChainAnchor::Previous(EMPTY_DIGEST)),
+        Err(VerificationError::RootChain(_))
+    ));
+
+    // A tampered root hash does not hash to the chain hash
+    let mut tampered = chain.clone();
+    tampered.root_hashes[1][0] ^= 1;
+    assert!(matches!(
+        verify_epoch_root_chain::<TC>(&tampered, RootChainAnchor::Previous(EMPTY_DIGEST)),
+        Err(VerificationError::RootChain(_))
+    ));
+    // Nor does a chain re-hashed by the server verify against the trusted chain hash
+    tampered.chain_hash = tampered.epochs().fold(
+        tampered.previous_chain_hash,
+        |previous, (epoch, root_hash)| EpochRootChain::link::<TC>(&previous, epoch, &root_hash),
+    );
+    assert!(matches!(
+        verify_epoch_root_chain::<TC>(&tampered, RootChainAnchor::Last(chain.chain_hash)),
+        Err(VerificationError::RootChain(_))
+    ));
+    let mut empty = chain;
+    empty.root_hashes.clear();
+    assert!(
+        verify_epoch_root_chain::<TC>(&empty, RootChainAnchor::Previous(EMPTY_DIGEST)).is_err()
+    );
+
+    assert!(matches!(
+        akd.get_epoch_hashes(1, 2).await,
+        Err(AkdError::Storage(StorageError::NotFound(_)))
+    ));
+    assert!(matches!(
+        akd.get_epoch_hashes(3, 2).await,
+        Err(AkdError::Directory(DirectoryError::InvalidEpoch(_)))
+    ));
+    assert!(matches!(
+        akd.get_epoch_hashes(2, 5).await,
+        Err(AkdError::Directory(DirectoryError::InvalidEpoch(_)))
+    ));
+
+    // The root hash of a bulk imported epoch is recorded as well
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let akd = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {})
+        .await?
+        .with_root_history();
+    let EpochHash(_, root_hash) = akd
+        .bulk_import(futures::stream::iter(vec![(
+            AkdLabel::from("a"),
+            AkdValue::from("a1"),
+        )]))
+        .await?;
+    let chain = akd.get_epoch_hashes(1, 1).await?;
+    assert_eq!(vec![root_hash], chain.root_hashes);
+    verify_epoch_root_chain::<TC>(&chain, RootChainAnchor::Previous(EMPTY_DIGEST))?;
+
+    Ok(())
+}
+
+// Test that the health check reports a corrupted tree and an unavailable VRF key
+test_config!(test_health_check);
+async fn test_health_check<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db.clone());
+    let vrf = InMemoryKeyVrf::new(HardCodedAkdVRF {}.retrieve().await?);
+    // The sampled paths are derived from the time of the clock of the directory
+    let clock = ManualClock::new(0);
+    let akd = Directory::<TC, _, _>::new(storage, vrf.clone())
+        .await?
+        .with_clock(clock.clone());
+
+    let report = akd.health_check().await;
+    assert!(report.is_healthy(), "{:?}", report.failures);
+    assert_eq!(Some(0), report.epoch);
+    assert_eq!(Some(true), report.root_hash_consistent);
+
+    akd.publish(
+        (0..20)
+            .map(|i| (AkdLabel(vec![i]), AkdValue::from("value")))
+            .collect(),
+    )
+    .await?;
+    let report = akd.health_check().await;
+    assert!(report.is_healthy(), "{:?}", report.failures);
+    assert_eq!(Some(1), report.epoch);
+    assert!(report.storage_reachable);
+    assert!(report.vrf_key_available);
+    assert_eq!(Some(true), report.root_hash_consistent);
+    let read_only =
+        ReadOnlyDirectory::<TC, _, _>::new(StorageManager::new_no_cache(db.clone()), vrf.clone())
+            .await?;
+    assert!(read_only.health_check().await.is_healthy());
+
+    // A root value which does not match its children is detected on any sampled path
+    let root_key = crate::tree_node::NodeKey(crate::NodeLabel::root());
+    let mut root = match db.get::<TreeNodeWithPreviousValue>(&root_key).await? {
+        DbRecord::TreeNode(root) => root,
+        _ => panic!("The root node is not a tree node"),
+    };
+    let stored_value = root.latest_node.hash;
+    root.latest_node.hash = crate::AzksValue([1u8; 32]);
+    db.set(DbRecord::TreeNode(root.clone())).await?;
+    for _ in 0..2 {
+        let report = akd.health_check().await;
+        assert!(!report.is_healthy());
+        assert_eq!(Some(false), report.root_hash_consistent);
+        clock.advance(1);
+    }
+    assert!(report.vrf_key_available);
+    root.latest_node.hash = stored_value;
+    db.set(DbRecord::TreeNode(root)).await?;
+    assert!(akd.health_check().await.is_healthy());
+
+    // The VRF key is no longer available once its secrets are destroyed
+    vrf.destroy_secrets().await?;
+    let report = akd.health_check().await;
+    assert!(!report.vrf_key_available);
+    assert_eq!(Some(true), report.root_hash_consistent);
+    assert_eq!(1, report.failures.len());
+
+    Ok(())
+}
+
+// Test that a lookup proof for a label holding a value set commits to the whole set
+test_config!(test_value_set_lookup);
+async fn test_value_set_lookup<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let vrf = HardCodedAkdVRF {};
+    let akd = Directory::<TC, _, _>::new(storage, vrf).await?;
+    let pk = akd.get_public_key().await?;
+
+    let mut devices = [AkdValue::from("device1"), AkdValue::from("device2")]
+        .into_iter()
+        .collect::<AkdValueSet>();
+    akd.publish(vec![(AkdLabel::from("hello"), devices.clone().into())])
+        .await?;
+    devices.insert(AkdValue::from("device3"));
+    akd.publish(vec![(AkdLabel::from("hello"), devices.clone().into())])
+        .await?;
+
+    let (lookup_proof, root_hash) = akd.lookup(AkdLabel::from("hello")).await?;
+    let result = lookup_verify::<TC>(
+        pk.as_bytes(),
+        root_hash.hash(),
+        root_hash.epoch(),
+        AkdLabel::from("hello"),
+        lookup_proof.clone(),
+    )?;
+    assert_eq!(2, result.version);
+    assert_eq!(Ok(devices), AkdValueSet::try_from(&result.value));
+
+    // Dropping a device from the set invalidates the proof
+    let mut tampered = lookup_proof;
+    tampered.value = [AkdValue::from("device1"), AkdValue::from("device2")]
+        .into_iter()
+        .collect::<AkdValueSet>()
+        .into();
+    assert!(lookup_verify::<TC>(
+        pk.as_bytes(),
+        root_hash.hash(),
+        root_hash.epoch(),
+        AkdLabel::from("hello"),
+        tampered,
+    )
+    .is_err());
+
+    Ok(())
+}
+
+// Test that labels from different namespaces are kept apart, and that only labels
+// of registered namespaces can be published
+test_config!(test_label_namespaces);
+async fn test_label_namespaces<TC: Configuration>() -> Result<(), AkdError> {
+    let users = LabelNamespace::new("user").unwrap();
+    let devices = LabelNamespace::new("device").unwrap();
+
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let vrf = HardCodedAkdVRF {};
+    let akd = Directory::<TC, _, _>::new(storage, vrf)
+        .await?
+        .with_label_namespace(users.clone())
+        .with_label_namespace(devices.clone());
+    assert_eq!(vec![devices.clone(), users.clone()], akd.label_namespaces());
+    let pk = akd.get_public_key().await?;
+
+    akd.publish(vec![
+        (users.label(b"alice"), AkdValue::from("user key")),
+        (devices.label(b"alice"), AkdValue::from("device key")),
+    ])
+    .await?;
+
+    // The same name in each namespace is a distinct label with its own value
+    for (label, value) in [
+        (users.label(b"alice"), AkdValue::from("user key")),
+        (devices.label(b"alice"), AkdValue::from("device key")),
+    ] {
+        let (lookup_proof, root_hash) = akd.lookup(label.clone()).await?;
+        let result = lookup_verify::<TC>(
+            pk.as_bytes(),
+            root_hash.hash(),
+            root_hash.epoch(),
+            label,
+            lookup_proof,
+        )?;
+        assert_eq!(value, result.value);
+    }
+
+    // Labels outside of the registered namespaces are rejected
+    let groups = LabelNamespace::new("group").unwrap();
+    for label in [AkdLabel::from("alice"), groups.label(b"alice")] {
+        let result = akd
+            .publish(vec![(label.clone(), AkdValue::from("key"))])
+            .await;
+        assert!(matches!(
+            result,
+            Err(AkdError::Directory(DirectoryError::Publish(_)))
+        ));
+        assert!(akd
+            .preview_publish(vec![(label, AkdValue::from("key"))])
+            .await
+            .is_err());
+    }
+    assert_eq!(1, akd.get_epoch_hash().await?.epoch());
+
+    Ok(())
+}
+
+// Test that proof generations beyond the configured limits are rejected
+test_config!(test_admission_control);
+async fn test_admission_control<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let vrf = HardCodedAkdVRF {};
+    let akd = Directory::<TC, _, _>::new(storage, vrf)
+        .await?
+        .with_admission_control(AdmissionConfig::new(2).with_max_call_cost(4));
+    for i in 0..5 {
+        akd.publish(vec![(
+            AkdLabel::from("hello"),
+            AkdValue::Bytes(format!("world{i}").into_bytes()),
+        )])
+        .await?;
+    }
+
+    // A complete history of all five versions exceeds the budget, while a partial one fits
+    let result = akd
+        .key_history(&AkdLabel::from("hello"), HistoryParams::Complete)
+        .await;
+    assert!(matches!(
+        result,
+        Err(AkdError::Directory(DirectoryError::Overloaded(_)))
+    ));
+    akd.key_history(&AkdLabel::from("hello"), HistoryParams::MostRecent(1))
+        .await?;
+    akd.lookup(AkdLabel::from("hello")).await?;
+    let labels = vec![AkdLabel::from("hello"); 5];
+    assert!(akd.batch_lookup(&labels).await.is_err());
+
+    assert_eq!(2, akd.stats().await?.rejected_proof_generations);
+
+    // Without any slots every proof generation is shed
+    let akd = akd.with_admission_control(AdmissionConfig::new(0));
+    assert!(matches!(
+        akd.lookup(AkdLabel::from("hello")).await,
+        Err(AkdError::Directory(DirectoryError::Overloaded(_)))
+    ));
+
+    Ok(())
+}
+
+// Test that cancelled operations abort without modifying the directory
+test_config!(test_cancellation);
+async fn test_cancellation<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db.clone());
+    let vrf = HardCodedAkdVRF {};
+    let akd = Directory::<TC, _, _>::new(storage, vrf).await?;
+    akd.publish(vec![(AkdLabel::from("hello"), AkdValue::from("world"))])
+        .await?;
+    akd.publish(vec![(AkdLabel::from("hello"), AkdValue::from("world2"))])
+        .await?;
+
+    let cancelled = CancellationToken::new();
+    cancelled.cancel();
+    let expired = CancellationToken::with_timeout(Duration::ZERO);
+    for token in [&cancelled, &expired] {
+        let result = akd
+            .publish_cancellable(
+                vec![(AkdLabel::from("hello2"), AkdValue::from("world"))],
+                None,
+                token,
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(AkdError::Directory(DirectoryError::Cancelled(_)))
+        ));
+        assert!(akd
+            .key_history_cancellable(&AkdLabel::from("hello"), HistoryParams::Complete, token)
+            .await
+            .is_err());
+        assert!(akd.audit_cancellable(1, 2, token).await.is_err());
+    }
+
+    // Nothing was written by the cancelled publishes
+    assert_eq!(2, akd.get_epoch_hash().await?.epoch());
+    assert!(db.get_user_data(&AkdLabel::from("hello2")).await.is_err());
+
+    // The operations complete if the token is not cancelled in time
+    let token = CancellationToken::with_timeout(Duration::from_secs(60));
+    akd.publish_cancellable(
+        vec![(AkdLabel::from("hello2"), AkdValue::from("world"))],
+        None,
+        &token,
+    )
+    .await?;
+    let (history_proof, _) = akd
+        .key_history_cancellable(&AkdLabel::from("hello"), HistoryParams::Complete, &token)
+        .await?;
+    assert_eq!(2, history_proof.update_proofs.len());
+    akd.audit_cancellable(1, 3, &token).await?;
+
+    Ok(())
+}
+
+// Test the proofs of absence for labels which have not been registered
+test_config!(test_lookup_absent);
+async fn test_lookup_absent<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let vrf = HardCodedAkdVRF {};
+    let akd = Directory::<TC, _, _>::new(storage, vrf).await?;
+    let pk = akd.get_public_key().await?;
+
+    // There is no root hash to prove absence against before the first publish
+    assert!(matches!(
+        akd.lookup_absent(AkdLabel::from("hello")).await,
+        Err(AkdError::Directory(DirectoryError::InvalidEpoch(_)))
+    ));
+
+    akd.publish(vec![
+        (AkdLabel::from("hello"), AkdValue::from("world")),
+        (AkdLabel::from("hello2"), AkdValue::from("world2")),
+    ])
+    .await?;
+    akd.publish(vec![(AkdLabel::from("hello"), AkdValue::from("world3"))])
+        .await?;
+
+    let (proof, root_hash) = akd.lookup_absent(AkdLabel::from("hello3")).await?;
+    assert_eq!(2, root_hash.epoch());
+    lookup_absent_verify::<TC>(
+        pk.as_bytes(),
+        root_hash.hash(),
+        AkdLabel::from("hello3"),
+        proof.clone(),
+    )?;
+    // The proof is bound to the label it was generated for
+    assert!(lookup_absent_verify::<TC>(
+        pk.as_bytes(),
+        root_hash.hash(),
+        AkdLabel::from("hello"),
+        proof,
+    )
+    .is_err());
+
+    // The absence of registered labels cannot be proven
+    for label in ["hello", "hello2"] {
+        assert!(matches!(
+            akd.lookup_absent(AkdLabel::from(label)).await,
+            Err(AkdError::Directory(DirectoryError::LabelExists(_)))
+        ));
+    }
+
+    Ok(())
+}
+
+// Test that the nodes of lookup proofs are fetched from storage in batches, rather than
+// individually, even when the storage manager has no cache
+test_config!(test_lookup_storage_roundtrips);
+async fn test_lookup_storage_roundtrips<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db.clone());
+    let akd = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {}).await?;
+    for epoch in 0..3 {
+        let updates = (0..100)
+            .map(|i| {
+                (
+                    AkdLabel(format!("hello{i}").into_bytes()),
+                    AkdValue::Bytes(format!("world{epoch}").into_bytes()),
+                )
+            })
+            .collect();
+        akd.publish(updates).await?;
+    }
+    let pk = akd.get_public_key().await?;
+
+    // A fresh storage manager, so that only the reads of the lookups are counted
+    let storage = StorageManager::new_no_cache(db);
+    let akd = Directory::<TC, _, _>::new(storage.clone(), HardCodedAkdVRF {}).await?;
+    let reads_before = storage.stats().read_latency.samples;
+    let (proof, root_hash) = akd.lookup(AkdLabel::from("hello7")).await?;
+    // The aZKS and the value state, followed by the nodes on the path and their children
+    #[cfg(feature = "greedy_lookup_preload")]
+    assert_eq!(4, storage.stats().read_latency.samples - reads_before);
+    #[cfg(not(feature = "greedy_lookup_preload"))]
+    let _ = reads_before;
+    lookup_verify::<TC>(
+        pk.as_bytes(),
+        root_hash.hash(),
+        root_hash.epoch(),
+        AkdLabel::from("hello7"),
+        proof,
+    )?;
+
+    // The prefetched nodes are not reported as cache hits
+    assert_eq!(None, storage.stats().cache_hit_rate);
+
+    Ok(())
+}
+
+// Test that the insertion parallelism of a directory does not affect its root hashes
+test_config!(test_insert_parallelism);
+async fn test_insert_parallelism<TC: Configuration>() -> Result<(), AkdError> {
+    let mut root_hashes = vec![];
+    for num_tasks in [1, 8] {
+        let db = AsyncInMemoryDatabase::new();
+        let storage = StorageManager::new_no_cache(db);
+        let akd = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {})
+            .await?
+            .with_insert_parallelism(num_tasks);
+        let mut epoch_hashes = vec![];
+        for epoch in 0..3 {
+            let updates = (0..20)
+                .map(|i| {
+                    (
+                        AkdLabel(format!("hello{i}").into_bytes()),
+                        AkdValue::Bytes(format!("world{epoch}").into_bytes()),
+                    )
+                })
+                .collect();
+            epoch_hashes.push(akd.publish(updates).await?);
+        }
+        root_hashes.push(epoch_hashes);
+    }
+    assert_eq!(root_hashes[0], root_hashes[1]);
+
+    Ok(())
+}
+
+// Test that the VRF parallelism of a directory does not affect its root hashes, for any
+// number of tasks (including more tasks than evaluations)
+test_config!(test_vrf_parallelism);
+async fn test_vrf_parallelism<TC: Configuration>() -> Result<(), AkdError> {
+    let mut root_hashes = vec![];
+    for num_tasks in [None, Some(0), Some(3), Some(64)] {
+        let db = AsyncInMemoryDatabase::new();
+        let storage = StorageManager::new_no_cache(db);
+        let mut akd = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {}).await?;
+        if let Some(num_tasks) = num_tasks {
+            akd = akd.with_vrf_parallelism(num_tasks);
+        }
+        let mut epoch_hashes = vec![];
+        for epoch in 0..3 {
+            let updates = (0..20)
+                .map(|i| {
+                    (
+                        AkdLabel(format!("hello{i}").into_bytes()),
+                        AkdValue::Bytes(format!("world{epoch}").into_bytes()),
+                    )
+                })
+                .collect();
+            epoch_hashes.push(akd.publish(updates).await?);
+        }
+        root_hashes.push(epoch_hashes);
+    }
+    for epoch_hashes in root_hashes.iter().skip(1) {
+        assert_eq!(&root_hashes[0], epoch_hashes);
+    }
+
+    Ok(())
+}
+
+// Reassembling the segments of a streamed audit proof should result in the same
+// proof as the one generated by `audit`, for any segment size
+test_config!(test_audit_stream);
+async fn test_audit_stream<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let vrf = HardCodedAkdVRF {};
+    let akd = Directory::<TC, _, _>::new(storage, vrf).await?;
+
+    let mut root_hashes = vec![];
+    for epoch in 1..=4u64 {
+        let updates = (0..(5 * epoch))
+            .map(|i| {
+                (
+                    AkdLabel(format!("hello{i}").into_bytes()),
+                    AkdValue::Bytes(format!("world{epoch}").into_bytes()),
+                )
+            })
+            .collect();
+        root_hashes.push(akd.publish(updates).await?.hash());
+    }
+    let expected = akd.audit(1, 4).await?;
+    for segment_size in [1, 3, 1000] {
+        let segments = akd
+            .audit_stream(1, 4, segment_size)
+            .try_collect::<Vec<_>>()
+            .await?;
+        let mut proof = AppendOnlyProof {
+            proofs: vec![],
+            epochs: vec![],
+        };
+        let mut expected_index = 0;
+        for segment in segments {
+            assert!(segment.inserted.len() + segment.unchanged_nodes.len() <= segment_size);
+            assert_eq!(expected_index, segment.index);
+            if segment.index == 0 {
+                proof.epochs.push(segment.epoch);
+                proof.proofs.push(SingleAppendOnlyProof {
+                    inserted: vec![],
+                    unchanged_nodes: vec![],
+                });
+            }
+            assert_eq!(proof.epochs.last(), Some(&segment.epoch));
+            let single_proof = proof.proofs.last_mut().unwrap();
+            single_proof.inserted.extend(segment.inserted);
+            single_proof.unchanged_nodes.extend(segment.unchanged_nodes);
+            expected_index = if segment.last { 0 } else { segment.index + 1 };
+        }
+        assert_eq!(0, expected_index);
+        assert_eq!(expected, proof);
+        audit_verify::<TC>(root_hashes.clone(), proof).await?;
+    }
+
+    // Invalid epochs are reported when the stream is polled
+    let result = akd.audit_stream(3, 3, 10).try_collect::<Vec<_>>().await;
+    assert!(matches!(
+        result,
+        Err(AkdError::Directory(DirectoryError::InvalidEpoch(_)))
+    ));
+    let result = akd.audit_stream(1, 5, 10).try_collect::<Vec<_>>().await;
+    assert!(matches!(
+        result,
+        Err(AkdError::Directory(DirectoryError::InvalidEpoch(_)))
+    ));
+
+    Ok(())
+}
+
+// Test the incremental verification of streamed audit proofs, including the rejection
+// of tampered, reordered and incomplete streams
+test_config!(test_audit_verify_stream);
+async fn test_audit_verify_stream<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let vrf = HardCodedAkdVRF {};
+    let akd = Directory::<TC, _, _>::new(storage, vrf).await?;
+
+    // The first epoch has a single leaf, so that the root has a single child
+    let mut root_hashes = vec![akd
+        .publish(vec![(AkdLabel::from("hello"), AkdValue::from("world"))])
+        .await?
+        .hash()];
+    for epoch in 2..=4u64 {
+        let updates = (0..(5 * epoch))
+            .map(|i| {
+                (
+                    AkdLabel(format!("hello{i}").into_bytes()),
+                    AkdValue::Bytes(format!("world{epoch}").into_bytes()),
+                )
+            })
+            .collect();
+        root_hashes.push(akd.publish(updates).await?.hash());
+    }
+
+    for segment_size in [1, 4, 1000] {
+        audit_verify_stream::<TC>(root_hashes.clone(), akd.audit_stream(1, 4, segment_size))
+            .await?;
+    }
+    let segments = akd.audit_stream(1, 4, 4).try_collect::<Vec<_>>().await?;
+
+    // Verification can be resumed from a clone of the verifier
+    let mut verifier = AuditStreamVerifier::new(root_hashes.clone())?;
+    let (first, rest) = segments.split_at(segments.len() / 2);
+    for segment in first {
+        verifier.verify_segment::<TC>(segment.clone())?;
+    }
+    let mut resumed = verifier.clone();
+    for segment in rest {
+        resumed.verify_segment::<TC>(segment.clone())?;
+    }
+    resumed.finish()?;
+
+    // A tampered node
+    let mut tampered = segments.clone();
+    let segment = tampered
+        .iter_mut()
+        .find(|segment| !segment.inserted.is_empty())
+        .unwrap();
+    segment.inserted[0].value = crate::AzksValue([0u8; 32]);
+    let result = audit_verify_stream::<TC>(
+        root_hashes.clone(),
+        futures::stream::iter(tampered.into_iter().map(Ok)),
+    )
+    .await;
+    assert!(matches!(result, Err(AkdError::AzksErr(_))));
+
+    // Reordered segments
+    let mut reordered = segments.clone();
+    reordered.swap(0, 1);
+    let result = audit_verify_stream::<TC>(
+        root_hashes.clone(),
+        futures::stream::iter(reordered.into_iter().map(Ok)),
+    )
+    .await;
+    assert!(matches!(result, Err(AkdError::AuditErr(_))));
+
+    // Reordered nodes in a segment
+    let mut reordered = segments.clone();
+    let segment = reordered
+        .iter_mut()
+        .find(|segment| segment.unchanged_nodes.len() >= 2)
+        .unwrap();
+    segment.unchanged_nodes.swap(0, 1);
+    let result = audit_verify_stream::<TC>(
+        root_hashes.clone(),
+        futures::stream::iter(reordered.into_iter().map(Ok)),
+    )
+    .await;
+    assert!(matches!(result, Err(AkdError::AuditErr(_))));
+
+    // A missing segment at the end
+    let mut truncated = segments.clone();
+    truncated.pop();
+    let result = audit_verify_stream::<TC>(
+        root_hashes.clone(),
+        futures::stream::iter(truncated.into_iter().map(Ok)),
+    )
+    .await;
+    assert!(matches!(result, Err(AkdError::AuditErr(_))));
+
+    // Fewer hashes than epochs in the proof
+    let result = audit_verify_stream::<TC>(
+        root_hashes[..3].to_vec(),
+        futures::stream::iter(segments.into_iter().map(Ok)),
+    )
+    .await;
+    assert!(matches!(result, Err(AkdError::AuditErr(_))));
+
+    Ok(())
+}
+
+// Test that a compressed audit proof over a range of epochs verifies, while including
+// the unchanged nodes which are shared between epochs only once
+test_config!(test_audit_range_compressed);
+async fn test_audit_range_compressed<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let vrf = HardCodedAkdVRF {};
+    let akd = Directory::<TC, _, _>::new(storage, vrf).await?;
+
+    let updates = (0..30)
+        .map(|i| {
+            (
+                AkdLabel(format!("hello{i}").into_bytes()),
+                AkdValue::from("world"),
+            )
+        })
+        .collect();
+    let mut root_hashes = vec![akd.publish(updates).await?.hash()];
+    // Only a few labels are updated in each of the following epochs
+    for epoch in 2..=5u64 {
+        root_hashes.push(
+            akd.publish(vec![
+                (
+                    AkdLabel(format!("hello{epoch}").into_bytes()),
+                    AkdValue::Bytes(format!("world{epoch}").into_bytes()),
+                ),
+                (
+                    AkdLabel(format!("new{epoch}").into_bytes()),
+                    AkdValue::Bytes(format!("world{epoch}").into_bytes()),
+                ),
+            ])
+            .await?
+            .hash(),
+        );
+    }
+
+    let uncompressed = akd.audit(1, 5).await?;
+    let compressed = akd.audit_range_compressed(1, 5).await?;
+    assert_eq!(uncompressed.epochs, compressed.epochs);
+    let total_unchanged = uncompressed
+        .proofs
+        .iter()
+        .map(|proof| proof.unchanged_nodes.len())
+        .sum::<usize>();
+    assert!(compressed.unchanged_nodes.len() < total_unchanged);
+    for (proof, compressed_proof) in uncompressed.proofs.iter().zip(compressed.proofs.iter()) {
+        assert_eq!(proof.inserted, compressed_proof.inserted);
+        let unchanged_nodes = compressed_proof
+            .unchanged_nodes
+            .iter()
+            .map(|&index| compressed.unchanged_nodes[index as usize])
+            .collect::<Vec<_>>();
+        assert_eq!(proof.unchanged_nodes, unchanged_nodes);
+    }
+    audit_verify_compressed::<TC>(root_hashes.clone(), compressed.clone()).await?;
+
+    // An out-of-range node index
+    let mut invalid = compressed.clone();
+    invalid.proofs[0]
+        .unchanged_nodes
+        .push(compressed.unchanged_nodes.len() as u64);
+    let result = audit_verify_compressed::<TC>(root_hashes.clone(), invalid).await;
+    assert!(matches!(result, Err(AkdError::AuditErr(_))));
+
+    // A tampered node in the shared list
+    let mut invalid = compressed.clone();
+    invalid.unchanged_nodes[0].value = crate::AzksValue([0u8; 32]);
+    let result = audit_verify_compressed::<TC>(root_hashes.clone(), invalid).await;
+    assert!(matches!(result, Err(AkdError::AzksErr(_))));
+
+    // Mismatching number of hashes
+    let result = audit_verify_compressed::<TC>(root_hashes[1..].to_vec(), compressed).await;
+    assert!(matches!(result, Err(AkdError::AuditErr(_))));
+
+    Ok(())
+}
+
+// Test that an auditor can resume from a persisted state, verifying only the new epochs
+test_config!(test_auditor_resume);
+async fn test_auditor_resume<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let vrf = HardCodedAkdVRF {};
+    let akd = Directory::<TC, _, _>::new(storage, vrf).await?;
+
+    let mut root_hashes = vec![];
+    for epoch in 1..=5u64 {
+        root_hashes.push(
+            akd.publish(vec![(
+                AkdLabel(format!("hello{epoch}").into_bytes()),
+                AkdValue::from("world"),
+            )])
+            .await?
+            .hash(),
+        );
+    }
+
+    let state = AuditorState::new(1, root_hashes[0]);
+    let state = resume::<TC>(&state, root_hashes[1..3].to_vec(), akd.audit(1, 3).await?).await?;
+    assert_eq!(AuditorState::new(3, root_hashes[2]), state);
+
+    // The state survives a roundtrip through its encoding
+    let state = AuditorState::from_bytes(&state.to_bytes())?;
+    let state = resume::<TC>(&state, root_hashes[3..].to_vec(), akd.audit(3, 5).await?).await?;
+    assert_eq!(AuditorState::new(5, root_hashes[4]), state);
+    assert!(AuditorState::from_bytes(&state.to_bytes()[1..]).is_err());
+
+    // The proof must start at the epoch of the state
+    let state = AuditorState::new(2, root_hashes[1]);
+    assert!(
+        resume::<TC>(&state, root_hashes[3..].to_vec(), akd.audit(3, 5).await?)
+            .await
+            .is_err()
+    );
+    // The root hash of the state must match the proof
+    let state = AuditorState::new(3, root_hashes[1]);
+    assert!(
+        resume::<TC>(&state, root_hashes[3..].to_vec(), akd.audit(3, 5).await?)
+            .await
+            .is_err()
+    );
+
+    Ok(())
+}
+
+test_config!(test_parallel_audit_verify);
+async fn test_parallel_audit_verify<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let vrf = HardCodedAkdVRF {};
+    let akd = Directory::<TC, _, _>::new(storage, vrf).await?;
+
+    let mut root_hashes = vec![];
+    for epoch in 1..=3u64 {
+        let updates = (0..50)
+            .map(|i| {
+                (
+                    AkdLabel(format!("user{epoch}-{i}").into_bytes()),
+                    AkdValue::from("value"),
+                )
+            })
+            .collect();
+        root_hashes.push(akd.publish(updates).await?.hash());
+    }
+
+    let proof = akd.audit(1, 3).await?;
+    for num_tasks in [1, 2, 3, 16, 1000] {
+        for (i, single_proof) in proof.proofs.iter().enumerate() {
+            verify_consecutive_append_only_in_parallel::<TC>(
+                single_proof,
+                root_hashes[i],
+                root_hashes[i + 1],
+                proof.epochs[i] + 1,
+                num_tasks,
+            )
+            .await?;
+        }
+    }
+
+    // The order of the nodes in the proof does not matter
+    let mut single_proof = proof.proofs[0].clone();
+    single_proof.unchanged_nodes.reverse();
+    single_proof.inserted.reverse();
+    verify_consecutive_append_only_in_parallel::<TC>(
+        &single_proof,
+        root_hashes[0],
+        root_hashes[1],
+        proof.epochs[0] + 1,
+        4,
+    )
+    .await?;
+
+    // A tampered node fails to verify
+    single_proof.unchanged_nodes[0].value = crate::AzksValue([0u8; 32]);
+    assert!(verify_consecutive_append_only_in_parallel::<TC>(
+        &single_proof,
+        root_hashes[0],
+        root_hashes[1],
+        proof.epochs[0] + 1,
+        4,
+    )
+    .await
+    .is_err());
+
+    // The epoch is committed to by the inserted nodes
+    assert!(verify_consecutive_append_only_in_parallel::<TC>(
+        &proof.proofs[0],
+        root_hashes[0],
+        root_hashes[1],
+        proof.epochs[0] + 2,
+        4,
+    )
+    .await
+    .is_err());
+
+    Ok(())
+}
+
+// An epoch publisher which always fails
+struct FailingEpochPublisher;
+
+#[async_trait::async_trait]
+impl EpochPublisher for FailingEpochPublisher {
+    async fn publish_epoch(
+        &self,
+        _epoch: u64,
+        _root_hash: crate::Digest,
+        _signature: Option<&[u8]>,
+    ) -> Result<(), EpochPublisherError> {
+        Err(EpochPublisherError::Delivery("unreachable".to_string()))
+    }
+}
+
+test_config!(test_epoch_publishers);
+async fn test_epoch_publishers<TC: Configuration>() -> Result<(), AkdError> {
+    static LOG_ID: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+    let log_path = std::env::temp_dir().join(format!(
+        "akd_epoch_log_{}_{}",
+        std::process::id(),
+        LOG_ID.fetch_add(1, std::sync::atomic::Ordering::SeqCst)
+    ));
+    let _ = std::fs::remove_file(&log_path);
+
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let vrf = HardCodedAkdVRF {};
+    let signer = HardCodedEpochSigner {};
+    let public_key = signer.get_public_key().await?;
+    let akd = Directory::<TC, _, _>::new(storage, vrf)
+        .await?
+        .with_epoch_signer(signer)
+        // A failing publisher does not fail the publish, nor prevent the other publishers
+        .with_epoch_publisher(FailingEpochPublisher)
+        .with_epoch_publisher(LogFileEpochPublisher::new(&log_path).unwrap());
+
+    let mut roots = vec![];
+    for epoch in 1..=3u64 {
+        roots.push(
+            akd.publish(vec![(
+                AkdLabel(format!("hello{epoch}").into_bytes()),
+                AkdValue::from("world"),
+            )])
+            .await?,
+        );
+    }
+
+    let entries = LogFileEpochPublisher::read_log(&log_path).unwrap();
+    assert_eq!(roots.len(), entries.len());
+    for (root, entry) in roots.iter().zip(entries.iter()) {
+        assert_eq!(root.epoch(), entry.epoch);
+        assert_eq!(root.hash(), entry.root_hash);
+        crate::client::verify_epoch_signature(
+            &public_key,
+            entry.epoch,
+            entry.root_hash,
+            entry.signature.as_ref().expect("Missing signature"),
+        )?;
+    }
+
+    // A reopened log only accepts later epochs
+    let publisher = LogFileEpochPublisher::new(&log_path).unwrap();
+    assert_eq!(
+        Err(EpochPublisherError::OutOfOrder {
+            latest: 3,
+            epoch: 3
+        }),
+        publisher.publish_epoch(3, [0u8; 32], None).await
+    );
+    publisher.publish_epoch(4, [1u8; 32], None).await.unwrap();
+    let entries = LogFileEpochPublisher::read_log(&log_path).unwrap();
+    assert_eq!(
+        Some(&EpochLogEntry {
+            epoch: 4,
+            root_hash: [1u8; 32],
+            signature: None
+        }),
+        entries.last()
+    );
+
+    std::fs::remove_file(&log_path).unwrap();
+    Ok(())
+}
+
+// Checks that the proofs round-trip through JSON, with their byte fields hex-encoded
+test_config!(test_proof_json_serialization);
+async fn test_proof_json_serialization<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let vrf = HardCodedAkdVRF {};
+    let akd = Directory::<TC, _, _>::new(storage, vrf).await?;
+    for value in ["world", "world2", "world3"] {
+        akd.publish(vec![
+            (AkdLabel::from("hello"), AkdValue::from(value)),
+            (AkdLabel::from(value), AkdValue::from("hello")),
+        ])
+        .await?;
+    }
+    let vrf_pk = akd.get_public_key().await?;
+
+    let (lookup_proof, root_hash) = akd.lookup(AkdLabel::from("hello")).await?;
+    let json = serde_json::to_string(&lookup_proof).unwrap();
+    assert!(json.contains(&format!(
+        "\"commitment_nonce\":\"{}\"",
+        hex::encode_upper(&lookup_proof.commitment_nonce)
+    )));
+    let decoded: crate::LookupProof = serde_json::from_str(&json).unwrap();
+    assert_eq!(lookup_proof, decoded);
+    lookup_verify::<TC>(
+        vrf_pk.as_bytes(),
+        root_hash.hash(),
+        root_hash.epoch(),
+        AkdLabel::from("hello"),
+        decoded,
+    )?;
+
+    let (history_proof, _) = akd
+        .key_history(&AkdLabel::from("hello"), HistoryParams::default())
+        .await?;
+    let json = serde_json::to_string(&history_proof).unwrap();
+    let decoded: crate::HistoryProof = serde_json::from_str(&json).unwrap();
+    assert_eq!(history_proof, decoded);
+    key_history_verify::<TC>(
+        vrf_pk.as_bytes(),
+        root_hash.hash(),
+        root_hash.epoch(),
+        AkdLabel::from("hello"),
+        decoded,
+        HistoryVerificationParams::default(),
+    )?;
+
+    let audit_proof = akd.audit(1, 3).await?;
+    let json = serde_json::to_string(&audit_proof).unwrap();
+    let decoded: crate::AppendOnlyProof = serde_json::from_str(&json).unwrap();
+    assert_eq!(audit_proof, decoded);
+
+    Ok(())
+}
+
+test_config!(test_batch_lookup_verify);
+async fn test_batch_lookup_verify<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let vrf = HardCodedAkdVRF {};
+    let akd = Directory::<TC, _, _>::new(storage, vrf).await?;
+    let labels = (0..20)
+        .map(|i| AkdLabel(format!("label {i}").into_bytes()))
+        .collect::<Vec<_>>();
+    for value in ["value 1", "value 2"] {
+        akd.publish(
+            labels
+                .iter()
+                .map(|label| (label.clone(), AkdValue::from(value)))
+                .collect(),
+        )
+        .await?;
+    }
+    let vrf_pk = akd.get_public_key().await?;
+
+    let mut proofs = vec![];
+    let mut root_hash = None;
+    for label in labels.iter() {
+        let (lookup_proof, epoch_hash) = akd.lookup(label.clone()).await?;
+        proofs.push((label.clone(), lookup_proof));
+        root_hash = Some(epoch_hash);
+    }
+    let root_hash = root_hash.unwrap();
+
+    let results = crate::client::batch_lookup_verify::<TC>(
+        vrf_pk.as_bytes(),
+        root_hash.hash(),
+        root_hash.epoch(),
+        proofs.clone(),
+    );
+    assert_eq!(labels.len(), results.len());
+    for ((label, proof), result) in proofs.iter().zip(results) {
+        assert_eq!(
+            lookup_verify::<TC>(
+                vrf_pk.as_bytes(),
+                root_hash.hash(),
+                root_hash.epoch(),
+                label.clone(),
+                proof.clone(),
+            )?,
+            result?
+        );
+    }
+
+    // A proof whose siblings close to the root have been tampered with must not verify, even
+    // though the genuine siblings were verified by the preceding proofs
+    let mut tampered = proofs.clone();
+    let sibling_proofs = &mut tampered[10].1.existence_proof.sibling_proofs;
+    sibling_proofs[0].siblings[0].value = crate::AzksValue([0u8; 32]);
+    tampered[12].1.value = AkdValue::from("value 3");
+    let results = crate::client::batch_lookup_verify::<TC>(
+        vrf_pk.as_bytes(),
+        root_hash.hash(),
+        root_hash.epoch(),
+        tampered,
+    );
+    for (i, result) in results.into_iter().enumerate() {
+        assert_eq!(i == 10 || i == 12, result.is_err());
+    }
+
+    Ok(())
+}
+
+// Test that a directory whose configuration uses the P-256 VRF suite produces proofs which
+// verify with its P-256 public key (and not with the ed25519 public key of the same private key)
+#[tokio::test]
+async fn test_p256_vrf() -> Result<(), AkdError> {
+    type TC = crate::ExperimentalConfiguration<crate::ExampleLabel, crate::ecvrf::P256Sha256Tai>;
+
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let vrf = HardCodedAkdVRF {};
+    let akd = Directory::<TC, _, _>::new(storage, vrf).await?;
+
+    let label = AkdLabel::from("hello");
+    let mut root_hashes = vec![];
+    for value in ["world", "world 2", "world 3"] {
+        let epoch_hash = akd
+            .publish(vec![(label.clone(), AkdValue::from(value))])
+            .await?;
+        root_hashes.push(epoch_hash.hash());
+    }
+
+    let vrf_pk = akd.get_public_key_bytes().await?;
+    assert_eq!(33, vrf_pk.len());
+    assert!(akd.get_public_key().await.is_err());
+
+    let (lookup_proof, epoch_hash) = akd.lookup(label.clone()).await?;
+    let result = lookup_verify::<TC>(
+        &vrf_pk,
+        epoch_hash.hash(),
+        epoch_hash.epoch(),
+        label.clone(),
+        lookup_proof.clone(),
+    )?;
+    assert_eq!(AkdValue::from("world 3"), result.value);
+
+    let ed25519_pk = HardCodedAkdVRF {}.get_vrf_public_key().await?;
+    assert!(lookup_verify::<TC>(
+        ed25519_pk.as_bytes(),
+        epoch_hash.hash(),
+        epoch_hash.epoch(),
+        label.clone(),
+        lookup_proof,
+    )
+    .is_err());
+
+    let (history_proof, _) = akd.key_history(&label, HistoryParams::Complete).await?;
+    let results = key_history_verify::<TC>(
+        &vrf_pk,
+        epoch_hash.hash(),
+        epoch_hash.epoch(),
+        label,
+        history_proof,
+        HistoryVerificationParams::default(),
+    )?;
+    assert_eq!(3, results.len());
+
+    let audit_proof = akd.audit(1, 3).await?;
+    audit_verify::<TC>(root_hashes, audit_proof).await?;
+
+    Ok(())
+}
+
+/// A [VRFKeyStorage] which caches the VRF private key in memory, until its secrets are destroyed
+#[derive(Clone)]
+struct InMemoryKeyVrf(std::sync::Arc<std::sync::Mutex<Option<zeroize::Zeroizing<Vec<u8>>>>>);
+
+impl InMemoryKeyVrf {
+    fn new(private_key: Vec<u8>) -> Self {
+        Self(std::sync::Arc::new(std::sync::Mutex::new(Some(
+            zeroize::Zeroizing::new(private_key),
+        ))))
+    }
+}
+
+#[async_trait::async_trait]
+impl VRFKeyStorage for InMemoryKeyVrf {
+    async fn retrieve(&self) -> Result<Vec<u8>, VrfError> {
+        self.0
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|private_key| private_key.to_vec())
+            .ok_or_else(|| VrfError::SigningKey("The secrets were destroyed".to_string()))
+    }
+
+    async fn destroy_secrets(&self) -> Result<(), VrfError> {
+        // The private key is wiped when it is dropped
+        self.0.lock().unwrap().take();
+        Ok(())
+    }
+}
+
+/// A [RemoteVrfService] holding the key of [HardCodedAkdVRF], which records the sizes of the
+/// batches it receives and can be made to return proofs for the wrong inputs
+#[derive(Clone)]
+struct MockRemoteVrfService<V: crate::ecvrf::VrfScheme> {
+    private_key: Vec<u8>,
+    batch_sizes: std::sync::Arc<std::sync::Mutex<Vec<usize>>>,
+    faulty: bool,
+    _scheme: std::marker::PhantomData<V>,
+}
+
+impl<V: crate::ecvrf::VrfScheme> MockRemoteVrfService<V> {
+    fn new(private_key: Vec<u8>) -> Self {
+        Self {
+            private_key,
+            batch_sizes: Default::default(),
+            faulty: false,
+            _scheme: std::marker::PhantomData,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<V: crate::ecvrf::VrfScheme> RemoteVrfService for MockRemoteVrfService<V> {
+    async fn public_key(&self) -> Result<Vec<u8>, VrfError> {
+        Ok(V::public_key(&V::expand_private_key(&self.private_key)?))
+    }
+
+    async fn commitment_secret(&self) -> Result<Vec<u8>, VrfError> {
+        Ok(self.private_key.clone())
+    }
+
+    async fn prove_batch(&self, alphas: Vec<Vec<u8>>) -> Result<Vec<Vec<u8>>, VrfError> {
+        self.batch_sizes.lock().unwrap().push(alphas.len());
+        let key = V::expand_private_key(&self.private_key)?;
+        Ok(alphas
+            .iter()
+            .map(|alpha| {
+                if self.faulty {
+                    V::prove(&key, b"wrong input")
+                } else {
+                    V::prove(&key, alpha)
+                }
+            })
+            .collect())
+    }
+}
+
+// Checks that a directory whose VRF proofs are produced by a remote service requests them in
+// batches, and produces the same tree as one holding the key
+test_config!(test_remote_vrf_signer);
+async fn test_remote_vrf_signer<TC: Configuration>() -> Result<(), AkdError> {
+    let service = MockRemoteVrfService::<TC::Vrf>::new(HardCodedAkdVRF {}.retrieve().await?);
+    let signer = RemoteVrfSigner::new(service.clone()).with_max_batch_size(10);
+    let akd = Directory::<TC, _, _>::new(
+        StorageManager::new_no_cache(AsyncInMemoryDatabase::new()),
+        signer,
+    )
+    .await?;
+    let local_akd = Directory::<TC, _, _>::new(
+        StorageManager::new_no_cache(AsyncInMemoryDatabase::new()),
+        HardCodedAkdVRF {},
+    )
+    .await?;
+
+    let updates = (0..25)
+        .map(|i| {
+            (
+                AkdLabel(format!("user {i}").into_bytes()),
+                AkdValue::Bytes(format!("value {i}").into_bytes()),
+            )
+        })
+        .collect::<Vec<_>>();
+    let epoch_hash = akd.publish(updates.clone()).await?;
+    // The batches may reach the service in any order when they are sent concurrently
+    let mut batch_sizes = service.batch_sizes.lock().unwrap().clone();
+    batch_sizes.sort_unstable();
+    assert_eq!(vec![5, 10, 10], batch_sizes);
+    assert_eq!(local_akd.publish(updates).await?, epoch_hash);
+
+    let vrf_pk = akd.get_public_key().await?;
+    assert_eq!(HardCodedAkdVRF {}.get_vrf_public_key().await?, vrf_pk);
+    let label = AkdLabel::from("user 3");
+    let (lookup_proof, epoch_hash) = akd.lookup(label.clone()).await?;
+    lookup_verify::<TC>(
+        vrf_pk.as_bytes(),
+        epoch_hash.hash(),
+        epoch_hash.epoch(),
+        label.clone(),
+        lookup_proof,
+    )?;
+    let (history_proof, _) = akd.key_history(&label, HistoryParams::Complete).await?;
+    key_history_verify::<TC>(
+        vrf_pk.as_bytes(),
+        epoch_hash.hash(),
+        epoch_hash.epoch(),
+        label.clone(),
+        history_proof,
+        HistoryVerificationParams::default(),
+    )?;
+
+    // The proofs of a faulty service are rejected, unless their verification is disabled
+    let faulty_service = MockRemoteVrfService {
+        faulty: true,
+        ..service
+    };
+    let signer = RemoteVrfSigner::new(faulty_service.clone());
+    assert!(signer
+        .get_node_label::<TC>(&label, VersionFreshness::Fresh, 1)
+        .await
+        .is_err());
+    let signer = signer.with_proof_verification(false);
+    assert!(signer
+        .get_node_label::<TC>(&label, VersionFreshness::Fresh, 1)
+        .await
+        .is_ok());
+
+    Ok(())
+}
+
+// Checks that the labels are carried over to a new VRF key by a key rotation, and that the
+// continuity of their history can be verified with a key rotation proof
+test_config!(test_vrf_key_rotation);
+async fn test_vrf_key_rotation<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let previous_vrf = RemoteVrfSigner::new(MockRemoteVrfService::<TC::Vrf>::new(vec![1u8; 32]));
+    let new_vrf = RemoteVrfSigner::new(MockRemoteVrfService::<TC::Vrf>::new(vec![2u8; 32]));
+    let akd =
+        Directory::<TC, _, _>::new(StorageManager::new_no_cache(db.clone()), previous_vrf).await?;
+
+    let (alice, bob) = (AkdLabel::from("alice"), AkdLabel::from("bob"));
+    let mut root_hashes = vec![akd
+        .publish(vec![
+            (alice.clone(), AkdValue::from("alice 1")),
+            (bob.clone(), AkdValue::from("bob 1")),
+        ])
+        .await?
+        .hash()];
+    for value in ["alice 2", "alice 3"] {
+        let epoch_hash = akd
+            .publish(vec![(alice.clone(), AkdValue::from(value))])
+            .await?;
+        root_hashes.push(epoch_hash.hash());
+    }
+
+    let (rotated, epoch_hash) = akd.rotate_vrf_key(new_vrf.clone()).await?;
+    assert_eq!(4, epoch_hash.epoch());
+    root_hashes.push(epoch_hash.hash());
+    let epoch_hash = rotated
+        .publish(vec![(bob.clone(), AkdValue::from("bob 2"))])
+        .await?;
+    root_hashes.push(epoch_hash.hash());
+
+    let previous_pk = akd.get_public_key_bytes().await?;
+    let new_pk = rotated.get_public_key_bytes().await?;
+    assert_ne!(previous_pk, new_pk);
+
+    // The versions published before the rotation are reported at the rotation epoch
+    let (lookup_proof, epoch_hash) = rotated.lookup(alice.clone()).await?;
+    let result = lookup_verify::<TC>(
+        &new_pk,
+        epoch_hash.hash(),
+        epoch_hash.epoch(),
+        alice.clone(),
+        lookup_proof.clone(),
+    )?;
+    assert_eq!((4, 3), (result.epoch, result.version));
+    assert_eq!(AkdValue::from("alice 3"), result.value);
+
+    // During the transition window, the lookup proofs carry a cross-certification of the new
+    // key, so that clients which only know the previous key can verify them
+    let certificate = lookup_proof
+        .vrf_rotation
+        .clone()
+        .expect("The lookup proof should carry the rotation certificate");
+    assert_eq!(
+        Some(certificate.clone()),
+        rotated.vrf_rotation_certificate().await?
+    );
+    assert_eq!(
+        new_pk.as_slice(),
+        verify_vrf_rotation_certificate::<TC>(&previous_pk, epoch_hash.epoch(), &certificate)?
+    );
+    assert_eq!(
+        result,
+        lookup_verify::<TC>(
+            &previous_pk,
+            epoch_hash.hash(),
+            epoch_hash.epoch(),
+            alice.clone(),
+            lookup_proof.clone(),
+        )?
+    );
+    assert!(verify_vrf_rotation_certificate::<TC>(&previous_pk, 3, &certificate).is_err());
+
+    // The label derivations are checked against the new key as well, whichever key is known
+    for known_pk in [&previous_pk, &new_pk] {
+        let derivations =
+            lookup_label_derivations::<TC>(known_pk, epoch_hash.epoch(), &alice, &lookup_proof)?;
+        assert_eq!(3, derivations.len());
+        assert_eq!(
+            new_vrf
+                .get_node_label::<TC>(&alice, VersionFreshness::Fresh, 3)
+                .await?,
+            derivations[0].node_label
+        );
+    }
+    assert!(lookup_label_derivations::<TC>(&previous_pk, 3, &alice, &lookup_proof).is_err());
+    let mut tampered = certificate.clone();
+    tampered.new_vrf_public_key = vec![3u8; new_pk.len()];
+    assert!(
+        verify_vrf_rotation_certificate::<TC>(&previous_pk, epoch_hash.epoch(), &tampered).is_err()
+    );
+    let mut tampered = certificate.clone();
+    tampered.rotation_epoch = 3;
+    assert!(
+        verify_vrf_rotation_certificate::<TC>(&previous_pk, epoch_hash.epoch(), &tampered).is_err()
+    );
+    let mut tampered = certificate;
+    tampered.previous_key_proof = tampered.new_key_proof.clone();
+    assert!(
+        verify_vrf_rotation_certificate::<TC>(&previous_pk, epoch_hash.epoch(), &tampered).is_err()
+    );
+    let mut without_certificate = lookup_proof;
+    without_certificate.vrf_rotation = None;
+    assert!(lookup_verify::<TC>(
+        &previous_pk,
+        epoch_hash.hash(),
+        epoch_hash.epoch(),
+        alice.clone(),
+        without_certificate,
+    )
+    .is_err());
+
+    for (label, expected) in [
+        (&alice, vec![(4, 3), (4, 2), (4, 1)]),
+        (&bob, vec![(5, 2), (4, 1)]),
+    ] {
+        let (history_proof, _) = rotated.key_history(label, HistoryParams::Complete).await?;
+        let results = key_history_verify::<TC>(
+            &new_pk,
+            epoch_hash.hash(),
+            epoch_hash.epoch(),
+            label.clone(),
+            history_proof,
+            HistoryVerificationParams::default(),
+        )?;
+        assert_eq!(
+            expected,
+            results
+                .iter()
+                .map(|result| (result.epoch, result.version))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    // The key rotation proofs recover the epochs at which the versions were published
+    for (label, expected) in [(&alice, (3, 3, "alice 3")), (&bob, (1, 1, "bob 1"))] {
+        let (proof, epoch_hash) = rotated.key_rotation_proof(label).await?;
+        let result = key_rotation_verify::<TC>(
+            &previous_pk,
+            &new_pk,
+            epoch_hash.hash(),
+            epoch_hash.epoch(),
+            label.clone(),
+            proof.clone(),
+        )?;
+        assert_eq!(
+            (expected.0, expected.1, AkdValue::from(expected.2)),
+            (result.epoch, result.version, result.value)
+        );
+
+        assert!(key_rotation_verify::<TC>(
+            &new_pk,
+            &previous_pk,
+            epoch_hash.hash(),
+            epoch_hash.epoch(),
+            label.clone(),
+            proof.clone(),
+        )
+        .is_err());
+        let mut tampered = proof;
+        tampered.new_key_update_proof.value = AkdValue::from("mallory");
+        assert!(matches!(
+            key_rotation_verify::<TC>(
+                &previous_pk,
+                &new_pk,
+                epoch_hash.hash(),
+                epoch_hash.epoch(),
+                label.clone(),
+                tampered,
+            ),
+            Err(VerificationError::KeyRotation(_))
+        ));
+    }
+
+    audit_verify::<TC>(root_hashes, rotated.audit(1, 5).await?).await?;
+
+    // Another instance with the new key serves the same proofs once configured with the
+    // rotation, but can only produce key rotation proofs if it holds the previous key
+    let rotation = VrfKeyRotation {
+        rotation_epoch: 4,
+        previous_vrf: None,
+        previous_rotation_epoch: 0,
+    };
+    let other = Directory::<TC, _, _>::new(StorageManager::new_no_cache(db), new_vrf)
+        .await?
+        .with_vrf_key_rotation(rotation);
+    let (lookup_proof, epoch_hash) = other.lookup(alice.clone()).await?;
+    assert_eq!(None, lookup_proof.vrf_rotation);
+    lookup_verify::<TC>(
+        &new_pk,
+        epoch_hash.hash(),
+        epoch_hash.epoch(),
+        alice.clone(),
+        lookup_proof.clone(),
+    )?;
+    assert!(lookup_verify::<TC>(
+        &previous_pk,
+        epoch_hash.hash(),
+        epoch_hash.epoch(),
+        alice.clone(),
+        lookup_proof,
+    )
+    .is_err());
+    assert!(matches!(
+        other.key_rotation_proof(&alice).await,
+        Err(AkdError::Directory(DirectoryError::KeyRotation(_)))
+    ));
+    // The epochs after the rotation can be rolled back, but not the rotation itself
+    assert_eq!(4, other.rollback_last_epoch().await?.to_epoch);
+    assert!(matches!(
+        other.rollback_last_epoch().await,
+        Err(AkdError::Directory(DirectoryError::InvalidEpoch(msg))) if msg.contains("rotation")
+    ));
+    assert_eq!(4, other.get_epoch_hash().await?.epoch());
+
+    Ok(())
+}
+
+// Checks that the secrets of a directory's key storage (and of the cache wrapping it) are wiped
+// by destroy_secrets, after which neither the directory's clones can publish or generate proofs
+test_config!(test_destroy_secrets);
+async fn test_destroy_secrets<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db);
+    let vrf = CachingVrf::new(
+        InMemoryKeyVrf::new(HardCodedAkdVRF {}.retrieve().await?),
+        100,
+    );
+    let akd = Directory::<TC, _, _>::new(storage, vrf.clone()).await?;
+    akd.publish(vec![(AkdLabel::from("hello"), AkdValue::from("world"))])
+        .await?;
+    assert_eq!(1, vrf.stats().entries);
+
+    let clone = akd.clone();
+    akd.destroy_secrets().await?;
+    assert_eq!(0, vrf.stats().entries);
+    assert!(vrf.inner().0.lock().unwrap().is_none());
+
+    assert!(matches!(
+        clone
+            .publish(vec![(AkdLabel::from("hello"), AkdValue::from("world2"))])
+            .await,
+        Err(AkdError::Vrf(VrfError::SigningKey(_)))
+    ));
+    assert!(matches!(
+        clone.lookup(AkdLabel::from("hello")).await,
+        Err(AkdError::Vrf(VrfError::SigningKey(_)))
+    ));
+    Ok(())
+}
+
+// Checks that the node labels cached by a CachingVrf are those derived by the key storage it
+// wraps, and that a rotation to a new key does not reuse the labels of the previous one
+test_config!(test_caching_vrf);
+async fn test_caching_vrf<TC: Configuration>() -> Result<(), AkdError> {
+    let previous_vrf = CachingVrf::new(
+        RemoteVrfSigner::new(MockRemoteVrfService::<TC::Vrf>::new(vec![1u8; 32])),
+        10,
+    );
+    let akd = Directory::<TC, _, _>::new(
+        StorageManager::new_no_cache(AsyncInMemoryDatabase::new()),
+        previous_vrf.clone(),
+    )
+    .await?;
+
+    let label = AkdLabel::from("user 3");
+    let node_label = previous_vrf
+        .get_node_label::<TC>(&label, VersionFreshness::Fresh, 1)
+        .await?;
+    assert_eq!(
+        previous_vrf
+            .inner()
+            .get_node_label::<TC>(&label, VersionFreshness::Fresh, 1)
+            .await?,
+        node_label
+    );
+    assert_eq!((0, 1, 1), {
+        let stats = previous_vrf.stats();
+        (stats.hits, stats.misses, stats.entries)
+    });
+
+    // The label derived above is served from the cache by the publish, while the others are
+    // derived and evict each other once the cache is full
+    let updates = (0..25)
+        .map(|i| {
+            (
+                AkdLabel(format!("user {i}").into_bytes()),
+                AkdValue::Bytes(format!("value {i}").into_bytes()),
+            )
+        })
+        .collect::<Vec<_>>();
+    akd.publish(updates).await?;
+    let stats = previous_vrf.stats();
+    assert_eq!(
+        (1, 25, 15, 10),
+        (stats.hits, stats.misses, stats.evictions, stats.entries)
+    );
+    assert_eq!(Some(1.0 / 26.0), stats.hit_rate);
+
+    let (lookup_proof, epoch_hash) = akd.lookup(label.clone()).await?;
+    lookup_verify::<TC>(
+        &akd.get_public_key_bytes().await?,
+        epoch_hash.hash(),
+        epoch_hash.epoch(),
+        label.clone(),
+        lookup_proof,
+    )?;
+
+    let new_vrf = CachingVrf::new(
+        RemoteVrfSigner::new(MockRemoteVrfService::<TC::Vrf>::new(vec![2u8; 32])),
+        10,
+    );
+    let (rotated, _) = akd.rotate_vrf_key(new_vrf.clone()).await?;
+    assert_ne!(
+        node_label,
+        new_vrf
+            .get_node_label::<TC>(&label, VersionFreshness::Fresh, 1)
+            .await?
+    );
+    let (lookup_proof, epoch_hash) = rotated.lookup(label.clone()).await?;
+    lookup_verify::<TC>(
+        &rotated.get_public_key_bytes().await?,
+        epoch_hash.hash(),
+        epoch_hash.epoch(),
+        label,
+        lookup_proof,
+    )?;
+
+    previous_vrf.invalidate();
+    assert_eq!(0, previous_vrf.stats().entries);
+
+    Ok(())
+}
+
+/*
+=========== Test Helpers ===========
+*/
+
+async fn async_poll_helper_proof<TC: Configuration, T: Database + 'static, V: VRFKeyStorage>(
+    reader: &ReadOnlyDirectory<TC, T, V>,
+    value: AkdValue,
+) -> Result<(), AkdError> {
+    // reader should read "hello" and this will populate the "cache" a log
+    let (lookup_proof, root_hash) = reader.lookup(AkdLabel::from("hello")).await?;
+    assert_eq!(value, lookup_proof.value);
+    let pk = reader.get_public_key().await?;
+    lookup_verify::<TC>(
+        pk.as_bytes(),
+        root_hash.hash(),
+        root_hash.epoch(),
         AkdLabel::from("hello"),
         lookup_proof,
     )?;
     Ok(())
 }
+
+test_config!(test_proof_cache);
+async fn test_proof_cache<TC: Configuration>() -> Result<(), AkdError> {
+    let storage = StorageManager::new_no_cache(AsyncInMemoryDatabase::new());
+    let akd = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {})
+        .await?
+        .with_proof_cache(2);
+    let labels = ["hello", "hello2", "hello3"].map(AkdLabel::from);
+    akd.publish(
+        labels
+            .iter()
+            .map(|label| (label.clone(), AkdValue::from("world")))
+            .collect(),
+    )
+    .await?;
+
+    // The repeated lookup of a label is served from the cache, including by the clones
+    let (lookup_proof, epoch_hash) = akd.lookup(labels[0].clone()).await?;
+    assert_eq!(
+        (lookup_proof.clone(), epoch_hash.clone()),
+        akd.clone().lookup(labels[0].clone()).await?
+    );
+    let stats = akd.proof_cache_stats().unwrap();
+    assert_eq!((1, 1, 1), (stats.hits, stats.misses, stats.entries));
+    assert_eq!(Some(0.5), stats.hit_rate);
+
+    // Once full, the proofs cached first are evicted first
+    akd.lookup(labels[1].clone()).await?;
+    akd.lookup(labels[2].clone()).await?;
+    akd.lookup(labels[0].clone()).await?;
+    let stats = akd.proof_cache_stats().unwrap();
+    assert_eq!(
+        (1, 4, 2, 2),
+        (stats.hits, stats.misses, stats.evictions, stats.entries)
+    );
+
+    // A publish empties the cache, so that the new value is served
+    akd.publish(vec![(labels[0].clone(), AkdValue::from("world2"))])
+        .await?;
+    assert_eq!(0, akd.proof_cache_stats().unwrap().entries);
+    let (lookup_proof, epoch_hash) = akd.lookup(labels[0].clone()).await?;
+    assert_eq!(AkdValue::from("world2"), lookup_proof.value);
+    lookup_verify::<TC>(
+        &akd.get_public_key_bytes().await?,
+        epoch_hash.hash(),
+        epoch_hash.epoch(),
+        labels[0].clone(),
+        lookup_proof,
+    )?;
+
+    // As does a rollback, after which the epoch is published again with different contents
+    akd.rollback_last_epoch().await?;
+    assert_eq!(0, akd.proof_cache_stats().unwrap().entries);
+    akd.publish(vec![(labels[0].clone(), AkdValue::from("world3"))])
+        .await?;
+    akd.lookup(labels[0].clone()).await?;
+    let (lookup_proof, epoch_hash) = akd.lookup(labels[0].clone()).await?;
+    assert_eq!(AkdValue::from("world3"), lookup_proof.value);
+    assert_eq!(2, epoch_hash.epoch());
+
+    // Without a cache, there are no statistics
+    let storage = StorageManager::new_no_cache(AsyncInMemoryDatabase::new());
+    let uncached = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {}).await?;
+    assert_eq!(None, uncached.proof_cache_stats());
+    Ok(())
+}
+
+// Test that a publish whose transaction exceeds the memory cap builds the same tree
+test_config!(test_publish_with_transaction_memory_cap);
+async fn test_publish_with_transaction_memory_cap<TC: Configuration>() -> Result<(), AkdError> {
+    let storage = StorageManager::new_no_cache(AsyncInMemoryDatabase::new());
+    let uncapped = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {}).await?;
+    let storage = StorageManager::new(AsyncInMemoryDatabase::new(), None, None, None)
+        .with_transaction_memory_cap(4096);
+    let capped = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {}).await?;
+
+    for epoch in 1..=3 {
+        let updates = (0..20)
+            .map(|i| {
+                (
+                    AkdLabel::from(&format!("label{i}")),
+                    AkdValue::from(&format!("value{epoch}")),
+                )
+            })
+            .collect::<Vec<_>>();
+        let epoch_hash = uncapped.publish(updates.clone()).await?;
+        assert_eq!(epoch_hash, capped.publish(updates).await?);
+    }
+
+    let label = AkdLabel::from("label7");
+    let (lookup_proof, epoch_hash) = capped.lookup(label.clone()).await?;
+    let result = lookup_verify::<TC>(
+        &capped.get_public_key_bytes().await?,
+        epoch_hash.hash(),
+        epoch_hash.epoch(),
+        label,
+        lookup_proof,
+    )?;
+    assert_eq!(AkdValue::from("value3"), result.value);
+    Ok(())
+}
+
+test_config!(test_precomputed_lookup_proofs);
+async fn test_precomputed_lookup_proofs<TC: Configuration>() -> Result<(), AkdError> {
+    let db = AsyncInMemoryDatabase::new();
+    let storage = StorageManager::new_no_cache(db.clone());
+    let akd = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {}).await?;
+    let labels = ["hello", "hello2", "hello3"].map(AkdLabel::from);
+    akd.publish_with_proof_precompute(
+        labels
+            .iter()
+            .map(|label| (label.clone(), AkdValue::from("world")))
+            .collect(),
+    )
+    .await?;
+
+    // The proofs of the labels which were not updated are precomputed again as well
+    let epoch_hash = akd
+        .publish_with_proof_precompute(vec![(labels[0].clone(), AkdValue::from("world2"))])
+        .await?;
+    for (label, value) in labels.iter().zip(["world2", "world", "world"]) {
+        let precomputed = match db.get::<PrecomputedLookupProof>(label).await? {
+            DbRecord::PrecomputedLookupProof(precomputed) => precomputed,
+            _ => panic!("Expected a precomputed lookup proof"),
+        };
+        assert_eq!(
+            (2, epoch_hash.hash()),
+            (precomputed.epoch, precomputed.root_hash)
+        );
+        let (lookup_proof, served_epoch_hash) = akd.precomputed_lookup(label.clone()).await?;
+        assert_eq!(precomputed.proof, lookup_proof);
+        assert_eq!(epoch_hash, served_epoch_hash);
+        assert_eq!(AkdValue::from(value), lookup_proof.value);
+        lookup_verify::<TC>(
+            &akd.get_public_key_bytes().await?,
+            epoch_hash.hash(),
+            epoch_hash.epoch(),
+            label.clone(),
+            lookup_proof,
+        )?;
+    }
+
+    // After a publish without precomputation, the proofs are generated instead
+    let epoch_hash = akd
+        .publish(vec![(labels[1].clone(), AkdValue::from("world3"))])
+        .await?;
+    let (lookup_proof, served_epoch_hash) = akd.precomputed_lookup(labels[1].clone()).await?;
+    assert_eq!(epoch_hash, served_epoch_hash);
+    assert_eq!(AkdValue::from("world3"), lookup_proof.value);
+
+    // As they are after a rollback, once the epoch is published again with different contents
+    assert_eq!(3, akd.precompute_lookup_proofs().await?);
+    akd.rollback_last_epoch().await?;
+    // The proofs precomputed for the rolled back epoch are removed along with it
+    for label in labels.iter() {
+        assert!(matches!(
+            db.get::<PrecomputedLookupProof>(label).await,
+            Err(StorageError::NotFound(_))
+        ));
+    }
+    let epoch_hash = akd
+        .publish(vec![(labels[2].clone(), AkdValue::from("world4"))])
+        .await?;
+    let (lookup_proof, served_epoch_hash) = akd.precomputed_lookup(labels[2].clone()).await?;
+    assert_eq!(epoch_hash, served_epoch_hash);
+    assert_eq!(AkdValue::from("world4"), lookup_proof.value);
+    Ok(())
+}
+
+test_config!(test_key_history_bounded);
+async fn test_key_history_bounded<TC: Configuration>() -> Result<(), AkdError> {
+    let storage = StorageManager::new_no_cache(AsyncInMemoryDatabase::new());
+    let akd = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {}).await?;
+    let label = AkdLabel::from("hello");
+    for i in 0..6 {
+        akd.publish(vec![(
+            label.clone(),
+            AkdValue::Bytes(format!("world{i}").into_bytes()),
+        )])
+        .await?;
+    }
+    let mut sizes = vec![];
+    for params in [
+        HistoryParams::Complete,
+        HistoryParams::MostRecent(3),
+        HistoryParams::MostRecent(4),
+    ] {
+        sizes.push(akd.key_history(&label, params).await?.0.size_of());
+    }
+
+    // The complete history is returned if it fits
+    let (proof, _, params) = akd.key_history_bounded(&label, sizes[0]).await?;
+    assert!(matches!(params, HistoryParams::Complete));
+    assert_eq!(6, proof.update_proofs.len());
+
+    // Otherwise, the most recent updates which fit
+    let budget = sizes[1];
+    assert!(budget < sizes[2]);
+    let (proof, root_hash, params) = akd.key_history_bounded(&label, budget).await?;
+    assert!(matches!(params, HistoryParams::MostRecent(3)));
+    assert!(proof.size_of() <= budget);
+    key_history_verify::<TC>(
+        akd.get_public_key().await?.as_bytes(),
+        root_hash.hash(),
+        root_hash.epoch(),
+        label.clone(),
+        proof,
+        HistoryVerificationParams::Default {
+            history_params: params,
+        },
+    )?;
+
+    assert!(matches!(
+        akd.key_history_bounded(&label, 1).await,
+        Err(AkdError::Directory(DirectoryError::ProofTooLarge(_)))
+    ));
+    Ok(())
+}
+
+/// An [EpochSigner] whose signing key is malformed
+struct MalformedEpochSigner;
+
+#[async_trait::async_trait]
+impl EpochSigner for MalformedEpochSigner {
+    async fn retrieve(&self) -> Result<Vec<u8>, akd_core::signature::SignatureError> {
+        Ok(vec![1u8; 3])
+    }
+}
+
+// Checks that a directory built with a DirectoryBuilder is configured with its options, and that
+// invalid options are rejected when building it
+test_config!(test_directory_builder);
+async fn test_directory_builder<TC: Configuration>() -> Result<(), AkdError> {
+    let akd = Directory::<TC, _, _>::builder(AsyncInMemoryDatabase::new(), HardCodedAkdVRF {})
+        .with_cache_policy(CachePolicy::Disabled)
+        .with_retention_policy(RetentionPolicy::new().with_kept_epochs(2))
+        .with_publish_lease(b"builder".to_vec(), Duration::from_secs(60))
+        .with_epoch_signer(HardCodedEpochSigner {})
+        .with_admission_control(AdmissionConfig::new(4))
+        .with_proof_cache(10)
+        .build()
+        .await?;
+    let label = AkdLabel::from("hello");
+    let epoch_hash = akd
+        .publish(vec![(label.clone(), AkdValue::from("world"))])
+        .await?;
+    assert!(akd
+        .get_epoch_metadata(epoch_hash.epoch())
+        .await?
+        .signature
+        .is_some());
+    akd.lookup(label.clone()).await?;
+    akd.lookup(label).await?;
+    assert_eq!(1, akd.proof_cache_stats().unwrap().hits);
+
+    let invalid_options = [
+        (
+            "cache item lifetime",
+            Directory::<TC, _, _>::builder(AsyncInMemoryDatabase::new(), HardCodedAkdVRF {})
+                .with_cache_policy(CachePolicy::Timed {
+                    item_lifetime: Some(Duration::ZERO),
+                    limit_bytes: None,
+                    clean_frequency: None,
+                }),
+        ),
+        (
+            "publish lease holder",
+            Directory::<TC, _, _>::builder(AsyncInMemoryDatabase::new(), HardCodedAkdVRF {})
+                .with_publish_lease(vec![], Duration::from_secs(60)),
+        ),
+        (
+            "publish lease time-to-live",
+            Directory::<TC, _, _>::builder(AsyncInMemoryDatabase::new(), HardCodedAkdVRF {})
+                .with_publish_lease(b"builder".to_vec(), Duration::ZERO),
+        ),
+        (
+            "audit retention",
+            Directory::<TC, _, _>::builder(AsyncInMemoryDatabase::new(), HardCodedAkdVRF {})
+                .with_retention_policy(RetentionPolicy::new().with_audit_retention(Duration::ZERO)),
+        ),
+        (
+            "admission control",
+            Directory::<TC, _, _>::builder(AsyncInMemoryDatabase::new(), HardCodedAkdVRF {})
+                .with_admission_control(AdmissionConfig::new(0)),
+        ),
+        (
+            "proof cache capacity",
+            Directory::<TC, _, _>::builder(AsyncInMemoryDatabase::new(), HardCodedAkdVRF {})
+                .with_proof_cache(0),
+        ),
+    ];
+    for (expected, builder) in invalid_options {
+        match builder.build().await {
+            Err(AkdError::Directory(DirectoryError::InvalidConfiguration(
+                ConfigurationError::InvalidOption { option, .. },
+            ))) => assert_eq!(expected, option),
+            _ => panic!("The {expected} option should have been rejected"),
+        }
+    }
+
+    // The keys are retrieved upfront, and nothing is written to storage if they are unavailable
+    let db = AsyncInMemoryDatabase::new();
+    assert!(matches!(
+        Directory::<TC, _, _>::builder(db.clone(), InMemoryKeyVrf::new(vec![1u8; 3]))
+            .build()
+            .await,
+        Err(AkdError::Directory(DirectoryError::InvalidConfiguration(
+            ConfigurationError::VrfKey(_)
+        )))
+    ));
+    assert!(matches!(
+        Directory::<TC, _, _>::builder(db.clone(), HardCodedAkdVRF {})
+            .with_epoch_signer(MalformedEpochSigner)
+            .build()
+            .await,
+        Err(AkdError::Directory(DirectoryError::InvalidConfiguration(
+            ConfigurationError::EpochSigner(_)
+        )))
+    ));
+    assert!(matches!(
+        StorageManager::new_no_cache(db)
+            .get::<Azks>(&crate::append_only_zks::DEFAULT_AZKS_KEY)
+            .await,
+        Err(StorageError::NotFound(_))
+    ));
+    Ok(())
+}
+
+// Checks that a metrics recorder is installed when the directory is built, and that building
+// another directory with a recorder fails once one is installed. This is the only test which
+// installs a recorder, as it is installed for the whole process.
+#[tokio::test]
+async fn test_directory_builder_metrics_recorder() -> Result<(), AkdError> {
+    type TC = crate::ExperimentalConfiguration<crate::ExampleLabel>;
+    let recorder = crate::telemetry::PrometheusRecorder::new();
+    let akd = Directory::<TC, _, _>::builder(AsyncInMemoryDatabase::new(), HardCodedAkdVRF {})
+        .with_metrics_recorder(recorder.clone())
+        .build()
+        .await?;
+    akd.publish(vec![(AkdLabel::from("hello"), AkdValue::from("world"))])
+        .await?;
+    assert!(
+        recorder.counter(crate::telemetry::Counter::VrfEvaluations, &[]) > 0,
+        "The metrics of the directory should be emitted to the recorder"
+    );
+
+    assert!(matches!(
+        Directory::<TC, _, _>::builder(AsyncInMemoryDatabase::new(), HardCodedAkdVRF {})
+            .with_metrics_recorder(crate::telemetry::PrometheusRecorder::new())
+            .build()
+            .await,
+        Err(AkdError::Directory(DirectoryError::InvalidConfiguration(
+            ConfigurationError::MetricsRecorder
+        )))
+    ));
+    Ok(())
+}
+
+// Test that the blocking facade of a directory serves verifiable proofs without an async runtime
+#[test]
+fn test_blocking_directory() -> Result<(), AkdError> {
+    type TC = crate::ExperimentalConfiguration<crate::ExampleLabel>;
+    let storage = StorageManager::new_no_cache(AsyncInMemoryDatabase::new());
+    let akd = crate::blocking::Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {})?;
+    let vrf_pk = akd.get_public_key()?;
+
+    let label = AkdLabel::from("hello");
+    let first = akd.publish(vec![(label.clone(), AkdValue::from("world"))])?;
+    let second = akd.publish(vec![(label.clone(), AkdValue::from("world2"))])?;
+    assert_eq!(second, akd.get_epoch_hash()?);
+
+    let (lookup_proof, epoch_hash) = akd.lookup(label.clone())?;
+    let result = lookup_verify::<TC>(
+        vrf_pk.as_bytes(),
+        epoch_hash.hash(),
+        epoch_hash.epoch(),
+        label.clone(),
+        lookup_proof,
+    )?;
+    assert_eq!(AkdValue::from("world2"), result.value);
+
+    let (history_proof, epoch_hash) = akd.key_history(&label, HistoryParams::default())?;
+    let results = key_history_verify::<TC>(
+        vrf_pk.as_bytes(),
+        epoch_hash.hash(),
+        epoch_hash.epoch(),
+        label,
+        history_proof,
+        HistoryVerificationParams::default(),
+    )?;
+    assert_eq!(2, results.len());
+
+    let audit_proof = akd.audit(1, 2)?;
+    futures::executor::block_on(audit_verify::<TC>(
+        vec![first.hash(), second.hash()],
+        audit_proof,
+    ))?;
+
+    // The wrapped directory is still usable asynchronously
+    let directory = akd.into_inner();
+    assert_eq!(
+        second,
+        futures::executor::block_on(directory.get_epoch_hash())?
+    );
+
+    Ok(())
+}
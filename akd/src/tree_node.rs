@@ -14,7 +14,7 @@ use crate::storage::types::{DbRecord, StorageType};
 use crate::storage::{Database, Storable};
 use crate::AzksValue;
 use crate::PrefixOrdering;
-use crate::{node_label::*, Direction};
+use crate::{node_label::*, Direction, ARITY};
 use akd_core::configuration::Configuration;
 #[cfg(feature = "serde_serialization")]
 use akd_core::utils::serde_helpers::{azks_value_hex_deserialize, azks_value_hex_serialize};
@@ -377,10 +377,24 @@ impl TreeNode {
     }
 
     /// Recomputes the node's hash based on its children
+    #[cfg(test)]
     pub(crate) async fn update_hash<TC: Configuration, S: Database>(
         &mut self,
         storage: &StorageManager<S>,
         hash_mode: NodeHashingMode,
+    ) -> Result<(), AkdError> {
+        self.update_hash_with_children::<TC, _>(storage, hash_mode, [None, None])
+            .await
+    }
+
+    /// Recomputes the node's hash based on its children. The (left and right) children which
+    /// are already in memory in their current state can be provided, in which case they are
+    /// not read from storage.
+    pub(crate) async fn update_hash_with_children<TC: Configuration, S: Database>(
+        &mut self,
+        storage: &StorageManager<S>,
+        hash_mode: NodeHashingMode,
+        children: [Option<TreeNode>; ARITY],
     ) -> Result<(), AkdError> {
         match self.node_type {
             // For leaf nodes, updates the hash of the node by using the `hash` field (hash of the public key) and the hashed label.
@@ -391,12 +405,21 @@ impl TreeNode {
             // For non-leaf nodes, the hash is updated by merging the hashes of the node's children.
             // It is assumed that the children already updated their hashes.
             _ => {
-                let left_child = self
-                    .get_child_node(storage, Direction::Left, self.last_epoch)
-                    .await?;
-                let right_child = self
-                    .get_child_node(storage, Direction::Right, self.last_epoch)
-                    .await?;
+                let [left_child, right_child] = children;
+                let left_child = match left_child {
+                    Some(child) if Some(child.label) == self.left_child => Some(child),
+                    _ => {
+                        self.get_child_node(storage, Direction::Left, self.last_epoch)
+                            .await?
+                    }
+                };
+                let right_child = match right_child {
+                    Some(child) if Some(child.label) == self.right_child => Some(child),
+                    _ => {
+                        self.get_child_node(storage, Direction::Right, self.last_epoch)
+                            .await?
+                    }
+                };
                 self.hash = TC::compute_parent_hash_from_children(
                     &node_to_azks_value::<TC>(&left_child, hash_mode),
                     &node_to_label::<TC>(&left_child).value::<TC>(),
@@ -29,7 +29,7 @@ pub(crate) fn random_label(rng: &mut impl rand::Rng) -> crate::NodeLabel {
     }
 }
 
-/// NOTE(new_config): Add a new configuration here
+// NOTE(new_config): Add a new configuration here
 
 /// Macro used for running tests with different configurations
 #[cfg(any(test, feature = "public_tests"))]
@@ -48,6 +48,18 @@ macro_rules! test_config {
             async fn [<$x _ experimental_config>]() -> Result<(), AkdError> {
                 $x::<$crate::ExperimentalConfiguration<$crate::ExampleLabel>>().await
             }
+
+            #[cfg(feature = "sha3_256")]
+            #[tokio::test]
+            async fn [<$x _ sha3_256_config>]() -> Result<(), AkdError> {
+                $x::<$crate::Sha3Configuration<$crate::ExampleLabel>>().await
+            }
+
+            #[cfg(feature = "domain_separated")]
+            #[tokio::test]
+            async fn [<$x _ domain_separated_config>]() -> Result<(), AkdError> {
+                $x::<$crate::DomainSeparatedConfiguration<$crate::ExampleLabel>>().await
+            }
         }
     };
 }
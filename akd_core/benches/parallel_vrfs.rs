@@ -10,7 +10,6 @@
 extern crate criterion;
 use self::criterion::*;
 use akd_core::configuration::NamedConfiguration;
-use akd_core::ecvrf::{VRFExpandedPrivateKey, VRFPublicKey};
 use akd_core::VersionFreshness;
 use akd_core::{ecvrf::VRFKeyStorage, AkdLabel, AkdValue};
 use rand::distributions::Alphanumeric;
@@ -30,6 +29,11 @@ macro_rules! bench_config {
             fn [<$x _ experimental_config>](c: &mut Criterion) {
                 $x::<akd_core::ExperimentalConfiguration<akd_core::ExampleLabel>>(c)
             }
+
+            #[cfg(feature = "sha3_256")]
+            fn [<$x _ sha3_256_config>](c: &mut Criterion) {
+                $x::<akd_core::Sha3Configuration<akd_core::ExampleLabel>>(c)
+            }
         }
     };
 }
@@ -52,6 +56,13 @@ macro_rules! group_config {
                     [<$group _ experimental_config>],
                 )+
             );
+
+            #[cfg(feature = "sha3_256")]
+            criterion_group!(
+                $(
+                    [<$group _ sha3_256_config>],
+                )+
+            );
         }
     };
 }
@@ -65,6 +76,8 @@ fn main() {
     benches_whatsapp_v1_config();
     #[cfg(feature = "experimental")]
     benches_experimental_config();
+    #[cfg(feature = "sha3_256")]
+    benches_sha3_256_config();
 
     Criterion::default().configure_from_args().final_summary();
 }
@@ -82,11 +95,9 @@ fn bench_single_vrf<TC: NamedConfiguration>(c: &mut Criterion) {
     );
 
     let runtime = tokio::runtime::Builder::new_multi_thread().build().unwrap();
-    let key = runtime
-        .block_on(akd_core::ecvrf::HardCodedAkdVRF.get_vrf_private_key())
+    let expanded_key = runtime
+        .block_on(akd_core::ecvrf::HardCodedAkdVRF.get_expanded_private_key::<TC>())
         .unwrap();
-    let expanded_key = VRFExpandedPrivateKey::from(&key);
-    let pk = VRFPublicKey::from(&key);
 
     c.bench_function(
         &format!("Single VRF label generation ({})", TC::name()),
@@ -94,7 +105,6 @@ fn bench_single_vrf<TC: NamedConfiguration>(c: &mut Criterion) {
             b.iter(|| {
                 akd_core::ecvrf::HardCodedAkdVRF::get_node_label_with_expanded_key::<TC>(
                     &expanded_key,
-                    &pk,
                     &label,
                     VersionFreshness::Fresh,
                     1,
@@ -131,15 +141,12 @@ fn bench_parallel_vrfs<TC: NamedConfiguration>(c: &mut Criterion) {
 
     c.bench_function(&format!("Sequential VRFs ({})", TC::name()), |b| {
         b.iter(|| {
-            let key = runtime
-                .block_on(akd_core::ecvrf::HardCodedAkdVRF.get_vrf_private_key())
+            let expanded_key = runtime
+                .block_on(akd_core::ecvrf::HardCodedAkdVRF.get_expanded_private_key::<TC>())
                 .unwrap();
-            let expanded_key = VRFExpandedPrivateKey::from(&key);
-            let pk = VRFPublicKey::from(&key);
             for (label, stale, version, _) in labels.iter() {
                 akd_core::ecvrf::HardCodedAkdVRF::get_node_label_with_expanded_key::<TC>(
                     &expanded_key,
-                    &pk,
                     label,
                     *stale,
                     *version,
@@ -6,17 +6,22 @@
 // of this source tree. You may select, at your option, one of the above-listed licenses.
 
 //! This is the pre-compilation build script for the crate `akd_core`. Mainly it's used to compile
-//! protobuf files into rust code prior to compilation.
+//! protobuf files into rust code prior to compilation, which is only done with the `protobuf` feature
+//! so that the other builds of the crate do not depend on the protobuf code generator.
 
 // NOTE: build.rs documentation = https://doc.rust-lang.org/cargo/reference/build-scripts.html
 
 /// The shared-path for all protobuf specifications
+#[cfg(feature = "protobuf")]
 const PROTOBUF_BASE_DIRECTORY: &str = "src/proto/specs";
 /// The list of protobuf files to generate inside PROBUF_BASE_DIRECTORY
+#[cfg(feature = "protobuf")]
 const PROTOBUF_FILES: [&str; 1] = ["types"];
 /// The output directory in the cargo build folder to emit the generated sources to
+#[cfg(feature = "protobuf")]
 const PROTOS_OUTPUT_DIR: &str = "protos";
 
+#[cfg(feature = "protobuf")]
 fn build_protobufs() {
     let mut protobuf_files = Vec::with_capacity(PROTOBUF_FILES.len());
 
@@ -37,5 +42,6 @@ fn build_protobufs() {
 
 fn main() {
     // compile the spec files into Rust code
+    #[cfg(feature = "protobuf")]
     build_protobufs();
 }
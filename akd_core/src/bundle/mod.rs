@@ -0,0 +1,175 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! This module contains the [EpochBundle], a compact and self-contained statement of the root
+//! hash of an epoch which is signed by the directory's [crate::signature::EpochSigner]. A bundle
+//! is small enough to be distributed over constrained out-of-band channels (e.g. push
+//! notifications or QR codes), so that clients can cross-check the root hash they were served
+//! against the roots received from several independent channels.
+//!
+//! Alongside the epoch and its root hash, a bundle contains:
+//! - The root hash of the previous epoch, which links consecutive bundles into a chain
+//!   (see [crate::signature::verify_epoch_bundle_link])
+//! - A fingerprint of the VRF public key of the directory (see [EpochBundle::vrf_fingerprint]),
+//!   which allows clients to detect that a bundle was issued for another directory
+//! - An Ed25519 signature over all of the above, as given by [EpochBundle::signed_message]
+//!
+//! Bundles are encoded with [EpochBundle::encode] as a fixed-length byte string of
+//! [EPOCH_BUNDLE_LENGTH] bytes, which is prefixed by a format version.
+
+#[cfg(test)]
+mod tests;
+
+use crate::configuration::Configuration;
+use crate::hash::{Digest, DIGEST_BYTES};
+
+#[cfg(feature = "nostd")]
+use alloc::format;
+#[cfg(feature = "nostd")]
+use alloc::vec::Vec;
+use ed25519_dalek::SIGNATURE_LENGTH;
+
+const DOMAIN_SEPARATOR: &[u8] = b"akd_epoch_bundle";
+const VRF_FINGERPRINT_DOMAIN_SEPARATOR: &[u8] = b"akd_vrf_public_key_fingerprint";
+
+/// The current version of the encoding of an [EpochBundle]
+pub const EPOCH_BUNDLE_FORMAT_VERSION: u8 = 1;
+
+/// The length, in bytes, of the fingerprint of a VRF public key
+pub const VRF_FINGERPRINT_LENGTH: usize = 16;
+
+/// The length, in bytes, of an encoded [EpochBundle]
+pub const EPOCH_BUNDLE_LENGTH: usize =
+    1 + core::mem::size_of::<u64>() + 2 * DIGEST_BYTES + VRF_FINGERPRINT_LENGTH + SIGNATURE_LENGTH;
+
+/// Errors thrown when decoding an [EpochBundle]
+#[derive(Debug, Eq, PartialEq)]
+pub enum EpochBundleError {
+    /// The bundle was encoded with an unknown format version
+    UnsupportedFormatVersion(u8),
+    /// The encoded bundle does not have the expected length
+    InvalidLength {
+        /// The expected length of the encoded bundle
+        expected: usize,
+        /// The length of the provided bytes
+        actual: usize,
+    },
+}
+
+impl core::fmt::Display for EpochBundleError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let code = match &self {
+            EpochBundleError::UnsupportedFormatVersion(version) => {
+                format!("(Unsupported format version) - {version}")
+            }
+            EpochBundleError::InvalidLength { expected, actual } => {
+                format!("(Invalid length) - Expected {expected} bytes, got {actual}")
+            }
+        };
+        write!(f, "Epoch bundle error {code}")
+    }
+}
+
+/// A signed statement of the root hash of an epoch, meant for out-of-band distribution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpochBundle {
+    /// The epoch of the root hash
+    pub epoch: u64,
+    /// The root hash of the epoch
+    pub root_hash: Digest,
+    /// The root hash of the previous epoch
+    pub previous_root_hash: Digest,
+    /// The fingerprint of the VRF public key of the directory
+    pub vrf_public_key_fingerprint: [u8; VRF_FINGERPRINT_LENGTH],
+    /// The Ed25519 signature over [EpochBundle::signed_message]
+    pub signature: [u8; SIGNATURE_LENGTH],
+}
+
+impl EpochBundle {
+    /// Computes the fingerprint of a VRF public key, which is the truncated hash of the key
+    pub fn vrf_fingerprint<TC: Configuration>(
+        vrf_public_key: &[u8],
+    ) -> [u8; VRF_FINGERPRINT_LENGTH] {
+        let hash = TC::hash(&[VRF_FINGERPRINT_DOMAIN_SEPARATOR, vrf_public_key].concat());
+        let mut fingerprint = [0u8; VRF_FINGERPRINT_LENGTH];
+        fingerprint.copy_from_slice(&hash[..VRF_FINGERPRINT_LENGTH]);
+        fingerprint
+    }
+
+    /// Whether the bundle was issued for a directory with the given VRF public key
+    pub fn matches_vrf_public_key<TC: Configuration>(&self, vrf_public_key: &[u8]) -> bool {
+        self.vrf_public_key_fingerprint == Self::vrf_fingerprint::<TC>(vrf_public_key)
+    }
+
+    /// The message which is signed for the given contents of a bundle
+    pub fn signed_message(
+        epoch: u64,
+        root_hash: Digest,
+        previous_root_hash: Digest,
+        vrf_public_key_fingerprint: [u8; VRF_FINGERPRINT_LENGTH],
+    ) -> Vec<u8> {
+        [
+            DOMAIN_SEPARATOR,
+            &epoch.to_be_bytes(),
+            &root_hash,
+            &previous_root_hash,
+            &vrf_public_key_fingerprint,
+        ]
+        .concat()
+    }
+
+    /// Encodes the bundle as [EPOCH_BUNDLE_LENGTH] bytes
+    pub fn encode(&self) -> Vec<u8> {
+        [
+            &[EPOCH_BUNDLE_FORMAT_VERSION],
+            &self.epoch.to_be_bytes()[..],
+            &self.root_hash,
+            &self.previous_root_hash,
+            &self.vrf_public_key_fingerprint,
+            &self.signature,
+        ]
+        .concat()
+    }
+
+    /// Decodes a bundle which was encoded with [EpochBundle::encode]. Note that this
+    /// does not verify the signature of the bundle.
+    pub fn decode(bytes: &[u8]) -> Result<Self, EpochBundleError> {
+        match bytes.first() {
+            Some(&EPOCH_BUNDLE_FORMAT_VERSION) => {}
+            Some(version) => return Err(EpochBundleError::UnsupportedFormatVersion(*version)),
+            None => {
+                return Err(EpochBundleError::InvalidLength {
+                    expected: EPOCH_BUNDLE_LENGTH,
+                    actual: 0,
+                })
+            }
+        }
+        if bytes.len() != EPOCH_BUNDLE_LENGTH {
+            return Err(EpochBundleError::InvalidLength {
+                expected: EPOCH_BUNDLE_LENGTH,
+                actual: bytes.len(),
+            });
+        }
+
+        let (epoch, rest) = bytes[1..].split_at(core::mem::size_of::<u64>());
+        let (root_hash, rest) = rest.split_at(DIGEST_BYTES);
+        let (previous_root_hash, rest) = rest.split_at(DIGEST_BYTES);
+        let (vrf_public_key_fingerprint, signature) = rest.split_at(VRF_FINGERPRINT_LENGTH);
+        // The lengths of the slices are guaranteed by the length check above
+        Ok(Self {
+            epoch: u64::from_be_bytes(epoch.try_into().expect("Invalid epoch length")),
+            root_hash: root_hash.try_into().expect("Invalid root hash length"),
+            previous_root_hash: previous_root_hash
+                .try_into()
+                .expect("Invalid root hash length"),
+            vrf_public_key_fingerprint: vrf_public_key_fingerprint
+                .try_into()
+                .expect("Invalid fingerprint length"),
+            signature: signature.try_into().expect("Invalid signature length"),
+        })
+    }
+}
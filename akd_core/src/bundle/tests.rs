@@ -0,0 +1,184 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Tests for the encoding and verification of epoch bundles
+
+use super::*;
+
+use ed25519_dalek::{Signer, SigningKey};
+
+fn sign_bundle(
+    key: &SigningKey,
+    epoch: u64,
+    root_hash: Digest,
+    previous_root_hash: Digest,
+    vrf_public_key_fingerprint: [u8; VRF_FINGERPRINT_LENGTH],
+) -> EpochBundle {
+    let message = EpochBundle::signed_message(
+        epoch,
+        root_hash,
+        previous_root_hash,
+        vrf_public_key_fingerprint,
+    );
+    EpochBundle {
+        epoch,
+        root_hash,
+        previous_root_hash,
+        vrf_public_key_fingerprint,
+        signature: key.sign(&message).to_bytes(),
+    }
+}
+
+#[test]
+fn test_encode_decode_round_trip() {
+    let bundle = sign_bundle(
+        &SigningKey::from_bytes(&[7u8; 32]),
+        42,
+        [1u8; 32],
+        [2u8; 32],
+        [3u8; 16],
+    );
+    let encoded = bundle.encode();
+    assert_eq!(EPOCH_BUNDLE_LENGTH, encoded.len());
+    assert_eq!(EPOCH_BUNDLE_FORMAT_VERSION, encoded[0]);
+    assert_eq!(bundle, EpochBundle::decode(&encoded).unwrap());
+}
+
+#[test]
+fn test_decode_errors() {
+    let encoded = sign_bundle(
+        &SigningKey::from_bytes(&[7u8; 32]),
+        1,
+        [1u8; 32],
+        [0u8; 32],
+        [3u8; 16],
+    )
+    .encode();
+
+    assert_eq!(
+        Err(EpochBundleError::InvalidLength {
+            expected: EPOCH_BUNDLE_LENGTH,
+            actual: 0
+        }),
+        EpochBundle::decode(&[])
+    );
+    assert_eq!(
+        Err(EpochBundleError::InvalidLength {
+            expected: EPOCH_BUNDLE_LENGTH,
+            actual: EPOCH_BUNDLE_LENGTH - 1
+        }),
+        EpochBundle::decode(&encoded[..EPOCH_BUNDLE_LENGTH - 1])
+    );
+    let mut extended = encoded.clone();
+    extended.push(0);
+    assert!(EpochBundle::decode(&extended).is_err());
+
+    let mut unknown_version = encoded;
+    unknown_version[0] = EPOCH_BUNDLE_FORMAT_VERSION + 1;
+    assert_eq!(
+        Err(EpochBundleError::UnsupportedFormatVersion(
+            EPOCH_BUNDLE_FORMAT_VERSION + 1
+        )),
+        EpochBundle::decode(&unknown_version)
+    );
+    assert!(EpochBundle::decode(&[EPOCH_BUNDLE_FORMAT_VERSION; 3]).is_err());
+}
+
+mod verification {
+    use super::*;
+    use crate::signature::{
+        verify_epoch_bundle, verify_epoch_bundle_link, SignatureVerificationError,
+    };
+
+    type TC = crate::Sha3Configuration<crate::ExampleLabel>;
+
+    const VRF_PUBLIC_KEY: [u8; 32] = [9u8; 32];
+
+    #[test]
+    fn test_verify_epoch_bundle() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let public_key = key.verifying_key().to_bytes();
+        let fingerprint = EpochBundle::vrf_fingerprint::<TC>(&VRF_PUBLIC_KEY);
+        let bundle = sign_bundle(&key, 5, [1u8; 32], [2u8; 32], fingerprint);
+
+        // The bundle verifies after an encoding round trip
+        let decoded = EpochBundle::decode(&bundle.encode()).unwrap();
+        verify_epoch_bundle::<TC>(&public_key, &VRF_PUBLIC_KEY, &decoded).unwrap();
+
+        // The bundle is bound to the VRF public key of the directory
+        assert!(verify_epoch_bundle::<TC>(&public_key, &[8u8; 32], &decoded).is_err());
+
+        // Tampering with any field of the bundle invalidates it
+        let mut tampered = decoded.encode();
+        for index in 1..tampered.len() {
+            tampered[index] ^= 1;
+            let bundle = EpochBundle::decode(&tampered).unwrap();
+            assert!(verify_epoch_bundle::<TC>(&public_key, &VRF_PUBLIC_KEY, &bundle).is_err());
+            tampered[index] ^= 1;
+        }
+
+        // The bundle must be signed by the expected key
+        let other_key = SigningKey::from_bytes(&[8u8; 32])
+            .verifying_key()
+            .to_bytes();
+        assert!(verify_epoch_bundle::<TC>(&other_key, &VRF_PUBLIC_KEY, &decoded).is_err());
+        assert!(verify_epoch_bundle::<TC>(&public_key[1..], &VRF_PUBLIC_KEY, &decoded).is_err());
+    }
+
+    #[cfg(feature = "verify")]
+    #[test]
+    fn test_verify_epoch_bundle_errors() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let public_key = key.verifying_key().to_bytes();
+        let fingerprint = EpochBundle::vrf_fingerprint::<TC>(&VRF_PUBLIC_KEY);
+        let mut bundle = sign_bundle(&key, 5, [1u8; 32], [2u8; 32], fingerprint);
+        bundle.root_hash = [3u8; 32];
+
+        // The errors of the verify module are the same as those of the signature module
+        let Err(SignatureVerificationError::EpochBundle(message)) =
+            verify_epoch_bundle::<TC>(&public_key, &VRF_PUBLIC_KEY, &bundle)
+        else {
+            panic!("The tampered bundle was not rejected");
+        };
+        assert_eq!(
+            Err(crate::verify::VerificationError::EpochBundle(message)),
+            crate::verify::verify_epoch_bundle::<TC>(&public_key, &VRF_PUBLIC_KEY, &bundle)
+        );
+        assert!(matches!(
+            crate::verify::verify_epoch_signature(&public_key[1..], 5, [1u8; 32], &[0u8; 64]),
+            Err(crate::verify::VerificationError::EpochSignature(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_epoch_bundle_link() {
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let fingerprint = EpochBundle::vrf_fingerprint::<TC>(&VRF_PUBLIC_KEY);
+        let first = sign_bundle(&key, 1, [1u8; 32], [0u8; 32], fingerprint);
+        let second = sign_bundle(&key, 2, [2u8; 32], [1u8; 32], fingerprint);
+        verify_epoch_bundle_link(&first, &second).unwrap();
+
+        // The bundles must be for consecutive epochs, in order
+        assert!(verify_epoch_bundle_link(&second, &first).is_err());
+        let skipped = sign_bundle(&key, 3, [3u8; 32], [1u8; 32], fingerprint);
+        assert!(verify_epoch_bundle_link(&first, &skipped).is_err());
+
+        // The next bundle must link to the root hash of the previous one
+        let forked = sign_bundle(&key, 2, [2u8; 32], [4u8; 32], fingerprint);
+        assert!(verify_epoch_bundle_link(&first, &forked).is_err());
+
+        // The bundles must be issued for the same directory
+        let other_directory = sign_bundle(
+            &key,
+            2,
+            [2u8; 32],
+            [1u8; 32],
+            EpochBundle::vrf_fingerprint::<TC>(&[8u8; 32]),
+        );
+        assert!(verify_epoch_bundle_link(&first, &other_directory).is_err());
+    }
+}
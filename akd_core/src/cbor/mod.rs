@@ -0,0 +1,51 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! This module contains the [CBOR](https://www.rfc-editor.org/rfc/rfc8949) encoding of the proof
+//! types (e.g. [crate::LookupProof], [crate::HistoryProof] and [crate::AppendOnlyProof]), which
+//! is built on their serde serialization. Unlike their JSON serialization, byte fields are encoded
+//! as raw CBOR byte strings rather than hex strings, which makes the encoding considerably more
+//! compact. NOTE: CBOR encoding is NOT supported in nostd environments.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+// The tests decode the protobuf-encoded test vectors
+#[cfg(all(test, feature = "protobuf", feature = "vrf"))]
+mod tests;
+
+/// An error encoding or decoding CBOR
+#[derive(Debug, Eq, PartialEq)]
+pub enum CborError {
+    /// Error serializing a value to CBOR
+    Serialization(String),
+    /// Error deserializing a value from CBOR
+    Deserialization(String),
+}
+
+impl core::fmt::Display for CborError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let code = match &self {
+            CborError::Serialization(msg) => format!("(Serialization) - {msg}"),
+            CborError::Deserialization(msg) => format!("(Deserialization) - {msg}"),
+        };
+        write!(f, "CBOR error {code}")
+    }
+}
+
+/// Encodes the value (e.g. a proof) as CBOR
+pub fn to_vec<T: Serialize>(value: &T) -> Result<Vec<u8>, CborError> {
+    let mut bytes = Vec::new();
+    ciborium::into_writer(value, &mut bytes)
+        .map_err(|err| CborError::Serialization(err.to_string()))?;
+    Ok(bytes)
+}
+
+/// Decodes a value (e.g. a proof) from its CBOR encoding
+pub fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, CborError> {
+    ciborium::from_reader(bytes).map_err(|err| CborError::Deserialization(err.to_string()))
+}
@@ -0,0 +1,105 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Tests for the CBOR encoding, which round-trip the proofs of the test vectors in
+//! `akd_core/wasm/test_vectors`
+
+use super::*;
+use crate::configuration::Configuration;
+use crate::hash::try_parse_digest;
+use crate::proto::specs::types;
+use crate::verify::{key_history_verify, lookup_verify, HistoryVerificationParams};
+use crate::{AkdLabel, HistoryProof, LookupProof, NodeLabel};
+use protobuf::Message;
+
+/// The fields of a test vector which are needed to verify its proofs
+#[derive(serde::Deserialize)]
+struct TestVector {
+    vrf_public_key: String,
+    root_hash: String,
+    epoch: u64,
+    label: String,
+    lookup_proof: String,
+    history_proof: String,
+}
+
+fn check_round_trip<TC: Configuration>(test_vectors: &str) {
+    let vectors: Vec<TestVector> = serde_json::from_str(test_vectors).unwrap();
+    assert!(!vectors.is_empty());
+    for vector in vectors {
+        let vrf_public_key = hex::decode(&vector.vrf_public_key).unwrap();
+        let root_hash = try_parse_digest(&hex::decode(&vector.root_hash).unwrap()).unwrap();
+        let label = AkdLabel(hex::decode(&vector.label).unwrap());
+
+        let proof =
+            types::LookupProof::parse_from_bytes(&hex::decode(&vector.lookup_proof).unwrap())
+                .unwrap();
+        let lookup_proof: LookupProof = (&proof).try_into().unwrap();
+        let encoded = to_vec(&lookup_proof).unwrap();
+        assert!(encoded.len() < serde_json::to_vec(&lookup_proof).unwrap().len());
+        let decoded: LookupProof = from_slice(&encoded).unwrap();
+        assert_eq!(lookup_proof, decoded);
+        lookup_verify::<TC>(
+            &vrf_public_key,
+            root_hash,
+            vector.epoch,
+            label.clone(),
+            decoded,
+        )
+        .unwrap();
+
+        let proof =
+            types::HistoryProof::parse_from_bytes(&hex::decode(&vector.history_proof).unwrap())
+                .unwrap();
+        let history_proof: HistoryProof = (&proof).try_into().unwrap();
+        let decoded: HistoryProof = from_slice(&to_vec(&history_proof).unwrap()).unwrap();
+        assert_eq!(history_proof, decoded);
+        key_history_verify::<TC>(
+            &vrf_public_key,
+            root_hash,
+            vector.epoch,
+            label,
+            decoded,
+            HistoryVerificationParams::default(),
+        )
+        .unwrap();
+    }
+}
+
+#[cfg(feature = "whatsapp_v1")]
+#[test]
+fn test_round_trip_whatsapp_v1_config() {
+    check_round_trip::<crate::WhatsAppV1Configuration>(include_str!(
+        "../../wasm/test_vectors/whatsapp_v1.json"
+    ));
+}
+
+#[cfg(feature = "experimental")]
+#[test]
+fn test_round_trip_experimental_config() {
+    check_round_trip::<crate::ExperimentalConfiguration<crate::ExampleLabel>>(include_str!(
+        "../../wasm/test_vectors/experimental.json"
+    ));
+}
+
+#[test]
+fn test_decode_errors() {
+    let label = NodeLabel::new([7u8; 32], 256);
+    let encoded = to_vec(&label).unwrap();
+    assert_eq!(label, from_slice::<NodeLabel>(&encoded).unwrap());
+
+    assert!(matches!(
+        from_slice::<NodeLabel>(&encoded[..encoded.len() - 1]),
+        Err(CborError::Deserialization(_))
+    ));
+    // A label value of the wrong length is rejected
+    let encoded = to_vec(&crate::AkdValue::Bytes(vec![1, 2, 3])).unwrap();
+    assert!(matches!(
+        from_slice::<NodeLabel>(&encoded),
+        Err(CborError::Deserialization(_))
+    ));
+}
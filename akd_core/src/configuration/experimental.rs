@@ -9,33 +9,49 @@
 
 use core::marker::PhantomData;
 
-use super::traits::DomainLabel;
+use super::traits::{DomainLabel, HashPurpose};
 use crate::configuration::Configuration;
+use crate::ecvrf::{Ed25519Sha512Tai, VrfScheme};
 use crate::hash::{Digest, DIGEST_BYTES};
 use crate::utils::i2osp_array;
-use crate::{AkdLabel, AkdValue, AzksValue, AzksValueWithEpoch, NodeLabel, VersionFreshness};
+use crate::{
+    AkdLabel, AkdValue, AzksValue, AzksValueWithEpoch, NodeLabel, NodeLabelValue, VersionFreshness,
+};
 
 #[cfg(feature = "nostd")]
 use alloc::vec::Vec;
 
-/// An experimental configuration
+/// An experimental configuration, whose node labels are computed with the VRF suite `V`
 #[derive(Clone)]
-pub struct ExperimentalConfiguration<L>(PhantomData<L>);
+pub struct ExperimentalConfiguration<L, V = Ed25519Sha512Tai>(PhantomData<(L, V)>);
 
-unsafe impl<L> Send for ExperimentalConfiguration<L> {}
-unsafe impl<L> Sync for ExperimentalConfiguration<L> {}
+/// The configuration hashing with BLAKE3, for deployments favoring the throughput of publishes
+/// and audit verifications (see the `azks` and `directory` benchmarks of akd, which compare it
+/// against the other configurations). This is the [ExperimentalConfiguration].
+pub type Blake3Configuration<L, V = Ed25519Sha512Tai> = ExperimentalConfiguration<L, V>;
 
-impl<L: DomainLabel> ExperimentalConfiguration<L> {
+unsafe impl<L, V> Send for ExperimentalConfiguration<L, V> {}
+unsafe impl<L, V> Sync for ExperimentalConfiguration<L, V> {}
+
+impl<L: DomainLabel, V: VrfScheme> ExperimentalConfiguration<L, V> {
     /// Used by the client to supply a commitment nonce and value to reconstruct the commitment, via:
     /// commitment = H(i2osp_array(value), i2osp_array(nonce))
     fn generate_commitment_from_nonce_client(value: &crate::AkdValue, nonce: &[u8]) -> AzksValue {
-        AzksValue(<Self as Configuration>::hash(
-            &[i2osp_array(value), i2osp_array(nonce)].concat(),
+        AzksValue(<Self as Configuration>::hash_with_purpose(
+            HashPurpose::Commitment,
+            &[
+                &(value.len() as u64).to_be_bytes(),
+                value,
+                &(nonce.len() as u64).to_be_bytes(),
+                nonce,
+            ],
         ))
     }
 }
 
-impl<L: DomainLabel> Configuration for ExperimentalConfiguration<L> {
+impl<L: DomainLabel, V: VrfScheme> Configuration for ExperimentalConfiguration<L, V> {
+    type Vrf = V;
+
     fn hash(item: &[u8]) -> crate::hash::Digest {
         // Hash(domain label || item)
         let mut hasher = blake3::Hasher::new();
@@ -44,6 +60,16 @@ impl<L: DomainLabel> Configuration for ExperimentalConfiguration<L> {
         hasher.finalize().into()
     }
 
+    fn hash_all(items: &[&[u8]]) -> crate::hash::Digest {
+        // Hash(domain label || item_1 || ... || item_n)
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(L::domain_label());
+        for item in items {
+            hasher.update(item);
+        }
+        hasher.finalize().into()
+    }
+
     fn empty_root_value() -> AzksValue {
         AzksValue([0u8; 32])
     }
@@ -65,7 +91,7 @@ impl<L: DomainLabel> Configuration for ExperimentalConfiguration<L> {
         let mut data = [0; DIGEST_BYTES + 8];
         data[..DIGEST_BYTES].copy_from_slice(&commitment.0);
         data[DIGEST_BYTES..].copy_from_slice(&epoch.to_be_bytes());
-        AzksValueWithEpoch(Self::hash(&data))
+        AzksValueWithEpoch(Self::hash_with_purpose(HashPurpose::Leaf, &[&data]))
     }
 
     /// Used by the server to produce a commitment nonce for an AkdLabel, version, and AkdValue.
@@ -76,7 +102,10 @@ impl<L: DomainLabel> Configuration for ExperimentalConfiguration<L> {
         _version: u64,
         _value: &AkdValue,
     ) -> Digest {
-        Self::hash(&[commitment_key, &label.to_bytes()].concat())
+        Self::hash_with_purpose(
+            HashPurpose::CommitmentNonce,
+            &[commitment_key, &label.to_bytes()],
+        )
     }
 
     /// Used by the server to produce a commitment for an AkdLabel, version, and AkdValue
@@ -96,8 +125,9 @@ impl<L: DomainLabel> Configuration for ExperimentalConfiguration<L> {
         value: &AkdValue,
     ) -> AzksValue {
         let nonce = Self::get_commitment_nonce(commitment_key, label, version, value);
-        AzksValue(Self::hash(
-            &[i2osp_array(value), i2osp_array(&nonce)].concat(),
+        AzksValue(Self::hash_with_purpose(
+            HashPurpose::Commitment,
+            &[&i2osp_array(value), &i2osp_array(&nonce)],
         ))
     }
 
@@ -108,6 +138,7 @@ impl<L: DomainLabel> Configuration for ExperimentalConfiguration<L> {
     /// - I2OSP(len(label) as u64, label)
     /// - A single byte encoded as 0u8 if "stale", 1u8 if "fresh"
     /// - A u64 representing the version
+    ///
     /// These are all interpreted as a single byte array and hashed together, with the output
     /// of the hash returned.
     fn get_hash_from_label_input(
@@ -115,14 +146,14 @@ impl<L: DomainLabel> Configuration for ExperimentalConfiguration<L> {
         freshness: VersionFreshness,
         version: u64,
     ) -> Vec<u8> {
-        let freshness_bytes = [freshness as u8];
-        let hashed_label = Self::hash(
+        let hashed_label = Self::hash_with_purpose(
+            HashPurpose::LabelDerivation,
             &[
-                &crate::utils::i2osp_array(label)[..],
-                &freshness_bytes,
+                &(label.len() as u64).to_be_bytes(),
+                label,
+                &[freshness as u8],
                 &version.to_be_bytes(),
-            ]
-            .concat(),
+            ],
         );
         hashed_label.to_vec()
     }
@@ -134,8 +165,26 @@ impl<L: DomainLabel> Configuration for ExperimentalConfiguration<L> {
         right_val: &AzksValue,
         right_label: &[u8],
     ) -> AzksValue {
-        AzksValue(Self::hash(
-            &[&left_val.0, left_label, &right_val.0, right_label].concat(),
+        AzksValue(Self::hash_with_purpose(
+            HashPurpose::Parent,
+            &[&left_val.0, left_label, &right_val.0, right_label],
+        ))
+    }
+
+    fn compute_parent_hash_from_child_labels(
+        left_val: &AzksValue,
+        left_label: NodeLabel,
+        right_val: &AzksValue,
+        right_label: NodeLabel,
+    ) -> AzksValue {
+        AzksValue(Self::hash_with_purpose(
+            HashPurpose::Parent,
+            &[
+                &left_val.0,
+                &left_label.to_bytes(),
+                &right_val.0,
+                &right_label.to_bytes(),
+            ],
         ))
     }
 
@@ -150,8 +199,8 @@ impl<L: DomainLabel> Configuration for ExperimentalConfiguration<L> {
         AzksValue(crate::hash::EMPTY_DIGEST)
     }
 
-    fn compute_node_label_value(bytes: &[u8]) -> Vec<u8> {
-        bytes.to_vec()
+    fn compute_node_label_value(bytes: &[u8]) -> NodeLabelValue {
+        NodeLabelValue::from_slice(bytes)
     }
 
     fn empty_label() -> NodeLabel {
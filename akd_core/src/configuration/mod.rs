@@ -8,11 +8,14 @@
 //! Defines the configuration trait and implementations for various configurations
 
 mod traits;
-pub use traits::{Configuration, DomainLabel, ExampleLabel};
+pub use traits::{Configuration, DomainLabel, ExampleLabel, HashPurpose};
 
 #[cfg(feature = "public_tests")]
 pub use traits::NamedConfiguration;
 
+pub(crate) mod padded;
+pub use padded::PaddedConfiguration;
+
 // Note(new_config): Update this when adding a new configuration
 
 #[cfg(feature = "whatsapp_v1")]
@@ -23,4 +26,14 @@ pub use whatsapp_v1::WhatsAppV1Configuration;
 #[cfg(feature = "experimental")]
 pub(crate) mod experimental;
 #[cfg(feature = "experimental")]
-pub use experimental::ExperimentalConfiguration;
+pub use experimental::{Blake3Configuration, ExperimentalConfiguration};
+
+#[cfg(feature = "sha3_256")]
+pub(crate) mod sha3_256;
+#[cfg(feature = "sha3_256")]
+pub use sha3_256::Sha3Configuration;
+
+#[cfg(feature = "domain_separated")]
+pub(crate) mod domain_separated;
+#[cfg(feature = "domain_separated")]
+pub use domain_separated::DomainSeparatedConfiguration;
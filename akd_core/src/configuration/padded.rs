@@ -0,0 +1,150 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Defines a configuration which pads values to fixed-size buckets before committing to them
+
+use core::marker::PhantomData;
+
+use super::traits::HashPurpose;
+use crate::configuration::Configuration;
+use crate::hash::Digest;
+use crate::{
+    AkdLabel, AkdValue, AzksValue, AzksValueWithEpoch, NodeLabel, NodeLabelValue, VersionFreshness,
+};
+
+#[cfg(feature = "nostd")]
+use alloc::vec::Vec;
+
+/// A configuration which is the same as `TC`, except that values are padded to a multiple of
+/// `BUCKET_SIZE` bytes before they are committed to (see [AkdValue::pad]). The proofs served by
+/// the directory then only reveal which bucket the length of a value falls into, and clients
+/// verifying with this configuration remove the padding once the proofs are verified.
+///
+/// Since its commitments differ from those of `TC`, a directory cannot switch to it in place.
+#[derive(Clone)]
+pub struct PaddedConfiguration<TC, const BUCKET_SIZE: usize>(PhantomData<TC>);
+
+unsafe impl<TC, const BUCKET_SIZE: usize> Send for PaddedConfiguration<TC, BUCKET_SIZE> {}
+unsafe impl<TC, const BUCKET_SIZE: usize> Sync for PaddedConfiguration<TC, BUCKET_SIZE> {}
+
+impl<TC: Configuration, const BUCKET_SIZE: usize> Configuration
+    for PaddedConfiguration<TC, BUCKET_SIZE>
+{
+    type Vrf = TC::Vrf;
+
+    fn hash(item: &[u8]) -> Digest {
+        TC::hash(item)
+    }
+
+    fn hash_all(items: &[&[u8]]) -> Digest {
+        TC::hash_all(items)
+    }
+
+    fn hash_with_purpose(purpose: HashPurpose, items: &[&[u8]]) -> Digest {
+        TC::hash_with_purpose(purpose, items)
+    }
+
+    fn value_padding_bucket_size() -> Option<usize> {
+        Some(BUCKET_SIZE)
+    }
+
+    fn empty_root_value() -> AzksValue {
+        TC::empty_root_value()
+    }
+
+    fn empty_node_hash() -> AzksValue {
+        TC::empty_node_hash()
+    }
+
+    fn hash_leaf_with_value(value: &AkdValue, epoch: u64, nonce: &[u8]) -> AzksValueWithEpoch {
+        TC::hash_leaf_with_value(value, epoch, nonce)
+    }
+
+    fn hash_leaf_with_commitment(commitment: AzksValue, epoch: u64) -> AzksValueWithEpoch {
+        TC::hash_leaf_with_commitment(commitment, epoch)
+    }
+
+    fn get_commitment_nonce(
+        commitment_key: &[u8],
+        label: &NodeLabel,
+        version: u64,
+        value: &AkdValue,
+    ) -> Digest {
+        TC::get_commitment_nonce(commitment_key, label, version, value)
+    }
+
+    fn compute_fresh_azks_value(
+        commitment_key: &[u8],
+        label: &NodeLabel,
+        version: u64,
+        value: &AkdValue,
+    ) -> AzksValue {
+        TC::compute_fresh_azks_value(commitment_key, label, version, value)
+    }
+
+    fn get_blinded_commitment_nonce(
+        commitment_key: &[u8],
+        label: &NodeLabel,
+        version: u64,
+        value: &AkdValue,
+        blinding: &[u8],
+    ) -> Digest {
+        TC::get_blinded_commitment_nonce(commitment_key, label, version, value, blinding)
+    }
+
+    fn compute_blinded_azks_value(
+        commitment_key: &[u8],
+        label: &NodeLabel,
+        version: u64,
+        value: &AkdValue,
+        blinding: &[u8],
+    ) -> AzksValue {
+        TC::compute_blinded_azks_value(commitment_key, label, version, value, blinding)
+    }
+
+    fn get_hash_from_label_input(
+        label: &AkdLabel,
+        freshness: VersionFreshness,
+        version: u64,
+    ) -> Vec<u8> {
+        TC::get_hash_from_label_input(label, freshness, version)
+    }
+
+    fn compute_parent_hash_from_children(
+        left_val: &AzksValue,
+        left_label: &[u8],
+        right_val: &AzksValue,
+        right_label: &[u8],
+    ) -> AzksValue {
+        TC::compute_parent_hash_from_children(left_val, left_label, right_val, right_label)
+    }
+
+    fn compute_parent_hash_from_child_labels(
+        left_val: &AzksValue,
+        left_label: NodeLabel,
+        right_val: &AzksValue,
+        right_label: NodeLabel,
+    ) -> AzksValue {
+        TC::compute_parent_hash_from_child_labels(left_val, left_label, right_val, right_label)
+    }
+
+    fn compute_root_hash_from_val(root_val: &AzksValue) -> Digest {
+        TC::compute_root_hash_from_val(root_val)
+    }
+
+    fn stale_azks_value() -> AzksValue {
+        TC::stale_azks_value()
+    }
+
+    fn compute_node_label_value(bytes: &[u8]) -> NodeLabelValue {
+        TC::compute_node_label_value(bytes)
+    }
+
+    fn empty_label() -> NodeLabel {
+        TC::empty_label()
+    }
+}
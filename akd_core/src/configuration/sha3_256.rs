@@ -0,0 +1,221 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Defines a configuration which hashes with SHA3-256, for deployments whose compliance
+//! requirements pin the SHA-3 family of hash functions
+
+use core::marker::PhantomData;
+use sha3::{Digest as _, Sha3_256};
+
+use super::traits::{DomainLabel, HashPurpose};
+use crate::configuration::Configuration;
+use crate::ecvrf::{Ed25519Sha512Tai, VrfScheme};
+use crate::hash::{Digest, DIGEST_BYTES};
+use crate::utils::i2osp_array;
+use crate::{
+    AkdLabel, AkdValue, AzksValue, AzksValueWithEpoch, NodeLabel, NodeLabelValue, VersionFreshness,
+};
+
+#[cfg(feature = "nostd")]
+use alloc::vec::Vec;
+
+/// A configuration which is the same as the experimental one, except that all of the hashes of
+/// the tree, the commitments and the inputs of the VRF are computed with SHA3-256 (rather than
+/// BLAKE3). The node labels are computed with the VRF suite `V`.
+#[derive(Clone)]
+pub struct Sha3Configuration<L, V = Ed25519Sha512Tai>(PhantomData<(L, V)>);
+
+unsafe impl<L, V> Send for Sha3Configuration<L, V> {}
+unsafe impl<L, V> Sync for Sha3Configuration<L, V> {}
+
+impl<L: DomainLabel, V: VrfScheme> Sha3Configuration<L, V> {
+    /// Used by the client to supply a commitment nonce and value to reconstruct the commitment, via:
+    /// commitment = H(i2osp_array(value), i2osp_array(nonce))
+    fn generate_commitment_from_nonce_client(value: &crate::AkdValue, nonce: &[u8]) -> AzksValue {
+        AzksValue(<Self as Configuration>::hash_with_purpose(
+            HashPurpose::Commitment,
+            &[
+                &(value.len() as u64).to_be_bytes(),
+                value,
+                &(nonce.len() as u64).to_be_bytes(),
+                nonce,
+            ],
+        ))
+    }
+}
+
+impl<L: DomainLabel, V: VrfScheme> Configuration for Sha3Configuration<L, V> {
+    type Vrf = V;
+
+    fn hash(item: &[u8]) -> crate::hash::Digest {
+        // Hash(domain label || item)
+        let mut hasher = Sha3_256::new();
+        hasher.update(L::domain_label());
+        hasher.update(item);
+        hasher.finalize().into()
+    }
+
+    fn hash_all(items: &[&[u8]]) -> crate::hash::Digest {
+        // Hash(domain label || item_1 || ... || item_n)
+        let mut hasher = Sha3_256::new();
+        hasher.update(L::domain_label());
+        for item in items {
+            hasher.update(item);
+        }
+        hasher.finalize().into()
+    }
+
+    fn empty_root_value() -> AzksValue {
+        AzksValue([0u8; 32])
+    }
+
+    fn empty_node_hash() -> AzksValue {
+        AzksValue([0u8; 32])
+    }
+
+    fn hash_leaf_with_value(
+        value: &crate::AkdValue,
+        epoch: u64,
+        nonce: &[u8],
+    ) -> AzksValueWithEpoch {
+        let commitment = Self::generate_commitment_from_nonce_client(value, nonce);
+        Self::hash_leaf_with_commitment(commitment, epoch)
+    }
+
+    fn hash_leaf_with_commitment(commitment: AzksValue, epoch: u64) -> AzksValueWithEpoch {
+        let mut data = [0; DIGEST_BYTES + 8];
+        data[..DIGEST_BYTES].copy_from_slice(&commitment.0);
+        data[DIGEST_BYTES..].copy_from_slice(&epoch.to_be_bytes());
+        AzksValueWithEpoch(Self::hash_with_purpose(HashPurpose::Leaf, &[&data]))
+    }
+
+    /// Used by the server to produce a commitment nonce for an AkdLabel, version, and AkdValue.
+    /// Computes nonce = H(commitment key || label)
+    fn get_commitment_nonce(
+        commitment_key: &[u8],
+        label: &NodeLabel,
+        _version: u64,
+        _value: &AkdValue,
+    ) -> Digest {
+        Self::hash_with_purpose(
+            HashPurpose::CommitmentNonce,
+            &[commitment_key, &label.to_bytes()],
+        )
+    }
+
+    /// Used by the server to produce a commitment for an AkdLabel, version, and AkdValue
+    ///
+    /// nonce = H(commitment key || label)
+    /// commmitment = H(i2osp_array(value), i2osp_array(nonce))
+    ///
+    /// The nonce value is used to create a hiding and binding commitment using a
+    /// cryptographic hash function. Note that it is derived from the label, version, and
+    /// value (even though the binding to value is somewhat optional).
+    ///
+    /// Note that this commitment needs to be a hash function (random oracle) output
+    fn compute_fresh_azks_value(
+        commitment_key: &[u8],
+        label: &NodeLabel,
+        version: u64,
+        value: &AkdValue,
+    ) -> AzksValue {
+        let nonce = Self::get_commitment_nonce(commitment_key, label, version, value);
+        AzksValue(Self::hash_with_purpose(
+            HashPurpose::Commitment,
+            &[&i2osp_array(value), &i2osp_array(&nonce)],
+        ))
+    }
+
+    /// To convert a regular label (arbitrary string of bytes) into a [NodeLabel], we compute the
+    /// output as: H(label || freshness || version)
+    ///
+    /// Specifically, we concatenate the following together:
+    /// - I2OSP(len(label) as u64, label)
+    /// - A single byte encoded as 0u8 if "stale", 1u8 if "fresh"
+    /// - A u64 representing the version
+    ///
+    /// These are all interpreted as a single byte array and hashed together, with the output
+    /// of the hash returned.
+    fn get_hash_from_label_input(
+        label: &AkdLabel,
+        freshness: VersionFreshness,
+        version: u64,
+    ) -> Vec<u8> {
+        let hashed_label = Self::hash_with_purpose(
+            HashPurpose::LabelDerivation,
+            &[
+                &(label.len() as u64).to_be_bytes(),
+                label,
+                &[freshness as u8],
+                &version.to_be_bytes(),
+            ],
+        );
+        hashed_label.to_vec()
+    }
+
+    /// Computes the parent hash from the children hashes and labels
+    fn compute_parent_hash_from_children(
+        left_val: &AzksValue,
+        left_label: &[u8],
+        right_val: &AzksValue,
+        right_label: &[u8],
+    ) -> AzksValue {
+        AzksValue(Self::hash_with_purpose(
+            HashPurpose::Parent,
+            &[&left_val.0, left_label, &right_val.0, right_label],
+        ))
+    }
+
+    fn compute_parent_hash_from_child_labels(
+        left_val: &AzksValue,
+        left_label: NodeLabel,
+        right_val: &AzksValue,
+        right_label: NodeLabel,
+    ) -> AzksValue {
+        AzksValue(Self::hash_with_purpose(
+            HashPurpose::Parent,
+            &[
+                &left_val.0,
+                &left_label.to_bytes(),
+                &right_val.0,
+                &right_label.to_bytes(),
+            ],
+        ))
+    }
+
+    /// Given the top-level hash, compute the "actual" root hash that is published
+    /// by the directory maintainer
+    fn compute_root_hash_from_val(root_val: &AzksValue) -> Digest {
+        root_val.0
+    }
+
+    /// Similar to commit_fresh_value, but used for stale values.
+    fn stale_azks_value() -> AzksValue {
+        AzksValue(crate::hash::EMPTY_DIGEST)
+    }
+
+    fn compute_node_label_value(bytes: &[u8]) -> NodeLabelValue {
+        NodeLabelValue::from_slice(bytes)
+    }
+
+    fn empty_label() -> NodeLabel {
+        NodeLabel {
+            label_val: [
+                1u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+            ],
+            label_len: 0,
+        }
+    }
+}
+
+#[cfg(feature = "public_tests")]
+impl<L: DomainLabel> super::traits::NamedConfiguration for Sha3Configuration<L> {
+    fn name() -> &'static str {
+        "sha3_256"
+    }
+}
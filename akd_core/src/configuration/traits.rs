@@ -7,8 +7,11 @@
 
 //! Defines the configuration trait for customizing the directory's cryptographic operations
 
+use crate::ecvrf::VrfScheme;
 use crate::hash::Digest;
-use crate::{AkdLabel, AkdValue, AzksValue, AzksValueWithEpoch, NodeLabel, VersionFreshness};
+use crate::{
+    AkdLabel, AkdValue, AzksValue, AzksValueWithEpoch, NodeLabel, NodeLabelValue, VersionFreshness,
+};
 
 #[cfg(feature = "nostd")]
 use alloc::vec::Vec;
@@ -30,11 +33,72 @@ impl DomainLabel for ExampleLabel {
     }
 }
 
+/// The purposes for which a configuration hashes, which configurations can use to separate
+/// the domains of the hashes computed for each purpose (see [Configuration::hash_with_purpose])
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashPurpose {
+    /// The hash of a leaf, from the commitment to its value and its epoch
+    Leaf,
+    /// The hash of a parent node, from the values and labels of its children
+    Parent,
+    /// The commitment to a value, from the value and its nonce
+    Commitment,
+    /// The nonce of a commitment, from the commitment key and the label of the leaf
+    CommitmentNonce,
+    /// The commitment key, from the secret of the directory
+    CommitmentKey,
+    /// The input of the VRF, from a label, the freshness and the version
+    LabelDerivation,
+    /// The label or commitment of a decoy leaf, from the commitment key, the epoch and its index
+    Decoy,
+}
+
+impl HashPurpose {
+    /// Returns the tag which prefixes the hashes computed for this purpose by the configurations
+    /// separating their domains. No tag is a prefix of another.
+    pub fn tag(&self) -> &'static [u8] {
+        match self {
+            HashPurpose::Leaf => b"akd:leaf:",
+            HashPurpose::Parent => b"akd:parent:",
+            HashPurpose::Commitment => b"akd:commitment:",
+            HashPurpose::CommitmentNonce => b"akd:commitment_nonce:",
+            HashPurpose::CommitmentKey => b"akd:commitment_key:",
+            HashPurpose::LabelDerivation => b"akd:label_derivation:",
+            HashPurpose::Decoy => b"akd:decoy:",
+        }
+    }
+}
+
 /// Trait for customizing the directory's cryptographic operations
 pub trait Configuration: Clone + Send + Sync + 'static {
+    /// The VRF suite which maps the labels of the directory to the labels of their nodes
+    type Vrf: VrfScheme;
+
     /// Hash a single byte array
     fn hash(item: &[u8]) -> crate::hash::Digest;
 
+    /// Hash the concatenation of the byte arrays. Configurations should override this to
+    /// hash the items incrementally, so that the concatenation is never allocated.
+    fn hash_all(items: &[&[u8]]) -> crate::hash::Digest {
+        Self::hash(&items.concat())
+    }
+
+    /// Hash the concatenation of the byte arrays for a purpose. Every hash of the tree, the
+    /// commitments and the inputs of the VRF is computed with this. By default the purpose is
+    /// ignored (so that the hashes of the configurations which predate it are unchanged), and
+    /// configurations override this to prefix the items with [HashPurpose::tag].
+    fn hash_with_purpose(_purpose: HashPurpose, items: &[&[u8]]) -> crate::hash::Digest {
+        Self::hash_all(items)
+    }
+
+    /// The size of the buckets to which the server pads values (see [AkdValue::pad]) before
+    /// committing to them, so that proofs only reveal which bucket the length of a value falls
+    /// into. Clients remove the padding once a proof is verified. By default values are not
+    /// padded, see [crate::configuration::PaddedConfiguration].
+    fn value_padding_bucket_size() -> Option<usize> {
+        None
+    }
+
     /// The value stored in the root node upon initialization, with no children
     fn empty_root_value() -> AzksValue;
 
@@ -67,6 +131,50 @@ pub trait Configuration: Clone + Send + Sync + 'static {
         value: &AkdValue,
     ) -> AzksValue;
 
+    /// Used by the server to co-derive a commitment nonce from the nonce produced by
+    /// [Configuration::get_commitment_nonce] and a blinding supplied by the client, via:
+    /// nonce = H(server nonce || i2osp_array(blinding))
+    ///
+    /// Clients verify the resulting nonce like any other, but it cannot be re-derived from the
+    /// commitment key alone.
+    fn get_blinded_commitment_nonce(
+        commitment_key: &[u8],
+        label: &NodeLabel,
+        version: u64,
+        value: &AkdValue,
+        blinding: &[u8],
+    ) -> Digest {
+        let nonce = Self::get_commitment_nonce(commitment_key, label, version, value);
+        Self::hash_with_purpose(
+            HashPurpose::CommitmentNonce,
+            &[&nonce, &crate::utils::i2osp_array(blinding)],
+        )
+    }
+
+    /// Used by the server to produce a commitment like [Configuration::compute_fresh_azks_value],
+    /// but with the nonce produced by [Configuration::get_blinded_commitment_nonce]:
+    /// commitment = H(i2osp_array(value), i2osp_array(nonce))
+    ///
+    /// Configurations which commit to values differently in [Configuration::hash_leaf_with_value]
+    /// should override this accordingly.
+    fn compute_blinded_azks_value(
+        commitment_key: &[u8],
+        label: &NodeLabel,
+        version: u64,
+        value: &AkdValue,
+        blinding: &[u8],
+    ) -> AzksValue {
+        let nonce =
+            Self::get_blinded_commitment_nonce(commitment_key, label, version, value, blinding);
+        AzksValue(Self::hash_with_purpose(
+            HashPurpose::Commitment,
+            &[
+                &crate::utils::i2osp_array(value),
+                &crate::utils::i2osp_array(&nonce),
+            ],
+        ))
+    }
+
     /// To convert a regular label (arbitrary string of bytes) into a [NodeLabel], we compute the
     /// output as: H(label || freshness || version)
     ///
@@ -74,6 +182,7 @@ pub trait Configuration: Clone + Send + Sync + 'static {
     /// - I2OSP(len(label) as u64, label)
     /// - A single byte encoded as 0u8 if "stale", 1u8 if "fresh"
     /// - A u64 representing the version
+    ///
     /// These are all interpreted as a single byte array and hashed together, with the output
     /// of the hash returned.
     fn get_hash_from_label_input(
@@ -90,6 +199,24 @@ pub trait Configuration: Clone + Send + Sync + 'static {
         right_label: &[u8],
     ) -> AzksValue;
 
+    /// Computes the parent hash from the children hashes and labels, like
+    /// [Configuration::compute_parent_hash_from_children] on the values of the labels.
+    /// Configurations should override this to avoid allocating the values of the labels,
+    /// since it is called for each level of a membership proof during verification.
+    fn compute_parent_hash_from_child_labels(
+        left_val: &AzksValue,
+        left_label: NodeLabel,
+        right_val: &AzksValue,
+        right_label: NodeLabel,
+    ) -> AzksValue {
+        Self::compute_parent_hash_from_children(
+            left_val,
+            &left_label.value::<Self>(),
+            right_val,
+            &right_label.value::<Self>(),
+        )
+    }
+
     /// Given the top-level hash, compute the "actual" root hash that is published
     /// by the directory maintainer
     fn compute_root_hash_from_val(root_val: &AzksValue) -> Digest;
@@ -98,7 +225,7 @@ pub trait Configuration: Clone + Send + Sync + 'static {
     fn stale_azks_value() -> AzksValue;
 
     /// Computes the node label value from the bytes of the label
-    fn compute_node_label_value(bytes: &[u8]) -> Vec<u8>;
+    fn compute_node_label_value(bytes: &[u8]) -> NodeLabelValue;
 
     /// Returns the representation of the empty label
     fn empty_label() -> NodeLabel;
@@ -7,11 +7,13 @@
 
 //! Defines the WhatsApp v1 configuration
 
-use crate::configuration::Configuration;
+use crate::configuration::{Configuration, HashPurpose};
+use crate::ecvrf::Ed25519Sha512Tai;
 use crate::hash::{Digest, DIGEST_BYTES};
 use crate::utils::i2osp_array;
 use crate::{
-    AkdLabel, AkdValue, AzksValue, AzksValueWithEpoch, NodeLabel, VersionFreshness, EMPTY_VALUE,
+    AkdLabel, AkdValue, AzksValue, AzksValueWithEpoch, NodeLabel, NodeLabelValue, VersionFreshness,
+    EMPTY_VALUE,
 };
 
 #[cfg(feature = "nostd")]
@@ -28,29 +30,50 @@ impl WhatsAppV1Configuration {
     /// Used by the client to supply a commitment nonce and value to reconstruct the commitment, via:
     /// commitment = H(i2osp_array(value), i2osp_array(nonce))
     fn generate_commitment_from_nonce_client(value: &crate::AkdValue, nonce: &[u8]) -> AzksValue {
-        AzksValue(Self::hash(
-            &[i2osp_array(value), i2osp_array(nonce)].concat(),
+        AzksValue(Self::hash_with_purpose(
+            HashPurpose::Commitment,
+            &[
+                &(value.len() as u64).to_be_bytes(),
+                value,
+                &(nonce.len() as u64).to_be_bytes(),
+                nonce,
+            ],
         ))
     }
+
+    /// H(value || H(label)), which is hashed with its sibling to compute the parent hash
+    fn hash_child(val: &AzksValue, label: NodeLabel) -> Digest {
+        Self::hash_with_purpose(
+            HashPurpose::Parent,
+            &[&val.0, &Self::hash(&label.to_bytes())],
+        )
+    }
 }
 
 impl Configuration for WhatsAppV1Configuration {
+    type Vrf = Ed25519Sha512Tai;
+
     fn hash(item: &[u8]) -> crate::hash::Digest {
         ::blake3::hash(item).into()
     }
 
+    fn hash_all(items: &[&[u8]]) -> crate::hash::Digest {
+        let mut hasher = ::blake3::Hasher::new();
+        for item in items {
+            hasher.update(item);
+        }
+        hasher.finalize().into()
+    }
+
     fn empty_root_value() -> AzksValue {
         AzksValue(Self::hash(&crate::EMPTY_VALUE))
     }
 
     fn empty_node_hash() -> AzksValue {
-        AzksValue(Self::hash(
-            &[
-                Self::hash(&EMPTY_VALUE).to_vec(),
-                Self::empty_label().value::<Self>(),
-            ]
-            .concat(),
-        ))
+        AzksValue(Self::hash_all(&[
+            &Self::hash(&EMPTY_VALUE),
+            &Self::empty_label().value::<Self>(),
+        ]))
     }
 
     fn hash_leaf_with_value(
@@ -66,7 +89,7 @@ impl Configuration for WhatsAppV1Configuration {
         let mut data = [0; DIGEST_BYTES + 8];
         data[..DIGEST_BYTES].copy_from_slice(&commitment.0);
         data[DIGEST_BYTES..].copy_from_slice(&epoch.to_be_bytes());
-        AzksValueWithEpoch(Self::hash(&data))
+        AzksValueWithEpoch(Self::hash_with_purpose(HashPurpose::Leaf, &[&data]))
     }
 
     /// Used by the server to produce a commitment nonce for an AkdLabel, version, and AkdValue.
@@ -77,14 +100,14 @@ impl Configuration for WhatsAppV1Configuration {
         version: u64,
         value: &AkdValue,
     ) -> Digest {
-        Self::hash(
+        Self::hash_with_purpose(
+            HashPurpose::CommitmentNonce,
             &[
                 commitment_key,
                 &label.to_bytes(),
                 &version.to_be_bytes(),
                 &i2osp_array(value),
-            ]
-            .concat(),
+            ],
         )
     }
 
@@ -105,8 +128,9 @@ impl Configuration for WhatsAppV1Configuration {
         value: &AkdValue,
     ) -> AzksValue {
         let nonce = Self::get_commitment_nonce(commitment_key, label, version, value);
-        AzksValue(Self::hash(
-            &[i2osp_array(value), i2osp_array(&nonce)].concat(),
+        AzksValue(Self::hash_with_purpose(
+            HashPurpose::Commitment,
+            &[&i2osp_array(value), &i2osp_array(&nonce)],
         ))
     }
 
@@ -117,6 +141,7 @@ impl Configuration for WhatsAppV1Configuration {
     /// - I2OSP(len(label) as u64, label)
     /// - A single byte encoded as 0u8 if "stale", 1u8 if "fresh"
     /// - A u64 representing the version
+    ///
     /// These are all interpreted as a single byte array and hashed together, with the output
     /// of the hash returned.
     fn get_hash_from_label_input(
@@ -124,14 +149,14 @@ impl Configuration for WhatsAppV1Configuration {
         freshness: VersionFreshness,
         version: u64,
     ) -> Vec<u8> {
-        let freshness_bytes = [freshness as u8];
-        let hashed_label = Self::hash(
+        let hashed_label = Self::hash_with_purpose(
+            HashPurpose::LabelDerivation,
             &[
-                &crate::utils::i2osp_array(label)[..],
-                &freshness_bytes,
+                &(label.len() as u64).to_be_bytes(),
+                label,
+                &[freshness as u8],
                 &version.to_be_bytes(),
-            ]
-            .concat(),
+            ],
         );
         hashed_label.to_vec()
     }
@@ -143,19 +168,34 @@ impl Configuration for WhatsAppV1Configuration {
         right_val: &AzksValue,
         right_label: &[u8],
     ) -> AzksValue {
-        AzksValue(Self::hash(
+        AzksValue(Self::hash_with_purpose(
+            HashPurpose::Parent,
+            &[
+                &Self::hash_with_purpose(HashPurpose::Parent, &[&left_val.0, left_label]),
+                &Self::hash_with_purpose(HashPurpose::Parent, &[&right_val.0, right_label]),
+            ],
+        ))
+    }
+
+    fn compute_parent_hash_from_child_labels(
+        left_val: &AzksValue,
+        left_label: NodeLabel,
+        right_val: &AzksValue,
+        right_label: NodeLabel,
+    ) -> AzksValue {
+        AzksValue(Self::hash_with_purpose(
+            HashPurpose::Parent,
             &[
-                Self::hash(&[left_val.0.to_vec(), left_label.to_vec()].concat()),
-                Self::hash(&[right_val.0.to_vec(), right_label.to_vec()].concat()),
-            ]
-            .concat(),
+                &Self::hash_child(left_val, left_label),
+                &Self::hash_child(right_val, right_label),
+            ],
         ))
     }
 
     /// Given the top-level hash, compute the "actual" root hash that is published
     /// by the directory maintainer
     fn compute_root_hash_from_val(root_val: &AzksValue) -> Digest {
-        Self::hash(&[&root_val.0[..], &NodeLabel::root().value::<Self>()].concat())
+        Self::hash_all(&[&root_val.0, &Self::hash(&NodeLabel::root().to_bytes())])
     }
 
     /// Similar to commit_fresh_value, but used for stale values.
@@ -163,8 +203,8 @@ impl Configuration for WhatsAppV1Configuration {
         AzksValue(Self::hash(&EMPTY_VALUE))
     }
 
-    fn compute_node_label_value(bytes: &[u8]) -> Vec<u8> {
-        Self::hash(bytes).to_vec()
+    fn compute_node_label_value(bytes: &[u8]) -> NodeLabelValue {
+        NodeLabelValue::from_slice(&Self::hash(bytes))
     }
 
     fn empty_label() -> NodeLabel {
@@ -0,0 +1,277 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! This module implements [CachingVrf], a [VRFKeyStorage] which caches the node labels derived
+//! by another one, since evaluating the VRF dominates the cost of publishing large batches
+
+use super::{VRFKeyStorage, VRFPublicKey, VrfError, VrfScheme};
+use crate::configuration::Configuration;
+use crate::{AkdLabel, AkdValue, NodeLabel, VersionFreshness};
+
+use async_trait::async_trait;
+use std::any::TypeId;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// The inputs from which a node label is derived, along with the configuration deriving it
+type CacheKey = (TypeId, AkdLabel, VersionFreshness, u64);
+
+/// Statistics about the use of a [CachingVrf]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VrfCacheStats {
+    /// The number of node labels which were served from the cache
+    pub hits: u64,
+    /// The number of node labels which had to be derived by the wrapped key storage
+    pub misses: u64,
+    /// The number of node labels which were evicted to make room for others
+    pub evictions: u64,
+    /// The number of node labels currently held by the cache
+    pub entries: usize,
+    /// The fraction of node labels which were served from the cache, or [None] if the cache
+    /// has not been used yet
+    pub hit_rate: Option<f64>,
+}
+
+#[derive(Default)]
+struct CacheState {
+    labels: HashMap<CacheKey, NodeLabel>,
+    /// The keys of the cached labels, in the order in which they were inserted
+    insertion_order: VecDeque<CacheKey>,
+}
+
+/// A [VRFKeyStorage] which keeps up to a bounded number of the node labels derived by the
+/// wrapped key storage, keyed by the label, freshness and version they were derived from. Once
+/// full, the cache evicts the labels in the order in which they were inserted. The cache is
+/// shared by the clones of a [CachingVrf].
+///
+/// The VRF proofs, which are only needed to serve lookup and history proofs, are not cached.
+///
+/// A cache only holds the node labels of the key storage it wraps. When the VRF key is rotated
+/// (see `Directory::rotate_vrf_key` in the `akd` crate), the new key should be wrapped in a new
+/// [CachingVrf], which starts out empty. If the key held by the wrapped storage can change
+/// otherwise, [CachingVrf::invalidate] must be called when it does.
+#[derive(Clone)]
+pub struct CachingVrf<V> {
+    inner: V,
+    capacity: usize,
+    state: Arc<Mutex<CacheState>>,
+    hits: Arc<AtomicU64>,
+    misses: Arc<AtomicU64>,
+    evictions: Arc<AtomicU64>,
+}
+
+impl<V: VRFKeyStorage> CachingVrf<V> {
+    /// Wraps a key storage with a cache which holds up to `capacity` node labels
+    pub fn new(inner: V, capacity: usize) -> Self {
+        Self {
+            inner,
+            capacity,
+            state: Arc::new(Mutex::new(CacheState::default())),
+            hits: Arc::new(AtomicU64::new(0)),
+            misses: Arc::new(AtomicU64::new(0)),
+            evictions: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// The wrapped key storage
+    pub fn inner(&self) -> &V {
+        &self.inner
+    }
+
+    /// Removes all of the cached node labels
+    pub fn invalidate(&self) {
+        let mut state = self.lock_state();
+        state.labels.clear();
+        state.insertion_order.clear();
+    }
+
+    /// Reports statistics about the use of the cache since it was created
+    pub fn stats(&self) -> VrfCacheStats {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        let lookups = hits + misses;
+        VrfCacheStats {
+            hits,
+            misses,
+            evictions: self.evictions.load(Ordering::Relaxed),
+            entries: self.lock_state().labels.len(),
+            hit_rate: (lookups > 0).then(|| hits as f64 / lookups as f64),
+        }
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        // The state is left consistent at every point at which a panic could occur
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn cache_key<TC: Configuration>(
+        label: &AkdLabel,
+        freshness: VersionFreshness,
+        version: u64,
+    ) -> CacheKey {
+        (TypeId::of::<TC>(), label.clone(), freshness, version)
+    }
+
+    fn get(&self, key: &CacheKey) -> Option<NodeLabel> {
+        let node_label = self.lock_state().labels.get(key).copied();
+        match node_label {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+        node_label
+    }
+
+    fn insert(&self, entries: impl IntoIterator<Item = (CacheKey, NodeLabel)>) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut state = self.lock_state();
+        for (key, node_label) in entries {
+            if state.labels.insert(key.clone(), node_label).is_some() {
+                continue;
+            }
+            state.insertion_order.push_back(key);
+            if state.insertion_order.len() > self.capacity {
+                if let Some(evicted) = state.insertion_order.pop_front() {
+                    state.labels.remove(&evicted);
+                    self.evictions.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+
+    /// Returns the node labels which are cached, deriving the others with the inner key
+    /// storage (with the provided parallelism, if any) and caching them
+    async fn get_cached_node_labels<TC: Configuration>(
+        &self,
+        labels: &[(AkdLabel, VersionFreshness, u64, AkdValue)],
+        num_tasks: Option<usize>,
+    ) -> Result<Vec<((AkdLabel, VersionFreshness, u64, AkdValue), NodeLabel)>, VrfError> {
+        let mut results = Vec::with_capacity(labels.len());
+        let mut missing = Vec::new();
+        for input in labels {
+            let (label, freshness, version, _) = input;
+            match self.get(&Self::cache_key::<TC>(label, *freshness, *version)) {
+                Some(node_label) => results.push((input.clone(), node_label)),
+                None => missing.push(input.clone()),
+            }
+        }
+        if missing.is_empty() {
+            return Ok(results);
+        }
+
+        let derived = match num_tasks {
+            Some(num_tasks) => {
+                self.inner
+                    .get_node_labels_with_parallelism::<TC>(&missing, num_tasks)
+                    .await?
+            }
+            None => self.inner.get_node_labels::<TC>(&missing).await?,
+        };
+        self.insert(
+            derived
+                .iter()
+                .map(|((label, freshness, version, _), node_label)| {
+                    (
+                        Self::cache_key::<TC>(label, *freshness, *version),
+                        *node_label,
+                    )
+                }),
+        );
+        results.extend(derived);
+        Ok(results)
+    }
+}
+
+#[async_trait]
+impl<V: VRFKeyStorage> VRFKeyStorage for CachingVrf<V> {
+    async fn retrieve(&self) -> Result<Vec<u8>, VrfError> {
+        self.inner.retrieve().await
+    }
+
+    async fn retrieve_commitment_secret(&self) -> Result<Vec<u8>, VrfError> {
+        self.inner.retrieve_commitment_secret().await
+    }
+
+    async fn destroy_secrets(&self) -> Result<(), VrfError> {
+        self.invalidate();
+        self.inner.destroy_secrets().await
+    }
+
+    async fn get_vrf_public_key(&self) -> Result<VRFPublicKey, VrfError> {
+        self.inner.get_vrf_public_key().await
+    }
+
+    async fn get_expanded_private_key<TC: Configuration>(
+        &self,
+    ) -> Result<<TC::Vrf as VrfScheme>::ExpandedPrivateKey, VrfError> {
+        self.inner.get_expanded_private_key::<TC>().await
+    }
+
+    async fn get_vrf_public_key_bytes<TC: Configuration>(&self) -> Result<Vec<u8>, VrfError> {
+        self.inner.get_vrf_public_key_bytes::<TC>().await
+    }
+
+    async fn get_node_label<TC: Configuration>(
+        &self,
+        label: &AkdLabel,
+        freshness: VersionFreshness,
+        version: u64,
+    ) -> Result<NodeLabel, VrfError> {
+        let key = Self::cache_key::<TC>(label, freshness, version);
+        if let Some(node_label) = self.get(&key) {
+            return Ok(node_label);
+        }
+        let node_label = self
+            .inner
+            .get_node_label::<TC>(label, freshness, version)
+            .await?;
+        self.insert([(key, node_label)]);
+        Ok(node_label)
+    }
+
+    async fn get_node_label_from_vrf_proof<TC: Configuration>(
+        &self,
+        proof: &[u8],
+    ) -> Result<NodeLabel, VrfError> {
+        self.inner.get_node_label_from_vrf_proof::<TC>(proof).await
+    }
+
+    async fn get_label_proof<TC: Configuration>(
+        &self,
+        label: &AkdLabel,
+        freshness: VersionFreshness,
+        version: u64,
+    ) -> Result<Vec<u8>, VrfError> {
+        self.inner
+            .get_label_proof::<TC>(label, freshness, version)
+            .await
+    }
+
+    async fn get_proof<TC: Configuration>(&self, alpha: &[u8]) -> Result<Vec<u8>, VrfError> {
+        self.inner.get_proof::<TC>(alpha).await
+    }
+
+    async fn get_node_labels<TC: Configuration>(
+        &self,
+        labels: &[(AkdLabel, VersionFreshness, u64, AkdValue)],
+    ) -> Result<Vec<((AkdLabel, VersionFreshness, u64, AkdValue), NodeLabel)>, VrfError> {
+        self.get_cached_node_labels::<TC>(labels, None).await
+    }
+
+    async fn get_node_labels_with_parallelism<TC: Configuration>(
+        &self,
+        labels: &[(AkdLabel, VersionFreshness, u64, AkdValue)],
+        num_tasks: usize,
+    ) -> Result<Vec<((AkdLabel, VersionFreshness, u64, AkdValue), NodeLabel)>, VrfError> {
+        self.get_cached_node_labels::<TC>(labels, Some(num_tasks))
+            .await
+    }
+}
@@ -24,7 +24,7 @@ use zeroize::{Zeroize, ZeroizeOnDrop};
 
 /// The length of a node-label's value field in bytes.
 /// This is used for truncation of the hash to this many bytes
-const NODE_LABEL_LEN: usize = 32;
+pub(super) const NODE_LABEL_LEN: usize = 32;
 
 /*
  * NOTE: rust-analyzer gives an "unresolved import" error for the following since the entire
@@ -41,7 +41,7 @@ use ed25519_dalek::VerifyingKey as ed25519_PublicKey;
 use ed25519_dalek::SECRET_KEY_LENGTH;
 use ed25519_dalek::{Digest, PUBLIC_KEY_LENGTH};
 
-const SUITE: u8 = 0x03;
+pub(super) const SUITE: u8 = 0x03;
 const ZERO: u8 = 0x00;
 const ONE: u8 = 0x01;
 const TWO: u8 = 0x02;
@@ -375,22 +375,9 @@ pub struct Output([u8; OUTPUT_LENGTH]);
 
 impl Output {
     /// Converts an Output into bytes
-    #[cfg(test)]
-    #[allow(dead_code)]
     pub(crate) fn to_bytes(&self) -> [u8; OUTPUT_LENGTH] {
         self.0
     }
-
-    /// Retrieve a truncated version of the hash output. Truncated
-    /// to 32 bytes (NODE_LABEL_LEN). Truncation is for future-guarding
-    /// should we change the hash function to a smaller (e.g. BLAKE3) search
-    /// space. Presently it's SHA512, however for this purpose truncation is safe
-    /// since we're just comparing the first 32 bytes rather than the full 64
-    pub(crate) fn to_truncated_bytes(&self) -> [u8; NODE_LABEL_LEN] {
-        let mut truncated_hash: [u8; NODE_LABEL_LEN] = [0u8; NODE_LABEL_LEN];
-        truncated_hash.copy_from_slice(&self.0[..NODE_LABEL_LEN]);
-        truncated_hash
-    }
 }
 
 impl<'a> From<&'a Proof> for Output {
@@ -18,16 +18,34 @@
 //!
 //! This module implements an instantiation of a verifiable random function known as
 //! [ECVRF-EDWARDS25519-SHA512-TAI from RFC9381](https://www.ietf.org/rfc/rfc9381.html).
+//! With the `vrf_p256` feature, it also implements ECVRF-P256-SHA256-TAI from the same
+//! RFC. The suite used by a directory is chosen by its configuration, through the
+//! [VrfScheme] trait. VRF private keys which are held by a remote signer (such as an HSM)
+//! can be used through a [RemoteVrfSigner], and the node labels derived by any key storage can
+//! be cached with a [CachingVrf].
 //!
 //!
 //! Adapted from Diem's NextGen Crypto module available [here](https://github.com/diem/diem/blob/502936fbd59e35276e2cf455532b143796d68a16/crypto/nextgen_crypto/src/vrf/ecvrf.rs)
 
+#[cfg(not(feature = "nostd"))]
+mod cache;
 mod ecvrf_impl;
+#[cfg(feature = "vrf_p256")]
+mod p256;
+mod remote;
+mod scheme;
 mod traits;
 // export the functionality we want visible
+#[cfg(not(feature = "nostd"))]
+pub use crate::ecvrf::cache::{CachingVrf, VrfCacheStats};
 pub use crate::ecvrf::ecvrf_impl::{
     Output, Proof, VRFExpandedPrivateKey, VRFPrivateKey, VRFPublicKey,
 };
+#[cfg(feature = "vrf_p256")]
+pub use crate::ecvrf::p256::{P256ExpandedPrivateKey, P256Sha256Tai};
+pub use crate::ecvrf::remote::{RemoteVrfService, RemoteVrfSigner};
+pub(crate) use crate::ecvrf::scheme::node_label_from_output;
+pub use crate::ecvrf::scheme::{Ed25519Sha512Tai, VrfScheme};
 pub use crate::ecvrf::traits::VRFKeyStorage;
 #[cfg(feature = "nostd")]
 use alloc::boxed::Box;
@@ -81,3 +99,41 @@ impl VRFKeyStorage for HardCodedAkdVRF {
             .map_err(|hex_err| VrfError::PublicKey(hex_err.to_string()))
     }
 }
+
+/// A [VRFKeyStorage] which only holds the VRF public key, for use by instances which
+/// serve proofs but must not hold the VRF private key. Since VRF proofs (and the value
+/// commitments derived from the private key) cannot be computed without the private key,
+/// any operation requiring them fails with [VrfError::SigningKey].
+#[derive(Clone)]
+pub struct PublicKeyOnlyVRF(Vec<u8>);
+
+impl PublicKeyOnlyVRF {
+    /// Creates a new instance holding the provided public key
+    pub fn new(public_key: VRFPublicKey) -> Self {
+        Self(public_key.as_bytes().to_vec())
+    }
+
+    /// Creates a new instance holding the encoding of a public key, for any [VrfScheme]
+    pub fn from_bytes(public_key: Vec<u8>) -> Self {
+        Self(public_key)
+    }
+}
+
+#[async_trait::async_trait]
+impl VRFKeyStorage for PublicKeyOnlyVRF {
+    async fn retrieve(&self) -> Result<Vec<u8>, VrfError> {
+        Err(VrfError::SigningKey(
+            "The VRF private key is not available to this instance".to_string(),
+        ))
+    }
+
+    async fn get_vrf_public_key(&self) -> Result<VRFPublicKey, VrfError> {
+        VRFPublicKey::try_from(&self.0[..])
+    }
+
+    async fn get_vrf_public_key_bytes<TC: crate::configuration::Configuration>(
+        &self,
+    ) -> Result<Vec<u8>, VrfError> {
+        Ok(self.0.clone())
+    }
+}
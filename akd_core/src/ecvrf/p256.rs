@@ -0,0 +1,248 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! This module implements the
+//! [ECVRF-P256-SHA256-TAI suite from RFC9381](https://www.ietf.org/rfc/rfc9381.html#section-5.5),
+//! for deployments whose VRF private key is held by hardware which only supports P-256
+
+use super::{VrfError, VrfScheme};
+
+#[cfg(feature = "nostd")]
+use alloc::format;
+#[cfg(feature = "nostd")]
+use alloc::string::ToString;
+#[cfg(feature = "nostd")]
+use alloc::vec::Vec;
+use p256::elliptic_curve::bigint::ArrayEncoding;
+use p256::elliptic_curve::group::Group;
+use p256::elliptic_curve::ops::Reduce;
+use p256::elliptic_curve::sec1::{FromEncodedPoint, ToEncodedPoint};
+use p256::elliptic_curve::{Curve, PrimeField};
+use p256::{
+    AffinePoint, EncodedPoint, FieldBytes, NistP256, ProjectivePoint, Scalar, SecretKey, U256,
+};
+use sha2::{Digest, Sha256};
+
+const SUITE: u8 = 0x01;
+const ZERO: u8 = 0x00;
+const ONE: u8 = 0x01;
+const TWO: u8 = 0x02;
+const THREE: u8 = 0x03;
+
+/// The number of bytes of an encoded point (ptLen), in compressed SEC1 form
+const POINT_LENGTH: usize = 33;
+/// The number of bytes of the challenge of a proof (cLen)
+const CHALLENGE_LENGTH: usize = 16;
+/// The number of bytes of a scalar (qLen)
+const SCALAR_LENGTH: usize = 32;
+/// The number of bytes of a proof
+const PROOF_LENGTH: usize = POINT_LENGTH + CHALLENGE_LENGTH + SCALAR_LENGTH;
+
+/// The ECVRF-P256-SHA256-TAI suite from RFC 9381. Private keys are encoded as 32-byte
+/// big-endian scalars, and public keys as compressed SEC1 points.
+#[derive(Clone)]
+pub struct P256Sha256Tai;
+
+/// A P-256 VRF private key, along with its public key
+#[derive(Clone)]
+pub struct P256ExpandedPrivateKey {
+    secret: SecretKey,
+    public_point: ProjectivePoint,
+    public_key: [u8; POINT_LENGTH],
+}
+
+impl VrfScheme for P256Sha256Tai {
+    const SUITE_STRING: u8 = SUITE;
+
+    type ExpandedPrivateKey = P256ExpandedPrivateKey;
+
+    fn expand_private_key(private_key: &[u8]) -> Result<Self::ExpandedPrivateKey, VrfError> {
+        if private_key.len() != SCALAR_LENGTH {
+            return Err(VrfError::SigningKey(
+                "Wrong length, expected 32 byte private key".to_string(),
+            ));
+        }
+        let secret = SecretKey::from_bytes(FieldBytes::from_slice(private_key)).map_err(|_| {
+            VrfError::SigningKey("The private key is not a valid P-256 scalar".to_string())
+        })?;
+        let public_point = ProjectivePoint::GENERATOR * *secret.to_nonzero_scalar();
+        let mut public_key = [0u8; POINT_LENGTH];
+        public_key.copy_from_slice(point_to_string(&public_point).as_bytes());
+        Ok(P256ExpandedPrivateKey {
+            secret,
+            public_point,
+            public_key,
+        })
+    }
+
+    fn public_key(private_key: &Self::ExpandedPrivateKey) -> Vec<u8> {
+        private_key.public_key.to_vec()
+    }
+
+    fn prove(private_key: &Self::ExpandedPrivateKey, alpha: &[u8]) -> Vec<u8> {
+        let x = *private_key.secret.to_nonzero_scalar();
+        let h_point = encode_to_curve(&private_key.public_key, alpha);
+        let h_string = point_to_string(&h_point);
+        let gamma = h_point * x;
+        let k = nonce_generation(&private_key.secret, h_string.as_bytes());
+        let c = challenge_generation(&[
+            private_key.public_point,
+            h_point,
+            gamma,
+            ProjectivePoint::GENERATOR * k,
+            h_point * k,
+        ]);
+        let s = k + challenge_to_scalar(&c) * x;
+
+        let mut proof = Vec::with_capacity(PROOF_LENGTH);
+        proof.extend_from_slice(point_to_string(&gamma).as_bytes());
+        proof.extend_from_slice(&c);
+        proof.extend_from_slice(&s.to_repr());
+        proof
+    }
+
+    fn evaluate(private_key: &Self::ExpandedPrivateKey, alpha: &[u8]) -> Vec<u8> {
+        let h_point = encode_to_curve(&private_key.public_key, alpha);
+        gamma_to_output(&(h_point * *private_key.secret.to_nonzero_scalar()))
+    }
+
+    fn verify(public_key: &[u8], proof: &[u8], alpha: &[u8]) -> Result<Vec<u8>, VrfError> {
+        let y_point = string_to_point(public_key).ok_or_else(|| {
+            VrfError::PublicKey("Failed to decode public key into a P-256 point".to_string())
+        })?;
+        let (gamma, c, s) = decode_proof(proof)?;
+        let h_point = encode_to_curve(public_key, alpha);
+        let c_scalar = challenge_to_scalar(&c);
+        let cprime = challenge_generation(&[
+            y_point,
+            h_point,
+            gamma,
+            ProjectivePoint::GENERATOR * s - y_point * c_scalar,
+            h_point * s - gamma * c_scalar,
+        ]);
+
+        if c == cprime {
+            Ok(gamma_to_output(&gamma))
+        } else {
+            Err(VrfError::Verification(
+                "The proof failed to verify for this public key".to_string(),
+            ))
+        }
+    }
+
+    fn proof_to_hash(proof: &[u8]) -> Result<Vec<u8>, VrfError> {
+        let (gamma, _, _) = decode_proof(proof)?;
+        Ok(gamma_to_output(&gamma))
+    }
+}
+
+/// Encodes a point in compressed SEC1 form (the identity is encoded as a single zero byte)
+fn point_to_string(point: &ProjectivePoint) -> EncodedPoint {
+    point.to_affine().to_encoded_point(true)
+}
+
+/// Decodes a point from its compressed SEC1 form, rejecting the identity
+fn string_to_point(bytes: &[u8]) -> Option<ProjectivePoint> {
+    if bytes.len() != POINT_LENGTH {
+        return None;
+    }
+    let encoded = EncodedPoint::from_bytes(bytes).ok()?;
+    let point: Option<AffinePoint> = AffinePoint::from_encoded_point(&encoded).into();
+    point
+        .map(ProjectivePoint::from)
+        .filter(|point| !bool::from(point.is_identity()))
+}
+
+/// Implements the [ECVRF_encode_to_curve_try_and_increment](https://www.ietf.org/rfc/rfc9381.html#section-5.4.1.1)
+/// algorithm, where the public key is used as the salt
+fn encode_to_curve(public_key: &[u8], alpha: &[u8]) -> ProjectivePoint {
+    for counter in 0..=u8::MAX {
+        let hash = Sha256::new()
+            .chain_update([SUITE, ONE])
+            .chain_update(public_key)
+            .chain_update(alpha)
+            .chain_update([counter, ZERO])
+            .finalize();
+        // interpret_hash_value_as_a_point(s) = string_to_point(0x02 || s)
+        let mut candidate = [TWO; POINT_LENGTH];
+        candidate[1..].copy_from_slice(&hash);
+        if let Some(point) = string_to_point(&candidate) {
+            return point;
+        }
+    }
+    // Each candidate is a valid point with probability close to 1/2, so this is never reached
+    // in practice (with probability 2^-256)
+    panic!("Failed to encode the input to a P-256 point")
+}
+
+/// Implements the [ECVRF_nonce_generation_RFC6979](https://www.ietf.org/rfc/rfc9381.html#section-5.4.2.1)
+/// algorithm
+fn nonce_generation(secret: &SecretKey, h_string: &[u8]) -> Scalar {
+    let h1 = <Scalar as Reduce<U256>>::reduce_bytes(&Sha256::digest(h_string));
+    let k = rfc6979::generate_k::<Sha256, _>(
+        &secret.to_bytes(),
+        &NistP256::ORDER.to_be_byte_array(),
+        &h1.to_repr(),
+        &[],
+    );
+    // The generated nonce is already reduced modulo the order of the group
+    <Scalar as Reduce<U256>>::reduce_bytes(&k)
+}
+
+/// Implements the [ECVRF_challenge_generation](https://www.ietf.org/rfc/rfc9381.html#section-5.4.3)
+/// algorithm
+fn challenge_generation(points: &[ProjectivePoint; 5]) -> [u8; CHALLENGE_LENGTH] {
+    let mut hash = Sha256::new().chain_update([SUITE, TWO]);
+    for point in points.iter() {
+        hash.update(point_to_string(point).as_bytes());
+    }
+    let mut challenge = [0u8; CHALLENGE_LENGTH];
+    challenge.copy_from_slice(&hash.chain_update([ZERO]).finalize()[..CHALLENGE_LENGTH]);
+    challenge
+}
+
+fn challenge_to_scalar(challenge: &[u8; CHALLENGE_LENGTH]) -> Scalar {
+    let mut bytes = FieldBytes::default();
+    bytes[SCALAR_LENGTH - CHALLENGE_LENGTH..].copy_from_slice(challenge);
+    <Scalar as Reduce<U256>>::reduce_bytes(&bytes)
+}
+
+/// Implements the [ECVRF_decode_proof](https://www.ietf.org/rfc/rfc9381.html#section-5.4.4)
+/// algorithm
+fn decode_proof(
+    proof: &[u8],
+) -> Result<(ProjectivePoint, [u8; CHALLENGE_LENGTH], Scalar), VrfError> {
+    if proof.len() != PROOF_LENGTH {
+        return Err(VrfError::Verification(format!(
+            "Invalid proof length, expected {PROOF_LENGTH} bytes"
+        )));
+    }
+    let gamma = string_to_point(&proof[..POINT_LENGTH]).ok_or_else(|| {
+        VrfError::Verification("Failed to decode gamma into a P-256 point".to_string())
+    })?;
+    let mut c = [0u8; CHALLENGE_LENGTH];
+    c.copy_from_slice(&proof[POINT_LENGTH..POINT_LENGTH + CHALLENGE_LENGTH]);
+    let s: Option<Scalar> = Scalar::from_repr(*FieldBytes::from_slice(
+        &proof[POINT_LENGTH + CHALLENGE_LENGTH..],
+    ))
+    .into();
+    let s = s.ok_or_else(|| {
+        VrfError::Verification("The s component of the proof is not reduced".to_string())
+    })?;
+    Ok((gamma, c, s))
+}
+
+/// Implements the [ECVRF_proof_to_hash](https://www.ietf.org/rfc/rfc9381.html#section-5.2)
+/// algorithm (the cofactor of P-256 is 1)
+fn gamma_to_output(gamma: &ProjectivePoint) -> Vec<u8> {
+    Sha256::new()
+        .chain_update([SUITE, THREE])
+        .chain_update(point_to_string(gamma).as_bytes())
+        .chain_update([ZERO])
+        .finalize()
+        .to_vec()
+}
@@ -0,0 +1,257 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! This module implements a [VRFKeyStorage] for VRF private keys which never leave a remote
+//! signer (such as an HSM or a KMS), by requesting the VRF proofs from the signer in batches
+
+use super::{node_label_from_output, VRFKeyStorage, VRFPublicKey, VrfError, VrfScheme};
+use crate::configuration::Configuration;
+use crate::{AkdLabel, AkdValue, NodeLabel, VersionFreshness};
+
+#[cfg(feature = "nostd")]
+use alloc::boxed::Box;
+#[cfg(feature = "nostd")]
+use alloc::format;
+#[cfg(feature = "nostd")]
+use alloc::string::ToString;
+#[cfg(feature = "nostd")]
+use alloc::vec;
+#[cfg(feature = "nostd")]
+use alloc::vec::Vec;
+use async_trait::async_trait;
+use core::convert::TryFrom;
+
+/// A remote signer holding the VRF private key, to be implemented by the client of the HSM or
+/// KMS holding the key. The proofs must be produced with the VRF suite of the configuration of
+/// the directory.
+#[async_trait]
+pub trait RemoteVrfService: Clone + Send + Sync + 'static {
+    /// Retrieve the encoding of the VRF public key
+    async fn public_key(&self) -> Result<Vec<u8>, VrfError>;
+
+    /// Retrieve the secret from which the key committing to the values of the directory is
+    /// derived. It must be kept as secret as the VRF private key, and never change.
+    async fn commitment_secret(&self) -> Result<Vec<u8>, VrfError>;
+
+    /// Produce the encodings of the VRF proofs for a batch of inputs, in the order of the inputs
+    async fn prove_batch(&self, alphas: Vec<Vec<u8>>) -> Result<Vec<Vec<u8>>, VrfError>;
+}
+
+/// A [VRFKeyStorage] whose VRF proofs are produced by a [RemoteVrfService].
+///
+/// The VRF computations of a publish are split into batches of at most
+/// [RemoteVrfSigner::with_max_batch_size] inputs, one request being sent per batch. With the
+/// `parallel_vrf` feature, up to [RemoteVrfSigner::with_max_concurrent_requests] requests are
+/// in flight at the same time.
+#[derive(Clone)]
+pub struct RemoteVrfSigner<S> {
+    service: S,
+    max_batch_size: usize,
+    max_concurrent_requests: usize,
+    verify_proofs: bool,
+}
+
+impl<S: RemoteVrfService> RemoteVrfSigner<S> {
+    /// The default maximum number of inputs in a request to the service
+    pub const DEFAULT_MAX_BATCH_SIZE: usize = 1000;
+    /// The default maximum number of requests to the service which are in flight at once
+    pub const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 8;
+
+    /// Creates a signer for the provided service, which verifies the proofs it receives
+    pub fn new(service: S) -> Self {
+        Self {
+            service,
+            max_batch_size: Self::DEFAULT_MAX_BATCH_SIZE,
+            max_concurrent_requests: Self::DEFAULT_MAX_CONCURRENT_REQUESTS,
+            verify_proofs: true,
+        }
+    }
+
+    /// Sets the maximum number of inputs in a request to the service (at least 1)
+    pub fn with_max_batch_size(mut self, max_batch_size: usize) -> Self {
+        self.max_batch_size = max_batch_size.max(1);
+        self
+    }
+
+    /// Sets the maximum number of requests to the service which are in flight at once (at
+    /// least 1). This only has an effect with the `parallel_vrf` feature.
+    pub fn with_max_concurrent_requests(mut self, max_concurrent_requests: usize) -> Self {
+        self.max_concurrent_requests = max_concurrent_requests.max(1);
+        self
+    }
+
+    /// Sets whether the proofs received from the service are verified against its public key,
+    /// so that a faulty service cannot corrupt the directory. This roughly doubles the cost of
+    /// the VRF computations, and is enabled by default.
+    pub fn with_proof_verification(mut self, verify_proofs: bool) -> Self {
+        self.verify_proofs = verify_proofs;
+        self
+    }
+
+    /// Requests the proofs for the inputs from the service, returning them along with the
+    /// outputs of the VRF for each input
+    async fn prove_all<TC: Configuration>(
+        &self,
+        alphas: Vec<Vec<u8>>,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, VrfError> {
+        let public_key = if self.verify_proofs {
+            Some(self.service.public_key().await?)
+        } else {
+            None
+        };
+        let batches: Vec<Vec<Vec<u8>>> = alphas
+            .chunks(self.max_batch_size)
+            .map(|batch| batch.to_vec())
+            .collect();
+        let proofs = self.request_batches(batches).await?;
+
+        let mut results = Vec::with_capacity(alphas.len());
+        for (alpha, proof) in alphas.iter().zip(proofs) {
+            let output = match &public_key {
+                Some(public_key) => TC::Vrf::verify(public_key, &proof, alpha)?,
+                None => TC::Vrf::proof_to_hash(&proof)?,
+            };
+            results.push((proof, output));
+        }
+        Ok(results)
+    }
+
+    /// Sends a request to the service for each batch, returning the proofs of all the batches
+    /// in order
+    async fn request_batches(&self, batches: Vec<Vec<Vec<u8>>>) -> Result<Vec<Vec<u8>>, VrfError> {
+        let mut proofs = Vec::new();
+
+        #[cfg(feature = "parallel_vrf")]
+        {
+            let mut batches = batches.into_iter();
+            loop {
+                let mut join_set = tokio::task::JoinSet::new();
+                for (index, batch) in batches
+                    .by_ref()
+                    .take(self.max_concurrent_requests)
+                    .enumerate()
+                {
+                    let service = self.service.clone();
+                    join_set.spawn(async move {
+                        let num_inputs = batch.len();
+                        (index, num_inputs, service.prove_batch(batch).await)
+                    });
+                }
+                if join_set.is_empty() {
+                    break;
+                }
+
+                let mut responses = vec![None; join_set.len()];
+                while let Some(res) = join_set.join_next().await {
+                    let (index, num_inputs, response) = res.map_err(|join_err| {
+                        VrfError::SigningKey(format!("Parallel VRF join error {join_err}"))
+                    })?;
+                    responses[index] = Some(check_response(num_inputs, response?)?);
+                }
+                proofs.extend(responses.into_iter().flatten().flatten());
+            }
+        }
+        #[cfg(not(feature = "parallel_vrf"))]
+        {
+            for batch in batches {
+                let num_inputs = batch.len();
+                let response = self.service.prove_batch(batch).await?;
+                proofs.extend(check_response(num_inputs, response)?);
+            }
+        }
+
+        Ok(proofs)
+    }
+}
+
+/// Checks that the service returned a proof for each input of a batch
+fn check_response(num_inputs: usize, proofs: Vec<Vec<u8>>) -> Result<Vec<Vec<u8>>, VrfError> {
+    if proofs.len() != num_inputs {
+        return Err(VrfError::SigningKey(format!(
+            "Expected {num_inputs} proofs from the remote VRF service, but got {}",
+            proofs.len()
+        )));
+    }
+    Ok(proofs)
+}
+
+#[async_trait]
+impl<S: RemoteVrfService> VRFKeyStorage for RemoteVrfSigner<S> {
+    async fn retrieve(&self) -> Result<Vec<u8>, VrfError> {
+        Err(VrfError::SigningKey(
+            "The VRF private key is held by a remote signer".to_string(),
+        ))
+    }
+
+    async fn retrieve_commitment_secret(&self) -> Result<Vec<u8>, VrfError> {
+        self.service.commitment_secret().await
+    }
+
+    async fn get_vrf_public_key(&self) -> Result<VRFPublicKey, VrfError> {
+        VRFPublicKey::try_from(&self.service.public_key().await?[..])
+    }
+
+    async fn get_vrf_public_key_bytes<TC: Configuration>(&self) -> Result<Vec<u8>, VrfError> {
+        self.service.public_key().await
+    }
+
+    async fn get_node_label<TC: Configuration>(
+        &self,
+        label: &AkdLabel,
+        freshness: VersionFreshness,
+        version: u64,
+    ) -> Result<NodeLabel, VrfError> {
+        let hashed_label = TC::get_hash_from_label_input(label, freshness, version);
+        let (_, output) = self.prove_all::<TC>(vec![hashed_label]).await?.remove(0);
+        Ok(node_label_from_output(&output))
+    }
+
+    async fn get_label_proof<TC: Configuration>(
+        &self,
+        label: &AkdLabel,
+        freshness: VersionFreshness,
+        version: u64,
+    ) -> Result<Vec<u8>, VrfError> {
+        let hashed_label = TC::get_hash_from_label_input(label, freshness, version);
+        let (proof, _) = self.prove_all::<TC>(vec![hashed_label]).await?.remove(0);
+        Ok(proof)
+    }
+
+    async fn get_proof<TC: Configuration>(&self, alpha: &[u8]) -> Result<Vec<u8>, VrfError> {
+        let (proof, _) = self.prove_all::<TC>(vec![alpha.to_vec()]).await?.remove(0);
+        Ok(proof)
+    }
+
+    async fn get_node_labels<TC: Configuration>(
+        &self,
+        labels: &[(AkdLabel, VersionFreshness, u64, AkdValue)],
+    ) -> Result<Vec<((AkdLabel, VersionFreshness, u64, AkdValue), NodeLabel)>, VrfError> {
+        let alphas = labels
+            .iter()
+            .map(|(label, freshness, version, _)| {
+                TC::get_hash_from_label_input(label, *freshness, *version)
+            })
+            .collect();
+        let outputs = self.prove_all::<TC>(alphas).await?;
+        Ok(labels
+            .iter()
+            .cloned()
+            .zip(outputs)
+            .map(|(input, (_, output))| (input, node_label_from_output(&output)))
+            .collect())
+    }
+
+    async fn get_node_labels_with_parallelism<TC: Configuration>(
+        &self,
+        labels: &[(AkdLabel, VersionFreshness, u64, AkdValue)],
+        _num_tasks: usize,
+    ) -> Result<Vec<((AkdLabel, VersionFreshness, u64, AkdValue), NodeLabel)>, VrfError> {
+        // The evaluations are bound by the requests to the service, whose concurrency is
+        // configured with RemoteVrfSigner::with_max_concurrent_requests
+        self.get_node_labels::<TC>(labels).await
+    }
+}
@@ -0,0 +1,101 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! This module defines the [VrfScheme] trait, which abstracts over the ECVRF suites of
+//! [RFC9381](https://www.ietf.org/rfc/rfc9381.html) which can be used by a directory
+
+use super::ecvrf_impl::NODE_LABEL_LEN;
+use super::{Output, Proof, VRFExpandedPrivateKey, VRFPrivateKey, VRFPublicKey, VrfError};
+use crate::NodeLabel;
+
+#[cfg(feature = "nostd")]
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+/// A VRF suite, which maps the inputs computed from the labels of the directory to the
+/// labels of their nodes. Keys, proofs and outputs are exchanged in their encodings as
+/// defined by the suite in RFC 9381, so that the proofs which are produced by the
+/// directory can be checked by any implementation of the suite.
+///
+/// The suite used by a directory is selected by its [Configuration](crate::configuration::Configuration).
+pub trait VrfScheme: Clone + Send + Sync + 'static {
+    /// The suite_string of the suite, as defined in RFC 9381
+    const SUITE_STRING: u8;
+
+    /// A private key, along with anything which can be derived from it ahead of time to
+    /// speed up repeated evaluations of the VRF (such as its public key)
+    type ExpandedPrivateKey: Clone + Send + Sync + 'static;
+
+    /// Decodes a private key, as retrieved from a [VRFKeyStorage](super::VRFKeyStorage)
+    fn expand_private_key(private_key: &[u8]) -> Result<Self::ExpandedPrivateKey, VrfError>;
+
+    /// Returns the encoding of the public key corresponding to a private key
+    fn public_key(private_key: &Self::ExpandedPrivateKey) -> Vec<u8>;
+
+    /// Produces the encoding of a proof for an input
+    fn prove(private_key: &Self::ExpandedPrivateKey, alpha: &[u8]) -> Vec<u8>;
+
+    /// Directly evaluates the VRF for an input, without producing a proof
+    fn evaluate(private_key: &Self::ExpandedPrivateKey, alpha: &[u8]) -> Vec<u8>;
+
+    /// Verifies the encoding of a proof for an input with the encoding of a public key,
+    /// returning the output of the VRF if the proof is valid
+    fn verify(public_key: &[u8], proof: &[u8], alpha: &[u8]) -> Result<Vec<u8>, VrfError>;
+
+    /// Returns the output of the VRF from the encoding of a proof, without verifying it
+    fn proof_to_hash(proof: &[u8]) -> Result<Vec<u8>, VrfError>;
+}
+
+/// Returns the [NodeLabel] corresponding to an output of the VRF.
+///
+/// The output is truncated to the first 32 bytes (NODE_LABEL_LEN), which is safe since the
+/// outputs of all supported suites hold at least as many bytes, and guards against changing
+/// the suite to one with a smaller output.
+pub(crate) fn node_label_from_output(output: &[u8]) -> NodeLabel {
+    let mut truncated_hash = [0u8; NODE_LABEL_LEN];
+    truncated_hash.copy_from_slice(&output[..NODE_LABEL_LEN]);
+    NodeLabel::new(truncated_hash, 256)
+}
+
+/// The ECVRF-EDWARDS25519-SHA512-TAI suite from RFC 9381, implemented by [VRFPrivateKey]
+/// and [VRFPublicKey]
+#[derive(Clone)]
+pub struct Ed25519Sha512Tai;
+
+impl VrfScheme for Ed25519Sha512Tai {
+    const SUITE_STRING: u8 = super::ecvrf_impl::SUITE;
+
+    type ExpandedPrivateKey = (VRFExpandedPrivateKey, VRFPublicKey);
+
+    fn expand_private_key(private_key: &[u8]) -> Result<Self::ExpandedPrivateKey, VrfError> {
+        let key = VRFPrivateKey::try_from(private_key)?;
+        Ok((VRFExpandedPrivateKey::from(&key), VRFPublicKey::from(&key)))
+    }
+
+    fn public_key((_, public_key): &Self::ExpandedPrivateKey) -> Vec<u8> {
+        public_key.as_bytes().to_vec()
+    }
+
+    fn prove((private_key, public_key): &Self::ExpandedPrivateKey, alpha: &[u8]) -> Vec<u8> {
+        private_key.prove(public_key, alpha).to_bytes().to_vec()
+    }
+
+    fn evaluate((private_key, public_key): &Self::ExpandedPrivateKey, alpha: &[u8]) -> Vec<u8> {
+        private_key.evaluate(public_key, alpha).to_bytes().to_vec()
+    }
+
+    fn verify(public_key: &[u8], proof: &[u8], alpha: &[u8]) -> Result<Vec<u8>, VrfError> {
+        let public_key = VRFPublicKey::try_from(public_key)?;
+        let proof = Proof::try_from(proof)?;
+        public_key.verify(&proof, alpha)?;
+        Ok(Output::from(&proof).to_bytes().to_vec())
+    }
+
+    fn proof_to_hash(proof: &[u8]) -> Result<Vec<u8>, VrfError> {
+        Ok(Output::from(&Proof::try_from(proof)?).to_bytes().to_vec())
+    }
+}
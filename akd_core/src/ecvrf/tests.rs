@@ -8,6 +8,9 @@
 //! This module contains tests on the Elliptic curve VRF implemented within the
 //! AKD crate. Adapted from [here](https://github.com/diem/diem/blob/502936fbd59e35276e2cf455532b143796d68a16/crypto/nextgen_crypto/src/vrf/unit_tests/vrf_test.rs)
 
+// Older `proptest_derive` versions emit their `Arbitrary` impls inside an anonymous const
+#![allow(non_local_definitions)]
+
 use crate::ecvrf::ecvrf_impl::*;
 
 #[cfg(feature = "nostd")]
@@ -192,7 +195,7 @@ fn test_expand_secret_key() {
     for tv in TESTVECTORS.iter() {
         let sk = from_string!(VRFPrivateKey, tv.SK);
         let esk = VRFExpandedPrivateKey::from(&sk);
-        let pk = VRFPublicKey::try_from(&sk).unwrap();
+        let pk = VRFPublicKey::from(&sk);
         assert_eq!(tv.PK, to_string!(pk));
         assert_eq!(tv.x, to_string!(esk.key));
     }
@@ -317,7 +320,7 @@ proptest! {
         keypair in uniform_keypair_strategy::<VRFPrivateKey, VRFPublicKey>()
     ) {
         let (pk, sk) = (&keypair.public_key, &keypair.private_key);
-        let pk_test = VRFPublicKey::try_from(sk).unwrap();
+        let pk_test = VRFPublicKey::from(sk);
         prop_assert_eq!(pk, &pk_test);
         let (input1, input2) = (hash1.hash.as_ref(), hash2.hash.as_ref());
         let proof1 = sk.prove(input1);
@@ -360,3 +363,113 @@ fn test_tryfrom_vrf_proof() {
     let bytes = [0u8; PROOF_LENGTH - 1];
     assert!(Proof::try_from(&bytes[..]).is_err());
 }
+
+#[cfg(feature = "vrf_p256")]
+mod p256 {
+    use crate::ecvrf::{Ed25519Sha512Tai, P256Sha256Tai, VrfScheme};
+
+    #[allow(non_snake_case)]
+    struct P256TestVector {
+        SK: &'static str,
+        PK: &'static str,
+        alpha: &'static [u8],
+        pi: &'static str,
+        beta: &'static str,
+    }
+
+    /// These test vectors are taken from [RFC9381, Section B.1](https://www.ietf.org/rfc/rfc9381.html#name-ecvrf-p256-sha256-tai).
+    const TESTVECTORS: [P256TestVector; 2] = [
+        // Example 10
+        P256TestVector {
+            SK: "c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721",
+            PK: "0360fed4ba255a9d31c961eb74c6356d68c049b8923b61fa6ce669622e60f29fb6",
+            alpha: b"sample",
+            pi: "035b5c726e8c0e2c488a107c600578ee75cb702343c153cb1eb8dec77f4b5071b4a53f0a46f018bc2c56e58d383f2305e0975972c26feea0eb122fe7893c15af376b33edf7de17c6ea056d4d82de6bc02f",
+            beta: "a3ad7b0ef73d8fc6655053ea22f9bede8c743f08bbed3d38821f0e16474b505e",
+        },
+        // Example 11
+        P256TestVector {
+            SK: "c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721",
+            PK: "0360fed4ba255a9d31c961eb74c6356d68c049b8923b61fa6ce669622e60f29fb6",
+            alpha: b"test",
+            pi: "034dac60aba508ba0c01aa9be80377ebd7562c4a52d74722e0abae7dc3080ddb56c19e067b15a8a8174905b13617804534214f935b94c2287f797e393eb0816969d864f37625b443f30f1a5a33f2b3c854",
+            beta: "a284f94ceec2ff4b3794629da7cbafa49121972671b466cab4ce170aa365f26d",
+        },
+    ];
+
+    fn expand(sk: &str) -> <P256Sha256Tai as VrfScheme>::ExpandedPrivateKey {
+        P256Sha256Tai::expand_private_key(&hex::decode(sk).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_p256_test_vectors() {
+        for tv in TESTVECTORS.iter() {
+            let key = expand(tv.SK);
+            assert_eq!(tv.PK, hex::encode(P256Sha256Tai::public_key(&key)));
+            assert_eq!(tv.pi, hex::encode(P256Sha256Tai::prove(&key, tv.alpha)));
+            assert_eq!(
+                tv.beta,
+                hex::encode(P256Sha256Tai::evaluate(&key, tv.alpha))
+            );
+
+            let pk = hex::decode(tv.PK).unwrap();
+            let pi = hex::decode(tv.pi).unwrap();
+            assert_eq!(
+                tv.beta,
+                hex::encode(P256Sha256Tai::verify(&pk, &pi, tv.alpha).unwrap())
+            );
+            assert_eq!(
+                tv.beta,
+                hex::encode(P256Sha256Tai::proof_to_hash(&pi).unwrap())
+            );
+        }
+    }
+
+    #[test]
+    fn test_p256_invalid_proofs() {
+        let tv = &TESTVECTORS[0];
+        let pk = hex::decode(tv.PK).unwrap();
+        let pi = hex::decode(tv.pi).unwrap();
+
+        // A proof does not verify for another input
+        assert!(P256Sha256Tai::verify(&pk, &pi, b"another input").is_err());
+
+        // A proof does not verify once any of its components is modified
+        for index in [1, 40, 60] {
+            let mut tampered = pi.clone();
+            tampered[index] ^= 1;
+            assert!(P256Sha256Tai::verify(&pk, &tampered, tv.alpha).is_err());
+        }
+
+        // A proof does not verify for another public key
+        let other_key = expand("2ca1411a41b17b24cc8c3b089cfd033f1920202a6c0de8abb97df1498d50d2c8");
+        let other_pk = P256Sha256Tai::public_key(&other_key);
+        assert!(P256Sha256Tai::verify(&other_pk, &pi, tv.alpha).is_err());
+
+        // A proof whose s component is not reduced modulo the group order is rejected
+        let mut unreduced = pi.clone();
+        for byte in unreduced[49..].iter_mut() {
+            *byte = 0xff;
+        }
+        assert!(P256Sha256Tai::verify(&pk, &unreduced, tv.alpha).is_err());
+
+        assert!(P256Sha256Tai::verify(&pk, &pi[..pi.len() - 1], tv.alpha).is_err());
+        assert!(P256Sha256Tai::verify(&pk[..32], &pi, tv.alpha).is_err());
+        assert!(P256Sha256Tai::proof_to_hash(&pi[1..]).is_err());
+
+        // Proofs of the ed25519 suite are not accepted by this suite, and vice versa
+        let ed25519_key =
+            Ed25519Sha512Tai::expand_private_key(&hex::decode(tv.SK).unwrap()).unwrap();
+        let ed25519_pk = Ed25519Sha512Tai::public_key(&ed25519_key);
+        let ed25519_pi = Ed25519Sha512Tai::prove(&ed25519_key, tv.alpha);
+        assert!(P256Sha256Tai::verify(&ed25519_pk, &ed25519_pi, tv.alpha).is_err());
+        assert!(Ed25519Sha512Tai::verify(&pk, &pi, tv.alpha).is_err());
+    }
+
+    #[test]
+    fn test_p256_invalid_private_keys() {
+        assert!(P256Sha256Tai::expand_private_key(&[0u8; 32]).is_err());
+        assert!(P256Sha256Tai::expand_private_key(&[0xffu8; 32]).is_err());
+        assert!(P256Sha256Tai::expand_private_key(&[1u8; 31]).is_err());
+    }
+}
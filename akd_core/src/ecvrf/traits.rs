@@ -7,7 +7,7 @@
 
 //! This module implements traits for managing ECVRF, mainly pertaining to storage
 //! of public and private keys
-use super::{Output, Proof, VRFExpandedPrivateKey, VRFPrivateKey, VRFPublicKey, VrfError};
+use super::{node_label_from_output, VRFPrivateKey, VRFPublicKey, VrfError, VrfScheme};
 use crate::configuration::Configuration;
 use crate::{AkdLabel, AkdValue, NodeLabel, VersionFreshness};
 
@@ -17,6 +17,7 @@ use alloc::boxed::Box;
 use alloc::vec::Vec;
 use async_trait::async_trait;
 use core::convert::TryInto;
+use zeroize::Zeroizing;
 
 /// Represents a secure storage of the VRF private key. Since the VRF private key
 /// should change never (if it does, the entire tree is no longer a consistent mapping
@@ -24,7 +25,10 @@ use core::convert::TryInto;
 /// static cache of the private key bytes which lives for the life of the process.
 ///
 /// I.e. retrieve the byte vector 1 time, and simply keep serving it up without doing
-/// network access calls
+/// network access calls. Such a cache should be wiped by [VRFKeyStorage::destroy_secrets].
+///
+/// The bytes returned by [VRFKeyStorage::retrieve] and [VRFKeyStorage::retrieve_commitment_secret]
+/// are zeroized once the keys have been derived from them.
 #[async_trait]
 pub trait VRFKeyStorage: Clone + Sync + Send {
     /* ======= To be implemented ====== */
@@ -34,9 +38,24 @@ pub trait VRFKeyStorage: Clone + Sync + Send {
 
     /* ======= Common trait functionality ====== */
 
-    /// Retrieve the properly constructed VRF Private key
+    /// Retrieve the secret from which the key committing to the values of the directory is
+    /// derived. This is the VRF private key, unless the storage does not hold it (in which
+    /// case another secret, which never changes, must be provided).
+    async fn retrieve_commitment_secret(&self) -> Result<Vec<u8>, VrfError> {
+        self.retrieve().await
+    }
+
+    /// Wipes the secret material which this storage holds in memory (such as a cached copy of
+    /// the private key), after which it can no longer be used to produce VRF proofs or
+    /// commitments. This does nothing for storages which do not hold secrets in memory.
+    async fn destroy_secrets(&self) -> Result<(), VrfError> {
+        Ok(())
+    }
+
+    /// Retrieve the properly constructed VRF Private key of the
+    /// [Ed25519Sha512Tai](super::Ed25519Sha512Tai) suite
     async fn get_vrf_private_key(&self) -> Result<VRFPrivateKey, VrfError> {
-        match self.retrieve().await {
+        match self.retrieve().await.map(Zeroizing::new) {
             Ok(bytes) => {
                 let pk_ref: &[u8] = &bytes;
                 pk_ref.try_into()
@@ -45,11 +64,26 @@ pub trait VRFKeyStorage: Clone + Sync + Send {
         }
     }
 
-    /// Retrieve the VRF public key
+    /// Retrieve the VRF public key of the [Ed25519Sha512Tai](super::Ed25519Sha512Tai) suite
     async fn get_vrf_public_key(&self) -> Result<VRFPublicKey, VrfError> {
         self.get_vrf_private_key().await.map(|key| (&key).into())
     }
 
+    /// Retrieve the VRF private key, expanded for the VRF suite of the configuration
+    async fn get_expanded_private_key<TC: Configuration>(
+        &self,
+    ) -> Result<<TC::Vrf as VrfScheme>::ExpandedPrivateKey, VrfError> {
+        TC::Vrf::expand_private_key(&Zeroizing::new(self.retrieve().await?))
+    }
+
+    /// Retrieve the encoding of the VRF public key for the VRF suite of the configuration,
+    /// which is the public key expected by the verification of the proofs
+    async fn get_vrf_public_key_bytes<TC: Configuration>(&self) -> Result<Vec<u8>, VrfError> {
+        Ok(TC::Vrf::public_key(
+            &self.get_expanded_private_key::<TC>().await?,
+        ))
+    }
+
     /// Returns the [NodeLabel] that corresponds to a version of the label argument.
     ///
     /// The stale boolean here is to indicate whether we are getting the [NodeLabel] for a fresh version,
@@ -60,12 +94,9 @@ pub trait VRFKeyStorage: Clone + Sync + Send {
         freshness: VersionFreshness,
         version: u64,
     ) -> Result<NodeLabel, VrfError> {
-        let key = self.get_vrf_private_key().await?;
-        let expanded_key = VRFExpandedPrivateKey::from(&key);
-        let pk = VRFPublicKey::from(&key);
+        let expanded_key = self.get_expanded_private_key::<TC>().await?;
         Ok(Self::get_node_label_with_expanded_key::<TC>(
             &expanded_key,
-            &pk,
             label,
             freshness,
             version,
@@ -78,62 +109,67 @@ pub trait VRFKeyStorage: Clone + Sync + Send {
     /// The stale boolean here is to indicate whether we are getting the [NodeLabel] for a fresh version,
     /// or a version that we are retiring.
     fn get_node_label_with_expanded_key<TC: Configuration>(
-        expanded_private_key: &VRFExpandedPrivateKey,
-        pk: &VRFPublicKey,
+        expanded_private_key: &<TC::Vrf as VrfScheme>::ExpandedPrivateKey,
         label: &AkdLabel,
         freshness: VersionFreshness,
         version: u64,
     ) -> NodeLabel {
-        let output = Self::get_label_with_key_helper::<TC>(
-            expanded_private_key,
-            pk,
-            label,
-            freshness,
-            version,
-        );
-        NodeLabel::new(output.to_truncated_bytes(), 256)
+        let output =
+            Self::get_label_with_key_helper::<TC>(expanded_private_key, label, freshness, version);
+        node_label_from_output(&output)
     }
 
-    /// Returns the tree nodelabel that corresponds to a vrf proof.
-    async fn get_node_label_from_vrf_proof(&self, proof: Proof) -> NodeLabel {
-        let output: super::ecvrf_impl::Output = (&proof).into();
-        NodeLabel::new(output.to_truncated_bytes(), 256)
+    /// Returns the tree nodelabel that corresponds to the encoding of a vrf proof.
+    async fn get_node_label_from_vrf_proof<TC: Configuration>(
+        &self,
+        proof: &[u8],
+    ) -> Result<NodeLabel, VrfError> {
+        Ok(node_label_from_output(&TC::Vrf::proof_to_hash(proof)?))
     }
 
-    /// Retrieve the proof for a specific label
+    /// Retrieve the encoding of the proof for a specific label
     async fn get_label_proof<TC: Configuration>(
         &self,
         label: &AkdLabel,
         freshness: VersionFreshness,
         version: u64,
-    ) -> Result<Proof, VrfError> {
-        let key = self.get_vrf_private_key().await?;
+    ) -> Result<Vec<u8>, VrfError> {
+        let expanded_key = self.get_expanded_private_key::<TC>().await?;
         Ok(Self::get_label_proof_with_key::<TC>(
-            &key, label, freshness, version,
+            &expanded_key,
+            label,
+            freshness,
+            version,
         ))
     }
 
-    /// Retrieve the proof for a specific label, with a supplied private key
+    /// Retrieve the encoding of the proof for a specific label, with a supplied private key
     fn get_label_proof_with_key<TC: Configuration>(
-        key: &VRFPrivateKey,
+        expanded_private_key: &<TC::Vrf as VrfScheme>::ExpandedPrivateKey,
         label: &AkdLabel,
         freshness: VersionFreshness,
         version: u64,
-    ) -> Proof {
+    ) -> Vec<u8> {
         let hashed_label = TC::get_hash_from_label_input(label, freshness, version);
-        key.prove(&hashed_label)
+        TC::Vrf::prove(expanded_private_key, &hashed_label)
+    }
+
+    /// Retrieve the encoding of the proof for an arbitrary input of the VRF, such as the message
+    /// of a [crate::VrfRotationCertificate]
+    async fn get_proof<TC: Configuration>(&self, alpha: &[u8]) -> Result<Vec<u8>, VrfError> {
+        let expanded_key = self.get_expanded_private_key::<TC>().await?;
+        Ok(TC::Vrf::prove(&expanded_key, alpha))
     }
 
     /// Retrieve the output for a specific label, with a supplied private key
     fn get_label_with_key_helper<TC: Configuration>(
-        expanded_private_key: &VRFExpandedPrivateKey,
-        pk: &VRFPublicKey,
+        expanded_private_key: &<TC::Vrf as VrfScheme>::ExpandedPrivateKey,
         label: &AkdLabel,
         freshness: VersionFreshness,
         version: u64,
-    ) -> Output {
+    ) -> Vec<u8> {
         let hashed_label = TC::get_hash_from_label_input(label, freshness, version);
-        expanded_private_key.evaluate(pk, &hashed_label)
+        TC::Vrf::evaluate(expanded_private_key, &hashed_label)
     }
 
     /// Returns the [NodeLabel]s that corresponds to a collection of (label, freshness, version) arguments
@@ -145,11 +181,9 @@ pub trait VRFKeyStorage: Clone + Sync + Send {
         &self,
         labels: &[(AkdLabel, VersionFreshness, u64, AkdValue)],
     ) -> Result<Vec<((AkdLabel, VersionFreshness, u64, AkdValue), NodeLabel)>, VrfError> {
-        let key = self.get_vrf_private_key().await?;
-        let expanded_key = VRFExpandedPrivateKey::from(&key);
-        let pk = VRFPublicKey::from(&key);
+        let expanded_key = self.get_expanded_private_key::<TC>().await?;
 
-        #[cfg(feature = "parallel_vrf")]
+        #[cfg(all(feature = "parallel_vrf", not(feature = "profiling")))]
         {
             #[cfg(feature = "nostd")]
             use alloc::format;
@@ -158,14 +192,12 @@ pub trait VRFKeyStorage: Clone + Sync + Send {
             let labels_vec = labels.to_vec();
             for (label, freshness, version, value) in labels_vec.into_iter() {
                 let expanded_key_ref = expanded_key.clone();
-                let pk_ref = pk.clone();
 
                 let future = {
                     async move {
                         (
                             Self::get_node_label_with_expanded_key::<TC>(
                                 &expanded_key_ref,
-                                &pk_ref,
                                 &label,
                                 freshness,
                                 version,
@@ -192,13 +224,12 @@ pub trait VRFKeyStorage: Clone + Sync + Send {
             }
             Ok(results)
         }
-        #[cfg(not(feature = "parallel_vrf"))]
+        #[cfg(any(not(feature = "parallel_vrf"), feature = "profiling"))]
         {
             let mut results = Vec::new();
             for (label, freshness, version, value) in labels {
                 let node_label = Self::get_node_label_with_expanded_key::<TC>(
                     &expanded_key,
-                    &pk,
                     label,
                     *freshness,
                     *version,
@@ -211,4 +242,59 @@ pub trait VRFKeyStorage: Clone + Sync + Send {
             Ok(results)
         }
     }
+
+    /// Same as [VRFKeyStorage::get_node_labels], but with the `parallel_vrf` feature the VRF
+    /// evaluations are split into `num_tasks` chunks of consecutive arguments, which are each
+    /// evaluated on the blocking thread pool of tokio. This avoids both the overhead of a task per
+    /// evaluation and stalling the asynchronous worker threads on CPU-bound work. The node labels
+    /// are returned in the order of the arguments.
+    ///
+    /// Implementations which override [VRFKeyStorage::get_node_labels] should override this too,
+    /// as it otherwise evaluates the VRF with the key returned by [VRFKeyStorage::retrieve].
+    async fn get_node_labels_with_parallelism<TC: Configuration>(
+        &self,
+        labels: &[(AkdLabel, VersionFreshness, u64, AkdValue)],
+        num_tasks: usize,
+    ) -> Result<Vec<((AkdLabel, VersionFreshness, u64, AkdValue), NodeLabel)>, VrfError> {
+        #[cfg(all(feature = "parallel_vrf", not(feature = "profiling")))]
+        {
+            #[cfg(feature = "nostd")]
+            use alloc::format;
+
+            let expanded_key = self.get_expanded_private_key::<TC>().await?;
+            let chunk_size = labels.len().div_ceil(num_tasks.max(1)).max(1);
+            let mut handles = Vec::new();
+            for chunk in labels.chunks(chunk_size) {
+                let expanded_key_ref = expanded_key.clone();
+                let chunk = chunk.to_vec();
+                handles.push(tokio::task::spawn_blocking(move || {
+                    chunk
+                        .into_iter()
+                        .map(|(label, freshness, version, value)| {
+                            let node_label = Self::get_node_label_with_expanded_key::<TC>(
+                                &expanded_key_ref,
+                                &label,
+                                freshness,
+                                version,
+                            );
+                            ((label, freshness, version, value), node_label)
+                        })
+                        .collect::<Vec<_>>()
+                }));
+            }
+
+            let mut results = Vec::with_capacity(labels.len());
+            for handle in handles {
+                results.extend(handle.await.map_err(|join_err| {
+                    VrfError::SigningKey(format!("Parallel VRF join error {join_err}"))
+                })?);
+            }
+            Ok(results)
+        }
+        #[cfg(any(not(feature = "parallel_vrf"), feature = "profiling"))]
+        {
+            let _ = num_tasks;
+            self.get_node_labels::<TC>(labels).await
+        }
+    }
 }
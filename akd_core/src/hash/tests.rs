@@ -24,3 +24,63 @@ fn test_try_parse_digest() {
     let data_bad_length = vec![0u8; DIGEST_BYTES + 1];
     assert!(try_parse_digest(&data_bad_length).is_err());
 }
+
+/// A domain label which is empty, to compare the hashes of configurations to test vectors
+#[cfg(any(feature = "sha3_256", feature = "domain_separated"))]
+#[derive(Clone)]
+struct EmptyLabel;
+
+#[cfg(any(feature = "sha3_256", feature = "domain_separated"))]
+impl crate::configuration::DomainLabel for EmptyLabel {
+    fn domain_label() -> &'static [u8] {
+        &[]
+    }
+}
+
+#[cfg(feature = "sha3_256")]
+#[test]
+fn test_sha3_256_configuration_hash() {
+    use crate::configuration::Configuration;
+
+    // The SHA3-256 test vector for "abc" from FIPS 202
+    let expected =
+        hex::decode("3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532").unwrap();
+    type TC = crate::Sha3Configuration<EmptyLabel>;
+    assert_eq!(expected, TC::hash(b"abc").to_vec());
+    assert_eq!(expected, TC::hash_all(&[b"a", b"", b"bc"]).to_vec());
+}
+
+#[cfg(feature = "domain_separated")]
+#[test]
+fn test_domain_separated_configuration_hash() {
+    use crate::configuration::{Configuration, HashPurpose};
+
+    type TC = crate::DomainSeparatedConfiguration<EmptyLabel>;
+    let purposes = [
+        HashPurpose::Leaf,
+        HashPurpose::Parent,
+        HashPurpose::Commitment,
+        HashPurpose::CommitmentNonce,
+        HashPurpose::CommitmentKey,
+        HashPurpose::LabelDerivation,
+        HashPurpose::Decoy,
+    ];
+    let mut digests = vec![TC::hash_all(&[b"abc"])];
+    for purpose in purposes {
+        // Each hash is prefixed with the tag of its purpose
+        let digest = TC::hash_with_purpose(purpose, &[b"a", b"bc"]);
+        assert_eq!(digest, TC::hash(&[purpose.tag(), b"abc"].concat()));
+        digests.push(digest);
+    }
+    // The hashes of the same items for different purposes are distinct
+    for (i, digest) in digests.iter().enumerate() {
+        assert!(!digests[i + 1..].contains(digest));
+    }
+
+    // The configurations which predate the purposes hash without a tag
+    #[cfg(feature = "sha3_256")]
+    assert_eq!(
+        crate::Sha3Configuration::<EmptyLabel>::hash(b"abc"),
+        crate::Sha3Configuration::<EmptyLabel>::hash_with_purpose(HashPurpose::Leaf, &[b"abc"])
+    );
+}
@@ -16,6 +16,22 @@
 //! or `default-features = false` in your Cargo.toml import to disable all of the default features
 //! which you can then enable one-by-one as you wish.
 //!
+//! With the `nostd` feature, the crate is built without the standard library (but still requires
+//! `alloc`). The verification of membership and non-membership proofs hashes its inputs
+//! incrementally (see [Configuration::hash_all]) rather than allocating at each level of the tree,
+//! so that constrained verifiers (e.g. in secure enclaves) can run it with a small heap.
+//!
+//! The proof types and the hashing of the tree only require the `vrf` feature (as the VRF is part
+//! of each configuration), while the verification of the proofs (the `verify` module) requires the
+//! `verify` feature, which is enabled by default, and their protobuf encoding (the `proto` module)
+//! requires the `protobuf` feature. The protobuf sources are only generated (by the build script)
+//! with the `protobuf` feature, so that a client which verifies proofs it constructs natively can
+//! depend on this crate with `default-features = false, features = ["verify", "experimental"]`
+//! (or its configuration of choice), without pulling in the protobuf runtime or code generator.
+//! The verification of the Ed25519 signatures over epoch root hashes and of epoch bundles (see the
+//! `signature` module) only requires the `vrf` feature, so that a `nostd` client can authenticate
+//! the root hashes it is served with `features = ["nostd", "vrf", "experimental"]`.
+//!
 //! In the following, we will cover the protocol-level implementation details behind:
 //! - The setup parameters for an AKD
 //! - How the tree (and its root hash) is constructed from a set of `([AkdLabel], [AkdValue])` pairs
@@ -56,6 +72,7 @@
 //! - The label in bytes
 //! - A single byte encoded as `0u8` if "stale", `1u8` if "fresh"
 //! - A `u64` representing the version (starting at 1 for newly inserted labels, and incremented by 1 for each update)
+//!
 //! The resulting values are hashed together and used as the byte string (truncated to 256 bits) that is stored
 //! as the [NodeLabel].
 //!
@@ -69,6 +86,14 @@
 //! - `commitment_nonce = Hash(commitment_key, node_label, version, I2OSP(len(value) as u64), value)`
 //! - `commmitment = Hash(I2OSP(len(value) as u64), value, I2OSP(len(commitment_nonce) as u64), commitment_nonce)`
 //!
+//! If the client supplied a blinding for the value, the nonce is instead co-derived with it by
+//! `get_blinded_commitment_nonce()`, as `Hash(commitment_nonce, I2OSP(len(blinding) as u64), blinding)`,
+//! so that the commitment cannot be opened with the commitment key alone.
+//!
+//! If the configuration pads values (see [Configuration::value_padding_bucket_size]), the `value` above is the
+//! value padded to a multiple of the bucket size, which is also the value included in proofs. Clients remove the
+//! padding once the proofs are verified.
+//!
 //! Finally, the commitment is hashed together with the epoch that it ends up being inserted into the tree,
 //! computed as: `azks_value = Hash(commitment, epoch)`
 //!
@@ -136,7 +161,7 @@
 //! Let `n` be the current version, and let `m` be the largest power
 //! of 2 that is at most `n`. The [LookupProof] consists of:
 //! - The `commitment_nonce` corresponding to the value, which the client
-//! can hash together with the value to reconstruct the commitment
+//!   can hash together with the value to reconstruct the commitment
 //! - A membership and VRF proof for version `n` being marked as fresh
 //! - A non-membership and VRF proof for version `n` being marked as stale
 //! - A membership and VRF proof for version `m` being marked as fresh
@@ -153,7 +178,7 @@
 //! Let `n` be the latest version, `n_next_pow` the next power of 2 after `n`, and `epoch_prev_pow` be the power of 2 that
 //! is at most the current epoch. The [HistoryProof] consists of:
 //! - A list of [UpdateProof]s, one for each version, which each contain a membership proof for the version `n` being fresh,
-//! and a membership proof for the version `n-1` being stale
+//!   and a membership proof for the version `n-1` being stale
 //! - A membership proof for `n_prev_pow` (or empty if n is a power of 2)
 //! - A series of non-membership proofs for each version in the range `[n+1, n_next_pow]`
 //! - A series of non-membership proofs for each power of 2 in the range `[n_next_pow, epoch_prev_pow]`
@@ -183,18 +208,30 @@ extern crate alloc;
 #[cfg(all(feature = "protobuf", not(feature = "nostd")))]
 pub mod proto;
 
+#[cfg(all(feature = "cbor", not(feature = "nostd")))]
+pub mod cbor;
+
+pub mod bundle;
 pub mod ecvrf;
 pub mod hash;
+pub mod signature;
 pub mod utils;
+#[cfg(feature = "verify")]
 pub mod verify;
 
 pub mod configuration;
-pub use configuration::{Configuration, DomainLabel, ExampleLabel};
+pub use configuration::{
+    Configuration, DomainLabel, ExampleLabel, HashPurpose, PaddedConfiguration,
+};
 
 // Note(new_config): Update this when adding a new configuration
 
+#[cfg(feature = "domain_separated")]
+pub use configuration::domain_separated::DomainSeparatedConfiguration;
 #[cfg(feature = "experimental")]
-pub use configuration::experimental::ExperimentalConfiguration;
+pub use configuration::experimental::{Blake3Configuration, ExperimentalConfiguration};
+#[cfg(feature = "sha3_256")]
+pub use configuration::sha3_256::Sha3Configuration;
 #[cfg(feature = "whatsapp_v1")]
 pub use configuration::whatsapp_v1::WhatsAppV1Configuration;
 
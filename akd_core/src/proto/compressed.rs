@@ -0,0 +1,481 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! This module contains the conversions between the native lookup and history proofs and their
+//! compressed protobuf encodings ([specs::types::CompressedLookupProof] and
+//! [specs::types::CompressedHistoryProof]), which are version 2 of the proof wire format.
+//!
+//! The labels along the path of a membership proof share their prefixes with the label of the
+//! proof, so they are encoded as the number of bytes which they share with it followed by their
+//! remaining bytes (or only by their length, if they are a prefix of it). The digests of all of the membership and non-membership proofs within a
+//! proof are deduplicated into a single list, and referred to by their index in it. The
+//! compression is lossless, so a decompressed proof verifies exactly as the original one.
+
+use super::{
+    decode_minimized_label, specs, ConversionError, ProofDecodingError, DIRECTION_BLINDING_FACTOR,
+    MAX_LABEL_LEN, MAX_LABEL_VAL_BYTES, MAX_SIBLING_PROOFS, MAX_UPDATE_PROOFS,
+};
+use crate::{AzksValue, Bit};
+
+use core::convert::{TryFrom, TryInto};
+use protobuf::MessageField;
+use std::collections::HashMap;
+
+/// The deduplicated digests of a proof being compressed
+#[derive(Default)]
+struct DigestTable {
+    digests: Vec<Vec<u8>>,
+    indices: HashMap<crate::hash::Digest, u32>,
+}
+
+impl DigestTable {
+    fn index(&mut self, value: &AzksValue) -> u32 {
+        *self.indices.entry(value.0).or_insert_with(|| {
+            self.digests.push(value.0.to_vec());
+            (self.digests.len() - 1) as u32
+        })
+    }
+}
+
+/// The digests of a compressed proof being decompressed
+struct Digests(Vec<crate::hash::Digest>);
+
+impl Digests {
+    fn parse(digests: &[Vec<u8>]) -> Result<Self, ConversionError> {
+        digests
+            .iter()
+            .map(|digest| {
+                crate::hash::try_parse_digest(digest).map_err(ConversionError::Deserialization)
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Self)
+    }
+
+    fn get(&self, index: u32) -> Result<AzksValue, ConversionError> {
+        self.0
+            .get(index as usize)
+            .map(|digest| AzksValue(*digest))
+            .ok_or_else(|| ProofDecodingError::InvalidDigestIndex(index).into())
+    }
+}
+
+// ==============================================================
+// DeltaNodeLabel
+// ==============================================================
+
+fn compress_label(
+    label: &crate::NodeLabel,
+    reference: &crate::NodeLabel,
+) -> specs::types::DeltaNodeLabel {
+    if *label == reference.get_prefix(label.label_len) {
+        return specs::types::DeltaNodeLabel {
+            label_len: Some(label.label_len),
+            ..Default::default()
+        };
+    }
+
+    let shared_bytes = label
+        .label_val
+        .iter()
+        .zip(reference.label_val.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    // The suffix omits the trailing zeros, as in the minimal encoding of a label
+    let suffix = &label.label_val[shared_bytes..];
+    let suffix_len = suffix
+        .iter()
+        .rposition(|b| *b != 0)
+        .map_or(0, |last| last + 1);
+    specs::types::DeltaNodeLabel {
+        shared_bytes: Some(shared_bytes as u32),
+        suffix: Some(suffix[..suffix_len].to_vec()),
+        label_len: Some(label.label_len),
+        ..Default::default()
+    }
+}
+
+fn decompress_label(
+    input: &specs::types::DeltaNodeLabel,
+    reference: &crate::NodeLabel,
+) -> Result<crate::NodeLabel, ConversionError> {
+    require!(input, has_label_len);
+    if input.label_len() > MAX_LABEL_LEN {
+        return Err(ProofDecodingError::LabelLenTooLarge(input.label_len()).into());
+    }
+    if !input.has_shared_bytes() && !input.has_suffix() {
+        return Ok(reference.get_prefix(input.label_len()));
+    }
+    require!(input, has_shared_bytes);
+    require!(input, has_suffix);
+
+    let shared_bytes = input.shared_bytes() as usize;
+    let suffix = input.suffix();
+    let len = shared_bytes.saturating_add(suffix.len());
+    if len > MAX_LABEL_VAL_BYTES {
+        return Err(ProofDecodingError::LabelValTooLong(len).into());
+    }
+
+    let mut label_val = decode_minimized_label(&reference.label_val[..shared_bytes]);
+    label_val[shared_bytes..len].copy_from_slice(suffix);
+    Ok(crate::NodeLabel {
+        label_val,
+        label_len: input.label_len(),
+    })
+}
+
+// ==============================================================
+// CompressedAzksElement
+// ==============================================================
+
+fn compress_element(
+    element: &crate::AzksElement,
+    reference: &crate::NodeLabel,
+    digests: &mut DigestTable,
+) -> specs::types::CompressedAzksElement {
+    specs::types::CompressedAzksElement {
+        label: MessageField::some(compress_label(&element.label, reference)),
+        value_index: Some(digests.index(&element.value)),
+        ..Default::default()
+    }
+}
+
+fn decompress_element(
+    input: &specs::types::CompressedAzksElement,
+    reference: &crate::NodeLabel,
+    digests: &Digests,
+) -> Result<crate::AzksElement, ConversionError> {
+    require_messagefield!(input, label);
+    require!(input, has_value_index);
+    Ok(crate::AzksElement {
+        label: decompress_label(input.label.as_ref().unwrap(), reference)?,
+        value: digests.get(input.value_index())?,
+    })
+}
+
+// ==============================================================
+// CompressedMembershipProof
+// ==============================================================
+
+fn compress_membership_proof(
+    proof: &crate::MembershipProof,
+    digests: &mut DigestTable,
+) -> specs::types::CompressedMembershipProof {
+    specs::types::CompressedMembershipProof {
+        label: MessageField::some((&proof.label).into()),
+        hash_val_index: Some(digests.index(&proof.hash_val)),
+        sibling_proofs: proof
+            .sibling_proofs
+            .iter()
+            .map(|sibling_proof| specs::types::CompressedSiblingProof {
+                label: MessageField::some(compress_label(&sibling_proof.label, &proof.label)),
+                sibling: MessageField::some(compress_element(
+                    &sibling_proof.siblings[0],
+                    &proof.label,
+                    digests,
+                )),
+                direction: Some(sibling_proof.direction as u32),
+                ..Default::default()
+            })
+            .collect::<Vec<_>>(),
+        ..Default::default()
+    }
+}
+
+fn decompress_membership_proof(
+    input: &specs::types::CompressedMembershipProof,
+    digests: &Digests,
+) -> Result<crate::MembershipProof, ConversionError> {
+    require_messagefield!(input, label);
+    require!(input, has_hash_val_index);
+    let label: crate::NodeLabel = input.label.as_ref().unwrap().try_into()?;
+
+    if input.sibling_proofs.len() > MAX_SIBLING_PROOFS {
+        return Err(ProofDecodingError::TooManySiblingProofs(input.sibling_proofs.len()).into());
+    }
+    let mut sibling_proofs = vec![];
+    for sibling_proof in input.sibling_proofs.iter() {
+        require_messagefield!(sibling_proof, label);
+        require_messagefield!(sibling_proof, sibling);
+        require!(sibling_proof, has_direction);
+
+        // blind out the highest bits to all 0's, since we're pulling it down to a u8
+        let direction = (sibling_proof.direction() & DIRECTION_BLINDING_FACTOR) as u8;
+        let bit = match direction {
+            0 => Bit::Zero,
+            1 => Bit::One,
+            _ => {
+                return Err(ConversionError::Deserialization(format!(
+                    "Invalid direction: {direction}"
+                )))
+            }
+        };
+        sibling_proofs.push(crate::SiblingProof {
+            label: decompress_label(sibling_proof.label.as_ref().unwrap(), &label)?,
+            siblings: [decompress_element(
+                sibling_proof.sibling.as_ref().unwrap(),
+                &label,
+                digests,
+            )?],
+            direction: crate::types::Direction::from(bit),
+        });
+    }
+
+    Ok(crate::MembershipProof {
+        label,
+        hash_val: digests.get(input.hash_val_index())?,
+        sibling_proofs,
+    })
+}
+
+// ==============================================================
+// CompressedNonMembershipProof
+// ==============================================================
+
+fn compress_non_membership_proof(
+    proof: &crate::NonMembershipProof,
+    digests: &mut DigestTable,
+) -> specs::types::CompressedNonMembershipProof {
+    specs::types::CompressedNonMembershipProof {
+        label: MessageField::some((&proof.label).into()),
+        longest_prefix: MessageField::some(compress_label(&proof.longest_prefix, &proof.label)),
+        longest_prefix_children: proof
+            .longest_prefix_children
+            .iter()
+            .map(|child| compress_element(child, &proof.label, digests))
+            .collect::<Vec<_>>(),
+        longest_prefix_membership_proof: MessageField::some(compress_membership_proof(
+            &proof.longest_prefix_membership_proof,
+            digests,
+        )),
+        ..Default::default()
+    }
+}
+
+fn decompress_non_membership_proof(
+    input: &specs::types::CompressedNonMembershipProof,
+    digests: &Digests,
+) -> Result<crate::NonMembershipProof, ConversionError> {
+    require_messagefield!(input, label);
+    require_messagefield!(input, longest_prefix);
+    require_messagefield!(input, longest_prefix_membership_proof);
+    let label: crate::NodeLabel = input.label.as_ref().unwrap().try_into()?;
+
+    let mut longest_prefix_children = vec![];
+    for child in input.longest_prefix_children.iter() {
+        longest_prefix_children.push(decompress_element(child, &label, digests)?);
+    }
+
+    Ok(crate::NonMembershipProof {
+        label,
+        longest_prefix: decompress_label(input.longest_prefix.as_ref().unwrap(), &label)?,
+        longest_prefix_children: longest_prefix_children.try_into().map_err(|_| {
+            ConversionError::Deserialization(
+                "Required field longest_prefix_children must be 2 elements long".to_string(),
+            )
+        })?,
+        longest_prefix_membership_proof: decompress_membership_proof(
+            input.longest_prefix_membership_proof.as_ref().unwrap(),
+            digests,
+        )?,
+    })
+}
+
+// ==============================================================
+// CompressedLookupProof
+// ==============================================================
+
+impl From<&crate::LookupProof> for specs::types::CompressedLookupProof {
+    fn from(input: &crate::LookupProof) -> Self {
+        let mut digests = DigestTable::default();
+        let existence_proof = compress_membership_proof(&input.existence_proof, &mut digests);
+        let marker_proof = compress_membership_proof(&input.marker_proof, &mut digests);
+        let freshness_proof = compress_non_membership_proof(&input.freshness_proof, &mut digests);
+        Self {
+            epoch: Some(input.epoch),
+            value: input.value.bytes().map(<[u8]>::to_vec),
+            version: Some(input.version),
+            existence_vrf_proof: Some(input.existence_vrf_proof.clone()),
+            existence_proof: MessageField::some(existence_proof),
+            marker_vrf_proof: Some(input.marker_vrf_proof.clone()),
+            marker_proof: MessageField::some(marker_proof),
+            freshness_vrf_proof: Some(input.freshness_vrf_proof.clone()),
+            freshness_proof: MessageField::some(freshness_proof),
+            commitment_nonce: Some(input.commitment_nonce.clone()),
+            digests: digests.digests,
+            vrf_rotation: MessageField::from_option(input.vrf_rotation.as_ref().map(Into::into)),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<&specs::types::CompressedLookupProof> for crate::LookupProof {
+    type Error = ConversionError;
+
+    fn try_from(input: &specs::types::CompressedLookupProof) -> Result<Self, Self::Error> {
+        require!(input, has_epoch);
+        require!(input, has_value);
+        require!(input, has_version);
+        require!(input, has_existence_vrf_proof);
+        require_messagefield!(input, existence_proof);
+        require!(input, has_marker_vrf_proof);
+        require_messagefield!(input, marker_proof);
+        require!(input, has_freshness_vrf_proof);
+        require_messagefield!(input, freshness_proof);
+        require!(input, has_commitment_nonce);
+        let digests = Digests::parse(&input.digests)?;
+
+        Ok(Self {
+            epoch: input.epoch(),
+            value: crate::AkdValue::Bytes(input.value().to_vec()),
+            version: input.version(),
+            existence_vrf_proof: input.existence_vrf_proof().to_vec(),
+            existence_proof: decompress_membership_proof(
+                input.existence_proof.as_ref().unwrap(),
+                &digests,
+            )?,
+            marker_vrf_proof: input.marker_vrf_proof().to_vec(),
+            marker_proof: decompress_membership_proof(
+                input.marker_proof.as_ref().unwrap(),
+                &digests,
+            )?,
+            freshness_vrf_proof: input.freshness_vrf_proof().to_vec(),
+            freshness_proof: decompress_non_membership_proof(
+                input.freshness_proof.as_ref().unwrap(),
+                &digests,
+            )?,
+            commitment_nonce: input.commitment_nonce().to_vec(),
+            vrf_rotation: input
+                .vrf_rotation
+                .as_ref()
+                .map(|item| item.try_into())
+                .transpose()?,
+        })
+    }
+}
+
+// ==============================================================
+// CompressedHistoryProof
+// ==============================================================
+
+fn compress_update_proof(
+    input: &crate::UpdateProof,
+    digests: &mut DigestTable,
+) -> specs::types::CompressedUpdateProof {
+    specs::types::CompressedUpdateProof {
+        epoch: Some(input.epoch),
+        value: input.value.bytes().map(<[u8]>::to_vec),
+        version: Some(input.version),
+        existence_vrf_proof: Some(input.existence_vrf_proof.clone()),
+        existence_proof: MessageField::some(compress_membership_proof(
+            &input.existence_proof,
+            digests,
+        )),
+        previous_version_vrf_proof: input.previous_version_vrf_proof.as_ref().cloned(),
+        previous_version_proof: MessageField::from_option(
+            input
+                .previous_version_proof
+                .as_ref()
+                .map(|proof| compress_membership_proof(proof, digests)),
+        ),
+        commitment_nonce: Some(input.commitment_nonce.clone()),
+        ..Default::default()
+    }
+}
+
+fn decompress_update_proof(
+    input: &specs::types::CompressedUpdateProof,
+    digests: &Digests,
+) -> Result<crate::UpdateProof, ConversionError> {
+    require!(input, has_epoch);
+    require!(input, has_version);
+    require!(input, has_existence_vrf_proof);
+    require_messagefield!(input, existence_proof);
+    require!(input, has_commitment_nonce);
+
+    Ok(crate::UpdateProof {
+        epoch: input.epoch(),
+        // A missing value denotes a tombstone
+        value: input
+            .value
+            .clone()
+            .map_or(crate::AkdValue::Tombstone, crate::AkdValue::Bytes),
+        version: input.version(),
+        existence_vrf_proof: input.existence_vrf_proof().to_vec(),
+        existence_proof: decompress_membership_proof(
+            input.existence_proof.as_ref().unwrap(),
+            digests,
+        )?,
+        previous_version_vrf_proof: input.previous_version_vrf_proof.clone(),
+        previous_version_proof: input
+            .previous_version_proof
+            .as_ref()
+            .map(|proof| decompress_membership_proof(proof, digests))
+            .transpose()?,
+        commitment_nonce: input.commitment_nonce().to_vec(),
+    })
+}
+
+impl From<&crate::HistoryProof> for specs::types::CompressedHistoryProof {
+    fn from(input: &crate::HistoryProof) -> Self {
+        let mut digests = DigestTable::default();
+        let update_proofs = input
+            .update_proofs
+            .iter()
+            .map(|proof| compress_update_proof(proof, &mut digests))
+            .collect::<Vec<_>>();
+        let existence_of_past_marker_proofs = input
+            .existence_of_past_marker_proofs
+            .iter()
+            .map(|proof| compress_membership_proof(proof, &mut digests))
+            .collect::<Vec<_>>();
+        let non_existence_of_future_marker_proofs = input
+            .non_existence_of_future_marker_proofs
+            .iter()
+            .map(|proof| compress_non_membership_proof(proof, &mut digests))
+            .collect::<Vec<_>>();
+        Self {
+            update_proofs,
+            past_marker_vrf_proofs: input.past_marker_vrf_proofs.to_vec(),
+            existence_of_past_marker_proofs,
+            future_marker_vrf_proofs: input.future_marker_vrf_proofs.to_vec(),
+            non_existence_of_future_marker_proofs,
+            digests: digests.digests,
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<&specs::types::CompressedHistoryProof> for crate::HistoryProof {
+    type Error = ConversionError;
+
+    fn try_from(input: &specs::types::CompressedHistoryProof) -> Result<Self, Self::Error> {
+        if input.update_proofs.len() > MAX_UPDATE_PROOFS {
+            return Err(ProofDecodingError::TooManyUpdateProofs(input.update_proofs.len()).into());
+        }
+        let digests = Digests::parse(&input.digests)?;
+
+        Ok(Self {
+            update_proofs: input
+                .update_proofs
+                .iter()
+                .map(|proof| decompress_update_proof(proof, &digests))
+                .collect::<Result<Vec<_>, _>>()?,
+            past_marker_vrf_proofs: input.past_marker_vrf_proofs.to_vec(),
+            existence_of_past_marker_proofs: input
+                .existence_of_past_marker_proofs
+                .iter()
+                .map(|proof| decompress_membership_proof(proof, &digests))
+                .collect::<Result<Vec<_>, _>>()?,
+            future_marker_vrf_proofs: input.future_marker_vrf_proofs.to_vec(),
+            non_existence_of_future_marker_proofs: input
+                .non_existence_of_future_marker_proofs
+                .iter()
+                .map(|proof| decompress_non_membership_proof(proof, &digests))
+                .collect::<Result<Vec<_>, _>>()?,
+        })
+    }
+}
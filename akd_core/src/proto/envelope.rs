@@ -0,0 +1,129 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! This module contains the versioned wire format of the proofs, which wraps the encoding of a
+//! proof in a [specs::types::ProofEnvelope] recording the version of the format. A client and
+//! server agree on a version with [negotiate_version], the server encodes its proofs in that
+//! version with [encode_versioned], and the client decodes them with [decode_versioned], which
+//! dispatches on the version of the envelope.
+//!
+//! Version 1 encodes the proofs as their [specs::types::LookupProof], [specs::types::HistoryProof]
+//! and [specs::types::AppendOnlyProof] messages, and version 2 encodes the lookup and history
+//! proofs in their compressed encodings (see [super::compressed]).
+
+use super::{specs, ConversionError};
+use protobuf::Message;
+
+/// The latest version of the proof wire format
+pub const CURRENT_FORMAT_VERSION: u32 = 2;
+
+/// The versions of the proof wire format which can be encoded and decoded
+pub const SUPPORTED_FORMAT_VERSIONS: [u32; 2] = [1, 2];
+
+/// Returns the latest version of the proof wire format which is supported by both
+/// this library and the peer, if any
+pub fn negotiate_version(peer_versions: &[u32]) -> Option<u32> {
+    SUPPORTED_FORMAT_VERSIONS
+        .iter()
+        .copied()
+        .filter(|version| peer_versions.contains(version))
+        .max()
+}
+
+/// A proof which can be encoded in the versioned wire format
+pub trait VersionedProof: Sized {
+    /// The type of the proof, as recorded in its envelope
+    const PROOF_TYPE: specs::types::ProofType;
+
+    /// Encodes the proof in the given version of the wire format
+    fn encode_payload(&self, version: u32) -> Result<Vec<u8>, ConversionError>;
+
+    /// Decodes a proof from the given version of the wire format
+    fn decode_payload(version: u32, payload: &[u8]) -> Result<Self, ConversionError>;
+}
+
+macro_rules! versioned_proof {
+    ($native:ty, $message:ty, $compressed_message:ty, $proof_type:expr) => {
+        impl VersionedProof for $native {
+            const PROOF_TYPE: specs::types::ProofType = $proof_type;
+
+            fn encode_payload(&self, version: u32) -> Result<Vec<u8>, ConversionError> {
+                // NOTE: Add new versions of the wire format here
+                match version {
+                    1 => Ok(<$message>::from(self).write_to_bytes()?),
+                    2 => Ok(<$compressed_message>::from(self).write_to_bytes()?),
+                    _ => Err(ConversionError::UnsupportedVersion(version)),
+                }
+            }
+
+            fn decode_payload(version: u32, payload: &[u8]) -> Result<Self, ConversionError> {
+                match version {
+                    1 => (&<$message>::parse_from_bytes(payload)?).try_into(),
+                    2 => (&<$compressed_message>::parse_from_bytes(payload)?).try_into(),
+                    _ => Err(ConversionError::UnsupportedVersion(version)),
+                }
+            }
+        }
+    };
+}
+
+versioned_proof!(
+    crate::LookupProof,
+    specs::types::LookupProof,
+    specs::types::CompressedLookupProof,
+    specs::types::ProofType::LOOKUP_PROOF
+);
+versioned_proof!(
+    crate::HistoryProof,
+    specs::types::HistoryProof,
+    specs::types::CompressedHistoryProof,
+    specs::types::ProofType::HISTORY_PROOF
+);
+// Append-only proofs are encoded identically in both versions
+versioned_proof!(
+    crate::AppendOnlyProof,
+    specs::types::AppendOnlyProof,
+    specs::types::AppendOnlyProof,
+    specs::types::ProofType::APPEND_ONLY_PROOF
+);
+
+/// Encodes the proof in an envelope, in the given version of the wire format (e.g. one
+/// agreed upon with [negotiate_version])
+pub fn encode_versioned<T: VersionedProof>(
+    proof: &T,
+    version: u32,
+) -> Result<Vec<u8>, ConversionError> {
+    let envelope = specs::types::ProofEnvelope {
+        version: Some(version),
+        proof_type: Some(T::PROOF_TYPE.into()),
+        payload: Some(proof.encode_payload(version)?),
+        ..Default::default()
+    };
+    Ok(envelope.write_to_bytes()?)
+}
+
+/// Decodes a proof from its envelope, in whichever supported version of the wire
+/// format it was encoded
+pub fn decode_versioned<T: VersionedProof>(bytes: &[u8]) -> Result<T, ConversionError> {
+    let envelope = specs::types::ProofEnvelope::parse_from_bytes(bytes)?;
+    if !envelope.has_version() || !envelope.has_payload() {
+        return Err(ConversionError::Deserialization(
+            "The proof envelope is missing its version or payload".to_string(),
+        ));
+    }
+    if envelope
+        .proof_type
+        .map(|proof_type| proof_type.enum_value())
+        != Some(Ok(T::PROOF_TYPE))
+    {
+        return Err(ConversionError::Deserialization(format!(
+            "Expected a proof of type {:?} in the proof envelope",
+            T::PROOF_TYPE
+        )));
+    }
+    T::decode_payload(envelope.version(), envelope.payload())
+}
@@ -12,6 +12,12 @@
 // Setup the protobuf specs
 pub mod specs;
 
+pub mod envelope;
+pub use envelope::{
+    decode_versioned, encode_versioned, negotiate_version, VersionedProof, CURRENT_FORMAT_VERSION,
+    SUPPORTED_FORMAT_VERSIONS,
+};
+
 #[cfg(test)]
 mod tests;
 
@@ -22,6 +28,59 @@ use protobuf::MessageField;
 
 const DIRECTION_BLINDING_FACTOR: u32 = 0x000Fu32;
 
+/// The maximum number of sibling proofs in a membership proof, which is the depth of the tree
+pub const MAX_SIBLING_PROOFS: usize = 256;
+/// The maximum number of update proofs in a history proof
+pub const MAX_UPDATE_PROOFS: usize = 1 << 16;
+/// The maximum length in bytes of the (minimally encoded) value of a node label
+pub const MAX_LABEL_VAL_BYTES: usize = 32;
+/// The maximum length in bits of a node label
+pub const MAX_LABEL_LEN: u32 = 256;
+
+/// A protobuf proof which exceeds the limits on its size or is malformed, and which is
+/// rejected before it is converted
+#[derive(Debug, Eq, PartialEq)]
+pub enum ProofDecodingError {
+    /// A membership proof has more than [MAX_SIBLING_PROOFS] sibling proofs
+    TooManySiblingProofs(usize),
+    /// A sibling proof does not have exactly one sibling
+    InvalidSiblingCount(usize),
+    /// A history proof has more than [MAX_UPDATE_PROOFS] update proofs
+    TooManyUpdateProofs(usize),
+    /// The value of a node label is longer than [MAX_LABEL_VAL_BYTES]
+    LabelValTooLong(usize),
+    /// The length of a node label is larger than [MAX_LABEL_LEN]
+    LabelLenTooLarge(u32),
+    /// A compressed proof refers to a digest which is not in its list of digests
+    InvalidDigestIndex(u32),
+}
+
+impl core::fmt::Display for ProofDecodingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> core::fmt::Result {
+        let code = match &self {
+            ProofDecodingError::TooManySiblingProofs(count) => format!(
+                "(TooManySiblingProofs) - {count} sibling proofs, should be at most {MAX_SIBLING_PROOFS}"
+            ),
+            ProofDecodingError::InvalidSiblingCount(count) => {
+                format!("(InvalidSiblingCount) - {count} siblings, should be exactly 1")
+            }
+            ProofDecodingError::TooManyUpdateProofs(count) => format!(
+                "(TooManyUpdateProofs) - {count} update proofs, should be at most {MAX_UPDATE_PROOFS}"
+            ),
+            ProofDecodingError::LabelValTooLong(len) => format!(
+                "(LabelValTooLong) - Label value is {len} bytes, should be at most {MAX_LABEL_VAL_BYTES}"
+            ),
+            ProofDecodingError::LabelLenTooLarge(len) => format!(
+                "(LabelLenTooLarge) - Label length is {len}, should be at most {MAX_LABEL_LEN}"
+            ),
+            ProofDecodingError::InvalidDigestIndex(index) => {
+                format!("(InvalidDigestIndex) - No digest at index {index}")
+            }
+        };
+        write!(f, "Proof decoding error {code}")
+    }
+}
+
 /// An error converting a protobuf proof
 #[derive(Debug, Eq, PartialEq)]
 pub enum ConversionError {
@@ -29,6 +88,10 @@ pub enum ConversionError {
     Deserialization(String),
     /// A core protobuf error occurred
     Protobuf(String),
+    /// The version of the proof wire format is not supported
+    UnsupportedVersion(u32),
+    /// The proof exceeds the decoding limits, or is malformed
+    Decoding(ProofDecodingError),
 }
 
 impl From<protobuf::Error> for ConversionError {
@@ -39,11 +102,21 @@ impl From<protobuf::Error> for ConversionError {
     }
 }
 
+impl From<ProofDecodingError> for ConversionError {
+    fn from(err: ProofDecodingError) -> Self {
+        Self::Decoding(err)
+    }
+}
+
 impl core::fmt::Display for ConversionError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> core::fmt::Result {
         let code = match &self {
             ConversionError::Deserialization(msg) => format!("(Deserialization) - {msg}"),
             ConversionError::Protobuf(msg) => format!("(Protobuf) - {msg}"),
+            ConversionError::UnsupportedVersion(version) => {
+                format!("(UnsupportedVersion) - Unsupported proof format version {version}")
+            }
+            ConversionError::Decoding(err) => format!("(Decoding) - {err}"),
         };
         write!(f, "Type conversion error {code}")
     }
@@ -94,6 +167,9 @@ macro_rules! convert_from_vector {
     }};
 }
 
+// The compressed encodings use the converter macros above
+pub mod compressed;
+
 // ==============================================================
 // NodeLabel
 // ==============================================================
@@ -133,18 +209,12 @@ impl TryFrom<&specs::types::NodeLabel> for crate::NodeLabel {
 
         let input_val = input.label_val();
         let label_len = input.label_len();
-        if input_val.len() > 32 {
-            return Err(ConversionError::Deserialization(format!(
-                "Label value is too long: {len}",
-                len = input_val.len()
-            )));
+        if input_val.len() > MAX_LABEL_VAL_BYTES {
+            return Err(ProofDecodingError::LabelValTooLong(input_val.len()).into());
         }
 
-        if label_len > 256 {
-            return Err(ConversionError::Deserialization(format!(
-                "Label length is too long, should be at most 256: {len}",
-                len = label_len
-            )));
+        if label_len > MAX_LABEL_LEN {
+            return Err(ProofDecodingError::LabelLenTooLarge(label_len).into());
         }
 
         // Note that we do not check that the bits beyond label_len are all 0, because
@@ -214,13 +284,15 @@ impl TryFrom<&specs::types::SiblingProof> for crate::SiblingProof {
         require_messagefield!(input, label);
         let label: crate::NodeLabel = input.label.as_ref().unwrap().try_into()?;
 
-        // get the raw data & it's length, but at most crate::hash::DIGEST_BYTES bytes
-        let siblings = input.siblings.first();
-        if siblings.is_none() {
-            return Err(ConversionError::Deserialization(
-                "Required field siblings missing".to_string(),
-            ));
-        }
+        let siblings = match input.siblings.as_slice() {
+            [sibling] => sibling,
+            [] => {
+                return Err(ConversionError::Deserialization(
+                    "Required field siblings missing".to_string(),
+                ))
+            }
+            siblings => return Err(ProofDecodingError::InvalidSiblingCount(siblings.len()).into()),
+        };
 
         // blind out the highest bits to all 0's, since we're pulling it down to a u8
         let direction = (input.direction() & DIRECTION_BLINDING_FACTOR) as u8;
@@ -236,7 +308,7 @@ impl TryFrom<&specs::types::SiblingProof> for crate::SiblingProof {
 
         Ok(Self {
             label,
-            siblings: [siblings.unwrap().try_into()?],
+            siblings: [siblings.try_into()?],
             direction: crate::types::Direction::from(bit),
         })
     }
@@ -271,6 +343,11 @@ impl TryFrom<&specs::types::MembershipProof> for crate::MembershipProof {
         let label: crate::NodeLabel = input.label.as_ref().unwrap().try_into()?;
         let hash_val: Digest = hash_from_bytes!(input.hash_val());
 
+        if input.sibling_proofs.len() > MAX_SIBLING_PROOFS {
+            return Err(
+                ProofDecodingError::TooManySiblingProofs(input.sibling_proofs.len()).into(),
+            );
+        }
         let mut sibling_proofs = vec![];
         for proof in input.sibling_proofs.iter() {
             sibling_proofs.push(proof.try_into()?);
@@ -348,7 +425,7 @@ impl From<&crate::LookupProof> for specs::types::LookupProof {
     fn from(input: &crate::LookupProof) -> Self {
         Self {
             epoch: Some(input.epoch),
-            value: Some(input.value.0.clone()),
+            value: input.value.bytes().map(<[u8]>::to_vec),
             version: Some(input.version),
             existence_vrf_proof: Some(input.existence_vrf_proof.clone()),
             existence_proof: MessageField::some((&input.existence_proof).into()),
@@ -357,6 +434,7 @@ impl From<&crate::LookupProof> for specs::types::LookupProof {
             freshness_vrf_proof: Some(input.freshness_vrf_proof.clone()),
             freshness_proof: MessageField::some((&input.freshness_proof).into()),
             commitment_nonce: Some(input.commitment_nonce.clone()),
+            vrf_rotation: MessageField::from_option(input.vrf_rotation.as_ref().map(Into::into)),
             ..Default::default()
         }
     }
@@ -379,7 +457,7 @@ impl TryFrom<&specs::types::LookupProof> for crate::LookupProof {
 
         Ok(Self {
             epoch: input.epoch(),
-            value: crate::AkdValue(input.value().to_vec()),
+            value: crate::AkdValue::Bytes(input.value().to_vec()),
             version: input.version(),
             existence_vrf_proof: input.existence_vrf_proof().to_vec(),
             existence_proof: input.existence_proof.as_ref().unwrap().try_into()?,
@@ -388,6 +466,48 @@ impl TryFrom<&specs::types::LookupProof> for crate::LookupProof {
             freshness_vrf_proof: input.freshness_vrf_proof().to_vec(),
             freshness_proof: input.freshness_proof.as_ref().unwrap().try_into()?,
             commitment_nonce: input.commitment_nonce().to_vec(),
+            vrf_rotation: input
+                .vrf_rotation
+                .as_ref()
+                .map(|item| item.try_into())
+                .transpose()?,
+        })
+    }
+}
+
+// ==============================================================
+// VrfRotationCertificate
+// ==============================================================
+
+impl From<&crate::VrfRotationCertificate> for specs::types::VrfRotationCertificate {
+    fn from(input: &crate::VrfRotationCertificate) -> Self {
+        Self {
+            rotation_epoch: Some(input.rotation_epoch),
+            previous_vrf_public_key: Some(input.previous_vrf_public_key.clone()),
+            new_vrf_public_key: Some(input.new_vrf_public_key.clone()),
+            previous_key_proof: Some(input.previous_key_proof.clone()),
+            new_key_proof: Some(input.new_key_proof.clone()),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<&specs::types::VrfRotationCertificate> for crate::VrfRotationCertificate {
+    type Error = ConversionError;
+
+    fn try_from(input: &specs::types::VrfRotationCertificate) -> Result<Self, Self::Error> {
+        require!(input, has_rotation_epoch);
+        require!(input, has_previous_vrf_public_key);
+        require!(input, has_new_vrf_public_key);
+        require!(input, has_previous_key_proof);
+        require!(input, has_new_key_proof);
+
+        Ok(Self {
+            rotation_epoch: input.rotation_epoch(),
+            previous_vrf_public_key: input.previous_vrf_public_key().to_vec(),
+            new_vrf_public_key: input.new_vrf_public_key().to_vec(),
+            previous_key_proof: input.previous_key_proof().to_vec(),
+            new_key_proof: input.new_key_proof().to_vec(),
         })
     }
 }
@@ -400,7 +520,7 @@ impl From<&crate::UpdateProof> for specs::types::UpdateProof {
     fn from(input: &crate::UpdateProof) -> Self {
         Self {
             epoch: Some(input.epoch),
-            value: Some(input.value.0.clone()),
+            value: input.value.bytes().map(<[u8]>::to_vec),
             version: Some(input.version),
             existence_vrf_proof: Some(input.existence_vrf_proof.clone()),
             existence_proof: MessageField::some((&input.existence_proof).into()),
@@ -419,7 +539,6 @@ impl TryFrom<&specs::types::UpdateProof> for crate::UpdateProof {
 
     fn try_from(input: &specs::types::UpdateProof) -> Result<Self, Self::Error> {
         require!(input, has_epoch);
-        require!(input, has_value);
         require!(input, has_version);
         require!(input, has_existence_vrf_proof);
         require_messagefield!(input, existence_proof);
@@ -436,7 +555,11 @@ impl TryFrom<&specs::types::UpdateProof> for crate::UpdateProof {
 
         Ok(Self {
             epoch: input.epoch(),
-            value: crate::AkdValue(input.value().to_vec()),
+            // A missing value denotes a tombstone
+            value: input
+                .value
+                .clone()
+                .map_or(crate::AkdValue::Tombstone, crate::AkdValue::Bytes),
             version: input.version(),
             existence_vrf_proof: input.existence_vrf_proof().to_vec(),
             existence_proof: input.existence_proof.as_ref().unwrap().try_into()?,
@@ -480,6 +603,9 @@ impl TryFrom<&specs::types::HistoryProof> for crate::HistoryProof {
     type Error = ConversionError;
 
     fn try_from(input: &specs::types::HistoryProof) -> Result<Self, Self::Error> {
+        if input.update_proofs.len() > MAX_UPDATE_PROOFS {
+            return Err(ProofDecodingError::TooManyUpdateProofs(input.update_proofs.len()).into());
+        }
         let update_proofs = convert_from_vector!(input.update_proofs, crate::UpdateProof);
 
         let past_marker_vrf_proofs = input
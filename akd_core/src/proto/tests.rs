@@ -10,6 +10,7 @@
 use super::specs::types::*;
 use super::*;
 use crate::{AzksValue, Direction};
+use protobuf::Message;
 use rand::{thread_rng, Rng};
 
 // ================= Test helpers ================= //
@@ -117,7 +118,7 @@ fn test_convert_lookup_proof() {
     let mut rng = thread_rng();
     let original = crate::LookupProof {
         epoch: rng.gen(),
-        value: crate::AkdValue(random_hash().to_vec()),
+        value: crate::AkdValue::Bytes(random_hash().to_vec()),
         version: rng.gen(),
         existence_vrf_proof: random_hash().to_vec(),
         existence_proof: crate::MembershipProof {
@@ -155,6 +156,7 @@ fn test_convert_lookup_proof() {
             },
         },
         commitment_nonce: random_hash().to_vec(),
+        vrf_rotation: Some(random_vrf_rotation_certificate()),
     };
 
     let protobuf: LookupProof = (&original).into();
@@ -166,7 +168,7 @@ fn test_convert_update_proof() {
     let mut rng = thread_rng();
     let original = crate::UpdateProof {
         epoch: rng.gen(),
-        value: crate::AkdValue(random_hash().to_vec()),
+        value: crate::AkdValue::Bytes(random_hash().to_vec()),
         version: rng.gen(),
         existence_vrf_proof: random_hash().to_vec(),
         existence_proof: crate::MembershipProof {
@@ -193,6 +195,17 @@ fn test_convert_update_proof() {
 
     let protobuf: UpdateProof = (&original).into();
     assert_eq!(original, (&protobuf).try_into().unwrap());
+
+    // A tombstone is encoded as a missing value, which is distinct from an empty one
+    for value in [crate::AkdValue::Tombstone, crate::AkdValue::Bytes(vec![])] {
+        let original = crate::UpdateProof {
+            value,
+            ..original.clone()
+        };
+        let protobuf: UpdateProof = (&original).into();
+        assert_eq!(original.value.is_tombstone(), !protobuf.has_value());
+        assert_eq!(original, (&protobuf).try_into().unwrap());
+    }
 }
 
 fn non_membership_proof() -> crate::NonMembershipProof {
@@ -216,7 +229,7 @@ fn upd_proof() -> crate::UpdateProof {
     let mut rng = thread_rng();
     crate::UpdateProof {
         epoch: rng.gen(),
-        value: crate::AkdValue(random_hash().to_vec()),
+        value: crate::AkdValue::Bytes(random_hash().to_vec()),
         version: rng.gen(),
         existence_vrf_proof: random_hash().to_vec(),
         existence_proof: crate::MembershipProof {
@@ -337,7 +350,12 @@ fn test_label_val_too_long() {
     proto_label.set_label_val(too_long_label.to_vec());
     proto_label.set_label_len(256);
 
-    assert!(crate::NodeLabel::try_from(&proto_label).is_err());
+    assert_eq!(
+        Err(ConversionError::Decoding(
+            ProofDecodingError::LabelValTooLong(33)
+        )),
+        crate::NodeLabel::try_from(&proto_label)
+    );
 }
 
 #[test]
@@ -351,5 +369,372 @@ fn test_label_len_too_large() {
     proto_label.set_label_val(full_label.to_vec());
     proto_label.set_label_len(257);
 
-    assert!(crate::NodeLabel::try_from(&proto_label).is_err());
+    assert_eq!(
+        Err(ConversionError::Decoding(
+            ProofDecodingError::LabelLenTooLarge(257)
+        )),
+        crate::NodeLabel::try_from(&proto_label)
+    );
+}
+
+#[test]
+fn test_too_many_sibling_proofs() {
+    let mut protobuf: MembershipProof = (&membership_proof()).into();
+    let sibling_proof = protobuf.sibling_proofs[0].clone();
+    protobuf.sibling_proofs = vec![sibling_proof; MAX_SIBLING_PROOFS];
+    assert!(crate::MembershipProof::try_from(&protobuf).is_ok());
+
+    protobuf
+        .sibling_proofs
+        .push(protobuf.sibling_proofs[0].clone());
+    assert_eq!(
+        Err(ConversionError::Decoding(
+            ProofDecodingError::TooManySiblingProofs(MAX_SIBLING_PROOFS + 1)
+        )),
+        crate::MembershipProof::try_from(&protobuf)
+    );
+}
+
+#[test]
+fn test_invalid_sibling_count() {
+    let mut protobuf: SiblingProof = (&membership_proof().sibling_proofs[0]).into();
+    protobuf.siblings.push(protobuf.siblings[0].clone());
+    assert_eq!(
+        Err(ConversionError::Decoding(
+            ProofDecodingError::InvalidSiblingCount(2)
+        )),
+        crate::SiblingProof::try_from(&protobuf)
+    );
+
+    protobuf.siblings.clear();
+    assert!(matches!(
+        crate::SiblingProof::try_from(&protobuf),
+        Err(ConversionError::Deserialization(_))
+    ));
+}
+
+#[test]
+fn test_too_many_update_proofs() {
+    let update_proof: UpdateProof = (&upd_proof()).into();
+    let protobuf = HistoryProof {
+        update_proofs: vec![update_proof; MAX_UPDATE_PROOFS + 1],
+        ..Default::default()
+    };
+    // The limit is checked before any of the update proofs is converted
+    assert_eq!(
+        Err(ConversionError::Decoding(
+            ProofDecodingError::TooManyUpdateProofs(MAX_UPDATE_PROOFS + 1)
+        )),
+        crate::HistoryProof::try_from(&protobuf)
+    );
+}
+
+// ================= Versioned wire format ================= //
+
+fn fixed_append_only_proof() -> crate::AppendOnlyProof {
+    let element = crate::AzksElement {
+        label: crate::NodeLabel::new([1u8; 32], 256),
+        value: AzksValue([2u8; 32]),
+    };
+    crate::AppendOnlyProof {
+        proofs: vec![crate::SingleAppendOnlyProof {
+            inserted: vec![element],
+            unchanged_nodes: vec![],
+        }],
+        epochs: vec![1, 2],
+    }
+}
+
+/// The version 1 envelope of [fixed_append_only_proof], which must keep decoding
+const V1_APPEND_ONLY_PROOF: &str = "080110031a510a4b0a490a250a2001010101010101010101010101010101\
+     0101010101010101010101010101010110800212200202020202020202020202020202\
+     02020202020202020202020202020202020210011002";
+
+fn random_lookup_proof() -> crate::LookupProof {
+    crate::LookupProof {
+        epoch: 3,
+        value: crate::AkdValue::Bytes(random_hash().to_vec()),
+        version: 2,
+        existence_vrf_proof: random_hash().to_vec(),
+        existence_proof: membership_proof(),
+        marker_vrf_proof: random_hash().to_vec(),
+        marker_proof: membership_proof(),
+        freshness_vrf_proof: random_hash().to_vec(),
+        freshness_proof: non_membership_proof(),
+        commitment_nonce: random_hash().to_vec(),
+        vrf_rotation: None,
+    }
+}
+
+fn random_vrf_rotation_certificate() -> crate::VrfRotationCertificate {
+    crate::VrfRotationCertificate {
+        rotation_epoch: thread_rng().gen(),
+        previous_vrf_public_key: random_hash().to_vec(),
+        new_vrf_public_key: random_hash().to_vec(),
+        previous_key_proof: random_hash().to_vec(),
+        new_key_proof: random_hash().to_vec(),
+    }
+}
+
+fn random_history_proof() -> crate::HistoryProof {
+    crate::HistoryProof {
+        update_proofs: vec![upd_proof()],
+        past_marker_vrf_proofs: vec![random_hash().to_vec()],
+        existence_of_past_marker_proofs: vec![membership_proof()],
+        future_marker_vrf_proofs: vec![random_hash().to_vec()],
+        non_existence_of_future_marker_proofs: vec![non_membership_proof()],
+    }
+}
+
+#[test]
+fn test_versioned_round_trip() {
+    for version in SUPPORTED_FORMAT_VERSIONS {
+        let lookup_proof = random_lookup_proof();
+        let bytes = encode_versioned(&lookup_proof, version).unwrap();
+        assert_eq!(
+            lookup_proof,
+            decode_versioned::<crate::LookupProof>(&bytes).unwrap()
+        );
+
+        let history_proof = random_history_proof();
+        let bytes = encode_versioned(&history_proof, version).unwrap();
+        assert_eq!(
+            history_proof,
+            decode_versioned::<crate::HistoryProof>(&bytes).unwrap()
+        );
+
+        let append_only_proof = fixed_append_only_proof();
+        let bytes = encode_versioned(&append_only_proof, version).unwrap();
+        assert_eq!(
+            append_only_proof,
+            decode_versioned::<crate::AppendOnlyProof>(&bytes).unwrap()
+        );
+    }
+}
+
+#[test]
+fn test_decode_v1_append_only_proof() {
+    let bytes = hex::decode(V1_APPEND_ONLY_PROOF).unwrap();
+    assert_eq!(
+        fixed_append_only_proof(),
+        decode_versioned::<crate::AppendOnlyProof>(&bytes).unwrap()
+    );
+    assert_eq!(
+        bytes,
+        encode_versioned(&fixed_append_only_proof(), 1).unwrap()
+    );
+}
+
+#[test]
+fn test_decode_v1_with_unknown_fields() {
+    // Fields which are added to the messages in the future are skipped by older decoders,
+    // both in the envelope and in the encoded proof
+    let unknown_field = |bytes: &mut Vec<u8>| {
+        // Field 15, of type length-delimited, holding 3 bytes
+        bytes.extend_from_slice(&[(15 << 3) | 2, 3, 0xaa, 0xbb, 0xcc]);
+        // Field 16, of type varint
+        bytes.extend_from_slice(&[0x80, 0x01, 42]);
+    };
+
+    let mut envelope =
+        ProofEnvelope::parse_from_bytes(&hex::decode(V1_APPEND_ONLY_PROOF).unwrap()).unwrap();
+    let mut payload = envelope.payload().to_vec();
+    unknown_field(&mut payload);
+    envelope.set_payload(payload);
+    let mut bytes = envelope.write_to_bytes().unwrap();
+    unknown_field(&mut bytes);
+
+    assert_eq!(
+        fixed_append_only_proof(),
+        decode_versioned::<crate::AppendOnlyProof>(&bytes).unwrap()
+    );
+}
+
+#[test]
+fn test_decode_versioned_errors() {
+    let bytes = hex::decode(V1_APPEND_ONLY_PROOF).unwrap();
+    assert!(matches!(
+        decode_versioned::<crate::LookupProof>(&bytes),
+        Err(ConversionError::Deserialization(_))
+    ));
+
+    let mut envelope = ProofEnvelope::parse_from_bytes(&bytes).unwrap();
+    envelope.set_version(3);
+    assert_eq!(
+        Err(ConversionError::UnsupportedVersion(3)),
+        decode_versioned::<crate::AppendOnlyProof>(&envelope.write_to_bytes().unwrap())
+    );
+    assert_eq!(
+        Err(ConversionError::UnsupportedVersion(3)),
+        encode_versioned(&fixed_append_only_proof(), 3)
+    );
+
+    envelope.clear_version();
+    assert!(matches!(
+        decode_versioned::<crate::AppendOnlyProof>(&envelope.write_to_bytes().unwrap()),
+        Err(ConversionError::Deserialization(_))
+    ));
+}
+
+#[test]
+fn test_negotiate_version() {
+    assert_eq!(Some(1), negotiate_version(&[1]));
+    assert_eq!(Some(2), negotiate_version(&[3, 1, 2]));
+    assert_eq!(Some(2), negotiate_version(&[2, 3]));
+    assert_eq!(None, negotiate_version(&[3, 4]));
+    assert_eq!(None, negotiate_version(&[]));
+}
+
+// ================= Compressed encoding ================= //
+
+/// A membership proof along the path of a leaf at the given depth, in which the labels are
+/// related as in the proofs of an actual tree
+fn path_membership_proof(leaf: crate::NodeLabel, depth: u32) -> crate::MembershipProof {
+    let sibling_proofs = (0..depth)
+        .map(|len| {
+            let (byte, mask) = ((len / 8) as usize, 1u8 << (7 - len % 8));
+            // The sibling shares the prefix of its parent, and then branches off. It is
+            // an internal node with a slightly longer label, except at the bottom
+            let mut sibling = leaf.label_val;
+            sibling[byte] ^= mask;
+            sibling[byte + 1..].copy_from_slice(&random_hash()[byte + 1..]);
+            let sibling_len = if len + 1 == depth {
+                256
+            } else {
+                len + 1 + thread_rng().gen::<u32>() % 8
+            };
+            crate::SiblingProof {
+                label: leaf.get_prefix(len),
+                siblings: [crate::AzksElement {
+                    label: crate::NodeLabel::new(sibling, 256).get_prefix(sibling_len),
+                    value: AzksValue(random_hash()),
+                }],
+                direction: if leaf.label_val[byte] & mask == 0 {
+                    Direction::Left
+                } else {
+                    Direction::Right
+                },
+            }
+        })
+        .collect();
+    crate::MembershipProof {
+        label: leaf,
+        hash_val: AzksValue(random_hash()),
+        sibling_proofs,
+    }
+}
+
+#[test]
+fn test_compressed_round_trip() {
+    let mut lookup_proof = random_lookup_proof();
+    let protobuf: CompressedLookupProof = (&lookup_proof).into();
+    assert_eq!(lookup_proof, (&protobuf).try_into().unwrap());
+    lookup_proof.vrf_rotation = Some(random_vrf_rotation_certificate());
+    let protobuf: CompressedLookupProof = (&lookup_proof).into();
+    assert_eq!(lookup_proof, (&protobuf).try_into().unwrap());
+
+    let history_proof = random_history_proof();
+    let protobuf: CompressedHistoryProof = (&history_proof).into();
+    assert_eq!(history_proof, (&protobuf).try_into().unwrap());
+}
+
+#[test]
+fn test_compressed_lookup_proof_size() {
+    let leaf = crate::NodeLabel::new(random_hash(), 256);
+    let mut lookup_proof = random_lookup_proof();
+    lookup_proof.existence_proof = path_membership_proof(leaf, 40);
+    lookup_proof.marker_proof = path_membership_proof(leaf, 40);
+    // The paths of the labels share their siblings close to the root
+    for i in 0..20 {
+        lookup_proof.marker_proof.sibling_proofs[i] =
+            lookup_proof.existence_proof.sibling_proofs[i].clone();
+    }
+
+    let protobuf: CompressedLookupProof = (&lookup_proof).into();
+    assert_eq!(lookup_proof, (&protobuf).try_into().unwrap());
+    // The digests of two leaves and their 40 siblings, of which 20 are shared, and the 4
+    // digests of the freshness proof
+    assert_eq!(2 * 41 - 20 + 4, protobuf.digests.len());
+    let compressed = protobuf.write_to_bytes().unwrap().len();
+    let uncompressed = LookupProof::from(&lookup_proof)
+        .write_to_bytes()
+        .unwrap()
+        .len();
+    assert!(
+        compressed * 10 < uncompressed * 9,
+        "{compressed} bytes compressed, from {uncompressed} bytes"
+    );
+}
+
+#[test]
+fn test_compressed_test_vectors() {
+    #[derive(serde::Deserialize)]
+    struct TestVector {
+        lookup_proof: String,
+        history_proof: String,
+    }
+
+    for test_vectors in [
+        include_str!("../../wasm/test_vectors/whatsapp_v1.json"),
+        include_str!("../../wasm/test_vectors/experimental.json"),
+        include_str!("../../wasm/test_vectors/sha3_256.json"),
+    ] {
+        let vectors: Vec<TestVector> = serde_json::from_str(test_vectors).unwrap();
+        for vector in vectors {
+            let bytes = hex::decode(&vector.lookup_proof).unwrap();
+            let lookup_proof: crate::LookupProof = (&LookupProof::parse_from_bytes(&bytes)
+                .unwrap())
+                .try_into()
+                .unwrap();
+            let compressed = encode_versioned(&lookup_proof, 2).unwrap();
+            assert!(compressed.len() < bytes.len());
+            assert_eq!(
+                lookup_proof,
+                decode_versioned::<crate::LookupProof>(&compressed).unwrap()
+            );
+
+            let bytes = hex::decode(&vector.history_proof).unwrap();
+            let history_proof: crate::HistoryProof = (&HistoryProof::parse_from_bytes(&bytes)
+                .unwrap())
+                .try_into()
+                .unwrap();
+            let compressed = encode_versioned(&history_proof, 2).unwrap();
+            assert!(compressed.len() < bytes.len());
+            assert_eq!(
+                history_proof,
+                decode_versioned::<crate::HistoryProof>(&compressed).unwrap()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_compressed_decoding_errors() {
+    let mut protobuf: CompressedLookupProof = (&random_lookup_proof()).into();
+    let digests = protobuf.digests.len() as u32;
+    protobuf
+        .existence_proof
+        .mut_or_insert_default()
+        .set_hash_val_index(digests);
+    assert_eq!(
+        Err(ConversionError::Decoding(
+            ProofDecodingError::InvalidDigestIndex(digests)
+        )),
+        crate::LookupProof::try_from(&protobuf)
+    );
+
+    let mut protobuf: CompressedLookupProof = (&random_lookup_proof()).into();
+    let label = protobuf
+        .freshness_proof
+        .mut_or_insert_default()
+        .longest_prefix
+        .mut_or_insert_default();
+    label.set_shared_bytes(30);
+    label.set_suffix(vec![1, 2, 3]);
+    assert_eq!(
+        Err(ConversionError::Decoding(
+            ProofDecodingError::LabelValTooLong(33)
+        )),
+        crate::LookupProof::try_from(&protobuf)
+    );
 }
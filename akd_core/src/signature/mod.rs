@@ -0,0 +1,323 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! This module contains the server-side signing of epoch root hashes, which allows
+//! relying parties to authenticate a root hash that they received out-of-band.
+//! Signatures are produced with Ed25519 over the message given by [epoch_signature_message].
+//!
+//! The verification of the signatures and of [EpochBundle]s (see [verify_epoch_signature] and
+//! [verify_epoch_bundle]) only depends on Ed25519, rather than on the verification of the
+//! proofs. Constrained clients (e.g. embedded verifiers built with the `nostd` feature) can
+//! therefore authenticate the root hash which they verify proofs against without enabling the
+//! `verify` feature.
+//!
+//! The signer can also bind a lookup response to a challenge supplied by the client (see
+//! [lookup_challenge_message]), so that a client detects a response which is replayed by a
+//! malicious transport, even while the epoch has not advanced.
+
+use crate::bundle::{EpochBundle, VRF_FINGERPRINT_LENGTH};
+use crate::configuration::Configuration;
+use crate::hash::Digest;
+
+#[cfg(feature = "nostd")]
+use alloc::boxed::Box;
+#[cfg(feature = "nostd")]
+use alloc::format;
+#[cfg(feature = "nostd")]
+use alloc::string::String;
+#[cfg(feature = "nostd")]
+use alloc::string::ToString;
+#[cfg(feature = "nostd")]
+use alloc::vec::Vec;
+use async_trait::async_trait;
+use ed25519_dalek::{
+    Signature, Signer, SigningKey, VerifyingKey, PUBLIC_KEY_LENGTH, SECRET_KEY_LENGTH,
+};
+
+const DOMAIN_SEPARATOR: &[u8] = b"akd_epoch_signature";
+const LOOKUP_CHALLENGE_DOMAIN_SEPARATOR: &[u8] = b"akd_lookup_challenge";
+
+/// Errors thrown when signing an epoch root hash
+#[derive(Debug, Eq, PartialEq)]
+pub enum SignatureError {
+    /// A problem retrieving or decoding the signing key
+    SigningKey(String),
+}
+
+impl core::fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let code = match &self {
+            SignatureError::SigningKey(msg) => format!("(Signing Key) - {msg}"),
+        };
+        write!(f, "Epoch signature error {code}")
+    }
+}
+
+/// The message which is signed for a given epoch and root hash
+pub fn epoch_signature_message(epoch: u64, root_hash: Digest) -> Vec<u8> {
+    [DOMAIN_SEPARATOR, &epoch.to_be_bytes(), &root_hash].concat()
+}
+
+/// The message which is signed to bind the response to a lookup of `label`, which was made
+/// against the root hash of the given epoch, to the challenge supplied by the client
+pub fn lookup_challenge_message(
+    challenge: &[u8],
+    label: &[u8],
+    epoch: u64,
+    root_hash: Digest,
+) -> Vec<u8> {
+    [
+        LOOKUP_CHALLENGE_DOMAIN_SEPARATOR,
+        &crate::utils::i2osp_array(challenge),
+        &crate::utils::i2osp_array(label),
+        &epoch.to_be_bytes(),
+        &root_hash,
+    ]
+    .concat()
+}
+
+/// Errors thrown when verifying a signature over an epoch root hash, or an [EpochBundle]
+#[derive(Debug, Eq, PartialEq)]
+pub enum SignatureVerificationError {
+    /// The signature over an epoch root hash is invalid, or the public key is malformed
+    EpochSignature(String),
+    /// The bundle is invalid, or does not follow the previous bundle
+    EpochBundle(String),
+    /// The signature binding a lookup response to the challenge of the client is invalid
+    LookupChallenge(String),
+}
+
+impl core::fmt::Display for SignatureVerificationError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let code = match &self {
+            SignatureVerificationError::EpochSignature(msg) => format!("(Epoch signature) - {msg}"),
+            SignatureVerificationError::EpochBundle(msg) => format!("(Epoch bundle) - {msg}"),
+            SignatureVerificationError::LookupChallenge(msg) => {
+                format!("(Lookup challenge) - {msg}")
+            }
+        };
+        write!(f, "Signature verification error {code}")
+    }
+}
+
+/// Verifies a signature produced by an [EpochSigner] over the root hash of the given epoch,
+/// using the signer's Ed25519 public key
+pub fn verify_epoch_signature(
+    public_key: &[u8],
+    epoch: u64,
+    root_hash: Digest,
+    signature: &[u8],
+) -> Result<(), SignatureVerificationError> {
+    let public_key = parse_public_key(public_key)?;
+    let signature = Signature::from_slice(signature).map_err(|err| {
+        SignatureVerificationError::EpochSignature(format!("Invalid signature: {err}"))
+    })?;
+
+    public_key
+        .verify_strict(&epoch_signature_message(epoch, root_hash), &signature)
+        .map_err(|_| {
+            SignatureVerificationError::EpochSignature(format!(
+                "Signature does not match the root hash for epoch {epoch}"
+            ))
+        })
+}
+
+/// Verifies a signature produced by an [EpochSigner] which binds the response to a lookup of
+/// `label` to the `challenge` supplied by the client, using the signer's Ed25519 public key.
+/// The epoch and root hash are those of the response, which the lookup proof should then be
+/// verified against.
+pub fn verify_lookup_challenge(
+    public_key: &[u8],
+    challenge: &[u8],
+    label: &[u8],
+    epoch: u64,
+    root_hash: Digest,
+    signature: &[u8],
+) -> Result<(), SignatureVerificationError> {
+    let public_key = parse_public_key(public_key)?;
+    let signature = Signature::from_slice(signature).map_err(|err| {
+        SignatureVerificationError::LookupChallenge(format!("Invalid signature: {err}"))
+    })?;
+
+    public_key
+        .verify_strict(
+            &lookup_challenge_message(challenge, label, epoch, root_hash),
+            &signature,
+        )
+        .map_err(|_| {
+            SignatureVerificationError::LookupChallenge(format!(
+                "Signature does not match the challenge for the lookup at epoch {epoch}"
+            ))
+        })
+}
+
+/// Verifies an [EpochBundle] which was produced by an [EpochSigner], using the signer's Ed25519
+/// public key. The bundle must also have been issued for the directory with the given VRF
+/// public key. Once verified, the root hash of the bundle can be compared against the root hash
+/// of the same epoch received from other channels.
+pub fn verify_epoch_bundle<TC: Configuration>(
+    public_key: &[u8],
+    vrf_public_key: &[u8],
+    bundle: &EpochBundle,
+) -> Result<(), SignatureVerificationError> {
+    if !bundle.matches_vrf_public_key::<TC>(vrf_public_key) {
+        return Err(SignatureVerificationError::EpochBundle(format!(
+            "Bundle for epoch {} was issued for another VRF public key",
+            bundle.epoch
+        )));
+    }
+
+    let public_key = parse_public_key(public_key)?;
+    let message = EpochBundle::signed_message(
+        bundle.epoch,
+        bundle.root_hash,
+        bundle.previous_root_hash,
+        bundle.vrf_public_key_fingerprint,
+    );
+    public_key
+        .verify_strict(&message, &Signature::from_bytes(&bundle.signature))
+        .map_err(|_| {
+            SignatureVerificationError::EpochBundle(format!(
+                "Signature does not match the contents of the bundle for epoch {}",
+                bundle.epoch
+            ))
+        })
+}
+
+/// Verifies that the `next` [EpochBundle] directly follows the `previous` one, i.e. that it is
+/// for the next epoch and that it links to the root hash of the previous bundle. This does not
+/// verify the signatures of the bundles, which should be checked with [verify_epoch_bundle].
+pub fn verify_epoch_bundle_link(
+    previous: &EpochBundle,
+    next: &EpochBundle,
+) -> Result<(), SignatureVerificationError> {
+    if previous.vrf_public_key_fingerprint != next.vrf_public_key_fingerprint {
+        return Err(SignatureVerificationError::EpochBundle(
+            "Bundles were issued for different VRF public keys".to_string(),
+        ));
+    }
+    if previous.epoch.checked_add(1) != Some(next.epoch) {
+        return Err(SignatureVerificationError::EpochBundle(format!(
+            "Bundle for epoch {} does not follow the bundle for epoch {}",
+            next.epoch, previous.epoch
+        )));
+    }
+    if next.previous_root_hash != previous.root_hash {
+        return Err(SignatureVerificationError::EpochBundle(format!(
+            "Bundle for epoch {} does not link to the root hash of epoch {}",
+            next.epoch, previous.epoch
+        )));
+    }
+    Ok(())
+}
+
+fn parse_public_key(public_key: &[u8]) -> Result<VerifyingKey, SignatureVerificationError> {
+    let public_key: [u8; PUBLIC_KEY_LENGTH] = public_key.try_into().map_err(|_| {
+        SignatureVerificationError::EpochSignature(format!(
+            "Expected a public key of {PUBLIC_KEY_LENGTH} bytes, got {}",
+            public_key.len()
+        ))
+    })?;
+    VerifyingKey::from_bytes(&public_key).map_err(|err| {
+        SignatureVerificationError::EpochSignature(format!("Invalid public key: {err}"))
+    })
+}
+
+/// Represents a secure storage of the Ed25519 private key used to sign the root hash
+/// of each published epoch. As with [crate::ecvrf::VRFKeyStorage], it is recommended
+/// to back this implementation with a static cache of the private key bytes.
+#[async_trait]
+pub trait EpochSigner: Sync + Send {
+    /* ======= To be implemented ====== */
+
+    /// Retrieve the signing key as a vector of bytes
+    async fn retrieve(&self) -> Result<Vec<u8>, SignatureError>;
+
+    /* ======= Common trait functionality ====== */
+
+    /// Retrieve the properly constructed signing key
+    async fn get_signing_key(&self) -> Result<SigningKey, SignatureError> {
+        let bytes = self.retrieve().await?;
+        let bytes: [u8; SECRET_KEY_LENGTH] = bytes.as_slice().try_into().map_err(|_| {
+            SignatureError::SigningKey(format!(
+                "Expected a signing key of {SECRET_KEY_LENGTH} bytes, got {}",
+                bytes.len()
+            ))
+        })?;
+        Ok(SigningKey::from_bytes(&bytes))
+    }
+
+    /// Retrieve the public key which verifies the produced signatures
+    async fn get_public_key(&self) -> Result<Vec<u8>, SignatureError> {
+        Ok(self
+            .get_signing_key()
+            .await?
+            .verifying_key()
+            .to_bytes()
+            .to_vec())
+    }
+
+    /// Sign the root hash for the given epoch
+    async fn sign_epoch(&self, epoch: u64, root_hash: Digest) -> Result<Vec<u8>, SignatureError> {
+        let key = self.get_signing_key().await?;
+        let message = epoch_signature_message(epoch, root_hash);
+        Ok(key.sign(&message).to_bytes().to_vec())
+    }
+
+    /// Sign the binding of the response to a lookup of `label`, which was made against the
+    /// root hash of the given epoch, to the challenge supplied by the client
+    async fn sign_lookup_challenge(
+        &self,
+        challenge: &[u8],
+        label: &[u8],
+        epoch: u64,
+        root_hash: Digest,
+    ) -> Result<Vec<u8>, SignatureError> {
+        let key = self.get_signing_key().await?;
+        let message = lookup_challenge_message(challenge, label, epoch, root_hash);
+        Ok(key.sign(&message).to_bytes().to_vec())
+    }
+
+    /// Sign an [EpochBundle] for the root hash of the given epoch, which is linked to the
+    /// root hash of the previous epoch. The fingerprint of the VRF public key of the directory
+    /// can be computed with [EpochBundle::vrf_fingerprint].
+    async fn sign_epoch_bundle(
+        &self,
+        epoch: u64,
+        root_hash: Digest,
+        previous_root_hash: Digest,
+        vrf_public_key_fingerprint: [u8; VRF_FINGERPRINT_LENGTH],
+    ) -> Result<EpochBundle, SignatureError> {
+        let key = self.get_signing_key().await?;
+        let message = EpochBundle::signed_message(
+            epoch,
+            root_hash,
+            previous_root_hash,
+            vrf_public_key_fingerprint,
+        );
+        Ok(EpochBundle {
+            epoch,
+            root_hash,
+            previous_root_hash,
+            vrf_public_key_fingerprint,
+            signature: key.sign(&message).to_bytes(),
+        })
+    }
+}
+
+/// This implementation of [EpochSigner] is meant only to be used for testing, as
+/// the signing key is hard-coded
+#[derive(Clone)]
+pub struct HardCodedEpochSigner;
+
+#[async_trait]
+impl EpochSigner for HardCodedEpochSigner {
+    async fn retrieve(&self) -> Result<Vec<u8>, SignatureError> {
+        hex::decode("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60")
+            .map_err(|hex_err| SignatureError::SigningKey(hex_err.to_string()))
+    }
+}
@@ -9,14 +9,16 @@
 //! to verify any of the following AKD proofs
 //!
 //! 1. Lookup
-//! 2: Key history
-//! 3: Audit (append-only)
+//! 2. Key history
+//! 3. Audit (append-only)
 
+use crate::configuration::Configuration;
 use crate::hash::Digest;
 #[cfg(feature = "serde_serialization")]
 use crate::utils::serde_helpers::{
     azks_value_hex_deserialize, azks_value_hex_serialize, bytes_deserialize_hex,
-    bytes_serialize_hex,
+    bytes_serialize_hex, bytes_vec_deserialize_hex, bytes_vec_serialize_hex,
+    optional_bytes_deserialize_hex, optional_bytes_serialize_hex,
 };
 use crate::ARITY;
 
@@ -31,9 +33,15 @@ use rand::{CryptoRng, Rng};
 #[cfg(not(feature = "nostd"))]
 use std::cmp::{Ord, Ordering, PartialOrd};
 
+pub mod namespace;
+pub use namespace::*;
+
 pub mod node_label;
 pub use node_label::*;
 
+pub mod value_set;
+pub use value_set::*;
+
 // ============================================
 // Traits
 // ============================================
@@ -52,6 +60,10 @@ pub trait SizeOf {
 /// Stale nodes are no longer active because a newer
 /// version exists to replace them.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Deserialize, serde::Serialize)
+)]
 #[repr(u8)]
 pub enum VersionFreshness {
     /// Represents not being the most recent version
@@ -214,52 +226,75 @@ impl AkdLabel {
 }
 
 /// The value of a particular entry in the AKD
+///
+/// A value is either the bytes which were published for a label, or a [AkdValue::Tombstone]
+/// denoting that the published bytes have since been removed from the storage layer (e.g. for
+/// data retention policies). Should a tombstone be encountered in a key history proof, the
+/// commitment to the removed value has to be taken at face value, which clients opt into with
+/// [crate::verify::history::HistoryVerificationParams::AllowMissingValues]. A tombstone cannot be
+/// published.
+///
+/// See [GitHub issue #130](https://github.com/novifinancial/akd/issues/130) for more context
 #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
-#[cfg_attr(
-    feature = "serde_serialization",
-    derive(serde::Serialize, serde::Deserialize)
-)]
-pub struct AkdValue(
-    #[cfg_attr(
-        feature = "serde_serialization",
-        serde(serialize_with = "bytes_serialize_hex")
-    )]
-    #[cfg_attr(
-        feature = "serde_serialization",
-        serde(deserialize_with = "bytes_deserialize_hex")
-    )]
-    pub Vec<u8>,
-);
+pub enum AkdValue {
+    /// The bytes of a published value
+    Bytes(Vec<u8>),
+    /// A published value which was removed from the storage layer
+    Tombstone,
+}
+
+/// The bytes of a [AkdValue::Tombstone], which has none
+static TOMBSTONE_BYTES: Vec<u8> = Vec::new();
 
 impl SizeOf for AkdValue {
     fn size_of(&self) -> usize {
-        self.0.len()
+        self.len()
     }
 }
 
 impl core::ops::Deref for AkdValue {
     type Target = Vec<u8>;
 
+    /// The bytes of the value, which are empty for a [AkdValue::Tombstone]
     fn deref(&self) -> &Self::Target {
-        &self.0
-    }
-}
-
-impl core::ops::DerefMut for AkdValue {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        match self {
+            Self::Bytes(bytes) => bytes,
+            Self::Tombstone => &TOMBSTONE_BYTES,
+        }
     }
 }
 
 impl core::convert::From<&str> for AkdValue {
     fn from(s: &str) -> Self {
-        Self(s.as_bytes().to_vec())
+        Self::Bytes(s.as_bytes().to_vec())
     }
 }
 
 impl core::convert::From<&String> for AkdValue {
     fn from(s: &String) -> Self {
-        Self(s.as_bytes().to_vec())
+        Self::Bytes(s.as_bytes().to_vec())
+    }
+}
+
+impl core::convert::From<Vec<u8>> for AkdValue {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self::Bytes(bytes)
+    }
+}
+
+#[cfg(feature = "serde_serialization")]
+impl serde::Serialize for AkdValue {
+    /// A tombstone is serialized as a missing value, and the bytes of any other value as hex
+    /// (or raw bytes, for formats which are not human-readable)
+    fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        optional_bytes_serialize_hex(&self.bytes().map(<[u8]>::to_vec), s)
+    }
+}
+
+#[cfg(feature = "serde_serialization")]
+impl<'de> serde::Deserialize<'de> for AkdValue {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(optional_bytes_deserialize_hex(deserializer)?.map_or(Self::Tombstone, Self::Bytes))
     }
 }
 
@@ -269,20 +304,68 @@ impl AkdValue {
     pub fn random<R: CryptoRng + Rng>(rng: &mut R) -> Self {
         let mut bytes = [0u8; 32];
         rng.fill_bytes(&mut bytes);
-        Self(bytes.to_vec())
+        Self::Bytes(bytes.to_vec())
+    }
+
+    /// Whether the value is a [AkdValue::Tombstone]
+    pub fn is_tombstone(&self) -> bool {
+        matches!(self, Self::Tombstone)
+    }
+
+    /// The bytes of the value, or [None] for a [AkdValue::Tombstone]
+    pub fn bytes(&self) -> Option<&[u8]> {
+        match self {
+            Self::Bytes(bytes) => Some(bytes),
+            Self::Tombstone => None,
+        }
+    }
+
+    /// Consumes the value, returning its bytes, or [None] for a [AkdValue::Tombstone]
+    pub fn into_bytes(self) -> Option<Vec<u8>> {
+        match self {
+            Self::Bytes(bytes) => Some(bytes),
+            Self::Tombstone => None,
+        }
+    }
+
+    /// Pads the value to the next multiple of `bucket_size` bytes (as in ISO/IEC 7816-4), by
+    /// appending a `0x80` byte followed by as many zero bytes as needed. Since the `0x80` byte
+    /// is always appended, a value whose length is a multiple of `bucket_size` grows by a whole
+    /// bucket. A `bucket_size` of 0 is treated as 1, and a tombstone is left as is.
+    pub fn pad(&self, bucket_size: usize) -> Self {
+        let Self::Bytes(bytes) = self else {
+            return Self::Tombstone;
+        };
+        let bucket_size = bucket_size.max(1);
+        let padded_len = (bytes.len() / bucket_size + 1) * bucket_size;
+        let mut padded = Vec::with_capacity(padded_len);
+        padded.extend_from_slice(bytes);
+        padded.push(0x80);
+        padded.resize(padded_len, 0);
+        Self::Bytes(padded)
+    }
+
+    /// Removes the padding added by [AkdValue::pad], returning [None] if the value is not
+    /// padded to a multiple of `bucket_size` bytes. A tombstone is left as is.
+    pub fn unpad(&self, bucket_size: usize) -> Option<Self> {
+        let Self::Bytes(bytes) = self else {
+            return Some(Self::Tombstone);
+        };
+        let bucket_size = bucket_size.max(1);
+        if bytes.is_empty() || !bytes.len().is_multiple_of(bucket_size) {
+            return None;
+        }
+        let marker = bytes.iter().rposition(|byte| *byte != 0)?;
+        if bytes[marker] != 0x80 || bytes.len() - marker > bucket_size {
+            return None;
+        }
+        Some(Self::Bytes(bytes[..marker].to_vec()))
     }
 }
 
 /// The value to be hashed every time an empty node's hash is to be considered
 pub const EMPTY_VALUE: [u8; 1] = [0u8];
 
-/// A "tombstone" is a false value in an AKD ValueState denoting that a real value has been removed (e.g. data rentention policies).
-/// Should a tombstone be encountered, we have to assume that the hash of the value is correct, and we move forward without being able to
-/// verify the raw value. We utilize an empty array to save space in the storage layer
-///
-/// See [GitHub issue #130](https://github.com/novifinancial/akd/issues/130) for more context
-pub const TOMBSTONE: &[u8] = &[];
-
 // ============================================
 // Structs
 // ============================================
@@ -363,6 +446,12 @@ pub struct SiblingProof {
     pub direction: Direction,
 }
 
+impl SizeOf for SiblingProof {
+    fn size_of(&self) -> usize {
+        self.label.size_of() + self.siblings[0].size_of() + self.direction.size_of()
+    }
+}
+
 /// Merkle proof of membership of a [`NodeLabel`] with a particular hash
 /// value in the tree at a given epoch
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -374,11 +463,31 @@ pub struct MembershipProof {
     /// The node label
     pub label: NodeLabel,
     /// The hash of the value
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(serialize_with = "azks_value_hex_serialize")
+    )]
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(deserialize_with = "azks_value_hex_deserialize")
+    )]
     pub hash_val: AzksValue,
     /// The parents of the node in question
     pub sibling_proofs: Vec<SiblingProof>,
 }
 
+impl SizeOf for MembershipProof {
+    fn size_of(&self) -> usize {
+        self.label.size_of()
+            + self.hash_val.0.len()
+            + self
+                .sibling_proofs
+                .iter()
+                .map(|proof| proof.size_of())
+                .sum::<usize>()
+    }
+}
+
 /// Merkle Patricia proof of non-membership for a [`NodeLabel`] in the tree
 /// at a given epoch.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -397,12 +506,28 @@ pub struct NonMembershipProof {
     pub longest_prefix_membership_proof: MembershipProof,
 }
 
+impl SizeOf for NonMembershipProof {
+    fn size_of(&self) -> usize {
+        self.label.size_of()
+            + self.longest_prefix.size_of()
+            + self
+                .longest_prefix_children
+                .iter()
+                .map(|child| child.size_of())
+                .sum::<usize>()
+            + self.longest_prefix_membership_proof.size_of()
+    }
+}
+
 /// Proof that a given label was at a particular state at the given epoch.
 /// This means we need to show that the state and version we are claiming for this node must have been:
 /// * committed in the tree,
 /// * not too far ahead of the most recent marker version,
 /// * not stale when served.
-/// This proof is sent in response to a lookup query for a particular key.
+///
+/// This proof is sent in response to a lookup query for a particular key. Its VRF proofs are
+/// full proofs of the VRF suite of the configuration (rather than only the node labels derived
+/// from them), which can be checked independently with [crate::verify::lookup_label_derivations].
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(
     feature = "serde_serialization",
@@ -416,19 +541,98 @@ pub struct LookupProof {
     /// The version of the record
     pub version: u64,
     /// VRF proof for the label corresponding to this version
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(serialize_with = "bytes_serialize_hex")
+    )]
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(deserialize_with = "bytes_deserialize_hex")
+    )]
     pub existence_vrf_proof: Vec<u8>,
     /// Record existence proof
     pub existence_proof: MembershipProof,
     /// VRF proof for the marker preceding (less than or equal to) this version
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(serialize_with = "bytes_serialize_hex")
+    )]
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(deserialize_with = "bytes_deserialize_hex")
+    )]
     pub marker_vrf_proof: Vec<u8>,
     /// Existence at specific marker
     pub marker_proof: MembershipProof,
     /// VRF proof for the label corresponding to this version being stale
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(serialize_with = "bytes_serialize_hex")
+    )]
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(deserialize_with = "bytes_deserialize_hex")
+    )]
     pub freshness_vrf_proof: Vec<u8>,
     /// Freshness proof (non member at previous epoch)
     pub freshness_proof: NonMembershipProof,
     /// Proof for commitment value derived from raw AkdLabel and AkdValue
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(serialize_with = "bytes_serialize_hex")
+    )]
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(deserialize_with = "bytes_deserialize_hex")
+    )]
     pub commitment_nonce: Vec<u8>,
+    /// If the proof was generated during the transition window of a rotation of the VRF key of
+    /// the directory, the cross-certification of the new key by the previous one. The VRF proofs
+    /// of the lookup are then under the new key, and clients which only know the previous key
+    /// verify them after checking the certificate (see [crate::verify::lookup_verify]).
+    pub vrf_rotation: Option<VrfRotationCertificate>,
+}
+
+impl SizeOf for LookupProof {
+    fn size_of(&self) -> usize {
+        core::mem::size_of::<u64>() * 2
+            + self.value.size_of()
+            + self.existence_vrf_proof.len()
+            + self.existence_proof.size_of()
+            + self.marker_vrf_proof.len()
+            + self.marker_proof.size_of()
+            + self.freshness_vrf_proof.len()
+            + self.freshness_proof.size_of()
+            + self.commitment_nonce.len()
+            + self
+                .vrf_rotation
+                .as_ref()
+                .map(|certificate| certificate.size_of())
+                .unwrap_or(0)
+    }
+}
+
+/// Proof that a label is not registered in the directory, i.e. that not even the first
+/// version of the label has been published. This proof is sent in response to a lookup
+/// query for a label which does not exist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct NonMembershipLookupProof {
+    /// VRF proof for the label corresponding to the first version
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(serialize_with = "bytes_serialize_hex")
+    )]
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(deserialize_with = "bytes_deserialize_hex")
+    )]
+    pub vrf_proof: Vec<u8>,
+    /// Non-existence of the first version
+    pub non_membership_proof: NonMembershipProof,
 }
 
 /// A vector of UpdateProofs are sent as the proof to a history query for a particular key.
@@ -451,24 +655,68 @@ pub struct UpdateProof {
     /// Version at this update
     pub version: u64,
     /// VRF proof for the label for the current version
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(serialize_with = "bytes_serialize_hex")
+    )]
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(deserialize_with = "bytes_deserialize_hex")
+    )]
     pub existence_vrf_proof: Vec<u8>,
     /// Membership proof to show that the key was included in this epoch
     pub existence_proof: MembershipProof,
     /// VRF proof for the label for the previous version which became stale
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(serialize_with = "optional_bytes_serialize_hex")
+    )]
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(deserialize_with = "optional_bytes_deserialize_hex")
+    )]
     pub previous_version_vrf_proof: Option<Vec<u8>>,
     /// Proof that previous value was set to old at this epoch
     pub previous_version_proof: Option<MembershipProof>,
     /// Nonce for commitment value derived from raw AkdLabel and AkdValue
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(serialize_with = "bytes_serialize_hex")
+    )]
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(deserialize_with = "bytes_deserialize_hex")
+    )]
     pub commitment_nonce: Vec<u8>,
 }
 
+impl SizeOf for UpdateProof {
+    fn size_of(&self) -> usize {
+        core::mem::size_of::<u64>() * 2
+            + self.value.size_of()
+            + self.existence_vrf_proof.len()
+            + self.existence_proof.size_of()
+            + self
+                .previous_version_vrf_proof
+                .as_ref()
+                .map(|proof| proof.len())
+                .unwrap_or(0)
+            + self
+                .previous_version_proof
+                .as_ref()
+                .map(|proof| proof.size_of())
+                .unwrap_or(0)
+            + self.commitment_nonce.len()
+    }
+}
+
 /// A client can query for a history of all versions associated with a given [AkdLabel], or the most recent k versions.
 /// The server returns a [HistoryProof] which can be verified to extract a list of [VerifyResult]s, one for each
 /// version.
 /// Let `n` be the latest version, `n_prev_pow` be the power of 2 that is at most n, `n_next_pow` the next power of 2 after `n`, and `epoch_prev_pow` be the power of 2 that
 /// is at most the current epoch. The [HistoryProof] consists of:
 /// - A list of [UpdateProof]s, one for each version, which each contain a membership proof for the version `n` being fresh,
-/// and a membership proof for the version `n-1` being stale
+///   and a membership proof for the version `n-1` being stale
 /// - A membership proof for `n_prev_pow` (or empty if n is a power of 2)
 /// - A series of non-membership proofs for each version in the range `[n+1, n_next_pow]`
 /// - A series of non-membership proofs for each power of 2 in the range `[n_next_pow, epoch_prev_pow]`
@@ -484,15 +732,206 @@ pub struct HistoryProof {
     /// The update proofs in the key history
     pub update_proofs: Vec<UpdateProof>,
     /// VRF Proofs for the labels of the values for past markers
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(serialize_with = "bytes_vec_serialize_hex")
+    )]
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(deserialize_with = "bytes_vec_deserialize_hex")
+    )]
     pub past_marker_vrf_proofs: Vec<Vec<u8>>,
     /// Proof that the values for the past markers exist
     pub existence_of_past_marker_proofs: Vec<MembershipProof>,
     /// VRF proofs for the labels of future marker entries
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(serialize_with = "bytes_vec_serialize_hex")
+    )]
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(deserialize_with = "bytes_vec_deserialize_hex")
+    )]
     pub future_marker_vrf_proofs: Vec<Vec<u8>>,
     /// Proof that future markers did not exist
     pub non_existence_of_future_marker_proofs: Vec<NonMembershipProof>,
 }
 
+impl SizeOf for HistoryProof {
+    fn size_of(&self) -> usize {
+        self.update_proofs
+            .iter()
+            .map(|proof| proof.size_of())
+            .sum::<usize>()
+            + self
+                .past_marker_vrf_proofs
+                .iter()
+                .map(|proof| proof.len())
+                .sum::<usize>()
+            + self
+                .existence_of_past_marker_proofs
+                .iter()
+                .map(|proof| proof.size_of())
+                .sum::<usize>()
+            + self
+                .future_marker_vrf_proofs
+                .iter()
+                .map(|proof| proof.len())
+                .sum::<usize>()
+            + self
+                .non_existence_of_future_marker_proofs
+                .iter()
+                .map(|proof| proof.size_of())
+                .sum::<usize>()
+    }
+}
+
+/// Proof that the state of a label was carried over from the previous VRF key of the directory
+/// to its new VRF key, when the key was rotated. At the rotation epoch, every version of the
+/// label is republished under the new key (at the rotation epoch), while the latest version
+/// under the previous key is marked stale so that the previous key can no longer serve lookups.
+///
+/// The [KeyRotationProof] consists of:
+/// - An [UpdateProof] of the latest version under the previous key, at the epoch at which
+///   it was originally published
+/// - A membership proof for that version being stale under the previous key, at the rotation
+///   epoch, which shows that it was the latest version until the rotation
+/// - An [UpdateProof] of the same version and value under the new key, at the rotation epoch
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct KeyRotationProof {
+    /// The epoch at which the VRF key was rotated
+    pub rotation_epoch: u64,
+    /// The update proof of the latest version under the previous key
+    pub previous_key_update_proof: UpdateProof,
+    /// VRF proof, under the previous key, for the stale label of the latest version
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(serialize_with = "bytes_serialize_hex")
+    )]
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(deserialize_with = "bytes_deserialize_hex")
+    )]
+    pub retirement_vrf_proof: Vec<u8>,
+    /// Proof that the latest version was set to stale under the previous key at the rotation epoch
+    pub retirement_proof: MembershipProof,
+    /// The update proof of the same version under the new key
+    pub new_key_update_proof: UpdateProof,
+}
+
+/// A cross-certification of a rotation of the VRF key of a directory, which is attached to
+/// the [LookupProof]s generated during the transition window of the rotation. It allows clients
+/// which only know the previous VRF public key to move on to the new one.
+///
+/// The certificate holds a VRF proof under each of the two keys over the same message (given by
+/// [VrfRotationCertificate::message]), which binds the rotation epoch and both public keys. As
+/// VRF proofs cannot be produced without the private key, the proof under the previous key
+/// certifies the new key, and the proof under the new key shows that it is held by the directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct VrfRotationCertificate {
+    /// The epoch at which the VRF key was rotated
+    pub rotation_epoch: u64,
+    /// The previous VRF public key
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(serialize_with = "bytes_serialize_hex")
+    )]
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(deserialize_with = "bytes_deserialize_hex")
+    )]
+    pub previous_vrf_public_key: Vec<u8>,
+    /// The new VRF public key
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(serialize_with = "bytes_serialize_hex")
+    )]
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(deserialize_with = "bytes_deserialize_hex")
+    )]
+    pub new_vrf_public_key: Vec<u8>,
+    /// VRF proof, under the previous key, over the message of the certificate
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(serialize_with = "bytes_serialize_hex")
+    )]
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(deserialize_with = "bytes_deserialize_hex")
+    )]
+    pub previous_key_proof: Vec<u8>,
+    /// VRF proof, under the new key, over the message of the certificate
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(serialize_with = "bytes_serialize_hex")
+    )]
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(deserialize_with = "bytes_deserialize_hex")
+    )]
+    pub new_key_proof: Vec<u8>,
+}
+
+impl SizeOf for VrfRotationCertificate {
+    fn size_of(&self) -> usize {
+        core::mem::size_of::<u64>()
+            + self.previous_vrf_public_key.len()
+            + self.new_vrf_public_key.len()
+            + self.previous_key_proof.len()
+            + self.new_key_proof.len()
+    }
+}
+
+impl VrfRotationCertificate {
+    const DOMAIN_SEPARATOR: &'static [u8] = b"akd_vrf_rotation";
+
+    /// The message over which both VRF proofs of the certificate are produced
+    pub fn message(
+        rotation_epoch: u64,
+        previous_vrf_public_key: &[u8],
+        new_vrf_public_key: &[u8],
+    ) -> Vec<u8> {
+        [
+            Self::DOMAIN_SEPARATOR,
+            &rotation_epoch.to_be_bytes(),
+            &(previous_vrf_public_key.len() as u64).to_be_bytes(),
+            previous_vrf_public_key,
+            &(new_vrf_public_key.len() as u64).to_be_bytes(),
+            new_vrf_public_key,
+        ]
+        .concat()
+    }
+}
+
+/// Proof that the state of a label in one of the directories which were merged into a new
+/// directory was carried over to the merged directory. The history of each label of the merged
+/// directory is replayed from one of the original directories, with the same versions, values
+/// and epochs, so that clients of that directory can keep on verifying it in the merged one.
+///
+/// The [MergeBridgeProof] consists of:
+/// - A [LookupProof] of the latest version of the label in the original directory
+/// - An [UpdateProof] of the same version and value in the merged directory, at the same epoch
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct MergeBridgeProof {
+    /// The lookup proof of the latest version of the label in the original directory
+    pub original_lookup_proof: LookupProof,
+    /// The update proof of the same version in the merged directory
+    pub merged_update_proof: UpdateProof,
+}
+
 /// The payload that is outputted as a result of successful verification of
 /// a [LookupProof] or [HistoryProof]. This includes the fields containing the
 /// epoch that the leaf was published in, the version corresponding to the value,
@@ -511,6 +950,58 @@ pub struct VerifyResult {
     pub value: AkdValue,
 }
 
+/// What a verified [HistoryProof] establishes about the versions of a label, beyond the
+/// [VerifyResult] of each of its updates, as returned by
+/// [crate::verify::history::key_history_verify_with_metadata]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Deserialize, serde::Serialize)
+)]
+pub struct HistoryMetadata {
+    /// The epoch against whose root hash the history was verified
+    pub epoch: u64,
+    /// Whether the history is complete, i.e. its oldest value is the first version of the label
+    pub complete: bool,
+    /// The versions older than the verified values which were proven to have been published
+    /// (the marker version preceding the oldest value, unless the history is complete)
+    pub past_marker_versions: Vec<u64>,
+    /// The versions newer than the latest verified value which were proven not to have been
+    /// published as of the epoch. These are all of the versions up to the next power of 2, and
+    /// then every power of 2 up to the epoch, so that the latest verified value is the latest
+    /// version of the label.
+    pub future_marker_versions: Vec<u64>,
+    /// The ranges of consecutive versions whose values are tombstones (i.e. were removed from
+    /// storage), from the most recent to the oldest
+    pub tombstoned_versions: Vec<core::ops::RangeInclusive<u64>>,
+}
+
+/// The derivation of a [NodeLabel] from a version of an [AkdLabel], as checked from the VRF
+/// proof of a lookup proof by [crate::verify::lookup_label_derivations]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Deserialize, serde::Serialize)
+)]
+pub struct LabelDerivation {
+    /// Whether the node label is for a fresh or a stale version
+    pub freshness: VersionFreshness,
+    /// The version from which the node label is derived
+    pub version: u64,
+    /// The node label, which is the truncation of the VRF output
+    pub node_label: NodeLabel,
+    /// The output of the VRF (beta in RFC 9381) for the version
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(serialize_with = "bytes_serialize_hex")
+    )]
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(deserialize_with = "bytes_deserialize_hex")
+    )]
+    pub vrf_output: Vec<u8>,
+}
+
 /// Proof that no leaves were deleted from the initial epoch.
 /// This means that unchanged_nodes should hash to the initial root hash
 /// and the vec of inserted is the set of leaves inserted between these epochs.
@@ -529,6 +1020,16 @@ pub struct SingleAppendOnlyProof {
     pub unchanged_nodes: Vec<AzksElement>,
 }
 
+impl SizeOf for SingleAppendOnlyProof {
+    fn size_of(&self) -> usize {
+        self.inserted
+            .iter()
+            .chain(self.unchanged_nodes.iter())
+            .map(|element| element.size_of())
+            .sum::<usize>()
+    }
+}
+
 /// Proof that no leaves were deleted from the initial epoch.
 /// This is done using a list of SingleAppendOnly proofs, one proof
 /// for each epoch between the initial epoch and final epochs which are
@@ -544,3 +1045,250 @@ pub struct AppendOnlyProof {
     /// Epochs over which this audit is being performed
     pub epochs: Vec<u64>,
 }
+
+impl SizeOf for AppendOnlyProof {
+    fn size_of(&self) -> usize {
+        self.proofs
+            .iter()
+            .map(|proof| proof.size_of())
+            .sum::<usize>()
+            + core::mem::size_of::<u64>() * self.epochs.len()
+    }
+}
+
+/// The proof for a single epoch of a [CompressedAppendOnlyProof], which refers to its
+/// unchanged nodes by their position in [CompressedAppendOnlyProof::unchanged_nodes]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Deserialize, serde::Serialize)
+)]
+pub struct CompressedSingleAppendOnlyProof {
+    /// The inserted nodes & digests
+    pub inserted: Vec<AzksElement>,
+    /// The indices of the unchanged nodes & digests
+    pub unchanged_nodes: Vec<u64>,
+}
+
+/// An [AppendOnlyProof] over a range of epochs, in which the unchanged nodes which are
+/// shared by the proofs of several epochs are only included once
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Deserialize, serde::Serialize)
+)]
+pub struct CompressedAppendOnlyProof {
+    /// The distinct unchanged nodes & digests of the proofs of all the epochs
+    pub unchanged_nodes: Vec<AzksElement>,
+    /// Proof for a single epoch being append-only
+    pub proofs: Vec<CompressedSingleAppendOnlyProof>,
+    /// Epochs over which this audit is being performed
+    pub epochs: Vec<u64>,
+}
+
+/// A segment of the [SingleAppendOnlyProof] for a single epoch, so that audit proofs
+/// can be generated (and verified) incrementally instead of materializing the full
+/// proof. The segments of an epoch are produced in order, and concatenating their
+/// inserted and unchanged nodes results in the [SingleAppendOnlyProof] of the epoch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Deserialize, serde::Serialize)
+)]
+pub struct AuditProofSegment {
+    /// The epoch which is proven to be extended in an append-only manner by `epoch + 1`
+    pub epoch: u64,
+    /// The position of this segment among the segments of the epoch, starting at 0
+    pub index: u64,
+    /// Whether this is the last segment of the epoch
+    pub last: bool,
+    /// The inserted nodes & digests of this segment
+    pub inserted: Vec<AzksElement>,
+    /// The unchanged nodes & digests of this segment
+    pub unchanged_nodes: Vec<AzksElement>,
+}
+
+/// Metadata recorded by the server alongside each published epoch. This
+/// includes the wall-clock time of publication, the number of label updates
+/// which were applied, and an optional note supplied by the operator.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Deserialize, serde::Serialize)
+)]
+pub struct EpochMetadata {
+    /// The epoch this metadata describes
+    pub epoch: u64,
+    /// The publication time, in milliseconds since the UNIX epoch
+    pub timestamp_ms: u64,
+    /// The number of label updates included in this epoch
+    pub update_count: u64,
+    /// An optional annotation provided by the operator at publish time
+    pub note: Option<String>,
+    /// Whether a commitment to this metadata was inserted into the tree when the
+    /// epoch was published, such that it can be proven against the root hash
+    pub bound: bool,
+    /// The signature over the root hash of this epoch, if the directory was configured
+    /// with a [crate::signature::EpochSigner]. As the signature is computed over the root
+    /// hash, it is not covered by the commitment to this metadata.
+    pub signature: Option<Vec<u8>>,
+}
+
+impl SizeOf for EpochMetadata {
+    fn size_of(&self) -> usize {
+        core::mem::size_of::<u64>() * 3
+            + core::mem::size_of::<bool>()
+            + self.note.as_ref().map(|n| n.len()).unwrap_or(0)
+            + self.signature.as_ref().map(|s| s.len()).unwrap_or(0)
+    }
+}
+
+impl EpochMetadata {
+    const DOMAIN_SEPARATOR: &'static [u8] = b"akd_epoch_metadata";
+
+    /// The maximum length, in bytes, of the operator note
+    pub const MAX_NOTE_LENGTH: usize = 2000;
+
+    /// The [NodeLabel] at which the metadata for the given epoch is inserted into the
+    /// tree, when the directory is configured to bind epoch metadata into the root hash.
+    /// These labels are derived from a hash with a dedicated domain separator, so they
+    /// cannot collide with the VRF-derived labels of regular entries.
+    pub fn node_label<TC: Configuration>(epoch: u64) -> NodeLabel {
+        let digest = TC::hash(&[Self::DOMAIN_SEPARATOR, &epoch.to_be_bytes()].concat());
+        NodeLabel::new(digest, 256)
+    }
+
+    /// Serializes the metadata into the canonical byte representation used for hashing.
+    /// Note that this excludes the signature, as it is computed over the root hash.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let note = self.note.as_ref().map(|n| n.as_bytes()).unwrap_or(&[]);
+        [
+            &self.epoch.to_be_bytes()[..],
+            &self.timestamp_ms.to_be_bytes(),
+            &self.update_count.to_be_bytes(),
+            &[self.note.is_some() as u8],
+            &crate::utils::i2osp_array(note),
+            &[self.bound as u8],
+        ]
+        .concat()
+    }
+
+    /// Used by the server to derive the nonce which salts the commitment to this
+    /// metadata, in the same manner as [Configuration::get_commitment_nonce]. Without
+    /// the nonce, the commitment cannot be brute-forced from a sequence of audit proofs.
+    pub fn commitment_nonce<TC: Configuration>(&self, commitment_key: &[u8]) -> Digest {
+        TC::hash(
+            &[
+                commitment_key,
+                &Self::node_label::<TC>(self.epoch).to_bytes(),
+                &self.to_bytes(),
+            ]
+            .concat(),
+        )
+    }
+
+    /// Computes the [AzksValue] which commits to this metadata in the tree, using
+    /// the nonce produced by [EpochMetadata::commitment_nonce]
+    pub fn commitment<TC: Configuration>(&self, nonce: &[u8]) -> AzksValue {
+        AzksValue(TC::hash(
+            &[Self::DOMAIN_SEPARATOR, nonce, &self.to_bytes()].concat(),
+        ))
+    }
+}
+
+/// Proof that a particular [EpochMetadata] was bound into the tree at the
+/// epoch it describes
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Deserialize, serde::Serialize)
+)]
+pub struct EpochMetadataProof {
+    /// The metadata in question
+    pub metadata: EpochMetadata,
+    /// The nonce used in the commitment to the metadata
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(serialize_with = "bytes_serialize_hex")
+    )]
+    #[cfg_attr(
+        feature = "serde_serialization",
+        serde(deserialize_with = "bytes_deserialize_hex")
+    )]
+    pub commitment_nonce: Vec<u8>,
+    /// Membership proof of the metadata commitment
+    pub membership_proof: MembershipProof,
+}
+
+/// The root hashes of a range of consecutive epochs, linked together by a hash chain
+/// over the root hashes recorded by the server. The chain hash of each epoch is the hash
+/// of the chain hash of the previous epoch, the epoch and its root hash (see
+/// [EpochRootChain::link]), so a client which trusts the chain hash of an epoch can
+/// check the root hashes of all the epochs leading up to it, and can extend a history
+/// it already holds with the root hashes of the following epochs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Deserialize, serde::Serialize)
+)]
+pub struct EpochRootChain {
+    /// The first epoch of the range
+    pub start_epoch: u64,
+    /// The chain hash of the epoch preceding the range, or [crate::hash::EMPTY_DIGEST] if
+    /// the chain starts at the first epoch of the range
+    pub previous_chain_hash: Digest,
+    /// The root hashes of the epochs of the range, in order
+    pub root_hashes: Vec<Digest>,
+    /// The chain hash of the last epoch of the range
+    pub chain_hash: Digest,
+}
+
+impl EpochRootChain {
+    const DOMAIN_SEPARATOR: &'static [u8] = b"akd_epoch_root_chain";
+
+    /// Computes the chain hash of an epoch from the chain hash of the previous epoch
+    /// (or [crate::hash::EMPTY_DIGEST] for the first epoch of the chain) and the root
+    /// hash of the epoch
+    pub fn link<TC: Configuration>(
+        previous_chain_hash: &Digest,
+        epoch: u64,
+        root_hash: &Digest,
+    ) -> Digest {
+        TC::hash(
+            &[
+                Self::DOMAIN_SEPARATOR,
+                previous_chain_hash,
+                &epoch.to_be_bytes(),
+                root_hash,
+            ]
+            .concat(),
+        )
+    }
+
+    /// The last epoch of the range, if it is not empty
+    pub fn end_epoch(&self) -> Option<u64> {
+        (self.root_hashes.len() as u64)
+            .checked_sub(1)
+            .map(|offset| self.start_epoch + offset)
+    }
+
+    /// The `(epoch, root_hash)` pairs of the range, in order
+    pub fn epochs(&self) -> impl Iterator<Item = (u64, Digest)> + '_ {
+        (self.start_epoch..)
+            .zip(self.root_hashes.iter())
+            .map(|(epoch, root_hash)| (epoch, *root_hash))
+    }
+}
+
+/// The chain hash which a client trusts, independently of the server, when verifying an
+/// [EpochRootChain] (see [crate::verify::verify_epoch_root_chain])
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RootChainAnchor {
+    /// The chain hash of the epoch preceding the range, e.g. from a previously verified
+    /// chain, or [crate::hash::EMPTY_DIGEST] if the client trusts that the range starts the
+    /// recorded history
+    Previous(Digest),
+    /// The chain hash of the last epoch of the range, e.g. received from an auditor or
+    /// over another channel than the chain itself
+    Last(Digest),
+}
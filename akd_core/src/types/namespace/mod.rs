@@ -0,0 +1,124 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! This module contains [LabelNamespace], which allows a single directory to host several
+//! independent label spaces (e.g. users, groups and devices). The namespace is encoded into
+//! the [AkdLabel] itself, and hence into the VRF input of every version of the label, so
+//! that equal labels from different namespaces never collide.
+
+use crate::AkdLabel;
+
+#[cfg(feature = "nostd")]
+use alloc::format;
+#[cfg(feature = "nostd")]
+use alloc::string::{String, ToString};
+#[cfg(feature = "nostd")]
+use alloc::vec::Vec;
+
+#[cfg(test)]
+mod tests;
+
+/// Prefix of every namespaced label, distinguishing it from a plain label
+const NAMESPACE_PREFIX: &[u8] = b"akd_ns";
+
+/// Errors thrown when constructing a [LabelNamespace]
+#[derive(Debug, Eq, PartialEq)]
+pub enum NamespaceError {
+    /// The namespace name is empty or too long
+    InvalidName(String),
+}
+
+impl core::fmt::Display for NamespaceError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let code = match &self {
+            NamespaceError::InvalidName(msg) => format!("(Invalid name) - {msg}"),
+        };
+        write!(f, "Label namespace error {code}")
+    }
+}
+
+/// A namespace of labels (e.g. "user" or "device"), which is domain separated from every
+/// other namespace. The labels of a namespace are constructed with [LabelNamespace::label],
+/// and can be published, looked up and verified like any other label.
+///
+/// A namespaced label is encoded as the prefix `akd_ns`, followed by the length of the
+/// namespace name as a single byte, the name and then the bytes of the label.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct LabelNamespace(String);
+
+impl LabelNamespace {
+    /// The maximum length of a namespace name, in bytes
+    pub const MAX_NAME_LENGTH: usize = u8::MAX as usize;
+
+    /// Creates a namespace with the provided name, which must be non-empty and
+    /// at most [LabelNamespace::MAX_NAME_LENGTH] bytes long
+    pub fn new(name: &str) -> Result<Self, NamespaceError> {
+        if name.is_empty() {
+            return Err(NamespaceError::InvalidName(
+                "Namespace name cannot be empty".to_string(),
+            ));
+        }
+        if name.len() > Self::MAX_NAME_LENGTH {
+            return Err(NamespaceError::InvalidName(format!(
+                "Namespace name of {} bytes exceeds the maximum length of {} bytes",
+                name.len(),
+                Self::MAX_NAME_LENGTH
+            )));
+        }
+        Ok(Self(name.to_string()))
+    }
+
+    /// The name of the namespace
+    pub fn name(&self) -> &str {
+        &self.0
+    }
+
+    /// Constructs the label within this namespace from the provided bytes
+    pub fn label(&self, label: &[u8]) -> AkdLabel {
+        let mut bytes = self.prefix();
+        bytes.extend_from_slice(label);
+        AkdLabel(bytes)
+    }
+
+    /// Returns the bytes of the label within this namespace, or [None] if the
+    /// label does not belong to this namespace
+    pub fn strip<'a>(&self, label: &'a AkdLabel) -> Option<&'a [u8]> {
+        label.strip_prefix(self.prefix().as_slice())
+    }
+
+    /// Returns whether the label belongs to this namespace
+    pub fn contains(&self, label: &AkdLabel) -> bool {
+        self.strip(label).is_some()
+    }
+
+    /// Splits a namespaced label into its namespace and the bytes of the label within
+    /// the namespace, or returns [None] if the label is not namespaced
+    pub fn split(label: &AkdLabel) -> Option<(Self, Vec<u8>)> {
+        let bytes = label.strip_prefix(NAMESPACE_PREFIX)?;
+        let (&len, bytes) = bytes.split_first()?;
+        if len == 0 || bytes.len() < len as usize {
+            return None;
+        }
+        let (name, rest) = bytes.split_at(len as usize);
+        let name = core::str::from_utf8(name).ok()?;
+        Some((Self(name.to_string()), rest.to_vec()))
+    }
+
+    fn prefix(&self) -> Vec<u8> {
+        [NAMESPACE_PREFIX, &[self.0.len() as u8], self.0.as_bytes()].concat()
+    }
+}
+
+impl core::fmt::Display for LabelNamespace {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
@@ -0,0 +1,54 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Tests for label namespaces
+
+use super::*;
+
+#[test]
+fn test_namespaced_label_round_trip() {
+    let users = LabelNamespace::new("user").unwrap();
+    let label = users.label(b"alice");
+    assert_eq!(
+        AkdLabel([b"akd_ns".as_slice(), &[4], b"user", b"alice"].concat()),
+        label
+    );
+    assert!(users.contains(&label));
+    assert_eq!(Some(b"alice".as_slice()), users.strip(&label));
+    assert_eq!(
+        Some((users, b"alice".to_vec())),
+        LabelNamespace::split(&label)
+    );
+
+    assert_eq!(None, LabelNamespace::split(&AkdLabel::from("alice")));
+    assert_eq!(None, LabelNamespace::split(&AkdLabel::from("akd_ns")));
+    assert_eq!(
+        None,
+        LabelNamespace::split(&AkdLabel([b"akd_ns".as_slice(), &[5], b"user"].concat()))
+    );
+}
+
+#[test]
+fn test_namespaces_are_separated() {
+    let users = LabelNamespace::new("user").unwrap();
+    let user_groups = LabelNamespace::new("user_group").unwrap();
+    let groups = LabelNamespace::new("group").unwrap();
+
+    // Neither the names nor the labels can be shifted into each other
+    assert_ne!(users.label(b"_groupalice"), user_groups.label(b"alice"));
+    assert_ne!(users.label(b"alice"), groups.label(b"alice"));
+    assert!(!users.contains(&user_groups.label(b"alice")));
+    assert!(!user_groups.contains(&users.label(b"_groupalice")));
+    assert!(!users.contains(&AkdLabel::from("alice")));
+}
+
+#[test]
+fn test_namespace_name_validation() {
+    assert!(LabelNamespace::new("").is_err());
+    assert!(LabelNamespace::new(&"a".repeat(LabelNamespace::MAX_NAME_LENGTH)).is_ok());
+    assert!(LabelNamespace::new(&"a".repeat(LabelNamespace::MAX_NAME_LENGTH + 1)).is_err());
+}
@@ -16,12 +16,16 @@ use crate::utils::serde_helpers::{bytes_deserialize_hex, bytes_serialize_hex};
 use alloc::format;
 #[cfg(feature = "nostd")]
 use alloc::string::String;
-#[cfg(feature = "nostd")]
-use alloc::vec::Vec;
+use smallvec::SmallVec;
 
 #[cfg(test)]
 mod tests;
 
+/// The value of a [NodeLabel] which is hashed into the hash of its parent node (see
+/// [NodeLabel::value]). It is held inline, since it is at most as long as the 36 bytes of the
+/// label for all configurations.
+pub type NodeLabelValue = SmallVec<[u8; 36]>;
+
 /// Represents the label of a AKD node
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[cfg_attr(
@@ -82,20 +86,21 @@ pub(crate) enum Bit {
 
 impl NodeLabel {
     /// Returns the value of the [NodeLabel]
-    pub fn value<TC: Configuration>(&self) -> Vec<u8> {
+    pub fn value<TC: Configuration>(&self) -> NodeLabelValue {
         TC::compute_node_label_value(&self.to_bytes())
     }
 
-    pub(crate) fn to_bytes(self) -> Vec<u8> {
-        [&self.label_len.to_be_bytes(), &self.label_val[..]].concat()
+    pub(crate) fn to_bytes(self) -> [u8; 36] {
+        let mut bytes = [0u8; 36];
+        bytes[..4].copy_from_slice(&self.label_len.to_be_bytes());
+        bytes[4..].copy_from_slice(&self.label_val);
+        bytes
     }
 
     /// Outputs whether or not self is a prefix of the other [NodeLabel]
     pub fn is_prefix_of(&self, other: &Self) -> bool {
-        if self.label_len > other.label_len {
-            return false;
-        }
-        (0..self.label_len).all(|i| self.get_bit_at(i) == other.get_bit_at(i))
+        self.label_len <= other.label_len
+            && self.get_prefix(self.label_len) == other.get_prefix(self.label_len)
     }
 
     /// Takes as input a pointer to the caller and another [NodeLabel],
@@ -112,14 +117,18 @@ impl NodeLabel {
             other.label_len
         };
 
+        // Compare the labels a byte at a time, up to the first bit at which they differ
         let mut prefix_len = 0;
-        while prefix_len < shorter_len
-            && self.get_bit_at(prefix_len) == other.get_bit_at(prefix_len)
-        {
-            prefix_len += 1;
+        for (byte, other_byte) in self.label_val.iter().zip(other.label_val.iter()) {
+            let diff = byte ^ other_byte;
+            if diff != 0 {
+                prefix_len += diff.leading_zeros();
+                break;
+            }
+            prefix_len += 8;
         }
 
-        self.get_prefix(prefix_len)
+        self.get_prefix(prefix_len.min(shorter_len))
     }
 
     /// Returns the bit at a specified index (either a 0 or a 1). Will
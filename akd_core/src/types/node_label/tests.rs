@@ -10,7 +10,7 @@
 use super::*;
 use crate::test_config_sync;
 #[cfg(feature = "nostd")]
-use alloc::vec;
+use alloc::{vec, vec::Vec};
 use rand::{thread_rng, Rng};
 
 // ================= Test helpers ================= //
@@ -49,7 +49,7 @@ fn byte_arr_from_u64_le(input_int: u64) -> [u8; 32] {
 #[test]
 fn test_get_bit_at_small() {
     let val = 0b1010u64 << 60;
-    let expected = vec![Bit::One, Bit::Zero, Bit::One, Bit::Zero];
+    let expected = [Bit::One, Bit::Zero, Bit::One, Bit::Zero];
     let label = NodeLabel::new(byte_arr_from_u64(val), 4);
     for (index, item) in expected.iter().enumerate().take(4) {
         assert!(
@@ -276,8 +276,8 @@ fn test_node_label_lcp_with_zero_length_label<TC: Configuration>() {
 // Test for get_longest_common_prefix between a label and its prefix.
 test_config_sync!(test_node_label_lcp_with_prefix_label);
 fn test_node_label_lcp_with_prefix_label<TC: Configuration>() {
-    let label_1 = NodeLabel::new(byte_arr_from_u64(01u64 << 62), 2u32);
-    let label_2 = NodeLabel::new(byte_arr_from_u64(01u64 << 62), 3u32);
+    let label_1 = NodeLabel::new(byte_arr_from_u64(1u64 << 62), 2u32);
+    let label_2 = NodeLabel::new(byte_arr_from_u64(1u64 << 62), 3u32);
     let expected = label_1;
     assert!(
         label_1.get_longest_common_prefix::<TC>(label_2) == expected,
@@ -390,6 +390,7 @@ fn test_node_label_lcp_some_leading_zero<TC: Configuration>() {
 // This test tests get_dir by manually computing the prefix and the bit
 // immediately following the prefix of that length.
 test_config_sync!(test_get_dir_large);
+#[allow(clippy::extra_unused_type_parameters)]
 fn test_get_dir_large<TC: Configuration>() {
     for i in 0..256 {
         let label_1 = random_label();
@@ -442,29 +443,29 @@ fn test_get_prefix_small() {
 
 test_config_sync!(test_is_prefix_of);
 fn test_is_prefix_of<TC: Configuration>() {
-    let label_1 = NodeLabel::new(byte_arr_from_u64(0b01u64 << 62), 4u32);
+    let label_1 = NodeLabel::new(byte_arr_from_u64(0b1u64 << 62), 4u32);
     let label_2 = NodeLabel::new(byte_arr_from_u64(0b010u64 << 61), 5u32);
     let label_3 = NodeLabel::new(byte_arr_from_u64(0b0u64), 4u32);
 
     // empty label is prefix of all labels
-    assert_eq!(TC::empty_label().is_prefix_of(&label_1), true);
-    assert_eq!(TC::empty_label().is_prefix_of(&label_2), true);
-    assert_eq!(TC::empty_label().is_prefix_of(&label_3), true);
+    assert!(TC::empty_label().is_prefix_of(&label_1));
+    assert!(TC::empty_label().is_prefix_of(&label_2));
+    assert!(TC::empty_label().is_prefix_of(&label_3));
 
     // every label is a prefix of itself
-    assert_eq!(label_1.is_prefix_of(&label_1), true);
-    assert_eq!(label_2.is_prefix_of(&label_2), true);
-    assert_eq!(label_3.is_prefix_of(&label_3), true);
+    assert!(label_1.is_prefix_of(&label_1));
+    assert!(label_2.is_prefix_of(&label_2));
+    assert!(label_3.is_prefix_of(&label_3));
 
     // valid prefixes
-    assert_eq!(label_1.is_prefix_of(&label_2), true);
+    assert!(label_1.is_prefix_of(&label_2));
 
     // invalid prefixes
-    assert_eq!(label_1.is_prefix_of(&label_3), false);
-    assert_eq!(label_2.is_prefix_of(&label_1), false);
-    assert_eq!(label_2.is_prefix_of(&label_3), false);
-    assert_eq!(label_3.is_prefix_of(&label_1), false);
-    assert_eq!(label_3.is_prefix_of(&label_2), false);
+    assert!(!label_1.is_prefix_of(&label_3));
+    assert!(!label_2.is_prefix_of(&label_1));
+    assert!(!label_2.is_prefix_of(&label_3));
+    assert!(!label_3.is_prefix_of(&label_1));
+    assert!(!label_3.is_prefix_of(&label_2));
 }
 
 // This test gets a prefix for a hard-coded random string and makes sure it is equal to a hand-computed value.
@@ -489,3 +490,34 @@ fn test_get_prefix_ordering_with_invalid_bits() {
         PrefixOrdering::WithZero
     );
 }
+
+// Test that hashing the child labels directly matches hashing their values
+test_config_sync!(test_parent_hash_from_child_labels);
+fn test_parent_hash_from_child_labels<TC: Configuration>() {
+    let left_label = random_label().get_prefix(100);
+    let right_label = random_label();
+    let left_val = crate::AzksValue(thread_rng().gen::<[u8; 32]>());
+    let right_val = crate::AzksValue(thread_rng().gen::<[u8; 32]>());
+
+    assert_eq!(
+        TC::compute_parent_hash_from_children(
+            &left_val,
+            &left_label.value::<TC>(),
+            &right_val,
+            &right_label.value::<TC>()
+        ),
+        TC::compute_parent_hash_from_child_labels(&left_val, left_label, &right_val, right_label)
+    );
+    assert_eq!(
+        TC::hash(&[&left_val.0[..], &right_label.to_bytes()].concat()),
+        TC::hash_all(&[&left_val.0, &right_label.to_bytes()])
+    );
+    assert_eq!(
+        [
+            &right_label.label_len.to_be_bytes()[..],
+            &right_label.label_val
+        ]
+        .concat(),
+        right_label.to_bytes()
+    );
+}
@@ -0,0 +1,188 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! This module contains [AkdValueSet], which allows multiple values (e.g. the keys of
+//! each of a user's devices) to be stored under a single label. The set is stored as
+//! one [AkdValue] with a canonical encoding, so that a lookup proof for the label
+//! commits to the entire set.
+
+use crate::AkdValue;
+
+#[cfg(feature = "nostd")]
+use alloc::collections::BTreeSet;
+#[cfg(feature = "nostd")]
+use alloc::format;
+#[cfg(feature = "nostd")]
+use alloc::string::{String, ToString};
+#[cfg(not(feature = "nostd"))]
+use std::collections::BTreeSet;
+
+#[cfg(test)]
+mod tests;
+
+/// Prefix of every encoded value set, distinguishing it from a plain value
+const VALUE_SET_PREFIX: &[u8] = b"akd_value_set";
+
+/// Errors thrown when decoding an [AkdValueSet] from an [AkdValue]
+#[derive(Debug, Eq, PartialEq)]
+pub enum ValueSetError {
+    /// The value is not a (canonically) encoded value set
+    Malformed(String),
+}
+
+impl core::fmt::Display for ValueSetError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let code = match &self {
+            ValueSetError::Malformed(msg) => format!("(Malformed) - {msg}"),
+        };
+        write!(f, "Value set error {code}")
+    }
+}
+
+/// A set of values stored under a single label. Publishing the set (converted into an
+/// [AkdValue]) replaces all previous values of the label, and the value of a verified
+/// lookup can be converted back with [AkdValueSet::try_from].
+///
+/// The values are kept in sorted order without duplicates, so that every set has exactly
+/// one encoding. The encoding is the prefix `akd_value_set`, followed by the number of
+/// values and then each value, with the count and each length as a 4-byte big-endian integer.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde_serialization",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct AkdValueSet(BTreeSet<AkdValue>);
+
+impl AkdValueSet {
+    /// Creates an empty set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a value to the set, returning whether it was not already present
+    pub fn insert(&mut self, value: AkdValue) -> bool {
+        self.0.insert(value)
+    }
+
+    /// Removes a value from the set, returning whether it was present
+    pub fn remove(&mut self, value: &AkdValue) -> bool {
+        self.0.remove(value)
+    }
+
+    /// Returns whether the set contains the value
+    pub fn contains(&self, value: &AkdValue) -> bool {
+        self.0.contains(value)
+    }
+
+    /// The number of values in the set
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns whether the set is empty
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Iterates over the values in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = &AkdValue> {
+        self.0.iter()
+    }
+
+    /// Returns whether the value is an encoded value set (as opposed to a plain value).
+    /// This only checks the prefix, decoding may still fail.
+    pub fn is_value_set(value: &AkdValue) -> bool {
+        value.starts_with(VALUE_SET_PREFIX)
+    }
+
+    /// Encodes the set into a single value
+    pub fn to_value(&self) -> AkdValue {
+        let mut bytes = VALUE_SET_PREFIX.to_vec();
+        bytes.extend_from_slice(&(self.0.len() as u32).to_be_bytes());
+        for value in self.0.iter() {
+            bytes.extend_from_slice(&(value.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(value);
+        }
+        AkdValue::Bytes(bytes)
+    }
+
+    /// Decodes a set from a value produced by [AkdValueSet::to_value]. Any other encoding
+    /// of the set (e.g. with values out of order) is rejected.
+    pub fn from_value(value: &AkdValue) -> Result<Self, ValueSetError> {
+        let mut bytes = value
+            .strip_prefix(VALUE_SET_PREFIX)
+            .ok_or_else(|| ValueSetError::Malformed("Missing value set prefix".to_string()))?;
+        let count = read_u32(&mut bytes)?;
+
+        let mut set = BTreeSet::new();
+        let mut previous: Option<&[u8]> = None;
+        for _ in 0..count {
+            let len = read_u32(&mut bytes)? as usize;
+            if bytes.len() < len {
+                return Err(ValueSetError::Malformed(format!(
+                    "Value of {len} bytes exceeds the remaining {} bytes",
+                    bytes.len()
+                )));
+            }
+            let (item, rest) = bytes.split_at(len);
+            if previous.is_some_and(|previous| previous >= item) {
+                return Err(ValueSetError::Malformed(
+                    "Values are not in strictly ascending order".to_string(),
+                ));
+            }
+            set.insert(AkdValue::Bytes(item.to_vec()));
+            previous = Some(item);
+            bytes = rest;
+        }
+        if !bytes.is_empty() {
+            return Err(ValueSetError::Malformed(format!(
+                "{} trailing bytes after the last value",
+                bytes.len()
+            )));
+        }
+        Ok(Self(set))
+    }
+}
+
+fn read_u32(bytes: &mut &[u8]) -> Result<u32, ValueSetError> {
+    if bytes.len() < 4 {
+        return Err(ValueSetError::Malformed(
+            "Unexpected end of the encoded value set".to_string(),
+        ));
+    }
+    let (int_bytes, rest) = bytes.split_at(4);
+    *bytes = rest;
+    Ok(u32::from_be_bytes(
+        int_bytes.try_into().expect("Slice with incorrect length"),
+    ))
+}
+
+impl FromIterator<AkdValue> for AkdValueSet {
+    fn from_iter<I: IntoIterator<Item = AkdValue>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl From<AkdValueSet> for AkdValue {
+    fn from(set: AkdValueSet) -> Self {
+        set.to_value()
+    }
+}
+
+impl From<&AkdValueSet> for AkdValue {
+    fn from(set: &AkdValueSet) -> Self {
+        set.to_value()
+    }
+}
+
+impl TryFrom<&AkdValue> for AkdValueSet {
+    type Error = ValueSetError;
+
+    fn try_from(value: &AkdValue) -> Result<Self, Self::Error> {
+        Self::from_value(value)
+    }
+}
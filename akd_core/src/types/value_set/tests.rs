@@ -0,0 +1,90 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Tests for value sets
+
+use super::*;
+#[cfg(feature = "nostd")]
+use alloc::vec;
+#[cfg(feature = "nostd")]
+use alloc::vec::Vec;
+
+fn encode(values: &[&[u8]]) -> AkdValue {
+    let mut bytes = VALUE_SET_PREFIX.to_vec();
+    bytes.extend_from_slice(&(values.len() as u32).to_be_bytes());
+    for value in values {
+        bytes.extend_from_slice(&(value.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(value);
+    }
+    AkdValue::Bytes(bytes)
+}
+
+#[test]
+fn test_value_set_round_trip() {
+    let set = [
+        AkdValue::from("device2"),
+        AkdValue::from("device1"),
+        AkdValue::from(""),
+        AkdValue::from("device1"),
+    ]
+    .into_iter()
+    .collect::<AkdValueSet>();
+    assert_eq!(3, set.len());
+
+    let value = AkdValue::from(&set);
+    assert!(AkdValueSet::is_value_set(&value));
+    assert_eq!(encode(&[b"", b"device1", b"device2"]), value);
+    assert_eq!(Ok(set), AkdValueSet::try_from(&value));
+
+    let empty = AkdValueSet::new();
+    assert_eq!(
+        Ok(empty.clone()),
+        AkdValueSet::from_value(&empty.to_value())
+    );
+}
+
+#[test]
+fn test_value_set_encoding_is_canonical() {
+    let mut first = AkdValueSet::new();
+    assert!(first.insert(AkdValue::from("a")));
+    assert!(first.insert(AkdValue::from("b")));
+    let second = [AkdValue::from("b"), AkdValue::from("a")]
+        .into_iter()
+        .collect::<AkdValueSet>();
+    assert_eq!(first.to_value(), second.to_value());
+
+    assert!(!first.insert(AkdValue::from("a")));
+    assert!(first.remove(&AkdValue::from("a")));
+    assert!(!first.contains(&AkdValue::from("a")));
+    assert_eq!(vec![&AkdValue::from("b")], first.iter().collect::<Vec<_>>());
+}
+
+#[test]
+fn test_value_set_rejects_malformed_values() {
+    let malformed = [
+        // a plain value
+        AkdValue::from("device1"),
+        // out of order, or duplicated values
+        encode(&[b"b", b"a"]),
+        encode(&[b"a", b"a"]),
+        // trailing bytes
+        AkdValue::Bytes([encode(&[b"a"]).to_vec(), vec![0u8]].concat()),
+    ];
+    for value in malformed.iter() {
+        assert!(matches!(
+            AkdValueSet::from_value(value),
+            Err(ValueSetError::Malformed(_))
+        ));
+    }
+
+    // truncated at any point
+    let value = encode(&[b"a", b"bc"]);
+    for len in 0..value.len() {
+        let truncated = AkdValue::Bytes(value[..len].to_vec());
+        assert!(AkdValueSet::from_value(&truncated).is_err());
+    }
+}
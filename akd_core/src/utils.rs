@@ -80,27 +80,85 @@ pub mod serde_helpers {
 
     use crate::AzksValue;
 
-    /// A serde hex serializer for bytes
+    /// A serde hex serializer for bytes. Formats which are not human-readable (e.g. CBOR)
+    /// serialize the raw bytes instead.
     pub fn bytes_serialize_hex<S, T>(x: &T, s: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
         T: AsRef<[u8]>,
     {
+        if !s.is_human_readable() {
+            return s.serialize_bytes(x.as_ref());
+        }
         let hex_str = &x.as_ref().encode_hex_upper::<String>();
         s.serialize_str(hex_str)
     }
 
-    /// A serde hex deserializer for bytes
+    /// A serde hex deserializer for bytes. Formats which are not human-readable (e.g. CBOR)
+    /// deserialize the raw bytes instead.
     pub fn bytes_deserialize_hex<'de, D, T>(deserializer: D) -> Result<T, D::Error>
     where
         D: serde::Deserializer<'de>,
-        T: AsRef<[u8]> + FromHex,
+        T: AsRef<[u8]> + FromHex + TryFrom<Vec<u8>>,
         <T as FromHex>::Error: core::fmt::Display,
     {
+        if !deserializer.is_human_readable() {
+            let buf = <Vec<u8> as serde_bytes::Deserialize>::deserialize(deserializer)?;
+            let len = buf.len();
+            return T::try_from(buf).map_err(|_| {
+                serde::de::Error::custom(format!("Unexpected number of bytes: {len}"))
+            });
+        }
         let hex_str = String::deserialize(deserializer)?;
         T::from_hex(hex_str).map_err(serde::de::Error::custom)
     }
 
+    /// A byte array which is (de)serialized as a hex string
+    #[derive(serde::Serialize, serde::Deserialize)]
+    struct HexBytes(
+        #[serde(serialize_with = "bytes_serialize_hex")]
+        #[serde(deserialize_with = "bytes_deserialize_hex")]
+        Vec<u8>,
+    );
+
+    /// A serde hex serializer for optional bytes
+    pub fn optional_bytes_serialize_hex<S>(x: &Option<Vec<u8>>, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::Serialize;
+        x.as_ref().map(|bytes| HexBytes(bytes.clone())).serialize(s)
+    }
+
+    /// A serde hex deserializer for optional bytes
+    pub fn optional_bytes_deserialize_hex<'de, D>(
+        deserializer: D,
+    ) -> Result<Option<Vec<u8>>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Ok(Option::<HexBytes>::deserialize(deserializer)?.map(|bytes| bytes.0))
+    }
+
+    /// A serde hex serializer for a list of byte arrays
+    pub fn bytes_vec_serialize_hex<S>(x: &[Vec<u8>], s: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        s.collect_seq(x.iter().map(|bytes| HexBytes(bytes.clone())))
+    }
+
+    /// A serde hex deserializer for a list of byte arrays
+    pub fn bytes_vec_deserialize_hex<'de, D>(deserializer: D) -> Result<Vec<Vec<u8>>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Ok(Vec::<HexBytes>::deserialize(deserializer)?
+            .into_iter()
+            .map(|bytes| bytes.0)
+            .collect())
+    }
+
     /// Serialize a digest
     pub fn azks_value_hex_serialize<S>(x: &AzksValue, s: S) -> Result<S::Ok, S::Error>
     where
@@ -156,6 +214,18 @@ macro_rules! test_config_sync {
             fn [<$x _ experimental_config>]() {
                 $x::<$crate::ExperimentalConfiguration<$crate::ExampleLabel>>()
             }
+
+            #[cfg(feature = "sha3_256")]
+            #[test]
+            fn [<$x _ sha3_256_config>]() {
+                $x::<$crate::Sha3Configuration<$crate::ExampleLabel>>()
+            }
+
+            #[cfg(feature = "domain_separated")]
+            #[test]
+            fn [<$x _ domain_separated_config>]() {
+                $x::<$crate::DomainSeparatedConfiguration<$crate::ExampleLabel>>()
+            }
         }
     };
 }
@@ -192,4 +262,25 @@ mod tests {
             get_marker_versions(6, 12, 127)
         );
     }
+
+    #[test]
+    fn test_value_padding() {
+        for len in 0..40 {
+            let value = crate::AkdValue::Bytes(vec![1u8; len]);
+            let padded = value.pad(16);
+            assert_eq!((len / 16 + 1) * 16, padded.len());
+            assert_eq!(Some(value), padded.unpad(16));
+        }
+
+        // Values which are not padded to a multiple of the bucket size
+        for bytes in [
+            vec![],
+            vec![1u8; 16],
+            vec![0u8; 16],
+            [vec![1u8; 15], vec![0x80], vec![0u8; 16]].concat(),
+            [vec![1u8; 15], vec![0x80]].concat()[1..].to_vec(),
+        ] {
+            assert_eq!(None, crate::AkdValue::Bytes(bytes).unpad(16));
+        }
+    }
 }
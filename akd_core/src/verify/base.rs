@@ -7,10 +7,10 @@
 
 //! Base functionality for verification operations (membership, non-membership, etc)
 
-use super::VerificationError;
+use super::{HashCheck, VerificationError};
 
 use crate::configuration::Configuration;
-use crate::ecvrf::{Proof, VrfError};
+use crate::ecvrf::{VrfError, VrfScheme};
 use crate::hash::Digest;
 use crate::{
     AkdLabel, AkdValue, AzksValue, Direction, MembershipProof, NodeLabel, NonMembershipProof,
@@ -18,10 +18,53 @@ use crate::{
 };
 
 #[cfg(feature = "nostd")]
-use alloc::format;
+use alloc::collections::BTreeMap;
 #[cfg(feature = "nostd")]
 use alloc::string::ToString;
-use core::convert::TryFrom;
+#[cfg(feature = "nostd")]
+use alloc::vec::Vec;
+#[cfg(not(feature = "nostd"))]
+use std::collections::BTreeMap;
+
+/// The hashes of the parent nodes computed while verifying membership proofs. When memoized
+/// (see [NodeHashes::memoized]), they are keyed by the children that they were computed from,
+/// so that the nodes close to the root which are shared by the paths of proofs verified
+/// together are only hashed once. Otherwise (by default), each hash is computed directly and
+/// nothing is allocated.
+#[derive(Debug, Default)]
+pub(crate) struct NodeHashes(
+    Option<BTreeMap<(NodeLabel, AzksValue, NodeLabel, AzksValue), AzksValue>>,
+);
+
+impl NodeHashes {
+    /// Memoizes the computed hashes across the proofs verified with these hashes
+    pub(crate) fn memoized() -> Self {
+        Self(Some(BTreeMap::new()))
+    }
+
+    fn parent_hash<TC: Configuration>(
+        &mut self,
+        left_val: AzksValue,
+        left_label: NodeLabel,
+        right_val: AzksValue,
+        right_label: NodeLabel,
+    ) -> AzksValue {
+        let compute = || {
+            TC::compute_parent_hash_from_child_labels(
+                &left_val,
+                left_label,
+                &right_val,
+                right_label,
+            )
+        };
+        match &mut self.0 {
+            Some(hashes) => *hashes
+                .entry((left_label, left_val, right_label, right_val))
+                .or_insert_with(compute),
+            None => compute(),
+        }
+    }
+}
 
 /// Verifies a membership proof with respect to a root hash
 ///
@@ -32,12 +75,13 @@ pub fn verify_membership_for_tests_only<TC: Configuration>(
     root_hash: Digest,
     proof: &MembershipProof,
 ) -> Result<(), VerificationError> {
-    verify_membership::<TC>(root_hash, proof)
+    verify_membership::<TC>(root_hash, proof, &mut NodeHashes::default())
 }
 
 pub(crate) fn verify_membership<TC: Configuration>(
     root_hash: Digest,
     proof: &MembershipProof,
+    hashes: &mut NodeHashes,
 ) -> Result<(), VerificationError> {
     let mut curr_val = proof.hash_val;
     let mut curr_label = proof.label;
@@ -45,31 +89,20 @@ pub(crate) fn verify_membership<TC: Configuration>(
     for sibling_proof in proof.sibling_proofs.iter().rev() {
         let sibling = sibling_proof.siblings[0];
         let (left_val, left_label, right_val, right_label) = match sibling_proof.direction {
-            Direction::Left => (
-                curr_val,
-                curr_label.value::<TC>(),
-                sibling.value,
-                sibling.label.value::<TC>(),
-            ),
-            Direction::Right => (
-                sibling.value,
-                sibling.label.value::<TC>(),
-                curr_val,
-                curr_label.value::<TC>(),
-            ),
+            Direction::Left => (curr_val, curr_label, sibling.value, sibling.label),
+            Direction::Right => (sibling.value, sibling.label, curr_val, curr_label),
         };
-        curr_val =
-            TC::compute_parent_hash_from_children(&left_val, &left_label, &right_val, &right_label);
+        curr_val = hashes.parent_hash::<TC>(left_val, left_label, right_val, right_label);
         curr_label = sibling_proof.label;
     }
 
     if TC::compute_root_hash_from_val(&curr_val) == root_hash {
         Ok(())
     } else {
-        Err(VerificationError::MembershipProof(format!(
-            "Membership proof for label {:?} did not verify",
-            proof.label
-        )))
+        Err(VerificationError::HashMismatch {
+            label: proof.label,
+            check: HashCheck::RootHash,
+        })
     }
 }
 
@@ -82,12 +115,13 @@ pub fn verify_nonmembership_for_tests_only<TC: Configuration>(
     root_hash: Digest,
     proof: &NonMembershipProof,
 ) -> Result<(), VerificationError> {
-    verify_nonmembership::<TC>(root_hash, proof)
+    verify_nonmembership::<TC>(root_hash, proof, &mut NodeHashes::default())
 }
 
 pub(crate) fn verify_nonmembership<TC: Configuration>(
     root_hash: Digest,
     proof: &NonMembershipProof,
+    hashes: &mut NodeHashes,
 ) -> Result<(), VerificationError> {
     // Verify that the proof's label is not equal to either of the children's labels
     if proof.label == proof.longest_prefix_children[0].label
@@ -120,20 +154,21 @@ pub(crate) fn verify_nonmembership<TC: Configuration>(
         ));
     }
 
-    let lcp_hash = TC::compute_parent_hash_from_children(
+    let lcp_hash = TC::compute_parent_hash_from_child_labels(
         &proof.longest_prefix_children[0].value,
-        &proof.longest_prefix_children[0].label.value::<TC>(),
+        proof.longest_prefix_children[0].label,
         &proof.longest_prefix_children[1].value,
-        &proof.longest_prefix_children[1].label.value::<TC>(),
+        proof.longest_prefix_children[1].label,
     );
     if lcp_children != proof.longest_prefix_membership_proof.label
         || lcp_hash != proof.longest_prefix_membership_proof.hash_val
     {
-        return Err(VerificationError::NonMembershipProof(
-            "lcp_hash != longest_prefix_hash".to_string(),
-        ));
+        return Err(VerificationError::HashMismatch {
+            label: proof.longest_prefix,
+            check: HashCheck::LongestPrefix,
+        });
     }
-    verify_membership::<TC>(root_hash, &proof.longest_prefix_membership_proof)?;
+    verify_membership::<TC>(root_hash, &proof.longest_prefix_membership_proof, hashes)?;
 
     Ok(())
 }
@@ -141,31 +176,30 @@ pub(crate) fn verify_nonmembership<TC: Configuration>(
 /// This function is called to verify that a given [NodeLabel] is indeed
 /// the VRF for a given version (fresh or stale) for a [AkdLabel].
 /// Hence, it also takes as input the server's public key.
-fn verify_label<TC: Configuration>(
+/// Returns the output of the VRF.
+pub(crate) fn verify_label<TC: Configuration>(
     vrf_public_key: &[u8],
     akd_label: &AkdLabel,
     freshness: VersionFreshness,
     version: u64,
     vrf_proof: &[u8],
     node_label: NodeLabel,
-) -> Result<(), VerificationError> {
-    let vrf_pk = crate::ecvrf::VRFPublicKey::try_from(vrf_public_key)?;
+) -> Result<Vec<u8>, VerificationError> {
     let hashed_label = TC::get_hash_from_label_input(akd_label, freshness, version);
 
     // VRF proof verification (returns VRF hash output)
-    let proof = Proof::try_from(vrf_proof)?;
-    vrf_pk.verify(&proof, &hashed_label)?;
-    let output: crate::ecvrf::Output = (&proof).into();
+    let output = TC::Vrf::verify(vrf_public_key, vrf_proof, &hashed_label)?;
 
-    if NodeLabel::new(output.to_truncated_bytes(), 256) != node_label {
+    if crate::ecvrf::node_label_from_output(&output) != node_label {
         return Err(VerificationError::Vrf(VrfError::Verification(
             "Expected first 32 bytes of the proof output did NOT match the supplied label"
                 .to_string(),
         )));
     }
-    Ok(())
+    Ok(output)
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn verify_existence<TC: Configuration>(
     vrf_public_key: &[u8],
     root_hash: Digest,
@@ -174,6 +208,7 @@ pub(crate) fn verify_existence<TC: Configuration>(
     version: u64,
     vrf_proof: &[u8],
     membership_proof: &MembershipProof,
+    hashes: &mut NodeHashes,
 ) -> Result<(), VerificationError> {
     verify_label::<TC>(
         vrf_public_key,
@@ -183,7 +218,7 @@ pub(crate) fn verify_existence<TC: Configuration>(
         vrf_proof,
         membership_proof.label,
     )?;
-    verify_membership::<TC>(root_hash, membership_proof)?;
+    verify_membership::<TC>(root_hash, membership_proof, hashes)?;
     Ok(())
 }
 
@@ -199,12 +234,14 @@ pub(crate) fn verify_existence_with_val<TC: Configuration>(
     version: u64,
     vrf_proof: &[u8],
     membership_proof: &MembershipProof,
+    hashes: &mut NodeHashes,
 ) -> Result<(), VerificationError> {
     if TC::hash_leaf_with_value(akd_value, epoch, commitment_nonce).0 != membership_proof.hash_val.0
     {
-        return Err(VerificationError::MembershipProof(
-            "Hash of plaintext value did not match existence proof hash".to_string(),
-        ));
+        return Err(VerificationError::HashMismatch {
+            label: membership_proof.label,
+            check: HashCheck::LeafValue,
+        });
     }
     verify_existence::<TC>(
         vrf_public_key,
@@ -214,6 +251,7 @@ pub(crate) fn verify_existence_with_val<TC: Configuration>(
         version,
         vrf_proof,
         membership_proof,
+        hashes,
     )?;
 
     Ok(())
@@ -230,11 +268,13 @@ pub(crate) fn verify_existence_with_commitment<TC: Configuration>(
     version: u64,
     vrf_proof: &[u8],
     membership_proof: &MembershipProof,
+    hashes: &mut NodeHashes,
 ) -> Result<(), VerificationError> {
     if TC::hash_leaf_with_commitment(commitment, epoch).0 != membership_proof.hash_val.0 {
-        return Err(VerificationError::MembershipProof(
-            "Hash of plaintext value did not match existence proof hash".to_string(),
-        ));
+        return Err(VerificationError::HashMismatch {
+            label: membership_proof.label,
+            check: HashCheck::LeafValue,
+        });
     }
     verify_existence::<TC>(
         vrf_public_key,
@@ -244,11 +284,13 @@ pub(crate) fn verify_existence_with_commitment<TC: Configuration>(
         version,
         vrf_proof,
         membership_proof,
+        hashes,
     )?;
 
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn verify_nonexistence<TC: Configuration>(
     vrf_public_key: &[u8],
     root_hash: Digest,
@@ -257,6 +299,7 @@ pub(crate) fn verify_nonexistence<TC: Configuration>(
     version: u64,
     vrf_proof: &[u8],
     nonmembership_proof: &NonMembershipProof,
+    hashes: &mut NodeHashes,
 ) -> Result<(), VerificationError> {
     verify_label::<TC>(
         vrf_public_key,
@@ -266,6 +309,15 @@ pub(crate) fn verify_nonexistence<TC: Configuration>(
         vrf_proof,
         nonmembership_proof.label,
     )?;
-    verify_nonmembership::<TC>(root_hash, nonmembership_proof)?;
+    verify_nonmembership::<TC>(root_hash, nonmembership_proof, hashes)?;
     Ok(())
 }
+
+/// Removes the padding of a value whose commitment was verified, if the configuration pads values
+/// (see [Configuration::value_padding_bucket_size]). Returns [None] if the value is not padded.
+pub(crate) fn unpad_value<TC: Configuration>(value: AkdValue) -> Option<AkdValue> {
+    match TC::value_padding_bucket_size() {
+        Some(bucket_size) => value.unpad(bucket_size),
+        None => Some(value),
+    }
+}
@@ -0,0 +1,157 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Verification of proofs over epoch-level data
+
+use super::base::{verify_membership, NodeHashes};
+use super::VerificationError;
+
+use crate::bundle::EpochBundle;
+use crate::configuration::Configuration;
+use crate::hash::Digest;
+use crate::signature;
+use crate::{EpochMetadata, EpochMetadataProof, EpochRootChain, RootChainAnchor};
+
+#[cfg(feature = "nostd")]
+use alloc::format;
+#[cfg(feature = "nostd")]
+use alloc::string::ToString;
+
+/// Verifies that the [EpochMetadata] contained in the proof was bound into the tree
+/// with the provided root hash. The root hash should correspond to the epoch
+/// described by the metadata, or any later epoch.
+pub fn verify_epoch_metadata<TC: Configuration>(
+    root_hash: Digest,
+    proof: &EpochMetadataProof,
+) -> Result<(), VerificationError> {
+    let metadata = &proof.metadata;
+    let membership_proof = &proof.membership_proof;
+
+    if !metadata.bound {
+        return Err(VerificationError::EpochMetadata(format!(
+            "Metadata for epoch {} was not bound into the tree",
+            metadata.epoch
+        )));
+    }
+
+    if membership_proof.label != EpochMetadata::node_label::<TC>(metadata.epoch) {
+        return Err(VerificationError::EpochMetadata(format!(
+            "Membership proof label does not correspond to the metadata for epoch {}",
+            metadata.epoch
+        )));
+    }
+
+    if TC::hash_leaf_with_commitment(
+        metadata.commitment::<TC>(&proof.commitment_nonce),
+        metadata.epoch,
+    )
+    .0 != membership_proof.hash_val.0
+    {
+        return Err(VerificationError::EpochMetadata(
+            "Hash of epoch metadata did not match the membership proof hash".to_string(),
+        ));
+    }
+
+    verify_membership::<TC>(root_hash, membership_proof, &mut NodeHashes::default())
+}
+
+/// Verifies a signature produced by a [crate::signature::EpochSigner] over the root hash
+/// of the given epoch, using the signer's Ed25519 public key (see
+/// [crate::signature::verify_epoch_signature], which does not require the `verify` feature)
+pub fn verify_epoch_signature(
+    public_key: &[u8],
+    epoch: u64,
+    root_hash: Digest,
+    signature: &[u8],
+) -> Result<(), VerificationError> {
+    Ok(signature::verify_epoch_signature(
+        public_key, epoch, root_hash, signature,
+    )?)
+}
+
+/// Verifies an [EpochBundle] which was produced by a [crate::signature::EpochSigner], using the
+/// signer's Ed25519 public key. The bundle must also have been issued for the directory with the
+/// given VRF public key. Once verified, the root hash of the bundle can be compared against the
+/// root hash of the same epoch received from other channels.
+///
+/// See [crate::signature::verify_epoch_bundle], which does not require the `verify` feature.
+pub fn verify_epoch_bundle<TC: Configuration>(
+    public_key: &[u8],
+    vrf_public_key: &[u8],
+    bundle: &EpochBundle,
+) -> Result<(), VerificationError> {
+    Ok(signature::verify_epoch_bundle::<TC>(
+        public_key,
+        vrf_public_key,
+        bundle,
+    )?)
+}
+
+/// Verifies that the `next` [EpochBundle] directly follows the `previous` one, i.e. that it is
+/// for the next epoch and that it links to the root hash of the previous bundle. This does not
+/// verify the signatures of the bundles, which should be checked with [verify_epoch_bundle].
+pub fn verify_epoch_bundle_link(
+    previous: &EpochBundle,
+    next: &EpochBundle,
+) -> Result<(), VerificationError> {
+    Ok(signature::verify_epoch_bundle_link(previous, next)?)
+}
+
+/// Verifies that the root hashes of an [EpochRootChain] hash to its chain hash, and that the
+/// chain is rooted in the chain hash which the client trusts (the `anchor`). The chain hash
+/// of the chain itself is provided by the server, and so only the anchor binds the root
+/// hashes to a history the client already holds ([RootChainAnchor::Previous]), or to a
+/// chain hash obtained independently of the server ([RootChainAnchor::Last]).
+pub fn verify_epoch_root_chain<TC: Configuration>(
+    chain: &EpochRootChain,
+    anchor: RootChainAnchor,
+) -> Result<(), VerificationError> {
+    if chain.root_hashes.is_empty() {
+        return Err(VerificationError::RootChain(
+            "The chain does not contain any root hash".to_string(),
+        ));
+    }
+    let Some(end_epoch) = chain
+        .start_epoch
+        .checked_add(chain.root_hashes.len() as u64 - 1)
+    else {
+        return Err(VerificationError::RootChain(format!(
+            "The range of {} epochs starting at epoch {} overflows",
+            chain.root_hashes.len(),
+            chain.start_epoch
+        )));
+    };
+    match anchor {
+        RootChainAnchor::Previous(previous_chain_hash) => {
+            if previous_chain_hash != chain.previous_chain_hash {
+                return Err(VerificationError::RootChain(format!(
+                    "The chain does not extend the trusted chain hash of epoch {}",
+                    chain.start_epoch.saturating_sub(1)
+                )));
+            }
+        }
+        RootChainAnchor::Last(chain_hash) => {
+            if chain_hash != chain.chain_hash {
+                return Err(VerificationError::RootChain(format!(
+                    "The chain does not end with the trusted chain hash of epoch {end_epoch}"
+                )));
+            }
+        }
+    }
+
+    let chain_hash = chain
+        .epochs()
+        .fold(chain.previous_chain_hash, |previous, (epoch, root_hash)| {
+            EpochRootChain::link::<TC>(&previous, epoch, &root_hash)
+        });
+    if chain_hash != chain.chain_hash {
+        return Err(VerificationError::RootChain(
+            "The root hashes do not hash to the chain hash".to_string(),
+        ));
+    }
+    Ok(())
+}
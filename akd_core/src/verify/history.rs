@@ -8,20 +8,29 @@
 //! Verification of key history proofs
 
 use super::base::{
-    verify_existence, verify_existence_with_commitment, verify_existence_with_val,
-    verify_nonexistence,
+    unpad_value, verify_existence, verify_existence_with_commitment, verify_existence_with_val,
+    verify_nonexistence, NodeHashes,
 };
-use super::VerificationError;
+use super::epoch::verify_epoch_root_chain;
+use super::{OrderingViolation, ProofShapeError, VerificationError};
 
 use crate::configuration::Configuration;
 use crate::hash::Digest;
-use crate::{AkdLabel, HistoryProof, UpdateProof, VerifyResult, VersionFreshness};
+use crate::{
+    AkdLabel, AkdValue, EpochRootChain, HistoryMetadata, HistoryProof, RootChainAnchor,
+    UpdateProof, VerifyResult, VersionFreshness,
+};
+#[cfg(feature = "nostd")]
+use alloc::collections::BTreeMap;
 #[cfg(feature = "nostd")]
 use alloc::format;
 #[cfg(feature = "nostd")]
 use alloc::string::ToString;
 #[cfg(feature = "nostd")]
 use alloc::vec::Vec;
+use core::ops::RangeInclusive;
+#[cfg(not(feature = "nostd"))]
+use std::collections::BTreeMap;
 
 /// The parameters that dictate how much of the history proof for the server to
 /// return to the consumer (either a complete history, or some limited form).
@@ -33,6 +42,21 @@ pub enum HistoryParams {
     MostRecent(usize),
 }
 
+impl HistoryParams {
+    /// The parameters of the history which a directory retaining at most `max_versions` versions
+    /// per label serves for these parameters, with which its proofs are verified. A history
+    /// truncated by the directory then verifies as its `max_versions` most recent updates, and a
+    /// history with fewer versions must still be complete, so that a directory cannot drop more
+    /// of the history than its policy allows. The truncation is reported by the
+    /// [HistoryMetadata] of [key_history_verify_with_metadata], which is not `complete`.
+    pub fn capped_to(self, max_versions: usize) -> Self {
+        match self {
+            Self::Complete => Self::MostRecent(max_versions),
+            Self::MostRecent(n) => Self::MostRecent(n.min(max_versions)),
+        }
+    }
+}
+
 impl Default for HistoryParams {
     /// By default, we return a complete history
     fn default() -> Self {
@@ -42,22 +66,51 @@ impl Default for HistoryParams {
 
 /// Parameters for customizing how history proof verification proceeds
 #[derive(Copy, Clone)]
-pub enum HistoryVerificationParams {
+pub enum HistoryVerificationParams<'a> {
     /// No customization to the verification procedure
     Default {
         /// the HistoryParams that was used to generate the history proof
         history_params: HistoryParams,
     },
-    /// Allows for the encountering of missing (tombstoned) values
+    /// Allows for the encountering of missing values ([AkdValue::Tombstone]s)
     /// instead of attempting to check if their hash matches the leaf node
     /// hash
     AllowMissingValues {
         /// the HistoryParams that was used to generate the history proof
         history_params: HistoryParams,
     },
+    /// Checks the history against the root hashes of past epochs which the client has already
+    /// verified (e.g. with a [super::RootTracker] or an audit), to detect a server which
+    /// presents the client with a view inconsistent with those it was served before.
+    ///
+    /// The pinned epochs are tied to the epoch of the proof with a [EpochRootChain] of the root
+    /// hashes from (at most) the earliest pinned epoch up to the epoch of the proof, as served
+    /// by the directory, which must be rooted in an `anchor` trusted independently of the
+    /// server (see [super::verify_epoch_root_chain]), e.g. the chain hash published by an
+    /// auditor which verified the append-only proofs between the epochs of the chain. The
+    /// proof is rejected if the chain does not verify against the anchor, if it does not end
+    /// with the root hash of the epoch of the proof, or if it does not contain the pinned root
+    /// hash of each pinned epoch (and so in particular if a pinned epoch follows the epoch of
+    /// the proof). The pinned roots and the root hash of the proof thus belong to one audited
+    /// append-only history, and as the leaf hashes bind the epoch of their insertion, each
+    /// update of the history up to a pinned epoch was in the tree of that pinned epoch.
+    PinnedRoots {
+        /// the HistoryParams that was used to generate the history proof
+        history_params: HistoryParams,
+        /// Whether missing values are allowed, as with
+        /// [HistoryVerificationParams::AllowMissingValues]
+        allow_missing_values: bool,
+        /// The verified root hash of each pinned epoch
+        pinned_roots: &'a BTreeMap<u64, Digest>,
+        /// The root hashes from the earliest pinned epoch (or an earlier one) up to the epoch
+        /// of the proof
+        root_chain: &'a EpochRootChain,
+        /// The chain hash, trusted independently of the server, in which `root_chain` is rooted
+        anchor: RootChainAnchor,
+    },
 }
 
-impl Default for HistoryVerificationParams {
+impl Default for HistoryVerificationParams<'_> {
     fn default() -> Self {
         Self::Default {
             history_params: HistoryParams::default(),
@@ -65,9 +118,72 @@ impl Default for HistoryVerificationParams {
     }
 }
 
+impl HistoryVerificationParams<'_> {
+    /// The HistoryParams that was used to generate the history proof
+    pub fn history_params(&self) -> HistoryParams {
+        match self {
+            Self::Default { history_params }
+            | Self::AllowMissingValues { history_params }
+            | Self::PinnedRoots { history_params, .. } => *history_params,
+        }
+    }
+
+    fn allows_missing_values(&self) -> bool {
+        match self {
+            Self::Default { .. } => false,
+            Self::AllowMissingValues { .. } => true,
+            Self::PinnedRoots {
+                allow_missing_values,
+                ..
+            } => *allow_missing_values,
+        }
+    }
+}
+
+/// Checks the epoch and root hash of a history proof against the pinned roots, through the
+/// anchored root chain (see [HistoryVerificationParams::PinnedRoots])
+fn verify_pinned_roots<TC: Configuration>(
+    pinned_roots: &BTreeMap<u64, Digest>,
+    root_chain: &EpochRootChain,
+    anchor: RootChainAnchor,
+    root_hash: Digest,
+    current_epoch: u64,
+) -> Result<(), VerificationError> {
+    verify_epoch_root_chain::<TC>(root_chain, anchor)?;
+    if root_chain.end_epoch() != Some(current_epoch) {
+        return Err(VerificationError::HistoryProof(format!(
+            "The root chain does not end at epoch {current_epoch} of the proof"
+        )));
+    }
+    if root_chain.root_hashes.last() != Some(&root_hash) {
+        return Err(VerificationError::HistoryProof(format!(
+            "The root hash of epoch {current_epoch} differs from the root hash of the root chain"
+        )));
+    }
+    for (&epoch, pinned) in pinned_roots {
+        let chained = epoch
+            .checked_sub(root_chain.start_epoch)
+            .and_then(|offset| root_chain.root_hashes.get(offset as usize));
+        match chained {
+            Some(chained) if chained == pinned => {}
+            Some(_) => {
+                return Err(VerificationError::HistoryProof(format!(
+                    "The root hash of epoch {epoch} differs from its pinned root hash"
+                )))
+            }
+            None => {
+                return Err(VerificationError::HistoryProof(format!(
+                    "The pinned epoch {epoch} is not within epochs {} to {current_epoch} of the root chain",
+                    root_chain.start_epoch
+                )))
+            }
+        }
+    }
+    Ok(())
+}
+
 fn verify_with_history_params(
     current_epoch: u64,
-    akd_label: &AkdLabel,
     proof: &HistoryProof,
     params: HistoryParams,
 ) -> Result<(Vec<u64>, Vec<u64>), VerificationError> {
@@ -75,9 +191,7 @@ fn verify_with_history_params(
 
     // Make sure the update proofs are non-empty
     if num_proofs == 0 {
-        return Err(VerificationError::HistoryProof(format!(
-            "No update proofs included in the proof of user {akd_label:?} at epoch {current_epoch:?}!"
-        )));
+        return Err(ProofShapeError::NoUpdateProofs.into());
     }
 
     // Check that the sent proofs are for a contiguous sequence of decreasing versions
@@ -86,14 +200,12 @@ fn verify_with_history_params(
         let prev_version = proof.update_proofs[count - 1].version;
         let curr_version = proof.update_proofs[count].version;
         if curr_version + 1 != prev_version {
-            return Err(VerificationError::HistoryProof(format!(
-                "Update proofs should be ordered consecutively and in decreasing order.
-                Error detected with version {} at index {}, followed by version {} at index {}",
-                prev_version,
-                count - 1,
-                curr_version,
-                count
-            )));
+            return Err(OrderingViolation::NonConsecutiveVersions {
+                index: count,
+                version: curr_version,
+                previous_version: prev_version,
+            }
+            .into());
         }
     }
 
@@ -109,42 +221,37 @@ fn verify_with_history_params(
     });
 
     if start_version == 0 {
-        return Err(VerificationError::HistoryProof(
-            "Computed start version for the key history should be non-zero".to_string(),
-        ));
+        return Err(OrderingViolation::ZeroVersion.into());
     }
 
     if end_version > current_epoch {
-        return Err(VerificationError::HistoryProof(
-            "Computed end version for the key history should not exceed current epoch".to_string(),
-        ));
+        return Err(OrderingViolation::VersionExceedsEpoch {
+            version: end_version,
+            epoch: current_epoch,
+        }
+        .into());
     }
 
     match params {
         HistoryParams::Complete => {
             // Make sure the start version is 1
             if start_version != 1 {
-                return Err(VerificationError::HistoryProof(format!(
-                    "Expected start version to be 1 given that it is a complete history, but got start_version = {}",
-                    start_version
-                )));
+                return Err(ProofShapeError::IncompleteHistory { start_version }.into());
             }
         }
         HistoryParams::MostRecent(recency) => {
             use core::cmp::Ordering;
             match num_proofs.cmp(&recency) {
                 Ordering::Greater => {
-                    return Err(VerificationError::HistoryProof(format!(
-                        "Expected at most {} update proofs, but got {} of them",
-                        recency, num_proofs
-                    )))
+                    return Err(ProofShapeError::TooManyUpdateProofs {
+                        expected: recency,
+                        actual: num_proofs,
+                    }
+                    .into())
                 }
                 Ordering::Less => {
                     if start_version != 1 {
-                        return Err(VerificationError::HistoryProof(format!(
-                            "Expected at most {} update proofs, but got {} of them",
-                            recency, num_proofs
-                        )));
+                        return Err(ProofShapeError::IncompleteHistory { start_version }.into());
                     }
                 }
                 Ordering::Equal => {}
@@ -157,43 +264,48 @@ fn verify_with_history_params(
 
     // Perform checks for expected number of past marker proofs
     if past_marker_versions.len() != proof.past_marker_vrf_proofs.len() {
-        return Err(VerificationError::HistoryProof(format!(
-            "Expected {} past marker proofs, but got {}",
-            past_marker_versions.len(),
-            proof.past_marker_vrf_proofs.len()
-        )));
+        return Err(ProofShapeError::UnexpectedLength {
+            field: "past_marker_vrf_proofs",
+            expected: past_marker_versions.len(),
+            actual: proof.past_marker_vrf_proofs.len(),
+        }
+        .into());
     }
     if proof.past_marker_vrf_proofs.len() != proof.existence_of_past_marker_proofs.len() {
-        return Err(VerificationError::HistoryProof(format!(
-            "Expected equal number of past marker proofs, but got ({}, {})",
-            proof.past_marker_vrf_proofs.len(),
-            proof.existence_of_past_marker_proofs.len()
-        )));
+        return Err(ProofShapeError::UnexpectedLength {
+            field: "existence_of_past_marker_proofs",
+            expected: proof.past_marker_vrf_proofs.len(),
+            actual: proof.existence_of_past_marker_proofs.len(),
+        }
+        .into());
     }
 
     // Perform checks for expected number of future marker proofs
     if future_marker_versions.len() != proof.future_marker_vrf_proofs.len() {
-        return Err(VerificationError::HistoryProof(format!(
-            "Expected {} future marker proofs, but got {}",
-            future_marker_versions.len(),
-            proof.future_marker_vrf_proofs.len()
-        )));
+        return Err(ProofShapeError::UnexpectedLength {
+            field: "future_marker_vrf_proofs",
+            expected: future_marker_versions.len(),
+            actual: proof.future_marker_vrf_proofs.len(),
+        }
+        .into());
     }
     if proof.future_marker_vrf_proofs.len() != proof.non_existence_of_future_marker_proofs.len() {
-        return Err(VerificationError::HistoryProof(format!(
-            "Expected equal number of future marker proofs, but got ({}, {})",
-            proof.future_marker_vrf_proofs.len(),
-            proof.non_existence_of_future_marker_proofs.len()
-        )));
+        return Err(ProofShapeError::UnexpectedLength {
+            field: "non_existence_of_future_marker_proofs",
+            expected: proof.future_marker_vrf_proofs.len(),
+            actual: proof.non_existence_of_future_marker_proofs.len(),
+        }
+        .into());
     }
 
     Ok((past_marker_versions, future_marker_versions))
 }
 
 /// Verifies a key history proof, given the corresponding sequence of hashes.
-/// Returns a vector of whether the validity of a hash could be verified.
-/// When false, the value <=> hash validity at the position could not be
-/// verified because the value has been removed ("tombstoned") from the storage layer.
+/// Returns the verified values, from the most recent to the oldest. A value which has been
+/// removed from the storage layer is returned as an [AkdValue::Tombstone], whose commitment
+/// could not be verified (which is only accepted with
+/// [HistoryVerificationParams::AllowMissingValues]).
 pub fn key_history_verify<TC: Configuration>(
     vrf_public_key: &[u8],
     root_hash: Digest,
@@ -202,14 +314,43 @@ pub fn key_history_verify<TC: Configuration>(
     proof: HistoryProof,
     verification_params: HistoryVerificationParams,
 ) -> Result<Vec<VerifyResult>, VerificationError> {
+    key_history_verify_with_metadata::<TC>(
+        vrf_public_key,
+        root_hash,
+        current_epoch,
+        akd_label,
+        proof,
+        verification_params,
+    )
+    .map(|(results, _)| results)
+}
+
+/// Same as [key_history_verify], but additionally returns the [HistoryMetadata] established by
+/// the proof: which marker versions were proven to exist or not, and which versions were
+/// tombstoned. This allows a client to render a timeline of the history of the label which
+/// accounts for the versions the proof does not include.
+pub fn key_history_verify_with_metadata<TC: Configuration>(
+    vrf_public_key: &[u8],
+    root_hash: Digest,
+    current_epoch: u64,
+    akd_label: AkdLabel,
+    proof: HistoryProof,
+    verification_params: HistoryVerificationParams,
+) -> Result<(Vec<VerifyResult>, HistoryMetadata), VerificationError> {
     let mut results = Vec::new();
+    let mut hashes = NodeHashes::default();
 
-    let params: HistoryParams = match verification_params {
-        HistoryVerificationParams::Default { history_params } => history_params,
-        HistoryVerificationParams::AllowMissingValues { history_params } => history_params,
-    };
     let (past_marker_versions, future_marker_versions) =
-        verify_with_history_params(current_epoch, &akd_label, &proof, params)?;
+        verify_with_history_params(current_epoch, &proof, verification_params.history_params())?;
+    if let HistoryVerificationParams::PinnedRoots {
+        pinned_roots,
+        root_chain,
+        anchor,
+        ..
+    } = verification_params
+    {
+        verify_pinned_roots::<TC>(pinned_roots, root_chain, anchor, root_hash, current_epoch)?;
+    }
 
     // Verify all individual update proofs
     let mut maybe_previous_update_epoch = None;
@@ -217,11 +358,11 @@ pub fn key_history_verify<TC: Configuration>(
         if let Some(previous_update_epoch) = maybe_previous_update_epoch {
             // Make sure this this epoch is more than the previous epoch you checked
             if update_proof.epoch > previous_update_epoch {
-                return Err(VerificationError::HistoryProof(format!(
-                    "Version numbers for updates are decreasing, but their corresponding
-                    epochs are not decreasing: epoch = {}, previous epoch = {}",
-                    update_proof.epoch, previous_update_epoch
-                )));
+                return Err(OrderingViolation::NonDecreasingEpochs {
+                    epoch: update_proof.epoch,
+                    previous_epoch: previous_update_epoch,
+                }
+                .into());
             }
         }
         maybe_previous_update_epoch = Some(update_proof.epoch);
@@ -231,6 +372,7 @@ pub fn key_history_verify<TC: Configuration>(
             update_proof,
             &akd_label,
             verification_params,
+            &mut hashes,
         )?;
         results.push(result);
     }
@@ -244,6 +386,7 @@ pub fn key_history_verify<TC: Configuration>(
             *version,
             &proof.past_marker_vrf_proofs[i],
             &proof.existence_of_past_marker_proofs[i],
+            &mut hashes,
         )?;
     }
 
@@ -257,31 +400,104 @@ pub fn key_history_verify<TC: Configuration>(
             *version,
             &proof.future_marker_vrf_proofs[i],
             &proof.non_existence_of_future_marker_proofs[i],
-        )
-        .map_err(|_| {
-            VerificationError::HistoryProof(format!(
-                "Non-existence of future marker proof of label {akd_label:?} with
-                version {version:?} at epoch {current_epoch:?} does not verify"
-            ))
-        })?;
+            &mut hashes,
+        )?;
+    }
+
+    let metadata = HistoryMetadata {
+        epoch: current_epoch,
+        complete: results.last().is_some_and(|result| result.version == 1),
+        tombstoned_versions: tombstoned_versions(&results),
+        past_marker_versions,
+        future_marker_versions,
+    };
+    Ok((results, metadata))
+}
+
+/// Verifies a key history proof generated from a previously verified version of the label (see
+/// `Directory::key_history_since` in the `akd` crate), so that a client which verified the
+/// history of a label up to the `previous` [VerifyResult] only verifies the updates published
+/// since. The proof includes the previously verified version, whose epoch and value must match
+/// the `previous` result (unless its value has since been removed from the storage layer, in which
+/// case it is a tombstone). Returns the verified values of the newer versions, from the most
+/// recent to the oldest, which is empty if the previously verified version is still the latest.
+pub fn key_history_since_verify<TC: Configuration>(
+    vrf_public_key: &[u8],
+    root_hash: Digest,
+    current_epoch: u64,
+    akd_label: AkdLabel,
+    proof: HistoryProof,
+    previous: &VerifyResult,
+) -> Result<Vec<VerifyResult>, VerificationError> {
+    let oldest = proof
+        .update_proofs
+        .last()
+        .ok_or(ProofShapeError::NoUpdateProofs)?;
+    if oldest.version != previous.version || oldest.epoch != previous.epoch {
+        return Err(VerificationError::HistoryProof(format!(
+            "The oldest update of the proof is version {} at epoch {}, rather than the previously verified version {} at epoch {}",
+            oldest.version, oldest.epoch, previous.version, previous.epoch
+        )));
     }
 
+    // The proof covers every version since the previously verified one
+    let history_params = HistoryParams::MostRecent(proof.update_proofs.len());
+    let mut results = key_history_verify::<TC>(
+        vrf_public_key,
+        root_hash,
+        current_epoch,
+        akd_label,
+        proof,
+        HistoryVerificationParams::AllowMissingValues { history_params },
+    )?;
+
+    let verified = results.pop().ok_or(ProofShapeError::NoUpdateProofs)?;
+    if !verified.value.is_tombstone() && verified.value != previous.value {
+        return Err(VerificationError::HistoryProof(format!(
+            "The value of version {} does not match the previously verified value",
+            previous.version
+        )));
+    }
+    if let Some(result) = results.iter().find(|result| result.value.is_tombstone()) {
+        return Err(VerificationError::HistoryProof(format!(
+            "The value of version {} is a tombstone, which is only accepted for the previously verified version",
+            result.version
+        )));
+    }
     Ok(results)
 }
 
+/// The ranges of consecutive versions of the results (in decreasing order of version) whose
+/// values are tombstones
+fn tombstoned_versions(results: &[VerifyResult]) -> Vec<RangeInclusive<u64>> {
+    let mut ranges: Vec<RangeInclusive<u64>> = Vec::new();
+    for result in results.iter().filter(|result| result.value.is_tombstone()) {
+        match ranges.last_mut() {
+            Some(range) if *range.start() == result.version + 1 => {
+                *range = result.version..=*range.end();
+            }
+            _ => ranges.push(result.version..=result.version),
+        }
+    }
+    ranges
+}
+
 /// Verifies a single update proof
-fn verify_single_update_proof<TC: Configuration>(
+pub(super) fn verify_single_update_proof<TC: Configuration>(
     root_hash: Digest,
     vrf_public_key: &[u8],
     proof: UpdateProof,
     akd_label: &AkdLabel,
     params: HistoryVerificationParams,
+    hashes: &mut NodeHashes,
 ) -> Result<VerifyResult, VerificationError> {
+    let value = unpad_value::<TC>(proof.value.clone()).ok_or_else(|| {
+        VerificationError::HistoryProof("The value is not padded as configured".to_string())
+    })?;
+
     // Verify the VRF and membership proof for the corresponding label for the version being updated to.
-    match (params, &proof.value) {
-        (HistoryVerificationParams::AllowMissingValues { .. }, bytes)
-            if bytes.0 == crate::TOMBSTONE =>
-        {
+    match (&proof.value, params.allows_missing_values()) {
+        (AkdValue::Tombstone, true) => {
             // A tombstone was encountered, we need to just take the
             // hash of the value at "face value" since we don't have
             // the real value available
@@ -293,9 +509,16 @@ fn verify_single_update_proof<TC: Configuration>(
                 proof.version,
                 &proof.existence_vrf_proof,
                 &proof.existence_proof,
+                hashes,
             )?;
         }
-        (_, akd_value) => {
+        (AkdValue::Tombstone, false) => {
+            return Err(VerificationError::HistoryProof(format!(
+                "The value of version {} is a tombstone, which is only accepted with HistoryVerificationParams::AllowMissingValues",
+                proof.version
+            )));
+        }
+        (akd_value @ AkdValue::Bytes(_), _) => {
             // No tombstone so hash the value found, and compare to the existence proof's value
             verify_existence_with_val::<TC>(
                 vrf_public_key,
@@ -308,6 +531,7 @@ fn verify_single_update_proof<TC: Configuration>(
                 proof.version,
                 &proof.existence_vrf_proof,
                 &proof.existence_proof,
+                hashes,
             )?;
         }
     };
@@ -315,7 +539,7 @@ fn verify_single_update_proof<TC: Configuration>(
     let verify_result = VerifyResult {
         epoch: proof.epoch,
         version: proof.version,
-        value: proof.value,
+        value,
     };
 
     if proof.version <= 1 {
@@ -326,13 +550,14 @@ fn verify_single_update_proof<TC: Configuration>(
     // ***** PART 2 ***************************
     // Verify the membership proof the for stale label of the previous version
 
-    let previous_version_proof = proof.previous_version_proof.as_ref().ok_or_else(|| {
-        VerificationError::HistoryProof("Missing membership proof for previous version".to_string())
-    })?;
-    let previous_version_vrf_proof =
-        proof.previous_version_vrf_proof.as_ref().ok_or_else(|| {
-            VerificationError::HistoryProof("Missing VRF proof for previous version".to_string())
-        })?;
+    let previous_version_proof = proof
+        .previous_version_proof
+        .as_ref()
+        .ok_or(ProofShapeError::MissingField("previous_version_proof"))?;
+    let previous_version_vrf_proof = proof
+        .previous_version_vrf_proof
+        .as_ref()
+        .ok_or(ProofShapeError::MissingField("previous_version_vrf_proof"))?;
 
     verify_existence_with_commitment::<TC>(
         vrf_public_key,
@@ -344,6 +569,7 @@ fn verify_single_update_proof<TC: Configuration>(
         proof.version - 1,
         previous_version_vrf_proof,
         previous_version_proof,
+        hashes,
     )?;
 
     Ok(verify_result)
@@ -7,28 +7,126 @@
 
 //! Verification of lookup proofs
 
-use super::base::{verify_existence, verify_existence_with_val, verify_nonexistence};
-use super::VerificationError;
+use super::base::{
+    unpad_value, verify_existence, verify_existence_with_val, verify_label, verify_nonexistence,
+    NodeHashes,
+};
+use super::rotation::verify_vrf_rotation_certificate;
+use super::{OrderingViolation, VerificationError};
 
 use crate::configuration::Configuration;
 use crate::hash::Digest;
-use crate::{AkdLabel, LookupProof, VerifyResult, VersionFreshness};
+use crate::{
+    AkdLabel, LabelDerivation, LookupProof, NonMembershipLookupProof, VerifyResult,
+    VersionFreshness,
+};
+#[cfg(feature = "nostd")]
+use alloc::string::ToString;
+#[cfg(feature = "nostd")]
+use alloc::vec;
+#[cfg(feature = "nostd")]
+use alloc::vec::Vec;
 
-/// Verifies a lookup with respect to the root_hash
+/// Verifies the signature of a [crate::signature::EpochSigner] which binds the response to a
+/// lookup of `akd_label`, made against `root_hash` at `current_epoch`, to the `challenge`
+/// supplied by the client. A client which supplies a fresh challenge with each lookup detects a
+/// response which is replayed, even while the epoch has not advanced. The lookup proof itself
+/// should still be verified with [lookup_verify].
+///
+/// See [crate::signature::verify_lookup_challenge], which does not require the `verify` feature.
+pub fn verify_lookup_challenge(
+    public_key: &[u8],
+    challenge: &[u8],
+    akd_label: &AkdLabel,
+    current_epoch: u64,
+    root_hash: Digest,
+    signature: &[u8],
+) -> Result<(), VerificationError> {
+    Ok(crate::signature::verify_lookup_challenge(
+        public_key,
+        challenge,
+        akd_label,
+        current_epoch,
+        root_hash,
+        signature,
+    )?)
+}
+
+/// Verifies a lookup with respect to the root_hash. If the proof carries a
+/// [crate::VrfRotationCertificate], its VRF proofs are verified under the new VRF public key
+/// which the certificate vouches for (see [super::verify_vrf_rotation_certificate]).
 pub fn lookup_verify<TC: Configuration>(
     vrf_public_key: &[u8],
     root_hash: Digest,
     current_epoch: u64,
     akd_label: AkdLabel,
     proof: LookupProof,
+) -> Result<VerifyResult, VerificationError> {
+    verify_lookup_proof::<TC>(
+        vrf_public_key,
+        root_hash,
+        current_epoch,
+        akd_label,
+        proof,
+        &mut NodeHashes::default(),
+    )
+}
+
+/// Verifies a batch of lookups with respect to the same root_hash, returning the result of
+/// each of them in order (which are the same as those of [lookup_verify]). The hashes of the
+/// tree nodes which the paths of the proofs share (e.g. those close to the root) are only
+/// computed once for the whole batch.
+pub fn batch_lookup_verify<TC: Configuration>(
+    vrf_public_key: &[u8],
+    root_hash: Digest,
+    current_epoch: u64,
+    proofs: Vec<(AkdLabel, LookupProof)>,
+) -> Vec<Result<VerifyResult, VerificationError>> {
+    let mut hashes = NodeHashes::memoized();
+    proofs
+        .into_iter()
+        .map(|(akd_label, proof)| {
+            verify_lookup_proof::<TC>(
+                vrf_public_key,
+                root_hash,
+                current_epoch,
+                akd_label,
+                proof,
+                &mut hashes,
+            )
+        })
+        .collect()
+}
+
+fn verify_lookup_proof<TC: Configuration>(
+    vrf_public_key: &[u8],
+    root_hash: Digest,
+    current_epoch: u64,
+    akd_label: AkdLabel,
+    proof: LookupProof,
+    hashes: &mut NodeHashes,
 ) -> Result<VerifyResult, VerificationError> {
     if proof.version > current_epoch {
-        return Err(VerificationError::LookupProof(alloc::format!(
-            "Proof version {} is greater than current epoch {}",
-            proof.version,
-            current_epoch
-        )));
+        return Err(OrderingViolation::VersionExceedsEpoch {
+            version: proof.version,
+            epoch: current_epoch,
+        }
+        .into());
+    }
+    if proof.value.is_tombstone() {
+        // The latest value of a label has to be verified, and so is never taken at face value
+        return Err(VerificationError::LookupProof(
+            "The value is a tombstone".to_string(),
+        ));
     }
+    // During the transition window of a rotation of the VRF key, the proof is generated under
+    // the new key, which is certified by the previous one
+    let vrf_public_key = match &proof.vrf_rotation {
+        Some(certificate) => {
+            verify_vrf_rotation_certificate::<TC>(vrf_public_key, current_epoch, certificate)?
+        }
+        None => vrf_public_key,
+    };
 
     verify_existence_with_val::<TC>(
         vrf_public_key,
@@ -41,6 +139,7 @@ pub fn lookup_verify<TC: Configuration>(
         proof.version,
         &proof.existence_vrf_proof,
         &proof.existence_proof,
+        hashes,
     )?;
 
     let marker_version = 1 << crate::utils::get_marker_version_log2(proof.version);
@@ -52,6 +151,7 @@ pub fn lookup_verify<TC: Configuration>(
         marker_version,
         &proof.marker_vrf_proof,
         &proof.marker_proof,
+        hashes,
     )?;
 
     verify_nonexistence::<TC>(
@@ -62,11 +162,97 @@ pub fn lookup_verify<TC: Configuration>(
         proof.version,
         &proof.freshness_vrf_proof,
         &proof.freshness_proof,
+        hashes,
     )?;
 
     Ok(VerifyResult {
         epoch: proof.epoch,
         version: proof.version,
-        value: proof.value,
+        value: unpad_value::<TC>(proof.value).ok_or_else(|| {
+            VerificationError::LookupProof("The value is not padded as configured".to_string())
+        })?,
     })
 }
+
+/// Checks the derivation of each of the node labels of a lookup proof (for the version, its
+/// marker version and the stale version, in that order) from the label, by verifying their VRF
+/// proofs against the VRF public key of the directory. The returned VRF outputs can be compared
+/// against those of any other implementation of the VRF suite of the configuration, for
+/// auditors who check the label derivations end to end.
+///
+/// This does not verify the membership proofs, which [lookup_verify] should be used for (and
+/// which performs the same checks of the VRF proofs). As with [lookup_verify], the VRF proofs of
+/// a lookup proof carrying a [crate::VrfRotationCertificate] are verified against the new key
+/// of the certificate, which is why the `current_epoch` of the proof is required.
+pub fn lookup_label_derivations<TC: Configuration>(
+    vrf_public_key: &[u8],
+    current_epoch: u64,
+    akd_label: &AkdLabel,
+    proof: &LookupProof,
+) -> Result<Vec<LabelDerivation>, VerificationError> {
+    let vrf_public_key = match &proof.vrf_rotation {
+        Some(certificate) => {
+            verify_vrf_rotation_certificate::<TC>(vrf_public_key, current_epoch, certificate)?
+        }
+        None => vrf_public_key,
+    };
+    let marker_version = 1 << crate::utils::get_marker_version_log2(proof.version);
+    let derivations = [
+        (
+            VersionFreshness::Fresh,
+            proof.version,
+            &proof.existence_vrf_proof,
+            proof.existence_proof.label,
+        ),
+        (
+            VersionFreshness::Fresh,
+            marker_version,
+            &proof.marker_vrf_proof,
+            proof.marker_proof.label,
+        ),
+        (
+            VersionFreshness::Stale,
+            proof.version,
+            &proof.freshness_vrf_proof,
+            proof.freshness_proof.label,
+        ),
+    ];
+    let mut results = vec![];
+    for (freshness, version, vrf_proof, node_label) in derivations {
+        let vrf_output = verify_label::<TC>(
+            vrf_public_key,
+            akd_label,
+            freshness,
+            version,
+            vrf_proof,
+            node_label,
+        )?;
+        results.push(LabelDerivation {
+            freshness,
+            version,
+            node_label,
+            vrf_output,
+        });
+    }
+    Ok(results)
+}
+
+/// Verifies with respect to the root_hash that a label has not been registered, i.e. that
+/// its first version is not in the tree
+pub fn lookup_absent_verify<TC: Configuration>(
+    vrf_public_key: &[u8],
+    root_hash: Digest,
+    akd_label: AkdLabel,
+    proof: NonMembershipLookupProof,
+) -> Result<(), VerificationError> {
+    verify_nonexistence::<TC>(
+        vrf_public_key,
+        root_hash,
+        &akd_label,
+        VersionFreshness::Fresh,
+        1,
+        &proof.vrf_proof,
+        &proof.non_membership_proof,
+        &mut NodeHashes::default(),
+    )
+}
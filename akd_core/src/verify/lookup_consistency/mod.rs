@@ -0,0 +1,241 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! This module contains [LookupConsistencyChecker], which checks the successive verified lookups
+//! of the same labels by a client against each other. A verified lookup proof only shows that the
+//! value is the latest one of the label as of the epoch of the proof, so a client which
+//! additionally checks it against the lookups it verified before detects a server which rolls
+//! back the value of a label, or presents the same version of a label differently.
+//!
+//! The epoch at which a version was published and the epoch of the lookup proof which revealed
+//! it are distinguished throughout: the former comes from the [VerifyResult], and the latter
+//! is the `current_epoch` the proof was verified against.
+
+use crate::{AkdLabel, VerifyResult};
+
+#[cfg(feature = "nostd")]
+use alloc::collections::BTreeMap;
+#[cfg(feature = "nostd")]
+use alloc::format;
+#[cfg(not(feature = "nostd"))]
+use std::collections::BTreeMap;
+
+#[cfg(test)]
+mod tests;
+
+/// Errors thrown when a verified lookup is inconsistent with a previously verified lookup of
+/// the same label
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum LookupConsistencyError {
+    /// An older version was returned by a lookup at an epoch at which the previously seen
+    /// version had already been published, i.e. the value of the label was rolled back
+    VersionRegression {
+        /// The previously seen version
+        previous_version: u64,
+        /// The epoch at which the previously seen version was published
+        previous_epoch: u64,
+        /// The version returned by the lookup
+        version: u64,
+        /// The epoch of the lookup
+        current_epoch: u64,
+    },
+    /// A version was published at an epoch which is not consistent with the order of the
+    /// versions, i.e. a newer version at the same or an earlier epoch than an older one
+    EpochRegression {
+        /// The previously seen version
+        previous_version: u64,
+        /// The epoch at which the previously seen version was published
+        previous_epoch: u64,
+        /// The version returned by the lookup
+        version: u64,
+        /// The epoch at which the version returned by the lookup was published
+        epoch: u64,
+    },
+    /// The same version was previously seen with a different value or publication epoch
+    Equivocation {
+        /// The version
+        version: u64,
+    },
+}
+
+impl core::fmt::Display for LookupConsistencyError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let code = match &self {
+            LookupConsistencyError::VersionRegression {
+                previous_version,
+                previous_epoch,
+                version,
+                current_epoch,
+            } => format!(
+                "(Version regression) - Version {version} was returned at epoch {current_epoch}, but version {previous_version} was published at epoch {previous_epoch}"
+            ),
+            LookupConsistencyError::EpochRegression {
+                previous_version,
+                previous_epoch,
+                version,
+                epoch,
+            } => format!(
+                "(Epoch regression) - Version {version} was published at epoch {epoch}, but version {previous_version} was published at epoch {previous_epoch}"
+            ),
+            LookupConsistencyError::Equivocation { version } => format!(
+                "(Equivocation) - Version {version} differs from the one previously seen"
+            ),
+        };
+        write!(f, "Lookup consistency error {code}")
+    }
+}
+
+/// How a verified lookup relates to the previously verified lookups of the label
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum LookupObservation {
+    /// The label was not looked up before
+    First,
+    /// The lookup returned the previously seen version
+    Unchanged,
+    /// The lookup returned a newer version than the previously seen one
+    Updated {
+        /// The previously seen version
+        previous_version: u64,
+    },
+    /// The lookup is at an epoch preceding the publication of the previously seen version, and
+    /// returned an older version accordingly. This is consistent, but suspicious if the server
+    /// is not expected to serve outdated epochs (e.g. from a lagging replica), as it shows a
+    /// stale value of the label.
+    Stale {
+        /// The previously seen version
+        latest_version: u64,
+        /// The epoch at which the previously seen version was published
+        latest_epoch: u64,
+    },
+}
+
+/// A lookup of a label which was verified at an epoch
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct VerifiedLookup {
+    /// The epoch the lookup proof was verified against
+    pub current_epoch: u64,
+    /// The result of the verification
+    pub result: VerifyResult,
+}
+
+/// Checks a verified lookup of a label against a previously verified lookup of the same label.
+///
+/// The version must not regress, unless the lookup is at an epoch preceding the publication of
+/// the previous version (see [LookupObservation::Stale]). A newer version must have been published
+/// at a later epoch, and the same version must have the same value and publication epoch.
+pub fn check_lookup_consistency(
+    previous: &VerifiedLookup,
+    current: &VerifiedLookup,
+) -> Result<LookupObservation, LookupConsistencyError> {
+    let (previous_result, result) = (&previous.result, &current.result);
+    let epoch_regression = || LookupConsistencyError::EpochRegression {
+        previous_version: previous_result.version,
+        previous_epoch: previous_result.epoch,
+        version: result.version,
+        epoch: result.epoch,
+    };
+
+    match result.version.cmp(&previous_result.version) {
+        core::cmp::Ordering::Equal => {
+            if result != previous_result {
+                return Err(LookupConsistencyError::Equivocation {
+                    version: result.version,
+                });
+            }
+            Ok(LookupObservation::Unchanged)
+        }
+        core::cmp::Ordering::Greater => {
+            if result.epoch <= previous_result.epoch {
+                return Err(epoch_regression());
+            }
+            Ok(LookupObservation::Updated {
+                previous_version: previous_result.version,
+            })
+        }
+        core::cmp::Ordering::Less => {
+            if current.current_epoch >= previous_result.epoch {
+                return Err(LookupConsistencyError::VersionRegression {
+                    previous_version: previous_result.version,
+                    previous_epoch: previous_result.epoch,
+                    version: result.version,
+                    current_epoch: current.current_epoch,
+                });
+            }
+            if result.epoch >= previous_result.epoch {
+                return Err(epoch_regression());
+            }
+            Ok(LookupObservation::Stale {
+                latest_version: previous_result.version,
+                latest_epoch: previous_result.epoch,
+            })
+        }
+    }
+}
+
+/// Tracks the latest verified lookup of each label seen by a client, checking each new one
+/// against it with [check_lookup_consistency].
+///
+/// The tracked lookups are only held in memory, and can be persisted by the client with
+/// [LookupConsistencyChecker::iter] and restored with [LookupConsistencyChecker::insert], so
+/// that the checks also hold across restarts.
+#[derive(Debug, Clone, Default)]
+pub struct LookupConsistencyChecker {
+    latest: BTreeMap<AkdLabel, VerifiedLookup>,
+}
+
+impl LookupConsistencyChecker {
+    /// Creates a checker which has not seen any lookup
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a lookup of a label as the latest one, without checking it
+    pub fn insert(&mut self, label: AkdLabel, lookup: VerifiedLookup) {
+        self.latest.insert(label, lookup);
+    }
+
+    /// The latest verified lookup of the label, if any
+    pub fn get(&self, label: &AkdLabel) -> Option<&VerifiedLookup> {
+        self.latest.get(label)
+    }
+
+    /// Iterates over the latest verified lookup of each label
+    pub fn iter(&self) -> impl Iterator<Item = (&AkdLabel, &VerifiedLookup)> {
+        self.latest.iter()
+    }
+
+    /// Checks a lookup of the label verified at `current_epoch` against the latest one seen,
+    /// recording it unless it is stale or inconsistent
+    pub fn observe(
+        &mut self,
+        label: &AkdLabel,
+        current_epoch: u64,
+        result: &VerifyResult,
+    ) -> Result<LookupObservation, LookupConsistencyError> {
+        let lookup = VerifiedLookup {
+            current_epoch,
+            result: result.clone(),
+        };
+        let observation = match self.latest.get(label) {
+            Some(previous) => check_lookup_consistency(previous, &lookup)?,
+            None => LookupObservation::First,
+        };
+        match observation {
+            LookupObservation::Stale { .. } => {}
+            LookupObservation::Unchanged => {
+                // Only the latest epoch at which the version was seen is retained
+                if let Some(previous) = self.latest.get_mut(label) {
+                    previous.current_epoch = previous.current_epoch.max(current_epoch);
+                }
+            }
+            LookupObservation::First | LookupObservation::Updated { .. } => {
+                self.latest.insert(label.clone(), lookup);
+            }
+        }
+        Ok(observation)
+    }
+}
@@ -0,0 +1,117 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Tests for the lookup consistency checks
+
+use super::*;
+use crate::AkdValue;
+#[cfg(feature = "nostd")]
+use alloc::format;
+
+/// The result of a lookup of version `version`, published at epoch `epoch`
+fn result(epoch: u64, version: u64) -> VerifyResult {
+    VerifyResult {
+        epoch,
+        version,
+        value: AkdValue::from(&format!("value{version}")),
+    }
+}
+
+#[test]
+fn test_lookup_consistency_accepts_consistent_lookups() {
+    let label = AkdLabel::from("hello");
+    let mut checker = LookupConsistencyChecker::new();
+
+    assert_eq!(
+        Ok(LookupObservation::First),
+        checker.observe(&label, 5, &result(3, 2))
+    );
+    assert_eq!(
+        Ok(LookupObservation::Unchanged),
+        checker.observe(&label, 7, &result(3, 2))
+    );
+    assert_eq!(
+        Ok(LookupObservation::Updated {
+            previous_version: 2
+        }),
+        checker.observe(&label, 9, &result(8, 3))
+    );
+    // An older version at an epoch before the latest one was published is stale, and is not
+    // recorded
+    assert_eq!(
+        Ok(LookupObservation::Stale {
+            latest_version: 3,
+            latest_epoch: 8
+        }),
+        checker.observe(&label, 7, &result(3, 2))
+    );
+    assert_eq!(9, checker.get(&label).unwrap().current_epoch);
+    assert_eq!(result(8, 3), checker.get(&label).unwrap().result);
+
+    // Labels are tracked independently
+    assert_eq!(
+        Ok(LookupObservation::First),
+        checker.observe(&AkdLabel::from("world"), 1, &result(1, 1))
+    );
+    assert_eq!(2, checker.iter().count());
+}
+
+#[test]
+fn test_lookup_consistency_rejects_rollbacks() {
+    let label = AkdLabel::from("hello");
+    let mut checker = LookupConsistencyChecker::new();
+    checker.observe(&label, 9, &result(8, 3)).unwrap();
+
+    assert_eq!(
+        Err(LookupConsistencyError::VersionRegression {
+            previous_version: 3,
+            previous_epoch: 8,
+            version: 2,
+            current_epoch: 10,
+        }),
+        checker.observe(&label, 10, &result(3, 2))
+    );
+    assert_eq!(
+        Err(LookupConsistencyError::EpochRegression {
+            previous_version: 3,
+            previous_epoch: 8,
+            version: 4,
+            epoch: 8,
+        }),
+        checker.observe(&label, 10, &result(8, 4))
+    );
+    // An older version cannot have been published after the latest one
+    assert_eq!(
+        Err(LookupConsistencyError::EpochRegression {
+            previous_version: 3,
+            previous_epoch: 8,
+            version: 2,
+            epoch: 8,
+        }),
+        checker.observe(&label, 7, &result(8, 2))
+    );
+    assert_eq!(
+        Err(LookupConsistencyError::Equivocation { version: 3 }),
+        checker.observe(&label, 10, &result(9, 3))
+    );
+    let mut other_value = result(8, 3);
+    other_value.value = AkdValue::from("other");
+    assert_eq!(
+        Err(LookupConsistencyError::Equivocation { version: 3 }),
+        checker.observe(&label, 10, &other_value)
+    );
+
+    // The rejected lookups are not recorded
+    assert_eq!(result(8, 3), checker.get(&label).unwrap().result);
+
+    // A checker restored from the recorded lookups detects the same inconsistencies
+    let mut restored = LookupConsistencyChecker::new();
+    for (label, lookup) in checker.iter() {
+        restored.insert(label.clone(), lookup.clone());
+    }
+    assert!(restored.observe(&label, 10, &result(3, 2)).is_err());
+}
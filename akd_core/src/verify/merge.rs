@@ -0,0 +1,81 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Verification of merge bridge proofs
+
+use super::base::NodeHashes;
+use super::history::{verify_single_update_proof, HistoryVerificationParams};
+use super::lookup::lookup_verify;
+use super::VerificationError;
+
+use crate::configuration::Configuration;
+use crate::hash::Digest;
+use crate::{AkdLabel, MergeBridgeProof, VerifyResult};
+
+#[cfg(feature = "nostd")]
+use alloc::format;
+#[cfg(feature = "nostd")]
+use alloc::string::ToString;
+
+/// Verifies that the state of a label in an original directory, as of its root hash at
+/// `original_epoch`, was carried over to the directory into which it was merged, as of its
+/// root hash at `merged_epoch`. On success, returns the latest version of the label in the
+/// original directory.
+///
+/// The merged directory reports this version at the same epoch as the original directory, so
+/// that clients which verified the history of the label in the original directory can check
+/// that it continues in the merged directory.
+#[allow(clippy::too_many_arguments)]
+pub fn merge_bridge_verify<TC: Configuration>(
+    original_vrf_public_key: &[u8],
+    original_root_hash: Digest,
+    original_epoch: u64,
+    merged_vrf_public_key: &[u8],
+    merged_root_hash: Digest,
+    merged_epoch: u64,
+    akd_label: AkdLabel,
+    proof: MergeBridgeProof,
+) -> Result<VerifyResult, VerificationError> {
+    let merged = &proof.merged_update_proof;
+    if merged.epoch > merged_epoch {
+        return Err(VerificationError::MergeBridge(format!(
+            "Version {} in the merged directory was published at epoch {}, which is after the current epoch {merged_epoch}",
+            merged.version, merged.epoch
+        )));
+    }
+
+    let result = lookup_verify::<TC>(
+        original_vrf_public_key,
+        original_root_hash,
+        original_epoch,
+        akd_label.clone(),
+        proof.original_lookup_proof,
+    )?;
+    if (merged.epoch, merged.version) != (result.epoch, result.version) {
+        return Err(VerificationError::MergeBridge(format!(
+            "Version {} at epoch {} in the merged directory does not match version {} at epoch {} in the original directory",
+            merged.version, merged.epoch, result.version, result.epoch
+        )));
+    }
+
+    let merged_result = verify_single_update_proof::<TC>(
+        merged_root_hash,
+        merged_vrf_public_key,
+        proof.merged_update_proof,
+        &akd_label,
+        HistoryVerificationParams::default(),
+        &mut NodeHashes::default(),
+    )?;
+    if merged_result.value != result.value {
+        return Err(VerificationError::MergeBridge(
+            "The value of the label in the merged directory differs from its value in the original directory"
+                .to_string(),
+        ));
+    }
+
+    Ok(result)
+}
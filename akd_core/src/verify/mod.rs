@@ -8,8 +8,15 @@
 //! This module contains verification calls for different proofs contained in the AKD crate
 
 pub mod base;
+pub mod epoch;
 pub mod history;
 pub mod lookup;
+pub mod lookup_consistency;
+pub mod merge;
+pub mod root_tracker;
+pub mod rotation;
+#[cfg(all(feature = "wasm", not(feature = "nostd")))]
+pub mod wasm;
 
 #[cfg(feature = "nostd")]
 use alloc::format;
@@ -18,6 +25,143 @@ use alloc::string::String;
 #[cfg(feature = "nostd")]
 use alloc::string::ToString;
 
+/// The check of a proof at which a computed hash did not match the expected one
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum HashCheck {
+    /// The root hash computed from a membership proof did not match the root hash
+    RootHash,
+    /// The hash of a leaf computed from its value (or commitment) did not match the hash in
+    /// its membership proof
+    LeafValue,
+    /// The hash computed from the children of the longest prefix of a non-membership proof did
+    /// not match the hash in the membership proof of the longest prefix
+    LongestPrefix,
+}
+
+impl core::fmt::Display for HashCheck {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let check = match &self {
+            HashCheck::RootHash => "root hash",
+            HashCheck::LeafValue => "leaf value",
+            HashCheck::LongestPrefix => "longest prefix",
+        };
+        write!(f, "{check}")
+    }
+}
+
+/// A violation of the expected ordering of the epochs and versions within a proof
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum OrderingViolation {
+    /// A version of the proof is greater than the current epoch
+    VersionExceedsEpoch {
+        /// The version of the proof
+        version: u64,
+        /// The current epoch
+        epoch: u64,
+    },
+    /// A version of the proof is 0, while versions start at 1
+    ZeroVersion,
+    /// The update proofs of a history proof are not for consecutive and decreasing versions
+    NonConsecutiveVersions {
+        /// The index of the update proof which does not follow the previous one
+        index: usize,
+        /// The version of the update proof
+        version: u64,
+        /// The version of the previous update proof
+        previous_version: u64,
+    },
+    /// The update proofs of a history proof are not for decreasing epochs
+    NonDecreasingEpochs {
+        /// The epoch of the update proof which does not follow the previous one
+        epoch: u64,
+        /// The epoch of the previous update proof
+        previous_epoch: u64,
+    },
+}
+
+impl core::fmt::Display for OrderingViolation {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match &self {
+            OrderingViolation::VersionExceedsEpoch { version, epoch } => {
+                write!(
+                    f,
+                    "Version {version} is greater than the current epoch {epoch}"
+                )
+            }
+            OrderingViolation::ZeroVersion => write!(f, "Versions should be non-zero"),
+            OrderingViolation::NonConsecutiveVersions {
+                index,
+                version,
+                previous_version,
+            } => write!(
+                f,
+                "Update proofs should be for consecutive and decreasing versions, \
+                but version {previous_version} is followed by version {version} at index {index}"
+            ),
+            OrderingViolation::NonDecreasingEpochs {
+                epoch,
+                previous_epoch,
+            } => write!(
+                f,
+                "Update proofs should be for decreasing epochs, \
+                but epoch {previous_epoch} is followed by epoch {epoch}"
+            ),
+        }
+    }
+}
+
+/// A proof which does not have the expected shape
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ProofShapeError {
+    /// A history proof does not have any update proofs
+    NoUpdateProofs,
+    /// A history proof has more update proofs than were requested
+    TooManyUpdateProofs {
+        /// The number of requested update proofs
+        expected: usize,
+        /// The number of update proofs
+        actual: usize,
+    },
+    /// A history proof for the complete history (or for more updates than there are) does not
+    /// start at version 1
+    IncompleteHistory {
+        /// The first version covered by the proof
+        start_version: u64,
+    },
+    /// A list of the proof does not have the expected number of elements
+    UnexpectedLength {
+        /// The field holding the list
+        field: &'static str,
+        /// The expected number of elements
+        expected: usize,
+        /// The number of elements
+        actual: usize,
+    },
+    /// A field which is required is missing from the proof
+    MissingField(&'static str),
+}
+
+impl core::fmt::Display for ProofShapeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match &self {
+            ProofShapeError::NoUpdateProofs => write!(f, "No update proofs"),
+            ProofShapeError::TooManyUpdateProofs { expected, actual } => {
+                write!(f, "Expected at most {expected} update proofs, but got {actual}")
+            }
+            ProofShapeError::IncompleteHistory { start_version } => write!(
+                f,
+                "Expected the history to start at version 1, but it starts at version {start_version}"
+            ),
+            ProofShapeError::UnexpectedLength {
+                field,
+                expected,
+                actual,
+            } => write!(f, "Expected {expected} elements in {field}, but got {actual}"),
+            ProofShapeError::MissingField(field) => write!(f, "Missing {field}"),
+        }
+    }
+}
+
 /// Proof verification error types
 #[derive(Debug, Eq, PartialEq)]
 pub enum VerificationError {
@@ -29,12 +173,35 @@ pub enum VerificationError {
     LookupProof(String),
     /// Error verifying a history proof
     HistoryProof(String),
+    /// Error verifying an epoch metadata proof
+    EpochMetadata(String),
+    /// Error verifying a signature over an epoch root hash
+    EpochSignature(String),
+    /// Error verifying an epoch bundle, or the link between two epoch bundles
+    EpochBundle(String),
+    /// Error verifying a key rotation proof
+    KeyRotation(String),
+    /// Error verifying a merge bridge proof
+    MergeBridge(String),
+    /// Error verifying the hash chain linking the root hashes of a range of epochs
+    RootChain(String),
     /// Error verifying a VRF proof
     #[cfg(feature = "vrf")]
     Vrf(crate::ecvrf::VrfError),
     /// Error converting protobuf types during verification
     #[cfg(feature = "protobuf")]
     Serialization(crate::proto::ConversionError),
+    /// A hash computed from a proof did not match the expected hash of a node
+    HashMismatch {
+        /// The label of the node whose proof failed to verify
+        label: crate::NodeLabel,
+        /// The check which failed
+        check: HashCheck,
+    },
+    /// The epochs or versions within a proof are not in the expected order
+    Ordering(OrderingViolation),
+    /// The proof does not have the expected shape
+    MalformedProof(ProofShapeError),
 }
 
 impl core::fmt::Display for VerificationError {
@@ -46,10 +213,21 @@ impl core::fmt::Display for VerificationError {
             }
             VerificationError::LookupProof(err) => format!("(Lookup proof) - {err}"),
             VerificationError::HistoryProof(err) => format!("(History proof) - {err}"),
+            VerificationError::EpochMetadata(err) => format!("(Epoch metadata) - {err}"),
+            VerificationError::EpochSignature(err) => format!("(Epoch signature) - {err}"),
+            VerificationError::EpochBundle(err) => format!("(Epoch bundle) - {err}"),
+            VerificationError::KeyRotation(err) => format!("(Key rotation) - {err}"),
+            VerificationError::MergeBridge(err) => format!("(Merge bridge) - {err}"),
+            VerificationError::RootChain(err) => format!("(Root chain) - {err}"),
             #[cfg(feature = "vrf")]
             VerificationError::Vrf(vrf) => vrf.to_string(),
             #[cfg(feature = "protobuf")]
             VerificationError::Serialization(proto) => proto.to_string(),
+            VerificationError::HashMismatch { label, check } => {
+                format!("(Hash mismatch) - Failed the {check} check for label {label:?}")
+            }
+            VerificationError::Ordering(violation) => format!("(Ordering) - {violation}"),
+            VerificationError::MalformedProof(shape) => format!("(Malformed proof) - {shape}"),
         };
         write!(f, "Verification error {code}")
     }
@@ -62,6 +240,34 @@ impl From<crate::ecvrf::VrfError> for VerificationError {
     }
 }
 
+impl From<OrderingViolation> for VerificationError {
+    fn from(input: OrderingViolation) -> Self {
+        VerificationError::Ordering(input)
+    }
+}
+
+impl From<ProofShapeError> for VerificationError {
+    fn from(input: ProofShapeError) -> Self {
+        VerificationError::MalformedProof(input)
+    }
+}
+
+impl From<crate::signature::SignatureVerificationError> for VerificationError {
+    fn from(input: crate::signature::SignatureVerificationError) -> Self {
+        match input {
+            crate::signature::SignatureVerificationError::EpochSignature(err) => {
+                VerificationError::EpochSignature(err)
+            }
+            crate::signature::SignatureVerificationError::EpochBundle(err) => {
+                VerificationError::EpochBundle(err)
+            }
+            crate::signature::SignatureVerificationError::LookupChallenge(err) => {
+                VerificationError::LookupProof(err)
+            }
+        }
+    }
+}
+
 #[cfg(feature = "protobuf")]
 impl From<crate::proto::ConversionError> for VerificationError {
     fn from(input: crate::proto::ConversionError) -> Self {
@@ -82,5 +288,22 @@ impl From<protobuf::Error> for VerificationError {
 #[cfg(feature = "public_tests")]
 pub use base::{verify_membership_for_tests_only, verify_nonmembership_for_tests_only};
 
-pub use history::{key_history_verify, HistoryVerificationParams};
-pub use lookup::lookup_verify;
+pub use epoch::{
+    verify_epoch_bundle, verify_epoch_bundle_link, verify_epoch_metadata, verify_epoch_root_chain,
+    verify_epoch_signature,
+};
+pub use history::{
+    key_history_since_verify, key_history_verify, key_history_verify_with_metadata,
+    HistoryVerificationParams,
+};
+pub use lookup::{
+    batch_lookup_verify, lookup_absent_verify, lookup_label_derivations, lookup_verify,
+    verify_lookup_challenge,
+};
+pub use lookup_consistency::{
+    check_lookup_consistency, LookupConsistencyChecker, LookupConsistencyError, LookupObservation,
+    VerifiedLookup,
+};
+pub use merge::merge_bridge_verify;
+pub use root_tracker::{InMemoryRootStore, RootStore, RootTracker, RootTrackerError};
+pub use rotation::{key_rotation_verify, verify_vrf_rotation_certificate};
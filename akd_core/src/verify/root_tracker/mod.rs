@@ -0,0 +1,172 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! This module contains [RootTracker], which keeps track of the `(epoch, root_hash)` pairs a
+//! client has seen in the responses of the server. A verified proof only shows that a response
+//! is consistent with the root hash which accompanied it, so a client which additionally checks
+//! each root hash against the ones seen before detects a server which rolls back its history, or
+//! presents the client with a different history (a fork) than previously.
+
+use crate::hash::Digest;
+
+#[cfg(feature = "nostd")]
+use alloc::collections::BTreeMap;
+#[cfg(feature = "nostd")]
+use alloc::format;
+#[cfg(feature = "nostd")]
+use alloc::string::String;
+#[cfg(feature = "nostd")]
+use alloc::vec::Vec;
+#[cfg(not(feature = "nostd"))]
+use std::collections::BTreeMap;
+
+#[cfg(test)]
+mod tests;
+
+/// Errors thrown when a root hash is inconsistent with the previously seen ones
+#[derive(Debug, Eq, PartialEq)]
+pub enum RootTrackerError {
+    /// The epoch precedes the latest epoch which was seen, and its root hash was not seen before
+    Regression {
+        /// The latest epoch which was seen
+        latest: u64,
+        /// The epoch of the response
+        epoch: u64,
+    },
+    /// A different root hash was previously seen for the epoch
+    Fork {
+        /// The epoch of the response
+        epoch: u64,
+        /// The root hash which was previously seen for the epoch
+        expected: Digest,
+        /// The root hash of the response
+        received: Digest,
+    },
+    /// The roots could not be loaded from, or persisted to, the [RootStore]
+    Storage(String),
+}
+
+impl core::fmt::Display for RootTrackerError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let code = match &self {
+            RootTrackerError::Regression { latest, epoch } => {
+                format!("(Regression) - Epoch {epoch} precedes the latest seen epoch {latest}")
+            }
+            RootTrackerError::Fork {
+                epoch,
+                expected,
+                received,
+            } => format!(
+                "(Fork) - Epoch {epoch} has root hash {}, but {} was seen before",
+                hex::encode(received),
+                hex::encode(expected)
+            ),
+            RootTrackerError::Storage(msg) => format!("(Storage) - {msg}"),
+        };
+        write!(f, "Root tracker error {code}")
+    }
+}
+
+/// The persistence of the roots seen by a [RootTracker], e.g. backed by the key-value
+/// store of a mobile platform, so that a regression or fork is also detected across
+/// restarts of the client.
+pub trait RootStore {
+    /// Loads all the persisted `(epoch, root_hash)` pairs
+    fn load(&self) -> Result<Vec<(u64, Digest)>, RootTrackerError>;
+
+    /// Persists a newly seen `(epoch, root_hash)` pair
+    fn store(&mut self, epoch: u64, root_hash: Digest) -> Result<(), RootTrackerError>;
+}
+
+/// A [RootStore] which does not persist the roots beyond the lifetime of the tracker
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryRootStore {
+    roots: Vec<(u64, Digest)>,
+}
+
+impl RootStore for InMemoryRootStore {
+    fn load(&self) -> Result<Vec<(u64, Digest)>, RootTrackerError> {
+        Ok(self.roots.clone())
+    }
+
+    fn store(&mut self, epoch: u64, root_hash: Digest) -> Result<(), RootTrackerError> {
+        self.roots.push((epoch, root_hash));
+        Ok(())
+    }
+}
+
+/// Tracks the root hashes seen by a client, rejecting any (verified) server response whose
+/// `(epoch, root_hash)` pair implies that the history of the directory was rolled back or forked.
+///
+/// A response is accepted if its epoch is the latest seen epoch or later, or if its root hash
+/// was seen before for that epoch. Newly seen roots are persisted to the [RootStore] before they
+/// are accepted.
+#[derive(Debug)]
+pub struct RootTracker<S: RootStore> {
+    store: S,
+    roots: BTreeMap<u64, Digest>,
+}
+
+impl<S: RootStore> RootTracker<S> {
+    /// Creates a tracker from the roots persisted in the store
+    pub fn new(store: S) -> Result<Self, RootTrackerError> {
+        let mut roots = BTreeMap::new();
+        for (epoch, root_hash) in store.load()? {
+            if let Some(expected) = roots.insert(epoch, root_hash) {
+                if expected != root_hash {
+                    return Err(RootTrackerError::Fork {
+                        epoch,
+                        expected,
+                        received: root_hash,
+                    });
+                }
+            }
+        }
+        Ok(Self { store, roots })
+    }
+
+    /// The latest seen epoch, along with its root hash
+    pub fn latest(&self) -> Option<(u64, Digest)> {
+        self.roots
+            .last_key_value()
+            .map(|(epoch, root_hash)| (*epoch, *root_hash))
+    }
+
+    /// The root hash which was seen for the epoch, if any
+    pub fn get(&self, epoch: u64) -> Option<Digest> {
+        self.roots.get(&epoch).copied()
+    }
+
+    /// Checks the `(epoch, root_hash)` pair of a server response against the previously
+    /// seen roots, recording it if it was not seen before
+    pub fn observe(&mut self, epoch: u64, root_hash: Digest) -> Result<(), RootTrackerError> {
+        if let Some(expected) = self.get(epoch) {
+            return if expected == root_hash {
+                Ok(())
+            } else {
+                Err(RootTrackerError::Fork {
+                    epoch,
+                    expected,
+                    received: root_hash,
+                })
+            };
+        }
+        if let Some((latest, _)) = self.latest() {
+            if epoch < latest {
+                return Err(RootTrackerError::Regression { latest, epoch });
+            }
+        }
+        self.store.store(epoch, root_hash)?;
+        self.roots.insert(epoch, root_hash);
+        Ok(())
+    }
+
+    /// Consumes the tracker, returning its store
+    pub fn into_store(self) -> S {
+        self.store
+    }
+}
@@ -0,0 +1,104 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Tests for the root tracker
+
+use super::*;
+#[cfg(feature = "nostd")]
+use alloc::string::ToString;
+#[cfg(feature = "nostd")]
+use alloc::vec;
+
+/// A store which rejects all writes
+struct ReadOnlyStore(Vec<(u64, Digest)>);
+
+impl RootStore for ReadOnlyStore {
+    fn load(&self) -> Result<Vec<(u64, Digest)>, RootTrackerError> {
+        Ok(self.0.clone())
+    }
+
+    fn store(&mut self, _epoch: u64, _root_hash: Digest) -> Result<(), RootTrackerError> {
+        Err(RootTrackerError::Storage("read-only".to_string()))
+    }
+}
+
+#[test]
+fn test_root_tracker_accepts_consistent_roots() {
+    let mut tracker = RootTracker::new(InMemoryRootStore::default()).unwrap();
+    assert_eq!(None, tracker.latest());
+
+    tracker.observe(2, [2u8; 32]).unwrap();
+    // The same root can be seen again, and epochs may be skipped
+    tracker.observe(2, [2u8; 32]).unwrap();
+    tracker.observe(5, [5u8; 32]).unwrap();
+    // An older root which was seen before is still consistent
+    tracker.observe(2, [2u8; 32]).unwrap();
+
+    assert_eq!(Some((5, [5u8; 32])), tracker.latest());
+    assert_eq!(Some([2u8; 32]), tracker.get(2));
+    assert_eq!(None, tracker.get(3));
+}
+
+#[test]
+fn test_root_tracker_rejects_regression_and_fork() {
+    let mut tracker = RootTracker::new(InMemoryRootStore::default()).unwrap();
+    tracker.observe(2, [2u8; 32]).unwrap();
+    tracker.observe(5, [5u8; 32]).unwrap();
+
+    assert_eq!(
+        Err(RootTrackerError::Regression {
+            latest: 5,
+            epoch: 3
+        }),
+        tracker.observe(3, [3u8; 32])
+    );
+    assert_eq!(
+        Err(RootTrackerError::Fork {
+            epoch: 5,
+            expected: [5u8; 32],
+            received: [6u8; 32]
+        }),
+        tracker.observe(5, [6u8; 32])
+    );
+    assert_eq!(
+        Err(RootTrackerError::Fork {
+            epoch: 2,
+            expected: [2u8; 32],
+            received: [6u8; 32]
+        }),
+        tracker.observe(2, [6u8; 32])
+    );
+    // The rejected roots are not recorded
+    assert_eq!(Some((5, [5u8; 32])), tracker.latest());
+}
+
+#[test]
+fn test_root_tracker_persistence() {
+    let mut tracker = RootTracker::new(InMemoryRootStore::default()).unwrap();
+    tracker.observe(1, [1u8; 32]).unwrap();
+    tracker.observe(4, [4u8; 32]).unwrap();
+
+    // A tracker restored from the store detects the same inconsistencies
+    let mut tracker = RootTracker::new(tracker.into_store()).unwrap();
+    assert_eq!(Some((4, [4u8; 32])), tracker.latest());
+    assert!(tracker.observe(3, [3u8; 32]).is_err());
+    assert!(tracker.observe(1, [0u8; 32]).is_err());
+
+    // A root which cannot be persisted is not accepted
+    let mut tracker = RootTracker::new(ReadOnlyStore(vec![(1, [1u8; 32])])).unwrap();
+    assert!(matches!(
+        tracker.observe(2, [2u8; 32]),
+        Err(RootTrackerError::Storage(_))
+    ));
+    assert_eq!(Some((1, [1u8; 32])), tracker.latest());
+
+    // A store with conflicting roots for an epoch is rejected
+    assert!(matches!(
+        RootTracker::new(ReadOnlyStore(vec![(1, [1u8; 32]), (1, [2u8; 32])])),
+        Err(RootTrackerError::Fork { epoch: 1, .. })
+    ));
+}
@@ -0,0 +1,149 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Verification of key rotation proofs
+
+use super::base::{verify_existence_with_commitment, NodeHashes};
+use super::history::{verify_single_update_proof, HistoryVerificationParams};
+use super::VerificationError;
+
+use crate::configuration::Configuration;
+use crate::ecvrf::VrfScheme;
+use crate::hash::Digest;
+use crate::{AkdLabel, KeyRotationProof, VerifyResult, VersionFreshness, VrfRotationCertificate};
+
+#[cfg(feature = "nostd")]
+use alloc::format;
+#[cfg(feature = "nostd")]
+use alloc::string::ToString;
+
+/// Verifies that the state of a label was carried over across a rotation of the VRF key of
+/// the directory, from `previous_vrf_public_key` to `new_vrf_public_key`. On success, returns
+/// the latest version of the label under the previous key, with the epoch at which it was
+/// originally published.
+///
+/// Under the new key, the versions published before the rotation are reported at the rotation
+/// epoch, and so clients which verified the history of the label under the previous key can use
+/// this to check that it continues under the new key.
+pub fn key_rotation_verify<TC: Configuration>(
+    previous_vrf_public_key: &[u8],
+    new_vrf_public_key: &[u8],
+    root_hash: Digest,
+    current_epoch: u64,
+    akd_label: AkdLabel,
+    proof: KeyRotationProof,
+) -> Result<VerifyResult, VerificationError> {
+    let mut hashes = NodeHashes::default();
+    let rotation_epoch = proof.rotation_epoch;
+    let previous = &proof.previous_key_update_proof;
+    let new = &proof.new_key_update_proof;
+
+    if rotation_epoch > current_epoch {
+        return Err(VerificationError::KeyRotation(format!(
+            "Rotation epoch {rotation_epoch} is after the current epoch {current_epoch}"
+        )));
+    }
+    if previous.epoch >= rotation_epoch {
+        return Err(VerificationError::KeyRotation(format!(
+            "Version {} under the previous key was published at epoch {}, which is not before the rotation epoch {rotation_epoch}",
+            previous.version, previous.epoch
+        )));
+    }
+    if new.epoch != rotation_epoch {
+        return Err(VerificationError::KeyRotation(format!(
+            "Version {} under the new key was published at epoch {} rather than at the rotation epoch {rotation_epoch}",
+            new.version, new.epoch
+        )));
+    }
+    if (new.version, &new.value) != (previous.version, &previous.value) {
+        return Err(VerificationError::KeyRotation(
+            "The state of the label under the new key differs from its state under the previous key"
+                .to_string(),
+        ));
+    }
+
+    let result = verify_single_update_proof::<TC>(
+        root_hash,
+        previous_vrf_public_key,
+        proof.previous_key_update_proof,
+        &akd_label,
+        HistoryVerificationParams::default(),
+        &mut hashes,
+    )?;
+
+    // The latest version can only have become stale under the previous key at the rotation
+    // epoch if no later version was published under the previous key
+    verify_existence_with_commitment::<TC>(
+        previous_vrf_public_key,
+        root_hash,
+        &akd_label,
+        TC::stale_azks_value(),
+        rotation_epoch,
+        VersionFreshness::Stale,
+        result.version,
+        &proof.retirement_vrf_proof,
+        &proof.retirement_proof,
+        &mut hashes,
+    )?;
+
+    verify_single_update_proof::<TC>(
+        root_hash,
+        new_vrf_public_key,
+        proof.new_key_update_proof,
+        &akd_label,
+        HistoryVerificationParams::default(),
+        &mut hashes,
+    )?;
+
+    Ok(result)
+}
+
+/// Verifies a [VrfRotationCertificate] with respect to the VRF public key which the client
+/// knows, returning the VRF public key under which the proofs carrying the certificate were
+/// generated. This is the new key of the certificate if the client knows the previous one, and
+/// the known key itself if the client already moved on to the new one.
+///
+/// The rotation must also have happened no later than the current epoch. On success, clients
+/// may replace the previous key they know with the returned one.
+pub fn verify_vrf_rotation_certificate<'a, TC: Configuration>(
+    vrf_public_key: &[u8],
+    current_epoch: u64,
+    certificate: &'a VrfRotationCertificate,
+) -> Result<&'a [u8], VerificationError> {
+    if certificate.rotation_epoch > current_epoch {
+        return Err(VerificationError::KeyRotation(format!(
+            "Rotation epoch {} is after the current epoch {current_epoch}",
+            certificate.rotation_epoch
+        )));
+    }
+    let message = VrfRotationCertificate::message(
+        certificate.rotation_epoch,
+        &certificate.previous_vrf_public_key,
+        &certificate.new_vrf_public_key,
+    );
+    // Both keys must have certified the rotation, whichever of them the client knows
+    TC::Vrf::verify(
+        &certificate.previous_vrf_public_key,
+        &certificate.previous_key_proof,
+        &message,
+    )?;
+    TC::Vrf::verify(
+        &certificate.new_vrf_public_key,
+        &certificate.new_key_proof,
+        &message,
+    )?;
+
+    if vrf_public_key == certificate.previous_vrf_public_key.as_slice()
+        || vrf_public_key == certificate.new_vrf_public_key.as_slice()
+    {
+        Ok(&certificate.new_vrf_public_key)
+    } else {
+        Err(VerificationError::KeyRotation(
+            "The rotation certificate does not involve the known VRF public key".to_string(),
+        ))
+    }
+}
@@ -0,0 +1,317 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! [wasm_bindgen] bindings of the lookup and history proof verification, for verifying proofs
+//! in a browser (or any other WebAssembly host). Proofs are passed in their protobuf encoding,
+//! and all keys, hashes, labels and values are passed as byte arrays (i.e. `Uint8Array`s on
+//! the JavaScript side). Verification errors are thrown as strings.
+//!
+//! There is one binding per configuration, e.g. [lookup_verify_whatsapp_v1] for the
+//! `WhatsAppV1Configuration`. The bindings can be built for Node.js with
+//! ```bash
+//! cargo rustc -p akd_core --lib --release --target wasm32-unknown-unknown \
+//!     --features wasm,whatsapp_v1,experimental,sha3_256 --crate-type cdylib
+//! wasm-bindgen --target nodejs --out-dir akd_core/wasm/pkg \
+//!     target/wasm32-unknown-unknown/release/akd_core.wasm
+//! ```
+//! and are tested against the test vectors in `akd_core/wasm` with `akd_core/wasm/test.sh`.
+
+use super::history::{HistoryParams, HistoryVerificationParams};
+use super::VerificationError;
+use crate::configuration::Configuration;
+use crate::hash::try_parse_digest;
+use crate::proto::specs::types;
+use crate::{AkdLabel, HistoryMetadata, VerifyResult};
+
+use protobuf::Message;
+use wasm_bindgen::prelude::*;
+
+/// A value of a label, as verified by a lookup or history proof
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedValue {
+    epoch: u64,
+    version: u64,
+    value: Option<Vec<u8>>,
+}
+
+#[wasm_bindgen]
+impl VerifiedValue {
+    /// The epoch at which the value was published
+    #[wasm_bindgen(getter)]
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// The version of the label for this value
+    #[wasm_bindgen(getter)]
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// The value, which is undefined for a tombstone (a value removed from storage)
+    #[wasm_bindgen(getter)]
+    pub fn value(&self) -> Option<Vec<u8>> {
+        self.value.clone()
+    }
+}
+
+impl From<VerifyResult> for VerifiedValue {
+    fn from(result: VerifyResult) -> Self {
+        Self {
+            epoch: result.epoch,
+            version: result.version,
+            value: result.value.into_bytes(),
+        }
+    }
+}
+
+/// The values of a label verified by a history proof, from the most recent to the oldest
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedHistory {
+    values: Vec<VerifiedValue>,
+    metadata: HistoryMetadata,
+}
+
+#[wasm_bindgen]
+impl VerifiedHistory {
+    /// The number of verified values
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> usize {
+        self.values.len()
+    }
+
+    /// The verified value at the index, if any
+    pub fn get(&self, index: usize) -> Option<VerifiedValue> {
+        self.values.get(index).cloned()
+    }
+
+    /// Whether the history is complete, i.e. its oldest value is the first version of the label
+    #[wasm_bindgen(getter)]
+    pub fn complete(&self) -> bool {
+        self.metadata.complete
+    }
+
+    /// The versions older than the verified values which were proven to have been published
+    #[wasm_bindgen(getter)]
+    pub fn past_marker_versions(&self) -> Vec<u64> {
+        self.metadata.past_marker_versions.clone()
+    }
+
+    /// The versions newer than the latest verified value which were proven not to have been
+    /// published
+    #[wasm_bindgen(getter)]
+    pub fn future_marker_versions(&self) -> Vec<u64> {
+        self.metadata.future_marker_versions.clone()
+    }
+}
+
+impl VerifiedHistory {
+    /// The verified values
+    pub fn values(&self) -> &[VerifiedValue] {
+        &self.values
+    }
+
+    /// What the proof established about the versions of the label beyond the verified values
+    pub fn metadata(&self) -> &HistoryMetadata {
+        &self.metadata
+    }
+}
+
+fn fallible_lookup_verify<TC: Configuration>(
+    vrf_public_key: &[u8],
+    root_hash: &[u8],
+    current_epoch: u64,
+    label: &[u8],
+    lookup_proof: &[u8],
+) -> Result<VerifiedValue, VerificationError> {
+    let root_hash = try_parse_digest(root_hash).map_err(VerificationError::LookupProof)?;
+    let proof = types::LookupProof::parse_from_bytes(lookup_proof)?;
+    super::lookup_verify::<TC>(
+        vrf_public_key,
+        root_hash,
+        current_epoch,
+        AkdLabel(label.to_vec()),
+        (&proof).try_into()?,
+    )
+    .map(VerifiedValue::from)
+}
+
+fn fallible_key_history_verify<TC: Configuration>(
+    vrf_public_key: &[u8],
+    root_hash: &[u8],
+    current_epoch: u64,
+    label: &[u8],
+    history_proof: &[u8],
+    most_recent: Option<u32>,
+    allow_missing_values: bool,
+) -> Result<VerifiedHistory, VerificationError> {
+    let root_hash = try_parse_digest(root_hash).map_err(VerificationError::HistoryProof)?;
+    let proof = types::HistoryProof::parse_from_bytes(history_proof)?;
+    let history_params = match most_recent {
+        Some(count) => HistoryParams::MostRecent(count as usize),
+        None => HistoryParams::Complete,
+    };
+    let params = if allow_missing_values {
+        HistoryVerificationParams::AllowMissingValues { history_params }
+    } else {
+        HistoryVerificationParams::Default { history_params }
+    };
+    let (results, metadata) = super::key_history_verify_with_metadata::<TC>(
+        vrf_public_key,
+        root_hash,
+        current_epoch,
+        AkdLabel(label.to_vec()),
+        (&proof).try_into()?,
+        params,
+    )?;
+    Ok(VerifiedHistory {
+        values: results.into_iter().map(VerifiedValue::from).collect(),
+        metadata,
+    })
+}
+
+// NOTE(new_config): Add a new configuration here
+
+/// Verify a protobuf-encoded lookup proof for the WhatsAppV1Configuration
+#[cfg(feature = "whatsapp_v1")]
+#[wasm_bindgen]
+pub fn lookup_verify_whatsapp_v1(
+    vrf_public_key: &[u8],
+    root_hash: &[u8],
+    current_epoch: u64,
+    label: &[u8],
+    lookup_proof: &[u8],
+) -> Result<VerifiedValue, String> {
+    fallible_lookup_verify::<crate::WhatsAppV1Configuration>(
+        vrf_public_key,
+        root_hash,
+        current_epoch,
+        label,
+        lookup_proof,
+    )
+    .map_err(|err| err.to_string())
+}
+
+/// Verify a protobuf-encoded history proof for the WhatsAppV1Configuration. The proof
+/// should have been generated for the `most_recent` updates of the label, or for its
+/// complete history if omitted.
+#[cfg(feature = "whatsapp_v1")]
+#[wasm_bindgen]
+pub fn key_history_verify_whatsapp_v1(
+    vrf_public_key: &[u8],
+    root_hash: &[u8],
+    current_epoch: u64,
+    label: &[u8],
+    history_proof: &[u8],
+    most_recent: Option<u32>,
+    allow_missing_values: bool,
+) -> Result<VerifiedHistory, String> {
+    fallible_key_history_verify::<crate::WhatsAppV1Configuration>(
+        vrf_public_key,
+        root_hash,
+        current_epoch,
+        label,
+        history_proof,
+        most_recent,
+        allow_missing_values,
+    )
+    .map_err(|err| err.to_string())
+}
+
+/// Verify a protobuf-encoded lookup proof for the ExperimentalConfiguration
+#[cfg(feature = "experimental")]
+#[wasm_bindgen]
+pub fn lookup_verify_experimental(
+    vrf_public_key: &[u8],
+    root_hash: &[u8],
+    current_epoch: u64,
+    label: &[u8],
+    lookup_proof: &[u8],
+) -> Result<VerifiedValue, String> {
+    fallible_lookup_verify::<crate::ExperimentalConfiguration<crate::ExampleLabel>>(
+        vrf_public_key,
+        root_hash,
+        current_epoch,
+        label,
+        lookup_proof,
+    )
+    .map_err(|err| err.to_string())
+}
+
+/// Verify a protobuf-encoded history proof for the ExperimentalConfiguration. The proof
+/// should have been generated for the `most_recent` updates of the label, or for its
+/// complete history if omitted.
+#[cfg(feature = "experimental")]
+#[wasm_bindgen]
+pub fn key_history_verify_experimental(
+    vrf_public_key: &[u8],
+    root_hash: &[u8],
+    current_epoch: u64,
+    label: &[u8],
+    history_proof: &[u8],
+    most_recent: Option<u32>,
+    allow_missing_values: bool,
+) -> Result<VerifiedHistory, String> {
+    fallible_key_history_verify::<crate::ExperimentalConfiguration<crate::ExampleLabel>>(
+        vrf_public_key,
+        root_hash,
+        current_epoch,
+        label,
+        history_proof,
+        most_recent,
+        allow_missing_values,
+    )
+    .map_err(|err| err.to_string())
+}
+
+/// Verify a protobuf-encoded lookup proof for the Sha3Configuration
+#[cfg(feature = "sha3_256")]
+#[wasm_bindgen]
+pub fn lookup_verify_sha3_256(
+    vrf_public_key: &[u8],
+    root_hash: &[u8],
+    current_epoch: u64,
+    label: &[u8],
+    lookup_proof: &[u8],
+) -> Result<VerifiedValue, String> {
+    fallible_lookup_verify::<crate::Sha3Configuration<crate::ExampleLabel>>(
+        vrf_public_key,
+        root_hash,
+        current_epoch,
+        label,
+        lookup_proof,
+    )
+    .map_err(|err| err.to_string())
+}
+
+/// Verify a protobuf-encoded history proof for the Sha3Configuration. The proof
+/// should have been generated for the `most_recent` updates of the label, or for its
+/// complete history if omitted.
+#[cfg(feature = "sha3_256")]
+#[wasm_bindgen]
+pub fn key_history_verify_sha3_256(
+    vrf_public_key: &[u8],
+    root_hash: &[u8],
+    current_epoch: u64,
+    label: &[u8],
+    history_proof: &[u8],
+    most_recent: Option<u32>,
+    allow_missing_values: bool,
+) -> Result<VerifiedHistory, String> {
+    fallible_key_history_verify::<crate::Sha3Configuration<crate::ExampleLabel>>(
+        vrf_public_key,
+        root_hash,
+        current_epoch,
+        label,
+        history_proof,
+        most_recent,
+        allow_missing_values,
+    )
+    .map_err(|err| err.to_string())
+}
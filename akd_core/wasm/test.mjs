@@ -0,0 +1,71 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+// Tests the WebAssembly bindings against the protobuf-encoded test vectors, which are
+// generated with `cargo run -p examples -- wasm-test-vectors --out akd_core/wasm/test_vectors`.
+// The bindings are built into ./pkg by ./test.sh, which also runs these tests.
+
+import assert from "node:assert/strict";
+import { readFileSync } from "node:fs";
+import { createRequire } from "node:module";
+import { test } from "node:test";
+
+const require = createRequire(import.meta.url);
+const bindings = require("./pkg/akd_core.js");
+
+const bytes = (hex) => Uint8Array.from(Buffer.from(hex, "hex"));
+
+// NOTE(new_config): Add a new configuration here
+for (const config of ["whatsapp_v1", "experimental", "sha3_256"]) {
+  const lookupVerify = bindings[`lookup_verify_${config}`];
+  const historyVerify = bindings[`key_history_verify_${config}`];
+  const vectors = JSON.parse(
+    readFileSync(new URL(`./test_vectors/${config}.json`, import.meta.url)),
+  );
+
+  for (const vector of vectors) {
+    const name = Buffer.from(vector.label, "hex").toString();
+    const verifyLookup = (rootHash) =>
+      lookupVerify(
+        bytes(vector.vrf_public_key),
+        rootHash,
+        BigInt(vector.epoch),
+        bytes(vector.label),
+        bytes(vector.lookup_proof),
+      );
+
+    test(`${config}: lookup proof for ${name}`, () => {
+      const result = verifyLookup(bytes(vector.root_hash));
+      assert.equal(result.epoch, BigInt(vector.lookup.epoch));
+      assert.equal(result.version, BigInt(vector.lookup.version));
+      assert.deepEqual(result.value, bytes(vector.lookup.value));
+    });
+
+    test(`${config}: lookup proof for ${name} with the wrong root hash`, () => {
+      assert.throws(() => verifyLookup(new Uint8Array(32)));
+    });
+
+    test(`${config}: history proof for ${name}`, () => {
+      const result = historyVerify(
+        bytes(vector.vrf_public_key),
+        bytes(vector.root_hash),
+        BigInt(vector.epoch),
+        bytes(vector.label),
+        bytes(vector.history_proof),
+        undefined,
+        false,
+      );
+      assert.equal(result.length, vector.history.length);
+      vector.history.forEach((expected, index) => {
+        const value = result.get(index);
+        assert.equal(value.epoch, BigInt(expected.epoch));
+        assert.equal(value.version, BigInt(expected.version));
+        assert.deepEqual(value.value, bytes(expected.value));
+      });
+    });
+  }
+}
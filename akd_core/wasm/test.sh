@@ -0,0 +1,20 @@
+#!/usr/bin/env bash
+# Copyright (c) Meta Platforms, Inc. and affiliates.
+#
+# This source code is dual-licensed under either the MIT license found in the
+# LICENSE-MIT file in the root directory of this source tree or the Apache
+# License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+# of this source tree. You may select, at your option, one of the above-listed licenses.
+
+# Builds the WebAssembly bindings of akd_core (see akd_core::verify::wasm) for Node.js, and
+# runs the JavaScript tests against the test vectors. Requires the wasm32-unknown-unknown
+# target, Node.js 18 or later, and the wasm-bindgen CLI with the same version as the
+# wasm-bindgen crate (as given by `cargo pkgid -p wasm-bindgen`).
+set -euo pipefail
+
+cd "$(dirname "$0")/../.."
+cargo rustc -p akd_core --lib --release --target wasm32-unknown-unknown \
+    --features wasm,whatsapp_v1,experimental,sha3_256 --crate-type cdylib
+wasm-bindgen --target nodejs --out-dir akd_core/wasm/pkg \
+    target/wasm32-unknown-unknown/release/akd_core.wasm
+node --test akd_core/wasm/test.mjs
@@ -0,0 +1,103 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the above-listed licenses.
+ */
+
+/*
+ * The C interface to the proof verification of akd. See akd_ffi/src/lib.rs for the
+ * documentation of each function.
+ */
+
+#ifndef AKD_FFI_H
+#define AKD_FFI_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define AKD_CONFIGURATION_WHATSAPP_V1 1
+#define AKD_CONFIGURATION_EXPERIMENTAL 2
+#define AKD_CONFIGURATION_SHA3_256 3
+
+/* The values are stable, and will never be renumbered */
+typedef enum AkdStatus {
+  AKD_STATUS_OK = 0,
+  AKD_STATUS_NULL_POINTER = 1,
+  AKD_STATUS_UNKNOWN_CONFIGURATION = 2,
+  AKD_STATUS_INVALID_ARGUMENT = 3,
+  AKD_STATUS_DESERIALIZATION = 4,
+  AKD_STATUS_VERIFICATION = 5,
+  AKD_STATUS_INTERNAL = 6,
+} AkdStatus;
+
+typedef struct AkdBytes {
+  uint8_t *data;
+  size_t len;
+} AkdBytes;
+
+typedef struct AkdVerifyResult {
+  uint64_t epoch;
+  uint64_t version;
+  AkdBytes value;
+  bool tombstone;
+} AkdVerifyResult;
+
+typedef struct AkdHistoryResult {
+  AkdVerifyResult *results;
+  size_t len;
+} AkdHistoryResult;
+
+/* Valid until the next call into the library on the same thread, or NULL */
+const char *akd_last_error_message(void);
+
+AkdStatus akd_lookup_verify(uint32_t configuration,
+                            const uint8_t *vrf_public_key,
+                            size_t vrf_public_key_len,
+                            const uint8_t *root_hash,
+                            size_t root_hash_len,
+                            uint64_t current_epoch,
+                            const uint8_t *label,
+                            size_t label_len,
+                            const uint8_t *proof,
+                            size_t proof_len,
+                            AkdVerifyResult *result);
+
+/* most_recent is 0 for a proof of the complete history */
+AkdStatus akd_key_history_verify(uint32_t configuration,
+                                 const uint8_t *vrf_public_key,
+                                 size_t vrf_public_key_len,
+                                 const uint8_t *root_hash,
+                                 size_t root_hash_len,
+                                 uint64_t current_epoch,
+                                 const uint8_t *label,
+                                 size_t label_len,
+                                 const uint8_t *proof,
+                                 size_t proof_len,
+                                 uint32_t most_recent,
+                                 bool allow_missing_values,
+                                 AkdHistoryResult *result);
+
+/* hashes holds num_hashes consecutive 32-byte root hashes */
+AkdStatus akd_audit_verify(uint32_t configuration,
+                           const uint8_t *hashes,
+                           size_t num_hashes,
+                           const uint8_t *proof,
+                           size_t proof_len);
+
+void akd_verify_result_free(AkdVerifyResult *result);
+
+void akd_history_result_free(AkdHistoryResult *result);
+
+#ifdef __cplusplus
+} /* extern "C" */
+#endif
+
+#endif /* AKD_FFI_H */
@@ -0,0 +1,474 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! A C interface to the verification of lookup, history and audit proofs, so that applications
+//! (e.g. on iOS or Android) can verify proofs by linking against this library, without
+//! integrating a Rust toolchain into their builds. The declarations are in `include/akd_ffi.h`.
+//!
+//! All proofs are passed in their protobuf encoding, as pointer and length pairs. Each function
+//! returns an [AkdStatus], whose values are stable across releases. When a function fails,
+//! a description of the error can be retrieved with [akd_last_error_message].
+//!
+//! Results containing variable-length data are allocated by this library, and must be released
+//! with the corresponding free function ([akd_verify_result_free] or [akd_history_result_free]).
+//!
+//! With the `uniffi` feature, the [mobile] module additionally defines the client verification
+//! API for UniFFI, from which Kotlin and Swift bindings can be generated.
+
+use akd::client::{HistoryVerificationParams, VerificationError};
+use akd::configuration::Configuration;
+use akd::errors::AkdError;
+use akd::proto::ConversionError;
+use akd::{AkdLabel, Digest, HistoryParams, VerifyResult};
+use protobuf::Message;
+use std::cell::RefCell;
+use std::ffi::{c_char, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+#[cfg(feature = "uniffi")]
+pub mod mobile;
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!();
+
+/// Selects the `WhatsAppV1Configuration`
+pub const AKD_CONFIGURATION_WHATSAPP_V1: u32 = 1;
+/// Selects the `ExperimentalConfiguration<ExampleLabel>`
+pub const AKD_CONFIGURATION_EXPERIMENTAL: u32 = 2;
+/// Selects the `Sha3Configuration<ExampleLabel>`
+pub const AKD_CONFIGURATION_SHA3_256: u32 = 3;
+
+/// The status returned by each function. The values are part of the stable interface,
+/// so existing variants must never be renumbered.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AkdStatus {
+    /// The proof was verified
+    Ok = 0,
+    /// A required pointer argument was null
+    NullPointer = 1,
+    /// The configuration is not one of the `AKD_CONFIGURATION_*` constants
+    UnknownConfiguration = 2,
+    /// An argument was malformed, e.g. a root hash of the wrong length
+    InvalidArgument = 3,
+    /// The proof could not be decoded
+    Deserialization = 4,
+    /// The proof failed to verify
+    Verification = 5,
+    /// An unexpected internal error
+    Internal = 6,
+}
+
+/// A byte buffer allocated by this library
+#[repr(C)]
+#[derive(Debug)]
+pub struct AkdBytes {
+    /// The bytes, or null if the buffer is empty
+    pub data: *mut u8,
+    /// The number of bytes
+    pub len: usize,
+}
+
+/// A value verified by a lookup or history proof
+#[repr(C)]
+#[derive(Debug)]
+pub struct AkdVerifyResult {
+    /// The epoch at which the value was published
+    pub epoch: u64,
+    /// The version of the label for this value
+    pub version: u64,
+    /// The value, which is released by [akd_verify_result_free]
+    pub value: AkdBytes,
+    /// Whether the value is a tombstone (a value removed from storage), whose bytes are empty
+    pub tombstone: bool,
+}
+
+/// The values verified by a history proof, from the most recent to the oldest
+#[repr(C)]
+#[derive(Debug)]
+pub struct AkdHistoryResult {
+    /// The verified values, or null if there are none
+    pub results: *mut AkdVerifyResult,
+    /// The number of verified values
+    pub len: usize,
+}
+
+/// An error within this library, which is reported to the caller as an [AkdStatus]
+/// along with its message
+struct FfiError(AkdStatus, String);
+
+impl From<VerificationError> for FfiError {
+    fn from(err: VerificationError) -> Self {
+        let status = match err {
+            VerificationError::Serialization(_) => AkdStatus::Deserialization,
+            _ => AkdStatus::Verification,
+        };
+        Self(status, err.to_string())
+    }
+}
+
+impl From<protobuf::Error> for FfiError {
+    fn from(err: protobuf::Error) -> Self {
+        Self(AkdStatus::Deserialization, err.to_string())
+    }
+}
+
+impl From<ConversionError> for FfiError {
+    fn from(err: ConversionError) -> Self {
+        Self(AkdStatus::Deserialization, err.to_string())
+    }
+}
+
+impl From<AkdError> for FfiError {
+    fn from(err: AkdError) -> Self {
+        let status = match err {
+            AkdError::AuditErr(_) | AkdError::AzksErr(_) => AkdStatus::Verification,
+            _ => AkdStatus::Internal,
+        };
+        Self(status, err.to_string())
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Returns the message of the last error which occurred on the calling thread, or null if
+/// the last call succeeded. The message is owned by the library, and remains valid until
+/// the next call into the library on the same thread.
+#[no_mangle]
+pub extern "C" fn akd_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last_error| {
+        last_error
+            .borrow()
+            .as_ref()
+            .map_or(std::ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Runs the function, recording its error (or a caught panic) as the last error
+fn run(f: impl FnOnce() -> Result<(), FfiError>) -> AkdStatus {
+    let (status, message) = match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => (AkdStatus::Ok, None),
+        Ok(Err(FfiError(status, message))) => (status, Some(message)),
+        Err(_) => (
+            AkdStatus::Internal,
+            Some("Verification panicked".to_string()),
+        ),
+    };
+    LAST_ERROR.with(|last_error| {
+        *last_error.borrow_mut() =
+            message.map(|message| CString::new(message.replace('\0', "")).unwrap_or_default());
+    });
+    status
+}
+
+/// Interprets a pointer and length pair as a slice
+///
+/// # Safety
+/// Unless `len` is 0, `ptr` must be valid for reads of `len` bytes for the lifetime `'a`
+unsafe fn slice<'a>(ptr: *const u8, len: usize, name: &str) -> Result<&'a [u8], FfiError> {
+    if len == 0 {
+        return Ok(&[]);
+    }
+    if ptr.is_null() {
+        return Err(FfiError(AkdStatus::NullPointer, format!("{name} is null")));
+    }
+    Ok(std::slice::from_raw_parts(ptr, len))
+}
+
+fn digest(bytes: &[u8]) -> Result<Digest, FfiError> {
+    akd::hash::try_parse_digest(bytes).map_err(|err| FfiError(AkdStatus::InvalidArgument, err))
+}
+
+fn into_bytes(bytes: Vec<u8>) -> AkdBytes {
+    if bytes.is_empty() {
+        return AkdBytes {
+            data: std::ptr::null_mut(),
+            len: 0,
+        };
+    }
+    let len = bytes.len();
+    AkdBytes {
+        data: Box::into_raw(bytes.into_boxed_slice()) as *mut u8,
+        len,
+    }
+}
+
+impl From<VerifyResult> for AkdVerifyResult {
+    fn from(result: VerifyResult) -> Self {
+        Self {
+            epoch: result.epoch,
+            version: result.version,
+            tombstone: result.value.is_tombstone(),
+            value: into_bytes(result.value.into_bytes().unwrap_or_default()),
+        }
+    }
+}
+
+/// Calls the generic function with the type of the selected configuration
+macro_rules! with_configuration {
+    ($configuration:expr, $func:ident($($arg:expr),* $(,)?)) => {
+        // NOTE(new_config): Add a new configuration here
+        match $configuration {
+            AKD_CONFIGURATION_WHATSAPP_V1 => $func::<akd::WhatsAppV1Configuration>($($arg),*),
+            AKD_CONFIGURATION_EXPERIMENTAL => {
+                $func::<akd::ExperimentalConfiguration<akd::ExampleLabel>>($($arg),*)
+            }
+            AKD_CONFIGURATION_SHA3_256 => {
+                $func::<akd::Sha3Configuration<akd::ExampleLabel>>($($arg),*)
+            }
+            other => Err(FfiError(
+                AkdStatus::UnknownConfiguration,
+                format!("Unknown configuration {other}"),
+            )),
+        }
+    };
+}
+
+fn lookup_verify<TC: Configuration>(
+    vrf_public_key: &[u8],
+    root_hash: Digest,
+    current_epoch: u64,
+    label: &[u8],
+    proof: &[u8],
+) -> Result<VerifyResult, FfiError> {
+    let proof = akd::proto::specs::types::LookupProof::parse_from_bytes(proof)?;
+    Ok(akd::client::lookup_verify::<TC>(
+        vrf_public_key,
+        root_hash,
+        current_epoch,
+        AkdLabel(label.to_vec()),
+        (&proof).try_into()?,
+    )?)
+}
+
+fn key_history_verify<TC: Configuration>(
+    vrf_public_key: &[u8],
+    root_hash: Digest,
+    current_epoch: u64,
+    label: &[u8],
+    proof: &[u8],
+    params: HistoryVerificationParams,
+) -> Result<Vec<VerifyResult>, FfiError> {
+    let proof = akd::proto::specs::types::HistoryProof::parse_from_bytes(proof)?;
+    Ok(akd::client::key_history_verify::<TC>(
+        vrf_public_key,
+        root_hash,
+        current_epoch,
+        AkdLabel(label.to_vec()),
+        (&proof).try_into()?,
+        params,
+    )?)
+}
+
+fn audit_verify<TC: Configuration>(hashes: Vec<Digest>, proof: &[u8]) -> Result<(), FfiError> {
+    let proof = akd::proto::specs::types::AppendOnlyProof::parse_from_bytes(proof)?;
+    let proof = (&proof).try_into()?;
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .map_err(|err| FfiError(AkdStatus::Internal, err.to_string()))?;
+    Ok(runtime.block_on(akd::auditor::audit_verify::<TC>(hashes, proof))?)
+}
+
+/// Verifies a protobuf-encoded lookup proof for the label, against the root hash of the
+/// current epoch. On success, the verified value is written to `result`, which must then be
+/// released with [akd_verify_result_free].
+///
+/// # Safety
+/// Each pointer must be valid for reads of the accompanying length (or may be null if the
+/// length is 0), and `result` must be valid for writes of an [AkdVerifyResult].
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn akd_lookup_verify(
+    configuration: u32,
+    vrf_public_key: *const u8,
+    vrf_public_key_len: usize,
+    root_hash: *const u8,
+    root_hash_len: usize,
+    current_epoch: u64,
+    label: *const u8,
+    label_len: usize,
+    proof: *const u8,
+    proof_len: usize,
+    result: *mut AkdVerifyResult,
+) -> AkdStatus {
+    run(|| {
+        if result.is_null() {
+            return Err(FfiError(
+                AkdStatus::NullPointer,
+                "result is null".to_string(),
+            ));
+        }
+        let vrf_public_key = slice(vrf_public_key, vrf_public_key_len, "vrf_public_key")?;
+        let root_hash = digest(slice(root_hash, root_hash_len, "root_hash")?)?;
+        let label = slice(label, label_len, "label")?;
+        let proof = slice(proof, proof_len, "proof")?;
+        let verified = with_configuration!(
+            configuration,
+            lookup_verify(vrf_public_key, root_hash, current_epoch, label, proof)
+        )?;
+        result.write(verified.into());
+        Ok(())
+    })
+}
+
+/// Verifies a protobuf-encoded history proof for the label, against the root hash of the
+/// current epoch. The proof should have been generated for the `most_recent` updates of the
+/// label, or for its complete history if `most_recent` is 0. If `allow_missing_values` is
+/// set, values which were removed from storage (tombstones) are accepted. On success, the
+/// verified values are written to `result`, which must then be released with
+/// [akd_history_result_free].
+///
+/// # Safety
+/// Each pointer must be valid for reads of the accompanying length (or may be null if the
+/// length is 0), and `result` must be valid for writes of an [AkdHistoryResult].
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn akd_key_history_verify(
+    configuration: u32,
+    vrf_public_key: *const u8,
+    vrf_public_key_len: usize,
+    root_hash: *const u8,
+    root_hash_len: usize,
+    current_epoch: u64,
+    label: *const u8,
+    label_len: usize,
+    proof: *const u8,
+    proof_len: usize,
+    most_recent: u32,
+    allow_missing_values: bool,
+    result: *mut AkdHistoryResult,
+) -> AkdStatus {
+    run(|| {
+        if result.is_null() {
+            return Err(FfiError(
+                AkdStatus::NullPointer,
+                "result is null".to_string(),
+            ));
+        }
+        let vrf_public_key = slice(vrf_public_key, vrf_public_key_len, "vrf_public_key")?;
+        let root_hash = digest(slice(root_hash, root_hash_len, "root_hash")?)?;
+        let label = slice(label, label_len, "label")?;
+        let proof = slice(proof, proof_len, "proof")?;
+        let history_params = match most_recent {
+            0 => HistoryParams::Complete,
+            count => HistoryParams::MostRecent(count as usize),
+        };
+        let params = if allow_missing_values {
+            HistoryVerificationParams::AllowMissingValues { history_params }
+        } else {
+            HistoryVerificationParams::Default { history_params }
+        };
+        let verified = with_configuration!(
+            configuration,
+            key_history_verify(
+                vrf_public_key,
+                root_hash,
+                current_epoch,
+                label,
+                proof,
+                params
+            )
+        )?;
+
+        let results = verified
+            .into_iter()
+            .map(AkdVerifyResult::from)
+            .collect::<Vec<_>>();
+        let len = results.len();
+        let results = if results.is_empty() {
+            std::ptr::null_mut()
+        } else {
+            Box::into_raw(results.into_boxed_slice()) as *mut AkdVerifyResult
+        };
+        result.write(AkdHistoryResult { results, len });
+        Ok(())
+    })
+}
+
+/// Verifies a protobuf-encoded audit proof, given the root hashes of the audited epochs:
+/// `hashes` holds `num_hashes` consecutive 32-byte root hashes, one more than the number
+/// of epochs in the proof. The proof is verified on a runtime of its own, so this must not
+/// be called from within a Tokio runtime.
+///
+/// # Safety
+/// `hashes` must be valid for reads of `32 * num_hashes` bytes, and `proof` for reads of
+/// `proof_len` bytes (each may be null if the length is 0).
+#[no_mangle]
+pub unsafe extern "C" fn akd_audit_verify(
+    configuration: u32,
+    hashes: *const u8,
+    num_hashes: usize,
+    proof: *const u8,
+    proof_len: usize,
+) -> AkdStatus {
+    run(|| {
+        let hashes_len = num_hashes
+            .checked_mul(akd::hash::DIGEST_BYTES)
+            .ok_or_else(|| {
+                FfiError(
+                    AkdStatus::InvalidArgument,
+                    format!("Too many hashes: {num_hashes}"),
+                )
+            })?;
+        let hashes = slice(hashes, hashes_len, "hashes")?
+            .chunks(akd::hash::DIGEST_BYTES)
+            .map(digest)
+            .collect::<Result<Vec<_>, _>>()?;
+        let proof = slice(proof, proof_len, "proof")?;
+        with_configuration!(configuration, audit_verify(hashes, proof))
+    })
+}
+
+/// Releases the value of an [AkdVerifyResult]. The result itself is owned by the caller.
+///
+/// # Safety
+/// `result` must be null, or point to a result written by [akd_lookup_verify] which
+/// was not released before.
+#[no_mangle]
+pub unsafe extern "C" fn akd_verify_result_free(result: *mut AkdVerifyResult) {
+    if let Some(result) = result.as_mut() {
+        free_bytes(&mut result.value);
+    }
+}
+
+/// Releases the values of an [AkdHistoryResult]. The result itself is owned by the caller.
+///
+/// # Safety
+/// `result` must be null, or point to a result written by [akd_key_history_verify] which
+/// was not released before.
+#[no_mangle]
+pub unsafe extern "C" fn akd_history_result_free(result: *mut AkdHistoryResult) {
+    if let Some(result) = result.as_mut() {
+        if !result.results.is_null() {
+            let mut results = Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+                result.results,
+                result.len,
+            ));
+            for value in results.iter_mut() {
+                free_bytes(&mut value.value);
+            }
+        }
+        result.results = std::ptr::null_mut();
+        result.len = 0;
+    }
+}
+
+/// # Safety
+/// The bytes must have been allocated by [into_bytes], and not released before
+unsafe fn free_bytes(bytes: &mut AkdBytes) {
+    if !bytes.data.is_null() {
+        drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+            bytes.data, bytes.len,
+        )));
+    }
+    bytes.data = std::ptr::null_mut();
+    bytes.len = 0;
+}
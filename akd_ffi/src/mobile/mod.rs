@@ -0,0 +1,291 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! [UniFFI](https://mozilla.github.io/uniffi-rs/) definitions of the client verification API,
+//! from which Kotlin and Swift bindings are generated (see the README of this crate). Proofs
+//! are deserialized from their protobuf encoding into [LookupProof] and [HistoryProof] objects,
+//! which are then verified by [lookup_verify] and [key_history_verify].
+
+use akd::client::{HistoryVerificationParams, VerificationError};
+use akd::configuration::Configuration as AkdConfiguration;
+use akd::proto::ConversionError;
+use akd::{AkdLabel, Digest};
+use protobuf::Message;
+use std::sync::Arc;
+
+#[cfg(test)]
+mod tests;
+
+/// The configuration with which the proofs were generated
+#[derive(uniffi::Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Configuration {
+    /// The `WhatsAppV1Configuration`
+    WhatsAppV1,
+    /// The `ExperimentalConfiguration<ExampleLabel>`
+    Experimental,
+    /// The `Sha3Configuration<ExampleLabel>`
+    Sha3_256,
+}
+
+/// Errors thrown by the verification functions
+#[derive(uniffi::Error, Debug, Clone, PartialEq, Eq)]
+pub enum VerifyError {
+    /// An argument was malformed, e.g. a root hash of the wrong length
+    InvalidArgument {
+        /// A description of the error
+        message: String,
+    },
+    /// The proof could not be decoded
+    Deserialization {
+        /// A description of the error
+        message: String,
+    },
+    /// The proof failed to verify
+    Verification {
+        /// A description of the error
+        message: String,
+    },
+}
+
+impl std::error::Error for VerifyError {}
+
+impl std::fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let code = match &self {
+            VerifyError::InvalidArgument { message } => format!("(InvalidArgument) - {message}"),
+            VerifyError::Deserialization { message } => format!("(Deserialization) - {message}"),
+            VerifyError::Verification { message } => format!("(Verification) - {message}"),
+        };
+        write!(f, "Verify error {code}")
+    }
+}
+
+impl From<protobuf::Error> for VerifyError {
+    fn from(err: protobuf::Error) -> Self {
+        Self::Deserialization {
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<ConversionError> for VerifyError {
+    fn from(err: ConversionError) -> Self {
+        Self::Deserialization {
+            message: err.to_string(),
+        }
+    }
+}
+
+impl From<VerificationError> for VerifyError {
+    fn from(err: VerificationError) -> Self {
+        match err {
+            VerificationError::Serialization(err) => err.into(),
+            err => Self::Verification {
+                message: err.to_string(),
+            },
+        }
+    }
+}
+
+/// A value of a label, as verified by a lookup or history proof
+#[derive(uniffi::Record, Debug, Clone, PartialEq, Eq)]
+pub struct VerifyResult {
+    /// The epoch at which the value was published
+    pub epoch: u64,
+    /// The version of the label for this value
+    pub version: u64,
+    /// The value, which is missing for a tombstone (a value removed from storage)
+    pub value: Option<Vec<u8>>,
+}
+
+impl From<akd::VerifyResult> for VerifyResult {
+    fn from(result: akd::VerifyResult) -> Self {
+        Self {
+            epoch: result.epoch,
+            version: result.version,
+            value: result.value.into_bytes(),
+        }
+    }
+}
+
+/// The updates of a label which a history proof covers
+#[derive(uniffi::Enum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryParams {
+    /// The complete history of the label
+    Complete,
+    /// The most recent updates of the label
+    MostRecent {
+        /// The number of updates
+        count: u64,
+    },
+}
+
+impl From<HistoryParams> for akd::HistoryParams {
+    fn from(params: HistoryParams) -> Self {
+        match params {
+            HistoryParams::Complete => akd::HistoryParams::Complete,
+            HistoryParams::MostRecent { count } => akd::HistoryParams::MostRecent(count as usize),
+        }
+    }
+}
+
+/// A deserialized lookup proof, which is not verified yet
+#[derive(uniffi::Object, Debug)]
+pub struct LookupProof(akd::LookupProof);
+
+#[uniffi::export]
+impl LookupProof {
+    /// Decodes a protobuf-encoded lookup proof
+    #[uniffi::constructor]
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Arc<Self>, VerifyError> {
+        let proof = akd::proto::specs::types::LookupProof::parse_from_bytes(&bytes)?;
+        Ok(Arc::new(Self((&proof).try_into()?)))
+    }
+
+    /// The epoch claimed by the proof
+    pub fn epoch(&self) -> u64 {
+        self.0.epoch
+    }
+
+    /// The version claimed by the proof
+    pub fn version(&self) -> u64 {
+        self.0.version
+    }
+
+    /// The value claimed by the proof
+    pub fn value(&self) -> Vec<u8> {
+        self.0.value.to_vec()
+    }
+}
+
+/// A deserialized history proof, which is not verified yet
+#[derive(uniffi::Object, Debug)]
+pub struct HistoryProof(akd::HistoryProof);
+
+#[uniffi::export]
+impl HistoryProof {
+    /// Decodes a protobuf-encoded history proof
+    #[uniffi::constructor]
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Arc<Self>, VerifyError> {
+        let proof = akd::proto::specs::types::HistoryProof::parse_from_bytes(&bytes)?;
+        Ok(Arc::new(Self((&proof).try_into()?)))
+    }
+
+    /// The number of updates covered by the proof
+    pub fn update_count(&self) -> u64 {
+        self.0.update_proofs.len() as u64
+    }
+}
+
+fn digest(bytes: &[u8]) -> Result<Digest, VerifyError> {
+    akd::hash::try_parse_digest(bytes).map_err(|message| VerifyError::InvalidArgument { message })
+}
+
+/// Calls the generic function with the type of the configuration
+macro_rules! with_configuration {
+    ($configuration:expr, $func:ident($($arg:expr),* $(,)?)) => {
+        // NOTE(new_config): Add a new configuration here
+        match $configuration {
+            Configuration::WhatsAppV1 => $func::<akd::WhatsAppV1Configuration>($($arg),*),
+            Configuration::Experimental => {
+                $func::<akd::ExperimentalConfiguration<akd::ExampleLabel>>($($arg),*)
+            }
+            Configuration::Sha3_256 => {
+                $func::<akd::Sha3Configuration<akd::ExampleLabel>>($($arg),*)
+            }
+        }
+    };
+}
+
+/// Verifies a lookup proof for the label, against the root hash of the current epoch
+#[uniffi::export]
+pub fn lookup_verify(
+    configuration: Configuration,
+    vrf_public_key: Vec<u8>,
+    root_hash: Vec<u8>,
+    current_epoch: u64,
+    label: Vec<u8>,
+    proof: Arc<LookupProof>,
+) -> Result<VerifyResult, VerifyError> {
+    fn verify<TC: AkdConfiguration>(
+        vrf_public_key: &[u8],
+        root_hash: Digest,
+        current_epoch: u64,
+        label: AkdLabel,
+        proof: akd::LookupProof,
+    ) -> Result<akd::VerifyResult, VerificationError> {
+        akd::client::lookup_verify::<TC>(vrf_public_key, root_hash, current_epoch, label, proof)
+    }
+
+    let root_hash = digest(&root_hash)?;
+    let result = with_configuration!(
+        configuration,
+        verify(
+            &vrf_public_key,
+            root_hash,
+            current_epoch,
+            AkdLabel(label),
+            proof.0.clone(),
+        )
+    )?;
+    Ok(result.into())
+}
+
+/// Verifies a history proof for the label, against the root hash of the current epoch,
+/// returning the verified values from the most recent to the oldest. The proof should
+/// have been generated with the same [HistoryParams]. If `allow_missing_values` is set,
+/// values which were removed from storage (tombstones) are accepted.
+#[uniffi::export]
+#[allow(clippy::too_many_arguments)]
+pub fn key_history_verify(
+    configuration: Configuration,
+    vrf_public_key: Vec<u8>,
+    root_hash: Vec<u8>,
+    current_epoch: u64,
+    label: Vec<u8>,
+    proof: Arc<HistoryProof>,
+    params: HistoryParams,
+    allow_missing_values: bool,
+) -> Result<Vec<VerifyResult>, VerifyError> {
+    fn verify<TC: AkdConfiguration>(
+        vrf_public_key: &[u8],
+        root_hash: Digest,
+        current_epoch: u64,
+        label: AkdLabel,
+        proof: akd::HistoryProof,
+        params: HistoryVerificationParams,
+    ) -> Result<Vec<akd::VerifyResult>, VerificationError> {
+        akd::client::key_history_verify::<TC>(
+            vrf_public_key,
+            root_hash,
+            current_epoch,
+            label,
+            proof,
+            params,
+        )
+    }
+
+    let root_hash = digest(&root_hash)?;
+    let history_params = params.into();
+    let params = if allow_missing_values {
+        HistoryVerificationParams::AllowMissingValues { history_params }
+    } else {
+        HistoryVerificationParams::Default { history_params }
+    };
+    let results = with_configuration!(
+        configuration,
+        verify(
+            &vrf_public_key,
+            root_hash,
+            current_epoch,
+            AkdLabel(label),
+            proof.0.clone(),
+            params,
+        )
+    )?;
+    Ok(results.into_iter().map(VerifyResult::from).collect())
+}
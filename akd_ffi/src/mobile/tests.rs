@@ -0,0 +1,106 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Tests for the UniFFI definitions
+
+use super::*;
+use crate::tests::generate;
+
+macro_rules! test_config {
+    ( $x:ident ) => {
+        paste::paste! {
+            #[test]
+            fn [<$x _ whatsapp_v1_config>]() {
+                $x::<akd::WhatsAppV1Configuration>(Configuration::WhatsAppV1)
+            }
+
+            #[test]
+            fn [<$x _ experimental_config>]() {
+                $x::<akd::ExperimentalConfiguration<akd::ExampleLabel>>(Configuration::Experimental)
+            }
+
+            #[test]
+            fn [<$x _ sha3_256_config>]() {
+                $x::<akd::Sha3Configuration<akd::ExampleLabel>>(Configuration::Sha3_256)
+            }
+        }
+    };
+}
+
+test_config!(test_lookup_verify);
+fn test_lookup_verify<TC: AkdConfiguration>(configuration: Configuration) {
+    let proofs = generate::<TC>();
+    let proof = LookupProof::from_bytes(proofs.lookup_proof.clone()).unwrap();
+    assert_eq!((3, 3), (proof.epoch(), proof.version()));
+
+    let verify = |root_hash: Vec<u8>, label: &str| {
+        lookup_verify(
+            configuration,
+            proofs.vrf_public_key.clone(),
+            root_hash,
+            proofs.epoch,
+            label.as_bytes().to_vec(),
+            proof.clone(),
+        )
+    };
+    assert_eq!(
+        Ok(VerifyResult {
+            epoch: 3,
+            version: 3,
+            value: Some(b"value 3".to_vec()),
+        }),
+        verify(proofs.root_hash.to_vec(), "label")
+    );
+    assert!(matches!(
+        verify(proofs.root_hash.to_vec(), "other label"),
+        Err(VerifyError::Verification { .. })
+    ));
+    assert!(matches!(
+        verify(vec![0u8; 31], "label"),
+        Err(VerifyError::InvalidArgument { .. })
+    ));
+    assert!(matches!(
+        LookupProof::from_bytes(proofs.lookup_proof[..10].to_vec()),
+        Err(VerifyError::Deserialization { .. })
+    ));
+}
+
+test_config!(test_key_history_verify);
+fn test_key_history_verify<TC: AkdConfiguration>(configuration: Configuration) {
+    let proofs = generate::<TC>();
+    let proof = HistoryProof::from_bytes(proofs.history_proof.clone()).unwrap();
+    assert_eq!(3, proof.update_count());
+
+    let verify = |root_hash: Vec<u8>| {
+        key_history_verify(
+            configuration,
+            proofs.vrf_public_key.clone(),
+            root_hash,
+            proofs.epoch,
+            b"label".to_vec(),
+            proof.clone(),
+            HistoryParams::Complete,
+            false,
+        )
+    };
+    let results = verify(proofs.root_hash.to_vec()).unwrap();
+    assert_eq!(
+        vec![
+            (3, Some(b"value 3".to_vec())),
+            (2, Some(b"value 2".to_vec())),
+            (1, Some(b"value 1".to_vec()))
+        ],
+        results
+            .into_iter()
+            .map(|result| (result.epoch, result.value))
+            .collect::<Vec<_>>()
+    );
+    assert!(matches!(
+        verify(vec![0u8; 32]),
+        Err(VerifyError::Verification { .. })
+    ));
+}
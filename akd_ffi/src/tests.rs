@@ -0,0 +1,244 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Tests for the C interface
+
+use super::*;
+use akd::ecvrf::HardCodedAkdVRF;
+use akd::storage::memory::AsyncInMemoryDatabase;
+use akd::storage::StorageManager;
+use akd::{AkdValue, Directory};
+use std::ffi::CStr;
+
+/// The proofs produced by a small directory, along with the inputs to verify them
+pub(crate) struct Proofs {
+    pub(crate) vrf_public_key: Vec<u8>,
+    pub(crate) root_hash: Digest,
+    pub(crate) epoch: u64,
+    pub(crate) lookup_proof: Vec<u8>,
+    pub(crate) history_proof: Vec<u8>,
+    pub(crate) audit_hashes: Vec<u8>,
+    pub(crate) audit_proof: Vec<u8>,
+}
+
+macro_rules! test_config {
+    ( $x:ident ) => {
+        paste::paste! {
+            #[test]
+            fn [<$x _ whatsapp_v1_config>]() {
+                $x::<akd::WhatsAppV1Configuration>(AKD_CONFIGURATION_WHATSAPP_V1)
+            }
+
+            #[test]
+            fn [<$x _ experimental_config>]() {
+                $x::<akd::ExperimentalConfiguration<akd::ExampleLabel>>(
+                    AKD_CONFIGURATION_EXPERIMENTAL,
+                )
+            }
+
+            #[test]
+            fn [<$x _ sha3_256_config>]() {
+                $x::<akd::Sha3Configuration<akd::ExampleLabel>>(AKD_CONFIGURATION_SHA3_256)
+            }
+        }
+    };
+}
+
+/// Generates the proofs outside of any runtime, since [akd_audit_verify] starts its own
+pub(crate) fn generate<TC: Configuration>() -> Proofs {
+    tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap()
+        .block_on(generate_async::<TC>())
+}
+
+async fn generate_async<TC: Configuration>() -> Proofs {
+    let akd = Directory::<TC, _, _>::new(
+        StorageManager::new_no_cache(AsyncInMemoryDatabase::new()),
+        HardCodedAkdVRF {},
+    )
+    .await
+    .unwrap();
+    let mut audit_hashes = vec![];
+    for value in ["value 1", "value 2", "value 3"] {
+        let epoch_hash = akd
+            .publish(vec![(AkdLabel::from("label"), AkdValue::from(value))])
+            .await
+            .unwrap();
+        audit_hashes.extend_from_slice(&epoch_hash.hash());
+    }
+
+    let (lookup_proof, epoch_hash) = akd.lookup(AkdLabel::from("label")).await.unwrap();
+    let (history_proof, _) = akd
+        .key_history(&AkdLabel::from("label"), HistoryParams::Complete)
+        .await
+        .unwrap();
+    let audit_proof = akd.audit(1, 3).await.unwrap();
+    Proofs {
+        vrf_public_key: akd.get_public_key().await.unwrap().as_bytes().to_vec(),
+        root_hash: epoch_hash.hash(),
+        epoch: epoch_hash.epoch(),
+        lookup_proof: akd::proto::specs::types::LookupProof::from(&lookup_proof)
+            .write_to_bytes()
+            .unwrap(),
+        history_proof: akd::proto::specs::types::HistoryProof::from(&history_proof)
+            .write_to_bytes()
+            .unwrap(),
+        audit_hashes,
+        audit_proof: akd::proto::specs::types::AppendOnlyProof::from(&audit_proof)
+            .write_to_bytes()
+            .unwrap(),
+    }
+}
+
+fn lookup(configuration: u32, proofs: &Proofs, root_hash: &[u8]) -> (AkdStatus, AkdVerifyResult) {
+    let mut result = AkdVerifyResult {
+        epoch: 0,
+        version: 0,
+        value: AkdBytes {
+            data: std::ptr::null_mut(),
+            len: 0,
+        },
+        tombstone: false,
+    };
+    let status = unsafe {
+        akd_lookup_verify(
+            configuration,
+            proofs.vrf_public_key.as_ptr(),
+            proofs.vrf_public_key.len(),
+            root_hash.as_ptr(),
+            root_hash.len(),
+            proofs.epoch,
+            b"label".as_ptr(),
+            5,
+            proofs.lookup_proof.as_ptr(),
+            proofs.lookup_proof.len(),
+            &mut result,
+        )
+    };
+    (status, result)
+}
+
+fn last_error_message() -> Option<String> {
+    let message = akd_last_error_message();
+    (!message.is_null()).then(|| {
+        unsafe { CStr::from_ptr(message) }
+            .to_string_lossy()
+            .into_owned()
+    })
+}
+
+test_config!(test_lookup_verify);
+fn test_lookup_verify<TC: Configuration>(configuration: u32) {
+    let proofs = generate::<TC>();
+
+    let (status, mut result) = lookup(configuration, &proofs, &proofs.root_hash);
+    assert_eq!(AkdStatus::Ok, status);
+    assert_eq!(None, last_error_message());
+    assert_eq!((3, 3), (result.epoch, result.version));
+    let value = unsafe { std::slice::from_raw_parts(result.value.data, result.value.len) };
+    assert_eq!(b"value 3", value);
+    unsafe { akd_verify_result_free(&mut result) };
+    assert!(result.value.data.is_null());
+
+    // A proof which does not match the root hash fails to verify
+    let (status, _) = lookup(configuration, &proofs, &[0u8; 32]);
+    assert_eq!(AkdStatus::Verification, status);
+    assert!(last_error_message().is_some());
+
+    let (status, _) = lookup(configuration, &proofs, &[0u8; 31]);
+    assert_eq!(AkdStatus::InvalidArgument, status);
+
+    let (status, _) = lookup(0, &proofs, &proofs.root_hash);
+    assert_eq!(AkdStatus::UnknownConfiguration, status);
+
+    let mut corrupted = proofs;
+    corrupted.lookup_proof.truncate(10);
+    let (status, _) = lookup(configuration, &corrupted, &corrupted.root_hash);
+    assert_eq!(AkdStatus::Deserialization, status);
+
+    let status = unsafe {
+        akd_lookup_verify(
+            configuration,
+            std::ptr::null(),
+            32,
+            std::ptr::null(),
+            0,
+            0,
+            std::ptr::null(),
+            0,
+            std::ptr::null(),
+            0,
+            std::ptr::null_mut(),
+        )
+    };
+    assert_eq!(AkdStatus::NullPointer, status);
+}
+
+test_config!(test_key_history_verify);
+fn test_key_history_verify<TC: Configuration>(configuration: u32) {
+    let proofs = generate::<TC>();
+    let history = |root_hash: &Digest, result: &mut AkdHistoryResult| unsafe {
+        akd_key_history_verify(
+            configuration,
+            proofs.vrf_public_key.as_ptr(),
+            proofs.vrf_public_key.len(),
+            root_hash.as_ptr(),
+            root_hash.len(),
+            proofs.epoch,
+            b"label".as_ptr(),
+            5,
+            proofs.history_proof.as_ptr(),
+            proofs.history_proof.len(),
+            0,
+            false,
+            result,
+        )
+    };
+
+    let mut result = AkdHistoryResult {
+        results: std::ptr::null_mut(),
+        len: 0,
+    };
+    assert_eq!(AkdStatus::Ok, history(&proofs.root_hash, &mut result));
+    let values = unsafe { std::slice::from_raw_parts(result.results, result.len) };
+    assert_eq!(
+        vec![(3, 3), (2, 2), (1, 1)],
+        values
+            .iter()
+            .map(|value| (value.epoch, value.version))
+            .collect::<Vec<_>>()
+    );
+    unsafe { akd_history_result_free(&mut result) };
+    assert!(result.results.is_null());
+
+    assert_eq!(AkdStatus::Verification, history(&[0u8; 32], &mut result));
+}
+
+test_config!(test_audit_verify);
+fn test_audit_verify<TC: Configuration>(configuration: u32) {
+    let proofs = generate::<TC>();
+    let audit = |hashes: &[u8]| unsafe {
+        akd_audit_verify(
+            configuration,
+            hashes.as_ptr(),
+            hashes.len() / akd::hash::DIGEST_BYTES,
+            proofs.audit_proof.as_ptr(),
+            proofs.audit_proof.len(),
+        )
+    };
+
+    assert_eq!(AkdStatus::Ok, audit(&proofs.audit_hashes));
+
+    let mut tampered = proofs.audit_hashes.clone();
+    tampered[40] ^= 1;
+    assert_eq!(AkdStatus::Verification, audit(&tampered));
+    assert_eq!(
+        AkdStatus::Verification,
+        audit(&proofs.audit_hashes[akd::hash::DIGEST_BYTES..])
+    );
+}
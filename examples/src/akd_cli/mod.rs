@@ -0,0 +1,107 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! An interactive command-line interface for administering an in-memory directory, which
+//! covers the common operator tasks without writing a custom program: publishing updates from
+//! CSV or JSONL files, generating and verifying lookup, key history and audit proofs, reporting
+//! statistics, and exporting the state of the directory to a snapshot file (from which the
+//! CLI can be restarted).
+
+mod session;
+
+#[cfg(test)]
+mod tests;
+
+use akd::NamedConfiguration;
+use anyhow::Result;
+use clap::{Parser, ValueEnum};
+use colored::*;
+use session::{Session, SessionCommand};
+use std::io::{stdin, stdout, Write};
+use std::path::PathBuf;
+
+/// The configuration of the directory
+#[derive(ValueEnum, Clone, Debug)]
+enum CliConfiguration {
+    WhatsappV1,
+    Experimental,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub(crate) struct CliArgs {
+    /// A snapshot (see the `export` command) from which the directory is restored at startup
+    #[clap(long = "snapshot", short = 's')]
+    snapshot: Option<PathBuf>,
+
+    /// The configuration of the directory
+    #[clap(
+        value_enum,
+        long = "configuration",
+        ignore_case = true,
+        default_value = "whatsapp-v1"
+    )]
+    configuration: CliConfiguration,
+}
+
+/// A line entered at the prompt of the CLI
+#[derive(Parser, Debug)]
+#[clap(no_binary_name = true, disable_version_flag = true)]
+enum Line {
+    #[clap(flatten)]
+    Command(SessionCommand),
+    /// Exit the CLI
+    #[clap(alias = "quit")]
+    Exit,
+}
+
+pub(crate) async fn render_cli(args: CliArgs) -> Result<()> {
+    match args.configuration {
+        CliConfiguration::WhatsappV1 => run::<akd::WhatsAppV1Configuration>(args).await,
+        CliConfiguration::Experimental => {
+            run::<akd::ExperimentalConfiguration<akd::ExampleLabel>>(args).await
+        }
+    }
+}
+
+async fn run<TC: NamedConfiguration>(args: CliArgs) -> Result<()> {
+    let mut session = Session::<TC>::new().await?;
+    if let Some(snapshot) = &args.snapshot {
+        let output = session
+            .execute(SessionCommand::Import {
+                file: snapshot.clone(),
+            })
+            .await?;
+        println!("{output}");
+    }
+    println!(
+        "Administering an in-memory directory with the {} configuration, enter 'help' for the list of commands",
+        TC::name()
+    );
+
+    loop {
+        print!("> ");
+        stdout().flush()?;
+        let mut text = String::new();
+        if stdin().read_line(&mut text)? == 0 {
+            break;
+        }
+        let words = text.split_whitespace().collect::<Vec<_>>();
+        if words.is_empty() {
+            continue;
+        }
+        match Line::try_parse_from(words) {
+            Ok(Line::Exit) => break,
+            Ok(Line::Command(command)) => match session.execute(command).await {
+                Ok(output) => println!("{output}"),
+                Err(err) => println!("{} {err:#}", "Error:".red()),
+            },
+            // The error includes the help and usage messages
+            Err(err) => println!("{err}"),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,290 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! The commands of the CLI, and the directory which they are executed against
+
+use akd::ecvrf::HardCodedAkdVRF;
+use akd::storage::memory::AsyncInMemoryDatabase;
+use akd::storage::types::DbRecord;
+use akd::storage::{Database, DbSetState, StorageManager, StorageUtil};
+use akd::{
+    AkdLabel, AkdValue, Digest, Directory, HistoryParams, HistoryVerificationParams,
+    NamedConfiguration,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Subcommand;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum SessionCommand {
+    /// Publish the updates of a file in a new epoch. The file is either a CSV file with a
+    /// `label,value` line per update, or a JSONL file (with a `.jsonl` extension) with a
+    /// `{"label": .., "value": ..}` line per update
+    Publish { file: PathBuf },
+    /// Generate a lookup proof for a label, and verify it
+    Lookup { label: String },
+    /// Generate a key history proof for a label, and verify it
+    History {
+        label: String,
+        /// Only include the given number of most recent updates
+        #[clap(long = "most_recent")]
+        most_recent: Option<usize>,
+    },
+    /// Generate an audit proof between two epochs, and verify it
+    Audit { start: u64, end: u64 },
+    /// Report statistics about the directory
+    Stats,
+    /// Export the state of the directory to a snapshot file
+    Export { file: PathBuf },
+    /// Replace the directory with the state of a snapshot file
+    Import { file: PathBuf },
+}
+
+/// An update read from a JSONL file
+#[derive(Deserialize)]
+struct Update {
+    label: String,
+    value: String,
+}
+
+/// The state of a directory, as exported to a snapshot file
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Snapshot {
+    /// The name of the configuration of the directory
+    pub(crate) configuration: String,
+    /// The hex-encoded root hash of each epoch, starting from epoch 0
+    pub(crate) root_hashes: Vec<String>,
+    /// All of the records in storage
+    pub(crate) records: Vec<DbRecord>,
+}
+
+type CliDirectory<TC> = Directory<TC, AsyncInMemoryDatabase, HardCodedAkdVRF>;
+
+/// An in-memory directory, along with the root hashes of its epochs (which are needed to
+/// verify the audit proofs)
+pub(crate) struct Session<TC> {
+    pub(crate) storage: StorageManager<AsyncInMemoryDatabase>,
+    pub(crate) directory: CliDirectory<TC>,
+    pub(crate) root_hashes: Vec<Digest>,
+}
+
+impl<TC: NamedConfiguration> Session<TC> {
+    /// Creates a session for an empty directory
+    pub(crate) async fn new() -> Result<Self> {
+        let storage = StorageManager::new_no_cache(AsyncInMemoryDatabase::new());
+        let directory = CliDirectory::<TC>::new(storage.clone(), HardCodedAkdVRF {}).await?;
+        let root_hashes = vec![directory.get_epoch_hash().await?.hash()];
+        Ok(Self {
+            storage,
+            directory,
+            root_hashes,
+        })
+    }
+
+    /// Executes the command, returning its output
+    pub(crate) async fn execute(&mut self, command: SessionCommand) -> Result<String> {
+        match command {
+            SessionCommand::Publish { file } => self.publish(&file).await,
+            SessionCommand::Lookup { label } => self.lookup(label).await,
+            SessionCommand::History { label, most_recent } => {
+                self.key_history(label, most_recent).await
+            }
+            SessionCommand::Audit { start, end } => self.audit(start, end).await,
+            SessionCommand::Stats => {
+                let stats = self.directory.stats().await?;
+                Ok(serde_json::to_string_pretty(&stats)?)
+            }
+            SessionCommand::Export { file } => self.export(&file).await,
+            SessionCommand::Import { file } => self.import(&file).await,
+        }
+    }
+
+    async fn publish(&mut self, file: &Path) -> Result<String> {
+        let updates = read_updates(file)?;
+        let num_updates = updates.len();
+        let epoch_hash = self.directory.publish(updates).await?;
+        // Publishing values which are all unchanged does not create a new epoch
+        if epoch_hash.epoch() as usize == self.root_hashes.len() {
+            self.root_hashes.push(epoch_hash.hash());
+        }
+        Ok(format!(
+            "Published {num_updates} updates, the directory is at epoch {} with root hash {}",
+            epoch_hash.epoch(),
+            hex::encode(epoch_hash.hash())
+        ))
+    }
+
+    async fn lookup(&self, label: String) -> Result<String> {
+        let (proof, epoch_hash) = self.directory.lookup(AkdLabel::from(&label)).await?;
+        let public_key = self.directory.get_public_key().await?;
+        let result = akd::client::lookup_verify::<TC>(
+            public_key.as_bytes(),
+            epoch_hash.hash(),
+            epoch_hash.epoch(),
+            AkdLabel::from(&label),
+            proof,
+        )
+        .map_err(|err| anyhow!("Failed to verify the lookup proof: {err}"))?;
+        Ok(format!(
+            "Verified the lookup proof at epoch {}: version {} of '{label}' (published at epoch {}) is {}",
+            epoch_hash.epoch(),
+            result.version,
+            result.epoch,
+            display_value(&result.value)
+        ))
+    }
+
+    async fn key_history(&self, label: String, most_recent: Option<usize>) -> Result<String> {
+        let params = match most_recent {
+            Some(most_recent) => HistoryParams::MostRecent(most_recent),
+            None => HistoryParams::Complete,
+        };
+        let (proof, epoch_hash) = self
+            .directory
+            .key_history(&AkdLabel::from(&label), params)
+            .await?;
+        let public_key = self.directory.get_public_key().await?;
+        let results = akd::client::key_history_verify::<TC>(
+            public_key.as_bytes(),
+            epoch_hash.hash(),
+            epoch_hash.epoch(),
+            AkdLabel::from(&label),
+            proof,
+            HistoryVerificationParams::Default {
+                history_params: params,
+            },
+        )
+        .map_err(|err| anyhow!("Failed to verify the key history proof: {err}"))?;
+        let mut output = format!(
+            "Verified the key history proof at epoch {} for '{label}':",
+            epoch_hash.epoch()
+        );
+        for result in results {
+            output.push_str(&format!(
+                "\n  version {} (published at epoch {}): {}",
+                result.version,
+                result.epoch,
+                display_value(&result.value)
+            ));
+        }
+        Ok(output)
+    }
+
+    async fn audit(&self, start: u64, end: u64) -> Result<String> {
+        let proof = self.directory.audit(start, end).await?;
+        let hashes = self
+            .root_hashes
+            .get(start as usize..=end as usize)
+            .ok_or_else(|| anyhow!("The root hashes of epochs {start} to {end} are not known"))?
+            .to_vec();
+        let num_inserted = proof
+            .proofs
+            .iter()
+            .map(|proof| proof.inserted.len())
+            .sum::<usize>();
+        akd::auditor::audit_verify::<TC>(hashes, proof).await?;
+        Ok(format!(
+            "Verified the audit proof from epoch {start} to epoch {end} ({num_inserted} inserted leaves)"
+        ))
+    }
+
+    async fn export(&self, file: &Path) -> Result<String> {
+        let mut records = self.storage.get_db().batch_get_all_direct().await?;
+        // Sort the records to make the snapshot deterministic
+        records.sort();
+        let snapshot = Snapshot {
+            configuration: TC::name().to_string(),
+            root_hashes: self.root_hashes.iter().map(hex::encode).collect(),
+            records,
+        };
+        let num_records = snapshot.records.len();
+        std::fs::write(file, serde_json::to_string(&snapshot)?)
+            .with_context(|| format!("Failed to write {}", file.display()))?;
+        Ok(format!(
+            "Exported {num_records} records to {}",
+            file.display()
+        ))
+    }
+
+    pub(crate) async fn import(&mut self, file: &Path) -> Result<String> {
+        let contents = std::fs::read_to_string(file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        let snapshot: Snapshot = serde_json::from_str(&contents)?;
+        if snapshot.configuration != TC::name() {
+            bail!(
+                "The snapshot is of a directory with the {} configuration, rather than {}",
+                snapshot.configuration,
+                TC::name()
+            );
+        }
+        let root_hashes = snapshot
+            .root_hashes
+            .iter()
+            .map(|root_hash| {
+                akd::hash::try_parse_digest(&hex::decode(root_hash)?)
+                    .map_err(|err| anyhow!("Invalid root hash in snapshot: {err}"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let num_records = snapshot.records.len();
+
+        let db = AsyncInMemoryDatabase::new();
+        db.batch_set(snapshot.records, DbSetState::General).await?;
+        let storage = StorageManager::new_no_cache(db);
+        let directory = CliDirectory::<TC>::new(storage.clone(), HardCodedAkdVRF {}).await?;
+        let epoch_hash = directory.get_epoch_hash().await?;
+        if root_hashes.get(epoch_hash.epoch() as usize) != Some(&epoch_hash.hash()) {
+            bail!("The root hashes of the snapshot do not match its records");
+        }
+
+        *self = Self {
+            storage,
+            directory,
+            root_hashes,
+        };
+        Ok(format!(
+            "Imported {num_records} records from {}, the directory is at epoch {}",
+            file.display(),
+            epoch_hash.epoch()
+        ))
+    }
+}
+
+/// Reads the updates of a CSV or JSONL file
+fn read_updates(file: &Path) -> Result<Vec<(AkdLabel, AkdValue)>> {
+    let contents = std::fs::read_to_string(file)
+        .with_context(|| format!("Failed to read {}", file.display()))?;
+    let is_jsonl = file
+        .extension()
+        .is_some_and(|extension| extension == "jsonl");
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            let (label, value) = if is_jsonl {
+                let update: Update = serde_json::from_str(line)
+                    .with_context(|| format!("Invalid update on line {}", index + 1))?;
+                (update.label, update.value)
+            } else {
+                let (label, value) = line
+                    .split_once(',')
+                    .ok_or_else(|| anyhow!("Invalid update on line {}", index + 1))?;
+                (label.to_string(), value.to_string())
+            };
+            Ok((AkdLabel::from(&label), AkdValue::from(value.as_str())))
+        })
+        .collect()
+}
+
+/// Displays a value as a string if it is valid UTF-8, or otherwise as hex
+fn display_value(value: &AkdValue) -> String {
+    match std::str::from_utf8(value) {
+        Ok(value) => format!("'{value}'"),
+        Err(_) => format!("0x{}", hex::encode(value.as_slice())),
+    }
+}
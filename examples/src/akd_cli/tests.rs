@@ -0,0 +1,137 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Tests the commands of the CLI against an in-memory directory
+
+use super::session::{Session, SessionCommand};
+use super::Line;
+use crate::test_config;
+
+use akd::{AkdLabel, AkdValue, NamedConfiguration};
+use assert_fs::fixture::TempDir;
+use clap::Parser;
+
+test_config!(test_cli_session);
+async fn test_cli_session<TC: NamedConfiguration>() {
+    let dir = TempDir::new().unwrap();
+    let csv = dir.path().join("updates.csv");
+    std::fs::write(&csv, "hello,world\nhello2,world,with,commas\n\n").unwrap();
+    let jsonl = dir.path().join("updates.jsonl");
+    std::fs::write(
+        &jsonl,
+        "{\"label\": \"hello\", \"value\": \"world2\"}\n{\"label\": \"hello3\", \"value\": \"world\"}\n",
+    )
+    .unwrap();
+
+    let mut session = Session::<TC>::new().await.unwrap();
+    for file in [&csv, &jsonl] {
+        session
+            .execute(SessionCommand::Publish { file: file.clone() })
+            .await
+            .unwrap();
+    }
+    assert_eq!(3, session.root_hashes.len());
+    let (proof, _) = session
+        .directory
+        .lookup(AkdLabel::from("hello2"))
+        .await
+        .unwrap();
+    assert_eq!(AkdValue::from("world,with,commas"), proof.value);
+
+    // Republishing unchanged values does not create an epoch
+    session
+        .execute(SessionCommand::Publish {
+            file: jsonl.clone(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(3, session.root_hashes.len());
+
+    let output = session
+        .execute(SessionCommand::Lookup {
+            label: "hello".to_string(),
+        })
+        .await
+        .unwrap();
+    assert!(output.contains("version 2 of 'hello' (published at epoch 2) is 'world2'"));
+    let output = session
+        .execute(SessionCommand::History {
+            label: "hello".to_string(),
+            most_recent: None,
+        })
+        .await
+        .unwrap();
+    assert!(output.contains("version 1 (published at epoch 1): 'world'"));
+    session
+        .execute(SessionCommand::Audit { start: 0, end: 2 })
+        .await
+        .unwrap();
+    session.execute(SessionCommand::Stats).await.unwrap();
+    assert!(session
+        .execute(SessionCommand::Lookup {
+            label: "unknown".to_string(),
+        })
+        .await
+        .is_err());
+
+    // A snapshot restores the directory along with its root hashes
+    let snapshot = dir.path().join("snapshot.json");
+    session
+        .execute(SessionCommand::Export {
+            file: snapshot.clone(),
+        })
+        .await
+        .unwrap();
+    let mut restored = Session::<TC>::new().await.unwrap();
+    restored
+        .execute(SessionCommand::Import {
+            file: snapshot.clone(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(session.root_hashes, restored.root_hashes);
+    assert_eq!(
+        session.directory.get_epoch_hash().await.unwrap(),
+        restored.directory.get_epoch_hash().await.unwrap()
+    );
+    restored
+        .execute(SessionCommand::Audit { start: 1, end: 2 })
+        .await
+        .unwrap();
+    restored
+        .execute(SessionCommand::Lookup {
+            label: "hello3".to_string(),
+        })
+        .await
+        .unwrap();
+
+    // Malformed update files are rejected
+    let invalid = dir.path().join("invalid.csv");
+    std::fs::write(&invalid, "hello\n").unwrap();
+    assert!(session
+        .execute(SessionCommand::Publish { file: invalid })
+        .await
+        .is_err());
+}
+
+#[test]
+fn test_parse_line() {
+    assert!(matches!(
+        Line::try_parse_from(["history", "hello", "--most_recent", "2"]),
+        Ok(Line::Command(SessionCommand::History {
+            most_recent: Some(2),
+            ..
+        }))
+    ));
+    assert!(matches!(
+        Line::try_parse_from(["audit", "1", "2"]),
+        Ok(Line::Command(SessionCommand::Audit { start: 1, end: 2 }))
+    ));
+    assert!(matches!(Line::try_parse_from(["exit"]), Ok(Line::Exit)));
+    assert!(Line::try_parse_from(["audit", "1"]).is_err());
+    assert!(Line::try_parse_from(["unknown"]).is_err());
+}
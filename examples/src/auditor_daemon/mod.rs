@@ -0,0 +1,264 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! A long-running auditor, which continuously polls a source of audit proofs for new epochs
+//! and verifies that each of them is an append-only extension of the last verified epoch.
+//!
+//! The last verified epoch and its root hash are persisted in a checkpoint file, so that the
+//! auditor picks up where it left off after a restart. If a proof fails to verify, or the
+//! published history is inconsistent with the checkpoint, the failure is posted to the
+//! (optional) webhook and the daemon exits with an error.
+
+mod source;
+
+#[cfg(test)]
+mod tests;
+
+use akd::local_auditing::AuditBlob;
+use akd::{AppendOnlyProof, Configuration, Digest};
+use anyhow::{anyhow, bail, Result};
+use clap::{Parser, ValueEnum};
+use serde::{Deserialize, Serialize};
+use source::ProofSource;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// The configuration used to verify the proofs
+#[derive(ValueEnum, Clone, Debug)]
+enum AuditConfiguration {
+    WhatsappV1,
+    Experimental,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub(crate) struct CliArgs {
+    /// Where the audit proofs are published: the URL of a blob bucket, or a local directory
+    #[clap(long = "source", short = 's')]
+    source: String,
+
+    /// The file in which the last verified epoch and root hash are persisted
+    #[clap(
+        long = "checkpoint",
+        short = 'c',
+        default_value = "auditor_checkpoint.json"
+    )]
+    checkpoint: PathBuf,
+
+    /// The number of seconds to wait between polls of the source
+    #[clap(long = "poll_interval", short = 'i', default_value = "60")]
+    poll_interval_secs: u64,
+
+    /// A URL to which failures are reported with a JSON-encoded POST request
+    #[clap(long = "webhook", short = 'w')]
+    webhook: Option<String>,
+
+    /// Poll the source a single time, and exit once all new epochs are verified
+    #[clap(long = "once")]
+    once: bool,
+
+    /// The configuration with which the proofs were generated
+    #[clap(
+        value_enum,
+        long = "configuration",
+        ignore_case = true,
+        default_value = "whatsapp-v1"
+    )]
+    configuration: AuditConfiguration,
+}
+
+/// The last epoch which was verified by the auditor, with its root hash
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Checkpoint {
+    pub(crate) epoch: u64,
+    /// The hex-encoded root hash
+    pub(crate) root_hash: String,
+}
+
+impl Checkpoint {
+    fn new(epoch: u64, root_hash: Digest) -> Self {
+        Self {
+            epoch,
+            root_hash: hex::encode(root_hash),
+        }
+    }
+
+    /// Loads the checkpoint, or returns [None] if no checkpoint was persisted yet
+    pub(crate) fn load(path: &Path) -> Result<Option<Self>> {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => Ok(Some(serde_json::from_str(&contents)?)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Persists the checkpoint, replacing any previous one atomically
+    fn store(&self, path: &Path) -> Result<()> {
+        let mut temp_path = path.as_os_str().to_owned();
+        temp_path.push(".tmp");
+        std::fs::write(&temp_path, serde_json::to_string(self)?)?;
+        std::fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+
+    fn root_hash(&self) -> Result<Digest> {
+        akd::hash::try_parse_digest(&hex::decode(&self.root_hash)?)
+            .map_err(|err| anyhow!("Invalid root hash in checkpoint: {}", err))
+    }
+}
+
+/// The result of a poll of the source
+#[derive(Debug)]
+pub(crate) enum PollError {
+    /// The proofs could not be retrieved, which is retried on the next poll
+    Source(anyhow::Error),
+    /// The published proofs failed to verify
+    Verification(String),
+}
+
+/// The message posted to the webhook upon a verification failure
+#[derive(Serialize)]
+struct Alert<'a> {
+    source: &'a str,
+    last_verified_epoch: Option<u64>,
+    error: &'a str,
+}
+
+pub(crate) async fn render_cli(args: CliArgs) -> Result<()> {
+    match args.configuration {
+        AuditConfiguration::WhatsappV1 => run::<akd::WhatsAppV1Configuration>(args).await,
+        AuditConfiguration::Experimental => {
+            run::<akd::ExperimentalConfiguration<akd::ExampleLabel>>(args).await
+        }
+    }
+}
+
+async fn run<TC: Configuration>(args: CliArgs) -> Result<()> {
+    let source = ProofSource::parse(&args.source);
+    loop {
+        match poll::<TC>(&source, &args.checkpoint).await {
+            Ok(0) => {}
+            Ok(count) => println!("Verified {count} new epoch(s) from {}", args.source),
+            Err(PollError::Source(err)) if !args.once => {
+                eprintln!("Failed to retrieve audit proofs, retrying on the next poll: {err}");
+            }
+            Err(PollError::Source(err)) => return Err(err),
+            Err(PollError::Verification(message)) => {
+                eprintln!("AUDIT FAILURE: {message}");
+                if let Some(webhook) = &args.webhook {
+                    let last_verified_epoch = Checkpoint::load(&args.checkpoint)
+                        .ok()
+                        .flatten()
+                        .map(|checkpoint| checkpoint.epoch);
+                    if let Err(err) =
+                        send_alert(webhook, &args.source, last_verified_epoch, &message).await
+                    {
+                        eprintln!("Failed to notify the webhook: {err}");
+                    }
+                }
+                bail!("Audit failed: {}", message);
+            }
+        }
+        if args.once {
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_secs(args.poll_interval_secs)).await;
+    }
+}
+
+/// Verifies the epochs published after the checkpoint, in order, advancing the checkpoint
+/// after each verified epoch. Returns the number of newly verified epochs.
+pub(crate) async fn poll<TC: Configuration>(
+    source: &ProofSource,
+    checkpoint_path: &Path,
+) -> Result<usize, PollError> {
+    let mut checkpoint = Checkpoint::load(checkpoint_path).map_err(PollError::Source)?;
+    let summaries = source.list().await.map_err(PollError::Source)?;
+
+    let last_verified_epoch = checkpoint.as_ref().map(|checkpoint| checkpoint.epoch);
+    let mut verified = 0;
+    for summary in summaries
+        .iter()
+        .filter(|summary| last_verified_epoch.is_none_or(|epoch| summary.name.epoch > epoch))
+    {
+        if let Some(checkpoint) = &checkpoint {
+            if summary.name.epoch != checkpoint.epoch + 1 {
+                return Err(PollError::Verification(format!(
+                    "Epoch {} was published after the last verified epoch {}, skipping the epochs in between",
+                    summary.name.epoch, checkpoint.epoch
+                )));
+            }
+            let root_hash = checkpoint.root_hash().map_err(PollError::Source)?;
+            if summary.name.previous_hash != root_hash {
+                return Err(PollError::Verification(format!(
+                    "The previous root hash of epoch {} does not match the verified root hash of epoch {}",
+                    summary.name.epoch, checkpoint.epoch
+                )));
+            }
+        }
+
+        let blob = source.fetch(summary).await.map_err(PollError::Source)?;
+        verify_blob::<TC>(blob)
+            .await
+            .map_err(|err| PollError::Verification(err.to_string()))?;
+
+        let next = Checkpoint::new(summary.name.epoch, summary.name.current_hash);
+        next.store(checkpoint_path).map_err(PollError::Source)?;
+        checkpoint = Some(next);
+        verified += 1;
+    }
+    Ok(verified)
+}
+
+async fn verify_blob<TC: Configuration>(blob: AuditBlob) -> Result<()> {
+    let (end_epoch, previous_hash, current_hash, proof) = blob.decode().map_err(|err| {
+        anyhow!(
+            "Failed to decode the proof of epoch {}: {:?}",
+            blob.name.epoch,
+            err
+        )
+    })?;
+    if end_epoch == 0 {
+        bail!("Epoch 0 has no audit proof");
+    }
+    akd::auditor::audit_verify::<TC>(
+        vec![previous_hash, current_hash],
+        AppendOnlyProof {
+            proofs: vec![proof],
+            // The blobs are named by their ending epoch, while the proof starts at the previous one
+            epochs: vec![end_epoch - 1],
+        },
+    )
+    .await
+    .map_err(|err| {
+        anyhow!(
+            "Audit proof for epoch {} failed to verify: {}",
+            end_epoch,
+            err
+        )
+    })
+}
+
+async fn send_alert(
+    webhook: &str,
+    source: &str,
+    last_verified_epoch: Option<u64>,
+    error: &str,
+) -> Result<()> {
+    let alert = Alert {
+        source,
+        last_verified_epoch,
+        error,
+    };
+    reqwest::Client::new()
+        .post(webhook)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(serde_json::to_string(&alert)?)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
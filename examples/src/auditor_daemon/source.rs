@@ -0,0 +1,67 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! The locations from which the auditor daemon retrieves audit proofs
+
+use crate::whatsapp_kt_auditor::{auditor, EpochSummary};
+
+use akd::local_auditing::{AuditBlob, LocalAuditBlobStorage, ProofIndex};
+use anyhow::{anyhow, Result};
+use std::path::PathBuf;
+
+/// A location where audit blobs are published, named by their [akd::local_auditing::AuditBlobName]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum ProofSource {
+    /// A blob bucket (e.g. S3 or Cloudfront) which supports the list-bucket API
+    Bucket(String),
+    /// A local directory, in which each blob is stored at the path given by its name
+    Directory(PathBuf),
+}
+
+impl ProofSource {
+    /// Interprets URLs (starting with http:// or https://) as buckets, and anything else
+    /// as the path of a local directory
+    pub(crate) fn parse(source: &str) -> Self {
+        if source.starts_with("http://") || source.starts_with("https://") {
+            Self::Bucket(source.trim_end_matches('/').to_string())
+        } else {
+            Self::Directory(PathBuf::from(source))
+        }
+    }
+
+    /// Lists the available audit blobs, sorted by epoch
+    pub(crate) async fn list(&self) -> Result<Vec<EpochSummary>> {
+        let mut summaries = match self {
+            Self::Bucket(url) => auditor::list_proofs(url).await?,
+            Self::Directory(root) => {
+                let index = ProofIndex::load(&LocalAuditBlobStorage::new(root), "")
+                    .await
+                    .map_err(|err| anyhow!("Failed to list {}: {:?}", root.display(), err))?;
+                index
+                    .blobs()
+                    .map(|name| EpochSummary {
+                        name: *name,
+                        key: index.key(name),
+                    })
+                    .collect()
+            }
+        };
+        summaries.sort_by_key(|summary| summary.name.epoch);
+        Ok(summaries)
+    }
+
+    /// Retrieves the contents of an audit blob
+    pub(crate) async fn fetch(&self, summary: &EpochSummary) -> Result<AuditBlob> {
+        match self {
+            Self::Bucket(url) => auditor::get_proof(url, summary).await,
+            Self::Directory(root) => Ok(AuditBlob {
+                data: tokio::fs::read(root.join(&summary.key)).await?,
+                name: summary.name,
+            }),
+        }
+    }
+}
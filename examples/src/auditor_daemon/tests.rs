@@ -0,0 +1,126 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Tests the auditor daemon against a local directory of audit blobs
+
+use super::source::ProofSource;
+use super::{poll, Checkpoint, PollError};
+use crate::test_config;
+
+use akd::ecvrf::HardCodedAkdVRF;
+use akd::local_auditing::AuditBlob;
+use akd::storage::memory::AsyncInMemoryDatabase;
+use akd::storage::StorageManager;
+use akd::{AkdLabel, AkdValue, Configuration, Directory};
+use assert_fs::fixture::TempDir;
+use std::path::Path;
+
+type TestDirectory<TC> = Directory<TC, AsyncInMemoryDatabase, HardCodedAkdVRF>;
+
+/// Publishes a new epoch, and writes its audit blob into the directory
+async fn publish_epoch<TC: Configuration>(akd: &TestDirectory<TC>, dir: &Path) -> AuditBlob {
+    let previous = akd.get_epoch_hash().await.unwrap();
+    let current = akd
+        .publish(vec![(
+            AkdLabel(format!("user{}", previous.epoch()).into_bytes()),
+            AkdValue::from("value"),
+        )])
+        .await
+        .unwrap();
+    let proof = akd.audit(previous.epoch(), current.epoch()).await.unwrap();
+    let blob = AuditBlob::new(
+        previous.hash(),
+        current.hash(),
+        current.epoch(),
+        &proof.proofs[0],
+    )
+    .unwrap();
+    write_blob(dir, &blob);
+    blob
+}
+
+fn write_blob(dir: &Path, blob: &AuditBlob) {
+    let path = dir.join(blob.name.to_string());
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, &blob.data).unwrap();
+}
+
+test_config!(test_daemon_poll);
+async fn test_daemon_poll<TC: Configuration>() {
+    let proofs = TempDir::new().unwrap();
+    let checkpoint_dir = TempDir::new().unwrap();
+    let checkpoint = checkpoint_dir.path().join("checkpoint.json");
+    let source = ProofSource::parse(proofs.path().to_str().unwrap());
+    let akd = TestDirectory::<TC>::new(
+        StorageManager::new_no_cache(AsyncInMemoryDatabase::new()),
+        HardCodedAkdVRF {},
+    )
+    .await
+    .unwrap();
+
+    for _ in 0..3 {
+        publish_epoch(&akd, proofs.path()).await;
+    }
+    assert_eq!(3, poll::<TC>(&source, &checkpoint).await.unwrap());
+    assert_eq!(
+        Some(Checkpoint::new(
+            3,
+            akd.get_epoch_hash().await.unwrap().hash()
+        )),
+        Checkpoint::load(&checkpoint).unwrap()
+    );
+
+    // Only the new epochs are verified on the next poll
+    assert_eq!(0, poll::<TC>(&source, &checkpoint).await.unwrap());
+    publish_epoch(&akd, proofs.path()).await;
+    assert_eq!(1, poll::<TC>(&source, &checkpoint).await.unwrap());
+
+    // A proof which fails to verify does not advance the checkpoint
+    let mut blob = publish_epoch(&akd, proofs.path()).await;
+    blob.name.current_hash = [0u8; 32];
+    std::fs::remove_dir_all(proofs.path().join(blob.name.epoch.to_string())).unwrap();
+    write_blob(proofs.path(), &blob);
+    assert!(matches!(
+        poll::<TC>(&source, &checkpoint).await,
+        Err(PollError::Verification(_))
+    ));
+    assert_eq!(4, Checkpoint::load(&checkpoint).unwrap().unwrap().epoch);
+}
+
+test_config!(test_daemon_detects_inconsistent_history);
+async fn test_daemon_detects_inconsistent_history<TC: Configuration>() {
+    let proofs = TempDir::new().unwrap();
+    let checkpoint_dir = TempDir::new().unwrap();
+    let checkpoint = checkpoint_dir.path().join("checkpoint.json");
+    let source = ProofSource::parse(proofs.path().to_str().unwrap());
+    let akd = TestDirectory::<TC>::new(
+        StorageManager::new_no_cache(AsyncInMemoryDatabase::new()),
+        HardCodedAkdVRF {},
+    )
+    .await
+    .unwrap();
+
+    publish_epoch(&akd, proofs.path()).await;
+    assert_eq!(1, poll::<TC>(&source, &checkpoint).await.unwrap());
+
+    // A gap in the published epochs
+    let skipped = publish_epoch(&akd, proofs.path()).await;
+    std::fs::remove_dir_all(proofs.path().join(skipped.name.epoch.to_string())).unwrap();
+    publish_epoch(&akd, proofs.path()).await;
+    assert!(matches!(
+        poll::<TC>(&source, &checkpoint).await,
+        Err(PollError::Verification(_))
+    ));
+
+    // A checkpoint whose root hash differs from the published history
+    write_blob(proofs.path(), &skipped);
+    Checkpoint::new(1, [1u8; 32]).store(&checkpoint).unwrap();
+    assert!(matches!(
+        poll::<TC>(&source, &checkpoint).await,
+        Err(PollError::Verification(_))
+    ));
+}
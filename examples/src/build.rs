@@ -0,0 +1,81 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! This is the pre-compilation build script for the crate `examples`. It compiles the protobuf
+//! specification of the gRPC server example into rust code, along with the stubs of its service.
+
+// NOTE: build.rs documentation = https://doc.rust-lang.org/cargo/reference/build-scripts.html
+
+/// The path to the protobuf specification of the gRPC service
+const SERVICE_PROTOBUF_FILE: &str = "src/grpc_server/specs/service.proto";
+/// The shared-path for the protobuf specifications of akd_core, which the service imports
+const AKD_PROTOBUF_DIRECTORY: &str = "../akd_core/src/proto/specs";
+/// The output directory in the cargo build folder to emit the generated sources to
+const PROTOS_OUTPUT_DIR: &str = "protos";
+
+/// The methods of the service, as (method name, route name, request type, response type)
+const SERVICE_METHODS: [(&str, &str, &str, &str); 6] = [
+    ("publish", "Publish", "PublishRequest", "EpochHash"),
+    (
+        "get_epoch_hash",
+        "GetEpochHash",
+        "EpochHashRequest",
+        "EpochHash",
+    ),
+    (
+        "get_public_key",
+        "GetPublicKey",
+        "PublicKeyRequest",
+        "PublicKeyResponse",
+    ),
+    ("lookup", "Lookup", "LookupRequest", "LookupResponse"),
+    (
+        "key_history",
+        "KeyHistory",
+        "KeyHistoryRequest",
+        "KeyHistoryResponse",
+    ),
+    ("audit", "Audit", "AuditRequest", "AuditResponse"),
+];
+
+fn build_protobufs() {
+    println!("cargo:rerun-if-changed={SERVICE_PROTOBUF_FILE}");
+
+    // Code generator writes to the output directory. Only the messages of the service are
+    // generated, as those of the proofs are provided by akd_core
+    protobuf_codegen::Codegen::new()
+        .pure()
+        .includes(["src/grpc_server/specs", AKD_PROTOBUF_DIRECTORY])
+        .input(SERVICE_PROTOBUF_FILE)
+        .cargo_out_dir(PROTOS_OUTPUT_DIR)
+        .run_from_script();
+}
+
+fn build_service() {
+    // The stubs are generated from the methods declared above (which must match those of the
+    // specification), with a codec for the messages generated by rust-protobuf
+    let mut service = tonic_build::manual::Service::builder()
+        .name("DirectoryService")
+        .package("akd");
+    for (name, route_name, request, response) in SERVICE_METHODS {
+        service = service.method(
+            tonic_build::manual::Method::builder()
+                .name(name)
+                .route_name(route_name)
+                .input_type(format!("crate::grpc_server::specs::service::{request}"))
+                .output_type(format!("crate::grpc_server::specs::service::{response}"))
+                .codec_path("crate::grpc_server::codec::ProtobufCodec")
+                .build(),
+        );
+    }
+    tonic_build::manual::Builder::new().compile(&[service.build()]);
+}
+
+fn main() {
+    build_protobufs();
+    build_service();
+}
@@ -16,13 +16,21 @@ use std::io::Write;
 use akd::directory::Directory;
 use akd::storage::types::DbRecord;
 use akd::storage::{StorageManager, StorageUtil};
-use akd::{AkdLabel, AkdValue, DomainLabel, NamedConfiguration};
+use akd::verify::VerificationError;
+use akd::{
+    AkdLabel, AkdValue, DomainLabel, HistoryProof, HistoryVerificationParams, LookupProof,
+    NamedConfiguration,
+};
 use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
 use rand::Rng;
 use rand::SeedableRng;
 use serde::{Deserialize, Serialize};
 
-use crate::fixture_generator::parser::Args;
+use crate::fixture_generator::parser::{Args, Format, Params};
+use crate::fixture_generator::tampering::{FailureCategory, Tampering};
+use crate::fixture_generator::writer::bin::BinWriter;
+use crate::fixture_generator::writer::json::JsonWriter;
 use crate::fixture_generator::writer::yaml::YamlWriter;
 use crate::fixture_generator::writer::Writer;
 
@@ -41,6 +49,36 @@ pub struct Delta {
     pub updates: Vec<(AkdLabel, AkdValue)>,
 }
 
+/// History comprises a key history proof for a label at the last epoch.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct History {
+    pub epoch: u32,
+    pub label: AkdLabel,
+    pub params: Params,
+    pub proof: HistoryProof,
+}
+
+/// A proof which has been tampered with.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TamperedProof {
+    Lookup(Box<LookupProof>),
+    History { params: Params, proof: HistoryProof },
+}
+
+/// Tampered comprises a tampered proof for a label at the last epoch, along
+/// with the (hex-encoded) root hash and VRF public key it should be verified
+/// against, and the category of error its verification fails with.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tampered {
+    pub epoch: u32,
+    pub root_hash: String,
+    pub vrf_public_key: String,
+    pub label: AkdLabel,
+    pub tampering: Tampering,
+    pub proof: TamperedProof,
+    pub expected_failure: FailureCategory,
+}
+
 /// Metadata about the output, including arguments passed to this tool and
 /// the tool version.
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -61,27 +99,55 @@ const HEADER_COMMENT: &str = concat!(
 const METADATA_COMMENT: &str = "Metadata";
 const STATE_COMMENT: &str = "State - Epoch";
 const DELTA_COMMENT: &str = "Delta - Epoch";
+const HISTORY_COMMENT: &str = "History - Label";
+const TAMPERED_COMMENT: &str = "Tampered - Label";
 
 pub async fn run(args: Args) {
     // NOTE(new_config): Add new configurations here
     type L = akd::ExampleLabel;
     generate::<akd::WhatsAppV1Configuration, L>(&args).await;
     generate::<akd::ExperimentalConfiguration<L>, L>(&args).await;
+    generate::<akd::Sha3Configuration<L>, L>(&args).await;
 }
 
 pub(crate) async fn generate<TC: NamedConfiguration, L: DomainLabel>(args: &Args) {
-    let mut rng = StdRng::seed_from_u64(42);
+    // initialize writer
+    let buffer: Box<dyn Write> = if let Some(ref file_path) = args.out {
+        Box::new(
+            File::create(format!(
+                "{}/{}.{}",
+                file_path,
+                TC::name(),
+                args.format.extension()
+            ))
+            .unwrap(),
+        )
+    } else {
+        Box::new(std::io::stdout())
+    };
+    match args.format {
+        Format::Json => write_fixture::<TC, L, _>(args, JsonWriter::new(buffer)).await,
+        Format::Yaml => write_fixture::<TC, L, _>(args, YamlWriter::new(buffer)).await,
+        Format::Bin => write_fixture::<TC, L, _>(args, BinWriter::new(buffer)).await,
+    }
+}
+
+async fn write_fixture<TC: NamedConfiguration, L: DomainLabel, W: Writer>(
+    args: &Args,
+    mut writer: W,
+) {
+    let mut rng = StdRng::seed_from_u64(args.seed);
 
     // args assertions
     assert!(args.max_updates >= args.min_updates);
     assert!(args
         .capture_states
         .as_ref()
-        .map_or(true, |states| states.iter().max().unwrap() <= &args.epochs));
+        .is_none_or(|states| states.iter().max().unwrap() <= &args.epochs));
     assert!(args
         .capture_deltas
         .as_ref()
-        .map_or(true, |deltas| deltas.iter().max().unwrap() <= &args.epochs));
+        .is_none_or(|deltas| deltas.iter().max().unwrap() <= &args.epochs));
 
     // process users
     let mut user_map = HashMap::new();
@@ -93,13 +159,10 @@ pub(crate) async fn generate<TC: NamedConfiguration, L: DomainLabel>(args: &Args
         user_map.insert(user.label.clone(), events_map);
     }
 
-    // initialize writer
-    let buffer: Box<dyn Write> = if let Some(ref file_path) = args.out {
-        Box::new(File::create(format!("{}/{}.yaml", file_path, TC::name())).unwrap())
-    } else {
-        Box::new(std::io::stdout())
-    };
-    let mut writer = YamlWriter::new(buffer);
+    // generate the labels random key updates are drawn from, if any
+    let labels = (0..args.num_labels.unwrap_or(0))
+        .map(|_| AkdLabel::random(&mut rng))
+        .collect::<Vec<_>>();
 
     // write raw args as comment
     let raw_args = format!(
@@ -148,8 +211,25 @@ pub(crate) async fn generate<TC: NamedConfiguration, L: DomainLabel>(args: &Args
         // generate random key updates if allowed
         if !args.no_generated_updates {
             let num_updates = rng.gen_range(args.min_updates..args.max_updates);
-            for _ in updates.len()..num_updates as usize {
-                updates.push((AkdLabel::random(&mut rng), AkdValue::random(&mut rng)));
+            let num_generated = (num_updates as usize).saturating_sub(updates.len());
+            if labels.is_empty() {
+                for _ in 0..num_generated {
+                    updates.push((AkdLabel::random(&mut rng), AkdValue::random(&mut rng)));
+                }
+            } else {
+                // a label can only be updated once per epoch
+                let candidates = labels
+                    .iter()
+                    .filter(|label| !updates.iter().any(|(updated, _)| updated == *label))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                let chosen = candidates
+                    .choose_multiple(&mut rng, num_generated)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                for label in chosen {
+                    updates.push((label, AkdValue::random(&mut rng)));
+                }
             }
         }
 
@@ -192,6 +272,106 @@ pub(crate) async fn generate<TC: NamedConfiguration, L: DomainLabel>(args: &Args
         }
     }
 
+    // write key history proofs if required
+    let history_labels = args
+        .users
+        .iter()
+        .map(|user| user.label.clone())
+        .chain(labels)
+        .collect::<Vec<_>>();
+    if !args.params.is_empty() {
+        for label in history_labels.iter().cloned() {
+            for params in &args.params {
+                // labels without any published key updates have no history
+                let Ok((proof, _)) = akd.key_history(&label, (*params).into()).await else {
+                    continue;
+                };
+                let comment = format!("{HISTORY_COMMENT} {} {params:?}", hex::encode(&label.0));
+                let history = History {
+                    epoch: args.epochs,
+                    label: label.clone(),
+                    params: *params,
+                    proof,
+                };
+                writer.write_line();
+                writer.write_comment(&comment);
+                writer.write_object(history);
+            }
+        }
+    }
+
+    // write tampered proofs if required
+    if args.tampered {
+        let epoch_hash = akd.get_epoch_hash().await.unwrap();
+        let vrf_public_key = akd.get_public_key().await.unwrap();
+        let verify = |label: &AkdLabel, proof: &TamperedProof| -> Result<(), VerificationError> {
+            match proof {
+                TamperedProof::Lookup(proof) => akd::client::lookup_verify::<TC>(
+                    vrf_public_key.as_bytes(),
+                    epoch_hash.hash(),
+                    epoch_hash.epoch(),
+                    label.clone(),
+                    *proof.clone(),
+                )
+                .map(|_| ()),
+                TamperedProof::History { params, proof } => akd::client::key_history_verify::<TC>(
+                    vrf_public_key.as_bytes(),
+                    epoch_hash.hash(),
+                    epoch_hash.epoch(),
+                    label.clone(),
+                    proof.clone(),
+                    HistoryVerificationParams::Default {
+                        history_params: (*params).into(),
+                    },
+                )
+                .map(|_| ()),
+            }
+        };
+
+        for label in history_labels {
+            // labels without any published key updates have no proofs to tamper with
+            let Ok((lookup_proof, _)) = akd.lookup(label.clone()).await else {
+                continue;
+            };
+            let mut history_proofs = vec![];
+            for params in &args.params {
+                let (proof, _) = akd.key_history(&label, (*params).into()).await.unwrap();
+                history_proofs.push((*params, proof));
+            }
+
+            for tampering in Tampering::ALL {
+                let lookup = tampering
+                    .tamper_lookup(&lookup_proof)
+                    .map(|proof| TamperedProof::Lookup(Box::new(proof)));
+                let histories = history_proofs.iter().filter_map(|(params, proof)| {
+                    tampering
+                        .tamper_history(proof)
+                        .map(|proof| TamperedProof::History {
+                            params: *params,
+                            proof,
+                        })
+                });
+                for proof in lookup.into_iter().chain(histories) {
+                    let error = verify(&label, &proof).expect_err("Tampered proof verified");
+                    let comment =
+                        format!("{TAMPERED_COMMENT} {} {tampering:?}", hex::encode(&label.0));
+                    let tampered = Tampered {
+                        epoch: args.epochs,
+                        root_hash: hex::encode(epoch_hash.hash()),
+                        vrf_public_key: hex::encode(vrf_public_key.as_bytes()),
+                        label: label.clone(),
+                        tampering,
+                        proof,
+                        expected_failure: FailureCategory::from(&error),
+                    };
+                    writer.write_line();
+                    writer.write_comment(&comment);
+                    writer.write_object(tampered);
+                }
+            }
+        }
+    }
+
     // flush writer and exit
     writer.flush();
 }
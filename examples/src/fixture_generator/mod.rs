@@ -21,6 +21,7 @@ mod examples;
 mod generator;
 mod parser;
 mod reader;
+mod tampering;
 mod writer;
 
 pub(crate) use parser::Args;
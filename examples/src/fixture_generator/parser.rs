@@ -7,8 +7,8 @@
 
 //! This module contains the CLI argument definitions and parser.
 
-use akd::{AkdLabel, AkdValue};
-use clap::Parser;
+use akd::{AkdLabel, AkdValue, HistoryParams};
+use clap::{Parser, ValueEnum};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 
@@ -34,6 +34,45 @@ pub struct User {
     pub events: Vec<UserEvent>,
 }
 
+/// The parameters of a key history proof the tool should emit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Params {
+    Complete,
+    MostRecent(usize),
+}
+
+impl From<Params> for HistoryParams {
+    fn from(params: Params) -> Self {
+        match params {
+            Params::Complete => HistoryParams::Complete,
+            Params::MostRecent(n) => HistoryParams::MostRecent(n),
+        }
+    }
+}
+
+/// The format of the tool output.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Format {
+    /// One JSON object per line, without comments
+    Json,
+    /// YAML docs, with comments describing each doc
+    #[default]
+    Yaml,
+    /// A sequence of CBOR data items, without comments
+    Bin,
+}
+
+impl Format {
+    /// The extension of the output files in this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Yaml => "yaml",
+            Format::Bin => "bin",
+        }
+    }
+}
+
 /// This tool allows a directory to be created with specified and random
 /// contents, capturing the directory state and epoch-to-epoch delta in
 /// an output file for use in debugging and as test fixtures.
@@ -57,9 +96,22 @@ pub struct Args {
 
     /// Number of epochs to advance the tree by
     /// e.g. a value of 3 will perform 3 publishes on an empty directory.
-    #[arg(long = "epochs", short = 'e')]
+    #[arg(long = "epochs", short = 'e', visible_alias = "num_epochs")]
     pub epochs: u32,
 
+    /// Number of labels the randomly generated key updates are drawn from.
+    /// If omitted, each generated key update is for a new random label.
+    /// Drawing from a small number of labels results in labels with many
+    /// versions.
+    #[arg(long = "num_labels")]
+    #[serde(default)]
+    pub num_labels: Option<u32>,
+
+    /// Seed of the randomly generated labels and values.
+    #[arg(long = "seed", default_value = "42")]
+    #[serde(default = "default_seed")]
+    pub seed: u64,
+
     /// Maximum number of key updates **per epoch** the tool should perform.
     /// Note that all user events explicitly passed for an epoch will be
     /// included even if the number exceeds this value.
@@ -86,11 +138,35 @@ pub struct Args {
     #[arg(long = "capture_deltas", short = 'd', num_args = 0..)]
     pub capture_deltas: Option<Vec<u32>>,
 
+    /// Key history proofs that should be captured in the output after the
+    /// last epoch, for each explicitly passed user and each of the labels
+    /// the generated key updates are drawn from.
+    /// Accepts "complete" or "most_recent:<n>", and multiple values are
+    /// accepted e.g. --params complete most_recent:1
+    #[arg(long = "params", num_args = 0.., value_parser = parse_params)]
+    #[serde(default)]
+    pub params: Vec<Params>,
+
+    /// Captures systematically tampered proofs in the output after the last
+    /// epoch, along with the category of error their verification fails with.
+    /// Tampered lookup proofs are captured for the same labels as the key
+    /// history proofs, and tampered key history proofs for each of the params.
+    #[arg(long = "tampered")]
+    #[serde(default)]
+    pub tampered: bool,
+
     /// Name of output path.
     /// If omitted, output will be printed to stdout.
     #[arg(long = "out", short = 'o')]
     pub out: Option<String>,
 
+    /// Format of the output.
+    /// JSON and binary (CBOR) output can be consumed natively by client
+    /// implementations in other languages.
+    #[arg(long = "format", value_enum, default_value = "yaml")]
+    #[serde(default)]
+    pub format: Format,
+
     /// Stops tool from generating random key updates in publishes.
     /// Use this if you want the tool to only use explicitly passed key updates.
     /// Explicilty passed key updates without values would still use randomly
@@ -99,6 +175,24 @@ pub struct Args {
     pub no_generated_updates: bool,
 }
 
+fn default_seed() -> u64 {
+    42
+}
+
+fn parse_params(s: &str) -> Result<Params, String> {
+    match s.trim().split_once(':') {
+        None if s.trim() == "complete" => Ok(Params::Complete),
+        Some(("most_recent", n)) => n
+            .trim()
+            .parse()
+            .map(Params::MostRecent)
+            .map_err(|err| format!("Invalid number of most recent updates: {err}")),
+        _ => Err(format!(
+            "Invalid params {s}, expected \"complete\" or \"most_recent:<n>\""
+        )),
+    }
+}
+
 fn parse_user_events(s: &str) -> Result<User, String> {
     let mut split = s.split(':');
     let username_text = split.next().unwrap();
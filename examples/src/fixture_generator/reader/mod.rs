@@ -10,7 +10,11 @@
 
 use std::result::Result;
 
-use crate::fixture_generator::generator::{Delta, Metadata, State};
+use akd::AkdLabel;
+
+use crate::fixture_generator::generator::{Delta, History, Metadata, State, Tampered};
+use crate::fixture_generator::parser::Params;
+use crate::fixture_generator::tampering::Tampering;
 
 /// Interface for reading output generated by the tool.
 pub trait Reader {
@@ -25,6 +29,20 @@ pub trait Reader {
     /// Reads a delta object for a given epoch.
     #[allow(dead_code)]
     fn read_delta(&mut self, epoch: u32) -> Result<Delta, ReaderError>;
+
+    /// Reads a history object for a given label and params.
+    #[allow(dead_code)]
+    fn read_history(&mut self, label: &AkdLabel, params: Params) -> Result<History, ReaderError>;
+
+    /// Reads a tampered object for a given label and tampering, of a lookup
+    /// proof if params is None, or of a key history proof otherwise.
+    #[allow(dead_code)]
+    fn read_tampered(
+        &mut self,
+        label: &AkdLabel,
+        tampering: Tampering,
+        params: Option<Params>,
+    ) -> Result<Tampered, ReaderError>;
 }
 
 #[derive(Debug, PartialEq, Eq)]
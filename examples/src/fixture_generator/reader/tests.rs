@@ -10,14 +10,15 @@
 use std::env;
 use std::fs::File;
 
-use akd::NamedConfiguration;
+use akd::{AkdLabel, NamedConfiguration};
 use assert_fs::fixture::{FileWriteStr, NamedTempFile, TempDir};
 use clap::Parser;
 
 use crate::fixture_generator::generator;
-use crate::fixture_generator::parser::Args;
+use crate::fixture_generator::parser::{Args, Params};
 use crate::fixture_generator::reader::yaml::YamlFileReader;
 use crate::fixture_generator::reader::{Reader, ReaderError};
+use crate::fixture_generator::tampering::{FailureCategory, Tampering};
 use crate::test_config;
 
 type L = akd::ExampleLabel;
@@ -59,6 +60,167 @@ async fn test_read<TC: NamedConfiguration>() {
     assert!(reader.read_metadata().is_ok());
 }
 
+test_config!(test_read_history);
+async fn test_read_history<TC: NamedConfiguration>() {
+    // generate a temp fixture file with key updates drawn from a few labels
+    let file = TempDir::new()
+        .unwrap()
+        .with_file_name(format!("{}.yaml", TC::name()));
+    let args = Args::parse_from(vec![
+        env!("CARGO_CRATE_NAME"),
+        "--user",
+        "User1: 1, 3",
+        "--num_epochs",
+        "5",
+        "--num_labels",
+        "3",
+        "--min_updates",
+        "2",
+        "--max_updates",
+        "4",
+        "--seed",
+        "7",
+        "--params",
+        "complete",
+        "most_recent:1",
+        "--out",
+        &format!("{}", file.parent().unwrap().display()),
+    ]);
+    assert_eq!(5, args.epochs);
+    assert_eq!(vec![Params::Complete, Params::MostRecent(1)], args.params);
+    generator::generate::<TC, L>(&args).await;
+
+    // initialize reader
+    let mut reader = YamlFileReader::new(File::open(file).unwrap()).unwrap();
+    assert_eq!(args, reader.read_metadata().unwrap().args);
+
+    // the key history proofs of the users are captured for each params
+    let label = AkdLabel::from("User1");
+    let complete = reader.read_history(&label, Params::Complete).unwrap();
+    assert_eq!(5, complete.epoch);
+    assert_eq!(2, complete.proof.update_proofs.len());
+    let most_recent = reader.read_history(&label, Params::MostRecent(1)).unwrap();
+    assert_eq!(1, most_recent.proof.update_proofs.len());
+    assert_eq!(
+        Err(ReaderError::NotFound),
+        reader.read_history(&label, Params::MostRecent(2))
+    );
+}
+
+test_config!(test_read_tampered);
+async fn test_read_tampered<TC: NamedConfiguration>() {
+    // generate a temp fixture file with tampered proofs
+    let file = TempDir::new()
+        .unwrap()
+        .with_file_name(format!("{}.yaml", TC::name()));
+    let args = Args::parse_from(vec![
+        env!("CARGO_CRATE_NAME"),
+        "--user",
+        "User1: 1, 3",
+        "--user",
+        "User2: 2",
+        "--epochs",
+        "4",
+        "--params",
+        "complete",
+        "--tampered",
+        "--out",
+        &format!("{}", file.parent().unwrap().display()),
+    ]);
+    generator::generate::<TC, L>(&args).await;
+
+    // initialize reader
+    let mut reader = YamlFileReader::new(File::open(file).unwrap()).unwrap();
+
+    // each tampering is captured with the category of error it fails with
+    let user1 = AkdLabel::from("User1");
+    let user2 = AkdLabel::from("User2");
+    for (label, tampering, params, expected_failure) in [
+        (
+            &user1,
+            Tampering::WrongEpoch,
+            None,
+            FailureCategory::HashMismatch,
+        ),
+        (
+            &user1,
+            Tampering::FlippedSiblingHash,
+            None,
+            FailureCategory::HashMismatch,
+        ),
+        (
+            &user1,
+            Tampering::TruncatedVrfProof,
+            None,
+            FailureCategory::Vrf,
+        ),
+        (
+            &user1,
+            Tampering::WrongEpoch,
+            Some(Params::Complete),
+            FailureCategory::HashMismatch,
+        ),
+        (
+            &user1,
+            Tampering::TruncatedVrfProof,
+            Some(Params::Complete),
+            FailureCategory::Vrf,
+        ),
+        (
+            &user1,
+            Tampering::ReorderedUpdateProofs,
+            Some(Params::Complete),
+            FailureCategory::Ordering,
+        ),
+        (
+            &user2,
+            Tampering::TruncatedVrfProof,
+            Some(Params::Complete),
+            FailureCategory::Vrf,
+        ),
+    ] {
+        let tampered = reader.read_tampered(label, tampering, params).unwrap();
+        assert_eq!(4, tampered.epoch);
+        assert_eq!(
+            expected_failure, tampered.expected_failure,
+            "{tampering:?} {params:?}"
+        );
+    }
+
+    // tamperings which do not apply to a proof are not captured
+    assert_eq!(
+        Err(ReaderError::NotFound),
+        reader.read_tampered(&user1, Tampering::ReorderedUpdateProofs, None)
+    );
+    assert_eq!(
+        Err(ReaderError::NotFound),
+        reader.read_tampered(
+            &user2,
+            Tampering::ReorderedUpdateProofs,
+            Some(Params::Complete)
+        )
+    );
+}
+
+#[test]
+fn test_parse_params() {
+    let parse = |params: &str| {
+        Args::try_parse_from(vec![
+            env!("CARGO_CRATE_NAME"),
+            "--epochs",
+            "1",
+            "--params",
+            params,
+        ])
+        .map(|args| args.params)
+    };
+    assert_eq!(vec![Params::Complete], parse("complete").unwrap());
+    assert_eq!(vec![Params::MostRecent(3)], parse("most_recent:3").unwrap());
+    assert!(parse("most_recent").is_err());
+    assert!(parse("most_recent:x").is_err());
+    assert!(parse("partial").is_err());
+}
+
 #[tokio::test]
 async fn test_read_invalid_format() {
     // create an invalid file with no YAML separators
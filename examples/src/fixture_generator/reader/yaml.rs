@@ -13,10 +13,15 @@ use std::io::{BufRead, BufReader, Lines, Seek};
 use std::iter::Peekable;
 use std::result::Result; // import without risk of name clashing
 
+use akd::AkdLabel;
 use serde::de::DeserializeOwned;
 
-use crate::fixture_generator::generator::{Delta, Metadata, State};
+use crate::fixture_generator::generator::{
+    Delta, History, Metadata, State, Tampered, TamperedProof,
+};
+use crate::fixture_generator::parser::Params;
 use crate::fixture_generator::reader::{Reader, ReaderError};
+use crate::fixture_generator::tampering::Tampering;
 use crate::fixture_generator::YAML_SEPARATOR;
 
 impl From<std::io::Error> for ReaderError {
@@ -132,4 +137,23 @@ impl Reader for YamlFileReader {
     fn read_delta(&mut self, epoch: u32) -> Result<Delta, ReaderError> {
         self.read_impl(|delta: &Delta| delta.epoch == epoch)
     }
+
+    fn read_history(&mut self, label: &AkdLabel, params: Params) -> Result<History, ReaderError> {
+        self.read_impl(|history: &History| &history.label == label && history.params == params)
+    }
+
+    fn read_tampered(
+        &mut self,
+        label: &AkdLabel,
+        tampering: Tampering,
+        params: Option<Params>,
+    ) -> Result<Tampered, ReaderError> {
+        self.read_impl(|tampered: &Tampered| {
+            let proof_params = match &tampered.proof {
+                TamperedProof::Lookup(_) => None,
+                TamperedProof::History { params, .. } => Some(*params),
+            };
+            &tampered.label == label && tampered.tampering == tampering && proof_params == params
+        })
+    }
 }
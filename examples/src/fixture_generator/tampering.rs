@@ -0,0 +1,124 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! This module contains the systematic modifications of valid proofs which the
+//! tool applies to produce negative test vectors, along with the categories of
+//! the verification errors that the tampered proofs are expected to fail with.
+
+use akd::verify::VerificationError;
+use akd::{HistoryProof, LookupProof, MembershipProof};
+use serde::{Deserialize, Serialize};
+
+/// A modification of a valid proof, which causes its verification to fail.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Tampering {
+    /// The epoch at which the (most recent) value was published is incremented.
+    WrongEpoch,
+    /// A bit of the first sibling hash of the (most recent) existence proof is
+    /// flipped.
+    FlippedSiblingHash,
+    /// The last byte of the (most recent) existence VRF proof is removed.
+    TruncatedVrfProof,
+    /// The two most recent update proofs of a history proof are swapped.
+    ReorderedUpdateProofs,
+}
+
+impl Tampering {
+    /// All of the modifications, in the order they are applied by the tool.
+    pub const ALL: [Tampering; 4] = [
+        Tampering::WrongEpoch,
+        Tampering::FlippedSiblingHash,
+        Tampering::TruncatedVrfProof,
+        Tampering::ReorderedUpdateProofs,
+    ];
+
+    /// Applies the modification to a lookup proof, returning None if the
+    /// modification does not apply to the proof.
+    pub fn tamper_lookup(&self, proof: &LookupProof) -> Option<LookupProof> {
+        let mut proof = proof.clone();
+        match self {
+            Tampering::WrongEpoch => proof.epoch += 1,
+            Tampering::FlippedSiblingHash => flip_sibling_hash(&mut proof.existence_proof)?,
+            Tampering::TruncatedVrfProof => {
+                proof.existence_vrf_proof.pop()?;
+            }
+            Tampering::ReorderedUpdateProofs => return None,
+        }
+        Some(proof)
+    }
+
+    /// Applies the modification to a key history proof, returning None if the
+    /// modification does not apply to the proof.
+    pub fn tamper_history(&self, proof: &HistoryProof) -> Option<HistoryProof> {
+        let mut proof = proof.clone();
+        match self {
+            Tampering::WrongEpoch => proof.update_proofs.first_mut()?.epoch += 1,
+            Tampering::FlippedSiblingHash => {
+                flip_sibling_hash(&mut proof.update_proofs.first_mut()?.existence_proof)?
+            }
+            Tampering::TruncatedVrfProof => {
+                proof.update_proofs.first_mut()?.existence_vrf_proof.pop()?;
+            }
+            Tampering::ReorderedUpdateProofs => {
+                if proof.update_proofs.len() < 2 {
+                    return None;
+                }
+                proof.update_proofs.swap(0, 1);
+            }
+        }
+        Some(proof)
+    }
+}
+
+fn flip_sibling_hash(proof: &mut MembershipProof) -> Option<()> {
+    let sibling = proof.sibling_proofs.first_mut()?;
+    sibling.siblings[0].value.0[0] ^= 1;
+    Some(())
+}
+
+/// The category of a verification error, which corresponds to the variant of
+/// the error.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FailureCategory {
+    MembershipProof,
+    NonMembershipProof,
+    LookupProof,
+    HistoryProof,
+    EpochMetadata,
+    EpochSignature,
+    EpochBundle,
+    KeyRotation,
+    MergeBridge,
+    RootChain,
+    Vrf,
+    Serialization,
+    HashMismatch,
+    Ordering,
+    MalformedProof,
+}
+
+impl From<&VerificationError> for FailureCategory {
+    fn from(error: &VerificationError) -> Self {
+        match error {
+            VerificationError::MembershipProof(_) => FailureCategory::MembershipProof,
+            VerificationError::NonMembershipProof(_) => FailureCategory::NonMembershipProof,
+            VerificationError::LookupProof(_) => FailureCategory::LookupProof,
+            VerificationError::HistoryProof(_) => FailureCategory::HistoryProof,
+            VerificationError::EpochMetadata(_) => FailureCategory::EpochMetadata,
+            VerificationError::EpochSignature(_) => FailureCategory::EpochSignature,
+            VerificationError::EpochBundle(_) => FailureCategory::EpochBundle,
+            VerificationError::KeyRotation(_) => FailureCategory::KeyRotation,
+            VerificationError::MergeBridge(_) => FailureCategory::MergeBridge,
+            VerificationError::RootChain(_) => FailureCategory::RootChain,
+            VerificationError::Vrf(_) => FailureCategory::Vrf,
+            VerificationError::Serialization(_) => FailureCategory::Serialization,
+            VerificationError::HashMismatch { .. } => FailureCategory::HashMismatch,
+            VerificationError::Ordering(_) => FailureCategory::Ordering,
+            VerificationError::MalformedProof(_) => FailureCategory::MalformedProof,
+        }
+    }
+}
@@ -0,0 +1,41 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! This module contains an implementor of the Writer trait for a binary format.
+
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::fixture_generator::writer::Writer;
+
+/// Binary format writer, which writes each object as a CBOR data item (RFC 8949),
+/// so that the output is a CBOR sequence (RFC 8742). Comments and newlines are
+/// not written.
+pub(crate) struct BinWriter<T: Write> {
+    out: T,
+}
+
+impl<T: Write> BinWriter<T> {
+    pub fn new(out: T) -> Self {
+        Self { out }
+    }
+}
+
+impl<T: Write> Writer for BinWriter<T> {
+    fn write_object(&mut self, object: impl Serialize) {
+        ciborium::into_writer(&object, &mut self.out).unwrap();
+    }
+
+    fn write_comment(&mut self, _comment: &str) {}
+
+    fn write_line(&mut self) {}
+
+    fn flush(&mut self) {
+        self.out.flush().unwrap();
+    }
+}
@@ -0,0 +1,41 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! This module contains an implementor of the Writer trait for the JSON format.
+
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::fixture_generator::writer::Writer;
+
+/// JSON format writer, which writes one object per line. Since JSON does not
+/// support comments, comments and newlines are not written.
+pub(crate) struct JsonWriter<T: Write> {
+    out: T,
+}
+
+impl<T: Write> JsonWriter<T> {
+    pub fn new(out: T) -> Self {
+        Self { out }
+    }
+}
+
+impl<T: Write> Writer for JsonWriter<T> {
+    fn write_object(&mut self, object: impl Serialize) {
+        serde_json::to_writer(&mut self.out, &object).unwrap();
+        writeln!(self.out).unwrap();
+    }
+
+    fn write_comment(&mut self, _comment: &str) {}
+
+    fn write_line(&mut self) {}
+
+    fn flush(&mut self) {
+        self.out.flush().unwrap();
+    }
+}
@@ -25,5 +25,14 @@ pub(crate) trait Writer {
     fn flush(&mut self);
 }
 
+/// Binary implementor of Writer trait.
+pub(crate) mod bin;
+
+/// JSON implementor of Writer trait.
+pub(crate) mod json;
+
 /// YAML implementor of Writer trait.
 pub(crate) mod yaml;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,116 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Tests that the writers of each format output the same objects.
+
+use std::env;
+use std::fs::File;
+
+use akd::storage::types::DbRecord;
+use akd::NamedConfiguration;
+use assert_fs::fixture::TempDir;
+use clap::Parser;
+
+use crate::fixture_generator::generator::{self, Delta, Metadata, State};
+use crate::fixture_generator::parser::{Args, Format};
+use crate::fixture_generator::reader::yaml::YamlFileReader;
+use crate::fixture_generator::reader::Reader;
+use crate::test_config;
+
+type L = akd::ExampleLabel;
+
+/// Generates a fixture in the given format, returning its contents.
+async fn generate<TC: NamedConfiguration>(format: &str) -> (Args, Vec<u8>) {
+    let dir = TempDir::new().unwrap();
+    let args = Args::parse_from(vec![
+        env!("CARGO_CRATE_NAME"),
+        "--epochs",
+        "10",
+        "--capture_deltas",
+        "10",
+        "--capture_states",
+        "9",
+        "10",
+        "--format",
+        format,
+        "--out",
+        &format!("{}", dir.path().display()),
+    ]);
+    generator::generate::<TC, L>(&args).await;
+    let file = dir
+        .path()
+        .join(format!("{}.{}", TC::name(), args.format.extension()));
+    (args, std::fs::read(file).unwrap())
+}
+
+/// Strips the timestamps of the epoch metadata records of a state, which differ
+/// between generations of a fixture.
+fn without_timestamps(mut state: State) -> State {
+    for record in state.records.iter_mut() {
+        if let DbRecord::EpochMetadata(metadata) = record {
+            metadata.timestamp_ms = 0;
+        }
+    }
+    state
+}
+
+test_config!(test_write_formats);
+async fn test_write_formats<TC: NamedConfiguration>() {
+    // the YAML output is read back with the YAML reader
+    let dir = TempDir::new().unwrap();
+    let (args, yaml) = generate::<TC>("yaml").await;
+    assert_eq!(Format::Yaml, args.format);
+    let file = dir.path().join("fixture.yaml");
+    std::fs::write(&file, yaml).unwrap();
+    let mut reader = YamlFileReader::new(File::open(file).unwrap()).unwrap();
+    let metadata = reader.read_metadata().unwrap();
+    let state_9 = without_timestamps(reader.read_state(9).unwrap());
+    let delta_10 = reader.read_delta(10).unwrap();
+    let state_10 = without_timestamps(reader.read_state(10).unwrap());
+
+    // the JSON output has one object per line
+    let (args, json) = generate::<TC>("json").await;
+    assert_eq!(Format::Json, args.format);
+    let json = String::from_utf8(json).unwrap();
+    let mut lines = json.lines();
+    let json_metadata: Metadata = serde_json::from_str(lines.next().unwrap()).unwrap();
+    assert_eq!(metadata.configuration, json_metadata.configuration);
+    assert_eq!(Format::Json, json_metadata.args.format);
+    assert_eq!(
+        state_9,
+        without_timestamps(serde_json::from_str(lines.next().unwrap()).unwrap())
+    );
+    assert_eq!(
+        delta_10,
+        serde_json::from_str::<Delta>(lines.next().unwrap()).unwrap()
+    );
+    assert_eq!(
+        state_10,
+        without_timestamps(serde_json::from_str(lines.next().unwrap()).unwrap())
+    );
+    assert_eq!(None, lines.next());
+
+    // the binary output is a sequence of CBOR data items
+    let (args, bin) = generate::<TC>("bin").await;
+    assert_eq!(Format::Bin, args.format);
+    let mut bin = bin.as_slice();
+    let bin_metadata: Metadata = ciborium::from_reader(&mut bin).unwrap();
+    assert_eq!(metadata.configuration, bin_metadata.configuration);
+    assert_eq!(
+        state_9,
+        without_timestamps(ciborium::from_reader(&mut bin).unwrap())
+    );
+    assert_eq!(
+        delta_10,
+        ciborium::from_reader::<Delta, _>(&mut bin).unwrap()
+    );
+    assert_eq!(
+        state_10,
+        without_timestamps(ciborium::from_reader(&mut bin).unwrap())
+    );
+    assert!(bin.is_empty());
+}
@@ -0,0 +1,68 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! A gRPC codec for the messages generated by rust-protobuf, which are the ones that
+//! akd_core converts its proofs to
+
+use bytes::{Buf, BufMut};
+use protobuf::Message;
+use std::marker::PhantomData;
+use tonic::codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
+use tonic::Status;
+
+/// Encodes messages of type `E` and decodes messages of type `D`
+pub(crate) struct ProtobufCodec<E, D>(PhantomData<(E, D)>);
+
+impl<E, D> Default for ProtobufCodec<E, D> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<E: Message, D: Message> Codec for ProtobufCodec<E, D> {
+    type Encode = E;
+    type Decode = D;
+    type Encoder = ProtobufEncoder<E>;
+    type Decoder = ProtobufDecoder<D>;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        ProtobufEncoder(PhantomData)
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        ProtobufDecoder(PhantomData)
+    }
+}
+
+pub(crate) struct ProtobufEncoder<E>(PhantomData<E>);
+
+impl<E: Message> Encoder for ProtobufEncoder<E> {
+    type Item = E;
+    type Error = Status;
+
+    fn encode(&mut self, item: E, dst: &mut EncodeBuf<'_>) -> Result<(), Status> {
+        let bytes = item
+            .write_to_bytes()
+            .map_err(|err| Status::internal(format!("Failed to encode message: {err}")))?;
+        dst.put_slice(&bytes);
+        Ok(())
+    }
+}
+
+pub(crate) struct ProtobufDecoder<D>(PhantomData<D>);
+
+impl<D: Message> Decoder for ProtobufDecoder<D> {
+    type Item = D;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<D>, Status> {
+        let bytes = src.copy_to_bytes(src.remaining());
+        let message = D::parse_from_bytes(&bytes)
+            .map_err(|err| Status::invalid_argument(format!("Failed to decode message: {err}")))?;
+        Ok(Some(message))
+    }
+}
@@ -0,0 +1,231 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! A reference gRPC server, which serves the lookup, key history and audit proofs of a directory
+//! as protobuf messages. The service is defined in `specs/service.proto`, and the proofs are
+//! encoded with the messages of akd_core, so that clients can convert them back with
+//! [akd::proto] and verify them.
+//!
+//! The directory is held in memory, and can be populated with randomly generated users at
+//! startup or through the `Publish` method.
+
+mod codec;
+pub(crate) mod specs;
+
+#[cfg(test)]
+mod tests;
+
+use akd::ecvrf::HardCodedAkdVRF;
+use akd::errors::{AkdError, DirectoryError, StorageError};
+use akd::proto::specs::types;
+use akd::storage::memory::AsyncInMemoryDatabase;
+use akd::storage::{Database, StorageManager};
+use akd::{AkdLabel, AkdValue, Configuration, Directory, HistoryParams};
+use anyhow::Result;
+use clap::{Parser, ValueEnum};
+use protobuf::MessageField;
+use specs::service;
+use std::net::SocketAddr;
+use stubs::directory_service_server::DirectoryServiceServer;
+use tonic::transport::server::TcpIncoming;
+use tonic::{Request, Response, Status};
+
+/// The stubs of the service, which are generated by the build script of the crate
+pub(crate) mod stubs {
+    include!(concat!(env!("OUT_DIR"), "/akd.DirectoryService.rs"));
+}
+
+/// The configuration of the served directory
+#[derive(ValueEnum, Clone, Debug)]
+enum ServerConfiguration {
+    WhatsappV1,
+    Experimental,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub(crate) struct CliArgs {
+    /// The address on which the service is served
+    #[clap(long = "address", short = 'a', default_value = "127.0.0.1:50051")]
+    address: SocketAddr,
+
+    /// The number of users with random values to publish to the directory at startup
+    #[clap(long = "users", short = 'u', default_value = "0")]
+    users: usize,
+
+    /// The configuration of the directory
+    #[clap(
+        value_enum,
+        long = "configuration",
+        ignore_case = true,
+        default_value = "whatsapp-v1"
+    )]
+    configuration: ServerConfiguration,
+}
+
+pub(crate) async fn render_cli(args: CliArgs) -> Result<()> {
+    match args.configuration {
+        ServerConfiguration::WhatsappV1 => run::<akd::WhatsAppV1Configuration>(args).await,
+        ServerConfiguration::Experimental => {
+            run::<akd::ExperimentalConfiguration<akd::ExampleLabel>>(args).await
+        }
+    }
+}
+
+async fn run<TC: Configuration>(args: CliArgs) -> Result<()> {
+    let storage = StorageManager::new_no_cache(AsyncInMemoryDatabase::new());
+    let directory = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {}).await?;
+
+    if args.users > 0 {
+        let mut rng = rand::thread_rng();
+        let updates = (0..args.users)
+            .map(|_| (AkdLabel::random(&mut rng), AkdValue::random(&mut rng)))
+            .collect::<Vec<_>>();
+        let epoch_hash = directory.publish(updates).await?;
+        println!(
+            "Published {} users at epoch {}",
+            args.users,
+            epoch_hash.epoch()
+        );
+    }
+
+    let incoming = TcpIncoming::bind(args.address).map_err(|err| anyhow::anyhow!(err))?;
+    println!("Serving the directory on {}", args.address);
+    serve(directory, incoming).await
+}
+
+/// Serves the directory on the incoming connections
+pub(crate) async fn serve<TC: Configuration, S: Database + 'static>(
+    directory: Directory<TC, S, HardCodedAkdVRF>,
+    incoming: TcpIncoming,
+) -> Result<()> {
+    tonic::transport::Server::builder()
+        .add_service(DirectoryServiceServer::new(DirectoryServer { directory }))
+        .serve_with_incoming(incoming)
+        .await?;
+    Ok(())
+}
+
+/// The implementation of the service for a directory
+struct DirectoryServer<TC, S: Database> {
+    directory: Directory<TC, S, HardCodedAkdVRF>,
+}
+
+#[tonic::async_trait]
+impl<TC: Configuration, S: Database + 'static> stubs::directory_service_server::DirectoryService
+    for DirectoryServer<TC, S>
+{
+    async fn publish(
+        &self,
+        request: Request<service::PublishRequest>,
+    ) -> Result<Response<service::EpochHash>, Status> {
+        let updates = request
+            .into_inner()
+            .updates
+            .into_iter()
+            .map(|update| {
+                (
+                    AkdLabel(update.label().to_vec()),
+                    AkdValue::Bytes(update.value().to_vec()),
+                )
+            })
+            .collect();
+        let epoch_hash = self.directory.publish(updates).await.map_err(to_status)?;
+        Ok(Response::new(to_epoch_hash(epoch_hash)))
+    }
+
+    async fn get_epoch_hash(
+        &self,
+        _request: Request<service::EpochHashRequest>,
+    ) -> Result<Response<service::EpochHash>, Status> {
+        let epoch_hash = self.directory.get_epoch_hash().await.map_err(to_status)?;
+        Ok(Response::new(to_epoch_hash(epoch_hash)))
+    }
+
+    async fn get_public_key(
+        &self,
+        _request: Request<service::PublicKeyRequest>,
+    ) -> Result<Response<service::PublicKeyResponse>, Status> {
+        let public_key = self.directory.get_public_key().await.map_err(to_status)?;
+        let mut response = service::PublicKeyResponse::new();
+        response.set_public_key(public_key.as_bytes().to_vec());
+        Ok(Response::new(response))
+    }
+
+    async fn lookup(
+        &self,
+        request: Request<service::LookupRequest>,
+    ) -> Result<Response<service::LookupResponse>, Status> {
+        let label = AkdLabel(request.into_inner().label().to_vec());
+        let (proof, epoch_hash) = self.directory.lookup(label).await.map_err(to_status)?;
+        Ok(Response::new(service::LookupResponse {
+            proof: MessageField::some(types::LookupProof::from(&proof)),
+            epoch_hash: MessageField::some(to_epoch_hash(epoch_hash)),
+            ..Default::default()
+        }))
+    }
+
+    async fn key_history(
+        &self,
+        request: Request<service::KeyHistoryRequest>,
+    ) -> Result<Response<service::KeyHistoryResponse>, Status> {
+        let request = request.into_inner();
+        let params = match request.most_recent {
+            Some(most_recent) => HistoryParams::MostRecent(most_recent as usize),
+            None => HistoryParams::Complete,
+        };
+        let label = AkdLabel(request.label().to_vec());
+        let (proof, epoch_hash) = self
+            .directory
+            .key_history(&label, params)
+            .await
+            .map_err(to_status)?;
+        Ok(Response::new(service::KeyHistoryResponse {
+            proof: MessageField::some(types::HistoryProof::from(&proof)),
+            epoch_hash: MessageField::some(to_epoch_hash(epoch_hash)),
+            ..Default::default()
+        }))
+    }
+
+    async fn audit(
+        &self,
+        request: Request<service::AuditRequest>,
+    ) -> Result<Response<service::AuditResponse>, Status> {
+        let request = request.into_inner();
+        let proof = self
+            .directory
+            .audit(request.start_epoch(), request.end_epoch())
+            .await
+            .map_err(to_status)?;
+        Ok(Response::new(service::AuditResponse {
+            proof: MessageField::some(types::AppendOnlyProof::from(&proof)),
+            ..Default::default()
+        }))
+    }
+}
+
+fn to_epoch_hash(epoch_hash: akd::EpochHash) -> service::EpochHash {
+    let mut message = service::EpochHash::new();
+    message.set_epoch(epoch_hash.epoch());
+    message.set_root_hash(epoch_hash.hash().to_vec());
+    message
+}
+
+/// Maps the errors of the directory to the status codes of gRPC
+fn to_status(err: AkdError) -> Status {
+    match &err {
+        AkdError::Storage(StorageError::NotFound(_)) => Status::not_found(err.to_string()),
+        AkdError::Directory(
+            DirectoryError::Publish(_)
+            | DirectoryError::InvalidEpoch(_)
+            | DirectoryError::InvalidVersion(_),
+        ) => Status::invalid_argument(err.to_string()),
+        AkdError::Directory(DirectoryError::Overloaded(_)) => {
+            Status::resource_exhausted(err.to_string())
+        }
+        _ => Status::internal(err.to_string()),
+    }
+}
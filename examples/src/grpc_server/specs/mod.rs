@@ -0,0 +1,13 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! @generated code
+
+// The messages of the proofs, which the generated messages of the service refer to
+pub(crate) use akd::proto::specs::types;
+
+include!(concat!(env!("OUT_DIR"), "/protos/mod.rs"));
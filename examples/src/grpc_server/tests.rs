@@ -0,0 +1,161 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Tests the gRPC server by verifying the proofs it serves to a client
+
+use super::serve;
+use super::specs::service;
+use super::stubs::directory_service_client::DirectoryServiceClient;
+use crate::test_config;
+
+use akd::ecvrf::HardCodedAkdVRF;
+use akd::storage::memory::AsyncInMemoryDatabase;
+use akd::storage::StorageManager;
+use akd::{
+    AkdLabel, AkdValue, AppendOnlyProof, Configuration, Directory, HistoryParams, HistoryProof,
+    HistoryVerificationParams, LookupProof,
+};
+use tonic::transport::server::TcpIncoming;
+use tonic::transport::Channel;
+use tonic::Code;
+
+/// Starts a server for an empty directory on a local port, and connects a client to it
+async fn start_server<TC: Configuration>() -> DirectoryServiceClient<Channel> {
+    let directory = Directory::<TC, _, _>::new(
+        StorageManager::new_no_cache(AsyncInMemoryDatabase::new()),
+        HardCodedAkdVRF {},
+    )
+    .await
+    .unwrap();
+    let incoming = TcpIncoming::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+    let address = incoming.local_addr().unwrap();
+    tokio::spawn(serve(directory, incoming));
+    DirectoryServiceClient::connect(format!("http://{address}"))
+        .await
+        .unwrap()
+}
+
+fn publish_request(updates: &[(&str, &str)]) -> service::PublishRequest {
+    let mut request = service::PublishRequest::new();
+    for (label, value) in updates {
+        let mut update = service::LabelValue::new();
+        update.set_label(label.as_bytes().to_vec());
+        update.set_value(value.as_bytes().to_vec());
+        request.updates.push(update);
+    }
+    request
+}
+
+test_config!(test_grpc_server);
+async fn test_grpc_server<TC: Configuration>() {
+    let mut client = start_server::<TC>().await;
+    let public_key = client
+        .get_public_key(service::PublicKeyRequest::new())
+        .await
+        .unwrap()
+        .into_inner()
+        .public_key()
+        .to_vec();
+
+    let mut epoch_hashes = vec![client
+        .get_epoch_hash(service::EpochHashRequest::new())
+        .await
+        .unwrap()
+        .into_inner()];
+    for updates in [
+        [("hello", "world"), ("hello2", "world")],
+        [("hello", "world2"), ("hello3", "world")],
+    ] {
+        let epoch_hash = client
+            .publish(publish_request(&updates))
+            .await
+            .unwrap()
+            .into_inner();
+        epoch_hashes.push(epoch_hash);
+    }
+    assert_eq!(
+        epoch_hashes[2],
+        client
+            .get_epoch_hash(service::EpochHashRequest::new())
+            .await
+            .unwrap()
+            .into_inner()
+    );
+
+    // The served lookup proof verifies
+    let mut request = service::LookupRequest::new();
+    request.set_label(b"hello".to_vec());
+    let response = client.lookup(request).await.unwrap().into_inner();
+    let proof = LookupProof::try_from(response.proof.get_or_default()).unwrap();
+    let result = akd::client::lookup_verify::<TC>(
+        &public_key,
+        akd::hash::try_parse_digest(response.epoch_hash.root_hash()).unwrap(),
+        response.epoch_hash.epoch(),
+        AkdLabel::from("hello"),
+        proof,
+    )
+    .unwrap();
+    assert_eq!(AkdValue::from("world2"), result.value);
+
+    // As do the key history proofs, complete or not
+    for (most_recent, params) in [
+        (None, HistoryParams::Complete),
+        (Some(1), HistoryParams::MostRecent(1)),
+    ] {
+        let mut request = service::KeyHistoryRequest::new();
+        request.set_label(b"hello".to_vec());
+        request.most_recent = most_recent;
+        let response = client.key_history(request).await.unwrap().into_inner();
+        let proof = HistoryProof::try_from(response.proof.get_or_default()).unwrap();
+        let results = akd::client::key_history_verify::<TC>(
+            &public_key,
+            akd::hash::try_parse_digest(response.epoch_hash.root_hash()).unwrap(),
+            response.epoch_hash.epoch(),
+            AkdLabel::from("hello"),
+            proof,
+            HistoryVerificationParams::Default {
+                history_params: params,
+            },
+        )
+        .unwrap();
+        assert_eq!(most_recent.unwrap_or(2) as usize, results.len());
+    }
+
+    // And the audit proof
+    let mut request = service::AuditRequest::new();
+    request.set_start_epoch(0);
+    request.set_end_epoch(2);
+    let response = client.audit(request).await.unwrap().into_inner();
+    let proof = AppendOnlyProof::try_from(response.proof.get_or_default()).unwrap();
+    let hashes = epoch_hashes
+        .iter()
+        .map(|epoch_hash| akd::hash::try_parse_digest(epoch_hash.root_hash()).unwrap())
+        .collect();
+    akd::auditor::audit_verify::<TC>(hashes, proof)
+        .await
+        .unwrap();
+
+    // The errors of the directory are mapped to status codes
+    let mut request = service::LookupRequest::new();
+    request.set_label(b"unknown".to_vec());
+    assert_eq!(
+        Code::NotFound,
+        client.lookup(request).await.unwrap_err().code()
+    );
+    let mut request = service::AuditRequest::new();
+    request.set_start_epoch(2);
+    request.set_end_epoch(1);
+    assert_eq!(
+        Code::InvalidArgument,
+        client.audit(request).await.unwrap_err().code()
+    );
+    let status = client
+        .publish(publish_request(&[("hello", "world3"), ("hello", "world4")]))
+        .await
+        .unwrap_err();
+    assert_eq!(Code::InvalidArgument, status.code());
+}
@@ -0,0 +1,94 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! A histogram of the latencies of an operation
+
+use std::fmt;
+use std::time::Duration;
+
+/// The latencies of an operation, which are reported in buckets whose upper bounds are
+/// powers of two (in microseconds), along with their percentiles
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Histogram {
+    latencies: Vec<Duration>,
+}
+
+impl Histogram {
+    /// Records the latency of an operation
+    pub(crate) fn record(&mut self, latency: Duration) {
+        self.latencies.push(latency);
+    }
+
+    /// Adds the latencies of another histogram
+    pub(crate) fn merge(&mut self, other: Histogram) {
+        self.latencies.extend(other.latencies);
+    }
+
+    /// The number of recorded latencies
+    pub(crate) fn count(&self) -> usize {
+        self.latencies.len()
+    }
+
+    /// The latency below which the given percentage of the latencies fall, or zero
+    /// if no latencies were recorded
+    pub(crate) fn percentile(&self, percentage: f64) -> Duration {
+        let mut latencies = self.latencies.clone();
+        latencies.sort();
+        let rank = (percentage / 100.0 * latencies.len() as f64).ceil() as usize;
+        latencies
+            .get(rank.saturating_sub(1))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// The number of latencies in each bucket, keyed by the upper bound of the bucket in
+    /// microseconds, from the smallest non-empty bucket to the largest
+    pub(crate) fn buckets(&self) -> Vec<(u128, usize)> {
+        let mut counts = Vec::<usize>::new();
+        for latency in &self.latencies {
+            let micros = latency.as_micros().max(1);
+            let index = (u128::BITS - (micros - 1).leading_zeros()) as usize;
+            if counts.len() <= index {
+                counts.resize(index + 1, 0);
+            }
+            counts[index] += 1;
+        }
+        let first = counts.iter().position(|count| *count > 0).unwrap_or(0);
+        counts
+            .into_iter()
+            .enumerate()
+            .skip(first)
+            .map(|(index, count)| (1u128 << index, count))
+            .collect()
+    }
+}
+
+impl fmt::Display for Histogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "  count: {}, p50: {:?}, p90: {:?}, p99: {:?}, max: {:?}",
+            self.count(),
+            self.percentile(50.0),
+            self.percentile(90.0),
+            self.percentile(99.0),
+            self.percentile(100.0)
+        )?;
+        let buckets = self.buckets();
+        let max_count = buckets.iter().map(|(_, count)| *count).max().unwrap_or(0);
+        for (upper_bound, count) in buckets {
+            // Scale the bars to at most 50 characters
+            let bar = "#".repeat((count * 50).div_ceil(max_count.max(1)));
+            writeln!(
+                f,
+                "  <= {:>12?} {count:>8} {bar}",
+                Duration::from_micros(upper_bound as u64)
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,314 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! A load-test harness, which drives a directory with a realistic workload in order to size
+//! the hardware of a deployment. The directory is first populated with the configured number
+//! of labels, after which concurrent workers perform lookups of labels drawn from a Zipf
+//! distribution (so that a few labels are much more popular than the rest), while updates to
+//! labels are published at a fixed cadence. The latencies of the lookups and publishes are
+//! reported as histograms.
+
+pub(crate) mod histogram;
+
+#[cfg(test)]
+mod tests;
+
+use crate::mysql_demo::mysql::AsyncMySqlDatabase;
+use akd::ecvrf::HardCodedAkdVRF;
+use akd::storage::memory::AsyncInMemoryDatabase;
+use akd::storage::{Database, StorageManager};
+use akd::{AkdLabel, AkdValue, Configuration, Directory, NamedConfiguration};
+use anyhow::Result;
+use clap::{Parser, ValueEnum};
+use histogram::Histogram;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// The configuration of the directory
+#[derive(ValueEnum, Clone, Debug)]
+pub(crate) enum LoadtestConfiguration {
+    WhatsappV1,
+    Experimental,
+}
+
+/// The storage backend of the directory
+#[derive(ValueEnum, Clone, Debug)]
+pub(crate) enum StorageBackend {
+    /// An in-memory database
+    Memory,
+    /// A MySQL database, such as the one of the MySQL demo. Its data is deleted
+    /// before the load test
+    Mysql,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub(crate) struct CliArgs {
+    /// The number of labels with which the directory is populated
+    #[clap(long = "num_labels", default_value = "1000")]
+    num_labels: usize,
+
+    /// The total number of lookups performed by the workers
+    #[clap(long = "num_lookups", default_value = "10000")]
+    num_lookups: usize,
+
+    /// The number of workers which concurrently perform lookups
+    #[clap(long = "concurrency", default_value = "8")]
+    concurrency: usize,
+
+    /// The exponent of the Zipf distribution of the looked up labels, where zero
+    /// gives a uniform distribution
+    #[clap(long = "zipf_exponent", default_value = "1.0")]
+    zipf_exponent: f64,
+
+    /// The number of milliseconds between the publishes made during the lookups, which
+    /// are not made if this is omitted
+    #[clap(long = "publish_interval_ms")]
+    publish_interval_ms: Option<u64>,
+
+    /// The number of labels which are updated by each publish made during the lookups
+    #[clap(long = "updates_per_publish", default_value = "100")]
+    updates_per_publish: usize,
+
+    /// The seed from which the labels to look up and update are drawn
+    #[clap(long = "seed", default_value = "0")]
+    seed: u64,
+
+    /// The storage backend of the directory
+    #[clap(
+        value_enum,
+        long = "storage",
+        ignore_case = true,
+        default_value = "memory"
+    )]
+    storage: StorageBackend,
+
+    /// The host of the MySQL database
+    #[clap(long = "mysql_host", default_value = "localhost")]
+    mysql_host: String,
+
+    /// The port of the MySQL database
+    #[clap(long = "mysql_port", default_value = "8001")]
+    mysql_port: u16,
+
+    /// The configuration of the directory
+    #[clap(
+        value_enum,
+        long = "configuration",
+        ignore_case = true,
+        default_value = "whatsapp-v1"
+    )]
+    configuration: LoadtestConfiguration,
+}
+
+/// The parameters of a workload
+#[derive(Clone, Debug)]
+pub(crate) struct Workload {
+    pub(crate) num_labels: usize,
+    pub(crate) num_lookups: usize,
+    pub(crate) concurrency: usize,
+    pub(crate) zipf_exponent: f64,
+    pub(crate) publish_interval: Option<Duration>,
+    pub(crate) updates_per_publish: usize,
+    pub(crate) seed: u64,
+}
+
+impl From<&CliArgs> for Workload {
+    fn from(args: &CliArgs) -> Self {
+        Self {
+            num_labels: args.num_labels,
+            num_lookups: args.num_lookups,
+            concurrency: args.concurrency,
+            zipf_exponent: args.zipf_exponent,
+            publish_interval: args.publish_interval_ms.map(Duration::from_millis),
+            updates_per_publish: args.updates_per_publish,
+            seed: args.seed,
+        }
+    }
+}
+
+/// The outcome of a workload
+pub(crate) struct Report {
+    /// The time taken to populate the directory
+    pub(crate) setup: Duration,
+    /// The time taken to perform the lookups
+    pub(crate) elapsed: Duration,
+    pub(crate) lookups: Histogram,
+    pub(crate) publishes: Histogram,
+}
+
+impl std::fmt::Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Populated the directory in {:?}", self.setup)?;
+        writeln!(
+            f,
+            "Performed {} lookups in {:?} ({:.1} lookups/s)",
+            self.lookups.count(),
+            self.elapsed,
+            self.lookups.count() as f64 / self.elapsed.as_secs_f64()
+        )?;
+        writeln!(f, "Lookup latencies:\n{}", self.lookups)?;
+        if self.publishes.count() > 0 {
+            writeln!(f, "Publish latencies:\n{}", self.publishes)?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) async fn render_cli(args: CliArgs) -> Result<()> {
+    match args.configuration {
+        LoadtestConfiguration::WhatsappV1 => run::<akd::WhatsAppV1Configuration>(args).await,
+        LoadtestConfiguration::Experimental => {
+            run::<akd::ExperimentalConfiguration<akd::ExampleLabel>>(args).await
+        }
+    }
+}
+
+async fn run<TC: NamedConfiguration>(args: CliArgs) -> Result<()> {
+    let workload = Workload::from(&args);
+    println!(
+        "Running a load test of {} lookups over {} labels against a {:?} directory with the {} configuration",
+        workload.num_lookups,
+        workload.num_labels,
+        args.storage,
+        TC::name()
+    );
+    let report = match args.storage {
+        StorageBackend::Memory => {
+            let storage = StorageManager::new_no_cache(AsyncInMemoryDatabase::new());
+            let directory = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {}).await?;
+            run_workload(directory, &workload).await?
+        }
+        StorageBackend::Mysql => {
+            let db = AsyncMySqlDatabase::new(
+                args.mysql_host.as_str(),
+                "default",
+                Some("root"),
+                Some("example"),
+                Some(args.mysql_port),
+                100,
+            )
+            .await?;
+            db.delete_data().await?;
+            let storage = StorageManager::new(
+                db,
+                Some(Duration::from_secs(10 * 60)),
+                None,
+                Some(Duration::from_secs(15)),
+            );
+            let directory = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {}).await?;
+            run_workload(directory, &workload).await?
+        }
+    };
+    print!("{report}");
+    Ok(())
+}
+
+fn label(index: usize) -> AkdLabel {
+    AkdLabel::from(format!("user_{index}").as_str())
+}
+
+/// Populates the directory with the labels of the workload, and then performs its lookups
+/// while publishing updates at its cadence
+pub(crate) async fn run_workload<TC: Configuration, S: Database + 'static>(
+    directory: Directory<TC, S, HardCodedAkdVRF>,
+    workload: &Workload,
+) -> Result<Report> {
+    let tic = Instant::now();
+    let updates = (0..workload.num_labels)
+        .map(|index| (label(index), AkdValue::from("value_0")))
+        .collect();
+    directory.publish(updates).await?;
+    let setup = tic.elapsed();
+
+    // The label of rank k (from 1) is drawn with a probability proportional to 1 / k^s
+    let popularity = Arc::new(WeightedIndex::new(
+        (1..=workload.num_labels).map(|rank| (rank as f64).powf(-workload.zipf_exponent)),
+    )?);
+    let remaining = Arc::new(AtomicUsize::new(workload.num_lookups));
+    let done = Arc::new(AtomicBool::new(false));
+
+    let publisher = workload.publish_interval.map(|interval| {
+        let directory = directory.clone();
+        let popularity = popularity.clone();
+        let done = done.clone();
+        let workload = workload.clone();
+        tokio::spawn(async move {
+            let mut rng = StdRng::seed_from_u64(workload.seed);
+            let mut publishes = Histogram::default();
+            let mut ticker = tokio::time::interval(interval);
+            // Publishes which take longer than the interval delay the next ones, rather
+            // than being followed by a burst of publishes
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            // The first tick completes immediately
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                if done.load(Ordering::Relaxed) {
+                    return Ok::<_, akd::errors::AkdError>(publishes);
+                }
+                // A label cannot be updated more than once in a publish
+                let indices = (0..workload.updates_per_publish)
+                    .map(|_| popularity.sample(&mut rng))
+                    .collect::<BTreeSet<_>>();
+                let value = AkdValue::from(format!("value_{}", publishes.count() + 1).as_str());
+                let updates = indices
+                    .into_iter()
+                    .map(|index| (label(index), value.clone()))
+                    .collect();
+                let tic = Instant::now();
+                directory.publish(updates).await?;
+                publishes.record(tic.elapsed());
+            }
+        })
+    });
+
+    let tic = Instant::now();
+    let workers = (0..workload.concurrency)
+        .map(|worker| {
+            let directory = directory.clone();
+            let popularity = popularity.clone();
+            let remaining = remaining.clone();
+            let seed = workload.seed.wrapping_add(worker as u64 + 1);
+            tokio::spawn(async move {
+                let mut rng = StdRng::seed_from_u64(seed);
+                let mut lookups = Histogram::default();
+                while remaining
+                    .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+                    .is_ok()
+                {
+                    let label = label(popularity.sample(&mut rng));
+                    let tic = Instant::now();
+                    directory.lookup(label).await?;
+                    lookups.record(tic.elapsed());
+                }
+                Ok::<_, akd::errors::AkdError>(lookups)
+            })
+        })
+        .collect::<Vec<_>>();
+    let mut lookups = Histogram::default();
+    for worker in workers {
+        lookups.merge(worker.await??);
+    }
+    let elapsed = tic.elapsed();
+
+    done.store(true, Ordering::Relaxed);
+    let publishes = match publisher {
+        Some(publisher) => publisher.await??,
+        None => Histogram::default(),
+    };
+    Ok(Report {
+        setup,
+        elapsed,
+        lookups,
+        publishes,
+    })
+}
@@ -0,0 +1,85 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Tests the load-test harness against an in-memory directory
+
+use super::histogram::Histogram;
+use super::{run_workload, Workload};
+use crate::test_config;
+
+use akd::ecvrf::HardCodedAkdVRF;
+use akd::storage::memory::AsyncInMemoryDatabase;
+use akd::storage::StorageManager;
+use akd::{AkdLabel, Configuration, Directory};
+use std::time::Duration;
+
+test_config!(test_run_workload);
+async fn test_run_workload<TC: Configuration>() {
+    let directory = Directory::<TC, _, _>::new(
+        StorageManager::new_no_cache(AsyncInMemoryDatabase::new()),
+        HardCodedAkdVRF {},
+    )
+    .await
+    .unwrap();
+    let workload = Workload {
+        num_labels: 50,
+        num_lookups: 20,
+        concurrency: 2,
+        zipf_exponent: 1.0,
+        publish_interval: Some(Duration::from_millis(100)),
+        updates_per_publish: 5,
+        seed: 0,
+    };
+    let report = run_workload(directory.clone(), &workload).await.unwrap();
+    assert_eq!(20, report.lookups.count());
+    assert_eq!(
+        report.lookups.count(),
+        report
+            .lookups
+            .buckets()
+            .iter()
+            .map(|(_, count)| count)
+            .sum::<usize>()
+    );
+
+    // The directory was populated, and updated once per recorded publish
+    let epoch_hash = directory.get_epoch_hash().await.unwrap();
+    assert_eq!(1 + report.publishes.count() as u64, epoch_hash.epoch());
+    let (proof, _) = directory.lookup(AkdLabel::from("user_49")).await.unwrap();
+    assert!(proof.version >= 1);
+}
+
+#[test]
+fn test_histogram() {
+    let mut histogram = Histogram::default();
+    assert_eq!(Duration::ZERO, histogram.percentile(50.0));
+    assert!(histogram.buckets().is_empty());
+
+    for micros in [3, 4, 5, 100] {
+        histogram.record(Duration::from_micros(micros));
+    }
+    let mut other = Histogram::default();
+    other.record(Duration::from_micros(1));
+    histogram.merge(other);
+
+    assert_eq!(5, histogram.count());
+    assert_eq!(Duration::from_micros(4), histogram.percentile(50.0));
+    assert_eq!(Duration::from_micros(100), histogram.percentile(100.0));
+    assert_eq!(
+        vec![
+            (1, 1),
+            (2, 0),
+            (4, 2),
+            (8, 1),
+            (16, 0),
+            (32, 0),
+            (64, 0),
+            (128, 1)
+        ],
+        histogram.buckets()
+    );
+}
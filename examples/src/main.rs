@@ -7,8 +7,15 @@
 
 //! A set of example applications and utilities for AKD
 
+mod akd_cli;
+mod auditor_daemon;
 mod fixture_generator;
+mod grpc_server;
+mod loadtest;
 mod mysql_demo;
+mod profiling;
+mod rest_server;
+mod storage_bench;
 mod wasm_client;
 mod whatsapp_kt_auditor;
 
@@ -32,17 +39,50 @@ enum ExampleType {
     MysqlDemo(mysql_demo::CliArgs),
     /// Fixture Generator
     FixtureGenerator(fixture_generator::Args),
+    /// Continuously running auditor
+    AuditorDaemon(auditor_daemon::CliArgs),
+    /// Test vectors for the WebAssembly verification bindings
+    WasmTestVectors(wasm_client::vectors::Args),
+    /// Reference gRPC server
+    GrpcServer(grpc_server::CliArgs),
+    /// Reference HTTP server with JSON proofs
+    RestServer(rest_server::CliArgs),
+    /// Interactive CLI for directory administration
+    AkdCli(akd_cli::CliArgs),
+    /// Load-test harness with publish and lookup workloads
+    Loadtest(loadtest::CliArgs),
+    /// Benchmark of the storage backends with a common workload
+    StorageBench(storage_bench::CliArgs),
+    /// Deterministic publish and proof generation workload for profiling
+    Profiling(profiling::CliArgs),
 }
 
 // MAIN //
-#[tokio::main]
-async fn main() -> Result<()> {
+fn main() -> Result<()> {
     let args = Arguments::parse();
 
+    // The profiling example runs on a single thread, so that its flamegraphs are not split
+    // across the worker threads of the runtime
+    let mut runtime = match args.example {
+        ExampleType::Profiling(_) => tokio::runtime::Builder::new_current_thread(),
+        _ => tokio::runtime::Builder::new_multi_thread(),
+    };
+    runtime.enable_all().build()?.block_on(run(args))
+}
+
+async fn run(args: Arguments) -> Result<()> {
     match args.example {
         ExampleType::WhatsappKtAuditor(args) => whatsapp_kt_auditor::render_cli(args).await?,
         ExampleType::MysqlDemo(args) => mysql_demo::render_cli(args).await?,
         ExampleType::FixtureGenerator(args) => fixture_generator::run(args).await,
+        ExampleType::AuditorDaemon(args) => auditor_daemon::render_cli(args).await?,
+        ExampleType::WasmTestVectors(args) => wasm_client::vectors::run(args).await?,
+        ExampleType::GrpcServer(args) => grpc_server::render_cli(args).await?,
+        ExampleType::RestServer(args) => rest_server::render_cli(args).await?,
+        ExampleType::AkdCli(args) => akd_cli::render_cli(args).await?,
+        ExampleType::Loadtest(args) => loadtest::render_cli(args).await?,
+        ExampleType::StorageBench(args) => storage_bench::render_cli(args).await?,
+        ExampleType::Profiling(args) => profiling::render_cli(args).await?,
     }
 
     Ok(())
@@ -65,6 +105,11 @@ macro_rules! test_config {
             async fn [<$x _ experimental_config>]() {
                 $x::<akd::ExperimentalConfiguration<akd::ExampleLabel>>().await
             }
+
+            #[tokio::test]
+            async fn [<$x _ sha3_256_config>]() {
+                $x::<akd::Sha3Configuration<akd::ExampleLabel>>().await
+            }
         }
     };
 }
@@ -86,6 +131,12 @@ macro_rules! test_config_serial {
             async fn [<$x _ experimental_config>]() {
                 $x::<akd::ExperimentalConfiguration<akd::ExampleLabel>>().await
             }
+
+            #[serial_test::serial]
+            #[tokio::test]
+            async fn [<$x _ sha3_256_config>]() {
+                $x::<akd::Sha3Configuration<akd::ExampleLabel>>().await
+            }
         }
     };
 }
@@ -0,0 +1,66 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! An [EpochPublisher] which distributes the root hash of each published epoch
+//! to an HTTP endpoint (e.g. a witness or a gossip service)
+
+use akd::epoch_publisher::{EpochPublisher, EpochPublisherError};
+use akd::Digest;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+/// The JSON body of the request sent for each epoch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct EpochAnnouncement {
+    pub(crate) epoch: u64,
+    /// The hex-encoded root hash
+    pub(crate) root_hash: String,
+    /// The hex-encoded signature over the root hash, if the directory signs its epochs
+    pub(crate) signature: Option<String>,
+}
+
+/// POSTs an [EpochAnnouncement] to the endpoint for each published epoch
+pub(crate) struct HttpEpochPublisher {
+    endpoint: String,
+    client: reqwest::Client,
+}
+
+impl HttpEpochPublisher {
+    pub(crate) fn new(endpoint: String) -> Self {
+        Self {
+            endpoint,
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl EpochPublisher for HttpEpochPublisher {
+    async fn publish_epoch(
+        &self,
+        epoch: u64,
+        root_hash: Digest,
+        signature: Option<&[u8]>,
+    ) -> Result<(), EpochPublisherError> {
+        let announcement = EpochAnnouncement {
+            epoch,
+            root_hash: hex::encode(root_hash),
+            signature: signature.map(hex::encode),
+        };
+        let body = serde_json::to_string(&announcement)
+            .map_err(|err| EpochPublisherError::Delivery(err.to_string()))?;
+        self.client
+            .post(&self.endpoint)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| EpochPublisherError::Delivery(err.to_string()))?;
+        Ok(())
+    }
+}
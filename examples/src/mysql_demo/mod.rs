@@ -8,10 +8,12 @@
 //! An example tool for running AKD backed by MySQL storage
 
 use akd::ecvrf::HardCodedAkdVRF;
+use akd::epoch_publisher::LogFileEpochPublisher;
 use akd::storage::StorageManager;
 use akd::Directory;
 use clap::{Parser, ValueEnum};
 use commands::Command;
+use epoch_publisher::HttpEpochPublisher;
 use log::{debug, error, info, warn};
 use mysql::AsyncMySqlDatabase;
 use rand::distributions::Alphanumeric;
@@ -25,8 +27,9 @@ use tokio::time::timeout;
 
 mod commands;
 mod directory_host;
+mod epoch_publisher;
 mod logs;
-mod mysql;
+pub(crate) mod mysql;
 mod mysql_storables;
 
 #[cfg(test)]
@@ -106,6 +109,14 @@ pub(crate) struct CliArgs {
         default_value = "100"
     )]
     mysql_insert_depth: usize,
+
+    /// Append the root hash of each published epoch to this (append-only) log file
+    #[clap(long = "root_log")]
+    root_log: Option<std::path::PathBuf>,
+
+    /// POST the root hash of each published epoch to this HTTP endpoint
+    #[clap(long = "root_endpoint")]
+    root_endpoint: Option<String>,
 }
 
 // NOTE(new_config): This can be adjusted in order to change the config run by poc/
@@ -144,9 +155,12 @@ pub(crate) async fn render_cli(args: CliArgs) -> Result<()> {
     if cli.memory_db {
         let db = akd::storage::memory::AsyncInMemoryDatabase::new();
         let storage_manager = StorageManager::new_no_cache(db);
-        let mut directory = Directory::<TC, _, _>::new(storage_manager, vrf)
-            .await
-            .unwrap();
+        let mut directory = with_epoch_publishers(
+            &cli,
+            Directory::<TC, _, _>::new(storage_manager, vrf)
+                .await
+                .unwrap(),
+        );
         if let Some(()) = pre_process_input(&cli, None).await {
             return Ok(());
         }
@@ -175,9 +189,12 @@ pub(crate) async fn render_cli(args: CliArgs) -> Result<()> {
             None,
             Some(Duration::from_secs(15)),
         );
-        let mut directory = Directory::<TC, _, _>::new(storage_manager.clone(), vrf)
-            .await
-            .unwrap();
+        let mut directory = with_epoch_publishers(
+            &cli,
+            Directory::<TC, _, _>::new(storage_manager.clone(), vrf)
+                .await
+                .unwrap(),
+        );
         tokio::spawn(async move {
             directory_host::init_host::<TC, _, HardCodedAkdVRF>(&mut rx, &mut directory).await
         });
@@ -188,6 +205,23 @@ pub(crate) async fn render_cli(args: CliArgs) -> Result<()> {
 }
 
 // Helpers //
+/// Registers the epoch publishers requested on the command line
+fn with_epoch_publishers<S: akd::storage::Database + 'static>(
+    cli: &CliArgs,
+    mut directory: Directory<TC, S, HardCodedAkdVRF>,
+) -> Directory<TC, S, HardCodedAkdVRF> {
+    if let Some(path) = &cli.root_log {
+        match LogFileEpochPublisher::new(path) {
+            Ok(publisher) => directory = directory.with_epoch_publisher(publisher),
+            Err(err) => error!("Failed to open the root log {}: {}", path.display(), err),
+        }
+    }
+    if let Some(endpoint) = &cli.root_endpoint {
+        directory = directory.with_epoch_publisher(HttpEpochPublisher::new(endpoint.clone()));
+    }
+    directory
+}
+
 // If () is returned, it means the command execution is complete and CLI should
 // return
 async fn pre_process_input(cli: &CliArgs, db: Option<&AsyncMySqlDatabase>) -> Option<()> {
@@ -236,6 +270,7 @@ async fn process_input(
                         1u32,
                         [1u8; 32],
                         1u64,
+                        None,
                     );
                     data.push(akd::storage::types::DbRecord::ValueState(state));
                 }
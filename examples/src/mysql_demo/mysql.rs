@@ -10,8 +10,11 @@
 use crate::mysql_demo::mysql_storables::MySqlStorable;
 use akd::errors::StorageError;
 use akd::hash::DIGEST_BYTES;
-use akd::storage::types::{DbRecord, KeyData, StorageType, ValueState, ValueStateRetrievalFlag};
-use akd::storage::{Database, Storable};
+use akd::storage::types::{
+    DbRecord, EpochRootRecord, KeyData, LabelEntry, PrecomputedLookupProof, PrecomputedVrfProof,
+    PublishLease, RollbackRecord, StorageType, UpdateJournal, ValueState, ValueStateRetrievalFlag,
+};
+use akd::storage::{BoxedDatabase, Storable};
 use akd::tree_node::TreeNodeWithPreviousValue;
 use akd::NodeLabel;
 use akd::{AkdLabel, AkdValue};
@@ -32,7 +35,11 @@ type MySqlError = mysql_async::Error;
 const TABLE_AZKS: &str = crate::mysql_demo::mysql_storables::TABLE_AZKS;
 const TABLE_HISTORY_TREE_NODES: &str = crate::mysql_demo::mysql_storables::TABLE_HISTORY_TREE_NODES;
 const TABLE_USER: &str = crate::mysql_demo::mysql_storables::TABLE_USER;
+const TABLE_EPOCH_METADATA: &str = crate::mysql_demo::mysql_storables::TABLE_EPOCH_METADATA;
+const TABLE_RECORDS: &str = crate::mysql_demo::mysql_storables::TABLE_RECORDS;
 const TEMP_IDS_TABLE: &str = crate::mysql_demo::mysql_storables::TEMP_IDS_TABLE;
+const TABLE_PUBLISH_LEASE: &str = "publish_lease";
+const PUBLISH_LEASE_KEY: u8 = 1;
 
 const MAXIMUM_SQL_TIER_CONNECTION_TIMEOUT_SECS: u64 = 300;
 const SQL_RECONNECTION_DELAY_SECS: u64 = 5;
@@ -325,10 +332,34 @@ impl<'a> AsyncMySqlDatabase {
         let command = "CREATE TABLE IF NOT EXISTS `".to_owned()
             + TABLE_USER
             + "` (`username` VARBINARY(256) NOT NULL, `epoch` BIGINT UNSIGNED NOT NULL, `version` BIGINT UNSIGNED NOT NULL,"
-            + " `node_label_val` VARBINARY(32) NOT NULL, `node_label_len` INT UNSIGNED NOT NULL, `data` VARBINARY(2000),"
+            + " `node_label_val` VARBINARY(32) NOT NULL, `node_label_len` INT UNSIGNED NOT NULL, `data` VARBINARY(2000), `blinding` VARBINARY(64),"
             + " PRIMARY KEY(`username`, `epoch`))";
         tx.query_drop(command).await?;
 
+        // Epoch metadata table
+        let command = "CREATE TABLE IF NOT EXISTS `".to_owned()
+            + TABLE_EPOCH_METADATA
+            + "` (`epoch` BIGINT UNSIGNED NOT NULL, `timestamp_ms` BIGINT UNSIGNED NOT NULL,"
+            + " `update_count` BIGINT UNSIGNED NOT NULL, `note` VARCHAR(2000),"
+            + " `bound` BOOLEAN NOT NULL, `signature` VARBINARY(64), PRIMARY KEY(`epoch`))";
+        tx.query_drop(command).await?;
+
+        // Publish lease table
+        let command = "CREATE TABLE IF NOT EXISTS `".to_owned()
+            + TABLE_PUBLISH_LEASE
+            + "` (`key` SMALLINT UNSIGNED NOT NULL, `holder` VARBINARY(256) NOT NULL,"
+            + " `fencing_token` BIGINT UNSIGNED NOT NULL, `expires_at_ms` BIGINT UNSIGNED NOT NULL,"
+            + " PRIMARY KEY (`key`))";
+        tx.query_drop(command).await?;
+
+        // Serialized records table (rollback records, precomputed proofs, update journals and
+        // epoch roots)
+        let command = "CREATE TABLE IF NOT EXISTS `".to_owned()
+            + TABLE_RECORDS
+            + "` (`record_type` SMALLINT UNSIGNED NOT NULL, `record_key` VARBINARY(512) NOT NULL,"
+            + " `data` LONGBLOB NOT NULL, PRIMARY KEY (`record_key`), INDEX (`record_type`))";
+        tx.query_drop(command).await?;
+
         // if we got here, we're good to commit. Transaction's will auto-rollback when memory freed if commit wasn't done.
         tx.commit().await?;
         Ok(())
@@ -348,6 +379,15 @@ impl<'a> AsyncMySqlDatabase {
         let command = "DELETE FROM `".to_owned() + TABLE_HISTORY_TREE_NODES + "`";
         tx.query_drop(command).await?;
 
+        let command = "DELETE FROM `".to_owned() + TABLE_EPOCH_METADATA + "`";
+        tx.query_drop(command).await?;
+
+        let command = "DELETE FROM `".to_owned() + TABLE_PUBLISH_LEASE + "`";
+        tx.query_drop(command).await?;
+
+        let command = "DELETE FROM `".to_owned() + TABLE_RECORDS + "`";
+        tx.query_drop(command).await?;
+
         tx.commit().await?;
 
         Ok(())
@@ -367,6 +407,15 @@ impl<'a> AsyncMySqlDatabase {
         let command = "DROP TABLE IF EXISTS `".to_owned() + TABLE_HISTORY_TREE_NODES + "`";
         tx.query_drop(command).await?;
 
+        let command = "DROP TABLE IF EXISTS `".to_owned() + TABLE_EPOCH_METADATA + "`";
+        tx.query_drop(command).await?;
+
+        let command = "DROP TABLE IF EXISTS `".to_owned() + TABLE_PUBLISH_LEASE + "`";
+        tx.query_drop(command).await?;
+
+        let command = "DROP TABLE IF EXISTS `".to_owned() + TABLE_RECORDS + "`";
+        tx.query_drop(command).await?;
+
         tx.commit().await?;
 
         Ok(())
@@ -439,6 +488,18 @@ impl<'a> AsyncMySqlDatabase {
                 DbRecord::ValueState(_) => {
                     DbRecord::set_batch_statement::<akd::storage::types::ValueState>(i)
                 }
+                DbRecord::EpochMetadata(_) => {
+                    DbRecord::set_batch_statement::<akd::EpochMetadata>(i)
+                }
+                DbRecord::RollbackRecord(_) => DbRecord::set_batch_statement::<RollbackRecord>(i),
+                DbRecord::PrecomputedLookupProof(_) => {
+                    DbRecord::set_batch_statement::<PrecomputedLookupProof>(i)
+                }
+                DbRecord::UpdateJournal(_) => DbRecord::set_batch_statement::<UpdateJournal>(i),
+                DbRecord::EpochRootRecord(_) => DbRecord::set_batch_statement::<EpochRootRecord>(i),
+                DbRecord::PrecomputedVrfProof(_) => {
+                    DbRecord::set_batch_statement::<PrecomputedVrfProof>(i)
+                }
             }
         };
 
@@ -628,27 +689,16 @@ impl<'a> AsyncMySqlDatabase {
             }
         }
     }
-}
 
-#[async_trait]
-impl Database for AsyncMySqlDatabase {
-    /// Storage a record in the data layer
-    async fn set(&self, record: DbRecord) -> core::result::Result<(), StorageError> {
-        match self.internal_set(record, None).await {
-            Ok(_) => Ok(()),
-            Err(error) => {
-                error!("MySQL error {}", error);
-                Err(StorageError::Other(format!("MySQL Error {error}")))
-            }
-        }
-    }
-
-    async fn batch_set(
+    /// Writes a batch of records, and deletes a batch of records by their full binary ids, in a
+    /// single MySQL transaction
+    async fn batch_write(
         &self,
         records: Vec<DbRecord>,
-        _state: akd::storage::DbSetState,
+        deletions: Vec<Vec<u8>>,
+        state: akd::storage::DbSetState,
     ) -> core::result::Result<(), StorageError> {
-        if records.is_empty() {
+        if records.is_empty() && deletions.is_empty() {
             // nothing to do, save the cycles
             return Ok(());
         }
@@ -669,6 +719,30 @@ impl Database for AsyncMySqlDatabase {
                     .entry(StorageType::ValueState)
                     .or_insert_with(Vec::new)
                     .push(record),
+                DbRecord::EpochMetadata(_) => groups
+                    .entry(StorageType::EpochMetadata)
+                    .or_insert_with(Vec::new)
+                    .push(record),
+                DbRecord::RollbackRecord(_) => groups
+                    .entry(StorageType::RollbackRecord)
+                    .or_insert_with(Vec::new)
+                    .push(record),
+                DbRecord::PrecomputedLookupProof(_) => groups
+                    .entry(StorageType::PrecomputedLookupProof)
+                    .or_insert_with(Vec::new)
+                    .push(record),
+                DbRecord::UpdateJournal(_) => groups
+                    .entry(StorageType::UpdateJournal)
+                    .or_insert_with(Vec::new)
+                    .push(record),
+                DbRecord::EpochRootRecord(_) => groups
+                    .entry(StorageType::EpochRootRecord)
+                    .or_insert_with(Vec::new)
+                    .push(record),
+                DbRecord::PrecomputedVrfProof(_) => groups
+                    .entry(StorageType::PrecomputedVrfProof)
+                    .or_insert_with(Vec::new)
+                    .push(record),
             }
         }
         // now execute each type'd batch in batch operations
@@ -681,6 +755,26 @@ impl Database for AsyncMySqlDatabase {
             tx.query_drop("SET unique_checks=0").await?;
             tx.query_drop("SET foreign_key_checks=0").await?;
 
+            if let akd::storage::DbSetState::Fenced(fencing_token) = state {
+                // lock the lease row (if any) so that it cannot change hands during the write
+                let statement = "SELECT `fencing_token` FROM `".to_owned()
+                    + TABLE_PUBLISH_LEASE
+                    + "` WHERE `key` = :key FOR UPDATE";
+                let current: Option<u64> = tx
+                    .exec_first(statement, params! { "key" => PUBLISH_LEASE_KEY })
+                    .await?;
+                if let Some(current) = current.filter(|current| *current > fencing_token) {
+                    return Err(MySqlError::Other(
+                        format!(
+                            "Write fenced off: the publish lease was issued with fencing token {current} after {fencing_token}"
+                        )
+                        .into(),
+                    ));
+                }
+            }
+
+            tx = self.internal_batch_delete(deletions, tx).await?;
+
             for (_key, mut value) in groups.into_iter() {
                 if !value.is_empty() {
                     // Sort the records to match db-layer sorting which will help with insert performance
@@ -725,6 +819,94 @@ impl Database for AsyncMySqlDatabase {
         }
     }
 
+    /// Deletes a batch of records of any type by their full binary ids in the transaction
+    async fn internal_batch_delete(
+        &self,
+        deletions: Vec<Vec<u8>>,
+        mut trans: mysql_async::Transaction<'a>,
+    ) -> core::result::Result<mysql_async::Transaction<'a>, MySqlError> {
+        if deletions.is_empty() {
+            return Ok(trans);
+        }
+
+        self.record_call_stats('w', "internal_batch_delete".to_string(), "".to_string())
+            .await;
+
+        for bin_id in deletions {
+            let data_type = <[u8]>::first(&bin_id).copied().unwrap_or_default();
+            trans = match data_type {
+                x if x == StorageType::Azks as u8 => {
+                    Self::internal_delete::<akd::Azks>(&bin_id, trans).await?
+                }
+                x if x == StorageType::TreeNode as u8 => {
+                    Self::internal_delete::<TreeNodeWithPreviousValue>(&bin_id, trans).await?
+                }
+                x if x == StorageType::ValueState as u8 => {
+                    Self::internal_delete::<ValueState>(&bin_id, trans).await?
+                }
+                x if x == StorageType::EpochMetadata as u8 => {
+                    Self::internal_delete::<akd::EpochMetadata>(&bin_id, trans).await?
+                }
+                x if x == StorageType::RollbackRecord as u8 => {
+                    Self::internal_delete::<RollbackRecord>(&bin_id, trans).await?
+                }
+                x if x == StorageType::PrecomputedLookupProof as u8 => {
+                    Self::internal_delete::<PrecomputedLookupProof>(&bin_id, trans).await?
+                }
+                x if x == StorageType::UpdateJournal as u8 => {
+                    Self::internal_delete::<UpdateJournal>(&bin_id, trans).await?
+                }
+                x if x == StorageType::EpochRootRecord as u8 => {
+                    Self::internal_delete::<EpochRootRecord>(&bin_id, trans).await?
+                }
+                x if x == StorageType::PrecomputedVrfProof as u8 => {
+                    Self::internal_delete::<PrecomputedVrfProof>(&bin_id, trans).await?
+                }
+                other => {
+                    return Err(MySqlError::Other(
+                        format!("Unknown storage type {other} of a deleted record").into(),
+                    ))
+                }
+            };
+        }
+        Ok(trans)
+    }
+
+    async fn internal_delete<St: Storable>(
+        bin_id: &[u8],
+        mut trans: mysql_async::Transaction<'a>,
+    ) -> core::result::Result<mysql_async::Transaction<'a>, MySqlError> {
+        let key = St::key_from_full_binary(bin_id).map_err(|err| MySqlError::Other(err.into()))?;
+        let statement = DbRecord::get_specific_delete_statement::<St>();
+        match DbRecord::get_specific_params::<St>(&key) {
+            Some(params) => trans.exec_drop(statement, params).await?,
+            None => trans.query_drop(statement).await?,
+        }
+        Ok(trans)
+    }
+}
+
+#[async_trait]
+impl BoxedDatabase for AsyncMySqlDatabase {
+    /// Storage a record in the data layer
+    async fn set(&self, record: DbRecord) -> core::result::Result<(), StorageError> {
+        match self.internal_set(record, None).await {
+            Ok(_) => Ok(()),
+            Err(error) => {
+                error!("MySQL error {}", error);
+                Err(StorageError::Other(format!("MySQL Error {error}")))
+            }
+        }
+    }
+
+    async fn batch_set(
+        &self,
+        records: Vec<DbRecord>,
+        state: akd::storage::DbSetState,
+    ) -> core::result::Result<(), StorageError> {
+        self.batch_write(records, vec![], state).await
+    }
+
     /// Retrieve a stored record from the data layer
     async fn get<St: Storable>(
         &self,
@@ -872,7 +1054,7 @@ impl Database for AsyncMySqlDatabase {
         let result = async {
             let mut conn = self.get_connection().await?;
             let statement_text =
-                "SELECT `username`, `epoch`, `version`, `node_label_val`, `node_label_len`, `data` FROM `"
+                "SELECT `username`, `epoch`, `version`, `node_label_val`, `node_label_len`, `data`, `blinding` FROM `"
                     .to_owned()
                     + TABLE_USER
                     + "` WHERE `username` = :the_user";
@@ -888,13 +1070,15 @@ impl Database for AsyncMySqlDatabase {
                         Some(node_label_val),
                         Some(node_label_len),
                         Some(data),
+                        Some(blinding),
                     ) = (
                         row.take(0),
                         row.take(1),
                         row.take(2),
                         row.take::<Vec<u8>, _>(3),
                         row.take(4),
-                        row.take(5),
+                        row.take::<Option<Vec<u8>>, _>(5),
+                        row.take(6),
                     ) {
                         // explicitly check the array length for safety
                         let r: core::result::Result<[u8; 32], _> = node_label_val.try_into();
@@ -906,8 +1090,10 @@ impl Database for AsyncMySqlDatabase {
                                     label_val,
                                     label_len: node_label_len,
                                 },
-                                value: AkdValue(data),
+                                // A tombstone is stored as a null value
+                                value: data.map_or(AkdValue::Tombstone, AkdValue::Bytes),
                                 username: AkdLabel(username),
+                                blinding,
                             });
                         }
                     }
@@ -942,7 +1128,7 @@ impl Database for AsyncMySqlDatabase {
         let result = async {
             let mut conn = self.get_connection().await?;
             let mut statement_text =
-                "SELECT `username`, `epoch`, `version`, `node_label_val`, `node_label_len`, `data` FROM `"
+                "SELECT `username`, `epoch`, `version`, `node_label_val`, `node_label_len`, `data`, `blinding` FROM `"
                     .to_owned()
                     + TABLE_USER
                     + "` WHERE `username` = :the_user";
@@ -978,13 +1164,15 @@ impl Database for AsyncMySqlDatabase {
                         Some(node_label_val),
                         Some(node_label_len),
                         Some(data),
+                        Some(blinding),
                     ) = (
                         row.take(0),
                         row.take(1),
                         row.take(2),
                         row.take::<Vec<_>, _>(3),
                         row.take(4),
-                        row.take(5),
+                        row.take::<Option<Vec<u8>>, _>(5),
+                        row.take(6),
                     ) {
                         // explicitly check the array length for safety
                         let r: core::result::Result<[u8; 32], _> = node_label_val.try_into();
@@ -996,8 +1184,10 @@ impl Database for AsyncMySqlDatabase {
                                     label_val,
                                     label_len: node_label_len,
                                 },
-                                value: AkdValue(data),
+                                // A tombstone is stored as a null value
+                                value: data.map_or(AkdValue::Tombstone, AkdValue::Bytes),
                                 username: AkdLabel(username),
+                                blinding,
                             });
                         }
                     }
@@ -1161,10 +1351,15 @@ impl Database for AsyncMySqlDatabase {
                 let _t = conn.query_iter(select_statement).await;
                 self.check_for_infra_error(_t)?
                     .reduce_and_drop(vec![], |mut acc, mut row: mysql_async::Row| {
-                        if let (Some(Ok(username)), Some(Ok(version)), Some(Ok(data))) =
-                            (row.take_opt(0), row.take_opt(1), row.take_opt(2))
-                        {
-                            acc.push((AkdLabel(username), (version, AkdValue(data))))
+                        if let (Some(Ok(username)), Some(Ok(version)), Some(Ok(data))) = (
+                            row.take_opt(0),
+                            row.take_opt(1),
+                            row.take_opt::<Option<Vec<u8>>, _>(2),
+                        ) {
+                            acc.push((
+                                AkdLabel(username),
+                                (version, data.map_or(AkdValue::Tombstone, AkdValue::Bytes)),
+                            ))
                         }
                         acc
                     })
@@ -1175,10 +1370,15 @@ impl Database for AsyncMySqlDatabase {
                     .await;
                 self.check_for_infra_error(_t)?
                     .reduce_and_drop(vec![], |mut acc, mut row: mysql_async::Row| {
-                        if let (Some(Ok(username)), Some(Ok(version)), Some(Ok(data))) =
-                            (row.take_opt(0), row.take_opt(1), row.take_opt(2))
-                        {
-                            acc.push((AkdLabel(username), (version, AkdValue(data))))
+                        if let (Some(Ok(username)), Some(Ok(version)), Some(Ok(data))) = (
+                            row.take_opt(0),
+                            row.take_opt(1),
+                            row.take_opt::<Option<Vec<u8>>, _>(2),
+                        ) {
+                            acc.push((
+                                AkdLabel(username),
+                                (version, data.map_or(AkdValue::Tombstone, AkdValue::Bytes)),
+                            ))
                         }
                         acc
                     })
@@ -1208,4 +1408,226 @@ impl Database for AsyncMySqlDatabase {
             }
         }
     }
+
+    async fn acquire_publish_lease(
+        &self,
+        holder: &[u8],
+        now_ms: u64,
+        ttl_ms: u64,
+    ) -> core::result::Result<Option<PublishLease>, StorageError> {
+        self.record_call_stats('w', "acquire_publish_lease".to_string(), "".to_string())
+            .await;
+
+        let result = async {
+            let mut conn = self.get_connection().await?;
+            let mut tx = conn.start_transaction(TxOpts::default()).await?;
+
+            // lock the lease row (if any) for the remainder of the transaction
+            let statement = "SELECT `holder`, `fencing_token`, `expires_at_ms` FROM `".to_owned()
+                + TABLE_PUBLISH_LEASE
+                + "` WHERE `key` = :key FOR UPDATE";
+            let current: Option<(Vec<u8>, u64, u64)> = tx
+                .exec_first(statement, params! { "key" => PUBLISH_LEASE_KEY })
+                .await?;
+
+            let fencing_token = match current {
+                Some((current_holder, token, expires_at_ms)) if expires_at_ms > now_ms => {
+                    if current_holder != holder {
+                        return Ok::<Option<PublishLease>, MySqlError>(None);
+                    }
+                    token
+                }
+                Some((_, token, _)) => token + 1,
+                None => 1,
+            };
+            let lease = PublishLease {
+                holder: holder.to_vec(),
+                fencing_token,
+                expires_at_ms: now_ms + ttl_ms,
+            };
+
+            let statement = "INSERT INTO `".to_owned()
+                + TABLE_PUBLISH_LEASE
+                + "` (`key`, `holder`, `fencing_token`, `expires_at_ms`)"
+                + " VALUES (:key, :holder, :fencing_token, :expires_at_ms)"
+                + " ON DUPLICATE KEY UPDATE `holder` = :holder, `fencing_token` = :fencing_token,"
+                + " `expires_at_ms` = :expires_at_ms";
+            tx.exec_drop(
+                statement,
+                params! {
+                    "key" => PUBLISH_LEASE_KEY,
+                    "holder" => lease.holder.clone(),
+                    "fencing_token" => lease.fencing_token,
+                    "expires_at_ms" => lease.expires_at_ms,
+                },
+            )
+            .await?;
+            tx.commit().await?;
+            Ok(Some(lease))
+        };
+        match result.await {
+            Ok(lease) => Ok(lease),
+            Err(error) => {
+                error!("MySQL error {}", error);
+                Err(StorageError::Other(format!("MySQL Error {error}")))
+            }
+        }
+    }
+
+    async fn release_publish_lease(
+        &self,
+        lease: &PublishLease,
+    ) -> core::result::Result<(), StorageError> {
+        self.record_call_stats('w', "release_publish_lease".to_string(), "".to_string())
+            .await;
+
+        let result = async {
+            let mut conn = self.get_connection().await?;
+            // expire the lease, but retain the fencing token so that it keeps increasing
+            let statement = "UPDATE `".to_owned()
+                + TABLE_PUBLISH_LEASE
+                + "` SET `expires_at_ms` = 0"
+                + " WHERE `key` = :key AND `holder` = :holder AND `fencing_token` = :fencing_token";
+            conn.exec_drop(
+                statement,
+                params! {
+                    "key" => PUBLISH_LEASE_KEY,
+                    "holder" => lease.holder.clone(),
+                    "fencing_token" => lease.fencing_token,
+                },
+            )
+            .await?;
+            Ok::<(), MySqlError>(())
+        };
+        match result.await {
+            Ok(()) => Ok(()),
+            Err(error) => {
+                error!("MySQL error {}", error);
+                Err(StorageError::Other(format!("MySQL Error {error}")))
+            }
+        }
+    }
+
+    async fn batch_delete<St: Storable>(
+        &self,
+        ids: &[St::StorageKey],
+    ) -> core::result::Result<(), StorageError> {
+        self.record_call_stats(
+            'w',
+            "batch_delete".to_string(),
+            format!("{:?}", St::data_type()),
+        )
+        .await;
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let result = async {
+            let mut conn = self.get_connection().await?;
+            let mut tx = conn.start_transaction(TxOpts::default()).await?;
+            let statement = DbRecord::get_specific_delete_statement::<St>();
+            for id in ids {
+                match DbRecord::get_specific_params::<St>(id) {
+                    Some(params) => tx.exec_drop(statement.clone(), params).await?,
+                    None => tx.query_drop(statement.clone()).await?,
+                }
+            }
+            tx.commit().await?;
+            Ok::<(), MySqlError>(())
+        };
+        match result.await {
+            Ok(()) => Ok(()),
+            Err(error) => {
+                error!("MySQL error {}", error);
+                Err(StorageError::Other(format!("MySQL Error {error}")))
+            }
+        }
+    }
+
+    async fn batch_set_and_delete(
+        &self,
+        records: Vec<DbRecord>,
+        deletions: Vec<Vec<u8>>,
+        state: akd::storage::DbSetState,
+    ) -> core::result::Result<(), StorageError> {
+        self.batch_write(records, deletions, state).await
+    }
+
+    async fn list_labels(
+        &self,
+        cursor: Option<&AkdLabel>,
+        limit: usize,
+        epoch: u64,
+    ) -> core::result::Result<Vec<LabelEntry>, StorageError> {
+        self.record_call_stats('r', "list_labels".to_string(), "".to_string())
+            .await;
+
+        let result = async {
+            let mut conn = self.get_connection().await?;
+            // versions only increase with epochs, so the maxima belong to the same state
+            let cursor_filter = if cursor.is_some() {
+                " AND `username` > :cursor"
+            } else {
+                ""
+            };
+            let statement = format!(
+                "SELECT `username`, MAX(`version`), MAX(`epoch`) FROM `{TABLE_USER}`
+                WHERE `epoch` <= :epoch{cursor_filter}
+                GROUP BY `username` ORDER BY `username` LIMIT :limit"
+            );
+            let params = match cursor {
+                Some(cursor) => params! {
+                    "epoch" => epoch,
+                    "cursor" => cursor.0.clone(),
+                    "limit" => limit as u64,
+                },
+                None => params! {
+                    "epoch" => epoch,
+                    "limit" => limit as u64,
+                },
+            };
+            let entries = conn
+                .exec_map(
+                    statement,
+                    params,
+                    |(username, version, last_epoch): (Vec<u8>, u64, u64)| LabelEntry {
+                        label: AkdLabel(username),
+                        current_version: Some(version),
+                        last_update_epoch: Some(last_epoch),
+                    },
+                )
+                .await?;
+            Ok::<Vec<LabelEntry>, MySqlError>(entries)
+        };
+        match result.await {
+            Ok(entries) => Ok(entries),
+            Err(error) => {
+                error!("MySQL error {}", error);
+                Err(StorageError::Other(format!("MySQL Error {error}")))
+            }
+        }
+    }
+
+    async fn count_labels(&self, epoch: u64) -> core::result::Result<u64, StorageError> {
+        self.record_call_stats('r', "count_labels".to_string(), "".to_string())
+            .await;
+
+        let result = async {
+            let mut conn = self.get_connection().await?;
+            let statement = format!(
+                "SELECT COUNT(DISTINCT `username`) FROM `{TABLE_USER}` WHERE `epoch` <= :epoch"
+            );
+            let count: Option<u64> = conn
+                .exec_first(statement, params! { "epoch" => epoch })
+                .await?;
+            Ok::<u64, MySqlError>(count.unwrap_or_default())
+        };
+        match result.await {
+            Ok(count) => Ok(count),
+            Err(error) => {
+                error!("MySQL error {}", error);
+                Err(StorageError::Other(format!("MySQL Error {error}")))
+            }
+        }
+    }
 }
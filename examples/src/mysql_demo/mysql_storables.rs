@@ -21,13 +21,23 @@ type MySqlError = mysql_async::Error;
 pub(crate) const TABLE_AZKS: &str = "azks";
 pub(crate) const TABLE_HISTORY_TREE_NODES: &str = "history";
 pub(crate) const TABLE_USER: &str = "users";
+pub(crate) const TABLE_EPOCH_METADATA: &str = "epoch_metadata";
+/// The records which are stored serialized, keyed by their full binary id
+pub(crate) const TABLE_RECORDS: &str = "records";
 pub(crate) const TEMP_IDS_TABLE: &str = "temp_ids_table";
 
 const SELECT_AZKS_DATA: &str = "`epoch`, `num_nodes`";
 const SELECT_HISTORY_TREE_NODE_DATA: &str =
     "`label_len`, `label_val`, `last_epoch`, `least_descendant_ep`, `parent_label_len`, `parent_label_val`, `node_type`, `left_child_len`, `left_child_label_val`, `right_child_len`, `right_child_label_val`, `hash`, `p_last_epoch`, `p_least_descendant_ep`, `p_parent_label_len`, `p_parent_label_val`, `p_node_type`, `p_left_child_len`, `p_left_child_label_val`, `p_right_child_len`, `p_right_child_label_val`, `p_hash`";
 const SELECT_USER_DATA: &str =
-    "`username`, `epoch`, `version`, `node_label_val`, `node_label_len`, `data`";
+    "`username`, `epoch`, `version`, `node_label_val`, `node_label_len`, `data`, `blinding`";
+const SELECT_EPOCH_METADATA_DATA: &str =
+    "`epoch`, `timestamp_ms`, `update_count`, `note`, `bound`, `signature`";
+const SELECT_RECORD_DATA: &str = "`record_key`, `data`";
+
+fn serialize_record(record: &DbRecord) -> Result<Vec<u8>> {
+    serde_json::to_vec(record).map_err(|err| Error::Other(err.into()))
+}
 
 pub(crate) trait MySqlStorable {
     fn set_statement(&self) -> String;
@@ -49,6 +59,8 @@ pub(crate) trait MySqlStorable {
 
     fn get_specific_statement<St: Storable>() -> String;
 
+    fn get_specific_delete_statement<St: Storable>() -> String;
+
     fn get_specific_params<St: Storable>(key: &St::StorageKey) -> Option<mysql_async::Params>;
 
     fn get_multi_row_specific_params<St: Storable>(
@@ -114,7 +126,23 @@ impl MySqlStorable for DbRecord {
                 , `p_right_child_len` = :p_right_child_len
                 , `p_right_child_label_val` = :p_right_child_label_val
                 , `p_hash` = :p_hash"),
-            DbRecord::ValueState(_) => format!("INSERT INTO `{TABLE_USER}` ({SELECT_USER_DATA}) VALUES (:username, :epoch, :version, :node_label_val, :node_label_len, :data)"),
+            DbRecord::ValueState(_) => format!("INSERT INTO `{TABLE_USER}` ({SELECT_USER_DATA}) VALUES (:username, :epoch, :version, :node_label_val, :node_label_len, :data, :blinding)"),
+            DbRecord::EpochMetadata(_) => format!("INSERT INTO `{TABLE_EPOCH_METADATA}` ({SELECT_EPOCH_METADATA_DATA})
+            VALUES (:epoch, :timestamp_ms, :update_count, :note, :bound, :signature)
+            ON DUPLICATE KEY UPDATE
+                `timestamp_ms` = :timestamp_ms
+                , `update_count` = :update_count
+                , `note` = :note
+                , `bound` = :bound
+                , `signature` = :signature"),
+            DbRecord::RollbackRecord(_)
+            | DbRecord::PrecomputedLookupProof(_)
+            | DbRecord::UpdateJournal(_)
+            | DbRecord::EpochRootRecord(_)
+            | DbRecord::PrecomputedVrfProof(_) => format!("INSERT INTO `{TABLE_RECORDS}` (`record_type`, {SELECT_RECORD_DATA})
+            VALUES (:record_type, :record_key, :data)
+            ON DUPLICATE KEY UPDATE
+                `data` = :data"),
         }
     }
 
@@ -150,8 +178,21 @@ impl MySqlStorable for DbRecord {
                 "p_hash" => node.previous_node.clone().map(|a| a.hash.0),
             }),
             DbRecord::ValueState(state) => Some(
-                params! { "username" => state.get_id().0, "epoch" => state.epoch, "version" => state.version, "node_label_len" => state.label.label_len, "node_label_val" => state.label.label_val, "data" => state.value.0.clone() },
+                params! { "username" => state.get_id().0, "epoch" => state.epoch, "version" => state.version, "node_label_len" => state.label.label_len, "node_label_val" => state.label.label_val, "data" => state.value.bytes().map(<[u8]>::to_vec), "blinding" => state.blinding.clone() },
+            ),
+            DbRecord::EpochMetadata(metadata) => Some(
+                params! { "epoch" => metadata.epoch, "timestamp_ms" => metadata.timestamp_ms, "update_count" => metadata.update_count, "note" => metadata.note.clone(), "bound" => metadata.bound, "signature" => metadata.signature.clone() },
             ),
+            DbRecord::RollbackRecord(_)
+            | DbRecord::PrecomputedLookupProof(_)
+            | DbRecord::UpdateJournal(_)
+            | DbRecord::EpochRootRecord(_)
+            | DbRecord::PrecomputedVrfProof(_) => {
+                let key = self.get_full_binary_id();
+                Some(
+                    params! { "record_type" => key[0], "record_key" => key, "data" => serialize_record(self).ok()? },
+                )
+            }
         }
     }
 
@@ -187,9 +228,21 @@ impl MySqlStorable for DbRecord {
                 }
                 StorageType::ValueState => {
                     parts = format!(
-                        "{parts}(:username{i}, :epoch{i}, :version{i}, :node_label_val{i}, :node_label_len{i}, :data{i})"
+                        "{parts}(:username{i}, :epoch{i}, :version{i}, :node_label_val{i}, :node_label_len{i}, :data{i}, :blinding{i})"
                     );
                 }
+                StorageType::EpochMetadata => {
+                    parts = format!(
+                        "{parts}(:epoch{i}, :timestamp_ms{i}, :update_count{i}, :note{i}, :bound{i}, :signature{i})"
+                    );
+                }
+                StorageType::RollbackRecord
+                | StorageType::PrecomputedLookupProof
+                | StorageType::UpdateJournal
+                | StorageType::EpochRootRecord
+                | StorageType::PrecomputedVrfProof => {
+                    parts = format!("{parts}(:record_type{i}, :record_key{i}, :data{i})");
+                }
                 _ => {
                     // azks
                 }
@@ -240,7 +293,28 @@ impl MySqlStorable for DbRecord {
                 `data` = new.data
                 , `node_label_val` = new.node_label_val
                 , `node_label_len` = new.node_label_len
-                , `version` = new.version"
+                , `version` = new.version
+                , `blinding` = new.blinding"
+            ),
+            StorageType::EpochMetadata => format!(
+                "INSERT INTO `{TABLE_EPOCH_METADATA}` ({SELECT_EPOCH_METADATA_DATA})
+            VALUES {parts} as new
+            ON DUPLICATE KEY UPDATE
+                `timestamp_ms` = new.timestamp_ms
+                , `update_count` = new.update_count
+                , `note` = new.note
+                , `bound` = new.bound
+                , `signature` = new.signature"
+            ),
+            StorageType::RollbackRecord
+            | StorageType::PrecomputedLookupProof
+            | StorageType::UpdateJournal
+            | StorageType::EpochRootRecord
+            | StorageType::PrecomputedVrfProof => format!(
+                "INSERT INTO `{TABLE_RECORDS}` (`record_type`, {SELECT_RECORD_DATA})
+            VALUES {parts} as new
+            ON DUPLICATE KEY UPDATE
+                `data` = new.data"
             ),
         }
     }
@@ -367,8 +441,44 @@ impl MySqlStorable for DbRecord {
                         format!("node_label_val{idx}"),
                         Value::from(state.label.label_val),
                     ),
-                    (format!("data{idx}"), Value::from(state.value.0.clone())),
+                    (
+                        format!("data{idx}"),
+                        Value::from(state.value.bytes().map(<[u8]>::to_vec)),
+                    ),
+                    (
+                        format!("blinding{idx}"),
+                        Value::from(state.blinding.clone()),
+                    ),
+                ]),
+                DbRecord::EpochMetadata(metadata) => Ok(vec![
+                    (format!("epoch{idx}"), Value::from(metadata.epoch)),
+                    (
+                        format!("timestamp_ms{idx}"),
+                        Value::from(metadata.timestamp_ms),
+                    ),
+                    (
+                        format!("update_count{idx}"),
+                        Value::from(metadata.update_count),
+                    ),
+                    (format!("note{idx}"), Value::from(metadata.note.clone())),
+                    (format!("bound{idx}"), Value::from(metadata.bound)),
+                    (
+                        format!("signature{idx}"),
+                        Value::from(metadata.signature.clone()),
+                    ),
                 ]),
+                DbRecord::RollbackRecord(_)
+                | DbRecord::PrecomputedLookupProof(_)
+                | DbRecord::UpdateJournal(_)
+                | DbRecord::EpochRootRecord(_)
+                | DbRecord::PrecomputedVrfProof(_) => {
+                    let key = item.get_full_binary_id();
+                    Ok(vec![
+                        (format!("record_type{idx}"), Value::from(key[0])),
+                        (format!("record_key{idx}"), Value::from(key)),
+                        (format!("data{idx}"), Value::from(serialize_record(item)?)),
+                    ])
+                }
             })
             .collect::<Result<Vec<_>>>()?
             .into_iter()
@@ -384,6 +494,17 @@ impl MySqlStorable for DbRecord {
                 format!("SELECT {SELECT_HISTORY_TREE_NODE_DATA} FROM `{TABLE_HISTORY_TREE_NODES}`")
             }
             StorageType::ValueState => format!("SELECT {SELECT_USER_DATA} FROM `{TABLE_USER}`"),
+            StorageType::EpochMetadata => {
+                format!("SELECT {SELECT_EPOCH_METADATA_DATA} FROM `{TABLE_EPOCH_METADATA}`")
+            }
+            StorageType::RollbackRecord
+            | StorageType::PrecomputedLookupProof
+            | StorageType::UpdateJournal
+            | StorageType::EpochRootRecord
+            | StorageType::PrecomputedVrfProof => format!(
+                "SELECT {SELECT_RECORD_DATA} FROM `{TABLE_RECORDS}` WHERE `record_type` = {}",
+                St::data_type() as u8
+            ),
         }
     }
 
@@ -404,6 +525,24 @@ impl MySqlStorable for DbRecord {
                     )
                 )
             },
+            StorageType::EpochMetadata => {
+                Some(
+                    format!(
+                        "CREATE TEMPORARY TABLE `{TEMP_IDS_TABLE}`(`epoch` BIGINT UNSIGNED NOT NULL, PRIMARY KEY(`epoch`))"
+                    )
+                )
+            },
+            StorageType::RollbackRecord
+            | StorageType::PrecomputedLookupProof
+            | StorageType::UpdateJournal
+            | StorageType::EpochRootRecord
+            | StorageType::PrecomputedVrfProof => {
+                Some(
+                    format!(
+                        "CREATE TEMPORARY TABLE `{TEMP_IDS_TABLE}`(`record_key` VARBINARY(512) NOT NULL, PRIMARY KEY(`record_key`))"
+                    )
+                )
+            },
         }
     }
 
@@ -416,6 +555,16 @@ impl MySqlStorable for DbRecord {
             StorageType::ValueState => {
                 format!("INSERT INTO `{TEMP_IDS_TABLE}` (`username`, `epoch`) VALUES ")
             }
+            StorageType::EpochMetadata => {
+                format!("INSERT INTO `{TEMP_IDS_TABLE}` (`epoch`) VALUES ")
+            }
+            StorageType::RollbackRecord
+            | StorageType::PrecomputedLookupProof
+            | StorageType::UpdateJournal
+            | StorageType::EpochRootRecord
+            | StorageType::PrecomputedVrfProof => {
+                format!("INSERT INTO `{TEMP_IDS_TABLE}` (`record_key`) VALUES ")
+            }
         };
         if let Some(item_count) = num_items {
             for i in 0..item_count {
@@ -427,6 +576,16 @@ impl MySqlStorable for DbRecord {
                     StorageType::ValueState => {
                         format!("(:username{i}, :epoch{i})")
                     }
+                    StorageType::EpochMetadata => {
+                        format!("(:epoch{i})")
+                    }
+                    StorageType::RollbackRecord
+                    | StorageType::PrecomputedLookupProof
+                    | StorageType::UpdateJournal
+                    | StorageType::EpochRootRecord
+                    | StorageType::PrecomputedVrfProof => {
+                        format!("(:record_key{i})")
+                    }
                 };
                 statement = format!("{statement}{append}");
 
@@ -440,6 +599,12 @@ impl MySqlStorable for DbRecord {
                 StorageType::Azks => "",
                 StorageType::TreeNode => "(:label_len, :label_val)",
                 StorageType::ValueState => "(:username, :epoch)",
+                StorageType::EpochMetadata => "(:epoch)",
+                StorageType::RollbackRecord
+                | StorageType::PrecomputedLookupProof
+                | StorageType::UpdateJournal
+                | StorageType::EpochRootRecord
+                | StorageType::PrecomputedVrfProof => "(:record_key)",
             };
         }
         statement
@@ -489,12 +654,41 @@ impl MySqlStorable for DbRecord {
                         , a.`node_label_val`
                         , a.`node_label_len`
                         , a.`data`
+                        , a.`blinding`
                     FROM `{TABLE_USER}` a
                     INNER JOIN {TEMP_IDS_TABLE} ids
                         ON ids.`username` = a.`username`
                         AND ids.`epoch` = a.`epoch`"
                 )
             }
+            StorageType::EpochMetadata => {
+                format!(
+                    "SELECT
+                        a.`epoch`
+                        , a.`timestamp_ms`
+                        , a.`update_count`
+                        , a.`note`
+                        , a.`bound`
+                        , a.`signature`
+                    FROM `{TABLE_EPOCH_METADATA}` a
+                    INNER JOIN {TEMP_IDS_TABLE} ids
+                        ON ids.`epoch` = a.`epoch`"
+                )
+            }
+            StorageType::RollbackRecord
+            | StorageType::PrecomputedLookupProof
+            | StorageType::UpdateJournal
+            | StorageType::EpochRootRecord
+            | StorageType::PrecomputedVrfProof => {
+                format!(
+                    "SELECT
+                        a.`record_key`
+                        , a.`data`
+                    FROM `{TABLE_RECORDS}` a
+                    INNER JOIN {TEMP_IDS_TABLE} ids
+                        ON ids.`record_key` = a.`record_key`"
+                )
+            }
         }
     }
 
@@ -509,6 +703,38 @@ impl MySqlStorable for DbRecord {
             StorageType::ValueState => format!(
                 "SELECT {SELECT_USER_DATA} FROM `{TABLE_USER}` WHERE `username` = :username AND `epoch` = :epoch"
             ),
+            StorageType::EpochMetadata => format!(
+                "SELECT {SELECT_EPOCH_METADATA_DATA} FROM `{TABLE_EPOCH_METADATA}` WHERE `epoch` = :epoch"
+            ),
+            StorageType::RollbackRecord
+            | StorageType::PrecomputedLookupProof
+            | StorageType::UpdateJournal
+            | StorageType::EpochRootRecord
+            | StorageType::PrecomputedVrfProof => format!(
+                "SELECT {SELECT_RECORD_DATA} FROM `{TABLE_RECORDS}` WHERE `record_key` = :record_key"
+            ),
+        }
+    }
+
+    fn get_specific_delete_statement<St: Storable>() -> String {
+        match St::data_type() {
+            StorageType::Azks => format!("DELETE FROM `{TABLE_AZKS}`"),
+            StorageType::TreeNode => format!(
+                "DELETE FROM `{TABLE_HISTORY_TREE_NODES}` WHERE `label_len` = :label_len AND `label_val` = :label_val"
+            ),
+            StorageType::ValueState => format!(
+                "DELETE FROM `{TABLE_USER}` WHERE `username` = :username AND `epoch` = :epoch"
+            ),
+            StorageType::EpochMetadata => {
+                format!("DELETE FROM `{TABLE_EPOCH_METADATA}` WHERE `epoch` = :epoch")
+            }
+            StorageType::RollbackRecord
+            | StorageType::PrecomputedLookupProof
+            | StorageType::UpdateJournal
+            | StorageType::EpochRootRecord
+            | StorageType::PrecomputedVrfProof => {
+                format!("DELETE FROM `{TABLE_RECORDS}` WHERE `record_key` = :record_key")
+            }
         }
     }
 
@@ -537,6 +763,23 @@ impl MySqlStorable for DbRecord {
                     None
                 }
             }
+            StorageType::EpochMetadata => {
+                let bin = St::get_full_binary_key_id(key);
+                if let Ok(epoch) = akd::EpochMetadata::key_from_full_binary(&bin) {
+                    Some(params! {
+                        "epoch" => epoch
+                    })
+                } else {
+                    None
+                }
+            }
+            StorageType::RollbackRecord
+            | StorageType::PrecomputedLookupProof
+            | StorageType::UpdateJournal
+            | StorageType::EpochRootRecord
+            | StorageType::PrecomputedVrfProof => Some(params! {
+                "record_key" => St::get_full_binary_key_id(key)
+            }),
         }
     }
 
@@ -581,6 +824,37 @@ impl MySqlStorable for DbRecord {
                     .collect::<Vec<_>>();
                 Some(mysql_async::Params::from(pvec))
             }
+            StorageType::EpochMetadata => {
+                let pvec = keys
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, key)| {
+                        let bin = St::get_full_binary_key_id(key);
+                        // Since these are constructed from a safe key, they should never fail
+                        // so we'll leave the unwrap to simplify
+                        let epoch = akd::EpochMetadata::key_from_full_binary(&bin).unwrap();
+                        (format!("epoch{idx}"), Value::from(epoch))
+                    })
+                    .collect::<Vec<_>>();
+                Some(mysql_async::Params::from(pvec))
+            }
+            StorageType::RollbackRecord
+            | StorageType::PrecomputedLookupProof
+            | StorageType::UpdateJournal
+            | StorageType::EpochRootRecord
+            | StorageType::PrecomputedVrfProof => {
+                let pvec = keys
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, key)| {
+                        (
+                            format!("record_key{idx}"),
+                            Value::from(St::get_full_binary_key_id(key)),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                Some(mysql_async::Params::from(pvec))
+            }
         }
     }
 
@@ -742,7 +1016,7 @@ impl MySqlStorable for DbRecord {
                 }
             }
             StorageType::ValueState => {
-                // `username`, `epoch`, `version`, `node_label_val`, `node_label_len`, `data`
+                // `username`, `epoch`, `version`, `node_label_val`, `node_label_len`, `data`, `blinding`
                 if let (
                     Some(Ok(username)),
                     Some(Ok(epoch)),
@@ -750,6 +1024,7 @@ impl MySqlStorable for DbRecord {
                     Some(Ok(node_label_val)),
                     Some(Ok(node_label_len)),
                     Some(Ok(data)),
+                    Some(blinding),
                 ) = (
                     row.take_opt(0),
                     row.take_opt(1),
@@ -757,6 +1032,7 @@ impl MySqlStorable for DbRecord {
                     row.take_opt(3),
                     row.take_opt(4),
                     row.take_opt(5),
+                    row.take(6),
                 ) {
                     let node_label_val_vec: Vec<u8> = node_label_val;
                     let state = DbRecord::build_user_state(
@@ -766,10 +1042,49 @@ impl MySqlStorable for DbRecord {
                         node_label_len,
                         node_label_val_vec.try_into().map_err(|_| cast_err())?,
                         epoch,
+                        blinding,
                     );
                     return Ok(DbRecord::ValueState(state));
                 }
             }
+            StorageType::EpochMetadata => {
+                // `epoch`, `timestamp_ms`, `update_count`, `note`, `bound`, `signature`
+                if let (
+                    Some(Ok(epoch)),
+                    Some(Ok(timestamp_ms)),
+                    Some(Ok(update_count)),
+                    Some(note),
+                    Some(Ok(bound)),
+                    Some(signature),
+                ) = (
+                    row.take_opt(0),
+                    row.take_opt(1),
+                    row.take_opt(2),
+                    row.take(3),
+                    row.take_opt(4),
+                    row.take(5),
+                ) {
+                    let metadata = DbRecord::build_epoch_metadata(
+                        epoch,
+                        timestamp_ms,
+                        update_count,
+                        note,
+                        bound,
+                        signature,
+                    );
+                    return Ok(DbRecord::EpochMetadata(metadata));
+                }
+            }
+            StorageType::RollbackRecord
+            | StorageType::PrecomputedLookupProof
+            | StorageType::UpdateJournal
+            | StorageType::EpochRootRecord
+            | StorageType::PrecomputedVrfProof => {
+                // `record_key`, `data`
+                if let Some(Ok(data)) = row.take_opt::<Vec<u8>, _>(1) {
+                    return serde_json::from_slice(&data).map_err(|err| Error::Other(err.into()));
+                }
+            }
         }
         // fallback
         let err = MySqlError::Driver(mysql_async::DriverError::FromRow { row: row.clone() });
@@ -0,0 +1,80 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+use crate::mysql_demo::epoch_publisher::{EpochAnnouncement, HttpEpochPublisher};
+use crate::test_config;
+use akd::ecvrf::HardCodedAkdVRF;
+use akd::signature::HardCodedEpochSigner;
+use akd::storage::memory::AsyncInMemoryDatabase;
+use akd::storage::StorageManager;
+use akd::{AkdLabel, AkdValue, Configuration, Directory};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Accepts a single HTTP request, replying with the provided status, and returns its body
+async fn receive_request(listener: &TcpListener, status: &str) -> String {
+    let (mut stream, _) = listener.accept().await.unwrap();
+    let mut request = vec![];
+    let mut buffer = [0u8; 4096];
+    let body = loop {
+        let read = stream.read(&mut buffer).await.unwrap();
+        request.extend_from_slice(&buffer[..read]);
+        let text = String::from_utf8_lossy(&request).to_string();
+        if let Some((headers, body)) = text.split_once("\r\n\r\n") {
+            let content_length = headers
+                .lines()
+                .find_map(|line| {
+                    line.to_ascii_lowercase()
+                        .strip_prefix("content-length: ")
+                        .map(|len| len.parse::<usize>().unwrap())
+                })
+                .unwrap_or(0);
+            if body.len() >= content_length {
+                break body.to_string();
+            }
+        }
+    };
+    let response = format!("HTTP/1.1 {status}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n");
+    stream.write_all(response.as_bytes()).await.unwrap();
+    body
+}
+
+test_config!(test_http_epoch_publisher);
+async fn test_http_epoch_publisher<TC: Configuration>() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let endpoint = format!("http://{}/roots", listener.local_addr().unwrap());
+    let akd = Directory::<TC, _, _>::new(
+        StorageManager::new_no_cache(AsyncInMemoryDatabase::new()),
+        HardCodedAkdVRF {},
+    )
+    .await
+    .unwrap()
+    .with_epoch_signer(HardCodedEpochSigner {})
+    .with_epoch_publisher(HttpEpochPublisher::new(endpoint));
+
+    for status in ["200 OK", "500 Internal Server Error"] {
+        let (root, body) = tokio::join!(
+            akd.publish(vec![(
+                AkdLabel(format!("hello {status}").into_bytes()),
+                AkdValue::from("world"),
+            )]),
+            receive_request(&listener, status)
+        );
+        // The publish succeeds regardless of the response of the endpoint
+        let root = root.unwrap();
+        let announcement: EpochAnnouncement = serde_json::from_str(&body).unwrap();
+        assert_eq!(root.epoch(), announcement.epoch);
+        assert_eq!(hex::encode(root.hash()), announcement.root_hash);
+        let signature = akd
+            .get_epoch_metadata(root.epoch())
+            .await
+            .unwrap()
+            .signature
+            .map(hex::encode);
+        assert_eq!(signature, announcement.signature);
+    }
+}
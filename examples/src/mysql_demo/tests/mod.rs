@@ -5,6 +5,7 @@
 // License, Version 2.0 found in the LICENSE-APACHE file in the root directory
 // of this source tree. You may select, at your option, one of the above-listed licenses.
 
+mod epoch_publisher_tests;
 mod memory_tests;
 mod mysql_db_tests;
 mod mysql_tests;
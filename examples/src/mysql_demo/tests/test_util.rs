@@ -51,7 +51,7 @@ pub(crate) fn log_init(level: Level) {
 
 pub(crate) fn format_log_record(io: &mut (dyn Write + Send), record: &Record) {
     let target = {
-        if let Some(target_str) = record.target().split(':').last() {
+        if let Some(target_str) = record.target().split(':').next_back() {
             if let Some(line) = record.line() {
                 format!(" ({target_str}:{line})")
             } else {
@@ -156,7 +156,7 @@ pub(crate) async fn test_lookups<TC: Configuration, S: Database + 'static, V: VR
                 for value in users.iter() {
                     data.push((
                         AkdLabel::from(value),
-                        AkdValue(format!("{i}").as_bytes().to_vec()),
+                        AkdValue::Bytes(format!("{i}").as_bytes().to_vec()),
                     ));
                 }
 
@@ -289,7 +289,7 @@ pub(crate) async fn directory_test_suite<
                 for value in users.iter() {
                     data.push((
                         AkdLabel::from(value),
-                        AkdValue(format!("{i}").as_bytes().to_vec()),
+                        AkdValue::Bytes(format!("{i}").as_bytes().to_vec()),
                     ));
                 }
 
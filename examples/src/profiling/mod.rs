@@ -0,0 +1,202 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! A deterministic workload of publishes and proof generations, which is intended to be run under
+//! a profiler (e.g. `cargo flamegraph`). The workload is run on a single-threaded runtime, and
+//! with the `profiling` feature of this crate the tree insertions and VRF evaluations of the
+//! directory are also run sequentially on the calling task, so that the samples of each
+//! operation are attributed to a single call stack rather than being split across worker threads.
+//!
+//! The labels, values and the labels whose proofs are generated are drawn from a seeded RNG, and
+//! the directory uses an in-memory database and a hard-coded VRF key, so that successive runs
+//! perform the same work.
+
+#[cfg(test)]
+mod tests;
+
+use crate::loadtest::LoadtestConfiguration;
+use akd::ecvrf::HardCodedAkdVRF;
+use akd::storage::memory::AsyncInMemoryDatabase;
+use akd::storage::StorageManager;
+use akd::{AkdLabel, AkdValue, Directory, HistoryParams, NamedConfiguration};
+use anyhow::Result;
+use clap::{Parser, ValueEnum};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeSet;
+use std::time::{Duration, Instant};
+
+/// An operation of the directory which is profiled
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ProfiledOperation {
+    /// Publishes which update random labels
+    Publish,
+    /// Lookup proofs of random labels
+    Lookup,
+    /// Complete key history proofs of random labels
+    History,
+    /// Audit proofs between consecutive epochs
+    Audit,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub(crate) struct CliArgs {
+    /// The operations to profile, which are run in the given order after the directory is
+    /// populated
+    #[clap(
+        value_enum,
+        long = "operations",
+        ignore_case = true,
+        value_delimiter = ',',
+        default_value = "publish,lookup,history,audit"
+    )]
+    operations: Vec<ProfiledOperation>,
+
+    /// The number of labels with which the directory is populated, in a single publish
+    #[clap(long = "num_labels", default_value = "100000")]
+    num_labels: usize,
+
+    /// The number of times each operation is performed
+    #[clap(long = "iterations", default_value = "100")]
+    iterations: usize,
+
+    /// The number of labels which are updated by each profiled publish
+    #[clap(long = "updates_per_publish", default_value = "1000")]
+    updates_per_publish: usize,
+
+    /// The seed from which the labels to update and generate proofs for are drawn
+    #[clap(long = "seed", default_value = "0")]
+    seed: u64,
+
+    /// The configuration of the directory
+    #[clap(
+        value_enum,
+        long = "configuration",
+        ignore_case = true,
+        default_value = "whatsapp-v1"
+    )]
+    configuration: LoadtestConfiguration,
+}
+
+/// The parameters of the profiled workload
+#[derive(Clone, Debug)]
+pub(crate) struct ProfilingWorkload {
+    pub(crate) operations: Vec<ProfiledOperation>,
+    pub(crate) num_labels: usize,
+    pub(crate) iterations: usize,
+    pub(crate) updates_per_publish: usize,
+    pub(crate) seed: u64,
+}
+
+impl From<&CliArgs> for ProfilingWorkload {
+    fn from(args: &CliArgs) -> Self {
+        Self {
+            operations: args.operations.clone(),
+            num_labels: args.num_labels,
+            iterations: args.iterations,
+            updates_per_publish: args.updates_per_publish,
+            seed: args.seed,
+        }
+    }
+}
+
+pub(crate) async fn render_cli(args: CliArgs) -> Result<()> {
+    if !cfg!(feature = "profiling") {
+        println!(
+            "Note: the examples were built without the `profiling` feature, so the directory \
+             may spread its work across other threads than the profiled one"
+        );
+    }
+    let workload = ProfilingWorkload::from(&args);
+    let timings = match args.configuration {
+        LoadtestConfiguration::WhatsappV1 => {
+            run_workload::<akd::WhatsAppV1Configuration>(&workload).await?
+        }
+        LoadtestConfiguration::Experimental => {
+            run_workload::<akd::ExperimentalConfiguration<akd::ExampleLabel>>(&workload).await?
+        }
+    };
+    for (operation, elapsed) in timings {
+        println!(
+            "{operation:?}: {} iterations in {elapsed:?} ({:?} per iteration)",
+            workload.iterations,
+            elapsed / workload.iterations.max(1) as u32
+        );
+    }
+    Ok(())
+}
+
+fn label(index: usize) -> AkdLabel {
+    AkdLabel::from(format!("user_{index}").as_str())
+}
+
+/// Populates a directory and runs the profiled operations against it, returning the time taken by
+/// each of them
+pub(crate) async fn run_workload<TC: NamedConfiguration>(
+    workload: &ProfilingWorkload,
+) -> Result<Vec<(ProfiledOperation, Duration)>> {
+    let storage = StorageManager::new_no_cache(AsyncInMemoryDatabase::new());
+    let directory = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {}).await?;
+    let mut rng = StdRng::seed_from_u64(workload.seed);
+
+    println!(
+        "Populating a directory of {} labels with the {} configuration",
+        workload.num_labels,
+        TC::name()
+    );
+    let updates = (0..workload.num_labels)
+        .map(|index| (label(index), AkdValue::from("value_0")))
+        .collect();
+    directory.publish(updates).await?;
+
+    let mut timings = Vec::new();
+    let mut num_publishes = 0;
+    for operation in workload.operations.iter() {
+        let tic = Instant::now();
+        for _ in 0..workload.iterations {
+            match operation {
+                ProfiledOperation::Publish => {
+                    num_publishes += 1;
+                    // A label cannot be updated more than once in a publish
+                    let indices = (0..workload.updates_per_publish)
+                        .map(|_| rng.gen_range(0..workload.num_labels))
+                        .collect::<BTreeSet<_>>();
+                    let value = AkdValue::from(format!("value_{num_publishes}").as_str());
+                    let updates = indices
+                        .into_iter()
+                        .map(|index| (label(index), value.clone()))
+                        .collect();
+                    directory.publish(updates).await?;
+                }
+                ProfiledOperation::Lookup => {
+                    directory
+                        .lookup(label(rng.gen_range(0..workload.num_labels)))
+                        .await?;
+                }
+                ProfiledOperation::History => {
+                    directory
+                        .key_history(
+                            &label(rng.gen_range(0..workload.num_labels)),
+                            HistoryParams::Complete,
+                        )
+                        .await?;
+                }
+                ProfiledOperation::Audit => {
+                    // Without the profiled publishes, the directory only has a single epoch,
+                    // which has no audit proof
+                    let latest_epoch = directory.get_epoch_hash().await?.epoch();
+                    if latest_epoch > 1 {
+                        let start = rng.gen_range(1..latest_epoch);
+                        directory.audit(start, start + 1).await?;
+                    }
+                }
+            }
+        }
+        timings.push((*operation, tic.elapsed()));
+    }
+    Ok(timings)
+}
@@ -0,0 +1,37 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Tests the profiled workload on a small directory
+
+use super::{run_workload, ProfiledOperation, ProfilingWorkload};
+use crate::test_config;
+
+use akd::NamedConfiguration;
+
+test_config!(test_run_workload);
+async fn test_run_workload<TC: NamedConfiguration>() {
+    let workload = ProfilingWorkload {
+        operations: vec![
+            ProfiledOperation::Publish,
+            ProfiledOperation::Lookup,
+            ProfiledOperation::History,
+            ProfiledOperation::Audit,
+        ],
+        num_labels: 50,
+        iterations: 3,
+        updates_per_publish: 5,
+        seed: 0,
+    };
+    let timings = run_workload::<TC>(&workload).await.unwrap();
+
+    // The operations are timed in the order in which they are given
+    let operations = timings
+        .iter()
+        .map(|(operation, _)| *operation)
+        .collect::<Vec<_>>();
+    assert_eq!(workload.operations, operations);
+}
@@ -0,0 +1,279 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! A reference HTTP server, which serves the lookup, key history and audit proofs of a
+//! directory as JSON, with the serde serialization of the proofs (in which bytes are
+//! hex-encoded). It exposes the following endpoints:
+//!
+//! - `GET /epoch`: The latest epoch and root hash of the directory
+//! - `GET /public_key`: The VRF public key of the directory
+//! - `GET /lookup/{label}`: A lookup proof for the label
+//! - `GET /history/{label}?most_recent={n}`: A key history proof for the label, which is
+//!   complete unless the number of most recent updates is provided
+//! - `GET /audit/{start}/{end}`: An audit proof from the start epoch to the end epoch
+//! - `POST /publish`: Publishes the label-value pairs of the body in a new epoch
+//!
+//! The labels in the paths, and the labels and values which are published, are UTF-8 strings.
+//! The directory is held in memory, and can be populated with randomly generated users at
+//! startup or through the publish endpoint.
+
+#[cfg(test)]
+mod tests;
+
+use akd::ecvrf::HardCodedAkdVRF;
+use akd::errors::{AkdError, DirectoryError, StorageError};
+use akd::storage::memory::AsyncInMemoryDatabase;
+use akd::storage::{Database, StorageManager};
+use akd::{
+    AkdLabel, AkdValue, AppendOnlyProof, Configuration, Directory, EpochHash, HistoryParams,
+    HistoryProof, LookupProof,
+};
+use anyhow::Result;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use clap::{Parser, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use tokio::net::TcpListener;
+
+/// The configuration of the served directory
+#[derive(ValueEnum, Clone, Debug)]
+enum ServerConfiguration {
+    WhatsappV1,
+    Experimental,
+}
+
+#[derive(Parser, Debug, Clone)]
+pub(crate) struct CliArgs {
+    /// The address on which the endpoints are served
+    #[clap(long = "address", short = 'a', default_value = "127.0.0.1:8080")]
+    address: SocketAddr,
+
+    /// The number of users with random values to publish to the directory at startup
+    #[clap(long = "users", short = 'u', default_value = "0")]
+    users: usize,
+
+    /// The configuration of the directory
+    #[clap(
+        value_enum,
+        long = "configuration",
+        ignore_case = true,
+        default_value = "whatsapp-v1"
+    )]
+    configuration: ServerConfiguration,
+}
+
+pub(crate) async fn render_cli(args: CliArgs) -> Result<()> {
+    match args.configuration {
+        ServerConfiguration::WhatsappV1 => run::<akd::WhatsAppV1Configuration>(args).await,
+        ServerConfiguration::Experimental => {
+            run::<akd::ExperimentalConfiguration<akd::ExampleLabel>>(args).await
+        }
+    }
+}
+
+async fn run<TC: Configuration>(args: CliArgs) -> Result<()> {
+    let storage = StorageManager::new_no_cache(AsyncInMemoryDatabase::new());
+    let directory = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {}).await?;
+
+    if args.users > 0 {
+        let mut rng = rand::thread_rng();
+        let updates = (0..args.users)
+            .map(|_| (AkdLabel::random(&mut rng), AkdValue::random(&mut rng)))
+            .collect::<Vec<_>>();
+        let epoch_hash = directory.publish(updates).await?;
+        println!(
+            "Published {} users at epoch {}",
+            args.users,
+            epoch_hash.epoch()
+        );
+    }
+
+    let listener = TcpListener::bind(args.address).await?;
+    println!("Serving the directory on http://{}", args.address);
+    serve(directory, listener).await
+}
+
+/// Serves the directory on the connections accepted by the listener
+pub(crate) async fn serve<TC: Configuration, S: Database + 'static>(
+    directory: Directory<TC, S, HardCodedAkdVRF>,
+    listener: TcpListener,
+) -> Result<()> {
+    axum::serve(listener, router(directory)).await?;
+    Ok(())
+}
+
+fn router<TC: Configuration, S: Database + 'static>(
+    directory: Directory<TC, S, HardCodedAkdVRF>,
+) -> Router {
+    Router::new()
+        .route("/epoch", get(get_epoch::<TC, S>))
+        .route("/public_key", get(get_public_key::<TC, S>))
+        .route("/lookup/{label}", get(lookup::<TC, S>))
+        .route("/history/{label}", get(key_history::<TC, S>))
+        .route("/audit/{start}/{end}", get(audit::<TC, S>))
+        .route("/publish", post(publish::<TC, S>))
+        .with_state(directory)
+}
+
+type DirectoryState<TC, S> = State<Directory<TC, S, HardCodedAkdVRF>>;
+
+/// The epoch of the directory along with its (hex-encoded) root hash
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct EpochResponse {
+    pub(crate) epoch: u64,
+    pub(crate) root_hash: String,
+}
+
+impl From<EpochHash> for EpochResponse {
+    fn from(epoch_hash: EpochHash) -> Self {
+        Self {
+            epoch: epoch_hash.epoch(),
+            root_hash: hex::encode(epoch_hash.hash()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct PublicKeyResponse {
+    /// The hex-encoded public key
+    pub(crate) public_key: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct LookupResponse {
+    #[serde(flatten)]
+    pub(crate) epoch_hash: EpochResponse,
+    pub(crate) proof: LookupProof,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct HistoryResponse {
+    #[serde(flatten)]
+    pub(crate) epoch_hash: EpochResponse,
+    pub(crate) proof: HistoryProof,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct AuditResponse {
+    pub(crate) proof: AppendOnlyProof,
+}
+
+#[derive(Deserialize)]
+struct HistoryQuery {
+    most_recent: Option<usize>,
+}
+
+/// A label-value pair to publish
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Update {
+    pub(crate) label: String,
+    pub(crate) value: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct PublishRequest {
+    pub(crate) updates: Vec<Update>,
+}
+
+/// An error of the directory, which is returned as a JSON object with its message
+pub(crate) struct ApiError(AkdError);
+
+impl From<AkdError> for ApiError {
+    fn from(err: AkdError) -> Self {
+        Self(err)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = match &self.0 {
+            AkdError::Storage(StorageError::NotFound(_)) => StatusCode::NOT_FOUND,
+            AkdError::Directory(
+                DirectoryError::Publish(_)
+                | DirectoryError::InvalidEpoch(_)
+                | DirectoryError::InvalidVersion(_),
+            ) => StatusCode::BAD_REQUEST,
+            AkdError::Directory(DirectoryError::Overloaded(_)) => StatusCode::TOO_MANY_REQUESTS,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let body = serde_json::json!({ "error": self.0.to_string() });
+        (status, Json(body)).into_response()
+    }
+}
+
+async fn get_epoch<TC: Configuration, S: Database + 'static>(
+    State(directory): DirectoryState<TC, S>,
+) -> Result<Json<EpochResponse>, ApiError> {
+    Ok(Json(directory.get_epoch_hash().await?.into()))
+}
+
+async fn get_public_key<TC: Configuration, S: Database + 'static>(
+    State(directory): DirectoryState<TC, S>,
+) -> Result<Json<PublicKeyResponse>, ApiError> {
+    let public_key = directory.get_public_key().await?;
+    Ok(Json(PublicKeyResponse {
+        public_key: hex::encode(public_key.as_bytes()),
+    }))
+}
+
+async fn lookup<TC: Configuration, S: Database + 'static>(
+    State(directory): DirectoryState<TC, S>,
+    Path(label): Path<String>,
+) -> Result<Json<LookupResponse>, ApiError> {
+    let (proof, epoch_hash) = directory.lookup(AkdLabel::from(&label)).await?;
+    Ok(Json(LookupResponse {
+        epoch_hash: epoch_hash.into(),
+        proof,
+    }))
+}
+
+async fn key_history<TC: Configuration, S: Database + 'static>(
+    State(directory): DirectoryState<TC, S>,
+    Path(label): Path<String>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<HistoryResponse>, ApiError> {
+    let params = match query.most_recent {
+        Some(most_recent) => HistoryParams::MostRecent(most_recent),
+        None => HistoryParams::Complete,
+    };
+    let (proof, epoch_hash) = directory
+        .key_history(&AkdLabel::from(&label), params)
+        .await?;
+    Ok(Json(HistoryResponse {
+        epoch_hash: epoch_hash.into(),
+        proof,
+    }))
+}
+
+async fn audit<TC: Configuration, S: Database + 'static>(
+    State(directory): DirectoryState<TC, S>,
+    Path((start, end)): Path<(u64, u64)>,
+) -> Result<Json<AuditResponse>, ApiError> {
+    let proof = directory.audit(start, end).await?;
+    Ok(Json(AuditResponse { proof }))
+}
+
+async fn publish<TC: Configuration, S: Database + 'static>(
+    State(directory): DirectoryState<TC, S>,
+    Json(request): Json<PublishRequest>,
+) -> Result<Json<EpochResponse>, ApiError> {
+    let updates = request
+        .updates
+        .iter()
+        .map(|update| {
+            (
+                AkdLabel::from(&update.label),
+                AkdValue::from(update.value.as_str()),
+            )
+        })
+        .collect();
+    Ok(Json(directory.publish(updates).await?.into()))
+}
@@ -0,0 +1,137 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Tests the HTTP server by verifying the proofs it serves to a client
+
+use super::{
+    serve, AuditResponse, EpochResponse, HistoryResponse, LookupResponse, PublicKeyResponse,
+    PublishRequest, Update,
+};
+use crate::test_config;
+
+use akd::ecvrf::HardCodedAkdVRF;
+use akd::storage::memory::AsyncInMemoryDatabase;
+use akd::storage::StorageManager;
+use akd::{
+    AkdLabel, AkdValue, Configuration, Digest, Directory, HistoryParams, HistoryVerificationParams,
+};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use tokio::net::TcpListener;
+
+/// Starts a server for an empty directory on a local port, returning its base URL
+async fn start_server<TC: Configuration>() -> String {
+    let directory = Directory::<TC, _, _>::new(
+        StorageManager::new_no_cache(AsyncInMemoryDatabase::new()),
+        HardCodedAkdVRF {},
+    )
+    .await
+    .unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(serve(directory, listener));
+    format!("http://{address}")
+}
+
+async fn get<T: DeserializeOwned>(url: String) -> T {
+    let response = reqwest::get(url).await.unwrap();
+    assert_eq!(StatusCode::OK, response.status());
+    serde_json::from_str(&response.text().await.unwrap()).unwrap()
+}
+
+async fn publish(base_url: &str, updates: &[(&str, &str)]) -> reqwest::Response {
+    let request = PublishRequest {
+        updates: updates
+            .iter()
+            .map(|(label, value)| Update {
+                label: label.to_string(),
+                value: value.to_string(),
+            })
+            .collect(),
+    };
+    reqwest::Client::new()
+        .post(format!("{base_url}/publish"))
+        .header("content-type", "application/json")
+        .body(serde_json::to_string(&request).unwrap())
+        .send()
+        .await
+        .unwrap()
+}
+
+fn root_hash(epoch_hash: &EpochResponse) -> Digest {
+    akd::hash::try_parse_digest(&hex::decode(&epoch_hash.root_hash).unwrap()).unwrap()
+}
+
+test_config!(test_rest_server);
+async fn test_rest_server<TC: Configuration>() {
+    let base_url = start_server::<TC>().await;
+    let public_key: PublicKeyResponse = get(format!("{base_url}/public_key")).await;
+    let public_key = hex::decode(public_key.public_key).unwrap();
+
+    let mut epoch_hashes = vec![get::<EpochResponse>(format!("{base_url}/epoch")).await];
+    for updates in [
+        [("hello", "world"), ("hello2", "world")],
+        [("hello", "world2"), ("hello3", "world")],
+    ] {
+        let response = publish(&base_url, &updates).await;
+        assert_eq!(StatusCode::OK, response.status());
+        epoch_hashes.push(serde_json::from_str(&response.text().await.unwrap()).unwrap());
+    }
+    assert_eq!(
+        epoch_hashes[2],
+        get::<EpochResponse>(format!("{base_url}/epoch")).await
+    );
+
+    // The served lookup proof verifies
+    let response: LookupResponse = get(format!("{base_url}/lookup/hello")).await;
+    let result = akd::client::lookup_verify::<TC>(
+        &public_key,
+        root_hash(&response.epoch_hash),
+        response.epoch_hash.epoch,
+        AkdLabel::from("hello"),
+        response.proof,
+    )
+    .unwrap();
+    assert_eq!(AkdValue::from("world2"), result.value);
+
+    // As do the key history proofs, complete or not
+    for (query, params, num_results) in [
+        ("", HistoryParams::Complete, 2),
+        ("?most_recent=1", HistoryParams::MostRecent(1), 1),
+    ] {
+        let response: HistoryResponse = get(format!("{base_url}/history/hello{query}")).await;
+        let results = akd::client::key_history_verify::<TC>(
+            &public_key,
+            root_hash(&response.epoch_hash),
+            response.epoch_hash.epoch,
+            AkdLabel::from("hello"),
+            response.proof,
+            HistoryVerificationParams::Default {
+                history_params: params,
+            },
+        )
+        .unwrap();
+        assert_eq!(num_results, results.len());
+    }
+
+    // And the audit proof
+    let response: AuditResponse = get(format!("{base_url}/audit/0/2")).await;
+    let hashes = epoch_hashes.iter().map(root_hash).collect();
+    akd::auditor::audit_verify::<TC>(hashes, response.proof)
+        .await
+        .unwrap();
+
+    // The errors of the directory are mapped to status codes
+    let response = reqwest::get(format!("{base_url}/lookup/unknown"))
+        .await
+        .unwrap();
+    assert_eq!(StatusCode::NOT_FOUND, response.status());
+    let response = reqwest::get(format!("{base_url}/audit/2/1")).await.unwrap();
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    let response = publish(&base_url, &[("hello", "world3"), ("hello", "world4")]).await;
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+}
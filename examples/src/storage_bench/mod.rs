@@ -0,0 +1,331 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! A benchmark of the storage backends of a directory, which runs an identical workload against
+//! each of them so that their latencies and throughputs can be compared. The workload is made of
+//! three phases:
+//!
+//! * the directory is populated with the configured number of labels, in publishes of a fixed
+//!   size,
+//! * a number of publishes then update randomly drawn labels,
+//! * and concurrent workers finally perform lookups of randomly drawn labels.
+//!
+//! Any backend implementing [Database] can be benchmarked with [run_benchmark].
+
+#[cfg(test)]
+mod tests;
+
+use crate::loadtest::histogram::Histogram;
+use crate::loadtest::{LoadtestConfiguration, StorageBackend};
+use crate::mysql_demo::mysql::AsyncMySqlDatabase;
+use akd::ecvrf::HardCodedAkdVRF;
+use akd::storage::memory::AsyncInMemoryDatabase;
+use akd::storage::{Database, StorageManager};
+use akd::{AkdLabel, AkdValue, Directory, NamedConfiguration};
+use anyhow::Result;
+use clap::Parser;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[derive(Parser, Debug, Clone)]
+pub(crate) struct CliArgs {
+    /// The storage backends to benchmark, one after the other
+    #[clap(
+        value_enum,
+        long = "storage",
+        ignore_case = true,
+        value_delimiter = ',',
+        default_value = "memory"
+    )]
+    storage: Vec<StorageBackend>,
+
+    /// The number of labels with which the directory is populated
+    #[clap(long = "num_labels", default_value = "10000")]
+    num_labels: usize,
+
+    /// The number of labels published by each of the publishes which populate the directory
+    #[clap(long = "populate_batch_size", default_value = "1000")]
+    populate_batch_size: usize,
+
+    /// The number of publishes which update the labels after the directory is populated
+    #[clap(long = "num_publishes", default_value = "10")]
+    num_publishes: usize,
+
+    /// The number of labels which are updated by each of these publishes
+    #[clap(long = "updates_per_publish", default_value = "100")]
+    updates_per_publish: usize,
+
+    /// The total number of lookups performed by the workers
+    #[clap(long = "num_lookups", default_value = "1000")]
+    num_lookups: usize,
+
+    /// The number of workers which concurrently perform lookups
+    #[clap(long = "concurrency", default_value = "8")]
+    concurrency: usize,
+
+    /// Whether the directory caches the records read from the backend. The backends are
+    /// compared without a cache by default, so that every read reaches them.
+    #[clap(long = "cache")]
+    cache: bool,
+
+    /// The seed from which the labels to update and look up are drawn
+    #[clap(long = "seed", default_value = "0")]
+    seed: u64,
+
+    /// The host of the MySQL database
+    #[clap(long = "mysql_host", default_value = "localhost")]
+    mysql_host: String,
+
+    /// The port of the MySQL database
+    #[clap(long = "mysql_port", default_value = "8001")]
+    mysql_port: u16,
+
+    /// The configuration of the directory
+    #[clap(
+        value_enum,
+        long = "configuration",
+        ignore_case = true,
+        default_value = "whatsapp-v1"
+    )]
+    configuration: LoadtestConfiguration,
+}
+
+/// The parameters of the workload run against each backend
+#[derive(Clone, Debug)]
+pub(crate) struct StorageWorkload {
+    pub(crate) num_labels: usize,
+    pub(crate) populate_batch_size: usize,
+    pub(crate) num_publishes: usize,
+    pub(crate) updates_per_publish: usize,
+    pub(crate) num_lookups: usize,
+    pub(crate) concurrency: usize,
+    pub(crate) seed: u64,
+}
+
+impl From<&CliArgs> for StorageWorkload {
+    fn from(args: &CliArgs) -> Self {
+        Self {
+            num_labels: args.num_labels,
+            populate_batch_size: args.populate_batch_size,
+            num_publishes: args.num_publishes,
+            updates_per_publish: args.updates_per_publish,
+            num_lookups: args.num_lookups,
+            concurrency: args.concurrency,
+            seed: args.seed,
+        }
+    }
+}
+
+/// The latencies of the operations of a phase of the workload, and its total duration
+pub(crate) struct PhaseReport {
+    /// The number of items (labels published or looked up) processed by the phase
+    pub(crate) items: usize,
+    pub(crate) elapsed: Duration,
+    pub(crate) latencies: Histogram,
+}
+
+impl PhaseReport {
+    /// The number of items processed per second
+    pub(crate) fn throughput(&self) -> f64 {
+        self.items as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+/// The outcome of the workload against a backend
+pub(crate) struct BackendReport {
+    pub(crate) backend: String,
+    pub(crate) populate: PhaseReport,
+    pub(crate) publish: PhaseReport,
+    pub(crate) lookup: PhaseReport,
+}
+
+impl BackendReport {
+    fn phases(&self) -> [(&'static str, &PhaseReport); 3] {
+        [
+            ("populate", &self.populate),
+            ("publish", &self.publish),
+            ("lookup", &self.lookup),
+        ]
+    }
+}
+
+/// Formats the reports of the backends as a table, with a row per backend and phase
+pub(crate) fn comparison_table(reports: &[BackendReport]) -> String {
+    let mut table = format!(
+        "{:<12} {:<10} {:>8} {:>14} {:>12} {:>12} {:>12}\n",
+        "backend", "phase", "ops", "items/s", "p50", "p99", "max"
+    );
+    for report in reports {
+        for (phase, phase_report) in report.phases() {
+            let latencies = &phase_report.latencies;
+            table.push_str(&format!(
+                "{:<12} {:<10} {:>8} {:>14.1} {:>12} {:>12} {:>12}\n",
+                report.backend,
+                phase,
+                latencies.count(),
+                phase_report.throughput(),
+                format!("{:?}", latencies.percentile(50.0)),
+                format!("{:?}", latencies.percentile(99.0)),
+                format!("{:?}", latencies.percentile(100.0)),
+            ));
+        }
+    }
+    table
+}
+
+pub(crate) async fn render_cli(args: CliArgs) -> Result<()> {
+    match args.configuration {
+        LoadtestConfiguration::WhatsappV1 => run::<akd::WhatsAppV1Configuration>(args).await,
+        LoadtestConfiguration::Experimental => {
+            run::<akd::ExperimentalConfiguration<akd::ExampleLabel>>(args).await
+        }
+    }
+}
+
+async fn run<TC: NamedConfiguration>(args: CliArgs) -> Result<()> {
+    let workload = StorageWorkload::from(&args);
+    let mut reports = Vec::new();
+    for backend in args.storage.iter() {
+        println!(
+            "Running the workload against the {backend:?} backend with the {} configuration",
+            TC::name()
+        );
+        let report = match backend {
+            StorageBackend::Memory => {
+                let storage = storage_manager(AsyncInMemoryDatabase::new(), args.cache);
+                run_benchmark::<TC, _>("memory", storage, &workload).await?
+            }
+            StorageBackend::Mysql => {
+                let db = AsyncMySqlDatabase::new(
+                    args.mysql_host.as_str(),
+                    "default",
+                    Some("root"),
+                    Some("example"),
+                    Some(args.mysql_port),
+                    100,
+                )
+                .await?;
+                db.delete_data().await?;
+                let storage = storage_manager(db, args.cache);
+                run_benchmark::<TC, _>("mysql", storage, &workload).await?
+            }
+        };
+        reports.push(report);
+    }
+    print!("{}", comparison_table(&reports));
+    Ok(())
+}
+
+fn storage_manager<S: Database>(db: S, cache: bool) -> StorageManager<S> {
+    if cache {
+        StorageManager::new(db, None, None, None)
+    } else {
+        StorageManager::new_no_cache(db)
+    }
+}
+
+fn label(index: usize) -> AkdLabel {
+    AkdLabel::from(format!("user_{index}").as_str())
+}
+
+/// Runs the workload against a directory over the given storage, which must be empty
+pub(crate) async fn run_benchmark<TC: NamedConfiguration, S: Database + 'static>(
+    backend: &str,
+    storage: StorageManager<S>,
+    workload: &StorageWorkload,
+) -> Result<BackendReport> {
+    let directory = Directory::<TC, _, _>::new(storage, HardCodedAkdVRF {}).await?;
+    let mut rng = StdRng::seed_from_u64(workload.seed);
+
+    let mut latencies = Histogram::default();
+    let tic = Instant::now();
+    for start in (0..workload.num_labels).step_by(workload.populate_batch_size.max(1)) {
+        let end = workload
+            .num_labels
+            .min(start + workload.populate_batch_size.max(1));
+        let updates = (start..end)
+            .map(|index| (label(index), AkdValue::from("value_0")))
+            .collect();
+        let publish_tic = Instant::now();
+        directory.publish(updates).await?;
+        latencies.record(publish_tic.elapsed());
+    }
+    let populate = PhaseReport {
+        items: workload.num_labels,
+        elapsed: tic.elapsed(),
+        latencies,
+    };
+
+    let mut latencies = Histogram::default();
+    let mut items = 0;
+    let tic = Instant::now();
+    for publish in 1..=workload.num_publishes {
+        // A label cannot be updated more than once in a publish
+        let indices = (0..workload.updates_per_publish)
+            .map(|_| rng.gen_range(0..workload.num_labels))
+            .collect::<BTreeSet<_>>();
+        items += indices.len();
+        let value = AkdValue::from(format!("value_{publish}").as_str());
+        let updates = indices
+            .into_iter()
+            .map(|index| (label(index), value.clone()))
+            .collect();
+        let publish_tic = Instant::now();
+        directory.publish(updates).await?;
+        latencies.record(publish_tic.elapsed());
+    }
+    let publish = PhaseReport {
+        items,
+        elapsed: tic.elapsed(),
+        latencies,
+    };
+
+    let remaining = Arc::new(AtomicUsize::new(workload.num_lookups));
+    let tic = Instant::now();
+    let workers = (0..workload.concurrency.max(1))
+        .map(|worker| {
+            let directory = directory.clone();
+            let remaining = remaining.clone();
+            let num_labels = workload.num_labels;
+            let seed = workload.seed.wrapping_add(worker as u64 + 1);
+            tokio::spawn(async move {
+                let mut rng = StdRng::seed_from_u64(seed);
+                let mut latencies = Histogram::default();
+                while remaining
+                    .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+                    .is_ok()
+                {
+                    let label = label(rng.gen_range(0..num_labels));
+                    let lookup_tic = Instant::now();
+                    directory.lookup(label).await?;
+                    latencies.record(lookup_tic.elapsed());
+                }
+                Ok::<_, akd::errors::AkdError>(latencies)
+            })
+        })
+        .collect::<Vec<_>>();
+    let mut latencies = Histogram::default();
+    for worker in workers {
+        latencies.merge(worker.await??);
+    }
+    let lookup = PhaseReport {
+        items: workload.num_lookups,
+        elapsed: tic.elapsed(),
+        latencies,
+    };
+
+    Ok(BackendReport {
+        backend: backend.to_string(),
+        populate,
+        publish,
+        lookup,
+    })
+}
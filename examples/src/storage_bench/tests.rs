@@ -0,0 +1,45 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Tests the storage benchmark against the in-memory backend
+
+use super::{comparison_table, run_benchmark, StorageWorkload};
+use crate::test_config;
+
+use akd::storage::memory::AsyncInMemoryDatabase;
+use akd::storage::StorageManager;
+use akd::NamedConfiguration;
+
+test_config!(test_run_benchmark);
+async fn test_run_benchmark<TC: NamedConfiguration>() {
+    let workload = StorageWorkload {
+        num_labels: 50,
+        populate_batch_size: 20,
+        num_publishes: 3,
+        updates_per_publish: 5,
+        num_lookups: 20,
+        concurrency: 2,
+        seed: 0,
+    };
+    let storage = StorageManager::new_no_cache(AsyncInMemoryDatabase::new());
+    let report = run_benchmark::<TC, _>("memory", storage, &workload)
+        .await
+        .unwrap();
+
+    // The labels are populated in 3 publishes, of 20, 20 and 10 labels
+    assert_eq!(3, report.populate.latencies.count());
+    assert_eq!(50, report.populate.items);
+    assert_eq!(3, report.publish.latencies.count());
+    assert!(report.publish.items > 0 && report.publish.items <= 15);
+    assert_eq!(20, report.lookup.latencies.count());
+    assert!(report.lookup.throughput() > 0.0);
+
+    // The table has a header, followed by a row per phase
+    let table = comparison_table(&[report]);
+    assert_eq!(4, table.lines().count());
+    assert!(table.lines().skip(1).all(|line| line.starts_with("memory")));
+}
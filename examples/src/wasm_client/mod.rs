@@ -19,7 +19,8 @@
 //! documentation which has reference material dependent on your environment.
 //!
 //! Note that this file is intended for demonstration purposes only and not meant to be executable
-//! as-is.
+//! as-is. The bindings which are maintained as part of the library live in `akd_core::verify::wasm`
+//! (behind the `wasm` feature), and are tested against the test vectors generated by [vectors].
 
 use akd_core::configuration::Configuration;
 use akd_core::proto::specs::types::LookupProof;
@@ -28,6 +29,8 @@ use core::convert::TryInto;
 use protobuf::Message;
 use wasm_bindgen::prelude::*;
 
+pub(crate) mod vectors;
+
 /// The result of a lookup proof validation. The value is hexadecimal encoded
 /// binary
 #[wasm_bindgen]
@@ -131,13 +134,13 @@ fn lookup_verify<TC: Configuration>(
         Ok(verification) => Ok(LookupResult::new(
             verification.epoch,
             verification.version,
-            hex::encode(verification.value.0),
+            hex::encode(&*verification.value),
         )),
         Err(error) => Err(error.to_string()),
     }
 }
 
-/// NOTE(new_config): Add a new configuration here
+// NOTE(new_config): Add a new configuration here
 
 /// Verify a lookup proof in WebAssembly for WhatsAppV1Configuration,
 /// utilizing serde serialized structure for the proof
@@ -181,6 +184,27 @@ pub fn lookup_verify_experimental(
     )
 }
 
+/// Verify a lookup proof in WebAssembly for Sha3Configuration,
+/// utilizing serde serialized structure for the proof
+#[allow(unused)]
+#[wasm_bindgen]
+pub fn lookup_verify_sha3_256(
+    vrf_public_key: &[u8],
+    root_hash_ref: &[u8],
+    current_epoch: u64,
+    label: &[u8],
+    // protobuf encoded proof
+    lookup_proof: &[u8],
+) -> Result<LookupResult, String> {
+    lookup_verify::<akd_core::configuration::Sha3Configuration<akd_core::ExampleLabel>>(
+        vrf_public_key,
+        root_hash_ref,
+        current_epoch,
+        label,
+        lookup_proof,
+    )
+}
+
 #[cfg(test)]
 pub mod tests {
     extern crate wasm_bindgen_test;
@@ -192,7 +216,21 @@ pub mod tests {
     use protobuf::Message;
 
     use super::*;
+    use akd_core::configuration::NamedConfiguration;
     use akd_core::ecvrf::HardCodedAkdVRF;
+    use akd_core::verify::wasm;
+    use std::path::Path;
+
+    type LookupBinding = fn(&[u8], &[u8], u64, &[u8], &[u8]) -> Result<wasm::VerifiedValue, String>;
+    type HistoryBinding = fn(
+        &[u8],
+        &[u8],
+        u64,
+        &[u8],
+        &[u8],
+        Option<u32>,
+        bool,
+    ) -> Result<wasm::VerifiedHistory, String>;
 
     /// NOTE(new_config): Add a new configuration here
     macro_rules! test_config {
@@ -207,6 +245,11 @@ pub mod tests {
                 async fn [<$x _ experimental_config>]() -> Result<(), AkdError> {
                     $x::<akd_core::configuration::ExperimentalConfiguration<akd_core::ExampleLabel>>().await
                 }
+
+                #[tokio::test]
+                async fn [<$x _ sha3_256_config>]() -> Result<(), AkdError> {
+                    $x::<akd_core::configuration::Sha3Configuration<akd_core::ExampleLabel>>().await
+                }
             }
         };
     }
@@ -255,4 +298,79 @@ pub mod tests {
         assert!(result.is_ok());
         Ok(())
     }
+
+    test_config!(test_wasm_test_vectors);
+    async fn test_wasm_test_vectors<TC: NamedConfiguration>() -> Result<(), AkdError> {
+        let generated = vectors::generate::<TC>()
+            .await
+            .expect("Failed to generate the test vectors");
+        let path = vectors::vector_path::<TC>(Path::new(vectors::TEST_VECTORS_PATH));
+        let checked_in = std::fs::read_to_string(&path).expect("Failed to read the test vectors");
+        assert_eq!(
+            checked_in,
+            vectors::to_json(&generated).unwrap(),
+            "The test vectors at {} are out of date, regenerate them with \
+            `cargo run -p examples -- wasm-test-vectors --out akd_core/wasm/test_vectors`",
+            path.display()
+        );
+
+        for vector in generated {
+            let bytes = |field: &str| hex::decode(field).unwrap();
+            let (lookup, history): (LookupBinding, HistoryBinding) = match TC::name() {
+                "whatsapp_v1" => (
+                    wasm::lookup_verify_whatsapp_v1,
+                    wasm::key_history_verify_whatsapp_v1,
+                ),
+                "experimental" => (
+                    wasm::lookup_verify_experimental,
+                    wasm::key_history_verify_experimental,
+                ),
+                "sha3_256" => (
+                    wasm::lookup_verify_sha3_256,
+                    wasm::key_history_verify_sha3_256,
+                ),
+                name => panic!("No bindings for configuration {name}"),
+            };
+
+            let verified = lookup(
+                &bytes(&vector.vrf_public_key),
+                &bytes(&vector.root_hash),
+                vector.epoch,
+                &bytes(&vector.label),
+                &bytes(&vector.lookup_proof),
+            )
+            .expect("Failed to verify the lookup proof");
+            assert_eq!(vector.lookup.epoch, verified.epoch());
+            assert_eq!(vector.lookup.version, verified.version());
+            assert_eq!(Some(bytes(&vector.lookup.value)), verified.value());
+
+            let verified = history(
+                &bytes(&vector.vrf_public_key),
+                &bytes(&vector.root_hash),
+                vector.epoch,
+                &bytes(&vector.label),
+                &bytes(&vector.history_proof),
+                None,
+                false,
+            )
+            .expect("Failed to verify the history proof");
+            assert_eq!(vector.history.len(), verified.length());
+            for (expected, value) in vector.history.iter().zip(verified.values()) {
+                assert_eq!(expected.epoch, value.epoch());
+                assert_eq!(expected.version, value.version());
+                assert_eq!(Some(bytes(&expected.value)), value.value());
+            }
+
+            // A proof for another root hash fails to verify
+            assert!(lookup(
+                &bytes(&vector.vrf_public_key),
+                &[0u8; 32],
+                vector.epoch,
+                &bytes(&vector.label),
+                &bytes(&vector.lookup_proof),
+            )
+            .is_err());
+        }
+        Ok(())
+    }
 }
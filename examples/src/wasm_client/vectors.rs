@@ -0,0 +1,143 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Generates the test vectors against which the WebAssembly bindings in
+//! `akd_core::verify::wasm` are tested (by `akd_core/wasm/test.mjs`). Each vector holds
+//! protobuf-encoded lookup and history proofs for a label, along with the verified values.
+//!
+//! The vectors are regenerated with
+//! ```bash
+//! cargo run -p examples -- wasm-test-vectors --out akd_core/wasm/test_vectors
+//! ```
+
+use akd::ecvrf::HardCodedAkdVRF;
+use akd::storage::memory::AsyncInMemoryDatabase;
+use akd::storage::StorageManager;
+use akd::{AkdLabel, AkdValue, Directory, HistoryParams, NamedConfiguration};
+use anyhow::Result;
+use clap::Parser;
+use protobuf::Message;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// The directory (relative to the examples crate) in which the test vectors are checked in
+#[cfg(test)]
+pub(crate) const TEST_VECTORS_PATH: &str = "../akd_core/wasm/test_vectors";
+
+#[derive(Parser, Debug, Clone)]
+pub(crate) struct Args {
+    /// The directory to which the test vectors are written, one file per configuration
+    #[clap(long = "out", short = 'o')]
+    out: PathBuf,
+}
+
+/// A value of a label, with all binary fields hex-encoded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct ExpectedValue {
+    pub(crate) epoch: u64,
+    pub(crate) version: u64,
+    pub(crate) value: String,
+}
+
+/// The proofs for a label at the latest epoch, with all binary fields hex-encoded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct TestVector {
+    pub(crate) vrf_public_key: String,
+    pub(crate) root_hash: String,
+    pub(crate) epoch: u64,
+    pub(crate) label: String,
+    pub(crate) lookup_proof: String,
+    pub(crate) lookup: ExpectedValue,
+    pub(crate) history_proof: String,
+    /// The values of the label, from the most recent to the oldest
+    pub(crate) history: Vec<ExpectedValue>,
+}
+
+pub(crate) async fn run(args: Args) -> Result<()> {
+    write::<akd::WhatsAppV1Configuration>(&args.out).await?;
+    write::<akd::ExperimentalConfiguration<akd::ExampleLabel>>(&args.out).await?;
+    write::<akd::Sha3Configuration<akd::ExampleLabel>>(&args.out).await?;
+    Ok(())
+}
+
+async fn write<TC: NamedConfiguration>(out: &Path) -> Result<()> {
+    std::fs::create_dir_all(out)?;
+    let path = vector_path::<TC>(out);
+    std::fs::write(&path, to_json(&generate::<TC>().await?)?)?;
+    println!("Wrote the test vectors to {}", path.display());
+    Ok(())
+}
+
+/// The file holding the test vectors of a configuration
+pub(crate) fn vector_path<TC: NamedConfiguration>(dir: &Path) -> PathBuf {
+    dir.join(format!("{}.json", TC::name()))
+}
+
+pub(crate) fn to_json(vectors: &[TestVector]) -> Result<String> {
+    Ok(serde_json::to_string_pretty(vectors)? + "\n")
+}
+
+/// Builds a small directory over a few epochs, and produces the proofs for each of its labels
+pub(crate) async fn generate<TC: NamedConfiguration>() -> Result<Vec<TestVector>> {
+    let akd = Directory::<TC, _, _>::new(
+        StorageManager::new_no_cache(AsyncInMemoryDatabase::new()),
+        HardCodedAkdVRF {},
+    )
+    .await?;
+    let epochs = [
+        vec![("alice", "alice key 1"), ("bob", "bob key 1")],
+        vec![("alice", "alice key 2")],
+        vec![("bob", "bob key 2"), ("carol", "carol key 1")],
+        vec![("alice", "alice key 3")],
+    ];
+    for updates in epochs {
+        akd.publish(
+            updates
+                .into_iter()
+                .map(|(label, value)| (AkdLabel::from(label), AkdValue::from(value)))
+                .collect(),
+        )
+        .await?;
+    }
+    let vrf_public_key = akd.get_public_key().await?;
+
+    let mut vectors = vec![];
+    for label in ["alice", "bob", "carol"] {
+        let label = AkdLabel::from(label);
+        let (lookup_proof, root_hash) = akd.lookup(label.clone()).await?;
+        let (history_proof, _) = akd.key_history(&label, HistoryParams::Complete).await?;
+        let lookup = ExpectedValue {
+            epoch: lookup_proof.epoch,
+            version: lookup_proof.version,
+            value: hex::encode(&*lookup_proof.value),
+        };
+        let history = history_proof
+            .update_proofs
+            .iter()
+            .map(|proof| ExpectedValue {
+                epoch: proof.epoch,
+                version: proof.version,
+                value: hex::encode(&*proof.value),
+            })
+            .collect();
+        vectors.push(TestVector {
+            vrf_public_key: hex::encode(vrf_public_key.as_bytes()),
+            root_hash: hex::encode(root_hash.hash()),
+            epoch: root_hash.epoch(),
+            label: hex::encode(&label.0),
+            lookup_proof: hex::encode(
+                akd::proto::specs::types::LookupProof::from(&lookup_proof).write_to_bytes()?,
+            ),
+            lookup,
+            history_proof: hex::encode(
+                akd::proto::specs::types::HistoryProof::from(&history_proof).write_to_bytes()?,
+            ),
+            history,
+        });
+    }
+    Ok(vectors)
+}
@@ -69,7 +69,7 @@ pub(crate) async fn audit_epoch(blob: akd::local_auditing::AuditBlob) -> Result<
 }
 
 pub(crate) fn display_audit_proofs_info(info: &mut [EpochSummary]) -> Result<String> {
-    info.sort_by(|a, b| a.name.epoch.cmp(&b.name.epoch));
+    info.sort_by_key(|a| a.name.epoch);
     if info.is_empty() {
         bail!("There are no epochs present in the storage repository");
     }
@@ -7,7 +7,7 @@
 
 //! A tool for verifying audit proofs published from WhatsApp's key transparency implementation
 
-mod auditor;
+pub(crate) mod auditor;
 
 use akd::local_auditing::AuditBlobName;
 use anyhow::{anyhow, bail, Result};
@@ -0,0 +1,33 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Fuzzes the verification of append-only proofs decoded from their versioned
+//! protobuf encodings. Verification must return an error rather than panic on
+//! any input.
+
+#![no_main]
+
+use akd::auditor::audit_verify;
+use akd::proto::decode_versioned;
+use akd::{AppendOnlyProof, Digest, WhatsAppV1Configuration};
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    hashes: Vec<Digest>,
+    proof: Vec<u8>,
+}
+
+fuzz_target!(|input: Input| {
+    if let Ok(proof) = decode_versioned::<AppendOnlyProof>(&input.proof) {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let _ = runtime.block_on(audit_verify::<WhatsAppV1Configuration>(input.hashes, proof));
+    }
+});
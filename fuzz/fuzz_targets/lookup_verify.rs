@@ -0,0 +1,54 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Fuzzes the verification of lookup proofs decoded from their versioned protobuf
+//! encodings, against the public key of [HardCodedAkdVRF] (so that the proofs of a
+//! test directory can be used to seed the corpus). Verification must return an
+//! error rather than panic on any input.
+
+#![no_main]
+
+use std::sync::OnceLock;
+
+use akd::client::lookup_verify;
+use akd::ecvrf::{HardCodedAkdVRF, VRFKeyStorage};
+use akd::proto::decode_versioned;
+use akd::{AkdLabel, Digest, LookupProof, WhatsAppV1Configuration};
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    root_hash: Digest,
+    current_epoch: u64,
+    label: Vec<u8>,
+    proof: Vec<u8>,
+}
+
+fn vrf_public_key() -> &'static [u8] {
+    static KEY: OnceLock<Vec<u8>> = OnceLock::new();
+    KEY.get_or_init(|| {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime
+            .block_on(HardCodedAkdVRF.get_vrf_public_key_bytes::<WhatsAppV1Configuration>())
+            .unwrap()
+    })
+}
+
+fuzz_target!(|input: Input| {
+    if let Ok(proof) = decode_versioned::<LookupProof>(&input.proof) {
+        let _ = lookup_verify::<WhatsAppV1Configuration>(
+            vrf_public_key(),
+            input.root_hash,
+            input.current_epoch,
+            AkdLabel(input.label),
+            proof,
+        );
+    }
+});
@@ -0,0 +1,34 @@
+// Copyright (c) Meta Platforms, Inc. and affiliates.
+//
+// This source code is dual-licensed under either the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree or the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree. You may select, at your option, one of the above-listed licenses.
+
+//! Fuzzes the decoding of the proofs from their versioned protobuf encodings. Any
+//! input must either be rejected or decode to a proof which can be re-encoded in
+//! each of the supported versions of the wire format.
+
+#![no_main]
+
+use akd::proto::{decode_versioned, encode_versioned, VersionedProof, SUPPORTED_FORMAT_VERSIONS};
+use akd::{AppendOnlyProof, HistoryProof, LookupProof};
+use libfuzzer_sys::fuzz_target;
+
+fn decode<T: VersionedProof>(data: &[u8]) {
+    if let Ok(proof) = decode_versioned::<T>(data) {
+        for version in SUPPORTED_FORMAT_VERSIONS {
+            let bytes = encode_versioned(&proof, version).expect("Failed to re-encode a proof");
+            assert!(
+                decode_versioned::<T>(&bytes).is_ok(),
+                "Failed to decode a re-encoded proof"
+            );
+        }
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    decode::<LookupProof>(data);
+    decode::<HistoryProof>(data);
+    decode::<AppendOnlyProof>(data);
+});